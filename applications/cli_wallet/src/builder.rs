@@ -0,0 +1,337 @@
+// Copyright 2020, The Tari Project
+//
+// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+// following conditions are met:
+//
+// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+// disclaimer.
+//
+// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+// following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+// 3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+// products derived from this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+// INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+// SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+// WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+// USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+use log::*;
+use std::{
+    env,
+    fs::OpenOptions,
+    io::{self, Write},
+    net::SocketAddr,
+    path::Path,
+    sync::Arc,
+    time::Duration,
+};
+use tari_common::GlobalConfig;
+use tari_comms::{
+    control_service::ControlServiceConfig,
+    multiaddr::Multiaddr,
+    peer_manager::{NodeIdentity, PeerFeatures},
+    types::{CommsPublicKey, CommsSecretKey},
+};
+use tari_core::transactions::{crypto::keys::SecretKey, types::CryptoFactories};
+use tari_p2p::initialization::CommsConfig;
+use tari_utilities::{hex::Hex, message_format::MessageFormat};
+use tari_wallet::{
+    contacts_service::storage::sqlite_db::ContactsServiceSqliteDatabase,
+    emoji::EmojiId,
+    output_manager_service::storage::sqlite_db::OutputManagerSqliteDatabase,
+    storage::{database::WalletDatabase, sqlite_db::WalletSqliteDatabase},
+    transaction_service::storage::sqlite_db::TransactionServiceSqliteDatabase,
+    wallet::WalletConfig,
+    Wallet,
+};
+use tokio::runtime::Runtime;
+
+const LOG_TARGET: &str = "console_wallet::initialization";
+/// The environment variable the passphrase of an encrypted wallet is read from, before it is prompted for. It is never
+/// taken as a command-line argument, which other users of the system can read from the process list.
+const PASSPHRASE_ENV_VAR: &str = "TARI_WALLET_PASSPHRASE";
+
+/// The wallet used by the console wallet, which keeps all of its data in the configured SQLite wallet file
+pub type ConsoleWallet = Wallet<
+    WalletSqliteDatabase,
+    TransactionServiceSqliteDatabase,
+    OutputManagerSqliteDatabase,
+    ContactsServiceSqliteDatabase,
+>;
+
+/// Opens the wallet stored in the configured wallet file, or creates a new wallet file if `create` is set. The base
+/// node peers that were added to the wallet before are added to the new instance. The passphrase of an encrypted
+/// wallet is read from the `TARI_WALLET_PASSPHRASE` environment variable, or prompted for if that is not set.
+pub fn initialize_wallet(config: &GlobalConfig, create: bool, runtime: Runtime) -> Result<ConsoleWallet, String> {
+    let wallet_file = &config.wallet_file;
+    let wallet_file_str = wallet_file.to_str().unwrap_or("??");
+    match (create, wallet_file.exists()) {
+        (true, true) => return Err(format!("A wallet already exists at {}.", wallet_file_str)),
+        (false, false) => {
+            return Err(format!(
+                "No wallet was found at {}. Create a new wallet with the create or restore command.",
+                wallet_file_str
+            ))
+        },
+        _ => (),
+    }
+    let data_dir = wallet_file.parent().unwrap_or_else(|| Path::new("."));
+    if !data_dir.exists() {
+        std::fs::create_dir_all(data_dir)
+            .map_err(|e| format!("Could not create wallet data folder. {}", e.to_string()))?;
+    }
+    let db_path = wallet_file_str.to_string();
+    let passphrase = if is_encrypted(&db_path)? {
+        Some(read_passphrase()?)
+    } else {
+        None
+    };
+
+    let identity_file = wallet_file.with_extension("id.json");
+    let secret_key = match load_identity_secret_key(&identity_file)? {
+        Some(k) => k,
+        None => match load_comms_secret_key(&db_path, passphrase.as_ref())? {
+            Some(k) => k,
+            None => {
+                debug!(target: LOG_TARGET, "No wallet node identity found. Creating a new one.");
+                let mut rng = rand::OsRng::new().map_err(|e| e.to_string())?;
+                CommsSecretKey::random(&mut rng)
+            },
+        },
+    };
+    let comms_config = create_comms_config(config, data_dir, secret_key)?;
+
+    let mut wallet = Wallet::new(
+        WalletConfig {
+            comms_config,
+            logging_path: None,
+            factories: CryptoFactories::default(),
+            passphrase,
+        },
+        runtime,
+        WalletSqliteDatabase::new(db_path.clone()).map_err(|e| e.to_string())?,
+        TransactionServiceSqliteDatabase::new(db_path.clone()).map_err(|e| e.to_string())?,
+        OutputManagerSqliteDatabase::new(db_path.clone()).map_err(|e| e.to_string())?,
+        ContactsServiceSqliteDatabase::new(db_path).map_err(|e| e.to_string())?,
+    )
+    .map_err(|e| format!("Could not start the wallet. {}", e.to_string()))?;
+    info!(
+        target: LOG_TARGET,
+        "Started wallet with public key {}",
+        wallet.comms.node_identity().public_key().to_hex()
+    );
+
+    // An encrypted wallet stores its node identity with its other secrets, so only an unencrypted wallet needs the
+    // identity file
+    if wallet
+        .db
+        .get_encryption_settings()
+        .map_err(|e| e.to_string())?
+        .is_none()
+    {
+        save_identity(&identity_file, &wallet.comms.node_identity())?;
+    } else if identity_file.exists() {
+        std::fs::remove_file(&identity_file)
+            .map_err(|e| format!("Could not remove the wallet identity file. {}", e.to_string()))?;
+    }
+
+    let peers = wallet.db.get_peers().map_err(|e| e.to_string())?;
+    for peer in peers {
+        if let Some(address) = peer.addresses.addresses.first() {
+            let address = address.net_address.to_string();
+            debug!(target: LOG_TARGET, "Adding saved base node peer [{}]", peer.node_id);
+            wallet
+                .add_base_node_peer(peer.public_key.clone(), address)
+                .map_err(|e| format!("Could not add the saved base node peer. {}", e.to_string()))?;
+        }
+    }
+
+    Ok(wallet)
+}
+
+/// Reads the secret key of an unencrypted wallet's node identity from its identity file, if there is one
+fn load_identity_secret_key(path: &Path) -> Result<Option<CommsSecretKey>, String> {
+    if !path.exists() {
+        return Ok(None);
+    }
+    let id_str = std::fs::read_to_string(path).map_err(|e| {
+        format!(
+            "The wallet identity file, {}, could not be read. {}",
+            path.to_str().unwrap_or("?"),
+            e.to_string()
+        )
+    })?;
+    let id = NodeIdentity::from_json(&id_str).map_err(|e| {
+        format!(
+            "The wallet identity file, {}, has an error. {}",
+            path.to_str().unwrap_or("?"),
+            e.to_string()
+        )
+    })?;
+    Ok(Some(id.secret_key().clone()))
+}
+
+/// Writes the node identity of an unencrypted wallet to its identity file, which only the current user can read
+fn save_identity(path: &Path, id: &NodeIdentity) -> Result<(), String> {
+    let id_str = id.to_json().map_err(|e| e.to_string())?;
+    let mut options = OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    options
+        .open(path)
+        .and_then(|mut file| file.write_all(id_str.as_bytes()))
+        .map_err(|e| {
+            format!(
+                "Error writing the wallet identity file, {}. {}",
+                path.to_str().unwrap_or("??"),
+                e.to_string()
+            )
+        })
+}
+
+/// Whether the wallet database is encrypted or has an encryption change pending, in which case its passphrase is
+/// needed to open it
+fn is_encrypted(db_path: &str) -> Result<bool, String> {
+    let backend = WalletSqliteDatabase::new(db_path.to_string())
+        .map_err(|e| format!("Could not open the wallet database. {}", e.to_string()))?;
+    let db = WalletDatabase::new(backend);
+    Ok(db.get_encryption_settings().map_err(|e| e.to_string())?.is_some() ||
+        db.get_pending_encryption().map_err(|e| e.to_string())?.is_some())
+}
+
+/// Reads the wallet passphrase from the `TARI_WALLET_PASSPHRASE` environment variable, which is then removed so that
+/// it is not passed on to child processes, or else prompts for it on the terminal without echoing it
+fn read_passphrase() -> Result<String, String> {
+    if let Ok(passphrase) = env::var(PASSPHRASE_ENV_VAR) {
+        env::remove_var(PASSPHRASE_ENV_VAR);
+        return Ok(passphrase);
+    }
+
+    print!("The wallet is encrypted. Enter its passphrase: ");
+    io::stdout().flush().map_err(|e| e.to_string())?;
+    set_terminal_echo(false);
+    let mut passphrase = String::new();
+    let result = io::stdin().read_line(&mut passphrase);
+    set_terminal_echo(true);
+    println!();
+    result.map_err(|e| format!("Could not read the wallet passphrase. {}", e.to_string()))?;
+
+    let len = passphrase.trim_end_matches(|c| c == '\r' || c == '\n').len();
+    passphrase.truncate(len);
+    if passphrase.is_empty() {
+        return Err(format!(
+            "The wallet is encrypted. Enter its passphrase when prompted or set {}.",
+            PASSPHRASE_ENV_VAR
+        ));
+    }
+    Ok(passphrase)
+}
+
+/// Turns the echoing of typed characters on the terminal on or off. Nothing is done if the input is not a terminal.
+fn set_terminal_echo(echo: bool) {
+    #[cfg(unix)]
+    {
+        let _ = std::process::Command::new("stty")
+            .arg(if echo { "echo" } else { "-echo" })
+            .stdin(std::process::Stdio::inherit())
+            .stderr(std::process::Stdio::null())
+            .status();
+    }
+    #[cfg(not(unix))]
+    let _ = echo;
+}
+
+/// Reads the secret key of the wallet's node identity from the wallet database, unlocking the database with the
+/// passphrase first if the wallet is encrypted. Only encrypted wallets store it, apart from unencrypted wallets that
+/// were created before they kept it in an identity file instead.
+fn load_comms_secret_key(db_path: &str, passphrase: Option<&String>) -> Result<Option<CommsSecretKey>, String> {
+    let backend = WalletSqliteDatabase::new(db_path.to_string())
+        .map_err(|e| format!("Could not open the wallet database. {}", e.to_string()))?;
+    let mut db = WalletDatabase::new(backend);
+    if let Some(settings) = db.get_encryption_settings().map_err(|e| e.to_string())? {
+        let passphrase = passphrase
+            .ok_or_else(|| format!("The wallet is encrypted. Set {} to its passphrase.", PASSPHRASE_ENV_VAR))?;
+        // The secret key stays encrypted with the current passphrase until a pending encryption change is completed
+        let cipher = match db.get_pending_encryption().map_err(|e| e.to_string())? {
+            Some(pending) => pending
+                .ciphers(Some(&settings), passphrase)
+                .map(|(cipher, _)| cipher.expect("The wallet is encrypted")),
+            None => settings.cipher(passphrase),
+        }
+        .map_err(|e| format!("Could not unlock the wallet. {}", e.to_string()))?;
+        db.unlock(cipher).map_err(|e| e.to_string())?;
+    }
+    db.get_comms_secret_key().map_err(|e| e.to_string())
+}
+
+fn create_comms_config(
+    config: &GlobalConfig,
+    data_dir: &Path,
+    secret_key: CommsSecretKey,
+) -> Result<CommsConfig, String>
+{
+    let address = config.wallet_address.parse::<Multiaddr>().map_err(|e| {
+        format!(
+            "Error. '{}' is not a valid wallet address. {}",
+            config.wallet_address,
+            e.to_string()
+        )
+    })?;
+    let id = NodeIdentity::new(secret_key, address, PeerFeatures::COMMUNICATION_CLIENT)
+        .map_err(|e| format!("We were unable to construct a node identity. {}", e.to_string()))?;
+    let datastore_path = data_dir
+        .to_str()
+        .ok_or_else(|| "The wallet data folder is not a valid UTF-8 string".to_string())?;
+    Ok(CommsConfig {
+        node_identity: Arc::new(id.clone()),
+        peer_connection_listening_address: "/ip4/0.0.0.0/tcp/0".parse().expect("cannot fail"),
+        socks_proxy_address: None,
+        control_service: ControlServiceConfig {
+            listening_address: id.control_service_address(),
+            socks_proxy_address: None,
+            public_peer_address: None,
+            requested_connection_timeout: Duration::from_millis(2000),
+        },
+        establish_connection_timeout: Duration::from_secs(10),
+        datastore_path: datastore_path.to_string(),
+        peer_database_name: "peers".to_string(),
+        inbound_buffer_size: 100,
+        outbound_buffer_size: 100,
+        dht: Default::default(),
+    })
+}
+
+/// Parses a public key given in hex or as an emoji ID
+pub fn parse_public_key(public_key: &str) -> Result<CommsPublicKey, String> {
+    CommsPublicKey::from_hex(public_key.trim()).or_else(|e| {
+        EmojiId::from_emoji_string(public_key)
+            .map(|emoji_id| emoji_id.to_pubkey())
+            .map_err(|_| format!("{} is not a valid public key. {}", public_key, e.to_string()))
+    })
+}
+
+/// Parses a peer given as public_key::address into its public key and address
+pub fn parse_peer(peer: &str) -> Result<(CommsPublicKey, String), String> {
+    let parts: Vec<&str> = peer.split("::").map(|s| s.trim()).collect();
+    if parts.len() != 2 {
+        return Err(format!("{} is not of the form public_key::address", peer));
+    }
+    Ok((parse_public_key(parts[0])?, parts[1].to_string()))
+}
+
+/// Parses the wallet's gRPC address, given as a TCP socket with or without the tcp:// prefix
+pub fn parse_grpc_address(address: &str) -> Result<SocketAddr, String> {
+    address
+        .trim_start_matches("tcp://")
+        .parse::<SocketAddr>()
+        .map_err(|e| format!("{} is not a valid wallet gRPC address. {}", address, e.to_string()))
+}
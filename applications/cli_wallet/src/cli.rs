@@ -0,0 +1,289 @@
+// Copyright 2020, The Tari Project
+//
+// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+// following conditions are met:
+//
+// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+// disclaimer.
+//
+// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+// following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+// 3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+// products derived from this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+// INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+// SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+// WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+// USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+use crate::consts;
+use chrono::{NaiveDate, NaiveDateTime};
+use clap::{clap_app, ArgMatches};
+use tari_common::{bootstrap_config_from_cli, ConfigBootstrap};
+
+/// The default fee per gram, in µT, of transactions sent from the console wallet
+const DEFAULT_FEE_PER_GRAM: u64 = 25;
+
+/// Prints a pretty banner on the console
+pub fn print_banner() {
+    println!(
+        "\n$ Tari Console Wallet\n$ Copyright 2019-2020. {}\n$ Version {}\n",
+        consts::AUTHOR,
+        consts::VERSION
+    );
+}
+
+/// The wallet command given on the command line
+pub enum Command {
+    Create,
+    Restore {
+        seed_words: Vec<String>,
+        base_node: String,
+        birthday: Option<u64>,
+    },
+    Balance,
+    Send {
+        destination: String,
+        amount: u64,
+        fee_per_gram: u64,
+        message: String,
+    },
+    Request {
+        amount: Option<u64>,
+        message: String,
+        expires_in: Option<u64>,
+    },
+    Pay {
+        uri: String,
+        amount: Option<u64>,
+        fee_per_gram: u64,
+    },
+    ListPending,
+    ListCompleted,
+    History {
+        from: Option<NaiveDateTime>,
+        to: Option<NaiveDateTime>,
+        offset: usize,
+        limit: Option<usize>,
+        json: bool,
+        output: Option<String>,
+    },
+    CancelTransaction {
+        tx_id: u64,
+    },
+    ListContacts,
+    AddContact {
+        alias: String,
+        public_key: String,
+    },
+    RemoveContact {
+        public_key: String,
+    },
+    SeedWords,
+    AddBaseNode {
+        public_key: String,
+        address: String,
+    },
+    Run,
+}
+
+/// Parsed command-line arguments
+pub struct Arguments {
+    pub bootstrap: ConfigBootstrap,
+    pub command: Command,
+}
+
+/// Parse the command-line args and populate the minimal bootstrap config object
+pub fn parse_cli_args() -> Arguments {
+    let matches = clap_app!(myapp =>
+        (version: consts::VERSION)
+        (author: consts::AUTHOR)
+        (about: "The reference Tari cryptocurrency console wallet")
+        (@setting SubcommandRequiredElseHelp)
+        (@arg config: -c --config +takes_value "A path to the configuration file to use (config.toml)")
+        (@arg log_config: -l --log_config +takes_value "A path to the logfile configuration (log4rs.yml))")
+        (@arg init: --init "Create a default configuration file if it doesn't exist")
+        (@subcommand create =>
+            (about: "Create a new wallet and display its seed words")
+        )
+        (@subcommand restore =>
+            (about: "Create a wallet from seed words and recover its funds from the blockchain of a base node")
+            (@arg seed_words: +required "The seed words of the wallet, separated by spaces")
+            (@arg base_node: -b --base_node +takes_value +required {is_peer} "The base node to recover the funds from, as public_key::address")
+            (@arg birthday: --birthday +takes_value {is_number} "The block height to start scanning the blockchain at")
+        )
+        (@subcommand balance =>
+            (about: "Display the available, pending and time-locked balances")
+        )
+        (@subcommand send =>
+            (about: "Send Tari to another wallet")
+            (@arg destination: +required "The public key of the recipient, in hex or as an emoji ID")
+            (@arg amount: +required {is_number} "The amount to send, in µT")
+            (@arg fee_per_gram: -f --fee_per_gram +takes_value {is_number} "The fee per gram, in µT (default 25)")
+            (@arg message: -m --message +takes_value "A message for the recipient")
+        )
+        (@subcommand request =>
+            (about: "Display a payment request URI and the emoji ID of the wallet to be paid by another wallet")
+            (@arg amount: {is_number} "The amount to request, in µT (the payer chooses the amount if omitted)")
+            (@arg message: -m --message +takes_value "A message for the payer")
+            (@arg expires_in: --expires_in +takes_value {is_number} "The number of minutes after which the request expires")
+        )
+        (@subcommand pay =>
+            (about: "Pay a payment request URI")
+            (@arg uri: +required "The payment request URI, tari://...")
+            (@arg amount: -a --amount +takes_value {is_number} "The amount to pay, in µT, if the request has no amount")
+            (@arg fee_per_gram: -f --fee_per_gram +takes_value {is_number} "The fee per gram, in µT (default 25)")
+        )
+        (@subcommand list_pending =>
+            (about: "List the pending inbound and outbound transactions")
+        )
+        (@subcommand list_completed =>
+            (about: "List the completed transactions")
+        )
+        (@subcommand history =>
+            (about: "Export the completed transactions, in the order they were completed in, as CSV or JSON")
+            (@arg from: --from +takes_value {is_date} "Only include the transactions completed on or after this date, as YYYY-MM-DD")
+            (@arg to: --to +takes_value {is_date} "Only include the transactions completed before this date, as YYYY-MM-DD")
+            (@arg offset: --offset +takes_value {is_number} "The number of transactions to skip")
+            (@arg limit: --limit +takes_value {is_number} "The maximum number of transactions to export")
+            (@arg json: --json "Export the transactions as JSON instead of CSV")
+            (@arg output: -o --output +takes_value "The file to write the transactions to, instead of the console")
+        )
+        (@subcommand cancel =>
+            (about: "Cancel a pending transaction and notify the counterparty")
+            (@arg tx_id: +required {is_number} "The id of the transaction to cancel")
+        )
+        (@subcommand contacts =>
+            (about: "Manage the wallet's contacts")
+            (@setting SubcommandRequiredElseHelp)
+            (@subcommand list =>
+                (about: "List the saved contacts")
+            )
+            (@subcommand add =>
+                (about: "Save a contact")
+                (@arg alias: +required "The alias of the contact")
+                (@arg public_key: +required "The public key of the contact, in hex or as an emoji ID")
+            )
+            (@subcommand remove =>
+                (about: "Remove a saved contact")
+                (@arg public_key: +required "The public key of the contact, in hex or as an emoji ID")
+            )
+        )
+        (@subcommand seed_words =>
+            (about: "Display the seed words of the wallet")
+        )
+        (@subcommand add_base_node =>
+            (about: "Add the base node that the wallet submits transactions to and validates its outputs against")
+            (@arg public_key: +required "The public key of the base node, in hex")
+            (@arg address: +required "The address of the base node, e.g. /ip4/127.0.0.1/tcp/18189")
+        )
+        (@subcommand run =>
+            (about: "Keep the wallet online to exchange transaction messages until Ctrl-C is pressed")
+        )
+    )
+    .get_matches();
+
+    let bootstrap = bootstrap_config_from_cli(&matches);
+    let command = parse_command(&matches);
+
+    Arguments { bootstrap, command }
+}
+
+/// Convert the matched subcommand into a wallet command. The values have already been validated by clap.
+fn parse_command(matches: &ArgMatches) -> Command {
+    match matches.subcommand() {
+        ("restore", Some(m)) => Command::Restore {
+            seed_words: value(m, "seed_words").split_whitespace().map(String::from).collect(),
+            base_node: value(m, "base_node"),
+            birthday: m.value_of("birthday").map(|b| b.parse().unwrap()),
+        },
+        ("balance", _) => Command::Balance,
+        ("send", Some(m)) => Command::Send {
+            destination: value(m, "destination"),
+            amount: value(m, "amount").parse().unwrap(),
+            fee_per_gram: m
+                .value_of("fee_per_gram")
+                .map(|f| f.parse().unwrap())
+                .unwrap_or(DEFAULT_FEE_PER_GRAM),
+            message: m.value_of("message").unwrap_or_default().to_string(),
+        },
+        ("request", Some(m)) => Command::Request {
+            amount: m.value_of("amount").map(|a| a.parse().unwrap()),
+            message: m.value_of("message").unwrap_or_default().to_string(),
+            expires_in: m.value_of("expires_in").map(|e| e.parse().unwrap()),
+        },
+        ("pay", Some(m)) => Command::Pay {
+            uri: value(m, "uri"),
+            amount: m.value_of("amount").map(|a| a.parse().unwrap()),
+            fee_per_gram: m
+                .value_of("fee_per_gram")
+                .map(|f| f.parse().unwrap())
+                .unwrap_or(DEFAULT_FEE_PER_GRAM),
+        },
+        ("list_pending", _) => Command::ListPending,
+        ("list_completed", _) => Command::ListCompleted,
+        ("history", Some(m)) => Command::History {
+            from: m.value_of("from").map(|d| parse_date(d).unwrap()),
+            to: m.value_of("to").map(|d| parse_date(d).unwrap()),
+            offset: m.value_of("offset").map(|o| o.parse().unwrap()).unwrap_or_default(),
+            limit: m.value_of("limit").map(|l| l.parse().unwrap()),
+            json: m.is_present("json"),
+            output: m.value_of("output").map(String::from),
+        },
+        ("cancel", Some(m)) => Command::CancelTransaction {
+            tx_id: value(m, "tx_id").parse().unwrap(),
+        },
+        ("contacts", Some(m)) => match m.subcommand() {
+            ("add", Some(m)) => Command::AddContact {
+                alias: value(m, "alias"),
+                public_key: value(m, "public_key"),
+            },
+            ("remove", Some(m)) => Command::RemoveContact {
+                public_key: value(m, "public_key"),
+            },
+            _ => Command::ListContacts,
+        },
+        ("seed_words", _) => Command::SeedWords,
+        ("add_base_node", Some(m)) => Command::AddBaseNode {
+            public_key: value(m, "public_key"),
+            address: value(m, "address"),
+        },
+        ("run", _) => Command::Run,
+        _ => Command::Create,
+    }
+}
+
+fn value(matches: &ArgMatches, name: &str) -> String {
+    matches.value_of(name).unwrap_or_default().to_string()
+}
+
+fn is_number(s: String) -> Result<(), String> {
+    s.parse::<u64>()
+        .map(|_| ())
+        .map_err(|_| format!("{} is not a valid number", s))
+}
+
+fn is_date(s: String) -> Result<(), String> {
+    parse_date(&s)
+        .map(|_| ())
+        .ok_or_else(|| format!("{} is not a valid date of the form YYYY-MM-DD", s))
+}
+
+/// The start of the given day, in UTC
+fn parse_date(s: &str) -> Option<NaiveDateTime> {
+    NaiveDate::parse_from_str(s, "%Y-%m-%d")
+        .ok()
+        .map(|d| d.and_hms(0, 0, 0))
+}
+
+fn is_peer(s: String) -> Result<(), String> {
+    if s.split("::").count() == 2 {
+        Ok(())
+    } else {
+        Err(format!("{} is not of the form public_key::address", s))
+    }
+}
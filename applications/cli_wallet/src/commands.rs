@@ -0,0 +1,358 @@
+// Copyright 2020, The Tari Project
+//
+// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+// following conditions are met:
+//
+// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+// disclaimer.
+//
+// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+// following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+// 3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+// products derived from this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+// INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+// SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+// WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+// USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+use crate::{
+    builder::{parse_grpc_address, parse_peer, parse_public_key, ConsoleWallet},
+    cli::Command,
+};
+use chrono::{Duration as ChronoDuration, Utc};
+use futures::{FutureExt, StreamExt};
+use log::*;
+use std::fs;
+use tari_common::GlobalConfig;
+use tari_core::transactions::tari_amount::MicroTari;
+use tari_grpc_wallet::wallet_server::WalletServer;
+use tari_utilities::hex::Hex;
+use tari_wallet::{
+    contacts_service::storage::database::Contact,
+    emoji::EmojiId,
+    payment_request::PaymentRequest,
+    recovery_service::handle::RecoveryEvent,
+    transaction_service::{history::TransactionHistoryFormat, storage::database::TransactionHistoryQuery},
+};
+use tokio::signal;
+
+const LOG_TARGET: &str = "console_wallet::commands";
+
+/// Runs a wallet command, printing its output on the console
+pub fn run_command(wallet: &mut ConsoleWallet, command: Command, config: &GlobalConfig) -> Result<(), String> {
+    match command {
+        Command::Create => {
+            println!("Created a new wallet with public key {}", public_key(wallet));
+            println!(
+                "Emoji ID: {}",
+                EmojiId::from_pubkey(wallet.comms.node_identity().public_key())
+            );
+            show_seed_words(wallet)
+        },
+        Command::Restore {
+            seed_words,
+            base_node,
+            birthday,
+        } => restore(wallet, seed_words, base_node, birthday),
+        Command::Balance => {
+            let balance = wallet
+                .runtime
+                .block_on(wallet.output_manager_service.get_balance())
+                .map_err(|e| e.to_string())?;
+            println!("Available balance: {}", balance.available_balance);
+            println!("Pending incoming balance: {}", balance.pending_incoming_balance);
+            println!("Pending outgoing balance: {}", balance.pending_outgoing_balance);
+            println!("Time-locked balance: {}", balance.time_locked_balance);
+            Ok(())
+        },
+        Command::Send {
+            destination,
+            amount,
+            fee_per_gram,
+            message,
+        } => {
+            let destination = parse_public_key(&destination)?;
+            let tx_id = wallet
+                .runtime
+                .block_on(wallet.transaction_service.send_transaction(
+                    destination,
+                    MicroTari::from(amount),
+                    MicroTari::from(fee_per_gram),
+                    message,
+                ))
+                .map_err(|e| e.to_string())?;
+            println!(
+                "Sent {} in transaction {}. Run the wallet to receive the reply of the recipient.",
+                MicroTari::from(amount),
+                tx_id
+            );
+            Ok(())
+        },
+        Command::Request {
+            amount,
+            message,
+            expires_in,
+        } => {
+            let mut request = PaymentRequest::new(config.network, wallet.comms.node_identity().public_key().clone())
+                .with_message(message);
+            if let Some(amount) = amount {
+                request = request.with_amount(MicroTari::from(amount));
+            }
+            if let Some(minutes) = expires_in {
+                request = request.with_expiry(Utc::now().naive_utc() + ChronoDuration::minutes(minutes as i64));
+            }
+            println!("Payment request: {}", request);
+            println!(
+                "Emoji ID: {}",
+                EmojiId::from_pubkey(wallet.comms.node_identity().public_key())
+            );
+            Ok(())
+        },
+        Command::Pay {
+            uri,
+            amount,
+            fee_per_gram,
+        } => {
+            let request = PaymentRequest::from_uri(&uri).map_err(|e| e.to_string())?;
+            let tx_id = wallet
+                .runtime
+                .block_on(wallet.transaction_service.pay_payment_request(
+                    request,
+                    amount.map(MicroTari::from),
+                    MicroTari::from(fee_per_gram),
+                ))
+                .map_err(|e| e.to_string())?;
+            println!(
+                "Paid the payment request in transaction {}. Run the wallet to receive the reply of the recipient.",
+                tx_id
+            );
+            Ok(())
+        },
+        Command::ListPending => {
+            let inbound = wallet
+                .runtime
+                .block_on(wallet.transaction_service.get_pending_inbound_transactions())
+                .map_err(|e| e.to_string())?;
+            let outbound = wallet
+                .runtime
+                .block_on(wallet.transaction_service.get_pending_outbound_transactions())
+                .map_err(|e| e.to_string())?;
+            println!("Pending inbound transactions: {}", inbound.len());
+            for tx in inbound.values() {
+                println!(
+                    "{} {} from {} at {} \"{}\"",
+                    tx.tx_id,
+                    tx.amount,
+                    tx.source_public_key.to_hex(),
+                    tx.timestamp,
+                    tx.message
+                );
+            }
+            println!("Pending outbound transactions: {}", outbound.len());
+            for tx in outbound.values() {
+                println!(
+                    "{} {} (fee {}) to {} at {} \"{}\"",
+                    tx.tx_id,
+                    tx.amount,
+                    tx.fee,
+                    tx.destination_public_key.to_hex(),
+                    tx.timestamp,
+                    tx.message
+                );
+            }
+            Ok(())
+        },
+        Command::ListCompleted => {
+            let completed = wallet
+                .runtime
+                .block_on(wallet.transaction_service.get_completed_transactions())
+                .map_err(|e| e.to_string())?;
+            println!("Completed transactions: {}", completed.len());
+            for tx in completed.values() {
+                println!(
+                    "{} {} (fee {}) from {} to {} at {} {:?} \"{}\"",
+                    tx.tx_id,
+                    tx.amount,
+                    tx.fee,
+                    tx.source_public_key.to_hex(),
+                    tx.destination_public_key.to_hex(),
+                    tx.timestamp,
+                    tx.status,
+                    tx.message
+                );
+            }
+            Ok(())
+        },
+        Command::History {
+            from,
+            to,
+            offset,
+            limit,
+            json,
+            output,
+        } => {
+            let query = TransactionHistoryQuery {
+                from,
+                to,
+                offset,
+                limit,
+            };
+            let format = if json {
+                TransactionHistoryFormat::Json
+            } else {
+                TransactionHistoryFormat::Csv
+            };
+            let history = wallet
+                .runtime
+                .block_on(wallet.transaction_service.export_transaction_history(query, format))
+                .map_err(|e| e.to_string())?;
+            match output {
+                Some(path) => {
+                    fs::write(&path, history).map_err(|e| format!("Could not write {}. {}", path, e))?;
+                    println!("Transaction history written to {}", path);
+                },
+                None => println!("{}", history.trim_end()),
+            }
+            Ok(())
+        },
+        Command::CancelTransaction { tx_id } => {
+            wallet
+                .runtime
+                .block_on(wallet.transaction_service.cancel_transaction(tx_id))
+                .map_err(|e| e.to_string())?;
+            println!("Cancelled transaction {}", tx_id);
+            Ok(())
+        },
+        Command::ListContacts => {
+            let contacts = wallet
+                .runtime
+                .block_on(wallet.contacts_service.get_contacts())
+                .map_err(|e| e.to_string())?;
+            for contact in contacts {
+                println!("{} {}", contact.alias, contact.public_key.to_hex());
+            }
+            Ok(())
+        },
+        Command::AddContact { alias, public_key } => {
+            let public_key = parse_public_key(&public_key)?;
+            wallet
+                .runtime
+                .block_on(wallet.contacts_service.save_contact(Contact { alias, public_key }))
+                .map_err(|e| e.to_string())?;
+            println!("Contact saved");
+            Ok(())
+        },
+        Command::RemoveContact { public_key } => {
+            let public_key = parse_public_key(&public_key)?;
+            let contact = wallet
+                .runtime
+                .block_on(wallet.contacts_service.remove_contact(public_key))
+                .map_err(|e| e.to_string())?;
+            println!("Contact {} removed", contact.alias);
+            Ok(())
+        },
+        Command::SeedWords => show_seed_words(wallet),
+        Command::AddBaseNode { public_key, address } => {
+            let public_key = parse_public_key(&public_key)?;
+            wallet
+                .add_base_node_peer(public_key, address)
+                .map_err(|e| e.to_string())?;
+            println!("Base node peer added");
+            Ok(())
+        },
+        Command::Run => {
+            if config.wallet_grpc_enabled {
+                start_grpc_server(wallet, &config.wallet_grpc_address)?;
+            }
+            println!("Wallet {} is running. Press Ctrl-C to quit..", public_key(wallet));
+            run(wallet);
+            Ok(())
+        },
+    }
+}
+
+fn public_key(wallet: &ConsoleWallet) -> String {
+    wallet.comms.node_identity().public_key().to_hex()
+}
+
+fn show_seed_words(wallet: &mut ConsoleWallet) -> Result<(), String> {
+    let seed_words = wallet
+        .runtime
+        .block_on(wallet.output_manager_service.get_seed_words())
+        .map_err(|e| e.to_string())?;
+    println!("Seed words: {}", seed_words.join(" "));
+    Ok(())
+}
+
+/// Recovers the wallet's funds from the base node's blockchain, printing the recovery progress until it has finished
+fn restore(
+    wallet: &mut ConsoleWallet,
+    seed_words: Vec<String>,
+    base_node: String,
+    birthday: Option<u64>,
+) -> Result<(), String>
+{
+    let (public_key, address) = parse_peer(&base_node)?;
+    wallet
+        .add_base_node_peer(public_key, address)
+        .map_err(|e| e.to_string())?;
+    let mut event_stream = wallet.recovery_service.get_event_stream_fused();
+    wallet
+        .recover_from_seed_words(seed_words, birthday)
+        .map_err(|e| e.to_string())?;
+
+    wallet.runtime.block_on(async move {
+        while let Some(event) = event_stream.next().await {
+            match &*event {
+                RecoveryEvent::Progress((height, tip)) => println!("Scanned {} of {} blocks", height, tip),
+                RecoveryEvent::OutputRecovered((height, value)) => {
+                    println!("Recovered an output of {} at height {}", value, height)
+                },
+                RecoveryEvent::RecoveryComplete((count, value)) => {
+                    println!(
+                        "Recovery complete. {} outputs with a total value of {} were recovered",
+                        count, value
+                    );
+                    return Ok(());
+                },
+                RecoveryEvent::Error(e) => return Err(format!("Recovery failed. {}", e)),
+            }
+        }
+        Err("The recovery service stopped unexpectedly".to_string())
+    })
+}
+
+/// Serves the wallet's gRPC API on the wallet's runtime for as long as the wallet is running
+fn start_grpc_server(wallet: &mut ConsoleWallet, address: &str) -> Result<(), String> {
+    let address = parse_grpc_address(address)?;
+    let server = WalletServer::new(wallet);
+    wallet.runtime.spawn(async move {
+        if let Err(e) = server.serve(address).await {
+            error!(
+                target: LOG_TARGET,
+                "The wallet gRPC server stopped with an error. {}", e
+            );
+        }
+    });
+    println!("Serving the wallet gRPC API at {}", address);
+    Ok(())
+}
+
+/// Keeps the wallet online, printing the transaction events, until Ctrl-C is pressed
+fn run(wallet: &mut ConsoleWallet) {
+    let mut event_stream = wallet.transaction_service.get_event_stream_fused();
+    wallet.runtime.block_on(async move {
+        let mut shutdown = signal::ctrl_c().boxed().fuse();
+        loop {
+            futures::select! {
+                event = event_stream.select_next_some() => println!("{:?}", *event),
+                _ = shutdown => break,
+            }
+        }
+    });
+}
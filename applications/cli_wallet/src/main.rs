@@ -0,0 +1,137 @@
+// Copyright 2020, The Tari Project
+//
+// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+// following conditions are met:
+//
+// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+// disclaimer.
+//
+// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+// following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+// 3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+// products derived from this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+// INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+// SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+// WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+// USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+/// Utilities and helpers for building the wallet instance
+mod builder;
+/// The command line interface definition and configuration
+mod cli;
+/// The implementation of the wallet commands
+mod commands;
+/// Application-specific constants
+mod consts;
+
+use crate::cli::Command;
+use log::*;
+use tari_common::{load_configuration, GlobalConfig};
+use tokio::runtime::Runtime;
+
+const LOG_TARGET: &str = "console_wallet::app";
+
+fn main() {
+    cli::print_banner();
+    // Create the tari data directory
+    if let Err(e) = tari_common::dir_utils::create_data_directory() {
+        println!(
+            "We couldn't create a default Tari data directory and have to quit now. This makes us sad :(\n {}",
+            e.to_string()
+        );
+        std::process::exit(1);
+    }
+
+    // Parse and validate command-line arguments
+    let arguments = cli::parse_cli_args();
+
+    // Initialise the logger
+    if !tari_common::initialize_logging(&arguments.bootstrap.log_config) {
+        std::process::exit(1);
+    }
+
+    // Load and apply configuration file
+    let cfg = match load_configuration(&arguments.bootstrap) {
+        Ok(cfg) => cfg,
+        Err(s) => {
+            error!(target: LOG_TARGET, "{}", s);
+            std::process::exit(1);
+        },
+    };
+
+    // Populate the configuration struct
+    let config = match GlobalConfig::convert_from(cfg) {
+        Ok(c) => c,
+        Err(e) => {
+            error!(target: LOG_TARGET, "The configuration file has an error. {}", e);
+            std::process::exit(1);
+        },
+    };
+
+    // Set up the Tokio runtime
+    let rt = match setup_runtime(&config) {
+        Ok(rt) => rt,
+        Err(s) => {
+            error!(target: LOG_TARGET, "{}", s);
+            std::process::exit(1);
+        },
+    };
+
+    // Only the create and restore commands make a new wallet file, every other command needs an existing one
+    let create = match arguments.command {
+        Command::Create | Command::Restore { .. } => true,
+        _ => false,
+    };
+    let mut wallet = match builder::initialize_wallet(&config, create, rt) {
+        Ok(w) => w,
+        Err(e) => {
+            println!("{}", e);
+            error!(target: LOG_TARGET, "Could not instantiate the wallet. {}", e);
+            std::process::exit(1);
+        },
+    };
+
+    let result = commands::run_command(&mut wallet, arguments.command, &config);
+
+    if let Err(e) = wallet.shutdown() {
+        warn!(
+            target: LOG_TARGET,
+            "The wallet did not shut down cleanly: {}",
+            e.to_string()
+        );
+    }
+    if let Err(e) = result {
+        println!("{}", e);
+        error!(target: LOG_TARGET, "The wallet command failed. {}", e);
+        std::process::exit(1);
+    }
+}
+
+fn setup_runtime(config: &GlobalConfig) -> Result<Runtime, String> {
+    let num_core_threads = config.core_threads;
+    let num_blocking_threads = config.blocking_threads;
+
+    debug!(
+        target: LOG_TARGET,
+        "Configuring the wallet to run on {} core threads and {} blocking worker threads.",
+        num_core_threads,
+        num_blocking_threads
+    );
+    tokio::runtime::Builder::new()
+        .threaded_scheduler()
+        .enable_all()
+        .max_threads(num_core_threads + num_blocking_threads)
+        .core_threads(num_core_threads)
+        .build()
+        .map_err(|e| {
+            format!(
+                "There was an error while building the wallet runtime. {}",
+                e.to_string()
+            )
+        })
+}
@@ -21,10 +21,6 @@
 // USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
 fn main() {
-    // Build wallet
-    tower_grpc_build::Config::new()
-        .enable_server(true)
-        .enable_client(true)
-        .build(&["proto/wallet_rpc.proto"], &["proto/"])
+    tonic_build::compile_protos("proto/wallet_rpc.proto")
         .unwrap_or_else(|e| panic!("protobuf compilation failed: {}", e));
 }
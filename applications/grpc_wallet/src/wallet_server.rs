@@ -20,63 +20,397 @@
 // WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
 // USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
-use crate::grpc_interface::{wallet_rpc::server, WalletRPC};
+use crate::wallet_rpc::{
+    wallet_rpc_server::{WalletRpc, WalletRpcServer},
+    Balance as BalanceRpc,
+    Contact as ContactRpc,
+    Contacts as ContactsRpc,
+    Empty,
+    GetTransactionInfoRequest,
+    GetTransactionInfoResponse,
+    Identity as IdentityRpc,
+    ListTransactionsResponse,
+    PublicKey as PublicKeyRpc,
+    SeedWords as SeedWordsRpc,
+    TransactionDirection,
+    TransactionEvent as TransactionEventRpc,
+    TransactionInfo,
+    TransactionStatus as TransactionStatusRpc,
+    TransferRequest,
+    TransferResponse,
+};
 use derive_error::Error;
-use futures::{future::Future, stream::Stream};
-use hyper::server::conn::Http;
+use futures::StreamExt;
 use log::*;
-use std::{net::AddrParseError, sync::Arc};
-use tari_utilities::message_format::MessageFormatError;
-use tari_wallet::Wallet;
-use tokio::net::TcpListener;
-use tower_hyper::Server;
+use std::{collections::HashMap, net::SocketAddr, sync::Arc};
+use tari_comms::{peer_manager::NodeIdentity, types::CommsPublicKey};
+use tari_core::transactions::tari_amount::MicroTari;
+use tari_utilities::hex::Hex;
+use tari_wallet::{
+    contacts_service::{
+        handle::ContactsServiceHandle,
+        storage::database::{Contact, ContactsBackend},
+    },
+    output_manager_service::{handle::OutputManagerHandle, storage::database::OutputManagerBackend, TxId},
+    storage::database::WalletBackend,
+    transaction_service::{
+        handle::{TransactionEvent, TransactionServiceHandle},
+        storage::database::{
+            CompletedTransaction,
+            InboundTransaction,
+            OutboundTransaction,
+            TransactionBackend,
+            TransactionStatus,
+        },
+    },
+    Wallet,
+};
+use tokio::sync::mpsc;
+use tonic::{transport::Server, Request, Response, Status};
 
 const LOG_TARGET: &str = "applications::grpc_wallet";
 
+/// The number of Transaction Service events that are buffered for a TransactionEvents stream
+const EVENT_BUFFER_SIZE: usize = 100;
+
 #[derive(Debug, Error)]
 pub enum WalletServerError {
-    AddrParseError(AddrParseError),
-    IoError(std::io::Error),
-    MessageFormatError(MessageFormatError),
+    TransportError(tonic::transport::Error),
 }
 
-/// Instance of the Wallet RPC Server with a reference to the Wallet API and the config
+/// Instance of the Wallet RPC Server, which serves the gRPC API over the service handles of a running wallet
+#[derive(Clone)]
 pub struct WalletServer {
     // TODO some form of authentication
-    port: u32,
-    wallet: Arc<Wallet>,
+    node_identity: Arc<NodeIdentity>,
+    output_manager_service: OutputManagerHandle,
+    transaction_service: TransactionServiceHandle,
+    contacts_service: ContactsServiceHandle,
 }
 
 impl WalletServer {
-    pub fn new(port: u32, wallet: Arc<Wallet>) -> WalletServer {
-        WalletServer { port, wallet }
+    pub fn new<T, U, V, W>(wallet: &Wallet<T, U, V, W>) -> WalletServer
+    where
+        T: WalletBackend,
+        U: TransactionBackend + Clone + 'static,
+        V: OutputManagerBackend + 'static,
+        W: ContactsBackend + 'static,
+    {
+        WalletServer {
+            node_identity: wallet.comms.node_identity(),
+            output_manager_service: wallet.output_manager_service.clone(),
+            transaction_service: wallet.transaction_service.clone(),
+            contacts_service: wallet.contacts_service.clone(),
+        }
     }
 
-    pub fn start(self) -> Result<(), WalletServerError> {
-        let new_service = server::WalletRpcServer::new(WalletRPC {
-            wallet: self.wallet.clone(),
-        });
+    /// Serve the gRPC API at the given address until the returned future is dropped
+    pub async fn serve(self, address: SocketAddr) -> Result<(), WalletServerError> {
+        info!(target: LOG_TARGET, "Starting Wallet gRPC Server at {}", address);
+        Server::builder()
+            .add_service(WalletRpcServer::new(self))
+            .serve(address)
+            .await?;
+        Ok(())
+    }
+
+    fn inbound_transaction_info(&self, tx: InboundTransaction) -> TransactionInfo {
+        TransactionInfo {
+            tx_id: tx.tx_id,
+            source_public_key: tx.source_public_key.to_hex(),
+            destination_public_key: self.node_identity.public_key().to_hex(),
+            direction: TransactionDirection::Inbound as i32,
+            amount: u64::from(tx.amount),
+            fee: 0,
+            status: TransactionStatusRpc::Pending as i32,
+            message: tx.message,
+            timestamp: tx.timestamp.to_string(),
+        }
+    }
+
+    fn outbound_transaction_info(&self, tx: OutboundTransaction) -> TransactionInfo {
+        TransactionInfo {
+            tx_id: tx.tx_id,
+            source_public_key: self.node_identity.public_key().to_hex(),
+            destination_public_key: tx.destination_public_key.to_hex(),
+            direction: TransactionDirection::Outbound as i32,
+            amount: u64::from(tx.amount),
+            fee: u64::from(tx.fee),
+            status: TransactionStatusRpc::Pending as i32,
+            message: tx.message,
+            timestamp: tx.timestamp.to_string(),
+        }
+    }
+
+    fn completed_transaction_info(&self, tx: CompletedTransaction) -> TransactionInfo {
+        let direction = if &tx.source_public_key == self.node_identity.public_key() {
+            TransactionDirection::Outbound
+        } else {
+            TransactionDirection::Inbound
+        };
+        let status = match tx.status {
+            TransactionStatus::Completed => TransactionStatusRpc::Completed,
+            TransactionStatus::Broadcast => TransactionStatusRpc::Broadcast,
+            TransactionStatus::Mined => TransactionStatusRpc::Mined,
+        };
+        TransactionInfo {
+            tx_id: tx.tx_id,
+            source_public_key: tx.source_public_key.to_hex(),
+            destination_public_key: tx.destination_public_key.to_hex(),
+            direction: direction as i32,
+            amount: u64::from(tx.amount),
+            fee: u64::from(tx.fee),
+            status: status as i32,
+            message: tx.message,
+            timestamp: tx.timestamp.to_string(),
+        }
+    }
+
+    /// Fetch all the pending and completed transactions of the wallet, keyed by their tx_id
+    async fn transactions(&self) -> Result<HashMap<TxId, TransactionInfo>, Status> {
+        let mut transaction_service = self.transaction_service.clone();
+        let inbound = transaction_service
+            .get_pending_inbound_transactions()
+            .await
+            .map_err(|e| Status::internal(e.to_string()))?;
+        let outbound = transaction_service
+            .get_pending_outbound_transactions()
+            .await
+            .map_err(|e| Status::internal(e.to_string()))?;
+        let completed = transaction_service
+            .get_completed_transactions()
+            .await
+            .map_err(|e| Status::internal(e.to_string()))?;
 
-        let mut server = Server::new(new_service);
+        let mut transactions = HashMap::new();
+        for (tx_id, tx) in inbound {
+            transactions.insert(tx_id, self.inbound_transaction_info(tx));
+        }
+        for (tx_id, tx) in outbound {
+            transactions.insert(tx_id, self.outbound_transaction_info(tx));
+        }
+        for (tx_id, tx) in completed {
+            transactions.insert(tx_id, self.completed_transaction_info(tx));
+        }
+        Ok(transactions)
+    }
+}
+
+impl From<Contact> for ContactRpc {
+    fn from(c: Contact) -> Self {
+        ContactRpc {
+            alias: c.alias,
+            public_key: c.public_key.to_hex(),
+        }
+    }
+}
+
+impl From<&TransactionEvent> for TransactionEventRpc {
+    fn from(event: &TransactionEvent) -> Self {
+        let (event, tx_id, details) = match event {
+            TransactionEvent::ReceivedTransaction(tx_id) => ("ReceivedTransaction", *tx_id, String::new()),
+            TransactionEvent::ReceivedTransactionReply(tx_id) => ("ReceivedTransactionReply", *tx_id, String::new()),
+            TransactionEvent::ReceivedFinalizedTransaction(tx_id) => {
+                ("ReceivedFinalizedTransaction", *tx_id, String::new())
+            },
+            TransactionEvent::TransactionSendDiscoveryComplete(tx_id, result) => {
+                ("TransactionSendDiscoveryComplete", *tx_id, result.to_string())
+            },
+            TransactionEvent::TransactionCancelled(tx_id) => ("TransactionCancelled", *tx_id, String::new()),
+            TransactionEvent::TransactionBroadcast(tx_id) => ("TransactionBroadcast", *tx_id, String::new()),
+            TransactionEvent::TransactionMined(tx_id) => ("TransactionMined", *tx_id, String::new()),
+            TransactionEvent::TransactionConfirmations(tx_id, confirmations) => {
+                ("TransactionConfirmations", *tx_id, confirmations.to_string())
+            },
+            TransactionEvent::TransactionDroppedFromMempool(tx_id) => {
+                ("TransactionDroppedFromMempool", *tx_id, String::new())
+            },
+            TransactionEvent::TransactionSendDiscoverySuccess(tx_id) => {
+                ("TransactionSendDiscoverySuccess", *tx_id, String::new())
+            },
+            TransactionEvent::TransactionSendDiscoveryFailure(tx_id) => {
+                ("TransactionSendDiscoveryFailure", *tx_id, String::new())
+            },
+            TransactionEvent::ReceivedOfflineTransactionReply(tx_id) => {
+                ("ReceivedOfflineTransactionReply", *tx_id, String::new())
+            },
+            TransactionEvent::TransactionStoreAndForwardSend(tx_id) => {
+                ("TransactionStoreAndForwardSend", *tx_id, String::new())
+            },
+            TransactionEvent::Error(e) => ("Error", 0, e.clone()),
+        };
+        TransactionEventRpc {
+            event: event.to_string(),
+            tx_id,
+            details,
+        }
+    }
+}
+
+fn parse_public_key(public_key: &str) -> Result<CommsPublicKey, Status> {
+    CommsPublicKey::from_hex(public_key)
+        .map_err(|e| Status::invalid_argument(format!("{} is not a valid public key: {}", public_key, e)))
+}
 
-        let http = Http::new().http2_only(true).clone();
-        let addr = format!("127.0.0.1:{}", self.port);
-        let bind = TcpListener::bind(&addr.clone().as_str().parse()?)?;
-        let serve = bind
-            .incoming()
-            .for_each(move |sock| {
-                if let Err(e) = sock.set_nodelay(true) {
-                    return Err(e);
+/// Implementation of the gRPC service methods
+#[tonic::async_trait]
+impl WalletRpc for WalletServer {
+    type TransactionEventsStream = mpsc::Receiver<Result<TransactionEventRpc, Status>>;
+
+    async fn get_balance(&self, request: Request<Empty>) -> Result<Response<BalanceRpc>, Status> {
+        trace!(target: LOG_TARGET, "GetBalance gRPC Request received: {:?}", request);
+        let balance = self
+            .output_manager_service
+            .clone()
+            .get_balance()
+            .await
+            .map_err(|e| Status::internal(e.to_string()))?;
+        Ok(Response::new(BalanceRpc {
+            available_balance: u64::from(balance.available_balance),
+            pending_incoming_balance: u64::from(balance.pending_incoming_balance),
+            pending_outgoing_balance: u64::from(balance.pending_outgoing_balance),
+            time_locked_balance: u64::from(balance.time_locked_balance),
+        }))
+    }
+
+    async fn transfer(&self, request: Request<TransferRequest>) -> Result<Response<TransferResponse>, Status> {
+        trace!(target: LOG_TARGET, "Transfer gRPC Request received: {:?}", request);
+        let request = request.into_inner();
+        let destination = parse_public_key(&request.destination)?;
+        let tx_id = self
+            .transaction_service
+            .clone()
+            .send_transaction(
+                destination,
+                MicroTari::from(request.amount),
+                MicroTari::from(request.fee_per_gram),
+                request.message,
+            )
+            .await
+            .map_err(|e| Status::internal(e.to_string()))?;
+        Ok(Response::new(TransferResponse { tx_id }))
+    }
+
+    async fn get_transaction_info(
+        &self,
+        request: Request<GetTransactionInfoRequest>,
+    ) -> Result<Response<GetTransactionInfoResponse>, Status>
+    {
+        trace!(
+            target: LOG_TARGET,
+            "GetTransactionInfo gRPC Request received: {:?}",
+            request
+        );
+        let mut all_transactions = self.transactions().await?;
+        let transactions = request
+            .into_inner()
+            .tx_ids
+            .into_iter()
+            .map(|tx_id| {
+                all_transactions
+                    .remove(&tx_id)
+                    .ok_or_else(|| Status::not_found(format!("Transaction {} not found", tx_id)))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(Response::new(GetTransactionInfoResponse { transactions }))
+    }
+
+    async fn list_transactions(&self, request: Request<Empty>) -> Result<Response<ListTransactionsResponse>, Status> {
+        trace!(
+            target: LOG_TARGET,
+            "ListTransactions gRPC Request received: {:?}",
+            request
+        );
+        let mut transactions = self
+            .transactions()
+            .await?
+            .into_iter()
+            .map(|(_, tx)| tx)
+            .collect::<Vec<_>>();
+        transactions.sort_by_key(|tx| tx.tx_id);
+        Ok(Response::new(ListTransactionsResponse { transactions }))
+    }
+
+    async fn transaction_events(
+        &self,
+        request: Request<Empty>,
+    ) -> Result<Response<Self::TransactionEventsStream>, Status>
+    {
+        trace!(
+            target: LOG_TARGET,
+            "TransactionEvents gRPC Request received: {:?}",
+            request
+        );
+        let mut event_stream = self.transaction_service.get_event_stream_fused();
+        let (mut sender, receiver) = mpsc::channel(EVENT_BUFFER_SIZE);
+        tokio::spawn(async move {
+            while let Some(event) = event_stream.next().await {
+                if sender.send(Ok(TransactionEventRpc::from(&*event))).await.is_err() {
+                    debug!(target: LOG_TARGET, "TransactionEvents gRPC stream closed by the client");
+                    break;
                 }
+            }
+        });
+        Ok(Response::new(receiver))
+    }
 
-                let serve = server.serve_with(sock, http.clone());
-                tokio::spawn(serve.map_err(|e| error!("Error starting Hyper service: {:?}", e)));
+    async fn get_contacts(&self, request: Request<Empty>) -> Result<Response<ContactsRpc>, Status> {
+        trace!(target: LOG_TARGET, "GetContacts gRPC Request received: {:?}", request);
+        let contacts = self
+            .contacts_service
+            .clone()
+            .get_contacts()
+            .await
+            .map_err(|e| Status::internal(e.to_string()))?;
+        Ok(Response::new(ContactsRpc {
+            contacts: contacts.into_iter().map(ContactRpc::from).collect(),
+        }))
+    }
 
-                Ok(())
+    async fn add_contact(&self, request: Request<ContactRpc>) -> Result<Response<Empty>, Status> {
+        trace!(target: LOG_TARGET, "AddContact gRPC Request received: {:?}", request);
+        let contact = request.into_inner();
+        let public_key = parse_public_key(&contact.public_key)?;
+        self.contacts_service
+            .clone()
+            .save_contact(Contact {
+                alias: contact.alias,
+                public_key,
             })
-            .map_err(|e| error!("Error accepting request: {:?}", e));
-        info!(target: LOG_TARGET, "Starting Wallet gRPC Server at {}", addr);
-        tokio::run(serve);
-        Ok(())
+            .await
+            .map_err(|e| Status::internal(e.to_string()))?;
+        Ok(Response::new(Empty {}))
+    }
+
+    async fn remove_contact(&self, request: Request<PublicKeyRpc>) -> Result<Response<ContactRpc>, Status> {
+        trace!(target: LOG_TARGET, "RemoveContact gRPC Request received: {:?}", request);
+        let public_key = parse_public_key(&request.into_inner().public_key)?;
+        let contact = self
+            .contacts_service
+            .clone()
+            .remove_contact(public_key)
+            .await
+            .map_err(|e| Status::internal(e.to_string()))?;
+        Ok(Response::new(contact.into()))
+    }
+
+    async fn get_seed_words(&self, request: Request<Empty>) -> Result<Response<SeedWordsRpc>, Status> {
+        trace!(target: LOG_TARGET, "GetSeedWords gRPC Request received: {:?}", request);
+        let words = self
+            .output_manager_service
+            .clone()
+            .get_seed_words()
+            .await
+            .map_err(|e| Status::internal(e.to_string()))?;
+        Ok(Response::new(SeedWordsRpc { words }))
+    }
+
+    async fn get_identity(&self, request: Request<Empty>) -> Result<Response<IdentityRpc>, Status> {
+        trace!(target: LOG_TARGET, "GetIdentity gRPC Request received: {:?}", request);
+        Ok(Response::new(IdentityRpc {
+            public_key: self.node_identity.public_key().to_hex(),
+            node_id: self.node_identity.node_id().to_hex(),
+            address: self.node_identity.control_service_address().to_string(),
+        }))
     }
 }
@@ -20,615 +20,275 @@
 // WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
 // USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
-use crossbeam_channel::bounded;
-use futures::future::Future;
-use hyper::client::connect::{Destination, HttpConnector};
-use log::*;
-use rand::{distributions::Alphanumeric, rngs::OsRng, Rng};
-use std::{iter, path::PathBuf, sync::Arc, thread, time::Duration};
+use std::{
+    net::{SocketAddr, TcpListener},
+    sync::Arc,
+    time::Duration,
+};
 use tari_comms::{
-    connection::{net_address::NetAddressWithStats, NetAddress, NetAddressesWithStats},
     control_service::ControlServiceConfig,
-    peer_manager::{peer::PeerFlags, NodeId, NodeIdentity, Peer},
-    types::{CommsPublicKey, CommsSecretKey},
+    multiaddr::Multiaddr,
+    peer_manager::{peer::PeerFlags, NodeId, NodeIdentity, Peer, PeerFeatures},
+    types::CommsPublicKey,
+};
+use tari_core::transactions::{
+    tari_amount::MicroTari,
+    transaction::UnblindedOutput,
+    types::{CryptoFactories, PrivateKey},
 };
-use tari_crypto::keys::{PublicKey, SecretKey};
+use tari_crypto::keys::SecretKey;
 use tari_grpc_wallet::{
-    grpc_interface::wallet_rpc::{
-        client::WalletRpc,
+    wallet_rpc::{
+        wallet_rpc_client::WalletRpcClient,
         Contact as ContactRpc,
-        RpcResponse,
-        ScreenName as ScreenNameRpc,
-        TextMessageToSend as TextMessageToSendRpc,
-        VoidParams,
+        Empty,
+        GetTransactionInfoRequest,
+        PublicKey as PublicKeyRpc,
+        TransactionDirection,
+        TransactionStatus,
+        TransferRequest,
     },
     wallet_server::WalletServer,
 };
 use tari_p2p::initialization::CommsConfig;
 use tari_utilities::hex::Hex;
-use tari_wallet::{text_message_service_sync::Contact, wallet::WalletConfig, Wallet};
+use tari_wallet::{
+    contacts_service::storage::memory_db::ContactsServiceMemoryDatabase,
+    output_manager_service::storage::memory_db::OutputManagerMemoryDatabase,
+    storage::memory_db::WalletMemoryDatabase,
+    transaction_service::storage::memory_db::TransactionMemoryDatabase,
+    wallet::WalletConfig,
+    Wallet,
+};
 use tempdir::TempDir;
-use tower_grpc::Request;
-use tower_hyper::{client, util};
-use tower_util::MakeService;
-
-const LOG_TARGET: &str = "applications::grpc_wallet";
-const WALLET_GRPC_PORT: u32 = 26778;
-
-pub fn init() {
-    let _ = env_logger::builder().is_test(true).try_init();
-}
-
-fn get_path(name: Option<&str>) -> String {
-    let mut path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
-    path.push("tests/data");
-    path.push(name.unwrap_or(""));
-    path.to_str().unwrap().to_string()
-}
-
-fn clean_up_sql_database(name: &str) {
-    if std::fs::metadata(get_path(Some(name))).is_ok() {
-        std::fs::remove_file(get_path(Some(name))).unwrap();
-    }
-}
-
-fn init_sql_database(name: &str) {
-    clean_up_sql_database(name);
-    let path = get_path(None);
-    let _ = std::fs::create_dir(&path).unwrap_or_default();
-}
-
-fn send_text_message_request(msg: TextMessageToSendRpc, desired_response: RpcResponse) {
-    let (tx, rx) = bounded(1);
-
-    let uri: http::Uri = format!("http://127.0.0.1:{}", WALLET_GRPC_PORT).parse().unwrap();
-
-    let dst = Destination::try_from_uri(uri.clone()).unwrap();
-    let connector = util::Connector::new(HttpConnector::new(1));
-    let settings = client::Builder::new().http2_only(true).clone();
-    let mut make_client = client::Connect::with_builder(connector, settings);
-
-    let send_text_message = make_client
-        .make_service(dst.clone())
-        .map_err(|e| panic!("connect error: {:?}", e))
-        .and_then(move |conn| {
-            let conn = tower_request_modifier::Builder::new()
-                .set_origin(uri.clone())
-                .build(conn)
-                .unwrap();
-
-            // Wait until the client is ready...
-            WalletRpc::new(conn).ready()
-        })
-        .and_then(|mut client| client.send_text_message(Request::new(msg)))
-        .and_then(move |response| {
-            info!(target: LOG_TARGET, "SendTextMessage Response received: {:?}", response);
-            let inbound = response.into_inner();
+use tokio::{runtime::Runtime, time::delay_for};
+use tonic::{transport::Channel, Request};
 
-            let _ = tx.send(inbound);
+type MemoryWallet =
+    Wallet<WalletMemoryDatabase, TransactionMemoryDatabase, OutputManagerMemoryDatabase, ContactsServiceMemoryDatabase>;
 
-            Ok(())
-        })
-        .map_err(|e| {
-            panic!("RPC Client error = {:?}", e);
-        });
-
-    tokio::run(send_text_message);
-    thread::sleep(Duration::from_millis(100));
-
-    let inbound = rx.recv().unwrap();
-
-    println!("Inbound: {:?}", inbound);
-    println!("Response: {:?}", desired_response);
-    assert_eq!(inbound.success, desired_response.success);
-    assert_eq!(inbound.message, desired_response.message);
+/// An address on the loopback interface with a port that the OS has just assigned, and that is therefore free
+fn ephemeral_address() -> SocketAddr {
+    TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap()
 }
 
-fn get_text_messages_request(sent_messages: Vec<String>, received_messages: Vec<String>, contact: Option<ContactRpc>) {
-    let mut recv_msg: Vec<String> = Vec::new();
-    let mut send_msg: Vec<String> = Vec::new();
-
-    // Check for new text messages up to 40 times with 100ms wait in between = 4000ms Timeout before moving on
-    for _ in 0..40 {
-        let move_contact = contact.clone();
-        let (tx, rx) = bounded(2);
-        let uri: http::Uri = format!("http://127.0.0.1:{}", WALLET_GRPC_PORT).parse().unwrap();
-        let dst = Destination::try_from_uri(uri.clone()).unwrap();
-        let connector = util::Connector::new(HttpConnector::new(1));
-        let settings = client::Builder::new().http2_only(true).clone();
-
-        let mut make_client = client::Connect::with_builder(connector, settings.clone());
-        let get_text_messages = make_client
-            .make_service(dst.clone())
-            .map_err(|e| panic!("connect error: {:?}", e))
-            .and_then(move |conn| {
-                let conn = tower_request_modifier::Builder::new()
-                    .set_origin(uri.clone())
-                    .build(conn)
-                    .unwrap();
-
-                // Wait until the client is ready...
-                WalletRpc::new(conn).ready()
-            })
-            .and_then(|mut client| {
-                if move_contact.is_some() {
-                    client.get_text_messages_by_contact(Request::new(move_contact.unwrap()))
-                } else {
-                    client.get_text_messages(Request::new(VoidParams {}))
-                }
-            })
-            .and_then(move |response| {
-                info!(target: LOG_TARGET, "GetTextMessages Response received: {:?}", response);
-                let inbound = response.into_inner();
-
-                let recv_msg = inbound.received_messages.iter().map(|m| m.message.clone()).collect();
-
-                let sent_msg = inbound.sent_messages.iter().map(|m| m.message.clone()).collect();
-
-                tx.send(recv_msg).unwrap();
-                tx.send(sent_msg).unwrap();
-
-                Ok(())
-            })
-            .map_err(|e| {
-                panic!("RPC Client error = {:?}", e);
-            });
-
-        tokio::run(get_text_messages);
-
-        recv_msg = rx.recv().unwrap();
-        send_msg = rx.recv().unwrap();
-
-        if recv_msg.len() > 0 && send_msg.len() > 0 {
-            break;
-        }
-        thread::sleep(Duration::from_millis(100));
-    }
-
-    assert_eq!(recv_msg.len(), received_messages.len());
-    assert_eq!(send_msg.len(), sent_messages.len());
-
-    recv_msg
-        .iter()
-        .for_each(|m| assert!(received_messages.iter().any(|m2| m == m2)));
-    send_msg
-        .iter()
-        .for_each(|m| assert!(sent_messages.iter().any(|m2| m == m2)));
-}
-
-fn set_get_screen_name(name: String) {
-    let requested_name = name.clone();
-    let uri: http::Uri = format!("http://127.0.0.1:{}", WALLET_GRPC_PORT).parse().unwrap();
-
-    let dst = Destination::try_from_uri(uri.clone()).unwrap();
-    let connector = util::Connector::new(HttpConnector::new(1));
-    let settings = client::Builder::new().http2_only(true).clone();
-    let mut make_client = client::Connect::with_builder(connector, settings.clone());
-
-    let set_screen_name = make_client
-        .make_service(dst.clone())
-        .map_err(|e| panic!("connect error: {:?}", e))
-        .and_then(move |conn| {
-            let conn = tower_request_modifier::Builder::new()
-                .set_origin(uri.clone())
-                .build(conn)
-                .unwrap();
-
-            // Wait until the client is ready...
-            WalletRpc::new(conn).ready()
-        })
-        .and_then(|mut client| client.set_screen_name(Request::new(ScreenNameRpc { screen_name: name })))
-        .and_then(move |response| {
-            info!(target: LOG_TARGET, "SetScreenName Response received: {:?}", response);
-            let inbound = response.into_inner();
-            assert_eq!(inbound.success, true);
-            Ok(())
-        })
-        .map_err(|e| {
-            panic!("RPC Client error = {:?}", e);
-        });
-
-    tokio::run(set_screen_name);
-    thread::sleep(Duration::from_millis(100));
-    let (tx, rx) = bounded(1);
-    let uri: http::Uri = format!("http://127.0.0.1:{}", WALLET_GRPC_PORT).parse().unwrap();
-    let connector = util::Connector::new(HttpConnector::new(1));
-    let settings = client::Builder::new().http2_only(true).clone();
-    let mut make_client = client::Connect::with_builder(connector, settings);
-
-    let get_screen_name = make_client
-        .make_service(dst.clone())
-        .map_err(|e| panic!("connect error: {:?}", e))
-        .and_then(move |conn| {
-            let conn = tower_request_modifier::Builder::new()
-                .set_origin(uri.clone())
-                .build(conn)
-                .unwrap();
-
-            // Wait until the client is ready...
-            WalletRpc::new(conn).ready()
-        })
-        .and_then(|mut client| client.get_screen_name(Request::new(VoidParams {})))
-        .and_then(move |response| {
-            info!(target: LOG_TARGET, "GetScreenName Response received: {:?}", response);
-
-            let _ = tx.send(response.into_inner());
-
-            Ok(())
-        })
-        .map_err(|e| {
-            panic!("RPC Client error = {:?}", e);
-        });
-
-    tokio::run(get_screen_name);
-
-    let recv_screen_name = rx.recv().unwrap();
-    assert_eq!(recv_screen_name.screen_name, requested_name);
-}
-
-fn get_pub_key(pub_key: String) {
-    let (tx, rx) = bounded(1);
-    let uri: http::Uri = format!("http://127.0.0.1:{}", WALLET_GRPC_PORT).parse().unwrap();
-    let dst = Destination::try_from_uri(uri.clone()).unwrap();
-    let connector = util::Connector::new(HttpConnector::new(1));
-    let settings = client::Builder::new().http2_only(true).clone();
-    let mut make_client = client::Connect::with_builder(connector, settings);
-
-    let get_pub_key = make_client
-        .make_service(dst.clone())
-        .map_err(|e| panic!("connect error: {:?}", e))
-        .and_then(move |conn| {
-            let conn = tower_request_modifier::Builder::new()
-                .set_origin(uri.clone())
-                .build(conn)
-                .unwrap();
-
-            // Wait until the client is ready...
-            WalletRpc::new(conn).ready()
-        })
-        .and_then(|mut client| client.get_public_key(Request::new(VoidParams {})))
-        .and_then(move |response| {
-            info!(target: LOG_TARGET, "GetPubKey Response received: {:?}", response);
-
-            let _ = tx.send(response.into_inner());
-
-            Ok(())
-        })
-        .map_err(|e| {
-            panic!("RPC Client error = {:?}", e);
-        });
-
-    tokio::run(get_pub_key);
-
-    let recv_pub_key = rx.recv().unwrap();
-    assert_eq!(recv_pub_key.pub_key, pub_key);
-}
-
-fn add_contact(contact: ContactRpc) {
-    let uri: http::Uri = format!("http://127.0.0.1:{}", WALLET_GRPC_PORT).parse().unwrap();
-    let dst = Destination::try_from_uri(uri.clone()).unwrap();
-    let connector = util::Connector::new(HttpConnector::new(1));
-    let settings = client::Builder::new().http2_only(true).clone();
-    let mut make_client = client::Connect::with_builder(connector, settings.clone());
-
-    let add_contact = make_client
-        .make_service(dst.clone())
-        .map_err(|e| panic!("connect error: {:?}", e))
-        .and_then(move |conn| {
-            let conn = tower_request_modifier::Builder::new()
-                .set_origin(uri.clone())
-                .build(conn)
-                .unwrap();
-
-            // Wait until the client is ready...
-            WalletRpc::new(conn).ready()
-        })
-        .and_then(|mut client| client.add_contact(Request::new(contact)))
-        .and_then(move |response| {
-            info!(target: LOG_TARGET, "AddContact Response received: {:?}", response);
-            let inbound = response.into_inner();
-            assert_eq!(inbound.success, true);
-            Ok(())
-        })
-        .map_err(|e| {
-            panic!("RPC Client error = {:?}", e);
-        });
-
-    tokio::run(add_contact);
-}
-
-fn contacts_crud() {
-    let mut rng = rand::OsRng::new().unwrap();
-
-    let mut contacts: Vec<ContactRpc> = Vec::new();
-    let screen_names = vec!["Andy".to_string(), "Bob".to_string(), "Carol".to_string()];
-    for i in 0..3 {
-        let contact_secret_key = CommsSecretKey::random(&mut rng);
-        let contact_public_key = CommsPublicKey::from_secret_key(&contact_secret_key);
-        contacts.push(ContactRpc {
-            screen_name: screen_names[i].clone(),
-            pub_key: contact_public_key.to_hex(),
-            address: "127.0.0.1:37522".to_string(),
-        });
-    }
-
-    add_contact(contacts[0].clone());
-    thread::sleep(Duration::from_millis(50));
-
-    add_contact(contacts[1].clone());
-    thread::sleep(Duration::from_millis(50));
-
-    add_contact(contacts[2].clone());
-    thread::sleep(Duration::from_millis(50));
-
-    // Remove a contact
-    let move_contact = contacts[1].clone();
-    let uri: http::Uri = format!("http://127.0.0.1:{}", WALLET_GRPC_PORT).parse().unwrap();
-    let dst = Destination::try_from_uri(uri.clone()).unwrap();
-    let connector = util::Connector::new(HttpConnector::new(1));
-    let settings = client::Builder::new().http2_only(true).clone();
-    let mut make_client = client::Connect::with_builder(connector, settings);
-
-    let remove_contact = make_client
-        .make_service(dst.clone())
-        .map_err(|e| panic!("connect error: {:?}", e))
-        .and_then(move |conn| {
-            let conn = tower_request_modifier::Builder::new()
-                .set_origin(uri.clone())
-                .build(conn)
-                .unwrap();
-
-            // Wait until the client is ready...
-            WalletRpc::new(conn).ready()
-        })
-        .and_then(|mut client| client.remove_contact(Request::new(move_contact)))
-        .and_then(move |response| {
-            info!(target: LOG_TARGET, "RemoveContact Response received: {:?}", response);
-
-            let inbound = response.into_inner();
-            assert_eq!(inbound.success, true);
-
-            Ok(())
-        })
-        .map_err(|e| {
-            panic!("RPC Client error = {:?}", e);
-        });
-
-    tokio::run(remove_contact);
-    thread::sleep(Duration::from_millis(100));
-
-    // Update a contact
-    let updated_contact = ContactRpc {
-        screen_name: "Updated".to_string(),
-        pub_key: contacts[0].pub_key.clone(),
-        address: contacts[0].address.clone(),
-    };
-    let uri: http::Uri = format!("http://127.0.0.1:{}", WALLET_GRPC_PORT).parse().unwrap();
-    let dst = Destination::try_from_uri(uri.clone()).unwrap();
-    let connector = util::Connector::new(HttpConnector::new(1));
-    let settings = client::Builder::new().http2_only(true).clone();
-    let mut make_client = client::Connect::with_builder(connector, settings);
-
-    let update_contact = make_client
-        .make_service(dst.clone())
-        .map_err(|e| panic!("connect error: {:?}", e))
-        .and_then(move |conn| {
-            let conn = tower_request_modifier::Builder::new()
-                .set_origin(uri.clone())
-                .build(conn)
-                .unwrap();
-
-            // Wait until the client is ready...
-            WalletRpc::new(conn).ready()
-        })
-        .and_then(|mut client| client.update_contact(Request::new(updated_contact)))
-        .and_then(move |response| {
-            info!(target: LOG_TARGET, "UpdateContact Response received: {:?}", response);
-
-            let inbound = response.into_inner();
-            assert_eq!(inbound.success, true);
-
-            Ok(())
-        })
-        .map_err(|e| {
-            panic!("RPC Client error = {:?}", e);
-        });
-
-    tokio::run(update_contact);
-    thread::sleep(Duration::from_millis(100));
-
-    // check contacts
-    let (tx, rx) = bounded(1);
-    let uri: http::Uri = format!("http://127.0.0.1:{}", WALLET_GRPC_PORT).parse().unwrap();
-    let dst = Destination::try_from_uri(uri.clone()).unwrap();
-    let connector = util::Connector::new(HttpConnector::new(1));
-    let settings = client::Builder::new().http2_only(true).clone();
-    let mut make_client = client::Connect::with_builder(connector, settings);
-
-    let get_contacts = make_client
-        .make_service(dst.clone())
-        .map_err(|e| panic!("connect error: {:?}", e))
-        .and_then(move |conn| {
-            let conn = tower_request_modifier::Builder::new()
-                .set_origin(uri.clone())
-                .build(conn)
-                .unwrap();
-
-            // Wait until the client is ready...
-            WalletRpc::new(conn).ready()
-        })
-        .and_then(|mut client| client.get_contacts(Request::new(VoidParams {})))
-        .and_then(move |response| {
-            info!(target: LOG_TARGET, "RemoveContact Response received: {:?}", response);
-
-            let _ = tx.send(response.into_inner());
-
-            Ok(())
-        })
-        .map_err(|e| {
-            panic!("RPC Client error = {:?}", e);
-        });
-
-    tokio::run(get_contacts);
-
-    let recv_contacts = rx.recv().unwrap();
-    assert_eq!(recv_contacts.contacts.len(), 3);
-    assert_eq!(recv_contacts.contacts[1], ContactRpc {
-        screen_name: "Updated".to_string(),
-        pub_key: contacts[0].pub_key.clone(),
-        address: contacts[0].address.clone(),
-    });
-    assert_eq!(recv_contacts.contacts[2], contacts[2]);
+fn ephemeral_multiaddr() -> Multiaddr {
+    format!("/ip4/127.0.0.1/tcp/{}", ephemeral_address().port())
+        .parse()
+        .unwrap()
 }
 
-fn create_peer(public_key: CommsPublicKey, net_address: NetAddress) -> Peer {
+fn create_peer(public_key: CommsPublicKey, net_address: Multiaddr) -> Peer {
     Peer::new(
         public_key.clone(),
         NodeId::from_key(&public_key).unwrap(),
-        NetAddressesWithStats::new(vec![NetAddressWithStats::new(net_address.clone())]),
+        net_address.into(),
         PeerFlags::empty(),
+        PeerFeatures::COMMUNICATION_NODE,
     )
 }
 
-pub fn random_string(len: usize) -> String {
-    let mut rng = OsRng::new().unwrap();
-    iter::repeat(()).map(|_| rng.sample(Alphanumeric)).take(len).collect()
-}
-
-#[test]
-fn test_rpc_text_message_service() {
-    let mut rng = rand::OsRng::new().unwrap();
-    let listener_address1: NetAddress = "127.0.0.1:32775".parse().unwrap();
-    let secret_key1 = CommsSecretKey::random(&mut rng);
-    let public_key1 = CommsPublicKey::from_secret_key(&secret_key1);
-
-    let listener_address2: NetAddress = "127.0.0.1:32776".parse().unwrap();
-    let secret_key2 = CommsSecretKey::random(&mut rng);
-    let public_key2 = CommsPublicKey::from_secret_key(&secret_key2);
-
-    let db_name1 = "test_rpc_text_message_service1.sqlite3";
-    let db_path1 = get_path(Some(db_name1));
-    init_sql_database(db_name1);
-
-    let db_name2 = "test_rpc_text_message_service2.sqlite3";
-    let db_path2 = get_path(Some(db_name2));
-    init_sql_database(db_name2);
-
-    let node_identity1 = NodeIdentity::new(secret_key1, listener_address1.clone())
-        .map(Arc::new)
-        .unwrap();
-    let temp_dir1 = TempDir::new(random_string(8).as_str()).unwrap();
-    let config1 = WalletConfig {
-        comms: CommsConfig {
-            control_service: ControlServiceConfig {
-                listener_address: listener_address1.clone(),
-                socks_proxy_address: None,
-                requested_connection_timeout: Duration::from_millis(5000),
-            },
+fn create_wallet(node_identity: NodeIdentity, datastore_path: &str, database_name: &str) -> MemoryWallet {
+    let comms_config = CommsConfig {
+        node_identity: Arc::new(node_identity.clone()),
+        peer_connection_listening_address: "/ip4/127.0.0.1/tcp/0".parse().unwrap(),
+        socks_proxy_address: None,
+        control_service: ControlServiceConfig {
+            listening_address: node_identity.control_service_address(),
             socks_proxy_address: None,
-            host: "127.0.0.1".parse().unwrap(),
-            node_identity: node_identity1,
-            datastore_path: temp_dir1.path().to_str().unwrap().to_string(),
-            peer_database_name: random_string(8),
-            // TODO: configureable
-            inbound_buffer_size: 30,
-            outbound_buffer_size: 30,
-            dht: Default::default(),
+            public_peer_address: None,
+            requested_connection_timeout: Duration::from_millis(2000),
         },
-        inbound_message_buffer_size: 10,
-        public_key: public_key1.clone(),
-        database_path: db_path1,
+        datastore_path: datastore_path.to_string(),
+        establish_connection_timeout: Duration::from_secs(10),
+        peer_database_name: database_name.to_string(),
+        inbound_buffer_size: 100,
+        outbound_buffer_size: 100,
+        dht: Default::default(),
     };
-
-    let node_identity2 = NodeIdentity::new(secret_key2, listener_address2.clone())
-        .map(Arc::new)
-        .unwrap();
-    let temp_dir2 = TempDir::new(random_string(8).as_str()).unwrap();
-    let config2 = WalletConfig {
-        comms: CommsConfig {
-            control_service: ControlServiceConfig {
-                listener_address: listener_address2.clone(),
-                socks_proxy_address: None,
-                requested_connection_timeout: Duration::from_millis(5000),
-            },
-            socks_proxy_address: None,
-            host: "127.0.0.1".parse().unwrap(),
-            node_identity: node_identity2,
-            datastore_path: temp_dir2.path().to_str().unwrap().to_string(),
-            peer_database_name: random_string(8),
-            // TODO: configureable
-            inbound_buffer_size: 30,
-            outbound_buffer_size: 30,
-            dht: Default::default(),
-        },
-        inbound_message_buffer_size: 10,
-        public_key: public_key2.clone(),
-        database_path: db_path2,
-    };
-
-    let wallet1 = Wallet::new(config1).unwrap();
-
-    thread::spawn(move || {
-        let wallet_server = WalletServer::new(WALLET_GRPC_PORT, Arc::new(wallet1));
-        let _ = wallet_server.start().unwrap();
-    });
-
-    let screen_name = "Bob".to_string();
-    let bob_contact = ContactRpc {
-        screen_name: screen_name.clone(),
-        pub_key: public_key2.to_hex(),
-        address: format!("{}", listener_address2.clone()),
+    let config = WalletConfig {
+        comms_config,
+        logging_path: None,
+        passphrase: None,
+        factories: CryptoFactories::default(),
     };
+    Wallet::new(
+        config,
+        Runtime::new().unwrap(),
+        WalletMemoryDatabase::new(),
+        TransactionMemoryDatabase::new(),
+        OutputManagerMemoryDatabase::new(),
+        ContactsServiceMemoryDatabase::new(),
+    )
+    .unwrap()
+}
 
-    add_contact(bob_contact.clone());
+/// Connect to the gRPC server, retrying while it is starting up
+async fn connect_client(address: SocketAddr) -> WalletRpcClient<Channel> {
+    let mut attempts = 0;
+    loop {
+        match WalletRpcClient::connect(format!("http://{}", address)).await {
+            Ok(client) => return client,
+            Err(e) => {
+                attempts += 1;
+                assert!(attempts < 50, "Could not connect to the wallet gRPC server: {}", e);
+                delay_for(Duration::from_millis(100)).await;
+            },
+        }
+    }
+}
 
-    thread::sleep(Duration::from_millis(100));
+#[test]
+fn test_wallet_grpc_server() {
+    let temp_dir = TempDir::new("grpc_wallet").unwrap();
+    let datastore_path = temp_dir.path().to_str().unwrap();
+    let mut rng = rand::OsRng::new().unwrap();
 
-    let wallet2 = Wallet::new(config2).unwrap();
+    let alice_identity =
+        NodeIdentity::random(&mut rng, ephemeral_multiaddr(), PeerFeatures::COMMUNICATION_NODE).unwrap();
+    let bob_identity = NodeIdentity::random(&mut rng, ephemeral_multiaddr(), PeerFeatures::COMMUNICATION_NODE).unwrap();
+    let mut alice_wallet = create_wallet(alice_identity.clone(), datastore_path, "alice");
+    let mut bob_wallet = create_wallet(bob_identity.clone(), datastore_path, "bob");
 
-    wallet2
-        .comms_services
+    alice_wallet
+        .comms
         .peer_manager()
-        .add_peer(create_peer(public_key1.clone(), listener_address1.clone()))
+        .add_peer(create_peer(
+            bob_identity.public_key().clone(),
+            bob_identity.control_service_address(),
+        ))
+        .unwrap();
+    bob_wallet
+        .comms
+        .peer_manager()
+        .add_peer(create_peer(
+            alice_identity.public_key().clone(),
+            alice_identity.control_service_address(),
+        ))
         .unwrap();
-    let alice_contact = Contact {
-        screen_name: "Alice".to_string(),
-        pub_key: public_key1.clone(),
-        address: listener_address1.clone(),
-    };
-    wallet2.text_message_service.add_contact(alice_contact).unwrap();
-
-    let test_msg = TextMessageToSendRpc {
-        dest_pub_key: public_key2.clone().to_hex(),
-        message: "Hey!".to_string(),
-    };
-
-    let test_msg2 = TextMessageToSendRpc {
-        dest_pub_key: public_key2.clone().to_hex(),
-        message: "Hoh!".to_string(),
-    };
-
-    let resp = RpcResponse {
-        success: true,
-        message: "Text Message Sent".to_string(),
-    };
-
-    send_text_message_request(test_msg, resp.clone());
-    send_text_message_request(test_msg2, resp);
 
-    wallet2
-        .text_message_service
-        .send_text_message(public_key1.clone(), "Here we go!".to_string())
+    let output = UnblindedOutput::new(MicroTari::from(2500), PrivateKey::random(&mut rng), None);
+    alice_wallet
+        .runtime
+        .block_on(alice_wallet.output_manager_service.add_output(output))
         .unwrap();
 
-    let sent_messages = vec!["Hey!".to_string(), "Hoh!".to_string()];
-    let received_messages = vec!["Here we go!".to_string()];
+    let server = WalletServer::new(&alice_wallet);
+    let grpc_address = ephemeral_address();
+    alice_wallet.runtime.spawn(server.serve(grpc_address));
+
+    let mut runtime = Runtime::new().unwrap();
+    runtime.block_on(async {
+        let mut client = connect_client(grpc_address).await;
+
+        let identity = client.get_identity(Request::new(Empty {})).await.unwrap().into_inner();
+        assert_eq!(identity.public_key, alice_identity.public_key().to_hex());
+        assert_eq!(identity.node_id, alice_identity.node_id().to_hex());
+
+        let balance = client.get_balance(Request::new(Empty {})).await.unwrap().into_inner();
+        assert_eq!(balance.available_balance, 2500);
+        assert_eq!(balance.pending_outgoing_balance, 0);
+
+        let seed_words = client
+            .get_seed_words(Request::new(Empty {}))
+            .await
+            .unwrap()
+            .into_inner();
+        assert_eq!(seed_words.words.len(), 24);
+
+        let bob_contact = ContactRpc {
+            alias: "Bob".to_string(),
+            public_key: bob_identity.public_key().to_hex(),
+        };
+        client.add_contact(Request::new(bob_contact.clone())).await.unwrap();
+        let contacts = client.get_contacts(Request::new(Empty {})).await.unwrap().into_inner();
+        assert_eq!(contacts.contacts, vec![bob_contact.clone()]);
+
+        let invalid_contact = ContactRpc {
+            alias: "Nobody".to_string(),
+            public_key: "not a public key".to_string(),
+        };
+        assert!(client.add_contact(Request::new(invalid_contact)).await.is_err());
+
+        let mut events = client
+            .transaction_events(Request::new(Empty {}))
+            .await
+            .unwrap()
+            .into_inner();
+
+        let tx_id = client
+            .transfer(Request::new(TransferRequest {
+                destination: bob_identity.public_key().to_hex(),
+                amount: 1000,
+                fee_per_gram: 20,
+                message: "gRPC transfer".to_string(),
+            }))
+            .await
+            .unwrap()
+            .into_inner()
+            .tx_id;
+
+        let reply_event = tokio::time::timeout(Duration::from_secs(20), async {
+            while let Some(event) = events.message().await.unwrap() {
+                if event.event == "ReceivedTransactionReply" {
+                    return event;
+                }
+            }
+            panic!("The event stream ended before the reply was received");
+        })
+        .await
+        .unwrap();
+        assert_eq!(reply_event.tx_id, tx_id);
+
+        let info = client
+            .get_transaction_info(Request::new(GetTransactionInfoRequest { tx_ids: vec![tx_id] }))
+            .await
+            .unwrap()
+            .into_inner();
+        assert_eq!(info.transactions.len(), 1);
+        let transaction = &info.transactions[0];
+        assert_eq!(transaction.tx_id, tx_id);
+        assert_eq!(transaction.amount, 1000);
+        assert_eq!(transaction.message, "gRPC transfer");
+        assert_eq!(transaction.destination_public_key, bob_identity.public_key().to_hex());
+        assert_eq!(transaction.direction, TransactionDirection::Outbound as i32);
+        assert_eq!(transaction.status, TransactionStatus::Completed as i32);
+
+        assert!(client
+            .get_transaction_info(Request::new(GetTransactionInfoRequest {
+                tx_ids: vec![tx_id + 1]
+            }))
+            .await
+            .is_err());
+
+        let transactions = client
+            .list_transactions(Request::new(Empty {}))
+            .await
+            .unwrap()
+            .into_inner();
+        assert_eq!(transactions.transactions, info.transactions);
+
+        let removed = client
+            .remove_contact(Request::new(PublicKeyRpc {
+                public_key: bob_identity.public_key().to_hex(),
+            }))
+            .await
+            .unwrap()
+            .into_inner();
+        assert_eq!(removed, bob_contact);
+        let contacts = client.get_contacts(Request::new(Empty {})).await.unwrap().into_inner();
+        assert!(contacts.contacts.is_empty());
+    });
 
-    get_text_messages_request(sent_messages.clone(), received_messages.clone(), None);
-    get_text_messages_request(sent_messages, received_messages, Some(bob_contact));
-    set_get_screen_name("Alice".to_string());
-    get_pub_key(public_key1.to_hex());
-    contacts_crud();
-    clean_up_sql_database(db_name1);
-    clean_up_sql_database(db_name2);
+    // Bob holds the transaction as a pending inbound transaction until Alice's finalized transaction arrives
+    let mut bob_ts = bob_wallet.transaction_service.clone();
+    let bob_pending = bob_wallet
+        .runtime
+        .block_on(bob_ts.get_pending_inbound_transactions())
+        .unwrap();
+    let bob_completed = bob_wallet
+        .runtime
+        .block_on(bob_ts.get_completed_transactions())
+        .unwrap();
+    assert_eq!(bob_pending.len() + bob_completed.len(), 1);
 }
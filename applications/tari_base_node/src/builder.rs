@@ -48,7 +48,7 @@ use tari_core::{
         MemoryDatabase,
         Validators,
     },
-    consensus::ConsensusManager,
+    consensus::{ConsensusConstants, ConsensusManager},
     mempool::{Mempool, MempoolConfig, MempoolValidators},
     proof_of_work::DiffAdjManager,
     transactions::{
@@ -190,7 +190,9 @@ pub fn configure_and_initialize_node(
                 TxInputAndMaturityValidator::new(db.clone()),
             );
             let mempool = Mempool::new(db.clone(), MempoolConfig::default(), mempool_validator);
-            let diff_adj_manager = DiffAdjManager::new(db.clone()).map_err(|e| e.to_string())?;
+            let diff_adj_manager =
+                DiffAdjManager::with_consensus_constants(db.clone(), ConsensusConstants::for_network(config.network))
+                    .map_err(|e| e.to_string())?;
             rules.set_diff_manager(diff_adj_manager).map_err(|e| e.to_string())?;
             let (comms, handles) =
                 setup_comms_services(rt, id.clone(), peers, &config.peer_db_path, db.clone(), mempool, rules);
@@ -225,7 +227,9 @@ pub fn configure_and_initialize_node(
                 TxInputAndMaturityValidator::new(db.clone()),
             );
             let mempool = Mempool::new(db.clone(), MempoolConfig::default(), mempool_validator);
-            let diff_adj_manager = DiffAdjManager::new(db.clone()).map_err(|e| e.to_string())?;
+            let diff_adj_manager =
+                DiffAdjManager::with_consensus_constants(db.clone(), ConsensusConstants::for_network(config.network))
+                    .map_err(|e| e.to_string())?;
             rules.set_diff_manager(diff_adj_manager).map_err(|e| e.to_string())?;
             let (comms, handles) =
                 setup_comms_services(rt, id.clone(), peers, &config.peer_db_path, db.clone(), mempool, rules);
@@ -267,6 +267,7 @@ mod test {
             height_of_longest_chain: Some(1),
             best_block: Some(vec![]),
             pruning_horizon: 64,
+            accumulated_difficulty: Some(100),
         }
     }
 
@@ -51,6 +51,7 @@ pub struct MmrStateRequest {
 pub enum NodeCommsRequest {
     GetChainMetadata,
     FetchKernels(Vec<HashOutput>),
+    FetchKernelHeights(Vec<HashOutput>),
     FetchHeaders(Vec<u64>),
     FetchUtxos(Vec<HashOutput>),
     FetchBlocks(Vec<u64>),
@@ -58,4 +59,5 @@ pub enum NodeCommsRequest {
     GetNewBlockTemplate,
     GetNewBlock(NewBlockTemplate),
     GetTargetDifficulty(PowAlgorithm),
+    GetFeePerGramEstimate(u64),
 }
@@ -24,7 +24,11 @@ use crate::{
     blocks::{blockheader::BlockHeader, Block, NewBlockTemplate},
     chain_storage::{ChainMetadata, HistoricalBlock, MutableMmrState},
     proof_of_work::Difficulty,
-    transactions::transaction::{TransactionKernel, TransactionOutput},
+    transactions::{
+        tari_amount::MicroTari,
+        transaction::{TransactionKernel, TransactionOutput},
+        types::HashOutput,
+    },
 };
 use serde::{Deserialize, Serialize};
 
@@ -33,6 +37,7 @@ use serde::{Deserialize, Serialize};
 pub enum NodeCommsResponse {
     ChainMetadata(ChainMetadata),
     TransactionKernels(Vec<TransactionKernel>),
+    KernelHeights(Vec<(HashOutput, u64)>),
     BlockHeaders(Vec<BlockHeader>),
     TransactionOutputs(Vec<TransactionOutput>),
     HistoricalBlocks(Vec<HistoricalBlock>),
@@ -40,4 +45,5 @@ pub enum NodeCommsResponse {
     NewBlockTemplate(NewBlockTemplate),
     NewBlock(Block),
     TargetDifficulty(Difficulty),
+    FeePerGramEstimate(MicroTari),
 }
@@ -36,7 +36,10 @@ use crate::{
     },
     consensus::{ConsensusConstants, ConsensusManager},
     mempool::Mempool,
-    transactions::transaction::{TransactionKernel, TransactionOutput},
+    transactions::{
+        transaction::{TransactionKernel, TransactionOutput},
+        types::HashOutput,
+    },
 };
 use futures::SinkExt;
 use log::*;
@@ -99,6 +102,15 @@ where T: BlockchainBackend + 'static
                 }
                 Ok(NodeCommsResponse::TransactionKernels(kernels))
             },
+            NodeCommsRequest::FetchKernelHeights(kernel_hashes) => {
+                let mut kernel_heights = Vec::<(HashOutput, u64)>::new();
+                for hash in kernel_hashes {
+                    if let Ok(height) = async_db::fetch_kernel_height(self.blockchain_db.clone(), hash.clone()).await {
+                        kernel_heights.push((hash.clone(), height));
+                    }
+                }
+                Ok(NodeCommsResponse::KernelHeights(kernel_heights))
+            },
             NodeCommsRequest::FetchHeaders(block_nums) => {
                 let mut block_headers = Vec::<BlockHeader>::new();
                 for block_num in block_nums {
@@ -173,6 +185,11 @@ where T: BlockchainBackend + 'static
             NodeCommsRequest::GetTargetDifficulty(pow_algo) => Ok(NodeCommsResponse::TargetDifficulty(
                 self.consensus_manager.get_target_difficulty(pow_algo)?,
             )),
+            NodeCommsRequest::GetFeePerGramEstimate(target_blocks) => Ok(NodeCommsResponse::FeePerGramEstimate(
+                self.mempool
+                    .estimate_fee_per_gram(*target_blocks)
+                    .map_err(|e| CommsInterfaceError::MempoolError(e.to_string()))?,
+            )),
         }
     }
 
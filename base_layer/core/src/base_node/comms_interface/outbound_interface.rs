@@ -31,6 +31,7 @@ use crate::{
     blocks::{blockheader::BlockHeader, Block},
     chain_storage::{ChainMetadata, HistoricalBlock, MmrTree, MutableMmrState},
     transactions::{
+        tari_amount::MicroTari,
         transaction::{TransactionKernel, TransactionOutput},
         types::HashOutput,
     },
@@ -79,6 +80,28 @@ impl OutboundNodeCommsInterface {
         Ok(responses)
     }
 
+    /// Fetch the heights of the blocks that contain the transaction kernels with the provided hashes from remote base
+    /// nodes. Kernels that are not in the blockchain are omitted from the response.
+    pub async fn fetch_kernel_heights(
+        &mut self,
+        hashes: Vec<HashOutput>,
+    ) -> Result<Vec<(HashOutput, u64)>, CommsInterfaceError>
+    {
+        if let Some(NodeCommsResponse::KernelHeights(kernel_heights)) = self
+            .request_sender
+            .call((
+                NodeCommsRequest::FetchKernelHeights(hashes),
+                NodeCommsRequestType::Single,
+            ))
+            .await??
+            .first()
+        {
+            Ok(kernel_heights.clone())
+        } else {
+            Err(CommsInterfaceError::UnexpectedApiResponse)
+        }
+    }
+
     /// Fetch the transaction kernels with the provided hashes from remote base nodes.
     pub async fn fetch_kernels(
         &mut self,
@@ -170,6 +193,24 @@ impl OutboundNodeCommsInterface {
         }
     }
 
+    /// Request an estimate of the fee-per-gram required for a transaction to be included within the specified number
+    /// of blocks from a remote base node.
+    pub async fn get_fee_per_gram_estimate(&mut self, target_blocks: u64) -> Result<MicroTari, CommsInterfaceError> {
+        if let Some(NodeCommsResponse::FeePerGramEstimate(fee_per_gram)) = self
+            .request_sender
+            .call((
+                NodeCommsRequest::GetFeePerGramEstimate(target_blocks),
+                NodeCommsRequestType::Single,
+            ))
+            .await??
+            .first()
+        {
+            Ok(*fee_per_gram)
+        } else {
+            Err(CommsInterfaceError::UnexpectedApiResponse)
+        }
+    }
+
     /// Transmit a block to remote base nodes, excluding the provided peers.
     pub async fn propagate_block(
         &mut self,
@@ -29,6 +29,7 @@ impl From<proto::ChainMetadata> for ChainMetadata {
             height_of_longest_chain: metadata.height_of_longest_chain,
             best_block: metadata.best_block,
             pruning_horizon: metadata.pruning_horizon,
+            accumulated_difficulty: metadata.accumulated_difficulty.map(Into::into),
         }
     }
 }
@@ -39,6 +40,7 @@ impl From<ChainMetadata> for proto::ChainMetadata {
             height_of_longest_chain: metadata.height_of_longest_chain,
             best_block: metadata.best_block,
             pruning_horizon: metadata.pruning_horizon,
+            accumulated_difficulty: metadata.accumulated_difficulty.map(|d| d.as_u64()),
         }
     }
 }
@@ -34,6 +34,7 @@ impl TryInto<ci::NodeCommsRequest> for ProtoNodeCommsRequest {
             // Field was not specified
             GetChainMetadata(_) => ci::NodeCommsRequest::GetChainMetadata,
             FetchKernels(hash_outputs) => ci::NodeCommsRequest::FetchKernels(hash_outputs.outputs),
+            FetchKernelHeights(hash_outputs) => ci::NodeCommsRequest::FetchKernelHeights(hash_outputs.outputs),
             FetchHeaders(block_heights) => ci::NodeCommsRequest::FetchHeaders(block_heights.heights),
             FetchUtxos(hash_outputs) => ci::NodeCommsRequest::FetchUtxos(hash_outputs.outputs),
             FetchBlocks(block_heights) => ci::NodeCommsRequest::FetchBlocks(block_heights.heights),
@@ -43,6 +44,7 @@ impl TryInto<ci::NodeCommsRequest> for ProtoNodeCommsRequest {
             GetTargetDifficulty(pow_algo) => {
                 ci::NodeCommsRequest::GetTargetDifficulty(PowAlgorithm::try_from(pow_algo)?)
             },
+            GetFeePerGramEstimate(target_blocks) => ci::NodeCommsRequest::GetFeePerGramEstimate(target_blocks),
         };
         Ok(request)
     }
@@ -54,6 +56,7 @@ impl From<ci::NodeCommsRequest> for ProtoNodeCommsRequest {
         match request {
             GetChainMetadata => ProtoNodeCommsRequest::GetChainMetadata(true),
             FetchKernels(hash_outputs) => ProtoNodeCommsRequest::FetchKernels(hash_outputs.into()),
+            FetchKernelHeights(hash_outputs) => ProtoNodeCommsRequest::FetchKernelHeights(hash_outputs.into()),
             FetchHeaders(block_heights) => ProtoNodeCommsRequest::FetchHeaders(block_heights.into()),
             FetchUtxos(hash_outputs) => ProtoNodeCommsRequest::FetchUtxos(hash_outputs.into()),
             FetchBlocks(block_heights) => ProtoNodeCommsRequest::FetchBlocks(block_heights.into()),
@@ -61,6 +64,7 @@ impl From<ci::NodeCommsRequest> for ProtoNodeCommsRequest {
             GetNewBlockTemplate => ProtoNodeCommsRequest::GetNewBlockTemplate(true),
             GetNewBlock(block_template) => ProtoNodeCommsRequest::GetNewBlock(block_template.into()),
             GetTargetDifficulty(pow_algo) => ProtoNodeCommsRequest::GetTargetDifficulty(*&pow_algo as u64),
+            GetFeePerGramEstimate(target_blocks) => ProtoNodeCommsRequest::GetFeePerGramEstimate(target_blocks),
         }
     }
 }
@@ -24,6 +24,8 @@ pub use super::base_node::base_node_service_response::Response as ProtoNodeComms
 use super::base_node::{
     BlockHeaders as ProtoBlockHeaders,
     HistoricalBlocks as ProtoHistoricalBlocks,
+    KernelHeight as ProtoKernelHeight,
+    KernelHeights as ProtoKernelHeights,
     TransactionKernels as ProtoTransactionKernels,
     TransactionOutputs as ProtoTransactionOutputs,
 };
@@ -31,7 +33,10 @@ use crate::{
     base_node::comms_interface as ci,
     proof_of_work::Difficulty,
     proto::core as core_proto_types,
-    transactions::proto::{types as transactions_proto, utils::try_convert_all},
+    transactions::{
+        proto::{types as transactions_proto, utils::try_convert_all},
+        tari_amount::MicroTari,
+    },
 };
 use std::{
     convert::TryInto,
@@ -49,6 +54,13 @@ impl TryInto<ci::NodeCommsResponse> for ProtoNodeCommsResponse {
                 let kernels = try_convert_all(kernels.kernels)?;
                 ci::NodeCommsResponse::TransactionKernels(kernels)
             },
+            KernelHeights(kernel_heights) => ci::NodeCommsResponse::KernelHeights(
+                kernel_heights
+                    .kernel_heights
+                    .into_iter()
+                    .map(|kh| (kh.hash, kh.height))
+                    .collect(),
+            ),
             BlockHeaders(headers) => {
                 let headers = try_convert_all(headers.headers)?;
                 ci::NodeCommsResponse::BlockHeaders(headers)
@@ -65,6 +77,9 @@ impl TryInto<ci::NodeCommsResponse> for ProtoNodeCommsResponse {
             NewBlockTemplate(block_template) => ci::NodeCommsResponse::NewBlockTemplate(block_template.try_into()?),
             NewBlock(block) => ci::NodeCommsResponse::NewBlock(block.try_into()?),
             TargetDifficulty(difficulty) => ci::NodeCommsResponse::TargetDifficulty(Difficulty::from(difficulty)),
+            FeePerGramEstimate(fee_per_gram) => {
+                ci::NodeCommsResponse::FeePerGramEstimate(MicroTari::from(fee_per_gram))
+            },
         };
 
         Ok(response)
@@ -80,6 +95,12 @@ impl From<ci::NodeCommsResponse> for ProtoNodeCommsResponse {
                 let kernels = kernels.into_iter().map(Into::into).collect();
                 ProtoNodeCommsResponse::TransactionKernels(kernels)
             },
+            KernelHeights(kernel_heights) => ProtoNodeCommsResponse::KernelHeights(
+                kernel_heights
+                    .into_iter()
+                    .map(|(hash, height)| ProtoKernelHeight { hash, height })
+                    .collect(),
+            ),
             BlockHeaders(headers) => {
                 let block_headers = headers.into_iter().map(Into::into).collect();
                 ProtoNodeCommsResponse::BlockHeaders(block_headers)
@@ -96,6 +117,7 @@ impl From<ci::NodeCommsResponse> for ProtoNodeCommsResponse {
             NewBlockTemplate(block_template) => ProtoNodeCommsResponse::NewBlockTemplate(block_template.into()),
             NewBlock(block) => ProtoNodeCommsResponse::NewBlock(block.into()),
             TargetDifficulty(difficulty) => ProtoNodeCommsResponse::TargetDifficulty(difficulty.as_u64()),
+            FeePerGramEstimate(fee_per_gram) => ProtoNodeCommsResponse::FeePerGramEstimate(fee_per_gram.into()),
         }
     }
 }
@@ -112,6 +134,14 @@ impl FromIterator<transactions_proto::TransactionKernel> for ProtoTransactionKer
     }
 }
 
+impl FromIterator<ProtoKernelHeight> for ProtoKernelHeights {
+    fn from_iter<T: IntoIterator<Item = ProtoKernelHeight>>(iter: T) -> Self {
+        Self {
+            kernel_heights: iter.into_iter().collect(),
+        }
+    }
+}
+
 impl FromIterator<core_proto_types::BlockHeader> for ProtoBlockHeaders {
     fn from_iter<T: IntoIterator<Item = core_proto_types::BlockHeader>>(iter: T) -> Self {
         Self {
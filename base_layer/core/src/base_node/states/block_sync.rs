@@ -28,6 +28,7 @@ use crate::{
     chain_storage::{BlockchainBackend, ChainMetadata},
 };
 use log::*;
+use std::cmp::Ordering;
 
 const LOG_TARGET: &str = "base_node::block_sync";
 
@@ -90,10 +91,11 @@ impl From<InitialSync> for BlockSyncInfo {
 async fn network_chain_tip<B: BlockchainBackend>(shared: &mut BaseNodeStateMachine<B>) -> Result<u64, String> {
     let metadata_list = shared.comms.get_metadata().await.map_err(|e| e.to_string())?;
     // TODO: Use heuristics to weed out outliers / dishonest nodes.
+    // Sync towards the chain tip of the peer with the most accumulated work
     Ok(metadata_list
         .into_iter()
         .fold(ChainMetadata::default(), |best, current| {
-            if current.height_of_longest_chain.unwrap_or(0) >= best.height_of_longest_chain.unwrap_or(0) {
+            if current.cmp_chain_strength(&best) != Ordering::Less {
                 current
             } else {
                 best
@@ -37,7 +37,7 @@ use crate::{
 };
 
 use log::*;
-use std::time::Duration;
+use std::{cmp::Ordering, time::Duration};
 
 const LOG_TARGET: &str = "base_node::initial_sync";
 // The number of times we'll request the chain metadata before giving up
@@ -119,9 +119,9 @@ impl InitialSync {
 
     fn summarize_network_data(&self, data: Vec<ChainMetadata>) -> ChainMetadata {
         // TODO: Use heuristics to weed out outliers / dishonest nodes.
-        // Right now, we a simple strategy of returning the max height
+        // Right now, we a simple strategy of returning the chain with the most accumulated work
         data.into_iter().fold(ChainMetadata::default(), |best, current| {
-            if current.height_of_longest_chain.unwrap_or(0) >= best.height_of_longest_chain.unwrap_or(0) {
+            if current.cmp_chain_strength(&best) != Ordering::Less {
                 current
             } else {
                 best
@@ -152,7 +152,7 @@ impl InitialSync {
                 // If the user-configured pruning horizon < HORIZON_WHEN_SYNCING, then use that
                 let horizon_block = std::cmp::max(horizon_block, local.horizon_block(network_tip));
                 let local_tip = local.height_of_longest_chain.unwrap_or(0);
-                if local_tip < horizon_block {
+                if local_tip < horizon_block && local.cmp_chain_strength(&network) == Ordering::Less {
                     info!(
                         target: LOG_TARGET,
                         "We're far behind the network chain tip (block #{}), so we're going to re-sync the entire \
@@ -162,13 +162,15 @@ impl InitialSync {
                     );
                     return BehindHorizon(horizon_block);
                 }
-                if local_tip < network_tip {
+                if local.cmp_chain_strength(&network) == Ordering::Less {
                     info!(
                         target: LOG_TARGET,
-                        "Our local blockchain history is a little behind that of the network. We're at block #{}, and \
-                         the chain tip is at #{}",
+                        "Our local blockchain history is a little behind that of the network. We're at block #{} with \
+                         accumulated difficulty {}, and the chain tip is at #{} with accumulated difficulty {}",
                         local_tip,
-                        network_tip
+                        local.accumulated_difficulty.unwrap_or_default(),
+                        network_tip,
+                        network.accumulated_difficulty.unwrap_or_default()
                     );
                     Lagging(network_tip)
                 } else {
@@ -104,13 +104,15 @@ impl Block {
         Ok(())
     }
 
-    /// This function will check all stxo to ensure that feature flags where followed
+    /// This function will check all stxo to ensure that feature flags where followed, and that hash locked outputs
+    /// are spent according to their hash locks
     pub fn check_stxo_rules(&self) -> Result<(), BlockValidationError> {
         for input in self.body.inputs() {
             if input.features.maturity > self.header.height {
                 return Err(BlockValidationError::InputMaturity);
             }
         }
+        self.body.check_hash_locks()?;
         Ok(())
     }
 
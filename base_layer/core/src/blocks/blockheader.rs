@@ -152,6 +152,14 @@ impl BlockHeader {
     pub fn achieved_difficulty(&self) -> Difficulty {
         self.pow.achieved_difficulty(self)
     }
+
+    /// Calculates the total accumulated difficulty of the chain up to and _including_ this block. This is used to
+    /// compare the strength of competing chain tips.
+    pub fn total_accumulated_difficulty_inclusive(&self) -> Difficulty {
+        let mut pow = ProofOfWork::default();
+        pow.add_difficulty(&self.pow, self.achieved_difficulty());
+        pow.total_accumulated_difficulty()
+    }
 }
 
 impl From<NewBlockHeaderTemplate> for BlockHeader {
@@ -48,6 +48,7 @@ pub fn get_genesis_block() -> Block {
             features: OutputFeatures {
                 flags: OutputFlags::COINBASE_OUTPUT,
                 maturity: 1,
+                hash_lock: None,
             },
             commitment: Commitment::from_hex(
                 "feba9eeee21bb01aea86cfa52ea3c905647e3785040581dd9c1f6c89510e6548",
@@ -66,6 +67,7 @@ pub fn get_genesis_block() -> Block {
             )
                 .unwrap(),
             excess_sig: sig,
+            hash_preimage: None,
         }],
     );
     body.sort();
@@ -156,6 +156,7 @@ macro_rules! make_async {
 
 make_async!(get_metadata() -> ChainMetadata);
 make_async!(fetch_kernel(hash: HashOutput) -> TransactionKernel);
+make_async!(fetch_kernel_height(hash: HashOutput) -> u64);
 make_async!(fetch_header_with_block_hash(hash: HashOutput) -> BlockHeader);
 make_async!(fetch_header(block_num: u64) -> BlockHeader);
 make_async!(fetch_utxo(hash: HashOutput) -> TransactionOutput);
@@ -29,7 +29,7 @@ use crate::{
         ChainMetadata,
         HistoricalBlock,
     },
-    proof_of_work::Difficulty,
+    proof_of_work::{Difficulty, ProofOfWork},
     transactions::{
         transaction::{TransactionInput, TransactionKernel, TransactionOutput},
         types::{BlindingFactor, Commitment, CommitmentFactory, HashOutput},
@@ -276,13 +276,14 @@ where T: BlockchainBackend
     fn read_metadata(db: &T) -> Result<ChainMetadata, ChainStorageError> {
         let height = fetch!(meta db, ChainHeight, None);
         let hash = fetch!(meta db, BestBlock, None);
-        let _work = fetch!(meta db, AccumulatedWork, 0);
+        let work = fetch!(meta db, AccumulatedWork, 0);
         // Set a default of 2880 blocks (2 days with 1min blocks)
         let horizon = fetch!(meta db, PruningHorizon, 2880);
         Ok(ChainMetadata {
             height_of_longest_chain: height,
             best_block: hash,
             pruning_horizon: horizon,
+            accumulated_difficulty: height.map(|_| Difficulty::from(work)),
         })
     }
 
@@ -329,7 +330,13 @@ where T: BlockchainBackend
         })
     }
 
-    fn update_metadata(&self, new_height: u64, new_hash: Vec<u8>) -> Result<(), ChainStorageError> {
+    fn update_metadata(
+        &self,
+        new_height: u64,
+        new_hash: Vec<u8>,
+        accumulated_difficulty: Difficulty,
+    ) -> Result<(), ChainStorageError>
+    {
         let mut db = self.metadata.write().map_err(|_| {
             ChainStorageError::AccessError(
                 "Could not obtain write access to blockchain metadata after storing block".into(),
@@ -337,6 +344,7 @@ where T: BlockchainBackend
         })?;
         db.height_of_longest_chain = Some(new_height);
         db.best_block = Some(new_hash);
+        db.accumulated_difficulty = Some(accumulated_difficulty);
         Ok(())
     }
 
@@ -367,7 +375,8 @@ where T: BlockchainBackend
     /// calling [BlockchainDatabase::try_recover_metadata] in that case to re-sync the metadata; or else
     /// just exit the program.
     pub fn get_total_work(&self) -> Result<Difficulty, ChainStorageError> {
-        unimplemented!()
+        let metadata = self.access_metadata()?;
+        Ok(metadata.accumulated_difficulty.unwrap_or_default())
     }
 
     /// Returns the transaction kernel with the given hash.
@@ -375,6 +384,26 @@ where T: BlockchainBackend
         fetch!(self, hash, TransactionKernel)
     }
 
+    /// Returns the height of the main chain block that contains the transaction kernel with the given hash. The kernel
+    /// MMR checkpoints are searched from the chain tip back to the pruning horizon, so recently mined kernels are found
+    /// quickly.
+    pub fn fetch_kernel_height(&self, hash: HashOutput) -> Result<u64, ChainStorageError> {
+        if !self.db.contains(&DbKey::TransactionKernel(hash.clone()))? {
+            return Err(ChainStorageError::ValueNotFound(DbKey::TransactionKernel(hash)));
+        }
+        let metadata = self.get_metadata()?;
+        let tip_height = metadata.height_of_longest_chain.ok_or(ChainStorageError::InvalidQuery(
+            "Cannot retrieve kernel height. Blockchain DB is empty".into(),
+        ))?;
+        for height in (metadata.horizon_block(tip_height)..=tip_height).rev() {
+            let (kernel_hashes, _) = self.db.fetch_mmr_checkpoint(MmrTree::Kernel, height)?.into_parts();
+            if kernel_hashes.contains(&hash) {
+                return Ok(height);
+            }
+        }
+        Err(ChainStorageError::BeyondPruningHorizon)
+    }
+
     /// Returns the block header at the given block height.
     pub fn fetch_header(&self, block_num: u64) -> Result<BlockHeader, ChainStorageError> {
         fetch!(self, block_num, BlockHeader)
@@ -522,15 +551,22 @@ where T: BlockchainBackend
             .block
             .validate(&block)
             .map_err(|e| ChainStorageError::ValidationError(e))?;
-        self.store_new_block(block)?;
-        self.update_metadata(block_height, block_hash)?;
+        // The accumulated difficulty is calculated in the same way as for the candidate chains of a reorg, so that the
+        // main chain and the candidate chains are always compared on equal terms.
+        let accumulated_difficulty = match block_height {
+            0 => block.header.total_accumulated_difficulty_inclusive(),
+            _ => calculate_accumulated_difficulty(&self.fetch_header(block_height - 1)?, std::slice::from_ref(&block)),
+        };
+        self.store_new_block(block, accumulated_difficulty)?;
+        self.update_metadata(block_height, block_hash, accumulated_difficulty)?;
         Ok(BlockAddResult::Ok)
     }
 
-    fn store_new_block(&self, block: Block) -> Result<(), ChainStorageError> {
+    fn store_new_block(&self, block: Block, accumulated_difficulty: Difficulty) -> Result<(), ChainStorageError> {
         let (header, inputs, outputs, kernels) = block.dissolve();
         // Build all the DB queries needed to add the block and the add it atomically
         let mut txn = DbTransaction::new();
+        txn.set_chain_metadata(header.height, header.hash(), accumulated_difficulty.as_u64());
         txn.insert_header(header);
         txn.spend_inputs(&inputs);
         outputs.iter().for_each(|utxo| txn.insert_utxo(utxo.clone(), true));
@@ -722,10 +758,12 @@ where T: BlockchainBackend
         txn.rewind_kernel_mmr(steps_back);
         txn.rewind_utxo_mmr(steps_back);
         txn.rewind_rp_mmr(steps_back);
+        let last_header = self.fetch_header(height)?;
+        let accumulated_difficulty = last_header.total_accumulated_difficulty_inclusive();
+        txn.set_chain_metadata(height, last_header.hash(), accumulated_difficulty.as_u64());
         self.commit(txn)?;
 
-        let last_block = self.fetch_block(height)?.block().clone();
-        self.update_metadata(height, last_block.hash())
+        self.update_metadata(height, last_header.hash(), accumulated_difficulty)
     }
 
     /// Checks whether we should add the block as an orphan. If it is the case, the orphan block is added and the chain
@@ -764,9 +802,10 @@ where T: BlockchainBackend
     }
 
     /// The handle_reorg function is triggered by the adding of orphaned blocks. Reorg chains are constructed by
-    /// building a chain of orphaned blocks from the leaf blocks with the highest accumulated difficulties back to the
-    /// main chain. When a valid reorg chain is constructed with a higher accumulated difficulty, then the main chain is
-    /// rewound and updated with the newly un-orphaned blocks from the reorg chain.
+    /// building a chain of orphaned blocks from the new block back to the main chain, and then extending it with all
+    /// the orphaned blocks that build on the new block. When a valid reorg chain is constructed with a higher total
+    /// accumulated difficulty than the main chain, then the main chain is rewound and updated with the newly
+    /// un-orphaned blocks from the reorg chain.
     fn handle_reorg(&self, new_block: Block) -> Result<BlockAddResult, ChainStorageError> {
         // We can assume that the new block is part of the re-org chain if it exists, otherwise the re-org would have
         // happened on the previous call to this function.
@@ -788,7 +827,7 @@ where T: BlockchainBackend
 
         let tree = self.build_orphan_tree(reorg_chain)?;
 
-        match self.find_longer_chain(tree) {
+        match self.find_longer_chain(&main_header, tree)? {
             None => Ok(BlockAddResult::OrphanBlock),
             Some(chain) => {
                 self.reorganise_chain(main_header, chain)?;
@@ -827,14 +866,71 @@ where T: BlockchainBackend
         Ok(reorg_chain)
     }
 
-    fn build_orphan_tree(&self, _reorg_chain: VecDeque<Block>) -> Result<(), ChainStorageError> {
-        // TODO - Build an return a tree for later blocks leading to new_block
-        Ok(())
+    /// Extends the reorg chain with the orphaned blocks that build on it. Every path from the start of the reorg chain
+    /// to a leaf of the orphan block tree is returned as a candidate chain.
+    fn build_orphan_tree(&self, reorg_chain: VecDeque<Block>) -> Result<Vec<Vec<Block>>, ChainStorageError> {
+        let mut candidate_chains = Vec::new();
+        let mut pending_chains = vec![Vec::from(reorg_chain)];
+        while let Some(chain) = pending_chains.pop() {
+            let tip_header = &chain.last().expect("Reorg chains cannot be empty").header;
+            let children = self.fetch_orphan_children(tip_header)?;
+            if children.is_empty() {
+                candidate_chains.push(chain);
+                continue;
+            }
+            for child in children {
+                let mut extended_chain = chain.clone();
+                extended_chain.push(child);
+                pending_chains.push(extended_chain);
+            }
+        }
+        Ok(candidate_chains)
     }
 
-    fn find_longer_chain(&self, _tree: ()) -> Option<Vec<Block>> {
-        // TODO - Check tree vs main chain for great accum difficulty
-        None
+    // Returns the orphaned blocks that directly build on the block with the provided header.
+    fn fetch_orphan_children(&self, header: &BlockHeader) -> Result<Vec<Block>, ChainStorageError> {
+        let hash = header.hash();
+        let mut children = Vec::new();
+        let mut result = Ok(());
+        self.db.for_each_orphan(|pair| match pair {
+            Ok((_, block)) => {
+                if block.header.prev_hash == hash && block.header.height == header.height + 1 {
+                    children.push(block);
+                }
+            },
+            Err(e) => result = Err(e),
+        })?;
+        result.map(|_| children)
+    }
+
+    /// Returns the candidate chain with the highest total accumulated difficulty, if it has more accumulated
+    /// difficulty than the main chain. The accumulated difficulties of the candidate chains are recalculated from
+    /// the fork point using the achieved difficulty of each block, rather than trusting the claimed values in the
+    /// orphaned block headers.
+    fn find_longer_chain(
+        &self,
+        fork_header: &BlockHeader,
+        candidate_chains: Vec<Vec<Block>>,
+    ) -> Result<Option<Vec<Block>>, ChainStorageError>
+    {
+        let main_chain_difficulty = self.get_total_work()?;
+        let best_chain = candidate_chains
+            .into_iter()
+            .map(|chain| (calculate_accumulated_difficulty(fork_header, &chain), chain))
+            .max_by_key(|(accumulated_difficulty, _)| *accumulated_difficulty);
+        Ok(match best_chain {
+            Some((accumulated_difficulty, chain)) if accumulated_difficulty > main_chain_difficulty => {
+                debug!(
+                    target: LOG_TARGET,
+                    "Found a reorg chain with {} blocks and accumulated difficulty {} (main chain: {}).",
+                    chain.len(),
+                    accumulated_difficulty,
+                    main_chain_difficulty
+                );
+                Some(chain)
+            },
+            _ => None,
+        })
     }
 
     fn reorganise_chain(&self, header: BlockHeader, chain: Vec<Block>) -> Result<(), ChainStorageError> {
@@ -888,7 +984,11 @@ where T: BlockchainBackend
             .chain_tip
             .validate(&last_header)?;
 
-        self.update_metadata(last_header.height, last_header.hash())?;
+        let accumulated_difficulty = last_header.total_accumulated_difficulty_inclusive();
+        let mut txn = DbTransaction::new();
+        txn.set_chain_metadata(last_header.height, last_header.hash(), accumulated_difficulty.as_u64());
+        self.commit(txn)?;
+        self.update_metadata(last_header.height, last_header.hash(), accumulated_difficulty)?;
 
         Ok(())
     }
@@ -924,6 +1024,22 @@ where T: BlockchainBackend
     }
 }
 
+// Calculates the total accumulated difficulty at the tip of a chain of blocks that builds on the block with the
+// provided fork header.
+fn calculate_accumulated_difficulty(fork_header: &BlockHeader, chain: &[Block]) -> Difficulty {
+    let mut prev_pow = fork_header.pow.clone();
+    let mut prev_achieved_difficulty = fork_header.achieved_difficulty();
+    for block in chain {
+        let mut pow = ProofOfWork::new(block.header.pow.pow_algo);
+        pow.add_difficulty(&prev_pow, prev_achieved_difficulty);
+        prev_pow = pow;
+        prev_achieved_difficulty = block.header.achieved_difficulty();
+    }
+    let mut pow = ProofOfWork::default();
+    pow.add_difficulty(&prev_pow, prev_achieved_difficulty);
+    pow.total_accumulated_difficulty()
+}
+
 fn unexpected_result<T>(req: DbKey, res: DbValue) -> Result<T, ChainStorageError> {
     let msg = format!("Unexpected result for database query {}. Response: {}", req, res);
     error!(target: LOG_TARGET, "{}", msg);
@@ -138,6 +138,23 @@ impl DbTransaction {
         )));
     }
 
+    /// Sets the height, best block hash and total accumulated work of the longest chain, so that the chain metadata
+    /// can be restored when the database is reopened.
+    pub fn set_chain_metadata(&mut self, height: u64, best_block: BlockHash, accumulated_work: u64) {
+        self.operations.push(WriteOperation::Insert(DbKeyValuePair::Metadata(
+            MetadataKey::ChainHeight,
+            MetadataValue::ChainHeight(Some(height)),
+        )));
+        self.operations.push(WriteOperation::Insert(DbKeyValuePair::Metadata(
+            MetadataKey::BestBlock,
+            MetadataValue::BestBlock(Some(best_block)),
+        )));
+        self.operations.push(WriteOperation::Insert(DbKeyValuePair::Metadata(
+            MetadataKey::AccumulatedWork,
+            MetadataValue::AccumulatedWork(accumulated_work),
+        )));
+    }
+
     /// Rewinds the Kernel MMR state by the given number of Checkpoints.
     pub fn rewind_kernel_mmr(&mut self, steps_back: usize) {
         self.operations
@@ -20,9 +20,12 @@
 // WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
 // USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
-use crate::blocks::blockheader::BlockHash;
+use crate::{blocks::blockheader::BlockHash, proof_of_work::Difficulty};
 use serde::{Deserialize, Serialize};
-use std::fmt::{Display, Error, Formatter};
+use std::{
+    cmp::Ordering,
+    fmt::{Display, Error, Formatter},
+};
 use tari_utilities::hex::Hex;
 
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
@@ -34,14 +37,18 @@ pub struct ChainMetadata {
     /// The number of blocks back from the tip that this database tracks. A value of 0 indicates that all blocks are
     /// tracked (i.e. the database is in full archival mode).
     pub pruning_horizon: u64,
+    /// The total accumulated difficulty (the geometric mean of the accumulated difficulties of the PoW algorithms) of
+    /// the longest valid chain, including the tip block, or `None` for an empty chain
+    pub accumulated_difficulty: Option<Difficulty>,
 }
 
 impl ChainMetadata {
-    pub fn new(height: u64, hash: BlockHash, horizon: u64) -> ChainMetadata {
+    pub fn new(height: u64, hash: BlockHash, horizon: u64, accumulated_difficulty: Difficulty) -> ChainMetadata {
         ChainMetadata {
             height_of_longest_chain: Some(height),
             best_block: Some(hash),
             pruning_horizon: horizon,
+            accumulated_difficulty: Some(accumulated_difficulty),
         }
     }
 
@@ -63,6 +70,20 @@ impl ChainMetadata {
     pub fn archival_mode(&mut self) {
         self.pruning_horizon = 0;
     }
+
+    /// Compares the strength of this chain to that of another chain. Chains are ordered by their total accumulated
+    /// difficulty, and then by height. If either chain did not provide an accumulated difficulty (e.g. metadata
+    /// received from an older peer), then only the chain heights are compared.
+    pub fn cmp_chain_strength(&self, other: &ChainMetadata) -> Ordering {
+        let height_ordering = self
+            .height_of_longest_chain
+            .unwrap_or(0)
+            .cmp(&other.height_of_longest_chain.unwrap_or(0));
+        match (self.accumulated_difficulty, other.accumulated_difficulty) {
+            (Some(ours), Some(theirs)) => ours.cmp(&theirs).then(height_ordering),
+            _ => height_ordering,
+        }
+    }
 }
 
 impl Default for ChainMetadata {
@@ -71,6 +92,7 @@ impl Default for ChainMetadata {
             height_of_longest_chain: None,
             best_block: None,
             pruning_horizon: 2880,
+            accumulated_difficulty: None,
         }
     }
 }
@@ -85,13 +107,18 @@ impl Display for ChainMetadata {
             .unwrap_or("Empty Database".into());
         fmt.write_str(&format!("Height of longest chain : {}\n", height))?;
         fmt.write_str(&format!("Best_block : {}\n", best_block))?;
-        fmt.write_str(&format!("Pruning horizon : {}\n", self.pruning_horizon))
+        fmt.write_str(&format!("Pruning horizon : {}\n", self.pruning_horizon))?;
+        fmt.write_str(&format!(
+            "Accumulated difficulty : {}\n",
+            self.accumulated_difficulty.unwrap_or_default()
+        ))
     }
 }
 
 #[cfg(test)]
 mod test {
     use super::ChainMetadata;
+    use std::cmp::Ordering;
 
     #[test]
     fn horizon_block_on_default() {
@@ -119,4 +146,25 @@ mod test {
         assert_eq!(metadata.horizon_block(100), 0);
         assert_eq!(metadata.horizon_block(2881), 0);
     }
+
+    #[test]
+    fn cmp_chain_strength() {
+        let mut ours = ChainMetadata::new(10, vec![], 0, 1_000.into());
+        let mut theirs = ChainMetadata::new(12, vec![], 0, 900.into());
+        // More accumulated difficulty wins, even on a shorter chain
+        assert_eq!(ours.cmp_chain_strength(&theirs), Ordering::Greater);
+        assert_eq!(theirs.cmp_chain_strength(&ours), Ordering::Less);
+        // Equal accumulated difficulties are ordered by height
+        theirs.accumulated_difficulty = Some(1_000.into());
+        assert_eq!(ours.cmp_chain_strength(&theirs), Ordering::Less);
+        // Without an accumulated difficulty only the heights are compared
+        theirs.accumulated_difficulty = None;
+        assert_eq!(ours.cmp_chain_strength(&theirs), Ordering::Less);
+        ours.height_of_longest_chain = Some(12);
+        assert_eq!(ours.cmp_chain_strength(&theirs), Ordering::Equal);
+        assert_eq!(
+            ChainMetadata::default().cmp_chain_strength(&ChainMetadata::default()),
+            Ordering::Equal
+        );
+    }
 }
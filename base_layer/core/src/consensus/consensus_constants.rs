@@ -20,11 +20,14 @@
 // WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
 // USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
+use crate::proof_of_work::{DifficultyAdjustment, DifficultyAdjustmentType};
 use chrono::{DateTime, Duration, Utc};
 use std::ops::Add;
+use tari_common::Network;
 use tari_utilities::epoch_time::EpochTime;
 
 /// This is the inner struct used to control all consensus values.
+#[derive(Clone, Debug)]
 pub struct ConsensusConstants {
     /// The min height maturity a coinbase utxo must have
     coinbase_lock_height: u64,
@@ -43,6 +46,8 @@ pub struct ConsensusConstants {
     pow_algo_count: u64,
     // This is how many blocks we use to count towards the median timestamp to ensure the block chain moves forward
     median_timestamp_count: usize,
+    // The difficulty adjustment algorithm used to calculate the target difficulty of each PoW algorithm
+    difficulty_adjustment_type: DifficultyAdjustmentType,
 }
 // The target time used by the difficulty adjustment algorithms, their target time is the target block interval * PoW
 // algorithm count
@@ -52,6 +57,21 @@ impl ConsensusConstants {
         ConsensusConstants::default()
     }
 
+    /// Returns the consensus constants used by the specified network.
+    pub fn for_network(network: Network) -> Self {
+        let difficulty_adjustment_type = match network {
+            Network::MainNet => DifficultyAdjustmentType::LinearWeightedMovingAverage,
+            Network::TestNet => DifficultyAdjustmentType::SimpleMovingAverage,
+        };
+        ConsensusConstants::default().with_difficulty_adjustment_type(difficulty_adjustment_type)
+    }
+
+    /// Selects the difficulty adjustment algorithm used to calculate the target difficulty of each PoW algorithm.
+    pub fn with_difficulty_adjustment_type(mut self, difficulty_adjustment_type: DifficultyAdjustmentType) -> Self {
+        self.difficulty_adjustment_type = difficulty_adjustment_type;
+        self
+    }
+
     /// The min height maturity a coinbase utxo must have
     pub fn coinbase_lock_height(&self) -> u64 {
         self.coinbase_lock_height
@@ -108,6 +128,20 @@ impl ConsensusConstants {
     pub fn get_median_timestamp_count(&self) -> usize {
         self.median_timestamp_count
     }
+
+    /// The difficulty adjustment algorithm used to calculate the target difficulty of each PoW algorithm
+    pub fn get_difficulty_adjustment_type(&self) -> DifficultyAdjustmentType {
+        self.difficulty_adjustment_type
+    }
+
+    /// Constructs a new instance of the selected difficulty adjustment algorithm, using the difficulty block window
+    /// and the difficulty target block interval.
+    pub fn create_difficulty_adjustment(&self) -> Box<dyn DifficultyAdjustment + Send + Sync> {
+        self.difficulty_adjustment_type.create(
+            self.difficulty_block_window as usize,
+            self.get_diff_target_block_interval(),
+        )
+    }
 }
 
 impl Default for ConsensusConstants {
@@ -123,6 +157,7 @@ impl Default for ConsensusConstants {
             max_block_transaction_weight: 10000, // TODO: a better weight estimate should be selected
             pow_algo_count: 2,
             median_timestamp_count: 11,
+            difficulty_adjustment_type: DifficultyAdjustmentType::LinearWeightedMovingAverage,
         }
     }
 }
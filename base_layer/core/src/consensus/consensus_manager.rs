@@ -180,7 +180,7 @@ where B: BlockchainBackend
 {
     fn default() -> Self {
         ConsensusManagerInner {
-            emission_schedule: EmissionSchedule::new(10_000_000.into(), 0.999, 100.into()),
+            emission_schedule: EmissionSchedule::default(),
             diff_adj_manager: RwLock::new(None),
         }
     }
@@ -92,6 +92,14 @@ impl EmissionSchedule {
     }
 }
 
+impl Default for EmissionSchedule {
+    /// The emission schedule used by the current consensus rules
+    fn default() -> Self {
+        // CONSENSUS_RULES
+        EmissionSchedule::new(MicroTari::from(10_000_000), 0.999, MicroTari::from(100))
+    }
+}
+
 pub struct EmissionValues<'a> {
     block_num: u64,
     supply: MicroTari,
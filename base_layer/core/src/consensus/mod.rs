@@ -20,10 +20,14 @@
 // WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
 // USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
-mod consensus_constants;
-mod consensus_manager;
+cfg_if! {
+    if #[cfg(feature = "base_node")] {
+        mod consensus_constants;
+        mod consensus_manager;
 
-pub mod emission;
+        pub use consensus_constants::ConsensusConstants;
+        pub use consensus_manager::{ConsensusManager, ConsensusManagerError};
+    }
+}
 
-pub use consensus_constants::ConsensusConstants;
-pub use consensus_manager::{ConsensusManager, ConsensusManagerError};
+pub mod emission;
@@ -20,7 +20,7 @@
 //  WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
 //  USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
-use crate::types::BaseNodeRng;
+use crate::{transactions::tari_amount::MicroTari, types::BaseNodeRng};
 use std::{cell::RefCell, time::Duration};
 
 /// The maximum number of transactions that can be stored in the Unconfirmed Transaction pool
@@ -28,6 +28,21 @@ pub const MEMPOOL_UNCONFIRMED_POOL_STORAGE_CAPACITY: usize = 1000;
 /// The maximum number of transactions that can be skipped when compiling a set of highest priority transactions,
 /// skipping over large transactions are performed in an attempt to fit more transactions into the remaining space.
 pub const MEMPOOL_UNCONFIRMED_POOL_WEIGHT_TRANSACTION_SKIP_COUNT: usize = 20;
+/// The maximum total weight of all transactions that can be stored in the Unconfirmed Transaction pool
+pub const MEMPOOL_UNCONFIRMED_POOL_MAX_WEIGHT: u64 = 100_000;
+/// The lowest fee-per-gram that a transaction must pay to be accepted into the Unconfirmed Transaction pool
+pub const MEMPOOL_UNCONFIRMED_POOL_MIN_FEE_PER_GRAM: MicroTari = MicroTari(1);
+/// The fee-per-gram of the replaced weight that a replacement transaction must pay on top of the fees of the
+/// transactions it replaces
+pub const MEMPOOL_UNCONFIRMED_POOL_MIN_REPLACEMENT_FEE_PER_GRAM: MicroTari = MicroTari(1);
+/// The time-to-live duration used for transactions stored in the Unconfirmed Transaction pool
+pub const MEMPOOL_UNCONFIRMED_POOL_CACHE_TTL: Duration = Duration::from_secs(72 * 60 * 60);
+
+/// The number of recently published blocks that are used by the mempool fee estimator
+pub const MEMPOOL_FEE_ESTIMATOR_BLOCK_HISTORY_LEN: usize = 30;
+
+/// The number of recent transaction rejection reasons that are retained by the mempool
+pub const MEMPOOL_REJECTION_CACHE_CAPACITY: usize = 1000;
 
 /// The maximum number of transactions that can be stored in the Orphan pool
 pub const MEMPOOL_ORPHAN_POOL_STORAGE_CAPACITY: usize = 1000;
@@ -35,23 +35,28 @@ extern crate cfg_if;
 
 cfg_if! {
     if #[cfg(feature = "base_node")] {
-        pub mod base_node;
         pub mod blocks;
         pub mod chain_storage;
-        pub mod consensus;
         pub mod consts;
         pub mod helpers;
         pub mod mining;
         pub mod proof_of_work;
-        pub mod proto;
         pub mod types;
         pub mod validation;
     }
 }
 
+#[cfg(any(feature = "base_node", feature = "base_node_proto"))]
+pub mod base_node;
+#[cfg(any(feature = "base_node", feature = "base_node_proto"))]
+pub mod proto;
+
 #[cfg(any(feature = "base_node", feature = "mempool_proto"))]
 pub mod mempool;
 
+#[cfg(any(feature = "base_node", feature = "transactions"))]
+pub mod consensus;
+
 #[cfg(feature = "transactions")]
 pub mod transactions;
 
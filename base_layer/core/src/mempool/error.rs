@@ -23,6 +23,7 @@
 use crate::{
     chain_storage::ChainStorageError,
     mempool::{
+        fee_estimator::FeeEstimatorError,
         orphan_pool::OrphanPoolError,
         pending_pool::PendingPoolError,
         reorg_pool::ReorgPoolError,
@@ -38,9 +39,12 @@ pub enum MempoolError {
     OrphanPoolError(OrphanPoolError),
     PendingPoolError(PendingPoolError),
     ReorgPoolError(ReorgPoolError),
+    FeeEstimatorError(FeeEstimatorError),
     TransactionError(TransactionError),
     ChainStorageError(ChainStorageError),
     /// The Blockchain height is undefined
     ChainHeightUndefined,
     ValidationError,
+    /// The Thread Safety has been breached and the data access has become poisoned
+    PoisonedAccess,
 }
@@ -0,0 +1,235 @@
+//  Copyright 2019 The Tari Project
+//
+//  Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+//  following conditions are met:
+//
+//  1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+//  disclaimer.
+//
+//  2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+//  following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+//  3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+//  products derived from this software without specific prior written permission.
+//
+//  THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+//  INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+//  DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+//  SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+//  SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+//  WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+//  USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+use crate::{
+    blocks::Block,
+    consts::MEMPOOL_FEE_ESTIMATOR_BLOCK_HISTORY_LEN,
+    mempool::fee_estimator::FeeEstimatorError,
+    transactions::{fee::Fee, tari_amount::MicroTari, transaction::Transaction},
+};
+use std::{
+    cmp::{max, Ordering},
+    collections::VecDeque,
+    sync::{Arc, RwLock},
+};
+
+/// Configuration for the FeeEstimator
+#[derive(Clone, Copy)]
+pub struct FeeEstimatorConfig {
+    /// The number of recently published blocks that are used when estimating fees
+    pub block_history_len: usize,
+}
+
+impl Default for FeeEstimatorConfig {
+    fn default() -> Self {
+        Self {
+            block_history_len: MEMPOOL_FEE_ESTIMATOR_BLOCK_HISTORY_LEN,
+        }
+    }
+}
+
+/// The FeeEstimator tracks the fee-per-gram paid by transactions in recently published blocks and, together with the
+/// transactions currently waiting in the UnconfirmedPool, estimates the fee-per-gram required for a new transaction
+/// to be included within a target number of blocks.
+pub struct FeeEstimator {
+    config: FeeEstimatorConfig,
+    block_fee_rates: Arc<RwLock<VecDeque<u64>>>,
+}
+
+impl FeeEstimator {
+    /// Create a new FeeEstimator with the specified configuration
+    pub fn new(config: FeeEstimatorConfig) -> Self {
+        Self {
+            config,
+            block_fee_rates: Arc::new(RwLock::new(VecDeque::with_capacity(config.block_history_len))),
+        }
+    }
+
+    /// Record the fee-per-gram that was required for inclusion in the provided published block. The lowest
+    /// fee-per-gram of the published transactions that passed through the mempool is used, falling back to the average
+    /// fee-per-gram of the block when none of its transactions were known. Blocks without fee paying transactions are
+    /// ignored.
+    pub fn process_published_block(
+        &self,
+        published_block: &Block,
+        published_txs: &[Arc<Transaction>],
+    ) -> Result<(), FeeEstimatorError>
+    {
+        let fee_rate = match published_txs
+            .iter()
+            .map(|tx| tx.calculate_ave_fee_per_gram())
+            .min_by(|a, b| a.partial_cmp(b).unwrap_or(Ordering::Equal))
+        {
+            Some(fee_rate) => fee_rate as u64,
+            None => {
+                let total_fees = u64::from(published_block.calculate_fees());
+                if total_fees == 0 {
+                    return Ok(());
+                }
+                let weight = Fee::calculate_weight(
+                    published_block.body.inputs().len(),
+                    published_block.body.outputs().len(),
+                );
+                total_fees / max(weight, 1)
+            },
+        };
+
+        let mut block_fee_rates = self
+            .block_fee_rates
+            .write()
+            .map_err(|_| FeeEstimatorError::PoisonedAccess)?;
+        while block_fee_rates.len() >= self.config.block_history_len {
+            block_fee_rates.pop_front();
+        }
+        block_fee_rates.push_back(fee_rate);
+        Ok(())
+    }
+
+    /// Estimate the fee-per-gram required for a transaction to be included within `target_blocks` blocks. The
+    /// estimate is the largest of:
+    /// 1. The minimum fee-per-gram currently accepted by the UnconfirmedPool.
+    /// 1. The fee-per-gram needed to outbid the unconfirmed transactions that would otherwise fill the next
+    /// `target_blocks` blocks.
+    /// 1. The fee-per-gram that was sufficient for inclusion in the recently published blocks, where the median is used
+    /// for a single block target and lower percentiles are used as the target increases.
+    pub fn estimate_fee_per_gram(
+        &self,
+        target_blocks: u64,
+        unconfirmed_txs: &[Arc<Transaction>],
+        min_fee_per_gram: MicroTari,
+        max_block_weight: u64,
+    ) -> Result<MicroTari, FeeEstimatorError>
+    {
+        let target_blocks = max(target_blocks, 1);
+
+        let mut unconfirmed_fee_rates: Vec<(f64, u64)> = unconfirmed_txs
+            .iter()
+            .map(|tx| (tx.calculate_ave_fee_per_gram(), tx.calculate_weight()))
+            .collect();
+        unconfirmed_fee_rates.sort_by(|a, b| b.0.partial_cmp(&a.0).unwrap_or(Ordering::Equal));
+        let target_weight = max_block_weight.saturating_mul(target_blocks);
+        let mut accumulated_weight: u64 = 0;
+        let mut mempool_fee_rate: u64 = 0;
+        for (fee_rate, weight) in unconfirmed_fee_rates {
+            accumulated_weight += weight;
+            if accumulated_weight > target_weight {
+                mempool_fee_rate = fee_rate as u64 + 1;
+                break;
+            }
+        }
+
+        let mut block_fee_rates: Vec<u64> = self
+            .block_fee_rates
+            .read()
+            .map_err(|_| FeeEstimatorError::PoisonedAccess)?
+            .iter()
+            .cloned()
+            .collect();
+        block_fee_rates.sort();
+        let block_fee_rate = block_fee_rates
+            .get(block_fee_rates.len() / (target_blocks as usize + 1))
+            .cloned()
+            .unwrap_or(0);
+
+        Ok(MicroTari::from(max(
+            u64::from(min_fee_per_gram),
+            max(mempool_fee_rate, block_fee_rate),
+        )))
+    }
+
+    /// Returns the number of published blocks that are currently used for fee estimation.
+    pub fn len(&self) -> Result<usize, FeeEstimatorError> {
+        Ok(self
+            .block_fee_rates
+            .read()
+            .map_err(|_| FeeEstimatorError::PoisonedAccess)?
+            .len())
+    }
+}
+
+impl Clone for FeeEstimator {
+    fn clone(&self) -> Self {
+        FeeEstimator {
+            config: self.config,
+            block_fee_rates: self.block_fee_rates.clone(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{helpers::create_orphan_block, tx};
+
+    #[test]
+    fn test_estimate_from_unconfirmed_txs() {
+        let tx1 = Arc::new(tx!(MicroTari(10_000), fee: MicroTari(50), inputs: 2, outputs: 1).0);
+        let tx2 = Arc::new(tx!(MicroTari(10_000), fee: MicroTari(20), inputs: 2, outputs: 1).0);
+        let tx3 = Arc::new(tx!(MicroTari(10_000), fee: MicroTari(100), inputs: 2, outputs: 1).0);
+        let unconfirmed_txs = vec![tx1.clone(), tx2.clone(), tx3.clone()];
+        let block_weight = tx1.calculate_weight();
+
+        let fee_estimator = FeeEstimator::new(FeeEstimatorConfig::default());
+        // Only the highest fee tx fits in the next block, a new tx needs to outbid tx1 to be included
+        let estimate = fee_estimator
+            .estimate_fee_per_gram(1, &unconfirmed_txs, MicroTari(1), block_weight)
+            .unwrap();
+        assert_eq!(estimate, MicroTari(tx1.calculate_ave_fee_per_gram() as u64 + 1));
+        // All unconfirmed txs fit into the next three blocks
+        let estimate = fee_estimator
+            .estimate_fee_per_gram(3, &unconfirmed_txs, MicroTari(1), block_weight)
+            .unwrap();
+        assert_eq!(estimate, MicroTari(1));
+    }
+
+    #[test]
+    fn test_estimate_from_published_blocks() {
+        let tx1 = Arc::new(tx!(MicroTari(10_000), fee: MicroTari(50), inputs: 2, outputs: 1).0);
+        let tx2 = Arc::new(tx!(MicroTari(10_000), fee: MicroTari(20), inputs: 2, outputs: 1).0);
+        let tx3 = Arc::new(tx!(MicroTari(10_000), fee: MicroTari(100), inputs: 2, outputs: 1).0);
+        let tx4 = Arc::new(tx!(MicroTari(10_000), fee: MicroTari(80), inputs: 2, outputs: 1).0);
+
+        let fee_estimator = FeeEstimator::new(FeeEstimatorConfig { block_history_len: 2 });
+        let block1 = create_orphan_block(0, vec![(*tx1).clone()]);
+        fee_estimator.process_published_block(&block1, &[tx1.clone()]).unwrap();
+        let block2 = create_orphan_block(1, vec![(*tx2).clone(), (*tx3).clone()]);
+        fee_estimator
+            .process_published_block(&block2, &[tx2.clone(), tx3.clone()])
+            .unwrap();
+        // Unknown transactions fall back to the average fee-per-gram of the block
+        let block3 = create_orphan_block(2, vec![(*tx4).clone()]);
+        fee_estimator.process_published_block(&block3, &[]).unwrap();
+        assert_eq!(fee_estimator.len().unwrap(), 2);
+
+        let block2_fee_rate = tx2.calculate_ave_fee_per_gram() as u64;
+        let block3_fee_rate = u64::from(block3.calculate_fees()) /
+            Fee::calculate_weight(block3.body.inputs().len(), block3.body.outputs().len());
+        let estimate = fee_estimator
+            .estimate_fee_per_gram(1, &[], MicroTari(1), 1_000)
+            .unwrap();
+        assert_eq!(estimate, MicroTari(max(block2_fee_rate, block3_fee_rate)));
+        let estimate = fee_estimator
+            .estimate_fee_per_gram(5, &[], MicroTari(1), 1_000)
+            .unwrap();
+        assert_eq!(estimate, MicroTari(std::cmp::min(block2_fee_rate, block3_fee_rate)));
+    }
+}
@@ -23,20 +23,27 @@
 use crate::{
     blocks::Block,
     chain_storage::{BlockchainBackend, BlockchainDatabase},
+    consensus::ConsensusConstants,
+    consts::MEMPOOL_REJECTION_CACHE_CAPACITY,
     mempool::{
         error::MempoolError,
+        fee_estimator::{FeeEstimator, FeeEstimatorConfig},
         orphan_pool::{OrphanPool, OrphanPoolConfig},
         pending_pool::{PendingPool, PendingPoolConfig},
+        rejection_cache::RejectionCache,
         reorg_pool::{ReorgPool, ReorgPoolConfig},
-        unconfirmed_pool::{UnconfirmedPool, UnconfirmedPoolConfig},
+        unconfirmed_pool::{UnconfirmedPool, UnconfirmedPoolConfig, UnconfirmedPoolError},
     },
-    transactions::{transaction::Transaction, types::Signature},
+    transactions::{tari_amount::MicroTari, transaction::Transaction, types::Signature},
     validation::{Validation, ValidationError, Validator},
 };
 use serde::{Deserialize, Serialize};
-use std::sync::Arc;
+use std::{
+    cmp::Ordering,
+    sync::{Arc, RwLock},
+};
 
-#[derive(Debug, PartialEq, Serialize, Deserialize)]
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
 pub enum TxStorageResponse {
     UnconfirmedPool,
     OrphanPool,
@@ -55,6 +62,61 @@ pub struct StatsResponse {
     pub total_weight: u64,
 }
 
+/// A summary of a transaction stored in one of the pools of the Mempool.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct TxSummary {
+    pub excess_sig: Signature,
+    pub storage: TxStorageResponse,
+    pub weight: u64,
+    pub fee: MicroTari,
+    /// The average fee-per-gram of the transaction, this is the primary priority metric used by the Mempool
+    pub fee_per_gram: MicroTari,
+    /// The earliest height at which the transaction can be included in a block
+    pub min_spendable_height: u64,
+}
+
+impl TxSummary {
+    fn new(tx: &Transaction, storage: TxStorageResponse) -> Self {
+        Self {
+            excess_sig: tx.body.kernels()[0].excess_sig.clone(),
+            storage,
+            weight: tx.calculate_weight(),
+            fee: tx.body.get_total_fee(),
+            fee_per_gram: MicroTari::from(tx.calculate_ave_fee_per_gram() as u64),
+            min_spendable_height: tx.min_spendable_height(),
+        }
+    }
+}
+
+/// The reason why a transaction was rejected or discarded by the Mempool.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum TxRejectionReason {
+    /// The transaction failed validation
+    ValidationFailed(String),
+    /// The transaction spends the same inputs as a stored transaction without paying a higher fee and fee-per-gram
+    DoubleSpend,
+    /// The transaction does not pay the minimum fee-per-gram required by the Mempool
+    LowFee,
+    /// The transaction is heavier than the Mempool allows
+    ExceedsMaxWeight,
+    /// The transaction is time-locked and could not be stored until its time-locks expire
+    Maturity,
+    /// The transaction was not included in a block before its Time-to-live expired
+    Expired,
+}
+
+impl TxRejectionReason {
+    // Returns the rejection reason corresponding to a transaction being refused by the UnconfirmedPool.
+    fn from_unconfirmed_pool_error(err: &UnconfirmedPoolError) -> Option<Self> {
+        match err {
+            UnconfirmedPoolError::LowFee => Some(TxRejectionReason::LowFee),
+            UnconfirmedPoolError::ExceedsMaxWeight => Some(TxRejectionReason::ExceedsMaxWeight),
+            UnconfirmedPoolError::InsufficientReplacementFee => Some(TxRejectionReason::DoubleSpend),
+            _ => None,
+        }
+    }
+}
+
 /// Configuration for the Mempool.
 #[derive(Clone, Copy)]
 pub struct MempoolConfig {
@@ -62,6 +124,9 @@ pub struct MempoolConfig {
     pub orphan_pool_config: OrphanPoolConfig,
     pub pending_pool_config: PendingPoolConfig,
     pub reorg_pool_config: ReorgPoolConfig,
+    pub fee_estimator_config: FeeEstimatorConfig,
+    /// The number of recent transaction rejection reasons that are retained
+    pub rejection_cache_capacity: usize,
 }
 
 impl Default for MempoolConfig {
@@ -71,6 +136,8 @@ impl Default for MempoolConfig {
             orphan_pool_config: OrphanPoolConfig::default(),
             pending_pool_config: PendingPoolConfig::default(),
             reorg_pool_config: ReorgPoolConfig::default(),
+            fee_estimator_config: FeeEstimatorConfig::default(),
+            rejection_cache_capacity: MEMPOOL_REJECTION_CACHE_CAPACITY,
         }
     }
 }
@@ -109,6 +176,8 @@ where T: BlockchainBackend
     orphan_pool: OrphanPool<T>,
     pending_pool: PendingPool,
     reorg_pool: ReorgPool,
+    fee_estimator: FeeEstimator,
+    rejection_cache: Arc<RwLock<RejectionCache>>,
     validator: Arc<Validator<Transaction, T>>,
 }
 
@@ -123,39 +192,90 @@ where T: BlockchainBackend
             orphan_pool: OrphanPool::new(config.orphan_pool_config, orphan_validator),
             pending_pool: PendingPool::new(config.pending_pool_config),
             reorg_pool: ReorgPool::new(config.reorg_pool_config),
+            fee_estimator: FeeEstimator::new(config.fee_estimator_config),
+            rejection_cache: Arc::new(RwLock::new(RejectionCache::new(config.rejection_cache_capacity))),
             blockchain_db,
             validator: Arc::new(mempool_validator),
         }
     }
 
     /// Insert an unconfirmed transaction into the Mempool. The transaction *MUST* have passed through the validation
-    /// pipeline already and will thus always be internally consistent by this stage
+    /// pipeline already and will thus always be internally consistent by this stage. The reason for rejecting a
+    /// transaction is recorded and can be retrieved using its excess_sig.
     pub fn insert(&self, tx: Arc<Transaction>) -> Result<(), MempoolError> {
+        self.remove_expired_txs()?;
+        let excess_sig = tx.body.kernels()[0].excess_sig.clone();
         // The transaction is already internally consistent
         match self.validator.validate(&tx) {
-            Ok(()) => self.unconfirmed_pool.insert(tx)?,
+            Ok(()) => {
+                if let Err(e) = self.unconfirmed_pool.insert(tx) {
+                    if let Some(reason) = TxRejectionReason::from_unconfirmed_pool_error(&e) {
+                        self.record_rejection(excess_sig, reason)?;
+                    }
+                    return Err(e.into());
+                }
+            },
             Err(ValidationError::UnknownInputs) => self.orphan_pool.insert(tx)?,
-            Err(ValidationError::MaturityError) => self.pending_pool.insert(tx)?,
-            _ => return Err(MempoolError::ValidationError),
+            Err(ValidationError::MaturityError) => {
+                self.pending_pool.insert(tx)?;
+                if !self.pending_pool.has_tx_with_excess_sig(&excess_sig)? {
+                    self.record_rejection(excess_sig, TxRejectionReason::Maturity)?;
+                    return Ok(());
+                }
+            },
+            Err(e) => {
+                self.record_rejection(excess_sig, TxRejectionReason::ValidationFailed(e.to_string()))?;
+                return Err(MempoolError::ValidationError);
+            },
         };
+        self.rejection_cache
+            .write()
+            .map_err(|_| MempoolError::PoisonedAccess)?
+            .remove(&excess_sig);
+        Ok(())
+    }
+
+    // Record the reason why the transaction with the provided excess_sig was rejected or discarded.
+    fn record_rejection(&self, excess_sig: Signature, reason: TxRejectionReason) -> Result<(), MempoolError> {
+        self.rejection_cache
+            .write()
+            .map_err(|_| MempoolError::PoisonedAccess)?
+            .insert(excess_sig, reason);
+        Ok(())
+    }
+
+    // Discard the transactions that have been waiting in the UnconfirmedPool for longer than their Time-to-live.
+    fn remove_expired_txs(&self) -> Result<(), MempoolError> {
+        for tx in self.unconfirmed_pool.remove_expired_txs()? {
+            self.record_rejection(tx.body.kernels()[0].excess_sig.clone(), TxRejectionReason::Expired)?;
+        }
         Ok(())
     }
 
-    /// Insert a set of new transactions into the UTxPool.
+    /// Insert a set of new transactions into the UTxPool. Transactions rejected by the fee, weight or replacement
+    /// policies of the UnconfirmedPool are discarded.
     fn insert_txs(&self, txs: Vec<Arc<Transaction>>) -> Result<(), MempoolError> {
         for tx in txs {
-            self.insert(tx)?;
+            match self.insert(tx) {
+                Err(MempoolError::UnconfirmedPoolError(e)) if e.is_rejection() => {},
+                result => result?,
+            }
         }
         Ok(())
     }
 
     /// Update the Mempool based on the received published block.
     pub fn process_published_block(&self, published_block: &Block) -> Result<(), MempoolError> {
+        // Discard txs that have been waiting in the UnconfirmedPool for longer than their Time-to-live
+        self.remove_expired_txs()?;
+
         // Move published txs to ReOrgPool and discard double spends
-        self.reorg_pool.insert_txs(
-            self.unconfirmed_pool
-                .remove_published_and_discard_double_spends(published_block)?,
-        )?;
+        let published_txs = self
+            .unconfirmed_pool
+            .remove_published_and_discard_double_spends(published_block)?;
+        self.fee_estimator
+            .process_published_block(published_block, &published_txs)?;
+        self.reorg_pool.insert_txs(published_txs)?;
 
         // Move txs with valid input UTXOs and expired time-locks to UnconfirmedPool and discard double spends
         self.unconfirmed_pool.insert_txs(
@@ -218,6 +338,80 @@ where T: BlockchainBackend
         }
     }
 
+    /// Returns the minimum fee-per-gram that a transaction needs to pay to be accepted into the UnconfirmedPool.
+    pub fn min_fee_per_gram(&self) -> Result<MicroTari, MempoolError> {
+        Ok(self.unconfirmed_pool.min_fee_per_gram()?)
+    }
+
+    /// Returns a summary of the transactions stored in the specified pool, or in all pools when no pool is specified.
+    /// The transactions of each pool are ordered from the highest to the lowest fee-per-gram.
+    pub fn list_txs(&self, storage: Option<TxStorageResponse>) -> Result<Vec<TxSummary>, MempoolError> {
+        let mut pools = Vec::with_capacity(4);
+        if storage.map_or(true, |s| s == TxStorageResponse::UnconfirmedPool) {
+            pools.push((TxStorageResponse::UnconfirmedPool, self.unconfirmed_pool.snapshot()?));
+        }
+        if storage.map_or(true, |s| s == TxStorageResponse::OrphanPool) {
+            pools.push((TxStorageResponse::OrphanPool, self.orphan_pool.snapshot()?));
+        }
+        if storage.map_or(true, |s| s == TxStorageResponse::PendingPool) {
+            pools.push((TxStorageResponse::PendingPool, self.pending_pool.snapshot()?));
+        }
+        if storage.map_or(true, |s| s == TxStorageResponse::ReorgPool) {
+            pools.push((TxStorageResponse::ReorgPool, self.reorg_pool.snapshot()?));
+        }
+
+        let mut tx_summaries = Vec::new();
+        for (storage, mut txs) in pools {
+            txs.sort_by(|a, b| {
+                b.calculate_ave_fee_per_gram()
+                    .partial_cmp(&a.calculate_ave_fee_per_gram())
+                    .unwrap_or(Ordering::Equal)
+            });
+            tx_summaries.extend(txs.iter().map(|tx| TxSummary::new(tx, storage)));
+        }
+        Ok(tx_summaries)
+    }
+
+    /// Returns the transaction with the specified excess_sig if it is stored in any of the pools of the Mempool.
+    pub fn fetch_tx_with_excess_sig(&self, excess_sig: &Signature) -> Result<Option<Arc<Transaction>>, MempoolError> {
+        if let Some(tx) = self.unconfirmed_pool.fetch_tx_with_excess_sig(excess_sig)? {
+            return Ok(Some(tx));
+        }
+        if let Some(tx) = self.orphan_pool.fetch_tx_with_excess_sig(excess_sig)? {
+            return Ok(Some(tx));
+        }
+        if let Some(tx) = self.pending_pool.fetch_tx_with_excess_sig(excess_sig)? {
+            return Ok(Some(tx));
+        }
+        Ok(self.reorg_pool.fetch_tx_with_excess_sig(excess_sig)?)
+    }
+
+    /// Returns the reason why the transaction with the specified excess_sig was rejected or discarded, if it was one of
+    /// the most recently rejected transactions.
+    pub fn fetch_tx_rejection_with_excess_sig(
+        &self,
+        excess_sig: &Signature,
+    ) -> Result<Option<TxRejectionReason>, MempoolError>
+    {
+        Ok(self
+            .rejection_cache
+            .read()
+            .map_err(|_| MempoolError::PoisonedAccess)?
+            .get(excess_sig)
+            .cloned())
+    }
+
+    /// Estimate the fee-per-gram that a transaction needs to pay to be included in a block within the specified number
+    /// of blocks.
+    pub fn estimate_fee_per_gram(&self, target_blocks: u64) -> Result<MicroTari, MempoolError> {
+        Ok(self.fee_estimator.estimate_fee_per_gram(
+            target_blocks,
+            &self.unconfirmed_pool.snapshot()?,
+            self.unconfirmed_pool.min_fee_per_gram()?,
+            ConsensusConstants::current().get_max_block_transaction_weight(),
+        )?)
+    }
+
     // Returns the total number of transactions in the Mempool.
     fn len(&self) -> Result<usize, MempoolError> {
         Ok(
@@ -259,6 +453,8 @@ where T: BlockchainBackend
             orphan_pool: self.orphan_pool.clone(),
             pending_pool: self.pending_pool.clone(),
             reorg_pool: self.reorg_pool.clone(),
+            fee_estimator: self.fee_estimator.clone(),
+            rejection_cache: self.rejection_cache.clone(),
             validator: self.validator.clone(),
         }
     }
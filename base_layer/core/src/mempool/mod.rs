@@ -23,16 +23,27 @@
 cfg_if! {
     if #[cfg(feature = "base_node")] {
         mod error;
+        mod fee_estimator;
         mod mempool;
         mod orphan_pool;
         mod pending_pool;
         mod priority;
+        mod rejection_cache;
         mod reorg_pool;
         mod service;
         mod unconfirmed_pool;
         // Public re-exports
         pub use error::MempoolError;
-        pub use mempool::{Mempool, MempoolConfig, MempoolValidators, TxStorageResponse};
+        pub use fee_estimator::{FeeEstimator, FeeEstimatorConfig};
+        pub use mempool::{
+            Mempool,
+            MempoolConfig,
+            MempoolValidators,
+            StatsResponse,
+            TxRejectionReason,
+            TxStorageResponse,
+            TxSummary,
+        };
         pub use service::{
             MempoolServiceConfig,
             MempoolServiceError,
@@ -98,6 +98,19 @@ where T: BlockchainBackend
             .has_tx_with_excess_sig(excess_sig))
     }
 
+    /// Returns the transaction with the provided excess_sig if it is stored in the OrphanPool
+    pub fn fetch_tx_with_excess_sig(
+        &self,
+        excess_sig: &Signature,
+    ) -> Result<Option<Arc<Transaction>>, OrphanPoolError>
+    {
+        Ok(self
+            .pool_storage
+            .read()
+            .map_err(|_| OrphanPoolError::PoisonedAccess)?
+            .fetch_tx_with_excess_sig(excess_sig))
+    }
+
     /// Check if the required UTXOs have been created and if the status of any of the transactions in the OrphanPool has
     /// changed. Remove valid transactions and valid transactions with time-locks from the OrphanPool.
     pub fn scan_for_and_remove_unorphaned_txs(
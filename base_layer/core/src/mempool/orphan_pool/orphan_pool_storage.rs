@@ -73,6 +73,11 @@ where T: BlockchainBackend
         self.txs_by_signature.contains_key(excess_sig)
     }
 
+    /// Returns the transaction with the provided excess_sig if it is stored in the OrphanPoolStorage
+    pub fn fetch_tx_with_excess_sig(&self, excess_sig: &Signature) -> Option<Arc<Transaction>> {
+        self.txs_by_signature.get(excess_sig).cloned()
+    }
+
     /// Check if the required UTXOs have been created and if the status of any of the transactions in the
     /// OrphanPoolStorage has changed. Remove valid transactions and valid transactions with time-locks from the
     /// OrphanPoolStorage.
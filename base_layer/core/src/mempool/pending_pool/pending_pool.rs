@@ -84,6 +84,19 @@ impl PendingPool {
             .has_tx_with_excess_sig(excess_sig))
     }
 
+    /// Returns the transaction with the provided excess_sig if it is stored in the PendingPool
+    pub fn fetch_tx_with_excess_sig(
+        &self,
+        excess_sig: &Signature,
+    ) -> Result<Option<Arc<Transaction>>, PendingPoolError>
+    {
+        Ok(self
+            .pool_storage
+            .read()
+            .map_err(|_| PendingPoolError::PoisonedAccess)?
+            .fetch_tx_with_excess_sig(excess_sig))
+    }
+
     /// Remove transactions with expired time-locks so that they can be move to the UnconfirmedPool. Double spend
     /// transactions are also removed.
     pub fn remove_unlocked_and_discard_double_spends(
@@ -112,6 +112,11 @@ impl PendingPoolStorage {
         self.txs_by_signature.contains_key(excess_sig)
     }
 
+    /// Returns the transaction with the provided excess_sig if it is stored in the PendingPoolStorage
+    pub fn fetch_tx_with_excess_sig(&self, excess_sig: &Signature) -> Option<Arc<Transaction>> {
+        self.txs_by_signature.get(excess_sig).map(|ptx| ptx.transaction.clone())
+    }
+
     /// Remove double-spends from the PendingPoolStorage. These transactions were orphaned by the provided published
     /// block. Check if any of the unspent transactions in the PendingPool has inputs that was spent by the provided
     /// published block.
@@ -21,7 +21,7 @@
 //  USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
 use crate::{mempool::priority::PriorityError, transactions::transaction::Transaction};
-use std::{convert::TryFrom, sync::Arc};
+use std::{convert::TryFrom, sync::Arc, time::Instant};
 use tari_utilities::message_format::MessageFormat;
 
 /// Create a unique unspent transaction priority based on the transaction fee, maturity of the oldest input UTXO and the
@@ -52,11 +52,13 @@ impl Clone for FeePriority {
     }
 }
 
-/// A prioritized transaction includes a transaction and the calculated priority of the transaction.
+/// A prioritized transaction includes a transaction, the calculated priority of the transaction and the time at which
+/// it was prioritized.
 pub struct PrioritizedTransaction {
     pub transaction: Arc<Transaction>,
     pub priority: FeePriority,
     pub weight: u64,
+    pub inserted_at: Instant,
 }
 
 impl TryFrom<Transaction> for PrioritizedTransaction {
@@ -66,6 +68,7 @@ impl TryFrom<Transaction> for PrioritizedTransaction {
         Ok(Self {
             priority: FeePriority::try_from(&transaction)?,
             weight: transaction.calculate_weight(),
+            inserted_at: Instant::now(),
             transaction: Arc::new(transaction),
         })
     }
@@ -20,7 +20,10 @@
 // WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
 // USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
-use super::mempool::mempool_service_request::Request as ProtoMempoolRequest;
+use super::mempool::{
+    mempool_service_request::Request as ProtoMempoolRequest,
+    TxStorageResponse as ProtoTxStorageResponse,
+};
 use crate::mempool::service::MempoolRequest;
 use std::convert::TryInto;
 use tari_utilities::ByteArrayError;
@@ -36,6 +39,21 @@ impl TryInto<MempoolRequest> for ProtoMempoolRequest {
             GetTxStateWithExcessSig(excess_sig) => MempoolRequest::GetTxStateWithExcessSig(
                 excess_sig.try_into().map_err(|err: ByteArrayError| err.to_string())?,
             ),
+            GetFeePerGramEstimate(target_blocks) => MempoolRequest::GetFeePerGramEstimate(target_blocks),
+            GetTxList(storage) => {
+                let storage = ProtoTxStorageResponse::from_i32(storage)
+                    .ok_or("Invalid or unrecognised `TxStorageResponse` enum".to_string())?;
+                MempoolRequest::GetTxList(match storage {
+                    ProtoTxStorageResponse::None => None,
+                    storage => Some(storage.try_into()?),
+                })
+            },
+            GetTxWithExcessSig(excess_sig) => MempoolRequest::GetTxWithExcessSig(
+                excess_sig.try_into().map_err(|err: ByteArrayError| err.to_string())?,
+            ),
+            GetTxRejectionWithExcessSig(excess_sig) => MempoolRequest::GetTxRejectionWithExcessSig(
+                excess_sig.try_into().map_err(|err: ByteArrayError| err.to_string())?,
+            ),
         };
         Ok(request)
     }
@@ -47,6 +65,15 @@ impl From<MempoolRequest> for ProtoMempoolRequest {
         match request {
             GetStats => ProtoMempoolRequest::GetStats(true),
             GetTxStateWithExcessSig(excess_sig) => ProtoMempoolRequest::GetTxStateWithExcessSig(excess_sig.into()),
+            GetFeePerGramEstimate(target_blocks) => ProtoMempoolRequest::GetFeePerGramEstimate(target_blocks),
+            GetTxList(storage) => {
+                let storage = storage.map_or(ProtoTxStorageResponse::None, ProtoTxStorageResponse::from);
+                ProtoMempoolRequest::GetTxList(storage.into())
+            },
+            GetTxWithExcessSig(excess_sig) => ProtoMempoolRequest::GetTxWithExcessSig(excess_sig.into()),
+            GetTxRejectionWithExcessSig(excess_sig) => {
+                ProtoMempoolRequest::GetTxRejectionWithExcessSig(excess_sig.into())
+            },
         }
     }
 }
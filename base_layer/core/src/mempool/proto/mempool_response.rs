@@ -20,8 +20,15 @@
 // WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
 // USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
-use super::mempool::mempool_service_response::Response as ProtoMempoolResponse;
-use crate::mempool::{proto::mempool::TxStorageResponse as ProtoTxStorageResponse, service::MempoolResponse};
+use super::mempool::{
+    mempool_service_response::Response as ProtoMempoolResponse,
+    TxListResponse as ProtoTxListResponse,
+    TxResponse as ProtoTxResponse,
+};
+use crate::{
+    mempool::{proto::mempool::TxStorageResponse as ProtoTxStorageResponse, service::MempoolResponse},
+    transactions::{proto::utils::try_convert_all, tari_amount::MicroTari},
+};
 use std::convert::TryInto;
 
 impl TryInto<MempoolResponse> for ProtoMempoolResponse {
@@ -36,6 +43,10 @@ impl TryInto<MempoolResponse> for ProtoMempoolResponse {
                     .ok_or("Invalid or unrecognised `TxStorageResponse` enum".to_string())?;
                 MempoolResponse::TxStorage(tx_storage_response.try_into()?)
             },
+            FeePerGramEstimate(fee_per_gram) => MempoolResponse::FeePerGramEstimate(MicroTari::from(fee_per_gram)),
+            TxList(tx_list) => MempoolResponse::TxList(try_convert_all(tx_list.txs)?),
+            Tx(tx_response) => MempoolResponse::Tx(tx_response.transaction.map(TryInto::try_into).transpose()?),
+            TxRejection(tx_rejection) => MempoolResponse::TxRejection(tx_rejection.try_into()?),
         };
         Ok(response)
     }
@@ -50,6 +61,14 @@ impl From<MempoolResponse> for ProtoMempoolResponse {
                 let tx_storage_response: ProtoTxStorageResponse = tx_storage_response.into();
                 ProtoMempoolResponse::TxStorage(tx_storage_response.into())
             },
+            FeePerGramEstimate(fee_per_gram) => ProtoMempoolResponse::FeePerGramEstimate(fee_per_gram.into()),
+            TxList(tx_summaries) => ProtoMempoolResponse::TxList(ProtoTxListResponse {
+                txs: tx_summaries.into_iter().map(Into::into).collect(),
+            }),
+            Tx(tx) => ProtoMempoolResponse::Tx(ProtoTxResponse {
+                transaction: tx.map(Into::into),
+            }),
+            TxRejection(reason) => ProtoMempoolResponse::TxRejection(reason.into()),
         }
     }
 }
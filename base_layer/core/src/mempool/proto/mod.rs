@@ -33,6 +33,8 @@ cfg_if! {
         pub mod mempool_request;
         pub mod mempool_response;
         pub mod stats_response;
+        pub mod tx_list_response;
+        pub mod tx_rejection_response;
         pub mod tx_storage_response;
 
         pub use mempool::{MempoolServiceRequest, MempoolServiceResponse};
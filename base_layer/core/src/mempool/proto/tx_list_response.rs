@@ -0,0 +1,65 @@
+// Copyright 2019, The Tari Project
+//
+// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+// following conditions are met:
+//
+// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+// disclaimer.
+//
+// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+// following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+// 3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+// products derived from this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+// INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+// SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+// WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+// USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+use crate::mempool::{
+    mempool::TxSummary,
+    proto::mempool::{TxStorageResponse as ProtoTxStorageResponse, TxSummary as ProtoTxSummary},
+};
+use std::convert::{TryFrom, TryInto};
+use tari_utilities::ByteArrayError;
+
+impl TryFrom<ProtoTxSummary> for TxSummary {
+    type Error = String;
+
+    fn try_from(tx_summary: ProtoTxSummary) -> Result<Self, Self::Error> {
+        let excess_sig = tx_summary
+            .excess_sig
+            .ok_or("Excess signature not provided".to_string())?
+            .try_into()
+            .map_err(|err: ByteArrayError| err.to_string())?;
+        let storage = ProtoTxStorageResponse::from_i32(tx_summary.storage)
+            .ok_or("Invalid or unrecognised `TxStorageResponse` enum".to_string())?
+            .try_into()?;
+        Ok(Self {
+            excess_sig,
+            storage,
+            weight: tx_summary.weight,
+            fee: tx_summary.fee.into(),
+            fee_per_gram: tx_summary.fee_per_gram.into(),
+            min_spendable_height: tx_summary.min_spendable_height,
+        })
+    }
+}
+
+impl From<TxSummary> for ProtoTxSummary {
+    fn from(tx_summary: TxSummary) -> Self {
+        let storage: ProtoTxStorageResponse = tx_summary.storage.into();
+        Self {
+            excess_sig: Some(tx_summary.excess_sig.into()),
+            storage: storage.into(),
+            weight: tx_summary.weight,
+            fee: tx_summary.fee.into(),
+            fee_per_gram: tx_summary.fee_per_gram.into(),
+            min_spendable_height: tx_summary.min_spendable_height,
+        }
+    }
+}
@@ -0,0 +1,64 @@
+// Copyright 2019, The Tari Project
+//
+// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+// following conditions are met:
+//
+// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+// disclaimer.
+//
+// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+// following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+// 3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+// products derived from this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+// INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+// SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+// WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+// USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+use crate::mempool::{
+    mempool::TxRejectionReason,
+    proto::mempool::{TxRejectionReason as ProtoTxRejectionReason, TxRejectionResponse as ProtoTxRejectionResponse},
+};
+use std::convert::TryInto;
+
+impl TryInto<Option<TxRejectionReason>> for ProtoTxRejectionResponse {
+    type Error = String;
+
+    fn try_into(self) -> Result<Option<TxRejectionReason>, Self::Error> {
+        use ProtoTxRejectionReason::*;
+        let reason = ProtoTxRejectionReason::from_i32(self.reason)
+            .ok_or("Invalid or unrecognised `TxRejectionReason` enum".to_string())?;
+        Ok(match reason {
+            None => Option::None,
+            ValidationFailed => Some(TxRejectionReason::ValidationFailed(self.message)),
+            DoubleSpend => Some(TxRejectionReason::DoubleSpend),
+            LowFee => Some(TxRejectionReason::LowFee),
+            ExceedsMaxWeight => Some(TxRejectionReason::ExceedsMaxWeight),
+            Maturity => Some(TxRejectionReason::Maturity),
+            Expired => Some(TxRejectionReason::Expired),
+        })
+    }
+}
+
+impl From<Option<TxRejectionReason>> for ProtoTxRejectionResponse {
+    fn from(reason: Option<TxRejectionReason>) -> Self {
+        let (reason, message) = match reason {
+            Option::None => (ProtoTxRejectionReason::None, String::new()),
+            Some(TxRejectionReason::ValidationFailed(message)) => (ProtoTxRejectionReason::ValidationFailed, message),
+            Some(TxRejectionReason::DoubleSpend) => (ProtoTxRejectionReason::DoubleSpend, String::new()),
+            Some(TxRejectionReason::LowFee) => (ProtoTxRejectionReason::LowFee, String::new()),
+            Some(TxRejectionReason::ExceedsMaxWeight) => (ProtoTxRejectionReason::ExceedsMaxWeight, String::new()),
+            Some(TxRejectionReason::Maturity) => (ProtoTxRejectionReason::Maturity, String::new()),
+            Some(TxRejectionReason::Expired) => (ProtoTxRejectionReason::Expired, String::new()),
+        };
+        Self {
+            reason: reason.into(),
+            message,
+        }
+    }
+}
@@ -0,0 +1,108 @@
+// Copyright 2019. The Tari Project
+//
+// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+// following conditions are met:
+//
+// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+// disclaimer.
+//
+// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+// following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+// 3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+// products derived from this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+// INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+// SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+// WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+// USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+use crate::{mempool::mempool::TxRejectionReason, transactions::types::Signature};
+use std::collections::{HashMap, VecDeque};
+
+/// The RejectionCache records the reasons why the most recently rejected or discarded transactions were not kept in the
+/// Mempool, keyed by the excess_sig of the transaction. Only the last `capacity` rejections are retained.
+pub struct RejectionCache {
+    capacity: usize,
+    reasons_by_signature: HashMap<Signature, TxRejectionReason>,
+    insertion_order: VecDeque<Signature>,
+}
+
+impl RejectionCache {
+    /// Create a new RejectionCache that retains up to `capacity` rejection reasons
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            reasons_by_signature: HashMap::new(),
+            insertion_order: VecDeque::with_capacity(capacity),
+        }
+    }
+
+    /// Record the rejection reason of a transaction, discarding the oldest rejection when the capacity is reached.
+    pub fn insert(&mut self, excess_sig: Signature, reason: TxRejectionReason) {
+        if self.capacity == 0 {
+            return;
+        }
+        if self.reasons_by_signature.insert(excess_sig.clone(), reason).is_some() {
+            self.insertion_order.retain(|sig| *sig != excess_sig);
+        }
+        self.insertion_order.push_back(excess_sig);
+        while self.insertion_order.len() > self.capacity {
+            if let Some(oldest_sig) = self.insertion_order.pop_front() {
+                self.reasons_by_signature.remove(&oldest_sig);
+            }
+        }
+    }
+
+    /// Returns the recorded rejection reason of the transaction with the provided excess_sig
+    pub fn get(&self, excess_sig: &Signature) -> Option<&TxRejectionReason> {
+        self.reasons_by_signature.get(excess_sig)
+    }
+
+    /// Remove the rejection reason of a transaction, used when the transaction has since been accepted
+    pub fn remove(&mut self, excess_sig: &Signature) {
+        if self.reasons_by_signature.remove(excess_sig).is_some() {
+            self.insertion_order.retain(|sig| sig != excess_sig);
+        }
+    }
+
+    /// Returns the number of recorded rejections
+    pub fn len(&self) -> usize {
+        self.reasons_by_signature.len()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{transactions::tari_amount::MicroTari, tx};
+
+    #[test]
+    fn test_insert_and_discard_oldest() {
+        let tx1 = tx!(MicroTari(5_000), fee: MicroTari(50), inputs: 2, outputs: 1).0;
+        let tx2 = tx!(MicroTari(5_000), fee: MicroTari(20), inputs: 2, outputs: 1).0;
+        let tx3 = tx!(MicroTari(5_000), fee: MicroTari(30), inputs: 2, outputs: 1).0;
+        let sig1 = tx1.body.kernels()[0].excess_sig.clone();
+        let sig2 = tx2.body.kernels()[0].excess_sig.clone();
+        let sig3 = tx3.body.kernels()[0].excess_sig.clone();
+
+        let mut rejection_cache = RejectionCache::new(2);
+        rejection_cache.insert(sig1.clone(), TxRejectionReason::LowFee);
+        rejection_cache.insert(sig2.clone(), TxRejectionReason::Expired);
+        // Updating an existing rejection makes it the most recent one
+        rejection_cache.insert(sig1.clone(), TxRejectionReason::DoubleSpend);
+        rejection_cache.insert(sig3.clone(), TxRejectionReason::Maturity);
+
+        assert_eq!(rejection_cache.len(), 2);
+        assert_eq!(rejection_cache.get(&sig1), Some(&TxRejectionReason::DoubleSpend));
+        assert_eq!(rejection_cache.get(&sig2), None);
+        assert_eq!(rejection_cache.get(&sig3), Some(&TxRejectionReason::Maturity));
+
+        rejection_cache.remove(&sig1);
+        assert_eq!(rejection_cache.get(&sig1), None);
+        assert_eq!(rejection_cache.len(), 1);
+    }
+}
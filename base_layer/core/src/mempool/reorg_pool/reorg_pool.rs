@@ -85,6 +85,15 @@ impl ReorgPool {
             .has_tx_with_excess_sig(excess_sig))
     }
 
+    /// Returns the transaction with the provided excess_sig if it is stored in the ReorgPool
+    pub fn fetch_tx_with_excess_sig(&self, excess_sig: &Signature) -> Result<Option<Arc<Transaction>>, ReorgPoolError> {
+        Ok(self
+            .pool_storage
+            .read()
+            .map_err(|_| ReorgPoolError::PoisonedAccess)?
+            .fetch_tx_with_excess_sig(excess_sig))
+    }
+
     /// Remove the transactions from the ReorgPool that were used in provided removed blocks. The transactions can be
     /// resubmitted to the Unconfirmed Pool.
     pub fn scan_for_and_remove_reorged_txs(
@@ -108,6 +117,15 @@ impl ReorgPool {
             .len())
     }
 
+    /// Returns all transaction stored in the ReorgPool.
+    pub fn snapshot(&self) -> Result<Vec<Arc<Transaction>>, ReorgPoolError> {
+        Ok(self
+            .pool_storage
+            .write()
+            .map_err(|_| ReorgPoolError::PoisonedAccess)?
+            .snapshot())
+    }
+
     /// Returns the total weight of all transactions stored in the pool.
     pub fn calculate_weight(&self) -> Result<u64, ReorgPoolError> {
         Ok(self
@@ -67,6 +67,11 @@ impl ReorgPoolStorage {
         self.txs_by_signature.contains_key(excess_sig)
     }
 
+    /// Returns the transaction with the provided excess_sig if it is stored in the ReorgPoolStorage
+    pub fn fetch_tx_with_excess_sig(&self, excess_sig: &Signature) -> Option<Arc<Transaction>> {
+        self.txs_by_signature.get(excess_sig).cloned()
+    }
+
     /// Remove the transactions from the ReorgPoolStorage that were used in provided removed blocks. The transactions
     /// can be resubmitted to the Unconfirmed Pool.
     pub fn scan_for_and_remove_reorged_txs(&mut self, removed_blocks: Vec<Block>) -> Vec<Arc<Transaction>> {
@@ -88,6 +93,13 @@ impl ReorgPoolStorage {
         (count)
     }
 
+    /// Returns all transaction stored in the ReorgPoolStorage.
+    pub fn snapshot(&mut self) -> Vec<Arc<Transaction>> {
+        let mut txs: Vec<Arc<Transaction>> = Vec::new();
+        self.txs_by_signature.iter().for_each(|(_, tx)| txs.push(tx.clone()));
+        txs
+    }
+
     /// Returns the total weight of all transactions stored in the pool.
     pub fn calculate_weight(&mut self) -> u64 {
         let mut weight: u64 = 0;
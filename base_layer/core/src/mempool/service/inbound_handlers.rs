@@ -56,6 +56,18 @@ where T: BlockchainBackend
             MempoolRequest::GetTxStateWithExcessSig(excess_sig) => Ok(MempoolResponse::TxStorage(
                 self.mempool.has_tx_with_excess_sig(excess_sig)?,
             )),
+            MempoolRequest::GetFeePerGramEstimate(target_blocks) => Ok(MempoolResponse::FeePerGramEstimate(
+                self.mempool.estimate_fee_per_gram(*target_blocks)?,
+            )),
+            MempoolRequest::GetTxList(storage) => Ok(MempoolResponse::TxList(self.mempool.list_txs(*storage)?)),
+            MempoolRequest::GetTxWithExcessSig(excess_sig) => Ok(MempoolResponse::Tx(
+                self.mempool
+                    .fetch_tx_with_excess_sig(excess_sig)?
+                    .map(|tx| (*tx).clone()),
+            )),
+            MempoolRequest::GetTxRejectionWithExcessSig(excess_sig) => Ok(MempoolResponse::TxRejection(
+                self.mempool.fetch_tx_rejection_with_excess_sig(excess_sig)?,
+            )),
         }
     }
 
@@ -22,10 +22,10 @@
 
 use crate::{
     mempool::{
-        mempool::{StatsResponse, TxStorageResponse},
+        mempool::{StatsResponse, TxRejectionReason, TxStorageResponse, TxSummary},
         service::{MempoolRequest, MempoolResponse, MempoolServiceError},
     },
-    transactions::{transaction::Transaction, types::Signature},
+    transactions::{tari_amount::MicroTari, transaction::Transaction, types::Signature},
 };
 use futures::channel::mpsc::UnboundedSender;
 use tari_comms::types::CommsPublicKey;
@@ -90,4 +90,69 @@ impl OutboundMempoolServiceInterface {
             Err(MempoolServiceError::UnexpectedApiResponse)
         }
     }
+
+    /// Request an estimate of the fee-per-gram required for a transaction to be included within the specified number
+    /// of blocks from the mempool of a remote base node.
+    pub async fn get_fee_per_gram_estimate(&mut self, target_blocks: u64) -> Result<MicroTari, MempoolServiceError> {
+        if let MempoolResponse::FeePerGramEstimate(fee_per_gram) = self
+            .request_sender
+            .call(MempoolRequest::GetFeePerGramEstimate(target_blocks))
+            .await??
+        {
+            Ok(fee_per_gram)
+        } else {
+            Err(MempoolServiceError::UnexpectedApiResponse)
+        }
+    }
+
+    /// Request a summary of the transactions stored in the specified pool, or in all pools when no pool is specified,
+    /// from the mempool of a remote base node.
+    pub async fn get_tx_list(
+        &mut self,
+        storage: Option<TxStorageResponse>,
+    ) -> Result<Vec<TxSummary>, MempoolServiceError>
+    {
+        if let MempoolResponse::TxList(tx_summaries) =
+            self.request_sender.call(MempoolRequest::GetTxList(storage)).await??
+        {
+            Ok(tx_summaries)
+        } else {
+            Err(MempoolServiceError::UnexpectedApiResponse)
+        }
+    }
+
+    /// Fetch the transaction with the specified excess_sig from the mempool of a remote base node.
+    pub async fn get_tx_with_excess_sig(
+        &mut self,
+        excess_sig: Signature,
+    ) -> Result<Option<Transaction>, MempoolServiceError>
+    {
+        if let MempoolResponse::Tx(tx) = self
+            .request_sender
+            .call(MempoolRequest::GetTxWithExcessSig(excess_sig))
+            .await??
+        {
+            Ok(tx)
+        } else {
+            Err(MempoolServiceError::UnexpectedApiResponse)
+        }
+    }
+
+    /// Request the reason why the transaction with the specified excess_sig was rejected by the mempool of a remote
+    /// base node.
+    pub async fn get_tx_rejection_with_excess_sig(
+        &mut self,
+        excess_sig: Signature,
+    ) -> Result<Option<TxRejectionReason>, MempoolServiceError>
+    {
+        if let MempoolResponse::TxRejection(reason) = self
+            .request_sender
+            .call(MempoolRequest::GetTxRejectionWithExcessSig(excess_sig))
+            .await??
+        {
+            Ok(reason)
+        } else {
+            Err(MempoolServiceError::UnexpectedApiResponse)
+        }
+    }
 }
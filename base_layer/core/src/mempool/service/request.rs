@@ -20,7 +20,7 @@
 //  WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
 //  USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
-use crate::transactions::types::Signature;
+use crate::{mempool::mempool::TxStorageResponse, transactions::types::Signature};
 use rand::RngCore;
 use serde::{Deserialize, Serialize};
 
@@ -37,6 +37,10 @@ where R: RngCore {
 pub enum MempoolRequest {
     GetStats,
     GetTxStateWithExcessSig(Signature),
+    GetFeePerGramEstimate(u64),
+    GetTxList(Option<TxStorageResponse>),
+    GetTxWithExcessSig(Signature),
+    GetTxRejectionWithExcessSig(Signature),
 }
 
 /// Request type for a received MempoolService request.
@@ -20,9 +20,12 @@
 // WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
 // USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
-use crate::mempool::{
-    mempool::{StatsResponse, TxStorageResponse},
-    service::RequestKey,
+use crate::{
+    mempool::{
+        mempool::{StatsResponse, TxRejectionReason, TxStorageResponse, TxSummary},
+        service::RequestKey,
+    },
+    transactions::{tari_amount::MicroTari, transaction::Transaction},
 };
 use serde::{Deserialize, Serialize};
 
@@ -31,6 +34,10 @@ use serde::{Deserialize, Serialize};
 pub enum MempoolResponse {
     Stats(StatsResponse),
     TxStorage(TxStorageResponse),
+    FeePerGramEstimate(MicroTari),
+    TxList(Vec<TxSummary>),
+    Tx(Option<Transaction>),
+    TxRejection(Option<TxRejectionReason>),
 }
 
 /// Response type for a received MempoolService requests
@@ -30,4 +30,23 @@ pub enum UnconfirmedPoolError {
     /// The Thread Safety has been breached and the data access has become poisoned
     PoisonedAccess,
     PriorityError(PriorityError),
+    /// The transaction does not pay the minimum fee-per-gram currently required by the pool
+    LowFee,
+    /// The transaction is heavier than the maximum weight of the pool
+    ExceedsMaxWeight,
+    /// The transaction spends the inputs of pooled transactions without paying a higher fee and fee-per-gram
+    InsufficientReplacementFee,
+}
+
+impl UnconfirmedPoolError {
+    /// Returns true if the error indicates that a transaction was rejected by the fee, weight or replacement policies
+    /// of the pool, rather than a failure of the pool itself.
+    pub fn is_rejection(&self) -> bool {
+        match self {
+            UnconfirmedPoolError::LowFee |
+            UnconfirmedPoolError::ExceedsMaxWeight |
+            UnconfirmedPoolError::InsufficientReplacementFee => true,
+            _ => false,
+        }
+    }
 }
@@ -22,11 +22,21 @@
 
 use crate::{
     blocks::Block,
-    consts::{MEMPOOL_UNCONFIRMED_POOL_STORAGE_CAPACITY, MEMPOOL_UNCONFIRMED_POOL_WEIGHT_TRANSACTION_SKIP_COUNT},
+    consts::{
+        MEMPOOL_UNCONFIRMED_POOL_CACHE_TTL,
+        MEMPOOL_UNCONFIRMED_POOL_MAX_WEIGHT,
+        MEMPOOL_UNCONFIRMED_POOL_MIN_FEE_PER_GRAM,
+        MEMPOOL_UNCONFIRMED_POOL_MIN_REPLACEMENT_FEE_PER_GRAM,
+        MEMPOOL_UNCONFIRMED_POOL_STORAGE_CAPACITY,
+        MEMPOOL_UNCONFIRMED_POOL_WEIGHT_TRANSACTION_SKIP_COUNT,
+    },
     mempool::unconfirmed_pool::{UnconfirmedPoolError, UnconfirmedPoolStorage},
-    transactions::{transaction::Transaction, types::Signature},
+    transactions::{tari_amount::MicroTari, transaction::Transaction, types::Signature},
+};
+use std::{
+    sync::{Arc, RwLock},
+    time::Duration,
 };
-use std::sync::{Arc, RwLock};
 
 /// Configuration for the UnconfirmedPool
 #[derive(Clone, Copy)]
@@ -36,6 +46,15 @@ pub struct UnconfirmedPoolConfig {
     /// The maximum number of transactions that can be skipped when compiling a set of highest priority transactions,
     /// skipping over large transactions are performed in an attempt to fit more transactions into the remaining space.
     pub weight_tx_skip_count: usize,
+    /// The maximum total weight of all transactions that can be stored in the Unconfirmed Transaction pool
+    pub max_weight: u64,
+    /// The minimum fee-per-gram that transactions must pay to be accepted while the pool is not full
+    pub min_fee_per_gram: MicroTari,
+    /// The fee-per-gram of the replaced weight that a replacement transaction must pay on top of the fees of the
+    /// transactions it replaces
+    pub min_replacement_fee_per_gram: MicroTari,
+    /// The Time-to-live for each stored transaction
+    pub tx_ttl: Duration,
 }
 
 impl Default for UnconfirmedPoolConfig {
@@ -43,6 +62,10 @@ impl Default for UnconfirmedPoolConfig {
         Self {
             storage_capacity: MEMPOOL_UNCONFIRMED_POOL_STORAGE_CAPACITY,
             weight_tx_skip_count: MEMPOOL_UNCONFIRMED_POOL_WEIGHT_TRANSACTION_SKIP_COUNT,
+            max_weight: MEMPOOL_UNCONFIRMED_POOL_MAX_WEIGHT,
+            min_fee_per_gram: MEMPOOL_UNCONFIRMED_POOL_MIN_FEE_PER_GRAM,
+            min_replacement_fee_per_gram: MEMPOOL_UNCONFIRMED_POOL_MIN_REPLACEMENT_FEE_PER_GRAM,
+            tx_ttl: MEMPOOL_UNCONFIRMED_POOL_CACHE_TTL,
         }
     }
 }
@@ -62,8 +85,11 @@ impl UnconfirmedPool {
     }
 
     /// Insert a new transaction into the UnconfirmedPool. Low priority transactions will be removed to make space for
-    /// higher priority transactions. The lowest priority transactions will be removed when the maximum capacity is
-    /// reached and the new transaction has a higher priority than the currently stored lowest priority transaction.
+    /// higher priority transactions. The lowest priority transactions will be removed when the maximum capacity or
+    /// weight is reached and the new transaction has a higher priority than the currently stored lowest priority
+    /// transaction. A transaction spending the same inputs as stored transactions will replace them if it pays a higher
+    /// fee-per-gram and a higher fee than all of them combined, by at least the minimum replacement fee-per-gram of
+    /// the replaced weight.
     pub fn insert(&self, transaction: Arc<Transaction>) -> Result<(), UnconfirmedPoolError> {
         self.pool_storage
             .write()
@@ -88,6 +114,19 @@ impl UnconfirmedPool {
             .has_tx_with_excess_sig(excess_sig))
     }
 
+    /// Returns the transaction with the provided excess_sig if it is stored in the UnconfirmedPool
+    pub fn fetch_tx_with_excess_sig(
+        &self,
+        excess_sig: &Signature,
+    ) -> Result<Option<Arc<Transaction>>, UnconfirmedPoolError>
+    {
+        Ok(self
+            .pool_storage
+            .read()
+            .map_err(|_| UnconfirmedPoolError::PoisonedAccess)?
+            .fetch_tx_with_excess_sig(excess_sig))
+    }
+
     /// Returns a set of the highest priority unconfirmed transactions, that can be included in a block
     pub fn highest_priority_txs(&self, total_weight: u64) -> Result<Vec<Arc<Transaction>>, UnconfirmedPoolError> {
         self.pool_storage
@@ -110,6 +149,25 @@ impl UnconfirmedPool {
             .remove_published_and_discard_double_spends(published_block))
     }
 
+    /// Remove all transactions that have exceeded their Time-to-live from the UnconfirmedPool. Returns the expired
+    /// transactions.
+    pub fn remove_expired_txs(&self) -> Result<Vec<Arc<Transaction>>, UnconfirmedPoolError> {
+        Ok(self
+            .pool_storage
+            .write()
+            .map_err(|_| UnconfirmedPoolError::PoisonedAccess)?
+            .remove_expired_txs())
+    }
+
+    /// Returns the minimum fee-per-gram that a new transaction must pay to be accepted into the UnconfirmedPool.
+    pub fn min_fee_per_gram(&self) -> Result<MicroTari, UnconfirmedPoolError> {
+        Ok(self
+            .pool_storage
+            .read()
+            .map_err(|_| UnconfirmedPoolError::PoisonedAccess)?
+            .min_fee_per_gram())
+    }
+
     /// Returns the total number of unconfirmed transactions stored in the UnconfirmedPool
     pub fn len(&self) -> Result<usize, UnconfirmedPoolError> {
         Ok(self
@@ -159,7 +217,8 @@ impl Clone for UnconfirmedPool {
 #[cfg(test)]
 mod test {
     use super::*;
-    use crate::{helpers::create_orphan_block, transactions::tari_amount::MicroTari, tx};
+    use crate::{helpers::create_orphan_block, tx};
+    use std::thread;
 
     #[test]
     fn test_insert_and_retrieve_highest_priority_txs() {
@@ -172,6 +231,7 @@ mod test {
         let unconfirmed_pool = UnconfirmedPool::new(UnconfirmedPoolConfig {
             storage_capacity: 4,
             weight_tx_skip_count: 3,
+            ..UnconfirmedPoolConfig::default()
         });
         unconfirmed_pool
             .insert_txs(vec![tx1.clone(), tx2.clone(), tx3.clone(), tx4.clone(), tx5.clone()])
@@ -232,6 +292,7 @@ mod test {
         let unconfirmed_pool = UnconfirmedPool::new(UnconfirmedPoolConfig {
             storage_capacity: 10,
             weight_tx_skip_count: 3,
+            ..UnconfirmedPoolConfig::default()
         });
         unconfirmed_pool
             .insert_txs(vec![tx1.clone(), tx2.clone(), tx3.clone(), tx4.clone(), tx5.clone()])
@@ -307,6 +368,7 @@ mod test {
         let unconfirmed_pool = UnconfirmedPool::new(UnconfirmedPoolConfig {
             storage_capacity: 10,
             weight_tx_skip_count: 3,
+            ..UnconfirmedPoolConfig::default()
         });
         unconfirmed_pool
             .insert_txs(vec![
@@ -365,4 +427,159 @@ mod test {
 
         assert!(unconfirmed_pool.check_status().unwrap());
     }
+
+    #[test]
+    fn test_weight_based_eviction() {
+        let tx1 = Arc::new(tx!(MicroTari(5_000), fee: MicroTari(50), inputs: 2, outputs: 1).0);
+        let tx2 = Arc::new(tx!(MicroTari(5_000), fee: MicroTari(20), inputs: 4, outputs: 1).0);
+        let tx3 = Arc::new(tx!(MicroTari(5_000), fee: MicroTari(100), inputs: 5, outputs: 1).0);
+        let tx4 = Arc::new(tx!(MicroTari(5_000), fee: MicroTari(10), inputs: 3, outputs: 1).0);
+
+        let unconfirmed_pool = UnconfirmedPool::new(UnconfirmedPoolConfig {
+            storage_capacity: 10,
+            weight_tx_skip_count: 3,
+            max_weight: tx1.calculate_weight() + tx3.calculate_weight(),
+            ..UnconfirmedPoolConfig::default()
+        });
+        unconfirmed_pool.insert_txs(vec![tx1.clone(), tx2.clone()]).unwrap();
+        assert_eq!(unconfirmed_pool.len().unwrap(), 2);
+
+        // tx3 has the highest priority and evicts the lowest priority tx to fit into the weight budget
+        unconfirmed_pool.insert(tx3.clone()).unwrap();
+        assert!(unconfirmed_pool
+            .has_tx_with_excess_sig(&tx1.body.kernels()[0].excess_sig)
+            .unwrap());
+        assert!(!unconfirmed_pool
+            .has_tx_with_excess_sig(&tx2.body.kernels()[0].excess_sig)
+            .unwrap());
+        assert!(unconfirmed_pool
+            .has_tx_with_excess_sig(&tx3.body.kernels()[0].excess_sig)
+            .unwrap());
+        assert_eq!(
+            unconfirmed_pool.calculate_weight().unwrap(),
+            tx1.calculate_weight() + tx3.calculate_weight()
+        );
+
+        // tx4 has a lower priority than all stored txs and is rejected
+        assert!(unconfirmed_pool.insert(tx4.clone()).is_err());
+        assert!(!unconfirmed_pool
+            .has_tx_with_excess_sig(&tx4.body.kernels()[0].excess_sig)
+            .unwrap());
+
+        assert!(unconfirmed_pool.check_status().unwrap());
+    }
+
+    #[test]
+    fn test_dynamic_min_fee_per_gram() {
+        let tx1 = Arc::new(tx!(MicroTari(5_000), fee: MicroTari(50), inputs: 2, outputs: 1).0);
+        let tx2 = Arc::new(tx!(MicroTari(5_000), fee: MicroTari(100), inputs: 2, outputs: 1).0);
+        let tx3 = Arc::new(tx!(MicroTari(5_000), fee: MicroTari(20), inputs: 2, outputs: 1).0);
+
+        let unconfirmed_pool = UnconfirmedPool::new(UnconfirmedPoolConfig {
+            storage_capacity: 2,
+            weight_tx_skip_count: 3,
+            min_fee_per_gram: MicroTari(4),
+            ..UnconfirmedPoolConfig::default()
+        });
+        assert_eq!(unconfirmed_pool.min_fee_per_gram().unwrap(), MicroTari(4));
+        unconfirmed_pool.insert_txs(vec![tx1.clone(), tx2.clone()]).unwrap();
+
+        // The pool is full, the minimum fee-per-gram rises to that of the lowest priority transaction
+        let lowest_fee_per_gram = MicroTari::from(tx1.calculate_ave_fee_per_gram() as u64);
+        assert_eq!(unconfirmed_pool.min_fee_per_gram().unwrap(), lowest_fee_per_gram);
+        match unconfirmed_pool.insert(tx3.clone()) {
+            Err(UnconfirmedPoolError::LowFee) => {},
+            _ => panic!("Expected a LowFee error"),
+        }
+
+        assert!(unconfirmed_pool.check_status().unwrap());
+    }
+
+    #[test]
+    fn test_remove_expired_txs() {
+        let tx1 = Arc::new(tx!(MicroTari(5_000), fee: MicroTari(50), inputs: 2, outputs: 1).0);
+        let tx2 = Arc::new(tx!(MicroTari(5_000), fee: MicroTari(20), inputs: 3, outputs: 1).0);
+
+        let unconfirmed_pool = UnconfirmedPool::new(UnconfirmedPoolConfig {
+            storage_capacity: 10,
+            weight_tx_skip_count: 3,
+            tx_ttl: Duration::from_millis(50),
+            ..UnconfirmedPoolConfig::default()
+        });
+        unconfirmed_pool.insert(tx1.clone()).unwrap();
+        thread::sleep(Duration::from_millis(60));
+        // Expired transactions are discarded when new transactions are inserted
+        unconfirmed_pool.insert(tx2.clone()).unwrap();
+        assert!(!unconfirmed_pool
+            .has_tx_with_excess_sig(&tx1.body.kernels()[0].excess_sig)
+            .unwrap());
+        assert!(unconfirmed_pool
+            .has_tx_with_excess_sig(&tx2.body.kernels()[0].excess_sig)
+            .unwrap());
+
+        thread::sleep(Duration::from_millis(60));
+        let expired_txs = unconfirmed_pool.remove_expired_txs().unwrap();
+        assert_eq!(expired_txs.len(), 1);
+        assert!(expired_txs.contains(&tx2));
+        assert_eq!(unconfirmed_pool.len().unwrap(), 0);
+
+        assert!(unconfirmed_pool.check_status().unwrap());
+    }
+
+    #[test]
+    fn test_replace_by_fee() {
+        let tx1 = Arc::new(tx!(MicroTari(5_000), fee: MicroTari(50), inputs: 2, outputs: 1).0);
+        let mut tx2 = tx!(MicroTari(5_000), fee: MicroTari(40), inputs: 2, outputs: 1).0;
+        let mut tx3 = tx!(MicroTari(5_000), fee: MicroTari(80), inputs: 2, outputs: 1).0;
+        // tx2 and tx3 both spend an input of tx1
+        tx2.body.inputs_mut()[0] = tx1.body.inputs()[0].clone();
+        tx3.body.inputs_mut()[1] = tx1.body.inputs()[1].clone();
+        let tx2 = Arc::new(tx2);
+        let tx3 = Arc::new(tx3);
+
+        let unconfirmed_pool = UnconfirmedPool::new(UnconfirmedPoolConfig {
+            storage_capacity: 10,
+            weight_tx_skip_count: 3,
+            min_replacement_fee_per_gram: MicroTari(2),
+            ..UnconfirmedPoolConfig::default()
+        });
+        unconfirmed_pool.insert(tx1.clone()).unwrap();
+
+        // A lower fee replacement is rejected
+        match unconfirmed_pool.insert(tx2.clone()) {
+            Err(UnconfirmedPoolError::InsufficientReplacementFee) => {},
+            _ => panic!("Expected an InsufficientReplacementFee error"),
+        }
+        // A heavier replacement that pays a higher total fee but a lower fee-per-gram is rejected
+        let mut tx4 = tx!(MicroTari(5_000), fee: MicroTari(45), inputs: 4, outputs: 3).0;
+        tx4.body.inputs_mut()[0] = tx1.body.inputs()[0].clone();
+        let tx4 = Arc::new(tx4);
+        assert!(tx4.body.get_total_fee() > tx1.body.get_total_fee());
+        match unconfirmed_pool.insert(tx4.clone()) {
+            Err(UnconfirmedPoolError::InsufficientReplacementFee) => {},
+            _ => panic!("Expected an InsufficientReplacementFee error"),
+        }
+        // A replacement with a higher fee-per-gram that does not pay enough for the replaced weight is rejected
+        let mut tx5 = tx!(MicroTari(5_000), fee: MicroTari(51), inputs: 2, outputs: 1).0;
+        tx5.body.inputs_mut()[0] = tx1.body.inputs()[0].clone();
+        let tx5 = Arc::new(tx5);
+        assert!(tx5.calculate_ave_fee_per_gram() > tx1.calculate_ave_fee_per_gram());
+        match unconfirmed_pool.insert(tx5.clone()) {
+            Err(UnconfirmedPoolError::InsufficientReplacementFee) => {},
+            _ => panic!("Expected an InsufficientReplacementFee error"),
+        }
+        // A replacement with a higher fee-per-gram and a sufficiently higher fee evicts the original transaction
+        unconfirmed_pool.insert(tx3.clone()).unwrap();
+        assert!(!unconfirmed_pool
+            .has_tx_with_excess_sig(&tx1.body.kernels()[0].excess_sig)
+            .unwrap());
+        assert!(!unconfirmed_pool
+            .has_tx_with_excess_sig(&tx2.body.kernels()[0].excess_sig)
+            .unwrap());
+        assert!(unconfirmed_pool
+            .has_tx_with_excess_sig(&tx3.body.kernels()[0].excess_sig)
+            .unwrap());
+
+        assert!(unconfirmed_pool.check_status().unwrap());
+    }
 }
@@ -26,7 +26,7 @@ use crate::{
         priority::{FeePriority, PrioritizedTransaction},
         unconfirmed_pool::{UnconfirmedPoolConfig, UnconfirmedPoolError},
     },
-    transactions::{transaction::Transaction, types::Signature},
+    transactions::{tari_amount::MicroTari, transaction::Transaction, types::Signature},
 };
 use std::{
     collections::{BTreeMap, HashMap},
@@ -45,6 +45,7 @@ pub struct UnconfirmedPoolStorage {
     config: UnconfirmedPoolConfig,
     txs_by_signature: HashMap<Signature, PrioritizedTransaction>,
     txs_by_priority: BTreeMap<FeePriority, Signature>,
+    total_weight: u64,
 }
 
 impl UnconfirmedPoolStorage {
@@ -54,46 +55,162 @@ impl UnconfirmedPoolStorage {
             config,
             txs_by_signature: HashMap::new(),
             txs_by_priority: BTreeMap::new(),
+            total_weight: 0,
         }
     }
 
-    fn lowest_priority(&self) -> &FeePriority {
-        self.txs_by_priority.iter().next().unwrap().0
+    fn is_full(&self) -> bool {
+        self.txs_by_signature.len() >= self.config.storage_capacity || self.total_weight >= self.config.max_weight
     }
 
-    fn remove_lowest_priority_tx(&mut self) {
-        if let Some((priority, sig)) = self.txs_by_priority.iter().next().map(|(p, s)| (p.clone(), s.clone())) {
-            self.txs_by_signature.remove(&sig);
-            self.txs_by_priority.remove(&priority);
+    // Remove the transaction with the provided excess_sig from both containers, keeping the total weight up to date.
+    fn remove_tx(&mut self, tx_key: &Signature) -> Option<PrioritizedTransaction> {
+        let ptx = self.txs_by_signature.remove(tx_key)?;
+        self.txs_by_priority.remove(&ptx.priority);
+        self.total_weight -= ptx.weight;
+        Some(ptx)
+    }
+
+    // Find the transactions in the pool that spend at least one of the inputs of the provided transaction.
+    fn find_conflicting_txs(&self, tx: &Transaction) -> Vec<Signature> {
+        self.txs_by_signature
+            .iter()
+            .filter(|(_, ptx)| {
+                ptx.transaction
+                    .body
+                    .inputs()
+                    .iter()
+                    .any(|input| tx.body.inputs().contains(input))
+            })
+            .map(|(tx_key, _)| tx_key.clone())
+            .collect()
+    }
+
+    // Determine which of the lowest priority transactions need to be evicted to make space for the provided
+    // transaction, taking into account the space that will be freed by the replaced transactions. A transaction will
+    // never evict a transaction with a higher priority than its own.
+    fn find_evictions(
+        &self,
+        prioritized_tx: &PrioritizedTransaction,
+        replaced_tx_keys: &[Signature],
+    ) -> Result<Vec<Signature>, UnconfirmedPoolError>
+    {
+        let mut count = self.txs_by_signature.len() - replaced_tx_keys.len() + 1;
+        let mut weight = self.total_weight + prioritized_tx.weight;
+        for tx_key in replaced_tx_keys {
+            weight -= self
+                .txs_by_signature
+                .get(tx_key)
+                .ok_or(UnconfirmedPoolError::StorageOutofSync)?
+                .weight;
+        }
+
+        let mut evicted_tx_keys: Vec<Signature> = Vec::new();
+        for (priority, tx_key) in self.txs_by_priority.iter() {
+            if count <= self.config.storage_capacity && weight <= self.config.max_weight {
+                break;
+            }
+            if replaced_tx_keys.contains(tx_key) {
+                continue;
+            }
+            if *priority > prioritized_tx.priority {
+                return Err(UnconfirmedPoolError::LowFee);
+            }
+            let ptx = self
+                .txs_by_signature
+                .get(tx_key)
+                .ok_or(UnconfirmedPoolError::StorageOutofSync)?;
+            count -= 1;
+            weight -= ptx.weight;
+            evicted_tx_keys.push(tx_key.clone());
+        }
+        Ok(evicted_tx_keys)
+    }
+
+    /// Returns the minimum fee-per-gram that a new transaction must pay to be accepted into the pool. The configured
+    /// minimum fee-per-gram is used while space is available, when the pool is full the minimum rises to the
+    /// fee-per-gram of the lowest priority transaction, as that transaction will need to be evicted to make space.
+    pub fn min_fee_per_gram(&self) -> MicroTari {
+        if self.is_full() {
+            if let Some(ptx) = self
+                .txs_by_priority
+                .iter()
+                .next()
+                .and_then(|(_, tx_key)| self.txs_by_signature.get(tx_key))
+            {
+                let lowest_fee_per_gram = MicroTari::from(ptx.transaction.calculate_ave_fee_per_gram() as u64);
+                if lowest_fee_per_gram > self.config.min_fee_per_gram {
+                    return lowest_fee_per_gram;
+                }
+            }
         }
+        self.config.min_fee_per_gram
     }
 
     /// Insert a new transaction into the UnconfirmedPoolStorage. Low priority transactions will be removed to make
     /// space for higher priority transactions. The lowest priority transactions will be removed when the maximum
-    /// capacity is reached and the new transaction has a higher priority than the currently stored lowest priority
-    /// transaction.
+    /// capacity or weight is reached and the new transaction has a higher priority than the currently stored lowest
+    /// priority transactions. A transaction that spends the same inputs as transactions already in the pool will
+    /// replace them if it pays a higher fee-per-gram than each of them, and a higher fee than all of them combined by
+    /// at least the minimum replacement fee-per-gram of the replaced weight. Requiring both stops a heavier replacement
+    /// from lowering the fee-per-gram of the pool.
     pub fn insert(&mut self, tx: Arc<Transaction>) -> Result<(), UnconfirmedPoolError> {
         let tx_key = tx.body.kernels()[0].excess_sig.clone();
-        if !self.txs_by_signature.contains_key(&tx_key) {
-            let prioritized_tx = PrioritizedTransaction::try_from((*tx).clone())?;
-            if self.txs_by_signature.len() >= self.config.storage_capacity {
-                if prioritized_tx.priority < *self.lowest_priority() {
-                    return Ok(());
-                }
-                self.remove_lowest_priority_tx();
+        if self.txs_by_signature.contains_key(&tx_key) {
+            return Ok(());
+        }
+        self.remove_expired_txs();
+
+        let prioritized_tx = PrioritizedTransaction::try_from((*tx).clone())?;
+        if prioritized_tx.weight > self.config.max_weight {
+            return Err(UnconfirmedPoolError::ExceedsMaxWeight);
+        }
+        if tx.calculate_ave_fee_per_gram() < u64::from(self.min_fee_per_gram()) as f64 {
+            return Err(UnconfirmedPoolError::LowFee);
+        }
+
+        let replaced_tx_keys = self.find_conflicting_txs(&tx);
+        if !replaced_tx_keys.is_empty() {
+            let replaced_txs = replaced_tx_keys
+                .iter()
+                .filter_map(|tx_key| self.txs_by_signature.get(tx_key))
+                .collect::<Vec<_>>();
+            let replaced_fee: MicroTari = replaced_txs
+                .iter()
+                .map(|ptx| ptx.transaction.body.get_total_fee())
+                .sum();
+            let replaced_weight: u64 = replaced_txs.iter().map(|ptx| ptx.weight).sum();
+            let fee_per_gram = tx.calculate_ave_fee_per_gram();
+            let min_fee = replaced_fee + self.config.min_replacement_fee_per_gram * replaced_weight;
+            if tx.body.get_total_fee() < min_fee ||
+                replaced_txs
+                    .iter()
+                    .any(|ptx| fee_per_gram <= ptx.transaction.calculate_ave_fee_per_gram())
+            {
+                return Err(UnconfirmedPoolError::InsufficientReplacementFee);
             }
+        }
 
-            self.txs_by_priority
-                .insert(prioritized_tx.priority.clone(), tx_key.clone());
-            self.txs_by_signature.insert(tx_key, prioritized_tx);
+        let evicted_tx_keys = self.find_evictions(&prioritized_tx, &replaced_tx_keys)?;
+        for removed_tx_key in replaced_tx_keys.iter().chain(evicted_tx_keys.iter()) {
+            self.remove_tx(removed_tx_key);
         }
+
+        self.total_weight += prioritized_tx.weight;
+        self.txs_by_priority
+            .insert(prioritized_tx.priority.clone(), tx_key.clone());
+        self.txs_by_signature.insert(tx_key, prioritized_tx);
         Ok(())
     }
 
-    /// Insert a set of new transactions into the UnconfirmedPoolStorage
+    /// Insert a set of new transactions into the UnconfirmedPoolStorage. Transactions that are rejected by the fee,
+    /// weight or replacement policies of the pool are discarded.
     pub fn insert_txs(&mut self, txs: Vec<Arc<Transaction>>) -> Result<(), UnconfirmedPoolError> {
         for tx in txs.into_iter() {
-            self.insert(tx)?;
+            match self.insert(tx) {
+                Err(e) if !e.is_rejection() => return Err(e),
+                _ => {},
+            }
         }
         Ok(())
     }
@@ -103,6 +220,11 @@ impl UnconfirmedPoolStorage {
         self.txs_by_signature.contains_key(excess_sig)
     }
 
+    /// Returns the transaction with the provided excess_sig if it is stored in the UnconfirmedPoolStorage
+    pub fn fetch_tx_with_excess_sig(&self, excess_sig: &Signature) -> Option<Arc<Transaction>> {
+        self.txs_by_signature.get(excess_sig).map(|ptx| ptx.transaction.clone())
+    }
+
     /// Returns a set of the highest priority unconfirmed transactions, that can be included in a block.
     pub fn highest_priority_txs(&self, total_weight: u64) -> Result<Vec<Arc<Transaction>>, UnconfirmedPoolError> {
         let mut selected_txs: Vec<Arc<Transaction>> = Vec::new();
@@ -136,14 +258,13 @@ impl UnconfirmedPoolStorage {
         for (tx_key, ptx) in self.txs_by_signature.iter() {
             for input in ptx.transaction.body.inputs() {
                 if published_block.body.inputs().contains(input) {
-                    self.txs_by_priority.remove(&ptx.priority);
                     removed_tx_keys.push(tx_key.clone());
                 }
             }
         }
 
         for tx_key in &removed_tx_keys {
-            self.txs_by_signature.remove(&tx_key);
+            self.remove_tx(&tx_key);
         }
     }
 
@@ -151,9 +272,8 @@ impl UnconfirmedPoolStorage {
     pub fn remove_published_and_discard_double_spends(&mut self, published_block: &Block) -> Vec<Arc<Transaction>> {
         let mut removed_txs: Vec<Arc<Transaction>> = Vec::new();
         published_block.body.kernels().iter().for_each(|kernel| {
-            if let Some(ptx) = self.txs_by_signature.get(&kernel.excess_sig) {
-                self.txs_by_priority.remove(&ptx.priority);
-                removed_txs.push(self.txs_by_signature.remove(&kernel.excess_sig).unwrap().transaction);
+            if let Some(ptx) = self.remove_tx(&kernel.excess_sig) {
+                removed_txs.push(ptx.transaction);
             }
         });
         // First remove published transactions before discarding double spends
@@ -162,6 +282,24 @@ impl UnconfirmedPoolStorage {
         removed_txs
     }
 
+    /// Remove all transactions that have been stored in the UnconfirmedPoolStorage for longer than the configured
+    /// Time-to-live. Returns the expired transactions.
+    pub fn remove_expired_txs(&mut self) -> Vec<Arc<Transaction>> {
+        let tx_ttl = self.config.tx_ttl;
+        let expired_tx_keys: Vec<Signature> = self
+            .txs_by_signature
+            .iter()
+            .filter(|(_, ptx)| ptx.inserted_at.elapsed() >= tx_ttl)
+            .map(|(tx_key, _)| tx_key.clone())
+            .collect();
+
+        expired_tx_keys
+            .iter()
+            .filter_map(|tx_key| self.remove_tx(tx_key))
+            .map(|ptx| ptx.transaction)
+            .collect()
+    }
+
     /// Returns the total number of unconfirmed transactions stored in the UnconfirmedPoolStorage
     pub fn len(&self) -> usize {
         self.txs_by_signature.len()
@@ -178,11 +316,7 @@ impl UnconfirmedPoolStorage {
 
     /// Returns the total weight of all transactions stored in the pool.
     pub fn calculate_weight(&self) -> u64 {
-        let mut weight: u64 = 0;
-        self.txs_by_signature
-            .iter()
-            .for_each(|(_, ptx)| weight += ptx.transaction.calculate_weight());
-        (weight)
+        self.total_weight
     }
 
     #[cfg(test)]
@@ -191,6 +325,10 @@ impl UnconfirmedPoolStorage {
         if self.txs_by_priority.len() != self.txs_by_signature.len() {
             return false;
         }
+        let weight: u64 = self.txs_by_signature.iter().map(|(_, ptx)| ptx.weight).sum();
+        if weight != self.total_weight {
+            return false;
+        }
         self.txs_by_priority
             .iter()
             .all(|(_, tx_key)| self.txs_by_signature.contains_key(tx_key))
@@ -30,6 +30,7 @@ use crate::{
             KernelBuilder,
             KernelFeatures,
             OutputFeatures,
+            RewindData,
             Transaction,
             TransactionBuilder,
             UnblindedOutput,
@@ -65,6 +66,7 @@ pub struct CoinbaseBuilder {
     fees: Option<MicroTari>,
     spend_key: Option<PrivateKey>,
     private_nonce: Option<PrivateKey>,
+    rewind_data: Option<RewindData>,
 }
 
 impl CoinbaseBuilder {
@@ -77,6 +79,7 @@ impl CoinbaseBuilder {
             fees: None,
             spend_key: None,
             private_nonce: None,
+            rewind_data: None,
         }
     }
 
@@ -104,6 +107,13 @@ impl CoinbaseBuilder {
         self
     }
 
+    /// Provides the rewind data for the coinbase output's range proof, so that a miner's wallet can recover the output
+    /// from the blockchain
+    pub fn with_rewind_data(mut self, rewind_data: RewindData) -> Self {
+        self.rewind_data = Some(rewind_data);
+        self
+    }
+
     /// Try and construct a Coinbase Transaction. The block reward is taken from the emission curve for the current
     /// block height. The other parameters (keys, nonces etc.) are provided by the caller. Other data is
     /// automatically set: Coinbase transactions have an offset of zero, no fees, the `COINBASE_OUTPUT` flags are set
@@ -127,9 +137,11 @@ impl CoinbaseBuilder {
         let sig = Signature::sign(key.clone(), nonce, &challenge)
             .map_err(|_| CoinbaseBuildError::BuildError("Challenge could not be represented as a scalar".into()))?;
         let output = UnblindedOutput::new(reward, key, Some(output_features));
-        let output = output
-            .as_transaction_output(&self.factories)
-            .map_err(|e| CoinbaseBuildError::BuildError(e.to_string()))?;
+        let output = match &self.rewind_data {
+            Some(rewind_data) => output.as_rewindable_transaction_output(&self.factories, rewind_data),
+            None => output.as_transaction_output(&self.factories),
+        }
+        .map_err(|e| CoinbaseBuildError::BuildError(e.to_string()))?;
         let kernel = KernelBuilder::new()
             .with_fee(0 * uT)
             .with_features(kernel_features)
@@ -157,10 +169,20 @@ mod test {
         consensus::ConsensusManager,
         helpers::MockBackend,
         mining::{coinbase_builder::CoinbaseBuildError, CoinbaseBuilder},
-        transactions::{helpers::TestParams, tari_amount::uT, transaction::OutputFlags, types::CryptoFactories},
+        transactions::{
+            helpers::TestParams,
+            tari_amount::uT,
+            transaction::{OutputFlags, RewindData},
+            types::{CryptoFactories, PrivateKey},
+        },
     };
+    use rand::OsRng;
     use std::sync::Arc;
-    use tari_crypto::commitment::HomomorphicCommitmentFactory;
+    use tari_crypto::{
+        commitment::HomomorphicCommitmentFactory,
+        keys::SecretKey,
+        range_proof::REWIND_PROOF_MESSAGE_LENGTH,
+    };
 
     fn get_builder() -> (CoinbaseBuilder, ConsensusManager<MockBackend>, Arc<CryptoFactories>) {
         let rules = ConsensusManager::default();
@@ -210,4 +232,35 @@ mod test {
         assert!(utxo.verify_range_proof(&factories.range_proof).unwrap());
         assert!(utxo.features.flags.contains(OutputFlags::COINBASE_OUTPUT));
     }
+
+    #[test]
+    fn rewindable_coinbase() {
+        let p = TestParams::new();
+        let mut rng = OsRng::new().unwrap();
+        let rewind_data = RewindData {
+            rewind_key: PrivateKey::random(&mut rng),
+            rewind_blinding_key: PrivateKey::random(&mut rng),
+            proof_message: [0u8; REWIND_PROOF_MESSAGE_LENGTH],
+        };
+        let (builder, rules, factories) = get_builder();
+        let builder = builder
+            .with_block_height(42)
+            .with_fees(145 * uT)
+            .with_nonce(p.nonce.clone())
+            .with_spend_key(p.spend_key.clone())
+            .with_rewind_data(rewind_data.clone());
+        let tx = builder.build(rules.clone()).unwrap();
+        let utxo = &tx.body.outputs()[0];
+        let block_reward = rules.emission_schedule().block_reward(42) + 145 * uT;
+        assert!(utxo.verify_range_proof(&factories.range_proof).unwrap());
+        let result = utxo
+            .full_rewind_range_proof(
+                &factories.range_proof,
+                &rewind_data.rewind_key,
+                &rewind_data.rewind_blinding_key,
+            )
+            .unwrap();
+        assert_eq!(result.committed_value, u64::from(block_reward));
+        assert_eq!(result.blinding_factor, p.spend_key);
+    }
 }
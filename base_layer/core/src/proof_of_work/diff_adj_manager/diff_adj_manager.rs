@@ -22,6 +22,7 @@
 
 use crate::{
     chain_storage::{BlockchainBackend, BlockchainDatabase},
+    consensus::ConsensusConstants,
     proof_of_work::{
         diff_adj_manager::{diff_adj_storage::DiffAdjStorage, error::DiffAdjManagerError},
         Difficulty,
@@ -49,6 +50,21 @@ where T: BlockchainBackend
         })
     }
 
+    /// Constructs a new DiffAdjManager that uses the difficulty adjustment algorithm selected by the provided consensus
+    /// constants.
+    pub fn with_consensus_constants(
+        blockchain_db: BlockchainDatabase<T>,
+        consensus_constants: ConsensusConstants,
+    ) -> Result<Self, DiffAdjManagerError>
+    {
+        Ok(Self {
+            diff_adj_storage: Arc::new(RwLock::new(DiffAdjStorage::with_consensus_constants(
+                blockchain_db,
+                consensus_constants,
+            ))),
+        })
+    }
+
     /// Returns the estimated target difficulty for the specified PoW algorithm at the chain tip.
     pub fn get_target_difficulty(&self, pow_algo: &PowAlgorithm) -> Result<Difficulty, DiffAdjManagerError> {
         self.diff_adj_storage
@@ -27,7 +27,6 @@ use crate::{
     proof_of_work::{
         diff_adj_manager::error::DiffAdjManagerError,
         difficulty::DifficultyAdjustment,
-        lwma_diff::LinearWeightedMovingAverage,
         Difficulty,
         PowAlgorithm,
         ProofOfWork,
@@ -44,14 +43,15 @@ enum UpdateState {
     Synced,
 }
 
-/// DiffAdjManager makes use of DiffAdjStorage to provide thread save access to its LinearWeightedMovingAverages for
-/// each PoW algorithm.
+/// DiffAdjManager makes use of DiffAdjStorage to provide thread save access to its difficulty adjustment algorithms for
+/// each PoW algorithm. The difficulty adjustment algorithm is selected by the consensus constants.
 pub struct DiffAdjStorage<T>
 where T: BlockchainBackend
 {
     blockchain_db: BlockchainDatabase<T>,
-    monero_lwma: LinearWeightedMovingAverage,
-    blake_lwma: LinearWeightedMovingAverage,
+    consensus_constants: ConsensusConstants,
+    monero_diff_adj: Box<dyn DifficultyAdjustment + Send + Sync>,
+    blake_diff_adj: Box<dyn DifficultyAdjustment + Send + Sync>,
     sync_data: Option<(u64, BlockHash)>,
     timestamps: VecDeque<EpochTime>,
 }
@@ -61,10 +61,21 @@ where T: BlockchainBackend
 {
     /// Constructs a new DiffAdjStorage with access to the blockchain db.
     pub fn new(blockchain_db: BlockchainDatabase<T>) -> Self {
+        Self::with_consensus_constants(blockchain_db, ConsensusConstants::current())
+    }
+
+    /// Constructs a new DiffAdjStorage that uses the difficulty adjustment algorithm selected by the provided consensus
+    /// constants.
+    pub fn with_consensus_constants(
+        blockchain_db: BlockchainDatabase<T>,
+        consensus_constants: ConsensusConstants,
+    ) -> Self
+    {
         Self {
             blockchain_db,
-            monero_lwma: LinearWeightedMovingAverage::default(),
-            blake_lwma: LinearWeightedMovingAverage::default(),
+            monero_diff_adj: consensus_constants.create_difficulty_adjustment(),
+            blake_diff_adj: consensus_constants.create_difficulty_adjustment(),
+            consensus_constants,
             sync_data: None,
             timestamps: VecDeque::new(),
         }
@@ -128,8 +139,8 @@ where T: BlockchainBackend
     {
         self.update(height)?;
         Ok(match pow_algo {
-            PowAlgorithm::Monero => self.monero_lwma.get_difficulty(),
-            PowAlgorithm::Blake => self.blake_lwma.get_difficulty(),
+            PowAlgorithm::Monero => self.monero_diff_adj.get_difficulty(),
+            PowAlgorithm::Blake => self.blake_diff_adj.get_difficulty(),
         })
     }
 
@@ -154,17 +165,17 @@ where T: BlockchainBackend
 
     // Resets the DiffAdjStorage.
     fn reset(&mut self) {
-        self.monero_lwma = LinearWeightedMovingAverage::default();
-        self.blake_lwma = LinearWeightedMovingAverage::default();
+        self.monero_diff_adj = self.consensus_constants.create_difficulty_adjustment();
+        self.blake_diff_adj = self.consensus_constants.create_difficulty_adjustment();
         self.sync_data = None;
         self.timestamps = VecDeque::new();
     }
 
-    // Adds the new PoW sample to the specific LinearWeightedMovingAverage specified by the PoW algorithm.
+    // Adds the new PoW sample to the specific difficulty adjustment algorithm specified by the PoW algorithm.
     fn add(&mut self, timestamp: EpochTime, pow: ProofOfWork) -> Result<(), DiffAdjManagerError> {
         match pow.pow_algo {
-            PowAlgorithm::Monero => self.monero_lwma.add(timestamp, pow.accumulated_monero_difficulty)?,
-            PowAlgorithm::Blake => self.blake_lwma.add(timestamp, pow.accumulated_blake_difficulty)?,
+            PowAlgorithm::Monero => self.monero_diff_adj.add(timestamp, pow.accumulated_monero_difficulty)?,
+            PowAlgorithm::Blake => self.blake_diff_adj.add(timestamp, pow.accumulated_blake_difficulty)?,
         }
         Ok(())
     }
@@ -177,13 +188,13 @@ where T: BlockchainBackend
     ) -> Result<(), DiffAdjManagerError>
     {
         self.reset();
-        let difficulty_block_window = ConsensusConstants::current().get_difficulty_block_window();
+        let difficulty_block_window = self.consensus_constants.get_difficulty_block_window();
         let mut monero_diff_list = Vec::<(EpochTime, Difficulty)>::with_capacity(difficulty_block_window as usize);
         let mut blake_diff_list = Vec::<(EpochTime, Difficulty)>::with_capacity(difficulty_block_window as usize);
         for height in (0..=height_of_longest_chain).rev() {
             let header = self.blockchain_db.fetch_header(height)?;
             // keep MEDIAN_TIMESTAMP_COUNT blocks for median timestamp
-            if self.timestamps.len() < ConsensusConstants::current().get_median_timestamp_count() {
+            if self.timestamps.len() < self.consensus_constants.get_median_timestamp_count() {
                 self.timestamps.push_front(header.timestamp);
             }
             match header.pow.pow_algo {
@@ -211,10 +222,10 @@ where T: BlockchainBackend
             }
         }
         for (timestamp, accumulated_difficulty) in monero_diff_list.into_iter().rev() {
-            self.monero_lwma.add(timestamp, accumulated_difficulty)?
+            self.monero_diff_adj.add(timestamp, accumulated_difficulty)?
         }
         for (timestamp, accumulated_difficulty) in blake_diff_list.into_iter().rev() {
-            self.blake_lwma.add(timestamp, accumulated_difficulty)?
+            self.blake_diff_adj.add(timestamp, accumulated_difficulty)?
         }
         self.sync_data = Some((height_of_longest_chain, best_block));
 
@@ -233,18 +244,18 @@ where T: BlockchainBackend
                 let header = self.blockchain_db.fetch_header(height)?;
                 // add new timestamps
                 self.timestamps.push_back(header.timestamp);
-                if self.timestamps.len() > ConsensusConstants::current().get_median_timestamp_count() {
+                if self.timestamps.len() > self.consensus_constants.get_median_timestamp_count() {
                     self.timestamps.remove(0); // remove oldest
                 }
                 match header.pow.pow_algo {
                     PowAlgorithm::Monero => {
-                        self.monero_lwma.add(
+                        self.monero_diff_adj.add(
                             header.timestamp,
                             header.pow.accumulated_monero_difficulty + header.achieved_difficulty(),
                         )?;
                     },
                     PowAlgorithm::Blake => {
-                        self.blake_lwma.add(
+                        self.blake_diff_adj.add(
                             header.timestamp,
                             header.pow.accumulated_blake_difficulty + header.achieved_difficulty(),
                         )?;
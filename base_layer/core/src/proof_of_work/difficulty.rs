@@ -20,7 +20,11 @@
 // WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
 // USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
-use crate::proof_of_work::error::DifficultyAdjustmentError;
+use crate::proof_of_work::{
+    error::DifficultyAdjustmentError,
+    lwma_diff::LinearWeightedMovingAverage,
+    sma_diff::SimpleMovingAverage,
+};
 use bitflags::_core::ops::Div;
 use newtype_ops::newtype_ops;
 use serde::{Deserialize, Serialize};
@@ -104,6 +108,27 @@ pub trait DifficultyAdjustment {
     fn get_difficulty(&self) -> Difficulty;
 }
 
+/// The difficulty adjustment algorithms that can be selected for a network in the consensus constants.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DifficultyAdjustmentType {
+    LinearWeightedMovingAverage,
+    SimpleMovingAverage,
+}
+
+impl DifficultyAdjustmentType {
+    /// Construct a new, empty instance of the selected difficulty adjustment algorithm.
+    pub fn create(&self, block_window: usize, target_time: u64) -> Box<dyn DifficultyAdjustment + Send + Sync> {
+        match self {
+            DifficultyAdjustmentType::LinearWeightedMovingAverage => {
+                Box::new(LinearWeightedMovingAverage::new(block_window, target_time))
+            },
+            DifficultyAdjustmentType::SimpleMovingAverage => {
+                Box::new(SimpleMovingAverage::new(block_window, target_time))
+            },
+        }
+    }
+}
+
 #[cfg(test)]
 mod test {
     use crate::proof_of_work::difficulty::Difficulty;
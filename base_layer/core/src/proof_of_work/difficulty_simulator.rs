@@ -0,0 +1,204 @@
+// Copyright 2019. The Tari Project
+//
+// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+// following conditions are met:
+//
+// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+// disclaimer.
+//
+// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+// following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+// 3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+// products derived from this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+// INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+// SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+// WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+// USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+//! An offline difficulty simulator that replays a hash rate schedule through a difficulty adjustment algorithm and
+//! reports block interval statistics. It is used to evaluate difficulty adjustment algorithms and their parameters
+//! before they are selected in the consensus constants.
+
+use crate::proof_of_work::{
+    difficulty::{Difficulty, DifficultyAdjustment},
+    error::DifficultyAdjustmentError,
+};
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use tari_utilities::epoch_time::EpochTime;
+
+/// A period of the simulation during which the network hash rate is constant.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct HashRatePhase {
+    /// The number of blocks mined during this phase
+    pub block_count: u64,
+    /// The network hash rate in difficulty units per second
+    pub hash_rate: u64,
+}
+
+impl HashRatePhase {
+    pub fn new(block_count: u64, hash_rate: u64) -> Self {
+        Self { block_count, hash_rate }
+    }
+}
+
+/// The DifficultySimulator mines the blocks of a hash rate schedule using the target difficulty provided by a
+/// difficulty adjustment algorithm. Solve times are the expected solve times for the target difficulty, unless a
+/// seed is provided, in which case they are sampled from an exponential distribution to model mining variance.
+pub struct DifficultySimulator {
+    schedule: Vec<HashRatePhase>,
+    seed: Option<u64>,
+    start_timestamp: EpochTime,
+}
+
+impl DifficultySimulator {
+    /// Constructs a new deterministic DifficultySimulator for the provided hash rate schedule.
+    pub fn new(schedule: Vec<HashRatePhase>) -> Self {
+        Self {
+            schedule,
+            seed: None,
+            start_timestamp: EpochTime::from(0),
+        }
+    }
+
+    /// Sample random solve times using an RNG seeded with the provided seed, so that simulations are reproducible.
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.seed = Some(seed);
+        self
+    }
+
+    /// Replays the hash rate schedule through the provided difficulty adjustment algorithm, which should be empty.
+    pub fn run(&self, diff_adj: &mut dyn DifficultyAdjustment) -> Result<SimulationReport, DifficultyAdjustmentError> {
+        let mut rng = self.seed.map(|seed| {
+            let mut seed_bytes = <StdRng as SeedableRng>::Seed::default();
+            seed_bytes[..8].copy_from_slice(&seed.to_le_bytes());
+            StdRng::from_seed(seed_bytes)
+        });
+        let mut timestamp = self.start_timestamp;
+        let mut accumulated_difficulty = Difficulty::default();
+        let mut report = SimulationReport::default();
+        diff_adj.add(timestamp, Difficulty::min())?;
+        accumulated_difficulty += Difficulty::min();
+        for phase in &self.schedule {
+            for _ in 0..phase.block_count {
+                let target_difficulty = diff_adj.get_difficulty();
+                let expected_solve_time = target_difficulty.as_u64() as f64 / phase.hash_rate.max(1) as f64;
+                let solve_time = match rng.as_mut() {
+                    Some(rng) => -(1.0 - rng.gen::<f64>()).ln() * expected_solve_time,
+                    None => expected_solve_time,
+                };
+                // Block timestamps have a resolution of one second and blocks cannot share a timestamp
+                let solve_time = (solve_time.round() as u64).max(1);
+                timestamp = timestamp.increase(solve_time);
+                accumulated_difficulty += target_difficulty;
+                diff_adj.add(timestamp, accumulated_difficulty)?;
+                report.block_intervals.push(solve_time);
+                report.difficulties.push(target_difficulty);
+            }
+        }
+        Ok(report)
+    }
+}
+
+/// The block intervals and target difficulties of the blocks mined during a simulation.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SimulationReport {
+    pub block_intervals: Vec<u64>,
+    pub difficulties: Vec<Difficulty>,
+}
+
+impl SimulationReport {
+    /// Returns a report containing only the blocks from the provided height onwards, this is used to exclude the
+    /// warm-up period or to inspect the response to a hash rate change.
+    pub fn from_height(&self, height: usize) -> SimulationReport {
+        let height = height.min(self.block_intervals.len());
+        SimulationReport {
+            block_intervals: self.block_intervals[height..].to_vec(),
+            difficulties: self.difficulties[height..].to_vec(),
+        }
+    }
+
+    /// Returns the block interval statistics of the simulated blocks.
+    pub fn stats(&self) -> BlockIntervalStats {
+        let count = self.block_intervals.len();
+        if count == 0 {
+            return BlockIntervalStats::default();
+        }
+        let mean = self.block_intervals.iter().sum::<u64>() as f64 / count as f64;
+        let variance = self
+            .block_intervals
+            .iter()
+            .map(|interval| (*interval as f64 - mean).powi(2))
+            .sum::<f64>() /
+            count as f64;
+        let mut sorted_intervals = self.block_intervals.clone();
+        sorted_intervals.sort();
+        BlockIntervalStats {
+            block_count: count,
+            mean,
+            std_dev: variance.sqrt(),
+            median: sorted_intervals[count / 2],
+            min: sorted_intervals[0],
+            max: sorted_intervals[count - 1],
+        }
+    }
+}
+
+/// Summary statistics of the block intervals, in seconds, of a simulation.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct BlockIntervalStats {
+    pub block_count: usize,
+    pub mean: f64,
+    pub std_dev: f64,
+    pub median: u64,
+    pub min: u64,
+    pub max: u64,
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::proof_of_work::{lwma_diff::LinearWeightedMovingAverage, sma_diff::SimpleMovingAverage};
+
+    #[test]
+    fn constant_hash_rate() {
+        let simulator = DifficultySimulator::new(vec![HashRatePhase::new(1000, 1_000)]);
+        let mut lwma = LinearWeightedMovingAverage::new(90, 240);
+        let stats = simulator.run(&mut lwma).unwrap().from_height(500).stats();
+        assert_eq!(stats.block_count, 500);
+        assert!((stats.mean - 240.0).abs() < 5.0);
+
+        let mut sma = SimpleMovingAverage::new(90, 240);
+        let stats = simulator.run(&mut sma).unwrap().from_height(500).stats();
+        assert!((stats.mean - 240.0).abs() < 5.0);
+    }
+
+    #[test]
+    fn hash_rate_increase() {
+        let simulator = DifficultySimulator::new(vec![HashRatePhase::new(500, 1_000), HashRatePhase::new(500, 4_000)]);
+        let mut lwma = LinearWeightedMovingAverage::new(90, 240);
+        let report = simulator.run(&mut lwma).unwrap();
+        // Blocks are mined faster immediately after the hash rate increases, until the difficulty catches up
+        assert!(report.block_intervals[500] < 100);
+        let stats = report.from_height(800).stats();
+        assert!((stats.mean - 240.0).abs() < 5.0);
+        assert!(report.difficulties[999] > report.difficulties[499] * 3);
+    }
+
+    #[test]
+    fn seeded_simulation_is_reproducible() {
+        let simulator = DifficultySimulator::new(vec![HashRatePhase::new(1000, 1_000)]).with_seed(42);
+        let mut lwma = LinearWeightedMovingAverage::new(90, 240);
+        let report1 = simulator.run(&mut lwma).unwrap();
+        let mut lwma = LinearWeightedMovingAverage::new(90, 240);
+        let report2 = simulator.run(&mut lwma).unwrap();
+        assert_eq!(report1, report2);
+        let stats = report1.from_height(200).stats();
+        assert!(stats.std_dev > 0.0);
+        assert!((stats.mean - 240.0).abs() < 40.0);
+    }
+}
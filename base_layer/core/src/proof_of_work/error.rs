@@ -28,6 +28,8 @@ pub enum PowError {
     InvalidProofOfWork,
     // Target difficulty not achieved
     AchievedDifficultyTooLow,
+    // The accumulated difficulty does not follow from the block that is built on
+    InvalidAccumulatedDifficulty,
 }
 
 #[derive(Debug, Error, Clone, PartialEq)]
@@ -30,11 +30,13 @@ mod proof_of_work;
 #[cfg(test)]
 pub use blake_pow::test as blake_test;
 
+pub mod difficulty_simulator;
 pub mod lwma_diff;
+pub mod sma_diff;
 
 pub use blake_pow::{blake_difficulty, blake_difficulty_with_hash};
 pub use diff_adj_manager::{DiffAdjManager, DiffAdjManagerError};
-pub use difficulty::{Difficulty, DifficultyAdjustment};
+pub use difficulty::{Difficulty, DifficultyAdjustment, DifficultyAdjustmentType};
 pub use error::{DifficultyAdjustmentError, PowError};
 pub use monero_rx::monero_difficulty;
 pub use proof_of_work::{PowAlgorithm, ProofOfWork};
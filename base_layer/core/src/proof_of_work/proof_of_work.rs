@@ -124,10 +124,13 @@ impl ProofOfWork {
     ///
     /// This uses a geometric mean to compare the two difficulties. See Issue #1075 (https://github.com/tari-project/tari/issues/1075) as to why this was done
     ///
+    /// The accumulated difficulty of each algorithm is floored at one, so that the total accumulated difficulty of a
+    /// chain that has only been mined with a single algorithm still increases with every block.
+    ///
     /// The total accumulated difficulty is most often used to decide on which of two forks is the longest chain.
     pub fn total_accumulated_difficulty(&self) -> Difficulty {
-        let d = (self.accumulated_monero_difficulty.as_u64() as f64 *
-            self.accumulated_blake_difficulty.as_u64() as f64)
+        let d = (self.accumulated_monero_difficulty.as_u64().max(1) as f64 *
+            self.accumulated_blake_difficulty.as_u64().max(1) as f64)
             .sqrt();
 
         Difficulty::from(d.ceil() as u64)
@@ -245,6 +248,12 @@ mod test {
         pow.accumulated_monero_difficulty = 1.into();
         pow.accumulated_blake_difficulty = 15_222_333_444_555_666_777.into();
         assert_eq!(pow.total_accumulated_difficulty(), 3_901_580_891.into(), "Case 4");
+        // Single algorithm chains
+        pow.accumulated_monero_difficulty = 0.into();
+        pow.accumulated_blake_difficulty = 10_000.into();
+        assert_eq!(pow.total_accumulated_difficulty(), 100.into(), "Case 5");
+        pow.accumulated_blake_difficulty = 0.into();
+        assert_eq!(pow.total_accumulated_difficulty(), 1.into(), "Case 6");
     }
 
     #[test]
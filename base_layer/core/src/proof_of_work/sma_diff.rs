@@ -0,0 +1,152 @@
+// Copyright 2019. The Tari Project
+//
+// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+// following conditions are met:
+//
+// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+// disclaimer.
+//
+// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+// following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+// 3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+// products derived from this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+// INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+// SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+// WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+// USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+use crate::{
+    consensus::ConsensusConstants,
+    proof_of_work::{
+        difficulty::{Difficulty, DifficultyAdjustment},
+        error::DifficultyAdjustmentError,
+    },
+};
+use std::collections::VecDeque;
+use tari_utilities::epoch_time::EpochTime;
+
+const INITIAL_DIFFICULTY: Difficulty = Difficulty::min();
+
+/// A simple moving average difficulty adjustment algorithm. The target difficulty is the total difficulty achieved
+/// over the block window scaled by the ratio of the target time to the time it took to mine the window. All solve
+/// times are weighted equally, making it slower to respond to hash rate changes than the LWMA.
+pub struct SimpleMovingAverage {
+    timestamps: VecDeque<EpochTime>,
+    accumulated_difficulties: VecDeque<Difficulty>,
+    block_window: usize,
+    target_time: u64,
+}
+
+impl Default for SimpleMovingAverage {
+    fn default() -> Self {
+        let consensus = ConsensusConstants::current();
+        SimpleMovingAverage::new(
+            consensus.get_difficulty_block_window() as usize,
+            consensus.get_diff_target_block_interval(),
+        )
+    }
+}
+
+impl SimpleMovingAverage {
+    pub fn new(block_window: usize, target_time: u64) -> SimpleMovingAverage {
+        SimpleMovingAverage {
+            timestamps: VecDeque::with_capacity(block_window + 1),
+            accumulated_difficulties: VecDeque::with_capacity(block_window + 1),
+            block_window,
+            target_time,
+        }
+    }
+
+    fn calculate(&self) -> Difficulty {
+        let timestamps = &self.timestamps;
+        if timestamps.len() <= 1 {
+            return INITIAL_DIFFICULTY;
+        }
+
+        // Use the array length rather than block_window to include early cases where the no. of pts < block_window
+        let n = (timestamps.len() - 1) as u64;
+        let difficulty: u64 = self.accumulated_difficulties[n as usize]
+            .checked_sub(self.accumulated_difficulties[0])
+            .expect("Accumulated difficulties cannot decrease in proof of work")
+            .as_u64();
+        // Every block in the window is assumed to have taken at least one second, so that out of order timestamps
+        // cannot cause a zero or negative window time.
+        let window_time = timestamps[n as usize]
+            .as_u64()
+            .saturating_sub(timestamps[0].as_u64())
+            .max(n);
+        let target = difficulty as f64 * self.target_time as f64 / window_time as f64;
+        if target > std::u64::MAX as f64 {
+            panic!("Difficulty target has overflowed");
+        }
+        let target = target.ceil() as u64; // difficulty should never be below 1, ceil(0.9) = 1
+        target.into()
+    }
+}
+
+impl DifficultyAdjustment for SimpleMovingAverage {
+    fn add(
+        &mut self,
+        timestamp: EpochTime,
+        accumulated_difficulty: Difficulty,
+    ) -> Result<(), DifficultyAdjustmentError>
+    {
+        if let Some(v) = self.accumulated_difficulties.back() {
+            if accumulated_difficulty <= *v {
+                return Err(DifficultyAdjustmentError::DecreasingAccumulatedDifficulty);
+            }
+        }
+        self.timestamps.push_back(timestamp);
+        self.accumulated_difficulties.push_back(accumulated_difficulty);
+        while self.timestamps.len() > self.block_window + 1 {
+            self.timestamps.pop_front();
+            self.accumulated_difficulties.pop_front();
+        }
+        Ok(())
+    }
+
+    fn get_difficulty(&self) -> Difficulty {
+        self.calculate()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn sma_zero_len() {
+        let dif = SimpleMovingAverage::default();
+        assert_eq!(dif.get_difficulty(), Difficulty::min());
+    }
+
+    #[test]
+    fn sma_add_non_increasing_diff() {
+        let mut dif = SimpleMovingAverage::default();
+        assert!(dif.add(100.into(), 100.into()).is_ok());
+        assert!(dif.add(100.into(), 100.into()).is_err());
+        assert!(dif.add(100.into(), 50.into()).is_err());
+    }
+
+    #[test]
+    fn sma_calculate() {
+        let mut dif = SimpleMovingAverage::new(2, 60);
+        let _ = dif.add(60.into(), 100.into());
+        assert_eq!(dif.get_difficulty(), 1.into());
+        let _ = dif.add(120.into(), 200.into());
+        assert_eq!(dif.get_difficulty(), 100.into());
+        let _ = dif.add(180.into(), 300.into());
+        assert_eq!(dif.get_difficulty(), 100.into());
+        // 200 difficulty over 80 seconds, the oldest sample has left the window
+        let _ = dif.add(200.into(), 400.into());
+        assert_eq!(dif.get_difficulty(), 150.into());
+        // Out of order timestamps are treated as a one second solve time
+        let _ = dif.add(150.into(), 500.into());
+        assert_eq!(dif.get_difficulty(), 6000.into());
+    }
+}
@@ -27,5 +27,6 @@ pub mod core {
     tari_utilities::include_proto_package!("tari.core");
 }
 
+#[cfg(feature = "base_node")]
 mod block;
 pub mod utils;
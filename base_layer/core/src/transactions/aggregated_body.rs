@@ -150,6 +150,55 @@ impl AggregateBody {
         fee
     }
 
+    /// Check that every hash locked output spent by this body is spent according to its hash lock. It must either be
+    /// claimed into its claim commitment by a transaction with a hash lock kernel that reveals the preimage of its
+    /// hash, or be refunded into its refund commitment by a transaction with a kernel that is not valid before its
+    /// refund height. Kernels must carry a hash preimage if and only if they have the hash lock feature, and the
+    /// preimage of a hash lock kernel must be `HASH_LOCK_PREIMAGE_LENGTH` bytes long and unlock a hash locked output
+    /// that is spent by this body, so that hash lock kernels cannot be used to publish arbitrary data.
+    pub fn check_hash_locks(&self) -> Result<(), TransactionError> {
+        for kernel in self.kernels.iter() {
+            match (
+                kernel.features.contains(KernelFeatures::HASH_LOCK),
+                &kernel.hash_preimage,
+            ) {
+                (false, None) => (),
+                (true, Some(preimage)) => {
+                    let unlocks_input = self.inputs.iter().any(|input| {
+                        input
+                            .features
+                            .hash_lock
+                            .as_ref()
+                            .map_or(false, |hash_lock| hash_lock.is_unlocked_by(preimage))
+                    });
+                    if preimage.len() != HASH_LOCK_PREIMAGE_LENGTH || !unlocks_input {
+                        return Err(TransactionError::InvalidHashLock);
+                    }
+                },
+                _ => return Err(TransactionError::InvalidHashLock),
+            }
+        }
+        for input in self.inputs.iter() {
+            let hash_lock = match &input.features.hash_lock {
+                Some(hash_lock) => hash_lock,
+                None => continue,
+            };
+            let creates_output = |commitment: &Commitment| self.outputs.iter().any(|o| &o.commitment == commitment);
+            let claimed = creates_output(&hash_lock.claim_commitment) &&
+                self.kernels.iter().any(|k| {
+                    k.hash_preimage
+                        .as_ref()
+                        .map_or(false, |preimage| hash_lock.is_unlocked_by(preimage))
+                });
+            let refunded = creates_output(&hash_lock.refund_commitment) &&
+                self.kernels.iter().any(|k| k.lock_height >= hash_lock.refund_height);
+            if !claimed && !refunded {
+                return Err(TransactionError::InvalidHashLock);
+            }
+        }
+        Ok(())
+    }
+
     /// Validate this transaction by checking the following:
     /// 1. The sum of inputs, outputs and fees equal the (public excess value + offset)
     /// 1. The signature signs the canonical message with the private excess
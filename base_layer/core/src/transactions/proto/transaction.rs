@@ -29,6 +29,7 @@ use crate::transactions::{
     proto::utils::try_convert_all,
     tari_amount::MicroTari,
     transaction::{
+        HashLock,
         KernelFeatures,
         OutputFeatures,
         OutputFlags,
@@ -66,6 +67,7 @@ impl TryFrom<proto::TransactionKernel> for TransactionKernel {
             linked_kernel: kernel.linked_kernel.map(Into::into),
             lock_height: kernel.lock_height,
             meta_info: kernel.meta_info.map(Into::into),
+            hash_preimage: Some(kernel.hash_preimage).filter(|p| !p.is_empty()),
         })
     }
 }
@@ -80,6 +82,7 @@ impl From<TransactionKernel> for proto::TransactionKernel {
             linked_kernel: kernel.linked_kernel.map(Into::into),
             lock_height: kernel.lock_height,
             meta_info: kernel.meta_info.map(Into::into),
+            hash_preimage: kernel.hash_preimage.unwrap_or_default(),
         }
     }
 }
@@ -159,6 +162,7 @@ impl TryFrom<proto::OutputFeatures> for OutputFeatures {
             flags: OutputFlags::from_bits(features.flags as u8)
                 .ok_or("Invalid or unrecognised output flags".to_string())?,
             maturity: features.maturity,
+            hash_lock: features.hash_lock.map(TryInto::try_into).transpose()?,
         })
     }
 }
@@ -168,6 +172,48 @@ impl From<OutputFeatures> for proto::OutputFeatures {
         Self {
             flags: features.flags.bits() as u32,
             maturity: features.maturity,
+            hash_lock: features.hash_lock.map(Into::into),
+        }
+    }
+}
+
+//---------------------------------- HashLock --------------------------------------------//
+
+impl TryFrom<proto::HashLock> for HashLock {
+    type Error = String;
+
+    fn try_from(hash_lock: proto::HashLock) -> Result<Self, Self::Error> {
+        let claim_commitment = hash_lock
+            .claim_commitment
+            .map(|commit| Commitment::from_bytes(&commit.data))
+            .ok_or("Hash lock claim commitment not provided".to_string())?
+            .map_err(|err| err.to_string())?;
+
+        let refund_commitment = hash_lock
+            .refund_commitment
+            .map(|commit| Commitment::from_bytes(&commit.data))
+            .ok_or("Hash lock refund commitment not provided".to_string())?
+            .map_err(|err| err.to_string())?;
+
+        Ok(Self {
+            hash: hash_lock
+                .hash
+                .map(Into::into)
+                .ok_or("Hash lock hash not provided".to_string())?,
+            claim_commitment,
+            refund_height: hash_lock.refund_height,
+            refund_commitment,
+        })
+    }
+}
+
+impl From<HashLock> for proto::HashLock {
+    fn from(hash_lock: HashLock) -> Self {
+        Self {
+            hash: Some(hash_lock.hash.into()),
+            claim_commitment: Some(hash_lock.claim_commitment.into()),
+            refund_height: hash_lock.refund_height,
+            refund_commitment: Some(hash_lock.refund_commitment.into()),
         }
     }
 }
@@ -36,6 +36,8 @@ use crate::transactions::{
         HashDigest,
         HashOutput,
         MessageHash,
+        PrivateKey,
+        PublicKey,
         RangeProof,
         RangeProofService,
         Signature,
@@ -44,13 +46,20 @@ use crate::transactions::{
 use derive_error::Error;
 use digest::Input;
 use serde::{Deserialize, Serialize};
+use sha2::Sha256;
 use std::{
     cmp::{max, min, Ordering},
     fmt::{Display, Formatter},
 };
 use tari_crypto::{
     commitment::HomomorphicCommitmentFactory,
-    range_proof::{RangeProofError, RangeProofService as RangeProofServiceTrait},
+    range_proof::{
+        FullRewindResult,
+        RangeProofError,
+        RangeProofService as RangeProofServiceTrait,
+        RewindResult,
+        REWIND_PROOF_MESSAGE_LENGTH,
+    },
 };
 use tari_utilities::{hex::Hex, message_format::MessageFormat, ByteArray, Hashable};
 
@@ -69,6 +78,8 @@ bitflags! {
     pub struct KernelFeatures: u8 {
         /// Coinbase transaction
         const COINBASE_KERNEL = 1u8;
+        /// Hash lock claim, the kernel reveals the preimage that unlocks the hash locked outputs it spends
+        const HASH_LOCK = 2u8;
     }
 }
 
@@ -86,12 +97,20 @@ pub struct OutputFeatures {
     /// the maturity of the specific UTXO. This is the min lock height at which an UTXO can be spend. Coinbase UTXO
     /// require a min maturity of the Coinbase_lock_height, this should be checked on receiving new blocks.
     pub maturity: u64,
+    /// The hash lock that restricts how the output can be spent, if it is a hash time-locked output
+    #[serde(default)]
+    pub hash_lock: Option<HashLock>,
 }
 
 impl OutputFeatures {
+    /// The canonical byte representation of the features. The hash lock is only appended when there is one, so that
+    /// the hashes of outputs without a hash lock are the same as before hash locks were introduced.
     pub fn to_bytes(&self) -> Vec<u8> {
         let mut buf = Vec::new();
-        bincode::serialize_into(&mut buf, self).unwrap(); // this should not fail
+        bincode::serialize_into(&mut buf, &(&self.flags, self.maturity)).unwrap(); // this should not fail
+        if let Some(hash_lock) = &self.hash_lock {
+            bincode::serialize_into(&mut buf, hash_lock).unwrap();
+        }
         buf
     }
 
@@ -99,6 +118,7 @@ impl OutputFeatures {
         OutputFeatures {
             flags: OutputFlags::COINBASE_OUTPUT,
             maturity: maturity_height,
+            hash_lock: None,
         }
     }
 
@@ -109,6 +129,14 @@ impl OutputFeatures {
             ..OutputFeatures::default()
         }
     }
+
+    /// Create an `OutputFeatures` with the given hash lock and all other values at their default setting
+    pub fn with_hash_lock(hash_lock: HashLock) -> OutputFeatures {
+        OutputFeatures {
+            hash_lock: Some(hash_lock),
+            ..OutputFeatures::default()
+        }
+    }
 }
 
 impl Default for OutputFeatures {
@@ -116,6 +144,7 @@ impl Default for OutputFeatures {
         OutputFeatures {
             flags: OutputFlags::empty(),
             maturity: 0,
+            hash_lock: None,
         }
     }
 }
@@ -140,6 +169,39 @@ bitflags! {
     }
 }
 
+/// The length in bytes of the preimage that unlocks a hash lock
+pub const HASH_LOCK_PREIMAGE_LENGTH: usize = 32;
+
+/// The spending conditions of a hash time-locked output. Before the refund height the output can only be spent into
+/// the claim commitment, by a transaction with a hash lock kernel that reveals the preimage of the hash. From the
+/// refund height onwards it can also be spent into the refund commitment, by a transaction whose kernel is not valid
+/// before the refund height. Because the destinations are fixed, knowing the spending key of the output is not enough
+/// to take its funds.
+#[derive(Debug, Clone, Hash, PartialEq, Deserialize, Serialize, Eq)]
+pub struct HashLock {
+    /// The SHA-256 hash of the preimage, the same hash function that is used by hash time-locked contracts on other
+    /// chains so that one preimage can unlock both sides of an atomic swap
+    pub hash: HashOutput,
+    /// The commitment of the output that a claim must create
+    pub claim_commitment: Commitment,
+    /// The block height from which the output can be refunded
+    pub refund_height: u64,
+    /// The commitment of the output that a refund must create
+    pub refund_commitment: Commitment,
+}
+
+impl HashLock {
+    /// The SHA-256 hash of a hash lock preimage
+    pub fn hash_preimage(preimage: &[u8]) -> HashOutput {
+        <Sha256 as digest::Digest>::digest(preimage).to_vec()
+    }
+
+    /// Checks whether the given preimage unlocks this hash lock
+    pub fn is_unlocked_by(&self, preimage: &[u8]) -> bool {
+        preimage.len() == HASH_LOCK_PREIMAGE_LENGTH && HashLock::hash_preimage(preimage) == self.hash
+    }
+}
+
 //----------------------------------------     TransactionError   ----------------------------------------------------//
 
 #[derive(Clone, Debug, PartialEq, Error, Deserialize, Serialize)]
@@ -153,13 +215,26 @@ pub enum TransactionError {
     NoSignatureError,
     // A range proof construction or verification has produced an error
     RangeProofError(RangeProofError),
+    // A hash locked output was not spent according to its hash lock
+    InvalidHashLock,
+}
+
+//-----------------------------------------     RewindData   ---------------------------------------------------------//
+
+/// The keys and message used to construct a rewindable range proof. The owner of the rewind keys can later extract the
+/// value, message and spending key of the output from its range proof alone.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RewindData {
+    pub rewind_key: PrivateKey,
+    pub rewind_blinding_key: PrivateKey,
+    pub proof_message: [u8; REWIND_PROOF_MESSAGE_LENGTH],
 }
 
 //-----------------------------------------     UnblindedOutput   ----------------------------------------------------//
 
 /// An unblinded output is one where the value and spending key (blinding factor) are known. This can be used to
 /// build both inputs and outputs (every input comes from an output)
-#[derive(Debug, Clone, Hash)]
+#[derive(Debug, Clone, Hash, Serialize, Deserialize)]
 pub struct UnblindedOutput {
     pub value: MicroTari,
     pub spending_key: BlindingFactor,
@@ -202,6 +277,35 @@ impl UnblindedOutput {
         }
         Ok(output)
     }
+
+    /// Commits an UnblindedOutput into a TransactionOutput whose range proof can be rewound with the given rewind data
+    pub fn as_rewindable_transaction_output(
+        &self,
+        factories: &CryptoFactories,
+        rewind_data: &RewindData,
+    ) -> Result<TransactionOutput, TransactionError>
+    {
+        let commitment = factories.commitment.commit(&self.spending_key, &self.value.into());
+        let proof_bytes = factories.range_proof.construct_proof_with_rewind_key(
+            &self.spending_key,
+            self.value.into(),
+            &rewind_data.rewind_key,
+            &rewind_data.rewind_blinding_key,
+            &rewind_data.proof_message,
+        )?;
+        let output = TransactionOutput {
+            features: self.features.clone(),
+            commitment,
+            proof: RangeProof::from_bytes(&proof_bytes)
+                .map_err(|_| TransactionError::RangeProofError(RangeProofError::ProofConstructionError))?,
+        };
+        if !output.verify_range_proof(&factories.range_proof)? {
+            return Err(TransactionError::ValidationError(
+                "Range proof could not be verified".into(),
+            ));
+        }
+        Ok(output)
+    }
 }
 
 // These implementations are used for order these outputs for UTXO selection which will be done by comparing the values
@@ -322,6 +426,39 @@ impl TransactionOutput {
     pub fn verify_range_proof(&self, prover: &RangeProofService) -> Result<bool, TransactionError> {
         Ok(prover.verify(&self.proof.to_vec(), &self.commitment))
     }
+
+    /// Attempt to extract the value and proof message from a rewindable range proof using the public rewind keys
+    pub fn rewind_range_proof_value_only(
+        &self,
+        prover: &RangeProofService,
+        rewind_public_key: &PublicKey,
+        rewind_blinding_public_key: &PublicKey,
+    ) -> Result<RewindResult, TransactionError>
+    {
+        Ok(prover.rewind_proof_value_only(
+            &self.proof.to_vec(),
+            &self.commitment,
+            rewind_public_key,
+            rewind_blinding_public_key,
+        )?)
+    }
+
+    /// Attempt to extract the value, proof message and spending key from a rewindable range proof using the private
+    /// rewind keys
+    pub fn full_rewind_range_proof(
+        &self,
+        prover: &RangeProofService,
+        rewind_key: &PrivateKey,
+        rewind_blinding_key: &PrivateKey,
+    ) -> Result<FullRewindResult<PrivateKey>, TransactionError>
+    {
+        Ok(prover.rewind_proof_commitment_data(
+            &self.proof.to_vec(),
+            &self.commitment,
+            rewind_key,
+            rewind_blinding_key,
+        )?)
+    }
 }
 
 /// Implement the canonical hashing function for TransactionOutput for use in ordering.
@@ -391,6 +528,9 @@ pub struct TransactionKernel {
     /// The signature proving the excess is a valid public key, which signs
     /// the transaction fee.
     pub excess_sig: Signature,
+    /// The preimage revealed by a hash lock kernel to claim the hash locked outputs spent by its transaction
+    #[serde(default)]
+    pub hash_preimage: Option<Vec<u8>>,
 }
 
 /// A version of Transaction kernel with optional fields. This struct is only used in constructing transaction kernels
@@ -402,6 +542,7 @@ pub struct KernelBuilder {
     linked_kernel: Option<MessageHash>,
     excess: Option<Commitment>,
     excess_sig: Option<Signature>,
+    hash_preimage: Option<Vec<u8>>,
 }
 
 /// Implementation of the transaction kernel
@@ -451,6 +592,14 @@ impl KernelBuilder {
         self
     }
 
+    /// Reveal the preimage that claims the hash locked outputs spent by the transaction. This also sets the hash lock
+    /// kernel feature.
+    pub fn with_hash_preimage(mut self, preimage: Vec<u8>) -> KernelBuilder {
+        self.features |= KernelFeatures::HASH_LOCK;
+        self.hash_preimage = Some(preimage);
+        self
+    }
+
     pub fn build(self) -> Result<TransactionKernel, TransactionError> {
         if self.excess.is_none() || self.excess_sig.is_none() {
             return Err(TransactionError::NoSignatureError);
@@ -463,6 +612,7 @@ impl KernelBuilder {
             meta_info: self.meta_info,
             excess: self.excess.unwrap(),
             excess_sig: self.excess_sig.unwrap(),
+            hash_preimage: self.hash_preimage,
         })
     }
 }
@@ -477,6 +627,7 @@ impl Default for KernelBuilder {
             meta_info: None,
             excess: None,
             excess_sig: None,
+            hash_preimage: None,
         }
     }
 }
@@ -503,8 +654,9 @@ impl TransactionKernel {
 impl Hashable for TransactionKernel {
     /// Produce a canonical hash for a transaction kernel. The hash is given by
     /// $$ H(feature_bits | fee | lock_height | P_excess | R_sum | s_sum)
+    /// A hash preimage is only appended when there is one, so that the hashes of other kernels are unchanged.
     fn hash(&self) -> Vec<u8> {
-        HashDigest::new()
+        let mut hasher = HashDigest::new()
             .chain(&[self.features.bits])
             .chain(u64::from(self.fee).to_le_bytes())
             .chain(self.lock_height.to_le_bytes())
@@ -512,9 +664,11 @@ impl Hashable for TransactionKernel {
             .chain(self.excess_sig.get_public_nonce().as_bytes())
             .chain(self.excess_sig.get_signature().as_bytes())
             .chain(self.meta_info.as_ref().unwrap_or(&vec![0]))
-            .chain(self.linked_kernel.as_ref().unwrap_or(&vec![0]))
-            .result()
-            .to_vec()
+            .chain(self.linked_kernel.as_ref().unwrap_or(&vec![0]));
+        if let Some(preimage) = &self.hash_preimage {
+            hasher = hasher.chain(preimage);
+        }
+        hasher.result().to_vec()
     }
 }
 
@@ -522,7 +676,7 @@ impl Display for TransactionKernel {
     fn fmt(&self, fmt: &mut Formatter<'_>) -> Result<(), std::fmt::Error> {
         let msg = format!(
             "Fee: {}\nLock height: {}\nFeatures: {:?}\nExcess: {}\nExcess signature: {}\nMeta_info: \
-             {}\nLinked_kernel: {}\n",
+             {}\nLinked_kernel: {}\nHash_preimage: {}\n",
             self.fee,
             self.lock_height,
             self.features,
@@ -538,6 +692,10 @@ impl Display for TransactionKernel {
                 None => "None".to_string(),
                 Some(v) => v.to_hex(),
             },
+            match &self.hash_preimage {
+                None => "None".to_string(),
+                Some(v) => v.to_hex(),
+            },
         );
         fmt.write_str(&msg)
     }
@@ -683,12 +841,18 @@ impl TransactionBuilder {
         self
     }
 
-    /// Set the kernel of a transaction. Currently only one kernel is allowed per transaction
+    /// Set the kernel of a transaction, replacing any kernels that were added before
     pub fn with_kernel(&mut self, kernel: TransactionKernel) -> &mut Self {
         self.body.set_kernel(kernel);
         self
     }
 
+    /// Add a kernel to a transaction. A transaction with more than one recipient has a kernel for each of them.
+    pub fn add_kernel(&mut self, kernel: TransactionKernel) -> &mut Self {
+        self.body.add_kernel(kernel);
+        self
+    }
+
     pub fn with_reward(&mut self, reward: MicroTari) -> &mut Self {
         self.reward = Some(reward);
         self
@@ -730,7 +894,10 @@ mod test {
         types::{BlindingFactor, PrivateKey, PublicKey, RangeProof},
     };
     use rand;
-    use tari_crypto::{keys::SecretKey as SecretKeyTrait, ristretto::pedersen::PedersenCommitmentFactory};
+    use tari_crypto::{
+        keys::{PublicKey as PublicKeyTrait, SecretKey as SecretKeyTrait},
+        ristretto::pedersen::PedersenCommitmentFactory,
+    };
 
     #[test]
     fn unblinded_input() {
@@ -780,6 +947,47 @@ mod test {
         assert_eq!(tx_output3.verify_range_proof(&factories.range_proof).unwrap(), false);
     }
 
+    #[test]
+    fn rewind_range_proof() {
+        let mut rng = rand::OsRng::new().unwrap();
+        let factories = CryptoFactories::default();
+        let k = BlindingFactor::random(&mut rng);
+        let rewind_data = RewindData {
+            rewind_key: PrivateKey::random(&mut rng),
+            rewind_blinding_key: PrivateKey::random(&mut rng),
+            proof_message: [42u8; REWIND_PROOF_MESSAGE_LENGTH],
+        };
+        let unblinded_output = UnblindedOutput::new(5000.into(), k.clone(), None);
+        let output = unblinded_output
+            .as_rewindable_transaction_output(&factories, &rewind_data)
+            .unwrap();
+        assert!(output.verify_range_proof(&factories.range_proof).unwrap());
+
+        let rewind_public_key = PublicKey::from_secret_key(&rewind_data.rewind_key);
+        let rewind_blinding_public_key = PublicKey::from_secret_key(&rewind_data.rewind_blinding_key);
+        let result = output
+            .rewind_range_proof_value_only(&factories.range_proof, &rewind_public_key, &rewind_blinding_public_key)
+            .unwrap();
+        assert_eq!(result.committed_value, 5000);
+        assert_eq!(result.proof_message, rewind_data.proof_message);
+
+        let result = output
+            .full_rewind_range_proof(
+                &factories.range_proof,
+                &rewind_data.rewind_key,
+                &rewind_data.rewind_blinding_key,
+            )
+            .unwrap();
+        assert_eq!(result.committed_value, 5000);
+        assert_eq!(result.blinding_factor, k);
+
+        let wrong_key = PrivateKey::random(&mut rng);
+        assert_eq!(
+            output.full_rewind_range_proof(&factories.range_proof, &wrong_key, &rewind_data.rewind_blinding_key),
+            Err(TransactionError::RangeProofError(RangeProofError::InvalidRewind))
+        );
+    }
+
     #[test]
     fn kernel_hash() {
         let s = PrivateKey::from_hex("6c6eebc5a9c02e1f3c16a69ba4331f9f63d0718401dea10adc4f9d3b879a2c09").unwrap();
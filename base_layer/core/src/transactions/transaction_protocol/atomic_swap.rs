@@ -0,0 +1,438 @@
+// Copyright 2020, The Tari Project
+//
+// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+// following conditions are met:
+//
+// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+// disclaimer.
+//
+// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+// following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+// 3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+// products derived from this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+// INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+// SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+// WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+// USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+//! An atomic swap exchanges Tari for coins on another chain with a pair of hash time-locked contracts that are unlocked
+//! by the same secret preimage.
+//!
+//! The funder of the Tari side creates a hash locked output `C = k.G + v.H` whose `HashLock` fixes where its value can
+//! go: to the claim commitment of the claimer, by a transaction with a hash lock kernel that reveals the preimage of
+//! the hash, or back to the refund commitment of the funder, by a transaction with a kernel that is not valid before
+//! the refund height. The funder shares `k` with the claimer. Because consensus only accepts spends into the claim or
+//! refund commitment, and only the owners of those outputs can produce their range proofs, knowing `k` does not let
+//! either party take the other's funds.
+//!
+//! The claimer reveals the preimage on the Tari chain when it claims, and the funder can read it from the claim kernel
+//! to unlock the contract on the other chain. The refund height must therefore be well before the refund time of the
+//! contract on the other chain, so that the funder has time to claim there.
+
+use crate::transactions::{
+    tari_amount::MicroTari,
+    transaction::{
+        HashLock,
+        KernelBuilder,
+        RewindData,
+        Transaction,
+        TransactionBuilder,
+        TransactionKernel,
+        UnblindedOutput,
+    },
+    transaction_protocol::{build_challenge, TransactionMetadata, TransactionProtocolError},
+    types::{Commitment, CryptoFactories, HashOutput, PrivateKey, PublicKey, Signature},
+};
+use rand::OsRng;
+use tari_crypto::{
+    commitment::HomomorphicCommitmentFactory,
+    keys::{PublicKey as PK, SecretKey as SK},
+};
+
+/// A step of the protocol that sets up and settles the Tari side of an atomic swap. The comments note who sends each
+/// step to whom.
+#[derive(Clone, Debug, PartialEq)]
+pub enum AtomicSwapStep {
+    /// Funder to claimer: offers a hash locked output of the given value, with the hash and the refund height
+    Offer((MicroTari, HashOutput, u64)),
+    /// Claimer to funder: accepts the offer with the commitment of the output that a claim will create
+    Accept(Commitment),
+    /// Funder to claimer: the hash locked output has been funded with this spending key and refund commitment
+    Funded((PrivateKey, Commitment)),
+    /// Claimer to funder: the output has been claimed with this preimage
+    Claimed(Vec<u8>),
+    /// Either side: the swap was abandoned for the given reason
+    Cancelled(String),
+}
+
+/// A message of the atomic swap protocol about the swap with the given id
+#[derive(Clone, Debug, PartialEq)]
+pub struct AtomicSwapMessage {
+    pub swap_id: u64,
+    pub step: AtomicSwapStep,
+}
+
+impl AtomicSwapMessage {
+    pub fn new(swap_id: u64, step: AtomicSwapStep) -> Self {
+        Self { swap_id, step }
+    }
+}
+
+/// Build a transaction that claims the given hash locked output into the claim output by revealing the preimage of
+/// its hash. The claim output must be the output whose commitment the hash lock fixed, and the fee is the difference
+/// between the values of the two outputs.
+pub fn build_claim_transaction(
+    hash_locked: &UnblindedOutput,
+    claim_output: &UnblindedOutput,
+    preimage: Vec<u8>,
+    rewind_data: Option<&RewindData>,
+    factories: &CryptoFactories,
+) -> Result<Transaction, TransactionProtocolError>
+{
+    let hash_lock = hash_lock_of(hash_locked)?;
+    check_destination(&hash_lock.claim_commitment, claim_output, factories)?;
+    if !hash_lock.is_unlocked_by(&preimage) {
+        return Err(TransactionProtocolError::ValidationError(
+            "The preimage does not unlock the hash lock".to_string(),
+        ));
+    }
+    build_spend_transaction(hash_locked, claim_output, 0, Some(preimage), rewind_data, factories)
+}
+
+/// Build a transaction that refunds the given hash locked output into the refund output. The transaction cannot be
+/// mined before the refund height of the hash lock.
+pub fn build_refund_transaction(
+    hash_locked: &UnblindedOutput,
+    refund_output: &UnblindedOutput,
+    rewind_data: Option<&RewindData>,
+    factories: &CryptoFactories,
+) -> Result<Transaction, TransactionProtocolError>
+{
+    let hash_lock = hash_lock_of(hash_locked)?;
+    check_destination(&hash_lock.refund_commitment, refund_output, factories)?;
+    build_spend_transaction(
+        hash_locked,
+        refund_output,
+        hash_lock.refund_height,
+        None,
+        rewind_data,
+        factories,
+    )
+}
+
+/// Find the preimage of the given hash among the preimages revealed by hash lock kernels
+pub fn find_hash_preimage(kernels: &[TransactionKernel], hash: &HashOutput) -> Option<Vec<u8>> {
+    kernels
+        .iter()
+        .filter_map(|k| k.hash_preimage.as_ref())
+        .find(|preimage| &HashLock::hash_preimage(preimage) == hash)
+        .cloned()
+}
+
+fn hash_lock_of(hash_locked: &UnblindedOutput) -> Result<&HashLock, TransactionProtocolError> {
+    hash_locked
+        .features
+        .hash_lock
+        .as_ref()
+        .ok_or_else(|| TransactionProtocolError::ValidationError("The output is not hash locked".to_string()))
+}
+
+fn check_destination(
+    commitment: &Commitment,
+    output: &UnblindedOutput,
+    factories: &CryptoFactories,
+) -> Result<(), TransactionProtocolError>
+{
+    if &factories.commitment.commit(&output.spending_key, &output.value.into()) != commitment {
+        return Err(TransactionProtocolError::ValidationError(
+            "The output does not match the commitment of the hash lock".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+fn build_spend_transaction(
+    hash_locked: &UnblindedOutput,
+    output: &UnblindedOutput,
+    lock_height: u64,
+    preimage: Option<Vec<u8>>,
+    rewind_data: Option<&RewindData>,
+    factories: &CryptoFactories,
+) -> Result<Transaction, TransactionProtocolError>
+{
+    if output.value >= hash_locked.value {
+        return Err(TransactionProtocolError::ValidationError(
+            "The output does not leave a fee".to_string(),
+        ));
+    }
+    let metadata = TransactionMetadata {
+        fee: hash_locked.value - output.value,
+        lock_height,
+        meta_info: None,
+        linked_kernel: None,
+    };
+    let mut rng = OsRng::new().unwrap();
+    let offset = PrivateKey::random(&mut rng);
+    let nonce = PrivateKey::random(&mut rng);
+    let excess_key = &(&output.spending_key - &hash_locked.spending_key) - &offset;
+    let challenge = build_challenge(&PublicKey::from_secret_key(&nonce), &metadata);
+    let signature = Signature::sign(excess_key.clone(), nonce, &challenge)?;
+
+    let mut kernel = KernelBuilder::new()
+        .with_fee(metadata.fee)
+        .with_lock_height(metadata.lock_height)
+        .with_excess(&Commitment::from_public_key(&PublicKey::from_secret_key(&excess_key)))
+        .with_signature(&signature);
+    if let Some(preimage) = preimage {
+        kernel = kernel.with_hash_preimage(preimage);
+    }
+    let transaction_output = match rewind_data {
+        Some(rewind_data) => output.as_rewindable_transaction_output(factories, rewind_data)?,
+        None => output.as_transaction_output(factories)?,
+    };
+    let mut builder = TransactionBuilder::new();
+    builder
+        .add_input(hash_locked.as_transaction_input(&factories.commitment, hash_locked.features.clone()))
+        .add_output(transaction_output)
+        .add_offset(offset)
+        .with_kernel(kernel.build()?);
+    let tx = builder.build(factories)?;
+    tx.body.check_hash_locks()?;
+    Ok(tx)
+}
+
+#[cfg(test)]
+mod test {
+    use crate::transactions::{
+        aggregated_body::AggregateBody,
+        tari_amount::*,
+        transaction::{HashLock, OutputFeatures, TransactionError, UnblindedOutput},
+        transaction_protocol::atomic_swap::{build_claim_transaction, build_refund_transaction, find_hash_preimage},
+        types::{CryptoFactories, HashOutput, PrivateKey},
+    };
+    use rand::{OsRng, RngCore};
+    use std::collections::HashMap;
+    use tari_crypto::{commitment::HomomorphicCommitmentFactory, keys::SecretKey as SK};
+
+    /// A stand-in for the chain on the other side of the swap, which supports hash time-locked contracts that pay
+    /// the recipient when the preimage is revealed, or refund the sender after a timeout
+    #[derive(Default)]
+    struct CounterpartyChain {
+        height: u64,
+        contracts: HashMap<u64, (HashOutput, u64, &'static str, &'static str)>,
+        balances: HashMap<&'static str, u64>,
+        revealed: Vec<Vec<u8>>,
+    }
+
+    impl CounterpartyChain {
+        fn lock(&mut self, id: u64, hash: HashOutput, timeout: u64, sender: &'static str, recipient: &'static str) {
+            self.contracts.insert(id, (hash, timeout, sender, recipient));
+        }
+
+        fn claim(&mut self, id: u64, preimage: Vec<u8>) -> bool {
+            match self.contracts.get(&id) {
+                Some((hash, timeout, _, recipient))
+                    if self.height < *timeout && &HashLock::hash_preimage(&preimage) == hash =>
+                {
+                    *self.balances.entry(*recipient).or_insert(0) += 1;
+                    self.contracts.remove(&id);
+                    self.revealed.push(preimage);
+                    true
+                }
+                _ => false,
+            }
+        }
+
+        fn refund(&mut self, id: u64) -> bool {
+            match self.contracts.get(&id) {
+                Some((_, timeout, sender, _)) if self.height >= *timeout => {
+                    *self.balances.entry(*sender).or_insert(0) += 1;
+                    self.contracts.remove(&id);
+                    true
+                },
+                _ => false,
+            }
+        }
+    }
+
+    struct Swap {
+        preimage: Vec<u8>,
+        hash_locked: UnblindedOutput,
+        claim_output: UnblindedOutput,
+        refund_output: UnblindedOutput,
+    }
+
+    fn setup_swap(factories: &CryptoFactories, refund_height: u64) -> Swap {
+        let mut rng = OsRng::new().unwrap();
+        let mut preimage = vec![0u8; 32];
+        rng.fill_bytes(&mut preimage);
+        let value = MicroTari::from(10_000);
+        let claim_output = UnblindedOutput::new(value - MicroTari::from(100), PrivateKey::random(&mut rng), None);
+        let refund_output = UnblindedOutput::new(value - MicroTari::from(150), PrivateKey::random(&mut rng), None);
+        let hash_lock = HashLock {
+            hash: HashLock::hash_preimage(&preimage),
+            claim_commitment: factories
+                .commitment
+                .commit(&claim_output.spending_key, &claim_output.value.into()),
+            refund_height,
+            refund_commitment: factories
+                .commitment
+                .commit(&refund_output.spending_key, &refund_output.value.into()),
+        };
+        let hash_locked = UnblindedOutput::new(
+            value,
+            PrivateKey::random(&mut rng),
+            Some(OutputFeatures::with_hash_lock(hash_lock)),
+        );
+        Swap {
+            preimage,
+            hash_locked,
+            claim_output,
+            refund_output,
+        }
+    }
+
+    #[test]
+    fn swap_is_claimed_on_both_chains() {
+        let factories = CryptoFactories::default();
+        let swap = setup_swap(&factories, 100);
+        let hash = swap.hash_locked.features.hash_lock.as_ref().unwrap().hash.clone();
+        // The claimer, who generated the preimage, locks its coins on the other chain for longer than the refund
+        // height of the Tari side
+        let mut chain = CounterpartyChain::default();
+        chain.lock(1, hash.clone(), 200, "claimer", "funder");
+
+        // The claimer claims the Tari side, which reveals the preimage in the claim kernel
+        let wrong_preimage = vec![1u8; 32];
+        assert!(build_claim_transaction(
+            &swap.hash_locked,
+            &swap.claim_output,
+            wrong_preimage.clone(),
+            None,
+            &factories
+        )
+        .is_err());
+        assert!(build_claim_transaction(
+            &swap.hash_locked,
+            &swap.refund_output,
+            swap.preimage.clone(),
+            None,
+            &factories
+        )
+        .is_err());
+        let tx = build_claim_transaction(
+            &swap.hash_locked,
+            &swap.claim_output,
+            swap.preimage.clone(),
+            None,
+            &factories,
+        )
+        .unwrap();
+        assert!(tx.validate_internal_consistency(&factories, None).is_ok());
+        assert!(tx.body.check_hash_locks().is_ok());
+        assert_eq!(tx.body.get_total_fee(), MicroTari::from(100));
+        assert_eq!(tx.min_spendable_height(), 0);
+
+        // The funder reads the preimage from the claim kernel and claims the other side with it
+        assert_eq!(find_hash_preimage(tx.body.kernels(), &vec![0u8; 32]), None);
+        let preimage = find_hash_preimage(tx.body.kernels(), &hash).unwrap();
+        assert!(!chain.claim(1, wrong_preimage));
+        assert!(chain.claim(1, preimage));
+        assert_eq!(chain.balances.get("funder"), Some(&1));
+        assert_eq!(chain.revealed, vec![swap.preimage]);
+    }
+
+    #[test]
+    fn swap_is_refunded_on_both_chains() {
+        let factories = CryptoFactories::default();
+        let swap = setup_swap(&factories, 100);
+        let hash = swap.hash_locked.features.hash_lock.as_ref().unwrap().hash.clone();
+        let mut chain = CounterpartyChain::default();
+        chain.lock(1, hash, 200, "claimer", "funder");
+
+        // The claimer never claims, so the funder refunds the Tari side once the refund height is reached
+        assert!(build_refund_transaction(&swap.hash_locked, &swap.claim_output, None, &factories).is_err());
+        let tx = build_refund_transaction(&swap.hash_locked, &swap.refund_output, None, &factories).unwrap();
+        assert!(tx.body.check_hash_locks().is_ok());
+        assert_eq!(tx.body.kernels()[0].lock_height, 100);
+        assert_eq!(tx.body.get_total_fee(), MicroTari::from(150));
+        assert!(tx.body.kernels()[0].hash_preimage.is_none());
+
+        // The claimer refunds the other side after its longer timeout
+        chain.height = 150;
+        assert!(!chain.refund(1));
+        chain.height = 200;
+        assert!(chain.refund(1));
+        assert_eq!(chain.balances.get("claimer"), Some(&1));
+    }
+
+    #[test]
+    fn hash_locks_are_enforced() {
+        let factories = CryptoFactories::default();
+        let swap = setup_swap(&factories, 100);
+        let claim = build_claim_transaction(
+            &swap.hash_locked,
+            &swap.claim_output,
+            swap.preimage.clone(),
+            None,
+            &factories,
+        )
+        .unwrap();
+        let refund = build_refund_transaction(&swap.hash_locked, &swap.refund_output, None, &factories).unwrap();
+
+        // A claim that does not create the claim output is rejected
+        let (inputs, _, kernels) = claim.body.clone().dissolve();
+        let refund_output = swap.refund_output.as_transaction_output(&factories).unwrap();
+        let other_output = AggregateBody::new(inputs, vec![refund_output], kernels);
+        assert_eq!(other_output.check_hash_locks(), Err(TransactionError::InvalidHashLock));
+
+        // A claim without the preimage is rejected, as are kernels whose hash lock feature and preimage do not match
+        let mut kernel = claim.body.kernels()[0].clone();
+        kernel.hash_preimage = Some(vec![2u8; 32]);
+        let mut wrong_preimage = claim.clone();
+        wrong_preimage.body.set_kernel(kernel.clone());
+        assert_eq!(
+            wrong_preimage.body.check_hash_locks(),
+            Err(TransactionError::InvalidHashLock)
+        );
+        kernel.hash_preimage = None;
+        let mut missing_preimage = claim.clone();
+        missing_preimage.body.set_kernel(kernel.clone());
+        assert_eq!(
+            missing_preimage.body.check_hash_locks(),
+            Err(TransactionError::InvalidHashLock)
+        );
+
+        // Hash lock kernels only carry preimages of the required length, that unlock a hash locked output they spend
+        let mut long_preimage = swap.preimage.clone();
+        long_preimage.push(0);
+        kernel.hash_preimage = Some(long_preimage);
+        let mut wrong_length = claim.clone();
+        wrong_length.body.set_kernel(kernel);
+        assert_eq!(
+            wrong_length.body.check_hash_locks(),
+            Err(TransactionError::InvalidHashLock)
+        );
+        let (_, outputs, kernels) = claim.body.clone().dissolve();
+        let nothing_unlocked = AggregateBody::new(Vec::new(), outputs, kernels);
+        assert_eq!(
+            nothing_unlocked.check_hash_locks(),
+            Err(TransactionError::InvalidHashLock)
+        );
+
+        // A refund whose kernel is valid before the refund height is rejected
+        let mut kernel = refund.body.kernels()[0].clone();
+        kernel.lock_height = 99;
+        let mut early_refund = refund.clone();
+        early_refund.body.set_kernel(kernel);
+        assert_eq!(
+            early_refund.body.check_hash_locks(),
+            Err(TransactionError::InvalidHashLock)
+        );
+    }
+}
@@ -13,6 +13,11 @@
 //! illustrates the progression of the two state machines and shows where the public data messages are constructed and
 //! accepted in each state machine
 //!
+//! A transaction can pay more than one Receiver. In that case the transaction has a kernel for each Receiver, which is
+//! signed with a share of the Sender's excess (`X_i`) and a nonce (`R_i`) that the Sender uses for that kernel only.
+//! Every Receiver therefore completes its part of the transaction in a single round, with its own `tx_id`, and the
+//! Sender finalizes the transaction once all of them have replied.
+//!
 //! <div class="mermaid">
 //!   sequenceDiagram
 //!   participant Sender
@@ -23,24 +28,17 @@
 //!   deactivate Sender
 //! #
 //!   activate Sender
-//!   Sender-->>+Receivers: [tx_id, amount_i]
-//!   note left of Sender: CollectingPubKeys
-//!   note right of Receivers: Initialization
-//!   Receivers-->>-Sender: [tx_id, Pi, Ri]
+//!   Sender-->>+Receivers: [tx_id_i, amount_i, X_i, R_i, metadata_i]
+//!   note left of Sender: CollectingSignatures
+//!   note right of Receivers: Signing
+//!   Receivers-->>Receivers: create output and sign
+//!   Receivers-->>-Sender: [tx_id_i, Output_i, P_i, s_i]
 //!   deactivate Sender
 //! #
 //!   alt invalid
 //!   Sender--XSender: failed
 //!   end
 //! #
-//!   activate Sender
-//!   Sender-->>+Receivers: [tx_id, ΣR, ΣP]
-//!   note left of Sender: CollectingSignatures
-//!   note right of Receivers: Signing
-//!   Receivers-->>Receivers: create output and sign
-//!   Receivers-->>-Sender: [tx_id, Output_i, s_i]
-//!   deactivate Sender
-//! #
 //!   note left of Sender: Finalizing
 //!   alt is_valid()
 //!   Sender-->>Sender: Finalized
@@ -49,9 +47,11 @@
 //!   end
 //! </div>
 
+pub mod atomic_swap;
 pub mod proto;
 pub mod recipient;
 pub mod sender;
+pub mod shared_output;
 pub mod single_receiver;
 pub mod transaction_initializer;
 
@@ -92,6 +92,9 @@ pub enum TransactionProtocolError {
     UnsupportedError(String),
     /// There has been an error serializing or deserializing this structure
     SerializationError,
+    /// The keys, nonces or signatures of the participants of a MuSig shared output could not be combined
+    #[error(msg_embedded, no_from, non_std)]
+    MuSigError(String),
 }
 
 /// Transaction metadata, including the fee and lock height
@@ -0,0 +1,90 @@
+// Copyright 2020, The Tari Project
+//
+// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+// following conditions are met:
+//
+// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+// disclaimer.
+//
+// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+// following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+// 3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+// products derived from this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+// INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+// SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+// WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+// USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+use super::protocol as proto;
+
+use crate::transactions::{
+    transaction_protocol::atomic_swap::{AtomicSwapMessage, AtomicSwapStep},
+    types::{Commitment, PrivateKey},
+};
+use proto::atomic_swap_message::Step as ProtoAtomicSwapStep;
+use std::convert::TryFrom;
+
+impl TryFrom<proto::AtomicSwapMessage> for AtomicSwapMessage {
+    type Error = String;
+
+    fn try_from(message: proto::AtomicSwapMessage) -> Result<Self, Self::Error> {
+        let step = match message.step.ok_or("AtomicSwapMessage.step not provided".to_string())? {
+            ProtoAtomicSwapStep::Offer(offer) => {
+                AtomicSwapStep::Offer((offer.value.into(), offer.hash, offer.refund_height))
+            },
+            ProtoAtomicSwapStep::Accept(commitment) => {
+                AtomicSwapStep::Accept(Commitment::try_from(commitment).map_err(|err| err.to_string())?)
+            },
+            ProtoAtomicSwapStep::Funded(funded) => {
+                let spending_key = funded
+                    .spending_key
+                    .ok_or("AtomicSwapFunded.spending_key not provided".to_string())?;
+                let refund_commitment = funded
+                    .refund_commitment
+                    .ok_or("AtomicSwapFunded.refund_commitment not provided".to_string())?;
+                AtomicSwapStep::Funded((
+                    PrivateKey::try_from(spending_key).map_err(|err| err.to_string())?,
+                    Commitment::try_from(refund_commitment).map_err(|err| err.to_string())?,
+                ))
+            },
+            ProtoAtomicSwapStep::Claimed(preimage) => AtomicSwapStep::Claimed(preimage),
+            ProtoAtomicSwapStep::Cancelled(reason) => AtomicSwapStep::Cancelled(reason),
+        };
+
+        Ok(Self {
+            swap_id: message.swap_id,
+            step,
+        })
+    }
+}
+
+impl From<AtomicSwapMessage> for proto::AtomicSwapMessage {
+    fn from(message: AtomicSwapMessage) -> Self {
+        let step = match message.step {
+            AtomicSwapStep::Offer((value, hash, refund_height)) => ProtoAtomicSwapStep::Offer(proto::AtomicSwapOffer {
+                value: value.into(),
+                hash,
+                refund_height,
+            }),
+            AtomicSwapStep::Accept(commitment) => ProtoAtomicSwapStep::Accept(commitment.into()),
+            AtomicSwapStep::Funded((spending_key, refund_commitment)) => {
+                ProtoAtomicSwapStep::Funded(proto::AtomicSwapFunded {
+                    spending_key: Some(spending_key.into()),
+                    refund_commitment: Some(refund_commitment.into()),
+                })
+            },
+            AtomicSwapStep::Claimed(preimage) => ProtoAtomicSwapStep::Claimed(preimage),
+            AtomicSwapStep::Cancelled(reason) => ProtoAtomicSwapStep::Cancelled(reason),
+        };
+
+        Self {
+            swap_id: message.swap_id,
+            step: Some(step),
+        }
+    }
+}
@@ -26,7 +26,9 @@ pub mod protocol {
     tari_utilities::include_proto_package!("tari.transaction_protocol");
 }
 
+pub mod atomic_swap;
 pub mod recipient_signed_message;
+pub mod shared_output;
 pub mod transaction_metadata;
 pub mod transaction_sender;
 
@@ -0,0 +1,121 @@
+// Copyright 2019, The Tari Project
+//
+// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+// following conditions are met:
+//
+// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+// disclaimer.
+//
+// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+// following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+// 3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+// products derived from this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+// INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+// SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+// WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+// USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+use super::protocol as proto;
+
+use crate::transactions::{
+    transaction_protocol::shared_output::{SharedOutputMessage, SharedOutputStep},
+    types::{Commitment, PrivateKey, PublicKey, Signature},
+};
+use proto::shared_output_message::Step as ProtoSharedOutputStep;
+use std::convert::TryFrom;
+use tari_utilities::ByteArray;
+
+impl TryFrom<proto::SharedOutputMessage> for SharedOutputMessage {
+    type Error = String;
+
+    fn try_from(message: proto::SharedOutputMessage) -> Result<Self, Self::Error> {
+        let step = match message
+            .step
+            .ok_or("SharedOutputMessage.step not provided".to_string())?
+        {
+            ProtoSharedOutputStep::Proposal(proposal) => {
+                SharedOutputStep::Proposal((proposal.value.into(), public_keys_from_bytes(&proposal.participants)?))
+            },
+            ProtoSharedOutputStep::PublicKeyShare(key) => {
+                SharedOutputStep::PublicKeyShare(PublicKey::from_bytes(&key).map_err(|err| err.to_string())?)
+            },
+            ProtoSharedOutputStep::PublicKeys(keys) => {
+                SharedOutputStep::PublicKeys(public_keys_from_bytes(&keys.keys)?)
+            },
+            ProtoSharedOutputStep::KeyShare(share) => {
+                SharedOutputStep::KeyShare(PrivateKey::try_from(share).map_err(|err| err.to_string())?)
+            },
+            ProtoSharedOutputStep::Created(commitment) => {
+                SharedOutputStep::Created(Commitment::try_from(commitment).map_err(|err| err.to_string())?)
+            },
+            ProtoSharedOutputStep::SpendRequest(metadata) => SharedOutputStep::SpendRequest(metadata.into()),
+            ProtoSharedOutputStep::NonceCommitment(commitment) => SharedOutputStep::NonceCommitment(commitment),
+            ProtoSharedOutputStep::RevealNonces(_) => SharedOutputStep::RevealNonces,
+            ProtoSharedOutputStep::PublicNonce(nonce) => {
+                SharedOutputStep::PublicNonce(PublicKey::from_bytes(&nonce).map_err(|err| err.to_string())?)
+            },
+            ProtoSharedOutputStep::SignRequest(nonces) => {
+                SharedOutputStep::SignRequest(public_keys_from_bytes(&nonces.keys)?)
+            },
+            ProtoSharedOutputStep::PartialSignature(signature) => {
+                SharedOutputStep::PartialSignature(Signature::try_from(signature).map_err(|err| err.to_string())?)
+            },
+            ProtoSharedOutputStep::Spent(_) => SharedOutputStep::Spent,
+            ProtoSharedOutputStep::Cancelled(reason) => SharedOutputStep::Cancelled(reason),
+        };
+
+        Ok(Self {
+            output_id: message.output_id,
+            step,
+        })
+    }
+}
+
+impl From<SharedOutputMessage> for proto::SharedOutputMessage {
+    fn from(message: SharedOutputMessage) -> Self {
+        let step = match message.step {
+            SharedOutputStep::Proposal((value, participants)) => {
+                ProtoSharedOutputStep::Proposal(proto::SharedOutputProposal {
+                    value: value.into(),
+                    participants: participants.iter().map(|p| p.to_vec()).collect(),
+                })
+            },
+            SharedOutputStep::PublicKeyShare(key) => ProtoSharedOutputStep::PublicKeyShare(key.to_vec()),
+            SharedOutputStep::PublicKeys(keys) => ProtoSharedOutputStep::PublicKeys(keys.into()),
+            SharedOutputStep::KeyShare(share) => ProtoSharedOutputStep::KeyShare(share.into()),
+            SharedOutputStep::Created(commitment) => ProtoSharedOutputStep::Created(commitment.into()),
+            SharedOutputStep::SpendRequest(metadata) => ProtoSharedOutputStep::SpendRequest(metadata.into()),
+            SharedOutputStep::NonceCommitment(commitment) => ProtoSharedOutputStep::NonceCommitment(commitment),
+            SharedOutputStep::RevealNonces => ProtoSharedOutputStep::RevealNonces(true),
+            SharedOutputStep::PublicNonce(nonce) => ProtoSharedOutputStep::PublicNonce(nonce.to_vec()),
+            SharedOutputStep::SignRequest(nonces) => ProtoSharedOutputStep::SignRequest(nonces.into()),
+            SharedOutputStep::PartialSignature(signature) => ProtoSharedOutputStep::PartialSignature(signature.into()),
+            SharedOutputStep::Spent => ProtoSharedOutputStep::Spent(true),
+            SharedOutputStep::Cancelled(reason) => ProtoSharedOutputStep::Cancelled(reason),
+        };
+
+        Self {
+            output_id: message.output_id,
+            step: Some(step),
+        }
+    }
+}
+
+impl From<Vec<PublicKey>> for proto::SharedOutputKeys {
+    fn from(keys: Vec<PublicKey>) -> Self {
+        Self {
+            keys: keys.iter().map(|k| k.to_vec()).collect(),
+        }
+    }
+}
+
+fn public_keys_from_bytes(keys: &[Vec<u8>]) -> Result<Vec<PublicKey>, String> {
+    keys.iter()
+        .map(|k| PublicKey::from_bytes(k).map_err(|err| err.to_string()))
+        .collect()
+}
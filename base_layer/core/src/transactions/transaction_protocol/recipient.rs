@@ -90,11 +90,44 @@ impl ReceiverTransactionProtocol {
         features: OutputFeatures,
         factories: &CryptoFactories,
     ) -> ReceiverTransactionProtocol
+    {
+        ReceiverTransactionProtocol::new_with_optional_rewind(info, nonce, spending_key, features, factories, None)
+    }
+
+    /// As for `new`, but the recipient's output is given a range proof that can be rewound with `rewind_data`, so that
+    /// the output can be recovered from the blockchain.
+    pub fn new_with_rewindable_output(
+        info: TransactionSenderMessage,
+        nonce: PrivateKey,
+        spending_key: PrivateKey,
+        features: OutputFeatures,
+        factories: &CryptoFactories,
+        rewind_data: &RewindData,
+    ) -> ReceiverTransactionProtocol
+    {
+        ReceiverTransactionProtocol::new_with_optional_rewind(
+            info,
+            nonce,
+            spending_key,
+            features,
+            factories,
+            Some(rewind_data),
+        )
+    }
+
+    fn new_with_optional_rewind(
+        info: TransactionSenderMessage,
+        nonce: PrivateKey,
+        spending_key: PrivateKey,
+        features: OutputFeatures,
+        factories: &CryptoFactories,
+        rewind_data: Option<&RewindData>,
+    ) -> ReceiverTransactionProtocol
     {
         let state = match info {
             TransactionSenderMessage::None => RecipientState::Failed(TransactionProtocolError::InvalidStateError),
             TransactionSenderMessage::Single(v) => {
-                ReceiverTransactionProtocol::single_round(nonce, spending_key, features, &v, factories)
+                ReceiverTransactionProtocol::single_round(nonce, spending_key, features, &v, factories, rewind_data)
             },
             TransactionSenderMessage::Multiple => Self::multi_round(),
         };
@@ -140,9 +173,20 @@ impl ReceiverTransactionProtocol {
         features: OutputFeatures,
         data: &SD,
         factories: &CryptoFactories,
+        rewind_data: Option<&RewindData>,
     ) -> RecipientState
     {
-        let signer = SingleReceiverTransactionProtocol::create(data, nonce, key, features, factories);
+        let signer = match rewind_data {
+            Some(rewind_data) => SingleReceiverTransactionProtocol::create_with_rewindable_output(
+                data,
+                nonce,
+                key,
+                features,
+                factories,
+                rewind_data,
+            ),
+            None => SingleReceiverTransactionProtocol::create(data, nonce, key, features, factories),
+        };
         match signer {
             Ok(signed_data) => RecipientState::Finalized(Box::new(signed_data)),
             Err(e) => RecipientState::Failed(e),
@@ -76,6 +76,9 @@ pub(super) struct RawTransactionInfo {
     pub recipient_info: RecipientInfo,
     pub signatures: Vec<Signature>,
     pub message: String,
+    // The kernel signed with each recipient when there is more than one
+    #[serde(default)]
+    pub recipient_kernels: Vec<RecipientKernelInfo>,
 }
 
 impl RawTransactionInfo {
@@ -85,6 +88,23 @@ impl RawTransactionInfo {
     }
 }
 
+/// A transaction with more than one recipient has a kernel for each of them. The sender signs each kernel with a share
+/// of its excess and a nonce of its own, so every recipient can complete its part of the transaction in a single round,
+/// exactly as it would for a single-recipient transaction.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub(super) struct RecipientKernelInfo {
+    pub tx_id: u64,
+    pub amount: MicroTari,
+    // The metadata of this kernel. The kernel fees sum to the transaction fee.
+    pub metadata: TransactionMetadata,
+    // The sender's share of the offset blinding factor
+    pub excess_share: BlindingFactor,
+    pub public_excess: PublicKey,
+    pub private_nonce: PrivateKey,
+    pub public_nonce: PublicKey,
+    pub recipient_data: Option<RecipientSignedMessage>,
+}
+
 #[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
 pub struct SingleRoundSenderData {
     /// The transaction id for the recipient
@@ -138,6 +158,23 @@ impl SenderTransactionProtocol {
         }
     }
 
+    /// Convenience method to check whether we're receiving data from the recipients of a multi-recipient transaction
+    pub fn is_collecting_multiple_signatures(&self) -> bool {
+        match &self.state {
+            SenderState::CollectingMultipleSignatures(_) => true,
+            _ => false,
+        }
+    }
+
+    /// Convenience method to check whether we're ready to send messages to the recipients of a multi-recipient
+    /// transaction
+    pub fn is_multi_recipient_messages_ready(&self) -> bool {
+        match &self.state {
+            SenderState::MultiRecipientMessagesReady(_) => true,
+            _ => false,
+        }
+    }
+
     /// Method to determine if we are in the SenderState::Finalizing state
     pub fn is_finalizing(&self) -> bool {
         match &self.state {
@@ -187,21 +224,27 @@ impl SenderTransactionProtocol {
         }
     }
 
-    /// Method to check if the provided tx_id matches this transaction
+    /// Method to check if the provided tx_id matches this transaction, or the part of it paying one of its recipients
     pub fn check_tx_id(&self, tx_id: u64) -> bool {
         match &self.state {
             SenderState::Finalizing(info) |
             SenderState::SingleRoundMessageReady(info) |
-            SenderState::CollectingSingleSignature(info) => info.ids[0] == tx_id,
+            SenderState::CollectingSingleSignature(info) |
+            SenderState::MultiRecipientMessagesReady(info) |
+            SenderState::CollectingMultipleSignatures(info) => info.ids.contains(&tx_id),
             _ => false,
         }
     }
 
+    /// Returns the tx_id of this transaction. A multi-recipient transaction takes the tx_id of its first recipient. A
+    /// transaction without recipients, such as a payment to self, has no tx_id and an error is returned.
     pub fn get_tx_id(&self) -> Result<u64, TPE> {
         match &self.state {
             SenderState::Finalizing(info) |
             SenderState::SingleRoundMessageReady(info) |
-            SenderState::CollectingSingleSignature(info) => Ok(info.ids[0]),
+            SenderState::CollectingSingleSignature(info) |
+            SenderState::MultiRecipientMessagesReady(info) |
+            SenderState::CollectingMultipleSignatures(info) => info.ids.first().cloned().ok_or(TPE::InvalidStateError),
             _ => Err(TPE::InvalidStateError),
         }
     }
@@ -211,7 +254,9 @@ impl SenderTransactionProtocol {
             SenderState::Initializing(info) |
             SenderState::Finalizing(info) |
             SenderState::SingleRoundMessageReady(info) |
-            SenderState::CollectingSingleSignature(info) => Ok(info.amounts.iter().sum()),
+            SenderState::CollectingSingleSignature(info) |
+            SenderState::MultiRecipientMessagesReady(info) |
+            SenderState::CollectingMultipleSignatures(info) => Ok(info.amounts.iter().sum()),
             SenderState::FinalizedTransaction(_) => Err(TPE::InvalidStateError),
             SenderState::Failed(_) => Err(TPE::InvalidStateError),
         }
@@ -223,7 +268,9 @@ impl SenderTransactionProtocol {
             SenderState::Initializing(info) |
             SenderState::Finalizing(info) |
             SenderState::SingleRoundMessageReady(info) |
-            SenderState::CollectingSingleSignature(info) => Ok(info.amount_to_self),
+            SenderState::CollectingSingleSignature(info) |
+            SenderState::MultiRecipientMessagesReady(info) |
+            SenderState::CollectingMultipleSignatures(info) => Ok(info.amount_to_self),
             SenderState::FinalizedTransaction(_) => Err(TPE::InvalidStateError),
             SenderState::Failed(_) => Err(TPE::InvalidStateError),
         }
@@ -235,7 +282,9 @@ impl SenderTransactionProtocol {
             SenderState::Initializing(info) |
             SenderState::Finalizing(info) |
             SenderState::SingleRoundMessageReady(info) |
-            SenderState::CollectingSingleSignature(info) => Ok(info.change),
+            SenderState::CollectingSingleSignature(info) |
+            SenderState::MultiRecipientMessagesReady(info) |
+            SenderState::CollectingMultipleSignatures(info) => Ok(info.change),
             SenderState::FinalizedTransaction(_) => Err(TPE::InvalidStateError),
             SenderState::Failed(_) => Err(TPE::InvalidStateError),
         }
@@ -247,7 +296,9 @@ impl SenderTransactionProtocol {
             SenderState::Initializing(info) |
             SenderState::Finalizing(info) |
             SenderState::SingleRoundMessageReady(info) |
-            SenderState::CollectingSingleSignature(info) => Ok(info.metadata.fee),
+            SenderState::CollectingSingleSignature(info) |
+            SenderState::MultiRecipientMessagesReady(info) |
+            SenderState::CollectingMultipleSignatures(info) => Ok(info.metadata.fee),
             SenderState::FinalizedTransaction(_) => Err(TPE::InvalidStateError),
             SenderState::Failed(_) => Err(TPE::InvalidStateError),
         }
@@ -257,16 +308,27 @@ impl SenderTransactionProtocol {
     pub fn build_single_round_message(&mut self) -> Result<SingleRoundSenderData, TPE> {
         match &self.state {
             SenderState::SingleRoundMessageReady(info) => {
-                let result = SingleRoundSenderData {
+                let result = self.get_single_round_message()?;
+                self.state = SenderState::CollectingSingleSignature(info.clone());
+                Ok(result)
+            },
+            _ => Err(TPE::InvalidStateError),
+        }
+    }
+
+    /// The sender's message for the single-round protocol, which is also available while waiting for the recipient's
+    /// reply so that it can be sent again
+    pub fn get_single_round_message(&self) -> Result<SingleRoundSenderData, TPE> {
+        match &self.state {
+            SenderState::SingleRoundMessageReady(info) | SenderState::CollectingSingleSignature(info) => {
+                Ok(SingleRoundSenderData {
                     tx_id: info.ids[0],
-                    amount: self.get_total_amount().unwrap(),
+                    amount: self.get_total_amount()?,
                     public_nonce: info.public_nonce.clone(),
                     public_excess: info.public_excess.clone(),
                     metadata: info.metadata.clone(),
                     message: info.message.clone(),
-                };
-                self.state = SenderState::CollectingSingleSignature(info.clone());
-                Ok(result)
+                })
             },
             _ => Err(TPE::InvalidStateError),
         }
@@ -299,6 +361,90 @@ impl SenderTransactionProtocol {
         }
     }
 
+    /// Build the sender's message for each recipient of a multi-recipient transaction and move to the next State. Each
+    /// message carries its recipient's tx_id and amount, and is answered exactly as a single-round message would be.
+    pub fn build_multi_recipient_messages(&mut self) -> Result<Vec<SingleRoundSenderData>, TPE> {
+        match &self.state {
+            SenderState::MultiRecipientMessagesReady(info) => {
+                let messages = self.get_multi_recipient_messages()?;
+                self.state = SenderState::CollectingMultipleSignatures(info.clone());
+                Ok(messages)
+            },
+            _ => Err(TPE::InvalidStateError),
+        }
+    }
+
+    /// The sender's message for each recipient of a multi-recipient transaction, which are also available while
+    /// waiting for the recipients' replies so that they can be sent again
+    pub fn get_multi_recipient_messages(&self) -> Result<Vec<SingleRoundSenderData>, TPE> {
+        match &self.state {
+            SenderState::MultiRecipientMessagesReady(info) | SenderState::CollectingMultipleSignatures(info) => {
+                Ok(info
+                    .recipient_kernels
+                    .iter()
+                    .map(|k| SingleRoundSenderData {
+                        tx_id: k.tx_id,
+                        amount: k.amount,
+                        public_nonce: k.public_nonce.clone(),
+                        public_excess: k.public_excess.clone(),
+                        metadata: k.metadata.clone(),
+                        message: info.message.clone(),
+                    })
+                    .collect())
+            },
+            _ => Err(TPE::InvalidStateError),
+        }
+    }
+
+    /// Add the signed data from one of the recipients of a multi-recipient transaction. Once every recipient has
+    /// replied, the protocol moves to the Finalizing state.
+    pub fn add_multi_recipient_info(
+        &mut self,
+        rec: RecipientSignedMessage,
+        prover: &RangeProofService,
+    ) -> Result<(), TPE>
+    {
+        match &mut self.state {
+            SenderState::CollectingMultipleSignatures(info) => {
+                let kernel = info
+                    .recipient_kernels
+                    .iter_mut()
+                    .find(|k| k.tx_id == rec.tx_id)
+                    .ok_or_else(|| TPE::ValidationError("Recipient tx_id is not part of this transaction".into()))?;
+                if kernel.recipient_data.is_some() {
+                    return Err(TPE::ValidationError("Recipient has already replied".into()));
+                }
+                if !rec.output.verify_range_proof(prover)? {
+                    return Err(TPE::ValidationError(
+                        "Recipient output range proof failed to verify".into(),
+                    ));
+                }
+                info.outputs.push(rec.output.clone());
+                kernel.recipient_data = Some(rec);
+                if info.recipient_kernels.iter().all(|k| k.recipient_data.is_some()) {
+                    self.state = SenderState::Finalizing(info.clone());
+                }
+                Ok(())
+            },
+            _ => Err(TPE::InvalidStateError),
+        }
+    }
+
+    /// The tx_ids of the recipients of a multi-recipient transaction that have not replied yet
+    pub fn get_recipients_awaiting_reply(&self) -> Result<Vec<u64>, TPE> {
+        match &self.state {
+            SenderState::MultiRecipientMessagesReady(info) | SenderState::CollectingMultipleSignatures(info) => {
+                Ok(info
+                    .recipient_kernels
+                    .iter()
+                    .filter(|k| k.recipient_data.is_none())
+                    .map(|k| k.tx_id)
+                    .collect())
+            },
+            _ => Err(TPE::InvalidStateError),
+        }
+    }
+
     /// Attempts to build the final transaction.
     fn build_transaction(
         info: &RawTransactionInfo,
@@ -315,6 +461,25 @@ impl SenderTransactionProtocol {
             tx_builder.add_output(o.clone());
         }
         tx_builder.add_offset(info.offset.clone());
+        if !info.recipient_kernels.is_empty() {
+            // Each recipient's kernel has the signature that was aggregated for it when signing
+            for (k, s) in info.recipient_kernels.iter().zip(info.signatures.iter()) {
+                let rec = k
+                    .recipient_data
+                    .as_ref()
+                    .ok_or_else(|| TPE::IncompleteStateError("A recipient has not replied".into()))?;
+                let excess = PedersenCommitment::from_public_key(&(&k.public_excess + &rec.public_spend_key));
+                let kernel = KernelBuilder::new()
+                    .with_fee(k.metadata.fee)
+                    .with_features(features)
+                    .with_lock_height(k.metadata.lock_height)
+                    .with_excess(&excess)
+                    .with_signature(s)
+                    .build()?;
+                tx_builder.add_kernel(kernel);
+            }
+            return tx_builder.build(factories).map_err(TPE::from);
+        }
         let mut s_agg = info.signatures[0].clone();
         info.signatures.iter().skip(1).for_each(|s| s_agg = &s_agg + s);
         let excess = PedersenCommitment::from_public_key(&info.public_excess);
@@ -354,7 +519,13 @@ impl SenderTransactionProtocol {
             if info.inputs.is_empty() {
                 return Err(TPE::ValidationError("A transaction cannot have zero inputs".into()));
             }
-            if info.signatures.len() != 1 + info.num_recipients {
+            // A multi-recipient transaction has one aggregated signature per kernel
+            let num_signatures = if info.recipient_kernels.is_empty() {
+                1 + info.num_recipients
+            } else {
+                info.num_recipients
+            };
+            if info.signatures.len() != num_signatures {
                 return Err(TPE::ValidationError(format!(
                     "Incorrect number of signatures ({})",
                     info.signatures.len()
@@ -369,6 +540,22 @@ impl SenderTransactionProtocol {
     /// Produce the sender's partial signature
     fn sign(&mut self) -> Result<(), TPE> {
         match &mut self.state {
+            SenderState::Finalizing(info) if !info.recipient_kernels.is_empty() => {
+                // Sign each recipient's kernel with the sender's share of the excess, and add the recipient's partial
+                // signature to it
+                for k in &info.recipient_kernels {
+                    let rec = k
+                        .recipient_data
+                        .as_ref()
+                        .ok_or_else(|| TPE::IncompleteStateError("A recipient has not replied".into()))?;
+                    let nonce_sum = &k.public_nonce + rec.partial_signature.get_public_nonce();
+                    let e = build_challenge(&nonce_sum, &k.metadata);
+                    let s = Signature::sign(k.excess_share.clone(), k.private_nonce.clone(), &e)
+                        .map_err(TPE::SigningError)?;
+                    info.signatures.push(&s + &rec.partial_signature);
+                }
+                Ok(())
+            },
             SenderState::Finalizing(info) => {
                 let e = build_challenge(&info.public_nonce_sum, &info.metadata);
                 let k = info.offset_blinding_factor.clone();
@@ -426,12 +613,13 @@ impl SenderTransactionProtocol {
     }
 
     /// This method is used to store a pending transaction to be sent which should be in the CollectionSingleSignature
-    /// state, This state will be serialized and returned as a string.
+    /// or CollectingMultipleSignatures state, This state will be serialized and returned as a string.
     pub fn save_pending_transaction_to_be_sent(&self) -> Result<String, TPE> {
         match &self.state {
             SenderState::Initializing(_) => Err(TPE::InvalidStateError),
             SenderState::SingleRoundMessageReady(_) => Err(TPE::InvalidStateError),
-            SenderState::CollectingSingleSignature(s) => {
+            SenderState::MultiRecipientMessagesReady(_) => Err(TPE::InvalidStateError),
+            SenderState::CollectingSingleSignature(s) | SenderState::CollectingMultipleSignatures(s) => {
                 let data = serde_json::to_string(s).map_err(|_| TPE::SerializationError)?;
                 Ok(data)
             },
@@ -445,9 +633,12 @@ impl SenderTransactionProtocol {
     /// Transaction from it.
     pub fn load_pending_transaction_to_be_sent(data: String) -> Result<Self, TPE> {
         let raw_data: RawTransactionInfo = serde_json::from_str(data.as_str()).map_err(|_| TPE::SerializationError)?;
-        Ok(Self {
-            state: SenderState::CollectingSingleSignature(Box::new(raw_data)),
-        })
+        let state = if raw_data.recipient_kernels.is_empty() {
+            SenderState::CollectingSingleSignature(Box::new(raw_data))
+        } else {
+            SenderState::CollectingMultipleSignatures(Box::new(raw_data))
+        };
+        Ok(Self { state })
     }
 }
 
@@ -469,6 +660,10 @@ pub(super) enum SenderState {
     SingleRoundMessageReady(Box<RawTransactionInfo>),
     /// Waiting for the signed transaction data in the single-round protocol
     CollectingSingleSignature(Box<RawTransactionInfo>),
+    /// The messages for each recipient of a multi-recipient transaction are ready
+    MultiRecipientMessagesReady(Box<RawTransactionInfo>),
+    /// Waiting for the signed transaction data from every recipient of a multi-recipient transaction
+    CollectingMultipleSignatures(Box<RawTransactionInfo>),
     /// The final transaction state is being validated - it will automatically transition to Failed or Finalized from
     /// here
     Finalizing(Box<RawTransactionInfo>),
@@ -486,9 +681,7 @@ impl SenderState {
             SenderState::Initializing(info) => match info.num_recipients {
                 0 => Ok(SenderState::Finalizing(info)),
                 1 => Ok(SenderState::SingleRoundMessageReady(info)),
-                _ => Ok(SenderState::Failed(TPE::UnsupportedError(
-                    "Multiple recipients are not supported yet".into(),
-                ))),
+                _ => Ok(SenderState::MultiRecipientMessagesReady(info)),
             },
             _ => Err(TPE::InvalidTransitionError),
         }
@@ -534,6 +727,8 @@ mod test {
         let mut sender = builder.build::<Blake256>(&factories).unwrap();
         assert_eq!(sender.is_failed(), false);
         assert!(sender.is_finalizing());
+        // There is no recipient to assign a tx_id to
+        assert!(sender.get_tx_id().is_err());
         match sender.finalize(KernelFeatures::empty(), &factories) {
             Ok(true) => (),
             Ok(false) => panic!("{:?}", sender.failure_reason()),
@@ -567,6 +762,8 @@ mod test {
         let msg = alice.build_single_round_message().unwrap();
         // Send message down the wire....and wait for response
         assert!(alice.is_collecting_single_signature());
+        // The message can be sent again while waiting
+        assert_eq!(alice.get_single_round_message().unwrap(), msg);
 
         // Test serializing the current state to be sent and resuming from that serialized data
         let ser = alice.save_pending_transaction_to_be_sent().unwrap();
@@ -677,6 +874,101 @@ mod test {
         assert!(tx.clone().validate_internal_consistency(&factories, None).is_ok());
     }
 
+    #[test]
+    fn multiple_recipients() {
+        let factories = CryptoFactories::default();
+        let mut rng = OsRng::new().unwrap();
+        // Alice's parameters
+        let a = TestParams::new();
+        // Bob's and Carol's parameters
+        let b = TestParams::new();
+        let c = TestParams::new();
+        let (utxo, input) = make_input(&mut rng, MicroTari(5000), &factories.commitment);
+        let mut builder = SenderTransactionProtocol::builder(2);
+        let fee = Fee::calculate(MicroTari(20), 1, 3);
+        builder
+            .with_lock_height(0)
+            .with_fee_per_gram(MicroTari(20))
+            .with_offset(a.offset.clone())
+            .with_private_nonce(a.nonce.clone())
+            .with_change_secret(a.change_key.clone())
+            .with_input(utxo.clone(), input)
+            .with_amount(0, MicroTari(500))
+            .with_amount(1, MicroTari(700));
+        let mut alice = builder.build::<Blake256>(&factories).unwrap();
+        assert!(alice.is_multi_recipient_messages_ready());
+        let msgs = alice.build_multi_recipient_messages().unwrap();
+        assert_eq!(msgs.len(), 2);
+        assert_eq!(msgs[0].amount, MicroTari(500));
+        assert_eq!(msgs[1].amount, MicroTari(700));
+        assert_eq!(alice.get_tx_id().unwrap(), msgs[0].tx_id);
+        assert!(alice.check_tx_id(msgs[1].tx_id));
+        // Send messages down the wire....and wait for responses
+        assert!(alice.is_collecting_multiple_signatures());
+        assert_eq!(alice.get_multi_recipient_messages().unwrap(), msgs);
+        assert_eq!(alice.get_recipients_awaiting_reply().unwrap(), vec![
+            msgs[0].tx_id,
+            msgs[1].tx_id
+        ]);
+
+        // Each receiver gets its message and replies exactly as it would to a single-recipient transaction
+        let bob_info = SingleReceiverTransactionProtocol::create(
+            &msgs[0],
+            b.nonce,
+            b.spend_key,
+            OutputFeatures::default(),
+            &factories,
+        )
+        .unwrap();
+        let carol_info = SingleReceiverTransactionProtocol::create(
+            &msgs[1],
+            c.nonce,
+            c.spend_key,
+            OutputFeatures::default(),
+            &factories,
+        )
+        .unwrap();
+        // Alice gets Carol's reply first
+        alice
+            .add_multi_recipient_info(carol_info.clone(), &factories.range_proof)
+            .unwrap();
+        assert!(alice.is_collecting_multiple_signatures());
+        assert_eq!(alice.get_recipients_awaiting_reply().unwrap(), vec![msgs[0].tx_id]);
+        assert_eq!(
+            alice.add_multi_recipient_info(carol_info.clone(), &factories.range_proof),
+            Err(TransactionProtocolError::ValidationError(
+                "Recipient has already replied".into()
+            ))
+        );
+
+        // Test serializing the current state while waiting for Bob and resuming from that serialized data
+        let ser = alice.save_pending_transaction_to_be_sent().unwrap();
+        let mut alice = SenderTransactionProtocol::load_pending_transaction_to_be_sent(ser).unwrap();
+        assert!(alice.is_collecting_multiple_signatures());
+
+        alice
+            .add_multi_recipient_info(bob_info.clone(), &factories.range_proof)
+            .unwrap();
+        // Transaction should be complete
+        assert!(alice.is_finalizing());
+        match alice.finalize(KernelFeatures::empty(), &factories) {
+            Ok(true) => (),
+            Ok(false) => panic!("{:?}", alice.failure_reason()),
+            Err(e) => panic!("{:?}", e),
+        };
+
+        assert!(alice.is_finalized());
+        let tx = alice.get_transaction().unwrap();
+        assert_eq!(tx.offset, a.offset);
+        assert_eq!(tx.body.kernels().len(), 2);
+        assert_eq!(tx.body.get_total_fee(), fee);
+        assert_eq!(tx.body.inputs().len(), 1);
+        assert_eq!(tx.body.outputs().len(), 3);
+        assert!(tx.body.outputs().contains(&bob_info.output));
+        assert!(tx.body.outputs().contains(&carol_info.output));
+        assert!(tx.clone().validate_internal_consistency(&factories, None).is_ok());
+    }
+
     #[test]
     fn single_recipient_range_proof_fail() {
         let factories = CryptoFactories::new(32);
@@ -0,0 +1,542 @@
+// Copyright 2020. The Tari Project
+//
+// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+// following conditions are met:
+//
+// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+// disclaimer.
+//
+// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+// following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+// 3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+// products derived from this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+// INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+// SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+// WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+// USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+//! A shared output is an output that is created by a trusted dealer, its initiator, and whose spends are signed by all
+//! of its participants together. **A shared output is not N-of-N custody:** the initiator learns the whole spending key
+//! of the output and can spend it on its own.
+//!
+//! Every participant `i` holds a secret key share `x_i` with public key `P_i`. The joint key
+//! `X = sum(a_i.P_i)`, with MuSig coefficients `a_i = H(H(P_1 || ... || P_n) || P_i)`, is built with
+//! `JointKeyBuilder` and is the blinding factor of the shared output's commitment `C = X + v.H`.
+//!
+//! # The initiator is a trusted dealer
+//!
+//! Creating the output needs a range proof, and the range proof needs the whole blinding factor. The range proof
+//! service cannot construct a proof from key shares, so the participants send their weighted key shares `a_i.x_i` to
+//! the initiator, who deals the blinding factor with `dealer_blinding_factor` and funds the output. The initiator is
+//! trusted to discard the blinding factor afterwards, which the other participants cannot check, so a shared output is
+//! only as safe as its initiator is trustworthy. A shared output without a trusted dealer needs a range proof that the
+//! participants construct together from their key shares, which the range proof service does not support.
+//!
+//! Provided the initiator has discarded the blinding factor, the output can only be spent by all of the participants
+//! together.
+//!
+//! A spending transaction pays the value of the shared output, less the fee, to an output of the coordinator of the
+//! spend, with spending key `k` and offset `o`. Its excess is `E = (k - o).G - X` and is signed in three rounds:
+//! 1. Every participant commits to a random nonce `r_i` by sending the hash of its public nonce `R_i`.
+//! 1. Once the coordinator has all of the commitments, the participants reveal their public nonces, which the
+//!    coordinator checks against the commitments.
+//! 1. Every participant signs with its weighted key share: `s_i = r_i - e.a_i.x_i`, where `e` is the kernel challenge
+//!    for `R = sum(R_i)` and the transaction metadata. The coordinator adds `e.(k - o)` to its own partial signature.
+//!
+//! The sum of the partial signatures `(R, s)` is the kernel signature for `E`. `RistrettoMuSig` is not used to run
+//! the rounds, because it signs a challenge that includes the joint public key, while a kernel signature has to
+//! verify against the challenge that `build_challenge` produces for the kernel.
+
+use crate::transactions::{
+    tari_amount::MicroTari,
+    transaction::{KernelBuilder, Transaction, TransactionBuilder, TransactionInput, TransactionOutput},
+    transaction_protocol::{build_challenge, TransactionMetadata, TransactionProtocolError},
+    types::{
+        BlindingFactor,
+        Commitment,
+        CommitmentFactory,
+        CryptoFactories,
+        HashDigest,
+        MessageHash,
+        PrivateKey,
+        PublicKey,
+        Signature,
+    },
+};
+use digest::Digest;
+use tari_crypto::{
+    commitment::{HomomorphicCommitment, HomomorphicCommitmentFactory},
+    keys::PublicKey as PK,
+    musig::{JointKey, JointKeyBuilder},
+};
+use tari_utilities::ByteArray;
+
+/// The MuSig joint key of the participants of a shared output
+pub type SharedOutputJointKey = JointKey<PublicKey, PrivateKey>;
+
+/// A step of the protocol that creates and spends a shared output. The comments note who sends each step to whom.
+#[derive(Clone, Debug, PartialEq)]
+pub enum SharedOutputStep {
+    /// Initiator to participants: proposes a shared output of the given value between the comms public keys
+    Proposal((MicroTari, Vec<PublicKey>)),
+    /// Participant to initiator: the public key of the participant's key share
+    PublicKeyShare(PublicKey),
+    /// Initiator to participants: the public key shares of all participants, in the order of the proposal
+    PublicKeys(Vec<PublicKey>),
+    /// Participant to initiator: the participant's key share weighted by its MuSig coefficient
+    KeyShare(PrivateKey),
+    /// Initiator to participants: the commitment of the funded shared output
+    Created(Commitment),
+    /// Coordinator to participants: asks for a joint signature spending the output with this kernel metadata
+    SpendRequest(TransactionMetadata),
+    /// Participant to coordinator: the hash of the participant's public nonce
+    NonceCommitment(MessageHash),
+    /// Coordinator to participants: every nonce commitment has been received
+    RevealNonces,
+    /// Participant to coordinator: the participant's public nonce
+    PublicNonce(PublicKey),
+    /// Coordinator to participants: the public nonces of all participants, in the order of the proposal
+    SignRequest(Vec<PublicKey>),
+    /// Participant to coordinator: the participant's partial signature of the kernel
+    PartialSignature(Signature),
+    /// Coordinator to participants: the spending transaction has been broadcast
+    Spent,
+    /// Either side: the round in progress was abandoned for the given reason
+    Cancelled(String),
+}
+
+/// A message of the shared output protocol about the shared output with the given id
+#[derive(Clone, Debug, PartialEq)]
+pub struct SharedOutputMessage {
+    pub output_id: u64,
+    pub step: SharedOutputStep,
+}
+
+impl SharedOutputMessage {
+    pub fn new(output_id: u64, step: SharedOutputStep) -> Self {
+        Self { output_id, step }
+    }
+}
+
+/// Build the MuSig joint key of the given public key shares. The keys are sorted, so the joint key does not depend on
+/// the order they are given in.
+pub fn build_joint_key(public_keys: &[PublicKey]) -> Result<SharedOutputJointKey, TransactionProtocolError> {
+    let mut builder = JointKeyBuilder::new(public_keys.len()).map_err(musig_error)?;
+    builder.add_keys(public_keys.iter().cloned()).map_err(musig_error)?;
+    builder.build::<HashDigest>().map_err(musig_error)
+}
+
+/// The key share `a_i.x_i` of the participant with the given public key share, weighted by its MuSig coefficient
+pub fn weighted_key_share(
+    joint_key: &SharedOutputJointKey,
+    public_key: &PublicKey,
+    key_share: &PrivateKey,
+) -> Result<PrivateKey, TransactionProtocolError>
+{
+    let index = joint_key.index_of(public_key).map_err(musig_error)?;
+    Ok(joint_key.get_musig_scalar(index) * key_share)
+}
+
+/// Check that a weighted key share received from the participant with the given public key share is `a_i.x_i`
+pub fn verify_weighted_key_share(
+    joint_key: &SharedOutputJointKey,
+    public_key: &PublicKey,
+    weighted_share: &PrivateKey,
+) -> Result<bool, TransactionProtocolError>
+{
+    let index = joint_key.index_of(public_key).map_err(musig_error)?;
+    Ok(PublicKey::from_secret_key(weighted_share) == joint_key.get_musig_scalar(index) * public_key)
+}
+
+/// Combine the weighted key shares of all of the participants into the blinding factor of the shared output. Only the
+/// trusted dealer of the shared output calls this.
+///
+/// **Warning:** the blinding factor is the whole spending key of the shared output. Whoever holds it can spend the
+/// output without the other participants, so the dealer must discard it as soon as the output has been funded. See the
+/// module documentation.
+pub fn dealer_blinding_factor(
+    joint_key: &SharedOutputJointKey,
+    weighted_shares: &[PrivateKey],
+) -> Result<BlindingFactor, TransactionProtocolError>
+{
+    let blinding_factor = weighted_shares
+        .iter()
+        .fold(BlindingFactor::default(), |acc, share| &acc + share);
+    if &PublicKey::from_secret_key(&blinding_factor) != joint_key.get_joint_pubkey() {
+        return Err(TransactionProtocolError::MuSigError(
+            "The key shares do not add up to the joint key".to_string(),
+        ));
+    }
+    Ok(blinding_factor)
+}
+
+/// The commitment `X + v.H` of a shared output with the given joint key and value. Participants that do not learn the
+/// blinding factor check the commitment of the funded output against it.
+pub fn shared_output_commitment(
+    joint_key: &SharedOutputJointKey,
+    value: MicroTari,
+    factory: &CommitmentFactory,
+) -> Commitment
+{
+    let value_commitment = factory.commit_value(&PrivateKey::default(), value.into());
+    Commitment::from_public_key(&(joint_key.get_joint_pubkey() + value_commitment.as_public_key()))
+}
+
+/// The commitment to a public nonce that is sent in the first signing round
+pub fn nonce_commitment(public_nonce: &PublicKey) -> MessageHash {
+    HashDigest::new().chain(public_nonce.as_bytes()).result().to_vec()
+}
+
+/// Sign the kernel of a spend of a shared output with a participant's key share and nonce, once the public nonces of
+/// all of the participants are known. The coordinator of the spend also passes the `excess_key` `k - o` of the output
+/// it receives, which is added to its partial signature.
+pub fn partial_signature(
+    joint_key: &SharedOutputJointKey,
+    public_key: &PublicKey,
+    key_share: &PrivateKey,
+    nonce: &PrivateKey,
+    public_nonces: &[PublicKey],
+    metadata: &TransactionMetadata,
+    excess_key: Option<&PrivateKey>,
+) -> Result<Signature, TransactionProtocolError>
+{
+    if !public_nonces.contains(&PublicKey::from_secret_key(nonce)) {
+        return Err(TransactionProtocolError::MuSigError(
+            "Our public nonce is not one of the nonces to sign with".to_string(),
+        ));
+    }
+    let challenge = build_challenge(&sum_public_keys(public_nonces), metadata);
+    let secret = excess_key.cloned().unwrap_or_default() - weighted_key_share(joint_key, public_key, key_share)?;
+    Ok(Signature::sign(secret, nonce.clone(), &challenge)?)
+}
+
+/// The coordinator's state of the joint signature that spends a shared output. It collects the nonce commitments,
+/// public nonces and partial signatures of the participants, and then builds the spending transaction.
+pub struct SharedOutputSpend {
+    joint_key: SharedOutputJointKey,
+    own_public_key: PublicKey,
+    metadata: TransactionMetadata,
+    nonce_commitments: Vec<Option<MessageHash>>,
+    public_nonces: Vec<Option<PublicKey>>,
+    partial_signatures: Vec<Option<Signature>>,
+}
+
+impl SharedOutputSpend {
+    /// Start a spend of the shared output with the given joint key, coordinated by the participant with the given
+    /// public key share
+    pub fn new(
+        joint_key: SharedOutputJointKey,
+        own_public_key: PublicKey,
+        metadata: TransactionMetadata,
+    ) -> Result<Self, TransactionProtocolError>
+    {
+        joint_key.index_of(&own_public_key).map_err(musig_error)?;
+        let n = joint_key.size();
+        Ok(Self {
+            joint_key,
+            own_public_key,
+            metadata,
+            nonce_commitments: vec![None; n],
+            public_nonces: vec![None; n],
+            partial_signatures: vec![None; n],
+        })
+    }
+
+    pub fn metadata(&self) -> &TransactionMetadata {
+        &self.metadata
+    }
+
+    /// Record the nonce commitment of a participant. A participant cannot change its commitment.
+    pub fn add_nonce_commitment(
+        &mut self,
+        public_key: &PublicKey,
+        commitment: MessageHash,
+    ) -> Result<(), TransactionProtocolError>
+    {
+        let index = self.joint_key.index_of(public_key).map_err(musig_error)?;
+        match &self.nonce_commitments[index] {
+            Some(c) if c != &commitment => Err(TransactionProtocolError::InvalidTransitionError),
+            _ => {
+                self.nonce_commitments[index] = Some(commitment);
+                Ok(())
+            },
+        }
+    }
+
+    /// Whether every participant has committed to its nonce, so that the nonces can be revealed
+    pub fn has_all_nonce_commitments(&self) -> bool {
+        self.nonce_commitments.iter().all(Option::is_some)
+    }
+
+    /// Record the public nonce of a participant, which has to match its commitment
+    pub fn add_public_nonce(
+        &mut self,
+        public_key: &PublicKey,
+        nonce: PublicKey,
+    ) -> Result<(), TransactionProtocolError>
+    {
+        if !self.has_all_nonce_commitments() {
+            return Err(TransactionProtocolError::InvalidStateError);
+        }
+        let index = self.joint_key.index_of(public_key).map_err(musig_error)?;
+        if self.nonce_commitments[index] != Some(nonce_commitment(&nonce)) {
+            return Err(TransactionProtocolError::MuSigError(
+                "The public nonce does not match the nonce commitment".to_string(),
+            ));
+        }
+        self.public_nonces[index] = Some(nonce);
+        Ok(())
+    }
+
+    /// The public nonces of all participants, in the order of the joint key, once they have all been revealed
+    pub fn public_nonces(&self) -> Option<Vec<PublicKey>> {
+        self.public_nonces.iter().cloned().collect()
+    }
+
+    /// Record the partial signature of a participant other than the coordinator, after checking that it is
+    /// `s_i.G = R_i - e.a_i.P_i`
+    pub fn add_partial_signature(
+        &mut self,
+        public_key: &PublicKey,
+        signature: Signature,
+    ) -> Result<(), TransactionProtocolError>
+    {
+        let public_nonces = self
+            .public_nonces()
+            .ok_or(TransactionProtocolError::InvalidStateError)?;
+        let index = self.joint_key.index_of(public_key).map_err(musig_error)?;
+        if public_key == &self.own_public_key || signature.get_public_nonce() != &public_nonces[index] {
+            return Err(TransactionProtocolError::MuSigError(format!(
+                "Unexpected partial signature for participant {}",
+                index
+            )));
+        }
+        let challenge = build_challenge(&sum_public_keys(&public_nonces), &self.metadata);
+        let e = PrivateKey::from_bytes(&challenge).map_err(|_| TransactionProtocolError::InvalidSignatureError)?;
+        let weighted_key = self.joint_key.get_musig_scalar(index) * public_key;
+        if &signature.calc_signature_verifier() + &(&e * &weighted_key) != public_nonces[index] {
+            return Err(TransactionProtocolError::MuSigError(format!(
+                "Invalid partial signature for participant {}",
+                index
+            )));
+        }
+        self.partial_signatures[index] = Some(signature);
+        Ok(())
+    }
+
+    /// Whether every participant other than the coordinator has provided a valid partial signature
+    pub fn has_all_partial_signatures(&self) -> bool {
+        let own_index = self.joint_key.index_of(&self.own_public_key).ok();
+        self.partial_signatures
+            .iter()
+            .enumerate()
+            .all(|(i, s)| s.is_some() || Some(i) == own_index)
+    }
+
+    /// Build the transaction that spends the shared output `input` to the coordinator's `output`, with spending key
+    /// `k`, and the `offset` `o`. The coordinator's own partial signature must include the excess key `k - o`.
+    pub fn build_transaction(
+        &self,
+        own_signature: Signature,
+        input: TransactionInput,
+        output: TransactionOutput,
+        offset: BlindingFactor,
+        excess_key: &PrivateKey,
+        factories: &CryptoFactories,
+    ) -> Result<Transaction, TransactionProtocolError>
+    {
+        if !self.has_all_partial_signatures() {
+            return Err(TransactionProtocolError::IncompleteStateError(
+                "Not all partial signatures have been received".to_string(),
+            ));
+        }
+        let public_nonces = self
+            .public_nonces()
+            .ok_or(TransactionProtocolError::InvalidStateError)?;
+        let signature = self
+            .partial_signatures
+            .iter()
+            .filter_map(Option::as_ref)
+            .fold(own_signature, |acc, s| &acc + s);
+        let excess = PublicKey::from_secret_key(excess_key) - self.joint_key.get_joint_pubkey();
+        let challenge = build_challenge(&sum_public_keys(&public_nonces), &self.metadata);
+        if !signature.verify_challenge(&excess, &challenge) {
+            return Err(TransactionProtocolError::InvalidSignatureError);
+        }
+
+        let kernel = KernelBuilder::new()
+            .with_fee(self.metadata.fee)
+            .with_lock_height(self.metadata.lock_height)
+            .with_excess(&Commitment::from_public_key(&excess))
+            .with_signature(&signature)
+            .build()?;
+        let mut builder = TransactionBuilder::new();
+        builder
+            .add_input(input)
+            .add_output(output)
+            .add_offset(offset)
+            .with_kernel(kernel);
+        Ok(builder.build(factories)?)
+    }
+}
+
+fn sum_public_keys(keys: &[PublicKey]) -> PublicKey {
+    keys.iter().fold(PublicKey::default(), |acc, k| &acc + k)
+}
+
+fn musig_error<E: std::fmt::Display>(e: E) -> TransactionProtocolError {
+    TransactionProtocolError::MuSigError(e.to_string())
+}
+
+#[cfg(test)]
+mod test {
+    use crate::transactions::{
+        tari_amount::*,
+        transaction::{OutputFeatures, UnblindedOutput},
+        transaction_protocol::{
+            shared_output::{
+                build_joint_key,
+                dealer_blinding_factor,
+                nonce_commitment,
+                partial_signature,
+                shared_output_commitment,
+                verify_weighted_key_share,
+                weighted_key_share,
+                SharedOutputSpend,
+            },
+            TransactionMetadata,
+        },
+        types::{CryptoFactories, PrivateKey, PublicKey},
+    };
+    use rand::OsRng;
+    use tari_crypto::keys::{PublicKey as PK, SecretKey as SK};
+
+    #[test]
+    fn create_and_spend_shared_output() {
+        let factories = CryptoFactories::default();
+        let mut rng = OsRng::new().unwrap();
+        let key_shares: Vec<PrivateKey> = (0..3).map(|_| PrivateKey::random(&mut rng)).collect();
+        let public_keys: Vec<PublicKey> = key_shares.iter().map(PublicKey::from_secret_key).collect();
+        let joint_key = build_joint_key(&public_keys).unwrap();
+        assert_eq!(
+            build_joint_key(&public_keys.iter().rev().cloned().collect::<Vec<_>>())
+                .unwrap()
+                .get_joint_pubkey(),
+            joint_key.get_joint_pubkey()
+        );
+
+        // The initiator, as the dealer, combines the weighted key shares into the blinding factor of the shared output
+        let weighted_shares: Vec<PrivateKey> = key_shares
+            .iter()
+            .zip(public_keys.iter())
+            .map(|(k, p)| weighted_key_share(&joint_key, p, k).unwrap())
+            .collect();
+        for (share, p) in weighted_shares.iter().zip(public_keys.iter()) {
+            assert!(verify_weighted_key_share(&joint_key, p, share).unwrap());
+        }
+        assert!(!verify_weighted_key_share(&joint_key, &public_keys[0], &weighted_shares[1]).unwrap());
+        assert!(dealer_blinding_factor(&joint_key, &weighted_shares[1..]).is_err());
+        let blinding_factor = dealer_blinding_factor(&joint_key, &weighted_shares).unwrap();
+        let value = MicroTari::from(10_000);
+        let input = UnblindedOutput::new(value, blinding_factor, None)
+            .as_transaction_input(&factories.commitment, OutputFeatures::default());
+        assert_eq!(
+            shared_output_commitment(&joint_key, value, &factories.commitment),
+            input.commitment
+        );
+
+        // Participant 0 coordinates a spend to an output of its own
+        let metadata = TransactionMetadata {
+            fee: MicroTari::from(100),
+            lock_height: 0,
+            meta_info: None,
+            linked_kernel: None,
+        };
+        let mut spend = SharedOutputSpend::new(joint_key, public_keys[0].clone(), metadata.clone()).unwrap();
+        let nonces: Vec<PrivateKey> = (0..3).map(|_| PrivateKey::random(&mut rng)).collect();
+        let public_nonces: Vec<PublicKey> = nonces.iter().map(PublicKey::from_secret_key).collect();
+        assert!(spend
+            .add_public_nonce(&public_keys[0], public_nonces[0].clone())
+            .is_err());
+        for (p, r) in public_keys.iter().zip(public_nonces.iter()) {
+            spend.add_nonce_commitment(p, nonce_commitment(r)).unwrap();
+        }
+        assert!(spend.has_all_nonce_commitments());
+        assert!(spend
+            .add_public_nonce(&public_keys[1], public_nonces[2].clone())
+            .is_err());
+        for (p, r) in public_keys.iter().zip(public_nonces.iter()) {
+            spend.add_public_nonce(p, r.clone()).unwrap();
+        }
+        let sign_nonces = spend.public_nonces().unwrap();
+
+        let joint_key = build_joint_key(&public_keys).unwrap();
+        for i in 1..3 {
+            let s = partial_signature(
+                &joint_key,
+                &public_keys[i],
+                &key_shares[i],
+                &nonces[i],
+                &sign_nonces,
+                &metadata,
+                None,
+            )
+            .unwrap();
+            assert!(spend
+                .add_partial_signature(&public_keys[(i % 2) + 1], s.clone())
+                .is_err());
+            spend.add_partial_signature(&public_keys[i], s).unwrap();
+        }
+        assert!(spend.has_all_partial_signatures());
+
+        let output_key = PrivateKey::random(&mut rng);
+        let offset = PrivateKey::random(&mut rng);
+        let excess_key = &output_key - &offset;
+        let output = UnblindedOutput::new(value - metadata.fee, output_key, None)
+            .as_transaction_output(&factories)
+            .unwrap();
+        let own_signature = partial_signature(
+            &joint_key,
+            &public_keys[0],
+            &key_shares[0],
+            &nonces[0],
+            &sign_nonces,
+            &metadata,
+            Some(&excess_key),
+        )
+        .unwrap();
+
+        // Leaving out the excess key of the output produces an invalid kernel signature
+        let unadjusted = partial_signature(
+            &joint_key,
+            &public_keys[0],
+            &key_shares[0],
+            &nonces[0],
+            &sign_nonces,
+            &metadata,
+            None,
+        )
+        .unwrap();
+        assert!(spend
+            .build_transaction(
+                unadjusted,
+                input.clone(),
+                output.clone(),
+                offset.clone(),
+                &excess_key,
+                &factories
+            )
+            .is_err());
+
+        let tx = spend
+            .build_transaction(own_signature, input, output, offset, &excess_key, &factories)
+            .unwrap();
+        assert!(tx.body.kernels()[0].verify_signature().is_ok());
+        assert!(tx.validate_internal_consistency(&factories, None).is_ok());
+    }
+}
@@ -21,7 +21,7 @@
 // USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
 use crate::transactions::{
-    transaction::{OutputFeatures, TransactionOutput},
+    transaction::{OutputFeatures, RewindData, TransactionOutput},
     transaction_protocol::{
         build_challenge,
         recipient::RecipientSignedMessage as RD,
@@ -53,9 +53,54 @@ impl SingleReceiverTransactionProtocol {
         features: OutputFeatures,
         factories: &CryptoFactories,
     ) -> Result<RD, TPE>
+    {
+        SingleReceiverTransactionProtocol::create_with_optional_rewind(
+            sender_info,
+            nonce,
+            spending_key,
+            features,
+            factories,
+            None,
+        )
+    }
+
+    /// As for `create`, but the receiver's output is given a range proof that can be rewound with `rewind_data`
+    pub fn create_with_rewindable_output(
+        sender_info: &SD,
+        nonce: SK,
+        spending_key: SK,
+        features: OutputFeatures,
+        factories: &CryptoFactories,
+        rewind_data: &RewindData,
+    ) -> Result<RD, TPE>
+    {
+        SingleReceiverTransactionProtocol::create_with_optional_rewind(
+            sender_info,
+            nonce,
+            spending_key,
+            features,
+            factories,
+            Some(rewind_data),
+        )
+    }
+
+    fn create_with_optional_rewind(
+        sender_info: &SD,
+        nonce: SK,
+        spending_key: SK,
+        features: OutputFeatures,
+        factories: &CryptoFactories,
+        rewind_data: Option<&RewindData>,
+    ) -> Result<RD, TPE>
     {
         SingleReceiverTransactionProtocol::validate_sender_data(sender_info)?;
-        let output = SingleReceiverTransactionProtocol::build_output(sender_info, &spending_key, features, factories)?;
+        let output = SingleReceiverTransactionProtocol::build_output(
+            sender_info,
+            &spending_key,
+            features,
+            factories,
+            rewind_data,
+        )?;
         let public_nonce = PublicKey::from_secret_key(&nonce);
         let public_spending_key = PublicKey::from_secret_key(&spending_key);
         let e = build_challenge(&(&sender_info.public_nonce + &public_nonce), &sender_info.metadata);
@@ -82,14 +127,24 @@ impl SingleReceiverTransactionProtocol {
         spending_key: &SK,
         features: OutputFeatures,
         factories: &CryptoFactories,
+        rewind_data: Option<&RewindData>,
     ) -> Result<TransactionOutput, TPE>
     {
         let commitment = factories
             .commitment
             .commit_value(&spending_key, sender_info.amount.into());
-        let proof = factories
-            .range_proof
-            .construct_proof(&spending_key, sender_info.amount.into())?;
+        let proof = match rewind_data {
+            Some(data) => factories.range_proof.construct_proof_with_rewind_key(
+                &spending_key,
+                sender_info.amount.into(),
+                &data.rewind_key,
+                &data.rewind_blinding_key,
+                &data.proof_message,
+            )?,
+            None => factories
+                .range_proof
+                .construct_proof(&spending_key, sender_info.amount.into())?,
+        };
         Ok(TransactionOutput::new(
             features,
             commitment,
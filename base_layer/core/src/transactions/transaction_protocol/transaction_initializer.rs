@@ -24,6 +24,7 @@ use crate::transactions::{
     fee::Fee,
     tari_amount::*,
     transaction::{
+        RewindData,
         TransactionInput,
         TransactionOutput,
         UnblindedOutput,
@@ -32,17 +33,18 @@ use crate::transactions::{
     },
     transaction_protocol::{
         recipient::RecipientInfo,
-        sender::{calculate_tx_id, RawTransactionInfo, SenderState, SenderTransactionProtocol},
+        sender::{calculate_tx_id, RawTransactionInfo, RecipientKernelInfo, SenderState, SenderTransactionProtocol},
         TransactionMetadata,
     },
     types::{BlindingFactor, CryptoFactories, PrivateKey, PublicKey},
 };
 use digest::Digest;
+use rand::OsRng;
 use std::{
     collections::HashMap,
     fmt::{Debug, Error, Formatter},
 };
-use tari_crypto::keys::PublicKey as PublicKeyTrait;
+use tari_crypto::keys::{PublicKey as PublicKeyTrait, SecretKey};
 use tari_utilities::fixed_set::FixedSet;
 
 /// The SenderTransactionInitializer is a Builder that helps set up the initial state for the Sender party of a new
@@ -67,6 +69,7 @@ pub struct SenderTransactionInitializer {
     excess_blinding_factor: BlindingFactor,
     private_nonce: Option<PrivateKey>,
     message: Option<String>,
+    rewind_data: Option<RewindData>,
 }
 
 pub struct BuildError {
@@ -95,6 +98,7 @@ impl SenderTransactionInitializer {
             private_nonce: None,
             excess_blinding_factor: BlindingFactor::default(),
             message: None,
+            rewind_data: None,
         }
     }
 
@@ -158,6 +162,13 @@ impl SenderTransactionInitializer {
         self
     }
 
+    /// Give the sender's own outputs, including any change output, range proofs that can be rewound with the given
+    /// rewind data
+    pub fn with_rewindable_outputs(&mut self, rewind_data: RewindData) -> &mut Self {
+        self.rewind_data = Some(rewind_data);
+        self
+    }
+
     /// Tries to make a change output with the given transaction parameters and add it to the set of outputs. The total
     /// fee, including the additional change output (if any) is returned along with the amount of change.
     /// The change output **always has default output features**.
@@ -199,6 +210,57 @@ impl SenderTransactionInitializer {
         }
     }
 
+    /// Set up the kernel signed with each recipient of a multi-recipient transaction. The sender's excess is split into
+    /// random shares, one for each kernel, and the fee is split between the kernels.
+    fn build_recipient_kernels(
+        rng: &mut OsRng,
+        ids: &[u64],
+        amounts: &[MicroTari],
+        offset_blinding_factor: &BlindingFactor,
+        total_fee: MicroTari,
+        lock_height: u64,
+    ) -> Vec<RecipientKernelInfo>
+    {
+        let num_kernels = ids.len() as u64;
+        let fee_share = u64::from(total_fee) / num_kernels;
+        let mut remaining_excess = offset_blinding_factor.clone();
+        ids.iter()
+            .zip(amounts.iter())
+            .enumerate()
+            .map(|(i, (tx_id, amount))| {
+                let excess_share = if i == ids.len() - 1 {
+                    remaining_excess.clone()
+                } else {
+                    let share = PrivateKey::random(rng);
+                    remaining_excess = &remaining_excess - &share;
+                    share
+                };
+                // The first kernel pays whatever does not divide evenly between them
+                let fee = if i == 0 {
+                    u64::from(total_fee) - fee_share * (num_kernels - 1)
+                } else {
+                    fee_share
+                };
+                let private_nonce = PrivateKey::random(rng);
+                RecipientKernelInfo {
+                    tx_id: *tx_id,
+                    amount: *amount,
+                    metadata: TransactionMetadata {
+                        fee: MicroTari(fee),
+                        lock_height,
+                        meta_info: None,
+                        linked_kernel: None,
+                    },
+                    public_excess: PublicKey::from_secret_key(&excess_share),
+                    excess_share,
+                    public_nonce: PublicKey::from_secret_key(&private_nonce),
+                    private_nonce,
+                    recipient_data: None,
+                }
+            })
+            .collect()
+    }
+
     fn check_value<T>(name: &str, val: &Option<T>, vec: &mut Vec<String>) {
         if val.is_none() {
             vec.push(name.to_string());
@@ -251,7 +313,10 @@ impl SenderTransactionInitializer {
         let outputs = match self
             .outputs
             .iter()
-            .map(|o| o.as_transaction_output(factories))
+            .map(|o| match &self.rewind_data {
+                Some(rewind_data) => o.as_rewindable_transaction_output(factories, rewind_data),
+                None => o.as_transaction_output(factories),
+            })
             .collect::<Result<Vec<TransactionOutput>, _>>()
         {
             Ok(o) => o,
@@ -260,6 +325,10 @@ impl SenderTransactionInitializer {
             },
         };
 
+        let mut rng = match OsRng::new() {
+            Ok(rng) => rng,
+            Err(e) => return self.build_err(&e.to_string()),
+        };
         let nonce = self.private_nonce.unwrap();
         let public_nonce = PublicKey::from_secret_key(&nonce);
         let offset = self.offset.unwrap();
@@ -277,15 +346,29 @@ impl SenderTransactionInitializer {
         for i in 0..self.num_recipients {
             ids.push(calculate_tx_id::<D>(&public_nonce, i));
         }
+        let amounts = self.amounts.into_vec();
+        let lock_height = self.lock_height.unwrap();
+        let recipient_kernels = if self.num_recipients > 1 {
+            Self::build_recipient_kernels(
+                &mut rng,
+                &ids,
+                &amounts,
+                &offset_blinding_factor,
+                total_fee,
+                lock_height,
+            )
+        } else {
+            Vec::new()
+        };
         let sender_info = RawTransactionInfo {
             num_recipients: self.num_recipients,
             amount_to_self,
             ids,
-            amounts: self.amounts.into_vec(),
+            amounts,
             change,
             metadata: TransactionMetadata {
                 fee: total_fee,
-                lock_height: self.lock_height.unwrap(),
+                lock_height,
                 meta_info: None,
                 linked_kernel: None,
             },
@@ -300,6 +383,7 @@ impl SenderTransactionInitializer {
             recipient_info,
             signatures: Vec::new(),
             message: self.message.unwrap_or("".to_string()),
+            recipient_kernels,
         };
         let state = SenderState::Initializing(Box::new(sender_info));
         let state = state
@@ -318,11 +402,7 @@ mod test {
         helpers::{make_input, TestParams},
         tari_amount::*,
         transaction::{UnblindedOutput, MAX_TRANSACTION_INPUTS},
-        transaction_protocol::{
-            sender::SenderState,
-            transaction_initializer::SenderTransactionInitializer,
-            TransactionProtocolError,
-        },
+        transaction_protocol::{sender::SenderState, transaction_initializer::SenderTransactionInitializer},
         types::CryptoFactories,
     };
     use rand::OsRng;
@@ -535,10 +615,22 @@ mod test {
             .with_fee_per_gram(MicroTari(20));
         let result = builder.build::<Blake256>(&factories).unwrap();
         // Peek inside and check the results
-        if let SenderState::Failed(TransactionProtocolError::UnsupportedError(s)) = result.state {
-            assert_eq!(s, "Multiple recipients are not supported yet")
+        if let SenderState::MultiRecipientMessagesReady(info) = result.state {
+            assert_eq!(info.num_recipients, 2, "Number of receivers");
+            assert_eq!(info.ids.len(), 2, "Number of tx_ids");
+            assert_ne!(info.ids[0], info.ids[1]);
+            assert_eq!(info.recipient_kernels.len(), 2, "Number of recipient kernels");
+            let kernel_fees = info
+                .recipient_kernels
+                .iter()
+                .fold(MicroTari(0), |sum, k| sum + k.metadata.fee);
+            assert_eq!(kernel_fees, info.metadata.fee, "Kernel fees sum to the transaction fee");
+            let excess_shares = &info.recipient_kernels[0].excess_share + &info.recipient_kernels[1].excess_share;
+            assert_eq!(excess_shares, info.offset_blinding_factor);
+            assert_eq!(info.recipient_kernels[0].amount, MicroTari(120));
+            assert_eq!(info.recipient_kernels[1].amount, MicroTari(110));
         } else {
-            panic!("We should not allow multiple recipients at this time");
+            panic!("There are multiple recipients, so the messages for them should be ready");
         }
     }
 
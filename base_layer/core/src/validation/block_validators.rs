@@ -31,7 +31,11 @@ use crate::{
     consensus::{ConsensusConstants, ConsensusManager},
     transactions::{transaction::OutputFlags, types::CryptoFactories},
     validation::{
-        helpers::{check_achieved_difficulty_at_chain_tip, check_median_timestamp_at_chain_tip},
+        helpers::{
+            check_achieved_difficulty_at_chain_tip,
+            check_chained_accumulated_difficulty,
+            check_median_timestamp_at_chain_tip,
+        },
         Validation,
         ValidationError,
     },
@@ -89,6 +93,7 @@ impl<B: BlockchainBackend> Validation<Block, B> for FullConsensusValidator<B> {
     /// 1. Is the block header timestamp greater than the median timestamp?
     /// 1. Is the Proof of Work valid?
     /// 1. Is the achieved difficulty of this block >= the target difficulty for this block?
+    /// 1. Does the accumulated difficulty of this block follow from the block it builds on?
     fn validate(&self, block: &Block) -> Result<(), ValidationError> {
         check_coinbase_output(block)?;
         block.check_stxo_rules().map_err(BlockValidationError::from)?;
@@ -97,6 +102,7 @@ impl<B: BlockchainBackend> Validation<Block, B> for FullConsensusValidator<B> {
         check_timestamp_ftl(&block.header)?;
         check_median_timestamp_at_chain_tip(&block.header, self.db()?, self.rules.clone())?;
         check_achieved_difficulty_at_chain_tip(&block.header, self.db()?, self.rules.clone())?; // Update function signature once diff adjuster is complete
+        check_chained_accumulated_difficulty(&block.header, self.db()?)?;
         Ok(())
     }
 }
@@ -27,7 +27,7 @@ use crate::{
     },
     chain_storage::{BlockchainBackend, BlockchainDatabase},
     consensus::ConsensusManager,
-    proof_of_work::PowError,
+    proof_of_work::{PowError, ProofOfWork},
     validation::ValidationError,
 };
 use tari_utilities::hash::Hashable;
@@ -108,3 +108,38 @@ pub fn check_achieved_difficulty<B: BlockchainBackend>(
     }
     Ok(())
 }
+
+/// Checks that the accumulated difficulties claimed by the block header follow from the stored block header that it
+/// builds on.
+pub fn check_chained_accumulated_difficulty<B: BlockchainBackend>(
+    block_header: &BlockHeader,
+    db: BlockchainDatabase<B>,
+) -> Result<(), ValidationError>
+{
+    if block_header.height == 0 || get_gen_block_hash() == block_header.hash() {
+        return Ok(()); // Its the genesis block, so there is no previous accumulated difficulty
+    }
+    let prev_header = db
+        .fetch_header_with_block_hash(block_header.prev_hash.clone())
+        .map_err(|_| ValidationError::BlockHeaderError(BlockHeaderValidationError::InvalidChaining))?;
+    check_accumulated_difficulty(block_header, &prev_header)
+}
+
+/// Checks that the accumulated difficulties claimed by the block header are the accumulated difficulties of the
+/// previous block header plus the difficulty achieved by the previous block.
+pub fn check_accumulated_difficulty(
+    block_header: &BlockHeader,
+    prev_header: &BlockHeader,
+) -> Result<(), ValidationError>
+{
+    let mut expected = ProofOfWork::new(block_header.pow.pow_algo);
+    expected.add_difficulty(&prev_header.pow, prev_header.achieved_difficulty());
+    if block_header.pow.accumulated_monero_difficulty != expected.accumulated_monero_difficulty ||
+        block_header.pow.accumulated_blake_difficulty != expected.accumulated_blake_difficulty
+    {
+        return Err(ValidationError::BlockHeaderError(
+            BlockHeaderValidationError::ProofOfWorkError(PowError::InvalidAccumulatedDifficulty),
+        ));
+    }
+    Ok(())
+}
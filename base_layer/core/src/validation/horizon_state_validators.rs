@@ -26,7 +26,7 @@ use crate::{
     chain_storage::{BlockchainBackend, BlockchainDatabase},
     validation::{
         error::ValidationError,
-        helpers::{check_achieved_difficulty, check_median_timestamp},
+        helpers::{check_achieved_difficulty, check_chained_accumulated_difficulty, check_median_timestamp},
         traits::Validation,
     },
 };
@@ -55,10 +55,12 @@ impl<B: BlockchainBackend> Validation<BlockHeader, B> for HorizonStateHeaderVali
     /// 1. Is the block header timestamp greater than the median timestamp?
     /// 1. Is the Proof of Work valid and is the achieved difficulty of this block >= the target difficulty for this
     /// block?
+    /// 1. Does the accumulated difficulty of the header follow from the header it builds on?
     fn validate(&self, block_header: &BlockHeader) -> Result<(), ValidationError> {
         check_header_sequence_and_chaining(block_header, self.db()?)?;
         check_median_timestamp(&block_header, block_header.height, self.rules.clone())?;
         check_achieved_difficulty(&block_header, block_header.height, self.rules.clone())?;
+        check_chained_accumulated_difficulty(&block_header, self.db()?)?;
 
         Ok(())
     }
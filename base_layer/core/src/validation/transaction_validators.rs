@@ -28,7 +28,8 @@ use crate::{
 use std::sync::Arc;
 use tari_utilities::hash::Hashable;
 
-/// This validator will only check that a transaction is internally consistent. It requires no state information.
+/// This validator will only check that a transaction is internally consistent and that it spends hash locked outputs
+/// according to their hash locks. It requires no state information.
 pub struct StatelessTxValidator {
     factories: Arc<CryptoFactories>,
 }
@@ -42,13 +43,14 @@ impl StatelessTxValidator {
 impl<B: BlockchainBackend> Validation<Transaction, B> for StatelessTxValidator {
     fn validate(&self, tx: &Transaction) -> Result<(), ValidationError> {
         verify_tx(tx, &self.factories)?;
+        verify_hash_locks(tx)?;
         Ok(())
     }
 }
 
 /// This validator will perform a full verification of the transaction. In order the following will be checked:
-/// Transaction integrity, All inputs exist in the backend, All timelocks (kernel lock heights and output maturities)
-/// have passed
+/// Transaction integrity, Hash locked inputs are spent according to their hash locks, All inputs exist in the backend,
+/// All timelocks (kernel lock heights and output maturities) have passed
 pub struct FullTxValidator<B: BlockchainBackend> {
     factories: Arc<CryptoFactories>,
     db: BlockchainDatabase<B>,
@@ -65,6 +67,7 @@ where B: BlockchainBackend
 impl<B: BlockchainBackend> Validation<Transaction, B> for FullTxValidator<B> {
     fn validate(&self, tx: &Transaction) -> Result<(), ValidationError> {
         verify_tx(tx, &self.factories)?;
+        verify_hash_locks(tx)?;
         verify_inputs(tx, self.db.clone())?;
         let height = self
             .db
@@ -155,6 +158,15 @@ fn verify_tx(tx: &Transaction, factories: &CryptoFactories) -> Result<(), Valida
         .map_err(|e| ValidationError::TransactionError(e))
 }
 
+// This function checks that the hash locked outputs spent by the provided transaction are either claimed with the
+// preimage of their hash or refunded, as their hash locks require. Whether a refund is not mined before the refund
+// height is enforced by the kernel lock height, which is checked with the other timelocks.
+fn verify_hash_locks(tx: &Transaction) -> Result<(), ValidationError> {
+    tx.body
+        .check_hash_locks()
+        .map_err(|e| ValidationError::TransactionError(e))
+}
+
 // This function checks that all the timelocks in the provided transaction pass. It checks kernel lock heights and
 // input maturities
 fn verify_timelocks(tx: &Transaction, current_height: u64) -> Result<(), ValidationError> {
@@ -20,18 +20,42 @@
 // WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
 // USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
+#[allow(dead_code)]
+mod helpers;
+
+use crate::helpers::block_builders::{append_block_with_coinbase, create_genesis_block_with_outputs};
+use rand::{OsRng, RngCore};
 use std::sync::Arc;
 use tari_core::{
-    blocks::genesis_block::get_genesis_block,
-    chain_storage::{BlockchainDatabase, MemoryDatabase, Validators},
+    blocks::{genesis_block::get_genesis_block, BlockValidationError},
+    chain_storage::{BlockchainDatabase, ChainStorageError, MemoryDatabase, Validators},
     consensus::ConsensusManager,
     proof_of_work::DiffAdjManager,
-    transactions::types::{CryptoFactories, HashDigest},
+    transactions::{
+        helpers::spend_utxos,
+        tari_amount::MicroTari,
+        transaction::{
+            HashLock,
+            KernelFeatures,
+            OutputFeatures,
+            Transaction,
+            TransactionError,
+            UnblindedOutput,
+            HASH_LOCK_PREIMAGE_LENGTH,
+        },
+        transaction_protocol::atomic_swap::{build_claim_transaction, find_hash_preimage},
+        types::{CryptoFactories, HashDigest, PrivateKey},
+    },
+    txn_schema,
     validation::{
         block_validators::{FullConsensusValidator, StatelessValidator},
         mocks::MockValidator,
+        transaction_validators::{FullTxValidator, StatelessTxValidator},
+        Validation,
+        ValidationError,
     },
 };
+use tari_crypto::{commitment::HomomorphicCommitmentFactory, keys::SecretKey};
 
 #[test]
 fn test_genesis_block() {
@@ -53,3 +77,163 @@ fn test_genesis_block() {
     let result = db.add_block(block);
     assert!(result.is_ok());
 }
+
+/// The outputs of one side of an atomic swap: a hash locked output, the output that it can be claimed into with the
+/// preimage, and an unrelated output of the same value
+struct HashLockedOutputs {
+    preimage: Vec<u8>,
+    hash_locked: UnblindedOutput,
+    claim_output: UnblindedOutput,
+    other_output: UnblindedOutput,
+}
+
+fn create_hash_locked_outputs(factories: &CryptoFactories) -> HashLockedOutputs {
+    let mut rng = OsRng::new().unwrap();
+    let mut preimage = vec![0u8; HASH_LOCK_PREIMAGE_LENGTH];
+    rng.fill_bytes(&mut preimage);
+    let value = MicroTari::from(10_000);
+    let claim_output = UnblindedOutput::new(value - MicroTari::from(100), PrivateKey::random(&mut rng), None);
+    let refund_output = UnblindedOutput::new(value - MicroTari::from(150), PrivateKey::random(&mut rng), None);
+    let hash_lock = HashLock {
+        hash: HashLock::hash_preimage(&preimage),
+        claim_commitment: factories
+            .commitment
+            .commit(&claim_output.spending_key, &claim_output.value.into()),
+        refund_height: 100,
+        refund_commitment: factories
+            .commitment
+            .commit(&refund_output.spending_key, &refund_output.value.into()),
+    };
+    HashLockedOutputs {
+        preimage,
+        hash_locked: UnblindedOutput::new(
+            value,
+            PrivateKey::random(&mut rng),
+            Some(OutputFeatures::with_hash_lock(hash_lock)),
+        ),
+        claim_output,
+        other_output: UnblindedOutput::new(value, PrivateKey::random(&mut rng), None),
+    }
+}
+
+/// Give the kernel of a transaction the hash lock feature and the given preimage
+fn with_hash_preimage(tx: &Transaction, preimage: Vec<u8>) -> Transaction {
+    let mut kernel = tx.body.kernels()[0].clone();
+    kernel.features |= KernelFeatures::HASH_LOCK;
+    kernel.hash_preimage = Some(preimage);
+    let mut tx = tx.clone();
+    tx.body.set_kernel(kernel);
+    tx
+}
+
+/// A claim whose preimage is too long, and a spend of an output that is not hash locked with a hash lock kernel
+fn invalid_hash_lock_spends(outputs: &HashLockedOutputs, factories: &CryptoFactories) -> Vec<Transaction> {
+    let claim = build_claim_transaction(
+        &outputs.hash_locked,
+        &outputs.claim_output,
+        outputs.preimage.clone(),
+        None,
+        factories,
+    )
+    .unwrap();
+    let mut long_preimage = outputs.preimage.clone();
+    long_preimage.push(0);
+    let (spend, _, _) = spend_utxos(txn_schema!(
+        from: vec![outputs.other_output.clone()],
+        to: vec![MicroTari::from(5_000)]
+    ));
+    vec![
+        with_hash_preimage(&claim, long_preimage),
+        with_hash_preimage(&spend, outputs.preimage.clone()),
+    ]
+}
+
+fn validate_tx<V>(validator: &V, tx: &Transaction) -> Result<(), ValidationError>
+where V: Validation<Transaction, MemoryDatabase<HashDigest>> {
+    validator.validate(tx)
+}
+
+#[test]
+fn test_hash_lock_transaction_validation() {
+    let factories = Arc::new(CryptoFactories::default());
+    let mut db = BlockchainDatabase::new(MemoryDatabase::<HashDigest>::default()).unwrap();
+    db.set_validators(Validators::new(
+        MockValidator::new(true),
+        MockValidator::new(true),
+        MockValidator::new(true),
+        MockValidator::new(true),
+        MockValidator::new(true),
+    ));
+    let outputs = create_hash_locked_outputs(&factories);
+    let genesis = create_genesis_block_with_outputs(&db, &factories, &[
+        outputs.hash_locked.clone(),
+        outputs.other_output.clone(),
+    ]);
+    db.add_block(genesis).unwrap();
+    let stateless_validator = StatelessTxValidator::new(factories.clone());
+    let full_validator = FullTxValidator::new(factories.clone(), db.clone());
+
+    let claim = build_claim_transaction(
+        &outputs.hash_locked,
+        &outputs.claim_output,
+        outputs.preimage.clone(),
+        None,
+        &factories,
+    )
+    .unwrap();
+    assert!(validate_tx(&stateless_validator, &claim).is_ok());
+    assert!(validate_tx(&full_validator, &claim).is_ok());
+
+    for tx in invalid_hash_lock_spends(&outputs, &factories) {
+        for result in vec![
+            validate_tx(&stateless_validator, &tx),
+            validate_tx(&full_validator, &tx),
+        ] {
+            match result {
+                Err(ValidationError::TransactionError(TransactionError::InvalidHashLock)) => (),
+                r => panic!("Unexpected result: {:?}", r),
+            }
+        }
+    }
+}
+
+#[test]
+fn test_hash_lock_block_validation() {
+    let factories = Arc::new(CryptoFactories::default());
+    let mut db = BlockchainDatabase::new(MemoryDatabase::<HashDigest>::default()).unwrap();
+    db.set_validators(Validators::new(
+        StatelessValidator::new(),
+        MockValidator::new(true),
+        MockValidator::new(true),
+        MockValidator::new(true),
+        MockValidator::new(true),
+    ));
+    let outputs = create_hash_locked_outputs(&factories);
+    let genesis = create_genesis_block_with_outputs(&db, &factories, &[
+        outputs.hash_locked.clone(),
+        outputs.other_output.clone(),
+    ]);
+    db.add_block(genesis.clone()).unwrap();
+
+    for tx in invalid_hash_lock_spends(&outputs, &factories) {
+        match append_block_with_coinbase(&db, &factories, &genesis, vec![tx]) {
+            Err(ChainStorageError::ValidationError(ValidationError::BlockError(
+                BlockValidationError::TransactionError(TransactionError::InvalidHashLock),
+            ))) => (),
+            r => panic!("Unexpected result: {:?}", r),
+        }
+    }
+
+    // The claim is mined, and the preimage can be read from the kernels of the block
+    let claim = build_claim_transaction(
+        &outputs.hash_locked,
+        &outputs.claim_output,
+        outputs.preimage.clone(),
+        None,
+        &factories,
+    )
+    .unwrap();
+    let block = append_block_with_coinbase(&db, &factories, &genesis, vec![claim]).unwrap();
+    let hash = HashLock::hash_preimage(&outputs.preimage);
+    assert_eq!(find_hash_preimage(block.body.kernels(), &hash), Some(outputs.preimage));
+}
@@ -363,6 +363,7 @@ fn add_multiple_blocks() {
     let factories = CryptoFactories::default();
     assert_eq!(metadata.height_of_longest_chain, None);
     assert_eq!(metadata.best_block, None);
+    assert_eq!(metadata.accumulated_difficulty, None);
     // Add the Genesis block
     let (block0, _) = create_genesis_block(&store, &factories);
     store.add_block(block0.clone()).unwrap();
@@ -372,6 +373,14 @@ fn add_multiple_blocks() {
     let hash = block1.hash();
     assert_eq!(metadata.height_of_longest_chain, Some(1));
     assert_eq!(metadata.best_block.unwrap(), hash);
+    assert_eq!(
+        metadata.accumulated_difficulty,
+        Some(block1.header.total_accumulated_difficulty_inclusive())
+    );
+    assert_eq!(
+        store.get_total_work().unwrap(),
+        block1.header.total_accumulated_difficulty_inclusive()
+    );
     // Adding blocks is idempotent
     assert_eq!(store.add_block(block1.clone()), Ok(BlockAddResult::BlockExists));
     // Check the metadata
@@ -380,6 +389,38 @@ fn add_multiple_blocks() {
     assert_eq!(metadata.best_block.unwrap(), hash);
 }
 
+#[test]
+fn restore_metadata() {
+    let factories = CryptoFactories::default();
+    let backend = MemoryDatabase::<HashDigest>::default();
+    let mut store = BlockchainDatabase::new(backend.clone()).unwrap();
+    store.set_validators(Validators::new(
+        MockValidator::new(true),
+        MockValidator::new(true),
+        MockValidator::new(true),
+        MockValidator::new(true),
+        MockValidator::new(true),
+    ));
+    let (block0, _) = create_genesis_block(&store, &factories);
+    store.add_block(block0.clone()).unwrap();
+    let block1 = append_block(&store, &block0, vec![]).unwrap();
+    let metadata = store.get_metadata().unwrap();
+
+    // Reopening the database restores the chain metadata from the backend
+    let store = BlockchainDatabase::new(backend).unwrap();
+    let restored_metadata = store.get_metadata().unwrap();
+    assert_eq!(restored_metadata.height_of_longest_chain, Some(1));
+    assert_eq!(restored_metadata.best_block, Some(block1.hash()));
+    assert_eq!(
+        restored_metadata.accumulated_difficulty,
+        metadata.accumulated_difficulty
+    );
+    assert_eq!(
+        store.get_total_work().unwrap(),
+        block1.header.total_accumulated_difficulty_inclusive()
+    );
+}
+
 #[test]
 fn test_checkpoints() {
     let factories = CryptoFactories::default();
@@ -401,6 +442,25 @@ fn test_checkpoints() {
     assert_eq!(block1, block_b);
 }
 
+#[test]
+fn fetch_kernel_height() {
+    let factories = CryptoFactories::default();
+    let store = create_mem_db();
+    let (block0, output) = create_genesis_block(&store, &factories);
+    store.add_block(block0.clone()).unwrap();
+    let txn = txn_schema!(from: vec![output], to: vec![MicroTari(5_000), MicroTari(6_000)]);
+    let (txn, _, _) = spend_utxos(txn);
+    let kernel_hash = txn.body.kernels()[0].hash();
+    let block1 = append_block(&store, &block0, vec![txn]).unwrap();
+    append_block(&store, &block1, vec![]).unwrap();
+    assert_eq!(store.fetch_kernel_height(kernel_hash), Ok(1));
+    let h = vec![0u8; 32];
+    assert_eq!(
+        store.fetch_kernel_height(h.clone()),
+        Err(ChainStorageError::ValueNotFound(DbKey::TransactionKernel(h)))
+    );
+}
+
 #[test]
 fn rewind_to_height() {
     let factories = CryptoFactories::default();
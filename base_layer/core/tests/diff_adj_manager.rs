@@ -24,18 +24,13 @@
 mod helpers;
 
 use helpers::block_builders::{chain_block, create_genesis_block};
+use tari_common::Network;
 use tari_core::{
     blocks::Block,
     chain_storage::{BlockchainDatabase, MemoryDatabase},
     consensus::ConsensusConstants,
     helpers::create_mem_db,
-    proof_of_work::{
-        lwma_diff::LinearWeightedMovingAverage,
-        DiffAdjManager,
-        Difficulty,
-        DifficultyAdjustment,
-        PowAlgorithm,
-    },
+    proof_of_work::{DiffAdjManager, Difficulty, DifficultyAdjustmentType, PowAlgorithm},
     transactions::types::{CryptoFactories, HashDigest},
 };
 use tari_utilities::epoch_time::EpochTime;
@@ -76,7 +71,18 @@ fn calculate_accumulated_difficulty(
     heights: Vec<u64>,
 ) -> Difficulty
 {
-    let mut lwma = LinearWeightedMovingAverage::default();
+    calculate_accumulated_difficulty_with_constants(db, heights, &ConsensusConstants::current())
+}
+
+// Calculated the accumulated difficulty for the selected blocks using the algorithm selected by the consensus
+// constants.
+fn calculate_accumulated_difficulty_with_constants(
+    db: &BlockchainDatabase<MemoryDatabase<HashDigest>>,
+    heights: Vec<u64>,
+    consensus_constants: &ConsensusConstants,
+) -> Difficulty
+{
+    let mut diff_adj = consensus_constants.create_difficulty_adjustment();
     for height in heights {
         let header = db.fetch_header(height).unwrap();
         let accumulated_difficulty = header.achieved_difficulty() +
@@ -84,9 +90,9 @@ fn calculate_accumulated_difficulty(
                 PowAlgorithm::Monero => header.pow.accumulated_monero_difficulty,
                 PowAlgorithm::Blake => header.pow.accumulated_blake_difficulty,
             };
-        lwma.add(header.timestamp, accumulated_difficulty).unwrap();
+        diff_adj.add(header.timestamp, accumulated_difficulty).unwrap();
     }
-    lwma.get_difficulty()
+    diff_adj.get_difficulty()
 }
 
 #[test]
@@ -120,6 +126,53 @@ fn test_initial_sync() {
     );
 }
 
+#[test]
+fn test_selected_difficulty_adjustment_type() {
+    let store = create_mem_db();
+    let pow_algos = vec![
+        PowAlgorithm::Blake, // GB default
+        PowAlgorithm::Monero,
+        PowAlgorithm::Blake,
+        PowAlgorithm::Blake,
+        PowAlgorithm::Monero,
+        PowAlgorithm::Blake,
+    ];
+    create_test_pow_blockchain(&store, pow_algos);
+
+    let consensus_constants = ConsensusConstants::for_network(Network::TestNet);
+    assert_eq!(
+        consensus_constants.get_difficulty_adjustment_type(),
+        DifficultyAdjustmentType::SimpleMovingAverage
+    );
+    let diff_adj_manager =
+        DiffAdjManager::with_consensus_constants(store.clone(), consensus_constants.clone()).unwrap();
+    assert_eq!(
+        diff_adj_manager.get_target_difficulty(&PowAlgorithm::Monero),
+        Ok(calculate_accumulated_difficulty_with_constants(
+            &store,
+            vec![1, 4],
+            &consensus_constants
+        ))
+    );
+    assert_eq!(
+        diff_adj_manager.get_target_difficulty(&PowAlgorithm::Blake),
+        Ok(calculate_accumulated_difficulty_with_constants(
+            &store,
+            vec![0, 2, 3, 5],
+            &consensus_constants
+        ))
+    );
+
+    let consensus_constants = ConsensusConstants::current()
+        .with_difficulty_adjustment_type(DifficultyAdjustmentType::LinearWeightedMovingAverage);
+    let diff_adj_manager =
+        DiffAdjManager::with_consensus_constants(store.clone(), consensus_constants.clone()).unwrap();
+    assert_eq!(
+        diff_adj_manager.get_target_difficulty(&PowAlgorithm::Blake),
+        Ok(calculate_accumulated_difficulty(&store, vec![0, 2, 3, 5]))
+    );
+}
+
 #[test]
 fn test_sync_to_chain_tip() {
     let store = create_mem_db();
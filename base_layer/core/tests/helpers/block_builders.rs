@@ -161,6 +161,26 @@ where
     (block, outputs)
 }
 
+/// Create a Genesis block with additional outputs, such as hash locked outputs, that are immediately available for
+/// spending. The spending keys of the outputs are already known to the caller.
+pub fn create_genesis_block_with_outputs<B>(
+    db: &BlockchainDatabase<B>,
+    factories: &CryptoFactories,
+    outputs: &[UnblindedOutput],
+) -> Block
+where
+    B: BlockchainBackend,
+{
+    let (mut template, _) = genesis_template(&factories, 100_000_000.into());
+    for output in outputs {
+        template
+            .body
+            .add_output(output.as_transaction_output(factories).unwrap());
+    }
+    db.calculate_mmr_roots(template)
+        .expect("Could not generate genesis block MMRs")
+}
+
 /// Create a new block using the provided transactions that adds to the blockchain given in `prev_block`.
 pub fn chain_block(prev_block: &Block, transactions: Vec<Transaction>) -> NewBlockTemplate {
     let header = BlockHeader::from_previous(&prev_block.header);
@@ -204,6 +224,22 @@ pub fn append_block<B: BlockchainBackend>(
     Ok(block)
 }
 
+/// Create a new block with the provided transactions and a coinbase. The new MMR roots are calculated, and then the new
+/// block is added to the database. The newly created block is returned as the result.
+pub fn append_block_with_coinbase<B: BlockchainBackend>(
+    db: &BlockchainDatabase<B>,
+    factories: &CryptoFactories,
+    prev_block: &Block,
+    txns: Vec<Transaction>,
+) -> Result<Block, ChainStorageError>
+{
+    let (coinbase_utxo, coinbase_kernel, _) = create_coinbase(factories, 100_000_000.into());
+    let template = chain_block_with_coinbase(prev_block, txns, coinbase_utxo, coinbase_kernel);
+    let block = db.calculate_mmr_roots(template)?;
+    db.add_block(block.clone())?;
+    Ok(block)
+}
+
 /// Generate a new block using the given transaction schema and add it to the provided database.
 /// The blocks and UTXO vectors are also updated with the info from the new block.
 pub fn generate_new_block(
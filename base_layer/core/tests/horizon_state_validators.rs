@@ -126,6 +126,47 @@ fn validate_median_timestamp() {
     assert!(store.validate_horizon_state().is_err());
 }
 
+#[test]
+fn validate_accumulated_difficulty() {
+    let db = MemoryDatabase::<HashDigest>::default();
+    let mut store = BlockchainDatabase::new(db).unwrap();
+    let consensus = ConsensusConstants::current();
+    let rules = ConsensusManager::default();
+    rules
+        .set_diff_manager(DiffAdjManager::new(store.clone()).unwrap())
+        .unwrap();
+    let validators = Validators::new(
+        MockValidator::new(true),
+        MockValidator::new(true),
+        HorizonStateHeaderValidator::new(rules, store.clone()),
+        MockValidator::new(true),
+        MockValidator::new(true),
+    );
+    store.set_validators(validators);
+
+    let mut header0 = BlockHeader::new(0);
+    find_header_with_achieved_difficulty(&mut header0, Difficulty::from(1));
+    let mut header1 = BlockHeader::from_previous(&header0);
+    header1.timestamp = header0.timestamp.increase(consensus.get_diff_target_block_interval());
+    find_header_with_achieved_difficulty(&mut header1, Difficulty::from(1));
+    let mut header2 = BlockHeader::from_previous(&header1);
+    header2.timestamp = header1.timestamp.increase(consensus.get_diff_target_block_interval());
+    // Claim more accumulated difficulty than the chain has achieved
+    header2.pow.accumulated_blake_difficulty = header2.pow.accumulated_blake_difficulty + Difficulty::from(1000);
+    find_header_with_achieved_difficulty(&mut header2, Difficulty::from(1));
+
+    let mut txn = DbTransaction::new();
+    txn.insert_header(header0);
+    txn.insert_header(header1);
+    assert!(store.commit(txn).is_ok());
+    assert!(store.validate_horizon_state().is_ok());
+
+    let mut txn = DbTransaction::new();
+    txn.insert_header(header2);
+    assert!(store.commit(txn).is_ok());
+    assert!(store.validate_horizon_state().is_err());
+}
+
 #[test]
 fn validate_achieved_difficulty() {
     let db = MemoryDatabase::<HashDigest>::default();
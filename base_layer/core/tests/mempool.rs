@@ -512,6 +512,72 @@ fn request_response_get_tx_state_with_excess_sig() {
     carol_node.comms.shutdown().unwrap();
 }
 
+#[test]
+fn request_response_get_fee_per_gram_estimate() {
+    let mut runtime = Runtime::new().unwrap();
+    let temp_dir = TempDir::new(string(8).as_str()).unwrap();
+    let (mut alice, bob) = create_network_with_2_base_nodes(&mut runtime, temp_dir.path().to_str().unwrap());
+
+    // Bob's mempool is empty, so the estimate is the minimum fee-per-gram of his unconfirmed pool
+    let local_estimate = bob.mempool.estimate_fee_per_gram(1).unwrap();
+    assert_eq!(local_estimate, 1 * uT);
+
+    runtime.block_on(async {
+        let received_estimate = alice.outbound_mp_interface.get_fee_per_gram_estimate(1).await.unwrap();
+        assert_eq!(received_estimate, local_estimate);
+    });
+
+    alice.comms.shutdown().unwrap();
+    bob.comms.shutdown().unwrap();
+}
+
+#[test]
+fn request_response_get_tx_with_excess_sig() {
+    let mut runtime = Runtime::new().unwrap();
+    let temp_dir = TempDir::new(string(8).as_str()).unwrap();
+    let (mut alice, bob) = create_network_with_2_base_nodes(&mut runtime, temp_dir.path().to_str().unwrap());
+
+    let (orphan_tx, _, _) = tx!(1*T, fee: 100*uT);
+    let (unpublished_tx, _, _) = tx!(2*T, fee: 100*uT);
+    let orphan_tx = Arc::new(orphan_tx);
+    bob.mempool.insert(orphan_tx.clone()).unwrap();
+
+    runtime.block_on(async {
+        let orphan_tx_excess_sig = orphan_tx.body.kernels()[0].excess_sig.clone();
+        let unpublished_tx_excess_sig = unpublished_tx.body.kernels()[0].excess_sig.clone();
+        assert_eq!(
+            alice
+                .outbound_mp_interface
+                .get_tx_with_excess_sig(orphan_tx_excess_sig.clone())
+                .await
+                .unwrap(),
+            Some((*orphan_tx).clone())
+        );
+        assert_eq!(
+            alice
+                .outbound_mp_interface
+                .get_tx_with_excess_sig(unpublished_tx_excess_sig)
+                .await
+                .unwrap(),
+            None
+        );
+
+        let tx_list = alice.outbound_mp_interface.get_tx_list(None).await.unwrap();
+        assert_eq!(tx_list.len(), 1);
+        assert_eq!(tx_list[0].excess_sig, orphan_tx_excess_sig);
+        assert_eq!(tx_list[0].storage, TxStorageResponse::OrphanPool);
+        let tx_list = alice
+            .outbound_mp_interface
+            .get_tx_list(Some(TxStorageResponse::UnconfirmedPool))
+            .await
+            .unwrap();
+        assert!(tx_list.is_empty());
+    });
+
+    alice.comms.shutdown().unwrap();
+    bob.comms.shutdown().unwrap();
+}
+
 #[test]
 fn receive_and_propagate_transaction() {
     let factories = CryptoFactories::default();
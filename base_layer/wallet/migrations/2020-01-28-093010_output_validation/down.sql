@@ -0,0 +1,17 @@
+CREATE TABLE outputs_without_valid (
+    spending_key BLOB PRIMARY KEY NOT NULL,
+    value INTEGER NOT NULL,
+    flags INTEGER NOT NULL,
+    maturity INTEGER NOT NULL,
+    spent INTEGER NOT NULL DEFAULT 0,
+    to_be_received INTEGER NOT NULL DEFAULT 0,
+    encumbered INTEGER NOT NULL DEFAULT 0,
+    tx_id INTEGER NULL,
+    FOREIGN KEY(tx_id) REFERENCES pending_transaction_outputs(tx_id)
+);
+
+INSERT INTO outputs_without_valid
+SELECT spending_key, value, flags, maturity, spent, to_be_received, encumbered, tx_id FROM outputs;
+
+DROP TABLE outputs;
+ALTER TABLE outputs_without_valid RENAME TO outputs;
@@ -0,0 +1 @@
+ALTER TABLE outputs ADD COLUMN valid INTEGER NOT NULL DEFAULT 1;
@@ -0,0 +1,15 @@
+CREATE TABLE outbound_transactions_without_recipients (
+    tx_id INTEGER PRIMARY KEY NOT NULL,
+    destination_public_key BLOB NOT NULL,
+    amount INTEGER NOT NULL,
+    fee INTEGER NOT NULL,
+    sender_protocol TEXT NOT NULL,
+    message TEXT NOT NULL,
+    timestamp DATETIME NOT NULL
+);
+
+INSERT INTO outbound_transactions_without_recipients
+SELECT tx_id, destination_public_key, amount, fee, sender_protocol, message, timestamp FROM outbound_transactions;
+
+DROP TABLE outbound_transactions;
+ALTER TABLE outbound_transactions_without_recipients RENAME TO outbound_transactions;
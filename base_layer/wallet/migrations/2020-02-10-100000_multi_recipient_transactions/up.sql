@@ -0,0 +1 @@
+ALTER TABLE outbound_transactions ADD COLUMN recipients TEXT NOT NULL DEFAULT '[]';
@@ -0,0 +1,31 @@
+CREATE TABLE inbound_transactions_without_cancelled (
+    tx_id INTEGER PRIMARY KEY NOT NULL,
+    source_public_key BLOB NOT NULL,
+    amount INTEGER NOT NULL,
+    receiver_protocol TEXT NOT NULL,
+    message TEXT NOT NULL,
+    timestamp DATETIME NOT NULL
+);
+
+INSERT INTO inbound_transactions_without_cancelled
+SELECT tx_id, source_public_key, amount, receiver_protocol, message, timestamp FROM inbound_transactions;
+
+DROP TABLE inbound_transactions;
+ALTER TABLE inbound_transactions_without_cancelled RENAME TO inbound_transactions;
+
+CREATE TABLE outbound_transactions_without_cancelled (
+    tx_id INTEGER PRIMARY KEY NOT NULL,
+    destination_public_key BLOB NOT NULL,
+    amount INTEGER NOT NULL,
+    fee INTEGER NOT NULL,
+    sender_protocol TEXT NOT NULL,
+    message TEXT NOT NULL,
+    timestamp DATETIME NOT NULL,
+    recipients TEXT NOT NULL DEFAULT '[]'
+);
+
+INSERT INTO outbound_transactions_without_cancelled
+SELECT tx_id, destination_public_key, amount, fee, sender_protocol, message, timestamp, recipients FROM outbound_transactions;
+
+DROP TABLE outbound_transactions;
+ALTER TABLE outbound_transactions_without_cancelled RENAME TO outbound_transactions;
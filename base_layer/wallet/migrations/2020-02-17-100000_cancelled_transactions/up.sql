@@ -0,0 +1,2 @@
+ALTER TABLE inbound_transactions ADD COLUMN cancelled INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE outbound_transactions ADD COLUMN cancelled INTEGER NOT NULL DEFAULT 0;
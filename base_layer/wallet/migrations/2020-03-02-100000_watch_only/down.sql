@@ -0,0 +1,2 @@
+DROP TABLE watched_outputs;
+DROP TABLE watch_only_states;
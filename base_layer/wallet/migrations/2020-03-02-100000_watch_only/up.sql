@@ -0,0 +1,15 @@
+CREATE TABLE watch_only_states (
+    id INTEGER PRIMARY KEY,
+    rewind_public_key BLOB,
+    rewind_blinding_public_key BLOB,
+    next_height INTEGER NOT NULL
+);
+
+CREATE TABLE watched_outputs (
+    commitment BLOB PRIMARY KEY NOT NULL,
+    value INTEGER NOT NULL,
+    flags INTEGER NOT NULL,
+    maturity INTEGER NOT NULL,
+    mined_height INTEGER,
+    spent_height INTEGER
+);
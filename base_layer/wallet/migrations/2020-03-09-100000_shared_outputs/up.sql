@@ -0,0 +1,9 @@
+CREATE TABLE shared_outputs (
+    output_id INTEGER PRIMARY KEY NOT NULL,
+    value INTEGER NOT NULL,
+    initiator BLOB NOT NULL,
+    participants BLOB NOT NULL,
+    key_share BLOB NOT NULL,
+    commitment BLOB,
+    status INTEGER NOT NULL
+);
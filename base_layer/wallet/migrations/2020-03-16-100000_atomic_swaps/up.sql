@@ -0,0 +1,16 @@
+CREATE TABLE atomic_swaps (
+    swap_id INTEGER PRIMARY KEY NOT NULL,
+    role INTEGER NOT NULL,
+    counterparty BLOB NOT NULL,
+    value INTEGER NOT NULL,
+    hash BLOB NOT NULL,
+    refund_height INTEGER NOT NULL,
+    fee_per_gram INTEGER NOT NULL,
+    output_value INTEGER NOT NULL,
+    output_key BLOB NOT NULL,
+    counterparty_commitment BLOB,
+    spending_key BLOB,
+    preimage BLOB,
+    message TEXT NOT NULL,
+    status INTEGER NOT NULL
+);
@@ -0,0 +1,19 @@
+DROP INDEX idx_completed_transactions_timestamp;
+
+CREATE TABLE completed_transactions_without_mined_height (
+    tx_id INTEGER PRIMARY KEY NOT NULL,
+    source_public_key BLOB NOT NULL,
+    destination_public_key BLOB NOT NULL,
+    amount INTEGER NOT NULL,
+    fee INTEGER NOT NULL,
+    transaction_protocol TEXT NOT NULL,
+    status INTEGER NOT NULL,
+    message TEXT NOT NULL,
+    timestamp DATETIME NOT NULL
+);
+
+INSERT INTO completed_transactions_without_mined_height
+SELECT tx_id, source_public_key, destination_public_key, amount, fee, transaction_protocol, status, message, timestamp FROM completed_transactions;
+
+DROP TABLE completed_transactions;
+ALTER TABLE completed_transactions_without_mined_height RENAME TO completed_transactions;
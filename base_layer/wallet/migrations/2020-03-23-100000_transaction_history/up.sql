@@ -0,0 +1,2 @@
+ALTER TABLE completed_transactions ADD COLUMN mined_height INTEGER NULL;
+CREATE INDEX idx_completed_transactions_timestamp ON completed_transactions (timestamp, tx_id);
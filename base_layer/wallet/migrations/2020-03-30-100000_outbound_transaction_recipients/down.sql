@@ -0,0 +1 @@
+DROP TABLE outbound_transaction_recipients;
@@ -0,0 +1,6 @@
+CREATE TABLE outbound_transaction_recipients (
+    recipient_tx_id INTEGER PRIMARY KEY NOT NULL,
+    tx_id INTEGER NOT NULL
+);
+
+CREATE INDEX idx_outbound_transaction_recipients_tx_id ON outbound_transaction_recipients (tx_id);
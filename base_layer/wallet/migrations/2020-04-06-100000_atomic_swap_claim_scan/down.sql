@@ -0,0 +1,22 @@
+CREATE TABLE atomic_swaps_without_scan_height (
+    swap_id INTEGER PRIMARY KEY NOT NULL,
+    role INTEGER NOT NULL,
+    counterparty BLOB NOT NULL,
+    value INTEGER NOT NULL,
+    hash BLOB NOT NULL,
+    refund_height INTEGER NOT NULL,
+    fee_per_gram INTEGER NOT NULL,
+    output_value INTEGER NOT NULL,
+    output_key BLOB NOT NULL,
+    counterparty_commitment BLOB,
+    spending_key BLOB,
+    preimage BLOB,
+    message TEXT NOT NULL,
+    status INTEGER NOT NULL
+);
+
+INSERT INTO atomic_swaps_without_scan_height
+SELECT swap_id, role, counterparty, value, hash, refund_height, fee_per_gram, output_value, output_key, counterparty_commitment, spending_key, preimage, message, status FROM atomic_swaps;
+
+DROP TABLE atomic_swaps;
+ALTER TABLE atomic_swaps_without_scan_height RENAME TO atomic_swaps;
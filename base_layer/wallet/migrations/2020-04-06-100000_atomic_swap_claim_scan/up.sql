@@ -0,0 +1 @@
+ALTER TABLE atomic_swaps ADD COLUMN scan_height INTEGER NULL;
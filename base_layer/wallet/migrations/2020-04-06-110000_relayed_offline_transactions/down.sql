@@ -0,0 +1 @@
+DROP TABLE relayed_offline_transactions;
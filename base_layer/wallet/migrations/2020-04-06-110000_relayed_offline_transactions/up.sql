@@ -0,0 +1,5 @@
+CREATE TABLE relayed_offline_transactions (
+    tx_id INTEGER PRIMARY KEY NOT NULL,
+    offline_transaction TEXT NOT NULL,
+    reply TEXT NULL
+);
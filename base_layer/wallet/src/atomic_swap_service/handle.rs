@@ -0,0 +1,193 @@
+// Copyright 2020, The Tari Project
+//
+// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+// following conditions are met:
+//
+// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+// disclaimer.
+//
+// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+// following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+// 3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+// products derived from this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+// INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+// SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+// WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+// USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+use crate::{
+    atomic_swap_service::error::AtomicSwapServiceError,
+    output_manager_service::storage::database::AtomicSwap,
+};
+use futures::{stream::Fuse, StreamExt};
+use tari_broadcast_channel::Subscriber;
+use tari_comms::types::CommsPublicKey;
+use tari_core::transactions::{tari_amount::MicroTari, types::HashOutput};
+use tari_service_framework::reply_channel::SenderService;
+use tower::Service;
+
+/// API Request enum
+#[derive(Debug)]
+pub enum AtomicSwapServiceRequest {
+    InitiateSwap((CommsPublicKey, MicroTari, HashOutput, u64, MicroTari, String)),
+    AcceptSwap((u64, MicroTari)),
+    ClaimSwap((u64, Vec<u8>)),
+    RefundSwap(u64),
+    CancelSwap(u64),
+    GetSwaps,
+    SetBaseNodePublicKey(CommsPublicKey),
+}
+
+/// API Reply enum
+#[derive(Debug)]
+pub enum AtomicSwapServiceResponse {
+    SwapInitiated(u64),
+    SwapAccepted,
+    SwapClaimed,
+    SwapRefunded,
+    SwapCancelled,
+    Swaps(Vec<AtomicSwap>),
+    BaseNodePublicKeySet,
+}
+
+/// Events that can be published on the Atomic Swap Service Event Stream
+#[derive(Clone, Debug, PartialEq)]
+pub enum AtomicSwapEvent {
+    /// Another wallet offered this wallet the swap with the given id. The offer has to be accepted before the other
+    /// wallet funds it.
+    SwapOffered(u64),
+    /// The hash locked output of the swap with the given id has been funded. The claimer should check that the
+    /// funding transaction has been mined before it locks its coins on the other chain.
+    SwapFunded(u64),
+    /// The swap with the given id has been claimed with the given preimage, which unlocks the other chain's contract.
+    /// The funder learns the preimage from the claimer's message or from the claim kernel on the blockchain.
+    SwapClaimed((u64, Vec<u8>)),
+    /// The hash locked output of the swap with the given id has been refunded to the funder
+    SwapRefunded(u64),
+    /// The swap with the given id was abandoned by the other wallet
+    SwapCancelled(u64),
+    Error(String),
+}
+
+#[derive(Clone)]
+pub struct AtomicSwapServiceHandle {
+    handle: SenderService<AtomicSwapServiceRequest, Result<AtomicSwapServiceResponse, AtomicSwapServiceError>>,
+    event_stream: Subscriber<AtomicSwapEvent>,
+}
+
+impl AtomicSwapServiceHandle {
+    pub fn new(
+        handle: SenderService<AtomicSwapServiceRequest, Result<AtomicSwapServiceResponse, AtomicSwapServiceError>>,
+        event_stream: Subscriber<AtomicSwapEvent>,
+    ) -> Self
+    {
+        AtomicSwapServiceHandle { handle, event_stream }
+    }
+
+    pub fn get_event_stream_fused(&self) -> Fuse<Subscriber<AtomicSwapEvent>> {
+        self.event_stream.clone().fuse()
+    }
+
+    /// Offer the wallet with the given public key a hash locked output of the given value, which it can claim with the
+    /// preimage of the given SHA-256 hash, or which this wallet can refund from the given block height. The output is
+    /// funded, paying the given fee per gram, once the other wallet has accepted the offer. Returns the id of the swap.
+    pub async fn initiate_swap(
+        &mut self,
+        claimer: CommsPublicKey,
+        value: MicroTari,
+        hash: HashOutput,
+        refund_height: u64,
+        fee_per_gram: MicroTari,
+        message: String,
+    ) -> Result<u64, AtomicSwapServiceError>
+    {
+        match self
+            .handle
+            .call(AtomicSwapServiceRequest::InitiateSwap((
+                claimer,
+                value,
+                hash,
+                refund_height,
+                fee_per_gram,
+                message,
+            )))
+            .await??
+        {
+            AtomicSwapServiceResponse::SwapInitiated(swap_id) => Ok(swap_id),
+            _ => Err(AtomicSwapServiceError::UnexpectedApiResponse),
+        }
+    }
+
+    /// Accept a swap that another wallet has offered. The claim transaction will pay the given fee per gram.
+    pub async fn accept_swap(&mut self, swap_id: u64, fee_per_gram: MicroTari) -> Result<(), AtomicSwapServiceError> {
+        match self
+            .handle
+            .call(AtomicSwapServiceRequest::AcceptSwap((swap_id, fee_per_gram)))
+            .await??
+        {
+            AtomicSwapServiceResponse::SwapAccepted => Ok(()),
+            _ => Err(AtomicSwapServiceError::UnexpectedApiResponse),
+        }
+    }
+
+    /// Claim the hash locked output of a funded swap by revealing the preimage of its hash
+    pub async fn claim_swap(&mut self, swap_id: u64, preimage: Vec<u8>) -> Result<(), AtomicSwapServiceError> {
+        match self
+            .handle
+            .call(AtomicSwapServiceRequest::ClaimSwap((swap_id, preimage)))
+            .await??
+        {
+            AtomicSwapServiceResponse::SwapClaimed => Ok(()),
+            _ => Err(AtomicSwapServiceError::UnexpectedApiResponse),
+        }
+    }
+
+    /// Refund the hash locked output of a swap that this wallet funded. The refund transaction cannot be mined before
+    /// the refund height of the swap.
+    pub async fn refund_swap(&mut self, swap_id: u64) -> Result<(), AtomicSwapServiceError> {
+        match self
+            .handle
+            .call(AtomicSwapServiceRequest::RefundSwap(swap_id))
+            .await??
+        {
+            AtomicSwapServiceResponse::SwapRefunded => Ok(()),
+            _ => Err(AtomicSwapServiceError::UnexpectedApiResponse),
+        }
+    }
+
+    /// Abandon a swap that has not been funded yet
+    pub async fn cancel_swap(&mut self, swap_id: u64) -> Result<(), AtomicSwapServiceError> {
+        match self
+            .handle
+            .call(AtomicSwapServiceRequest::CancelSwap(swap_id))
+            .await??
+        {
+            AtomicSwapServiceResponse::SwapCancelled => Ok(()),
+            _ => Err(AtomicSwapServiceError::UnexpectedApiResponse),
+        }
+    }
+
+    pub async fn get_swaps(&mut self) -> Result<Vec<AtomicSwap>, AtomicSwapServiceError> {
+        match self.handle.call(AtomicSwapServiceRequest::GetSwaps).await?? {
+            AtomicSwapServiceResponse::Swaps(swaps) => Ok(swaps),
+            _ => Err(AtomicSwapServiceError::UnexpectedApiResponse),
+        }
+    }
+
+    /// Set the base node whose blockchain is watched for the claims of the swaps that this wallet funded
+    pub async fn set_base_node_public_key(&mut self, public_key: CommsPublicKey) -> Result<(), AtomicSwapServiceError> {
+        match self
+            .handle
+            .call(AtomicSwapServiceRequest::SetBaseNodePublicKey(public_key))
+            .await??
+        {
+            AtomicSwapServiceResponse::BaseNodePublicKeySet => Ok(()),
+            _ => Err(AtomicSwapServiceError::UnexpectedApiResponse),
+        }
+    }
+}
@@ -0,0 +1,1021 @@
+// Copyright 2020, The Tari Project
+//
+// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+// following conditions are met:
+//
+// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+// disclaimer.
+//
+// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+// following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+// 3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+// products derived from this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+// INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+// SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+// WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+// USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+use crate::{
+    atomic_swap_service::{
+        config::AtomicSwapServiceConfig,
+        error::AtomicSwapServiceError,
+        handle::{AtomicSwapEvent, AtomicSwapServiceRequest, AtomicSwapServiceResponse},
+    },
+    output_manager_service::{
+        handle::OutputManagerHandle,
+        storage::database::{AtomicSwap, AtomicSwapRole, AtomicSwapStatus},
+    },
+    transaction_service::handle::TransactionServiceHandle,
+};
+use futures::{pin_mut, SinkExt, Stream, StreamExt};
+use log::*;
+use rand::RngCore;
+use std::{cmp, convert::TryFrom, time::Instant};
+use tari_broadcast_channel::Publisher;
+use tari_comms::types::CommsPublicKey;
+use tari_comms_dht::{
+    domain_message::OutboundDomainMessage,
+    outbound::{OutboundEncryption, OutboundMessageRequester, SendMessageResponse},
+};
+use tari_core::{
+    base_node::proto::base_node::{
+        base_node_service_request::Request as BaseNodeRequestProto,
+        base_node_service_response::Response as BaseNodeResponseProto,
+        BaseNodeServiceRequest,
+        BaseNodeServiceResponse,
+        BlockHeights,
+    },
+    proto::core::HistoricalBlock as HistoricalBlockProto,
+    transactions::{
+        aggregated_body::AggregateBody,
+        fee::Fee,
+        tari_amount::MicroTari,
+        transaction::{HashLock, OutputFeatures, TransactionKernel, UnblindedOutput, HASH_LOCK_PREIMAGE_LENGTH},
+        transaction_protocol::{
+            atomic_swap::{
+                build_claim_transaction,
+                build_refund_transaction,
+                find_hash_preimage,
+                AtomicSwapMessage,
+                AtomicSwapStep,
+            },
+            proto,
+        },
+        types::{Commitment, CryptoFactories, HashOutput, PrivateKey},
+    },
+};
+use tari_crypto::{commitment::HomomorphicCommitmentFactory, keys::SecretKey};
+use tari_p2p::{domain_message::DomainMessage, tari_message::TariMessageType};
+use tari_service_framework::reply_channel;
+use tokio::time;
+
+const LOG_TARGET: &'static str = "base_layer::wallet::atomic_swap_service";
+
+/// This service runs the Tari side of atomic swaps, in which Tari are exchanged for coins on another chain with a pair
+/// of hash time-locked contracts that are unlocked by the same secret preimage.
+///
+/// The funder offers the claimer a hash locked output of a given value. Once the claimer has accepted the offer with
+/// the commitment of the output it will claim into, the funder funds the hash locked output and sends the claimer its
+/// spending key. The claimer should then check that the output has been mined before locking its coins on the other
+/// chain. When the claimer claims the output it reveals the preimage, both in the claim kernel on the Tari chain and
+/// in a message to the funder, with which the funder unlocks the contract on the other chain. If the output is not
+/// claimed the funder can refund it from the refund height.
+///
+/// The funder does not rely on the claimer's message: at every scan interval the blocks that were added to the chain of
+/// the base node since the last scan are requested and their kernels are searched for the preimages of the swaps that
+/// this wallet funded. A swap is scanned for from the chain tip at the first scan after it was funded, so its claim is
+/// only found on the chain if a base node was set before the claim was mined.
+///
+/// The state of every swap is stored by the Output Manager Service, so swaps survive restarts of the wallet and there
+/// are no timeouts; it is up to the user to claim or refund in time.
+pub struct AtomicSwapService<ASStream, BNResponseStream>
+where
+    ASStream: Stream<Item = DomainMessage<proto::AtomicSwapMessage>>,
+    BNResponseStream: Stream<Item = DomainMessage<BaseNodeServiceResponse>>,
+{
+    config: AtomicSwapServiceConfig,
+    output_manager_service: OutputManagerHandle,
+    transaction_service: TransactionServiceHandle,
+    outbound_message_service: OutboundMessageRequester,
+    request_stream: Option<
+        reply_channel::Receiver<AtomicSwapServiceRequest, Result<AtomicSwapServiceResponse, AtomicSwapServiceError>>,
+    >,
+    atomic_swap_message_stream: Option<ASStream>,
+    base_node_response_stream: Option<BNResponseStream>,
+    base_node_public_key: Option<CommsPublicKey>,
+    scan: Option<ScanState>,
+    event_publisher: Publisher<AtomicSwapEvent>,
+    factories: CryptoFactories,
+}
+
+impl<ASStream, BNResponseStream> AtomicSwapService<ASStream, BNResponseStream>
+where
+    ASStream: Stream<Item = DomainMessage<proto::AtomicSwapMessage>>,
+    BNResponseStream: Stream<Item = DomainMessage<BaseNodeServiceResponse>>,
+{
+    pub fn new(
+        config: AtomicSwapServiceConfig,
+        output_manager_service: OutputManagerHandle,
+        transaction_service: TransactionServiceHandle,
+        outbound_message_service: OutboundMessageRequester,
+        request_stream: reply_channel::Receiver<
+            AtomicSwapServiceRequest,
+            Result<AtomicSwapServiceResponse, AtomicSwapServiceError>,
+        >,
+        atomic_swap_message_stream: ASStream,
+        base_node_response_stream: BNResponseStream,
+        event_publisher: Publisher<AtomicSwapEvent>,
+        factories: CryptoFactories,
+    ) -> Self
+    {
+        AtomicSwapService {
+            config,
+            output_manager_service,
+            transaction_service,
+            outbound_message_service,
+            request_stream: Some(request_stream),
+            atomic_swap_message_stream: Some(atomic_swap_message_stream),
+            base_node_response_stream: Some(base_node_response_stream),
+            base_node_public_key: None,
+            scan: None,
+            event_publisher,
+            factories,
+        }
+    }
+
+    pub async fn start(mut self) -> Result<(), AtomicSwapServiceError> {
+        let request_stream = self
+            .request_stream
+            .take()
+            .expect("Atomic Swap Service initialized without request_stream")
+            .fuse();
+        pin_mut!(request_stream);
+        let atomic_swap_message_stream = self
+            .atomic_swap_message_stream
+            .take()
+            .expect("Atomic Swap Service initialized without atomic_swap_message_stream")
+            .fuse();
+        pin_mut!(atomic_swap_message_stream);
+        let base_node_response_stream = self
+            .base_node_response_stream
+            .take()
+            .expect("Atomic Swap Service initialized without base_node_response_stream")
+            .fuse();
+        pin_mut!(base_node_response_stream);
+
+        let request_timeout = self.config.request_timeout;
+        let mut retry_tick = time::interval_at((Instant::now() + request_timeout).into(), request_timeout).fuse();
+        let scan_interval = self.config.scan_interval;
+        let mut scan_tick = time::interval_at((Instant::now() + scan_interval).into(), scan_interval).fuse();
+
+        info!(target: LOG_TARGET, "Atomic Swap Service started");
+        loop {
+            futures::select! {
+                request_context = request_stream.select_next_some() => {
+                    let (request, reply_tx) = request_context.split();
+                    let _ = reply_tx.send(self.handle_request(request).await.or_else(|resp| {
+                        error!(target: LOG_TARGET, "Error handling request: {:?}", resp);
+                        Err(resp)
+                    })).or_else(|resp| {
+                        error!(target: LOG_TARGET, "Failed to send reply");
+                        Err(resp)
+                    });
+                },
+                // Incoming messages from the Comms layer
+                msg = atomic_swap_message_stream.select_next_some() => {
+                    let swap_id = msg.inner.swap_id;
+                    let result = self.handle_message(msg.dht_header.origin_public_key, msg.inner).await;
+                    if let Err(e) = result {
+                        error!(target: LOG_TARGET, "Failed to handle incoming Atomic Swap message: {:?}", e);
+                        let _ = self
+                            .publish_event(AtomicSwapEvent::Error(format!(
+                                "Atomic swap (Id: {}) message could not be handled: {:?}",
+                                swap_id, e
+                            )))
+                            .await;
+                    }
+                },
+                msg = base_node_response_stream.select_next_some() => {
+                    let result = self.handle_base_node_response(msg.inner).await.or_else(|err| {
+                        error!(target: LOG_TARGET, "Failed to handle incoming Base Node response: {:?}", err);
+                        Err(err)
+                    });
+
+                    if let Err(e) = result {
+                        self.abort_scan(e).await;
+                    }
+                },
+                _ = retry_tick.select_next_some() => {
+                    let result = self.resend_pending_request().await.or_else(|err| {
+                        error!(target: LOG_TARGET, "Failed to resend request to the base node: {:?}", err);
+                        Err(err)
+                    });
+
+                    if let Err(e) = result {
+                        self.abort_scan(e).await;
+                    }
+                },
+                _ = scan_tick.select_next_some() => {
+                    match self.start_scan().await {
+                        // Swaps are not watched for until a base node has been set
+                        Ok(()) | Err(AtomicSwapServiceError::NoBaseNodePublicKey) => (),
+                        Err(e) => {
+                            error!(target: LOG_TARGET, "Failed to start scanning the blockchain: {:?}", e);
+                            self.abort_scan(e).await;
+                        },
+                    }
+                },
+                complete => {
+                    info!(target: LOG_TARGET, "Atomic Swap service shutting down");
+                    break;
+                }
+            }
+        }
+        info!(target: LOG_TARGET, "Atomic Swap Service ended");
+        Ok(())
+    }
+
+    /// This handler is called when the Service executor loops receives an API request
+    async fn handle_request(
+        &mut self,
+        request: AtomicSwapServiceRequest,
+    ) -> Result<AtomicSwapServiceResponse, AtomicSwapServiceError>
+    {
+        match request {
+            AtomicSwapServiceRequest::InitiateSwap((claimer, value, hash, refund_height, fee_per_gram, message)) => {
+                self.initiate_swap(claimer, value, hash, refund_height, fee_per_gram, message)
+                    .await
+                    .map(AtomicSwapServiceResponse::SwapInitiated)
+            },
+            AtomicSwapServiceRequest::AcceptSwap((swap_id, fee_per_gram)) => self
+                .accept_swap(swap_id, fee_per_gram)
+                .await
+                .map(|_| AtomicSwapServiceResponse::SwapAccepted),
+            AtomicSwapServiceRequest::ClaimSwap((swap_id, preimage)) => self
+                .claim_swap(swap_id, preimage)
+                .await
+                .map(|_| AtomicSwapServiceResponse::SwapClaimed),
+            AtomicSwapServiceRequest::RefundSwap(swap_id) => self
+                .refund_swap(swap_id)
+                .await
+                .map(|_| AtomicSwapServiceResponse::SwapRefunded),
+            AtomicSwapServiceRequest::CancelSwap(swap_id) => self
+                .cancel_swap(swap_id)
+                .await
+                .map(|_| AtomicSwapServiceResponse::SwapCancelled),
+            AtomicSwapServiceRequest::GetSwaps => Ok(AtomicSwapServiceResponse::Swaps(
+                self.output_manager_service.get_atomic_swaps().await?,
+            )),
+            AtomicSwapServiceRequest::SetBaseNodePublicKey(public_key) => {
+                self.base_node_public_key = Some(public_key);
+                Ok(AtomicSwapServiceResponse::BaseNodePublicKeySet)
+            },
+        }
+    }
+
+    /// Handle a message of the protocol from another wallet. Messages from wallets that are not the counterparty of
+    /// the swap they refer to, or that do not fit the state of the swap, are ignored.
+    async fn handle_message(
+        &mut self,
+        source: CommsPublicKey,
+        message: proto::AtomicSwapMessage,
+    ) -> Result<(), AtomicSwapServiceError>
+    {
+        let AtomicSwapMessage { swap_id, step } =
+            AtomicSwapMessage::try_from(message).map_err(AtomicSwapServiceError::InvalidMessageError)?;
+        match step {
+            AtomicSwapStep::Offer((value, hash, refund_height)) => {
+                self.accept_offer(source, swap_id, value, hash, refund_height).await
+            },
+            AtomicSwapStep::Accept(claim_commitment) => self.fund_swap(source, swap_id, claim_commitment).await,
+            AtomicSwapStep::Funded((spending_key, refund_commitment)) => {
+                self.accept_funded_swap(source, swap_id, spending_key, refund_commitment)
+                    .await
+            },
+            AtomicSwapStep::Claimed(preimage) => self.accept_claimed_swap(source, swap_id, preimage).await,
+            AtomicSwapStep::Cancelled(reason) => self.accept_cancellation(source, swap_id, reason).await,
+        }
+    }
+
+    /// Offer the claimer a hash locked output that this wallet funds once the offer is accepted
+    async fn initiate_swap(
+        &mut self,
+        claimer: CommsPublicKey,
+        value: MicroTari,
+        hash: HashOutput,
+        refund_height: u64,
+        fee_per_gram: MicroTari,
+        message: String,
+    ) -> Result<u64, AtomicSwapServiceError>
+    {
+        if hash.len() != 32 {
+            return Err(AtomicSwapServiceError::InvalidHash);
+        }
+        let fee = Fee::calculate(fee_per_gram, 1, 1);
+        if fee >= value {
+            return Err(AtomicSwapServiceError::FeeExceedsValue);
+        }
+
+        let mut rng = rand::OsRng::new().unwrap();
+        let swap_id = rng.next_u64();
+        let swap = AtomicSwap {
+            swap_id,
+            role: AtomicSwapRole::Funder,
+            counterparty: claimer.clone(),
+            value,
+            hash: hash.clone(),
+            refund_height,
+            fee_per_gram,
+            output_value: value - fee,
+            output_key: PrivateKey::random(&mut rng),
+            counterparty_commitment: None,
+            spending_key: None,
+            preimage: None,
+            message,
+            status: AtomicSwapStatus::Offered,
+        };
+        self.output_manager_service.save_atomic_swap(swap).await?;
+
+        self.send_message(claimer, swap_id, AtomicSwapStep::Offer((value, hash, refund_height)))
+            .await?;
+        info!(target: LOG_TARGET, "Atomic swap (Id: {}) of {} offered", swap_id, value);
+
+        Ok(swap_id)
+    }
+
+    /// Store a swap offered by another wallet, which has to be accepted before the other wallet funds it
+    async fn accept_offer(
+        &mut self,
+        source: CommsPublicKey,
+        swap_id: u64,
+        value: MicroTari,
+        hash: HashOutput,
+        refund_height: u64,
+    ) -> Result<(), AtomicSwapServiceError>
+    {
+        if hash.len() != 32 {
+            return Err(AtomicSwapServiceError::InvalidHash);
+        }
+        if self.fetch_swap(swap_id).await.is_ok() {
+            return Err(AtomicSwapServiceError::InvalidMessageError(
+                "An atomic swap with this id already exists".to_string(),
+            ));
+        }
+        let mut rng = rand::OsRng::new().unwrap();
+        let swap = AtomicSwap {
+            swap_id,
+            role: AtomicSwapRole::Claimer,
+            counterparty: source,
+            value,
+            hash,
+            refund_height,
+            fee_per_gram: MicroTari::from(0),
+            output_value: MicroTari::from(0),
+            output_key: PrivateKey::random(&mut rng),
+            counterparty_commitment: None,
+            spending_key: None,
+            preimage: None,
+            message: String::new(),
+            status: AtomicSwapStatus::Offered,
+        };
+        self.output_manager_service.save_atomic_swap(swap).await?;
+        info!(
+            target: LOG_TARGET,
+            "Atomic swap (Id: {}) of {} received", swap_id, value
+        );
+
+        self.publish_event(AtomicSwapEvent::SwapOffered(swap_id)).await
+    }
+
+    /// Accept a swap that another wallet has offered by sending it the commitment of the output that a claim will
+    /// create
+    async fn accept_swap(&mut self, swap_id: u64, fee_per_gram: MicroTari) -> Result<(), AtomicSwapServiceError> {
+        let mut swap = self.fetch_swap(swap_id).await?;
+        if swap.role != AtomicSwapRole::Claimer || swap.status != AtomicSwapStatus::Offered {
+            return Err(AtomicSwapServiceError::InvalidSwapState);
+        }
+        let fee = Fee::calculate(fee_per_gram, 1, 1);
+        if fee >= swap.value {
+            return Err(AtomicSwapServiceError::FeeExceedsValue);
+        }
+        swap.fee_per_gram = fee_per_gram;
+        swap.output_value = swap.value - fee;
+        swap.status = AtomicSwapStatus::Accepted;
+        let claim_commitment = self
+            .factories
+            .commitment
+            .commit_value(&swap.output_key, swap.output_value.into());
+        let counterparty = swap.counterparty.clone();
+        self.output_manager_service.save_atomic_swap(swap).await?;
+
+        self.send_message(counterparty, swap_id, AtomicSwapStep::Accept(claim_commitment))
+            .await
+    }
+
+    /// Fund the hash locked output of a swap that the claimer has accepted, and send the claimer its spending key
+    async fn fund_swap(
+        &mut self,
+        source: CommsPublicKey,
+        swap_id: u64,
+        claim_commitment: Commitment,
+    ) -> Result<(), AtomicSwapServiceError>
+    {
+        let mut swap = match self.fetch_swap(swap_id).await {
+            Ok(swap)
+                if swap.role == AtomicSwapRole::Funder &&
+                    swap.status == AtomicSwapStatus::Offered &&
+                    swap.counterparty == source =>
+            {
+                swap
+            },
+            _ => return Ok(()),
+        };
+        let refund_commitment = self
+            .factories
+            .commitment
+            .commit_value(&swap.output_key, swap.output_value.into());
+        let hash_lock = HashLock {
+            hash: swap.hash.clone(),
+            claim_commitment: claim_commitment.clone(),
+            refund_height: swap.refund_height,
+            refund_commitment: refund_commitment.clone(),
+        };
+        let spending_key = PrivateKey::random(&mut rand::OsRng::new().unwrap());
+
+        let (tx_id, fee, amount, transaction) = self
+            .output_manager_service
+            .create_hash_locked_funding(
+                swap.value,
+                spending_key.clone(),
+                OutputFeatures::with_hash_lock(hash_lock),
+                swap.fee_per_gram,
+                swap.message.clone(),
+            )
+            .await?;
+        self.transaction_service
+            .submit_transaction_to_self(tx_id, amount, fee, transaction, swap.message.clone())
+            .await?;
+        info!(
+            target: LOG_TARGET,
+            "Atomic swap (Id: {}) funded by transaction (TxId: {})", swap_id, tx_id
+        );
+
+        swap.counterparty_commitment = Some(claim_commitment);
+        swap.spending_key = Some(spending_key.clone());
+        swap.status = AtomicSwapStatus::Funded;
+        let counterparty = swap.counterparty.clone();
+        self.output_manager_service.save_atomic_swap(swap).await?;
+        self.publish_event(AtomicSwapEvent::SwapFunded(swap_id)).await?;
+
+        self.send_message(
+            counterparty,
+            swap_id,
+            AtomicSwapStep::Funded((spending_key, refund_commitment)),
+        )
+        .await?;
+
+        // Start watching the chain for the claim straight away, so the swap is scanned for from the current tip
+        match self.start_scan().await {
+            Ok(()) | Err(AtomicSwapServiceError::NoBaseNodePublicKey) => Ok(()),
+            Err(e) => {
+                self.abort_scan(e).await;
+                Ok(())
+            },
+        }
+    }
+
+    /// Store the spending key of the hash locked output of a swap that the funder has funded. It is up to the user to
+    /// check that the funding transaction has been mined before locking coins on the other chain.
+    async fn accept_funded_swap(
+        &mut self,
+        source: CommsPublicKey,
+        swap_id: u64,
+        spending_key: PrivateKey,
+        refund_commitment: Commitment,
+    ) -> Result<(), AtomicSwapServiceError>
+    {
+        let mut swap = match self.fetch_swap(swap_id).await {
+            Ok(swap)
+                if swap.role == AtomicSwapRole::Claimer &&
+                    swap.status == AtomicSwapStatus::Accepted &&
+                    swap.counterparty == source =>
+            {
+                swap
+            },
+            _ => return Ok(()),
+        };
+        swap.spending_key = Some(spending_key);
+        swap.counterparty_commitment = Some(refund_commitment);
+        swap.status = AtomicSwapStatus::Funded;
+        self.output_manager_service.save_atomic_swap(swap).await?;
+
+        self.publish_event(AtomicSwapEvent::SwapFunded(swap_id)).await
+    }
+
+    /// Claim the hash locked output of a funded swap into this wallet by revealing the preimage of its hash, and let
+    /// the funder know the preimage
+    async fn claim_swap(&mut self, swap_id: u64, preimage: Vec<u8>) -> Result<(), AtomicSwapServiceError> {
+        let mut swap = self.fetch_swap(swap_id).await?;
+        if swap.role != AtomicSwapRole::Claimer || swap.status != AtomicSwapStatus::Funded {
+            return Err(AtomicSwapServiceError::InvalidSwapState);
+        }
+        let hash_locked = self.hash_locked_output(&swap)?;
+        if preimage.len() != HASH_LOCK_PREIMAGE_LENGTH || HashLock::hash_preimage(&preimage) != swap.hash {
+            return Err(AtomicSwapServiceError::InvalidPreimage);
+        }
+        let claim_output = UnblindedOutput::new(swap.output_value, swap.output_key.clone(), None);
+        let rewind_data = self.output_manager_service.get_rewind_data().await?;
+        let transaction = build_claim_transaction(
+            &hash_locked,
+            &claim_output,
+            preimage.clone(),
+            Some(&rewind_data),
+            &self.factories,
+        )?;
+
+        let tx_id = rand::OsRng::new().unwrap().next_u64();
+        let fee = swap.value - swap.output_value;
+        self.output_manager_service
+            .add_pending_incoming_output(tx_id, claim_output)
+            .await?;
+        if let Err(e) = self
+            .transaction_service
+            .submit_transaction_to_self(tx_id, swap.output_value, fee, transaction, swap.message.clone())
+            .await
+        {
+            let _ = self.output_manager_service.cancel_transaction(tx_id).await;
+            return Err(e.into());
+        }
+        info!(
+            target: LOG_TARGET,
+            "Atomic swap (Id: {}) claimed by transaction (TxId: {})", swap_id, tx_id
+        );
+
+        swap.preimage = Some(preimage.clone());
+        swap.status = AtomicSwapStatus::Claimed;
+        let counterparty = swap.counterparty.clone();
+        self.output_manager_service.save_atomic_swap(swap).await?;
+        if let Err(e) = self
+            .send_message(counterparty, swap_id, AtomicSwapStep::Claimed(preimage))
+            .await
+        {
+            warn!(
+                target: LOG_TARGET,
+                "Could not send the preimage of atomic swap (Id: {}) to the funder: {:?}", swap_id, e
+            );
+        }
+        Ok(())
+    }
+
+    /// Store the preimage that the claimer of a swap that this wallet funded sent in its message
+    async fn accept_claimed_swap(
+        &mut self,
+        source: CommsPublicKey,
+        swap_id: u64,
+        preimage: Vec<u8>,
+    ) -> Result<(), AtomicSwapServiceError>
+    {
+        let swap = match self.fetch_swap(swap_id).await {
+            Ok(swap)
+                if swap.role == AtomicSwapRole::Funder &&
+                    swap.status == AtomicSwapStatus::Funded &&
+                    swap.counterparty == source =>
+            {
+                swap
+            },
+            _ => return Ok(()),
+        };
+        self.complete_claimed_swap(swap, preimage).await?;
+        info!(
+            target: LOG_TARGET,
+            "Atomic swap (Id: {}) claimed by the claimer", swap_id
+        );
+        Ok(())
+    }
+
+    /// Mark a swap that this wallet funded as claimed with the preimage that the claimer revealed
+    async fn complete_claimed_swap(
+        &mut self,
+        mut swap: AtomicSwap,
+        preimage: Vec<u8>,
+    ) -> Result<(), AtomicSwapServiceError>
+    {
+        if preimage.len() != HASH_LOCK_PREIMAGE_LENGTH || HashLock::hash_preimage(&preimage) != swap.hash {
+            return Err(AtomicSwapServiceError::InvalidPreimage);
+        }
+        let swap_id = swap.swap_id;
+        swap.preimage = Some(preimage.clone());
+        swap.status = AtomicSwapStatus::Claimed;
+        self.output_manager_service.save_atomic_swap(swap).await?;
+
+        self.publish_event(AtomicSwapEvent::SwapClaimed((swap_id, preimage)))
+            .await
+    }
+
+    /// Refund the hash locked output of a swap that this wallet funded. The refund transaction is rejected by the base
+    /// layer before the refund height of the swap.
+    async fn refund_swap(&mut self, swap_id: u64) -> Result<(), AtomicSwapServiceError> {
+        let mut swap = self.fetch_swap(swap_id).await?;
+        if swap.role != AtomicSwapRole::Funder || swap.status != AtomicSwapStatus::Funded {
+            return Err(AtomicSwapServiceError::InvalidSwapState);
+        }
+        let hash_locked = self.hash_locked_output(&swap)?;
+        let refund_output = UnblindedOutput::new(swap.output_value, swap.output_key.clone(), None);
+        let rewind_data = self.output_manager_service.get_rewind_data().await?;
+        let transaction = build_refund_transaction(&hash_locked, &refund_output, Some(&rewind_data), &self.factories)?;
+
+        let tx_id = rand::OsRng::new().unwrap().next_u64();
+        let fee = swap.value - swap.output_value;
+        self.output_manager_service
+            .add_pending_incoming_output(tx_id, refund_output)
+            .await?;
+        if let Err(e) = self
+            .transaction_service
+            .submit_transaction_to_self(tx_id, swap.output_value, fee, transaction, swap.message.clone())
+            .await
+        {
+            let _ = self.output_manager_service.cancel_transaction(tx_id).await;
+            return Err(e.into());
+        }
+        info!(
+            target: LOG_TARGET,
+            "Atomic swap (Id: {}) refunded by transaction (TxId: {})", swap_id, tx_id
+        );
+
+        swap.status = AtomicSwapStatus::Refunded;
+        let counterparty = swap.counterparty.clone();
+        self.output_manager_service.save_atomic_swap(swap).await?;
+        if let Err(e) = self
+            .send_message(counterparty, swap_id, AtomicSwapStep::Cancelled("Refunded".to_string()))
+            .await
+        {
+            warn!(
+                target: LOG_TARGET,
+                "Could not send the refund of atomic swap (Id: {}) to the claimer: {:?}", swap_id, e
+            );
+        }
+        self.publish_event(AtomicSwapEvent::SwapRefunded(swap_id)).await
+    }
+
+    /// Abandon a swap that has not been funded yet and let the counterparty know
+    async fn cancel_swap(&mut self, swap_id: u64) -> Result<(), AtomicSwapServiceError> {
+        let mut swap = self.fetch_swap(swap_id).await?;
+        if swap.status != AtomicSwapStatus::Offered && swap.status != AtomicSwapStatus::Accepted {
+            return Err(AtomicSwapServiceError::InvalidSwapState);
+        }
+        swap.status = AtomicSwapStatus::Cancelled;
+        let counterparty = swap.counterparty.clone();
+        self.output_manager_service.save_atomic_swap(swap).await?;
+        if let Err(e) = self
+            .send_message(
+                counterparty,
+                swap_id,
+                AtomicSwapStep::Cancelled("Cancelled by counterparty".to_string()),
+            )
+            .await
+        {
+            warn!(
+                target: LOG_TARGET,
+                "Could not send cancellation of atomic swap (Id: {}): {:?}", swap_id, e
+            );
+        }
+        Ok(())
+    }
+
+    /// Mark a swap as cancelled when its counterparty abandons it, unless it has already been settled
+    async fn accept_cancellation(
+        &mut self,
+        source: CommsPublicKey,
+        swap_id: u64,
+        reason: String,
+    ) -> Result<(), AtomicSwapServiceError>
+    {
+        let mut swap = match self.fetch_swap(swap_id).await {
+            Ok(swap)
+                if swap.counterparty == source &&
+                    swap.status != AtomicSwapStatus::Claimed &&
+                    swap.status != AtomicSwapStatus::Refunded &&
+                    swap.status != AtomicSwapStatus::Cancelled =>
+            {
+                swap
+            },
+            _ => return Ok(()),
+        };
+        swap.status = AtomicSwapStatus::Cancelled;
+        self.output_manager_service.save_atomic_swap(swap).await?;
+        info!(
+            target: LOG_TARGET,
+            "Atomic swap (Id: {}) cancelled by the counterparty: {}", swap_id, reason
+        );
+
+        self.publish_event(AtomicSwapEvent::SwapCancelled(swap_id)).await
+    }
+
+    /// Rebuild the hash locked output of a funded swap from its spending key and the commitments of both sides
+    fn hash_locked_output(&self, swap: &AtomicSwap) -> Result<UnblindedOutput, AtomicSwapServiceError> {
+        let (spending_key, counterparty_commitment) = match (&swap.spending_key, &swap.counterparty_commitment) {
+            (Some(k), Some(c)) => (k.clone(), c.clone()),
+            _ => return Err(AtomicSwapServiceError::InvalidSwapState),
+        };
+        let own_commitment = self
+            .factories
+            .commitment
+            .commit_value(&swap.output_key, swap.output_value.into());
+        let (claim_commitment, refund_commitment) = match swap.role {
+            AtomicSwapRole::Funder => (counterparty_commitment, own_commitment),
+            AtomicSwapRole::Claimer => (own_commitment, counterparty_commitment),
+        };
+        let hash_lock = HashLock {
+            hash: swap.hash.clone(),
+            claim_commitment,
+            refund_height: swap.refund_height,
+            refund_commitment,
+        };
+        Ok(UnblindedOutput::new(
+            swap.value,
+            spending_key,
+            Some(OutputFeatures::with_hash_lock(hash_lock)),
+        ))
+    }
+
+    /// Start scanning the blockchain for the claims of the swaps that this wallet funded by asking the base node for
+    /// the height of its chain. Nothing is done if a scan is already in progress or if no swap is waiting to be
+    /// claimed.
+    async fn start_scan(&mut self) -> Result<(), AtomicSwapServiceError> {
+        if self.scan.is_some() {
+            return Ok(());
+        }
+        if self.base_node_public_key.is_none() {
+            return Err(AtomicSwapServiceError::NoBaseNodePublicKey);
+        }
+        if self.fetch_funded_swaps().await?.is_empty() {
+            return Ok(());
+        }
+
+        self.scan = Some(ScanState {
+            next_height: 0,
+            chain_height: None,
+            pending_request: None,
+        });
+        let result = self.send_request(BaseNodeRequestProto::GetChainMetadata(true)).await;
+        if result.is_err() {
+            self.scan = None;
+        }
+        result
+    }
+
+    /// Handle the base node's responses to the chain metadata and block requests made during a scan
+    async fn handle_base_node_response(
+        &mut self,
+        response: BaseNodeServiceResponse,
+    ) -> Result<(), AtomicSwapServiceError>
+    {
+        let BaseNodeServiceResponse { request_key, response } = response;
+        let scan = match self.scan.as_mut() {
+            None => return Ok(()),
+            Some(s) => s,
+        };
+        match scan.pending_request {
+            Some(ref request) if request.request_key == request_key => (),
+            // Responses to queries made by other wallet services are ignored
+            _ => return Ok(()),
+        }
+        scan.pending_request = None;
+
+        match response {
+            Some(BaseNodeResponseProto::ChainMetadata(metadata)) => {
+                let chain_height = match metadata.height_of_longest_chain {
+                    Some(height) => height,
+                    None => {
+                        self.scan = None;
+                        return Ok(());
+                    },
+                };
+                // Swaps that have not been scanned for yet are scanned for from the chain tip, which the claim cannot
+                // be mined below as the funding transaction has already been submitted
+                let mut next_height = chain_height;
+                for mut swap in self.fetch_funded_swaps().await? {
+                    match swap.scan_height {
+                        Some(height) => next_height = cmp::min(next_height, height),
+                        None => {
+                            swap.scan_height = Some(chain_height);
+                            self.output_manager_service.save_atomic_swap(swap).await?;
+                        },
+                    }
+                }
+                if let Some(scan) = self.scan.as_mut() {
+                    scan.next_height = next_height;
+                    scan.chain_height = Some(chain_height);
+                }
+            },
+            Some(BaseNodeResponseProto::HistoricalBlocks(historical_blocks)) => {
+                let blocks = historical_blocks
+                    .blocks
+                    .into_iter()
+                    .map(block_kernels)
+                    .collect::<Result<Vec<_>, _>>()?;
+                self.find_claims(blocks).await?;
+            },
+            _ => return Err(AtomicSwapServiceError::UnexpectedBaseNodeResponse),
+        }
+
+        self.request_next_blocks().await
+    }
+
+    /// Search the kernels of the scanned blocks for the preimages of the funded swaps. Swaps whose preimage is found
+    /// are marked as claimed, and the scan heights of the others are moved past the scanned blocks.
+    async fn find_claims(&mut self, blocks: Vec<(u64, Vec<TransactionKernel>)>) -> Result<(), AtomicSwapServiceError> {
+        let last_height = match blocks.iter().map(|(height, _)| *height).max() {
+            Some(height) => height,
+            None => return Ok(()),
+        };
+        for mut swap in self.fetch_funded_swaps().await? {
+            let scan_height = match swap.scan_height {
+                Some(height) if height <= last_height => height,
+                _ => continue,
+            };
+            let found = blocks
+                .iter()
+                .filter(|(height, _)| *height >= scan_height)
+                .find_map(|(height, kernels)| find_hash_preimage(kernels, &swap.hash).map(|p| (*height, p)));
+            match found {
+                Some((height, preimage)) => {
+                    let swap_id = swap.swap_id;
+                    self.complete_claimed_swap(swap, preimage).await?;
+                    info!(
+                        target: LOG_TARGET,
+                        "Atomic swap (Id: {}) claimed in block #{}", swap_id, height
+                    );
+                },
+                None => {
+                    swap.scan_height = Some(last_height + 1);
+                    self.output_manager_service.save_atomic_swap(swap).await?;
+                },
+            }
+        }
+        Ok(())
+    }
+
+    /// Request the next range of blocks from the base node, or complete the scan once the chain tip is reached
+    async fn request_next_blocks(&mut self) -> Result<(), AtomicSwapServiceError> {
+        let blocks_per_request = cmp::max(self.config.blocks_per_request, 1);
+        let heights = match self.scan.as_mut() {
+            None => return Ok(()),
+            Some(scan) => match scan.chain_height {
+                Some(chain_height) if scan.next_height <= chain_height => {
+                    let last_height = cmp::min(scan.next_height + blocks_per_request - 1, chain_height);
+                    let heights = (scan.next_height..=last_height).collect::<Vec<_>>();
+                    scan.next_height = last_height + 1;
+                    heights
+                },
+                _ => {
+                    self.scan = None;
+                    return Ok(());
+                },
+            },
+        };
+
+        self.send_request(BaseNodeRequestProto::FetchBlocks(BlockHeights { heights }))
+            .await
+    }
+
+    /// Stop the scan that is in progress and publish the reason on the event stream. The blocks that were not scanned
+    /// are scanned again at the next scan interval.
+    async fn abort_scan(&mut self, error: AtomicSwapServiceError) {
+        if self.scan.take().is_some() {
+            let _ = self
+                .publish_event(AtomicSwapEvent::Error(format!(
+                    "Blockchain scan for atomic swap claims failed: {:?}",
+                    error
+                )))
+                .await;
+        }
+    }
+
+    /// Send the request to the base node again if it has not been answered by the time the retry interval elapses
+    async fn resend_pending_request(&mut self) -> Result<(), AtomicSwapServiceError> {
+        let request = match self.scan.as_ref().and_then(|s| s.pending_request.clone()) {
+            None => return Ok(()),
+            Some(request) => request,
+        };
+        warn!(
+            target: LOG_TARGET,
+            "Base node did not respond to request (key: {}), sending it again", request.request_key
+        );
+        self.send_base_node_request(request).await
+    }
+
+    async fn send_request(&mut self, request: BaseNodeRequestProto) -> Result<(), AtomicSwapServiceError> {
+        let request = BaseNodeServiceRequest {
+            request_key: rand::OsRng::new().unwrap().next_u64(),
+            request: Some(request),
+        };
+        if let Some(scan) = self.scan.as_mut() {
+            scan.pending_request = Some(request.clone());
+        }
+        self.send_base_node_request(request).await
+    }
+
+    async fn send_base_node_request(&mut self, request: BaseNodeServiceRequest) -> Result<(), AtomicSwapServiceError> {
+        let base_node_public_key = self
+            .base_node_public_key
+            .clone()
+            .ok_or(AtomicSwapServiceError::NoBaseNodePublicKey)?;
+        if let SendMessageResponse::Failed = self
+            .outbound_message_service
+            .send_direct(
+                base_node_public_key,
+                OutboundEncryption::EncryptForPeer,
+                OutboundDomainMessage::new(TariMessageType::BaseNodeRequest, request),
+            )
+            .await
+            .map_err(|_| AtomicSwapServiceError::OutboundSendFailure)?
+        {
+            return Err(AtomicSwapServiceError::OutboundSendFailure);
+        }
+
+        Ok(())
+    }
+
+    /// The swaps that this wallet funded and that have not been claimed or refunded yet
+    async fn fetch_funded_swaps(&mut self) -> Result<Vec<AtomicSwap>, AtomicSwapServiceError> {
+        Ok(self
+            .output_manager_service
+            .get_atomic_swaps()
+            .await?
+            .into_iter()
+            .filter(|s| s.role == AtomicSwapRole::Funder && s.status == AtomicSwapStatus::Funded)
+            .collect())
+    }
+
+    async fn fetch_swap(&mut self, swap_id: u64) -> Result<AtomicSwap, AtomicSwapServiceError> {
+        self.output_manager_service
+            .get_atomic_swaps()
+            .await?
+            .into_iter()
+            .find(|s| s.swap_id == swap_id)
+            .ok_or(AtomicSwapServiceError::SwapNotFound)
+    }
+
+    async fn send_message(
+        &mut self,
+        recipient: CommsPublicKey,
+        swap_id: u64,
+        step: AtomicSwapStep,
+    ) -> Result<(), AtomicSwapServiceError>
+    {
+        let message = proto::AtomicSwapMessage::from(AtomicSwapMessage::new(swap_id, step));
+        if let SendMessageResponse::Failed = self
+            .outbound_message_service
+            .send_direct(
+                recipient,
+                OutboundEncryption::EncryptForPeer,
+                OutboundDomainMessage::new(TariMessageType::AtomicSwap, message),
+            )
+            .await
+            .map_err(|_| AtomicSwapServiceError::OutboundSendFailure)?
+        {
+            return Err(AtomicSwapServiceError::OutboundSendFailure);
+        }
+
+        Ok(())
+    }
+
+    async fn publish_event(&mut self, event: AtomicSwapEvent) -> Result<(), AtomicSwapServiceError> {
+        self.event_publisher
+            .send(event)
+            .await
+            .map_err(|_| AtomicSwapServiceError::EventStreamError)
+    }
+}
+
+/// The progress of a scan for swap claims that is in progress
+struct ScanState {
+    next_height: u64,
+    chain_height: Option<u64>,
+    pending_request: Option<BaseNodeServiceRequest>,
+}
+
+/// The height and kernels of a block received from the base node
+fn block_kernels(
+    historical_block: HistoricalBlockProto,
+) -> Result<(u64, Vec<TransactionKernel>), AtomicSwapServiceError> {
+    let block = historical_block
+        .block
+        .ok_or_else(|| AtomicSwapServiceError::InvalidMessageError("Historical block has no block".to_string()))?;
+    let height = block
+        .header
+        .map(|h| h.height)
+        .ok_or_else(|| AtomicSwapServiceError::InvalidMessageError("Block has no header".to_string()))?;
+    let body = block
+        .body
+        .ok_or_else(|| AtomicSwapServiceError::InvalidMessageError("Block has no body".to_string()))
+        .and_then(|b| AggregateBody::try_from(b).map_err(AtomicSwapServiceError::InvalidMessageError))?;
+
+    Ok((height, body.kernels().clone()))
+}
@@ -0,0 +1,264 @@
+// Copyright 2020, The Tari Project
+//
+// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+// following conditions are met:
+//
+// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+// disclaimer.
+//
+// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+// following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+// 3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+// products derived from this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+// INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+// SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+// WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+// USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+//! The wallet backup file format. A backup bundles the node identity, the saved base node peers, the key manager
+//! state, every output, the transaction history and the contacts of a wallet. The backup is serialized to JSON and
+//! encrypted with a key derived from a user passphrase using the same authenticated cipher as the wallet's own
+//! secrets, so a backup that was encrypted with another passphrase or has been modified cannot be read.
+//!
+//! The file itself is a small JSON envelope holding the format version, the salt of the key derivation and the hex
+//! encoded cipher text. The version is repeated inside the encrypted contents and must match, so the unauthenticated
+//! envelope version cannot be altered to change how the contents are read.
+
+use crate::{
+    contacts_service::storage::database::Contact,
+    encryption::{SecretCipher, PASSPHRASE_SALT_LENGTH},
+    error::BackupError,
+    output_manager_service::storage::database::OutputManagerBackup,
+    transaction_service::storage::database::TransactionServiceBackup,
+};
+use rand::{OsRng, RngCore};
+use serde::{Deserialize, Serialize};
+use std::{fs, path::Path};
+use tari_comms::{peer_manager::Peer, types::CommsSecretKey};
+use tari_utilities::hex::{from_hex, to_hex};
+
+/// The version of the backup format written by this version of the wallet
+pub const BACKUP_FORMAT_VERSION: u32 = 1;
+/// The oldest version of the backup format that can still be restored
+pub const MIN_BACKUP_FORMAT_VERSION: u32 = 1;
+
+/// The contents of a wallet backup. Sections that were added after the first version of the format are defaulted when
+/// they are missing so that older backups can still be restored.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct WalletBackup {
+    pub version: u32,
+    pub comms_secret_key: CommsSecretKey,
+    #[serde(default)]
+    pub peers: Vec<Peer>,
+    pub output_manager: OutputManagerBackup,
+    #[serde(default)]
+    pub transactions: TransactionServiceBackup,
+    #[serde(default)]
+    pub contacts: Vec<Contact>,
+}
+
+/// The unencrypted envelope that is written to the backup file
+#[derive(Debug, Serialize, Deserialize)]
+struct BackupFile {
+    version: u32,
+    salt: String,
+    contents: String,
+}
+
+impl WalletBackup {
+    pub fn new(
+        comms_secret_key: CommsSecretKey,
+        peers: Vec<Peer>,
+        output_manager: OutputManagerBackup,
+        transactions: TransactionServiceBackup,
+        contacts: Vec<Contact>,
+    ) -> Self
+    {
+        Self {
+            version: BACKUP_FORMAT_VERSION,
+            comms_secret_key,
+            peers,
+            output_manager,
+            transactions,
+            contacts,
+        }
+    }
+
+    /// Serialize and encrypt the backup with the passphrase, returning the contents of the backup file
+    pub fn encrypt(&self, passphrase: &str) -> Result<String, BackupError> {
+        let plain_text = serde_json::to_vec(self).map_err(|e| BackupError::SerializationError(e.to_string()))?;
+        seal(self.version, &plain_text, passphrase)
+    }
+
+    /// Decrypt and deserialize the contents of a backup file with the passphrase it was encrypted with
+    pub fn decrypt(file_contents: &str, passphrase: &str) -> Result<Self, BackupError> {
+        let file: BackupFile = serde_json::from_str(file_contents).map_err(|_| BackupError::InvalidBackupFile)?;
+        check_version(file.version)?;
+
+        let salt = from_hex(&file.salt).map_err(|_| BackupError::InvalidBackupFile)?;
+        let cipher_text = from_hex(&file.contents).map_err(|_| BackupError::InvalidBackupFile)?;
+        let plain_text = SecretCipher::from_passphrase(passphrase, &salt)?
+            .decrypt(&cipher_text)
+            .map_err(|_| BackupError::DecryptionFailed)?;
+
+        let backup: WalletBackup =
+            serde_json::from_slice(&plain_text).map_err(|e| BackupError::SerializationError(e.to_string()))?;
+        if backup.version != file.version {
+            return Err(BackupError::InvalidBackupFile);
+        }
+
+        Ok(backup)
+    }
+
+    /// Write the encrypted backup to the file at the given path, replacing it if it exists
+    pub fn write_to_file(&self, path: &Path, passphrase: &str) -> Result<(), BackupError> {
+        fs::write(path, self.encrypt(passphrase)?).map_err(|e| BackupError::IoError(e.to_string()))
+    }
+
+    /// Read and decrypt the backup in the file at the given path
+    pub fn read_from_file(path: &Path, passphrase: &str) -> Result<Self, BackupError> {
+        let file_contents = fs::read_to_string(path).map_err(|e| BackupError::IoError(e.to_string()))?;
+        WalletBackup::decrypt(&file_contents, passphrase)
+    }
+}
+
+fn check_version(version: u32) -> Result<(), BackupError> {
+    if version < MIN_BACKUP_FORMAT_VERSION || version > BACKUP_FORMAT_VERSION {
+        return Err(BackupError::UnsupportedVersion(version));
+    }
+    Ok(())
+}
+
+/// Encrypt the serialized contents of a backup of the given format version with a key derived from the passphrase and
+/// a random salt
+fn seal(version: u32, plain_text: &[u8], passphrase: &str) -> Result<String, BackupError> {
+    let mut rng = OsRng::new().map_err(|_| BackupError::RandomnessUnavailable)?;
+    let mut salt = [0u8; PASSPHRASE_SALT_LENGTH];
+    rng.fill_bytes(&mut salt);
+    let cipher_text = SecretCipher::from_passphrase(passphrase, &salt)?.encrypt(plain_text)?;
+
+    let file = BackupFile {
+        version,
+        salt: to_hex(&salt),
+        contents: to_hex(&cipher_text),
+    };
+    serde_json::to_string(&file).map_err(|e| BackupError::SerializationError(e.to_string()))
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        backup::{seal, WalletBackup, BACKUP_FORMAT_VERSION},
+        error::BackupError,
+    };
+    use serde_json::json;
+    use tari_comms::types::CommsSecretKey;
+    use tari_crypto::keys::SecretKey;
+    use tari_utilities::hex::Hex;
+
+    fn version_1_contents(comms_secret_key: &CommsSecretKey, master_seed: &CommsSecretKey) -> serde_json::Value {
+        json!({
+            "version": 1,
+            "comms_secret_key": comms_secret_key.to_hex(),
+            "output_manager": {
+                "key_manager_state": {
+                    "master_seed": master_seed.to_hex(),
+                    "branch_seed": "",
+                    "primary_key_index": 5
+                },
+                "unspent_outputs": [],
+                "spent_outputs": [],
+                "invalid_outputs": [],
+                "pending_transactions": []
+            }
+        })
+    }
+
+    #[test]
+    fn backup_round_trip() {
+        let mut rng = rand::OsRng::new().unwrap();
+        let contents = version_1_contents(&CommsSecretKey::random(&mut rng), &CommsSecretKey::random(&mut rng));
+        let backup: WalletBackup = serde_json::from_value(contents).unwrap();
+
+        let file_contents = backup.encrypt("correct horse").unwrap();
+        assert!(!file_contents.contains(&backup.comms_secret_key.to_hex()));
+        assert_eq!(WalletBackup::decrypt(&file_contents, "correct horse").unwrap(), backup);
+        // Every backup is encrypted with a new salt
+        assert_ne!(backup.encrypt("correct horse").unwrap(), file_contents);
+    }
+
+    #[test]
+    fn version_1_backup_defaults_missing_sections() {
+        let mut rng = rand::OsRng::new().unwrap();
+        let comms_secret_key = CommsSecretKey::random(&mut rng);
+        let contents = version_1_contents(&comms_secret_key, &CommsSecretKey::random(&mut rng));
+        let file_contents = seal(1, contents.to_string().as_bytes(), "correct horse").unwrap();
+
+        let backup = WalletBackup::decrypt(&file_contents, "correct horse").unwrap();
+        assert_eq!(backup.version, 1);
+        assert_eq!(backup.comms_secret_key, comms_secret_key);
+        assert_eq!(backup.output_manager.key_manager_state.primary_key_index, 5);
+        assert!(backup.peers.is_empty());
+        assert!(backup.contacts.is_empty());
+        assert!(backup.transactions.completed_transactions.is_empty());
+    }
+
+    #[test]
+    fn unsupported_versions_are_rejected() {
+        let mut rng = rand::OsRng::new().unwrap();
+        let mut contents = version_1_contents(&CommsSecretKey::random(&mut rng), &CommsSecretKey::random(&mut rng));
+        let newer_version = BACKUP_FORMAT_VERSION + 1;
+        contents["version"] = json!(newer_version);
+        let file_contents = seal(newer_version, contents.to_string().as_bytes(), "correct horse").unwrap();
+        assert_eq!(
+            WalletBackup::decrypt(&file_contents, "correct horse").unwrap_err(),
+            BackupError::UnsupportedVersion(newer_version)
+        );
+
+        let file_contents = seal(0, contents.to_string().as_bytes(), "correct horse").unwrap();
+        assert_eq!(
+            WalletBackup::decrypt(&file_contents, "correct horse").unwrap_err(),
+            BackupError::UnsupportedVersion(0)
+        );
+
+        // The version of the envelope must match the version of the encrypted contents
+        let file_contents = seal(BACKUP_FORMAT_VERSION, contents.to_string().as_bytes(), "correct horse").unwrap();
+        assert_eq!(
+            WalletBackup::decrypt(&file_contents, "correct horse").unwrap_err(),
+            BackupError::InvalidBackupFile
+        );
+    }
+
+    #[test]
+    fn wrong_passphrase_and_tampering_are_detected() {
+        let mut rng = rand::OsRng::new().unwrap();
+        let contents = version_1_contents(&CommsSecretKey::random(&mut rng), &CommsSecretKey::random(&mut rng));
+        let backup: WalletBackup = serde_json::from_value(contents).unwrap();
+        let file_contents = backup.encrypt("correct horse").unwrap();
+
+        assert_eq!(
+            WalletBackup::decrypt(&file_contents, "battery staple").unwrap_err(),
+            BackupError::DecryptionFailed
+        );
+
+        let mut file: serde_json::Value = serde_json::from_str(&file_contents).unwrap();
+        let mut cipher_text = file["contents"].as_str().unwrap().to_string();
+        let last = if cipher_text.ends_with('0') { "1" } else { "0" };
+        cipher_text.replace_range(cipher_text.len() - 1.., last);
+        file["contents"] = json!(cipher_text);
+        assert_eq!(
+            WalletBackup::decrypt(&file.to_string(), "correct horse").unwrap_err(),
+            BackupError::DecryptionFailed
+        );
+
+        assert_eq!(
+            WalletBackup::decrypt("not a backup", "correct horse").unwrap_err(),
+            BackupError::InvalidBackupFile
+        );
+    }
+}
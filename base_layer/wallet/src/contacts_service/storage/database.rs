@@ -22,11 +22,12 @@
 
 use crate::contacts_service::error::ContactsServiceStorageError;
 use log::*;
+use serde::{Deserialize, Serialize};
 use std::fmt::{Display, Error, Formatter};
 use tari_comms::types::CommsPublicKey;
 const LOG_TARGET: &'static str = "wallet::contacts_service::database";
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Contact {
     pub alias: String,
     pub public_key: CommsPublicKey,
@@ -0,0 +1,179 @@
+// Copyright 2020, The Tari Project
+//
+// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+// following conditions are met:
+//
+// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+// disclaimer.
+//
+// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+// following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+// 3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+// products derived from this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+// INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+// SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+// WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+// USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+//! A human readable representation of a public key as a string of emoji, for users to compare when they check who they
+//! are paying. An emoji ID has one emoji for every byte of the public key from a table of 256 emoji, followed by a
+//! checksum emoji, so that a mistyped or truncated emoji ID is rejected instead of being read as another key.
+
+use crate::error::EmojiIdError;
+use digest::Digest;
+use std::fmt::{Display, Error, Formatter};
+use tari_comms::types::CommsPublicKey;
+use tari_crypto::common::Blake256;
+use tari_utilities::ByteArray;
+
+/// The number of emoji in an emoji ID, being one for each byte of the public key and one for the checksum
+pub const EMOJI_ID_LENGTH: usize = 33;
+
+/// The emoji that encode a byte with the value of their index
+const EMOJI: [char; 256] = [
+    '🌰', '🌱', '🌲', '🌳', '🌴', '🌵', '🌷', '🌸', '🌹', '🌺', '🌻', '🌼', '🌽', '🌾', '🌿', '🍀', '🍁', '🍂', '🍃',
+    '🍄', '🍅', '🍆', '🍇', '🍈', '🍉', '🍊', '🍋', '🍌', '🍍', '🍎', '🍏', '🍐', '🍑', '🍒', '🍓', '🍔', '🍕', '🍖',
+    '🍗', '🍘', '🍙', '🍚', '🍛', '🍜', '🍝', '🍞', '🍟', '🍠', '🍡', '🍢', '🍣', '🍤', '🍥', '🍦', '🍧', '🍨', '🍩',
+    '🍪', '🍫', '🍬', '🍭', '🍮', '🍯', '🍰', '🍱', '🍲', '🍳', '🍴', '🍵', '🍶', '🍷', '🍸', '🍹', '🍺', '🍻', '🍼',
+    '🎀', '🎁', '🎂', '🎃', '🎄', '🎅', '🎆', '🎇', '🎈', '🎉', '🎊', '🎋', '🎌', '🎍', '🎎', '🎏', '🎐', '🎑', '🎒',
+    '🎓', '🎠', '🎡', '🎢', '🎣', '🎤', '🎥', '🎦', '🎧', '🎨', '🎩', '🎪', '🎫', '🎬', '🎭', '🎮', '🎯', '🎰', '🎱',
+    '🎲', '🎳', '🎴', '🎵', '🎶', '🎷', '🎸', '🎹', '🎺', '🎻', '🎼', '🎽', '🎾', '🎿', '🏀', '🏁', '🏂', '🏃', '🏄',
+    '🏆', '🏇', '🏈', '🏉', '🏊', '🏠', '🏡', '🏢', '🏣', '🏤', '🏥', '🏦', '🏧', '🏨', '🏩', '🏪', '🏫', '🏬', '🏭',
+    '🏮', '🏯', '🏰', '🐀', '🐁', '🐂', '🐃', '🐄', '🐅', '🐆', '🐇', '🐈', '🐉', '🐊', '🐋', '🐌', '🐍', '🐎', '🐏',
+    '🐐', '🐑', '🐒', '🐓', '🐔', '🐕', '🐖', '🐗', '🐘', '🐙', '🐚', '🐛', '🐜', '🐝', '🐞', '🐟', '🐠', '🐡', '🐢',
+    '🐣', '🐤', '🐥', '🐦', '🐧', '🐨', '🐩', '🐪', '🐫', '🐬', '🐭', '🐮', '🐯', '🐰', '🐱', '🐲', '🐳', '🐴', '🐵',
+    '🐶', '🐷', '🐸', '🐹', '🐺', '🐻', '🐼', '🐽', '🐾', '💠', '💡', '💢', '💣', '💤', '💥', '💦', '💧', '💨', '💩',
+    '💪', '💫', '💬', '💭', '💮', '💯', '💰', '💱', '💲', '💳', '💴', '💵', '💶', '💷', '💸', '💹', '💺', '💻', '💼',
+    '💽', '💾', '💿', '📀', '📁', '📂', '📃', '📄', '📅',
+];
+
+/// A public key encoded as emoji with a checksum
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct EmojiId(String);
+
+impl EmojiId {
+    /// Encode the given public key as an emoji ID
+    pub fn from_pubkey(public_key: &CommsPublicKey) -> Self {
+        let bytes = public_key.as_bytes();
+        let emoji = bytes
+            .iter()
+            .chain(std::iter::once(&checksum(bytes)))
+            .map(|b| EMOJI[*b as usize])
+            .collect();
+        EmojiId(emoji)
+    }
+
+    /// Read an emoji ID, checking its length, emoji and checksum. Whitespace between the emoji is ignored.
+    pub fn from_emoji_string(emoji: &str) -> Result<Self, EmojiIdError> {
+        let bytes = emoji
+            .chars()
+            .filter(|c| !c.is_whitespace())
+            .map(|c| EMOJI.iter().position(|e| *e == c).map(|i| i as u8))
+            .collect::<Option<Vec<u8>>>()
+            .ok_or(EmojiIdError::InvalidEmoji)?;
+        if bytes.len() != EMOJI_ID_LENGTH {
+            return Err(EmojiIdError::InvalidLength);
+        }
+        let (key_bytes, check) = bytes.split_at(EMOJI_ID_LENGTH - 1);
+        if checksum(key_bytes) != check[0] {
+            return Err(EmojiIdError::InvalidChecksum);
+        }
+        let public_key = CommsPublicKey::from_bytes(key_bytes).map_err(|_| EmojiIdError::InvalidPublicKey)?;
+        Ok(EmojiId::from_pubkey(&public_key))
+    }
+
+    /// Whether the given string is a valid emoji ID
+    pub fn is_valid(emoji: &str) -> bool {
+        EmojiId::from_emoji_string(emoji).is_ok()
+    }
+
+    /// The public key that this emoji ID encodes
+    pub fn to_pubkey(&self) -> CommsPublicKey {
+        let bytes = self
+            .0
+            .chars()
+            .take(EMOJI_ID_LENGTH - 1)
+            .filter_map(|c| EMOJI.iter().position(|e| *e == c).map(|i| i as u8))
+            .collect::<Vec<u8>>();
+        CommsPublicKey::from_bytes(&bytes).expect("An emoji ID always encodes a valid public key")
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl Display for EmojiId {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), Error> {
+        f.write_str(&self.0)
+    }
+}
+
+/// The checksum byte of an emoji ID, the first byte of the Blake256 hash of the public key
+fn checksum(bytes: &[u8]) -> u8 {
+    Blake256::new().chain(b"emoji_id").chain(bytes).result()[0]
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        emoji::{EmojiId, EMOJI, EMOJI_ID_LENGTH},
+        error::EmojiIdError,
+    };
+    use rand::OsRng;
+    use tari_comms::types::{CommsPublicKey, CommsSecretKey};
+    use tari_crypto::keys::{PublicKey, SecretKey};
+
+    #[test]
+    fn emoji_table_has_distinct_emoji() {
+        let mut emoji = EMOJI.to_vec();
+        emoji.sort();
+        emoji.dedup();
+        assert_eq!(emoji.len(), 256);
+    }
+
+    #[test]
+    fn emoji_id_round_trip() {
+        let mut rng = OsRng::new().unwrap();
+        let public_key = CommsPublicKey::from_secret_key(&CommsSecretKey::random(&mut rng));
+        let emoji_id = EmojiId::from_pubkey(&public_key);
+        assert_eq!(emoji_id.as_str().chars().count(), EMOJI_ID_LENGTH);
+        assert_eq!(emoji_id.to_pubkey(), public_key);
+
+        let spaced = emoji_id
+            .as_str()
+            .chars()
+            .map(|c| c.to_string())
+            .collect::<Vec<_>>()
+            .join(" ");
+        assert_eq!(EmojiId::from_emoji_string(&spaced).unwrap(), emoji_id);
+        assert!(EmojiId::is_valid(&emoji_id.to_string()));
+    }
+
+    #[test]
+    fn invalid_emoji_ids_are_rejected() {
+        let mut rng = OsRng::new().unwrap();
+        let public_key = CommsPublicKey::from_secret_key(&CommsSecretKey::random(&mut rng));
+        let emoji = EmojiId::from_pubkey(&public_key)
+            .to_string()
+            .chars()
+            .collect::<Vec<_>>();
+
+        let truncated = emoji[1..].iter().collect::<String>();
+        assert_eq!(EmojiId::from_emoji_string(&truncated), Err(EmojiIdError::InvalidLength));
+
+        let mut altered = emoji.clone();
+        let check = EMOJI.iter().position(|e| *e == altered[EMOJI_ID_LENGTH - 1]).unwrap();
+        altered[EMOJI_ID_LENGTH - 1] = EMOJI[(check + 1) % 256];
+        let altered = altered.iter().collect::<String>();
+        assert_eq!(EmojiId::from_emoji_string(&altered), Err(EmojiIdError::InvalidChecksum));
+
+        let not_emoji = format!("a{}", emoji[1..].iter().collect::<String>());
+        assert_eq!(EmojiId::from_emoji_string(&not_emoji), Err(EmojiIdError::InvalidEmoji));
+    }
+}
@@ -0,0 +1,398 @@
+// Copyright 2020, The Tari Project
+//
+// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+// following conditions are met:
+//
+// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+// disclaimer.
+//
+// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+// following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+// 3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+// products derived from this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+// INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+// SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+// WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+// USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+use crate::{error::EncryptionError, types::KeyDigest};
+use argon2::{self, Config, Variant};
+use digest::Digest;
+use rand::{OsRng, RngCore};
+use serde::{Deserialize, Serialize};
+use std::{
+    fmt::{Debug, Error, Formatter},
+    ptr,
+};
+use tari_utilities::ciphers::{chacha20_blake2b::ChaCha20Blake2b, cipher::Cipher};
+
+/// The length of the random salts used for the passphrase hash and the key derivation
+pub const PASSPHRASE_SALT_LENGTH: usize = 16;
+const KEY_LENGTH: u32 = 32;
+const NONCE_LENGTH: usize = 12;
+
+fn argon2_config<'a>() -> Config<'a> {
+    Config {
+        variant: Variant::Argon2id,
+        hash_length: KEY_LENGTH,
+        ..Config::default()
+    }
+}
+
+/// The settings that are stored with an encrypted wallet: a hash of the passphrase, used to check a passphrase before
+/// it is used, and the salt of the key derivation. Different salts are used for the two, so the stored hash reveals
+/// nothing about the encryption key.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct EncryptionSettings {
+    pub passphrase_hash: String,
+    pub key_salt: Vec<u8>,
+}
+
+impl EncryptionSettings {
+    /// Create the settings for a new passphrase with random salts
+    pub fn new(passphrase: &str) -> Result<Self, EncryptionError> {
+        let mut rng = OsRng::new().map_err(|e| EncryptionError::KeyDerivationError(e.to_string()))?;
+        let mut hash_salt = [0u8; PASSPHRASE_SALT_LENGTH];
+        rng.fill_bytes(&mut hash_salt);
+        let mut key_salt = vec![0u8; PASSPHRASE_SALT_LENGTH];
+        rng.fill_bytes(&mut key_salt);
+        let passphrase_hash = argon2::hash_encoded(passphrase.as_bytes(), &hash_salt, &argon2_config())
+            .map_err(|e| EncryptionError::KeyDerivationError(e.to_string()))?;
+        Ok(Self {
+            passphrase_hash,
+            key_salt,
+        })
+    }
+
+    /// Check whether the passphrase is the one these settings were created with
+    pub fn verify(&self, passphrase: &str) -> bool {
+        argon2::verify_encoded(&self.passphrase_hash, passphrase.as_bytes()).unwrap_or(false)
+    }
+
+    /// Derive the cipher for the passphrase, if it is the one these settings were created with
+    pub fn cipher(&self, passphrase: &str) -> Result<SecretCipher, EncryptionError> {
+        if !self.verify(passphrase) {
+            return Err(EncryptionError::IncorrectPassphrase);
+        }
+        SecretCipher::from_passphrase(passphrase, &self.key_salt)
+    }
+}
+
+/// An encryption change that has been started but not completed. It is stored before any secret is re-encrypted, so
+/// that a change that is interrupted part way through can be completed when the wallet is next created, and it is
+/// removed in the same write that replaces the encryption settings once every secret has been re-encrypted.
+///
+/// The settings are those of the new passphrase, or none if encryption is being removed. When the passphrase of an
+/// encrypted wallet is changed, the new key is stored encrypted with the current key and the current key encrypted
+/// with the new key, so that the change can be completed with either passphrase.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct PendingEncryption {
+    pub settings: Option<EncryptionSettings>,
+    pub new_key: Option<Vec<u8>>,
+    pub current_key: Option<Vec<u8>>,
+}
+
+impl PendingEncryption {
+    /// Derive the ciphers of the change from the passphrase: the current cipher, if the wallet is encrypted, and the
+    /// new cipher, unless encryption is being removed. When the passphrase is being changed either passphrase can be
+    /// provided.
+    pub fn ciphers(
+        &self,
+        current_settings: Option<&EncryptionSettings>,
+        passphrase: &str,
+    ) -> Result<(Option<SecretCipher>, Option<SecretCipher>), EncryptionError>
+    {
+        match (current_settings, &self.settings) {
+            (None, Some(settings)) => Ok((None, Some(settings.cipher(passphrase)?))),
+            (Some(current_settings), None) => Ok((Some(current_settings.cipher(passphrase)?), None)),
+            (Some(current_settings), Some(settings)) => {
+                let (new_key, current_key) = match (&self.new_key, &self.current_key) {
+                    (Some(new_key), Some(current_key)) => (new_key, current_key),
+                    _ => {
+                        return Err(EncryptionError::KeyDerivationError(
+                            "The keys of the passphrase change are missing".to_string(),
+                        ))
+                    },
+                };
+                if current_settings.verify(passphrase) {
+                    let cipher = current_settings.cipher(passphrase)?;
+                    let new_cipher = cipher.decrypt_key(new_key)?;
+                    Ok((Some(cipher), Some(new_cipher)))
+                } else {
+                    let new_cipher = settings.cipher(passphrase)?;
+                    let cipher = new_cipher.decrypt_key(current_key)?;
+                    Ok((Some(cipher), Some(new_cipher)))
+                }
+            },
+            (None, None) => Err(EncryptionError::NotEncrypted),
+        }
+    }
+}
+
+/// Encrypts the wallet's secrets with a key derived from its passphrase using Argon2. The ChaCha20-Blake2b
+/// authenticated cipher is used, so decrypting with the wrong key or decrypting tampered data fails.
+///
+/// The nonce is derived from the key and the plain text, so the same secret always encrypts to the same cipher text.
+/// This lets backends look up records by their encrypted secret, such as outputs by their spending key, and it is
+/// safe because the encrypted secrets are unique.
+#[derive(Clone)]
+pub struct SecretCipher {
+    key: Vec<u8>,
+}
+
+impl SecretCipher {
+    /// Derive the cipher key from the passphrase and salt
+    pub fn from_passphrase(passphrase: &str, salt: &[u8]) -> Result<Self, EncryptionError> {
+        let key = argon2::hash_raw(passphrase.as_bytes(), salt, &argon2_config())
+            .map_err(|e| EncryptionError::KeyDerivationError(e.to_string()))?;
+        Ok(Self { key })
+    }
+
+    pub fn encrypt(&self, plain_text: &[u8]) -> Result<Vec<u8>, EncryptionError> {
+        let nonce = KeyDigest::new()
+            .chain(b"secret_nonce")
+            .chain(&self.key)
+            .chain(plain_text)
+            .result();
+        let nonce = &nonce[..NONCE_LENGTH];
+        let cipher_text = ChaCha20Blake2b::seal(&plain_text.to_vec(), &self.key, nonce)?;
+        let mut nonce_with_cipher_text = nonce.to_vec();
+        nonce_with_cipher_text.extend(cipher_text);
+        Ok(nonce_with_cipher_text)
+    }
+
+    pub fn decrypt(&self, cipher_text: &[u8]) -> Result<Vec<u8>, EncryptionError> {
+        Ok(ChaCha20Blake2b::open_with_integral_nonce(cipher_text, &self.key)?)
+    }
+
+    /// Encrypt the key of another cipher so that it can be stored and recovered with this one
+    pub fn encrypt_key(&self, other: &SecretCipher) -> Result<Vec<u8>, EncryptionError> {
+        self.encrypt(&other.key)
+    }
+
+    /// Recover a cipher from a key that was encrypted with this one
+    pub fn decrypt_key(&self, encrypted_key: &[u8]) -> Result<SecretCipher, EncryptionError> {
+        Ok(SecretCipher {
+            key: self.decrypt(encrypted_key)?,
+        })
+    }
+}
+
+/// Overwrite a passphrase in memory once it is no longer needed
+pub fn clear_passphrase(passphrase: Option<String>) {
+    if let Some(passphrase) = passphrase {
+        let mut bytes = passphrase.into_bytes();
+        // Volatile writes, so that the compiler cannot drop the writes to memory that is about to be freed
+        for b in bytes.iter_mut() {
+            unsafe { ptr::write_volatile(b, 0) };
+        }
+    }
+}
+
+impl Drop for SecretCipher {
+    fn drop(&mut self) {
+        for b in self.key.iter_mut() {
+            *b = 0;
+        }
+    }
+}
+
+impl Debug for SecretCipher {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), Error> {
+        f.write_str("SecretCipher")
+    }
+}
+
+/// Whether the secrets held by a storage backend are encrypted and, if they are, whether the cipher needed to read and
+/// write them is available
+#[derive(Clone, Debug)]
+pub enum EncryptionState {
+    Unencrypted,
+    Locked,
+    Unlocked(SecretCipher),
+}
+
+impl EncryptionState {
+    /// Convert a secret to the form in which it is stored
+    pub fn encrypt_secret(&self, secret: &[u8]) -> Result<Vec<u8>, EncryptionError> {
+        match self {
+            EncryptionState::Unencrypted => Ok(secret.to_vec()),
+            EncryptionState::Locked => Err(EncryptionError::WalletLocked),
+            EncryptionState::Unlocked(cipher) => cipher.encrypt(secret),
+        }
+    }
+
+    /// Convert a stored secret back to its plain form
+    pub fn decrypt_secret(&self, stored_secret: &[u8]) -> Result<Vec<u8>, EncryptionError> {
+        match self {
+            EncryptionState::Unencrypted => Ok(stored_secret.to_vec()),
+            EncryptionState::Locked => Err(EncryptionError::WalletLocked),
+            EncryptionState::Unlocked(cipher) => cipher.decrypt(stored_secret),
+        }
+    }
+
+    /// The state that results from applying the given cipher, or removing encryption if there is none. An encrypted
+    /// backend must be unlocked before its encryption can be changed.
+    pub fn with_cipher(&self, cipher: Option<SecretCipher>) -> Result<EncryptionState, EncryptionError> {
+        match (self, cipher) {
+            (EncryptionState::Locked, _) => Err(EncryptionError::WalletLocked),
+            (EncryptionState::Unencrypted, None) => Err(EncryptionError::NotEncrypted),
+            (_, None) => Ok(EncryptionState::Unencrypted),
+            (_, Some(cipher)) => Ok(EncryptionState::Unlocked(cipher)),
+        }
+    }
+
+    pub fn unlock(&mut self, cipher: SecretCipher) -> Result<(), EncryptionError> {
+        match self {
+            EncryptionState::Unencrypted => Err(EncryptionError::NotEncrypted),
+            _ => {
+                *self = EncryptionState::Unlocked(cipher);
+                Ok(())
+            },
+        }
+    }
+
+    pub fn lock(&mut self) -> Result<(), EncryptionError> {
+        match self {
+            EncryptionState::Unencrypted => Err(EncryptionError::NotEncrypted),
+            _ => {
+                *self = EncryptionState::Locked;
+                Ok(())
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        encryption::{EncryptionSettings, EncryptionState, PendingEncryption, SecretCipher},
+        error::EncryptionError,
+    };
+
+    #[test]
+    fn passphrase_settings() {
+        let settings = EncryptionSettings::new("correct horse").unwrap();
+        assert!(settings.verify("correct horse"));
+        assert!(!settings.verify("battery staple"));
+        assert_eq!(
+            settings.cipher("battery staple").unwrap_err(),
+            EncryptionError::IncorrectPassphrase
+        );
+        assert!(settings.cipher("correct horse").is_ok());
+        // A new passphrase hash is salted differently
+        assert_ne!(
+            EncryptionSettings::new("correct horse").unwrap().passphrase_hash,
+            settings.passphrase_hash
+        );
+    }
+
+    #[test]
+    fn encrypt_and_decrypt() {
+        let settings = EncryptionSettings::new("correct horse").unwrap();
+        let cipher = settings.cipher("correct horse").unwrap();
+        let secret = vec![42u8; 32];
+        let cipher_text = cipher.encrypt(&secret).unwrap();
+        assert_ne!(cipher_text, secret);
+        // Encryption is deterministic
+        assert_eq!(cipher.encrypt(&secret).unwrap(), cipher_text);
+        assert_ne!(cipher.encrypt(&vec![43u8; 32]).unwrap(), cipher_text);
+        assert_eq!(cipher.decrypt(&cipher_text).unwrap(), secret);
+
+        let other_cipher = SecretCipher::from_passphrase("correct horse", &[0u8; 16]).unwrap();
+        assert!(other_cipher.decrypt(&cipher_text).is_err());
+
+        // A key encrypted with one cipher recovers the other cipher
+        let encrypted_key = cipher.encrypt_key(&other_cipher).unwrap();
+        let recovered_cipher = cipher.decrypt_key(&encrypted_key).unwrap();
+        assert_eq!(
+            recovered_cipher
+                .decrypt(&other_cipher.encrypt(&secret).unwrap())
+                .unwrap(),
+            secret
+        );
+        assert!(other_cipher.decrypt_key(&encrypted_key).is_err());
+    }
+
+    #[test]
+    fn pending_encryption_ciphers() {
+        let settings = EncryptionSettings::new("correct horse").unwrap();
+        let cipher = settings.cipher("correct horse").unwrap();
+        let new_settings = EncryptionSettings::new("battery staple").unwrap();
+        let new_cipher = new_settings.cipher("battery staple").unwrap();
+        let secret = vec![42u8; 32];
+        let cipher_text = cipher.encrypt(&secret).unwrap();
+        let new_cipher_text = new_cipher.encrypt(&secret).unwrap();
+
+        let pending = PendingEncryption {
+            settings: Some(new_settings.clone()),
+            new_key: None,
+            current_key: None,
+        };
+        let (current, new) = pending.ciphers(None, "battery staple").unwrap();
+        assert!(current.is_none());
+        assert_eq!(new.unwrap().decrypt(&new_cipher_text).unwrap(), secret);
+        assert_eq!(
+            pending.ciphers(None, "correct horse").unwrap_err(),
+            EncryptionError::IncorrectPassphrase
+        );
+
+        let pending = PendingEncryption {
+            settings: None,
+            new_key: None,
+            current_key: None,
+        };
+        let (current, new) = pending.ciphers(Some(&settings), "correct horse").unwrap();
+        assert_eq!(current.unwrap().decrypt(&cipher_text).unwrap(), secret);
+        assert!(new.is_none());
+
+        // A passphrase change can be completed with either passphrase
+        let pending = PendingEncryption {
+            settings: Some(new_settings),
+            new_key: Some(cipher.encrypt_key(&new_cipher).unwrap()),
+            current_key: Some(new_cipher.encrypt_key(&cipher).unwrap()),
+        };
+        for passphrase in &["correct horse", "battery staple"] {
+            let (current, new) = pending.ciphers(Some(&settings), passphrase).unwrap();
+            assert_eq!(current.unwrap().decrypt(&cipher_text).unwrap(), secret);
+            assert_eq!(new.unwrap().decrypt(&new_cipher_text).unwrap(), secret);
+        }
+        assert_eq!(
+            pending.ciphers(Some(&settings), "wrong").unwrap_err(),
+            EncryptionError::IncorrectPassphrase
+        );
+    }
+
+    #[test]
+    fn encryption_state() {
+        let cipher = EncryptionSettings::new("correct horse")
+            .unwrap()
+            .cipher("correct horse")
+            .unwrap();
+        let secret = vec![42u8; 32];
+
+        let mut state = EncryptionState::Unencrypted;
+        assert_eq!(state.encrypt_secret(&secret).unwrap(), secret);
+        assert_eq!(state.lock(), Err(EncryptionError::NotEncrypted));
+        assert_eq!(state.with_cipher(None).unwrap_err(), EncryptionError::NotEncrypted);
+
+        state = state.with_cipher(Some(cipher.clone())).unwrap();
+        let stored_secret = state.encrypt_secret(&secret).unwrap();
+        assert_ne!(stored_secret, secret);
+        assert_eq!(state.decrypt_secret(&stored_secret).unwrap(), secret);
+
+        state.lock().unwrap();
+        assert_eq!(state.encrypt_secret(&secret), Err(EncryptionError::WalletLocked));
+        assert_eq!(state.decrypt_secret(&stored_secret), Err(EncryptionError::WalletLocked));
+        assert_eq!(state.with_cipher(None).unwrap_err(), EncryptionError::WalletLocked);
+
+        state.unlock(cipher).unwrap();
+        assert_eq!(state.decrypt_secret(&stored_secret).unwrap(), secret);
+        assert!(state.with_cipher(None).is_ok());
+    }
+}
@@ -21,17 +21,26 @@
 // USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
 use crate::{
+    atomic_swap_service::error::AtomicSwapServiceError,
     contacts_service::error::ContactsServiceError,
     output_manager_service::error::OutputManagerError,
+    recovery_service::error::RecoveryServiceError,
+    shared_output_service::error::SharedOutputServiceError,
     storage::database::DbKey,
     transaction_service::error::TransactionServiceError,
+    watch_only_service::error::WatchOnlyServiceError,
 };
 use derive_error::Error;
 use diesel::result::Error as DieselError;
 use log::SetLoggerError;
 use serde_json::Error as SerdeJsonError;
-use tari_comms::{builder::CommsError, multiaddr, peer_manager::PeerManagerError};
+use tari_comms::{
+    builder::CommsError,
+    multiaddr,
+    peer_manager::{node_identity::NodeIdentityError, PeerManagerError},
+};
 use tari_p2p::initialization::CommsInitializationError;
+use tari_utilities::ciphers::cipher::CipherError;
 
 #[derive(Debug, Error)]
 pub enum WalletError {
@@ -40,10 +49,19 @@ pub enum WalletError {
     OutputManagerError(OutputManagerError),
     TransactionServiceError(TransactionServiceError),
     PeerManagerError(PeerManagerError),
+    NodeIdentityError(NodeIdentityError),
     MultiaddrError(multiaddr::Error),
     WalletStorageError(WalletStorageError),
     SetLoggerError(SetLoggerError),
     ContactsServiceError(ContactsServiceError),
+    RecoveryServiceError(RecoveryServiceError),
+    WatchOnlyServiceError(WatchOnlyServiceError),
+    SharedOutputServiceError(SharedOutputServiceError),
+    AtomicSwapServiceError(AtomicSwapServiceError),
+    EncryptionError(EncryptionError),
+    BackupError(BackupError),
+    /// A backup can only be restored into a wallet that does not hold any outputs or transactions yet
+    WalletNotEmpty,
 }
 
 #[derive(Debug, Error)]
@@ -66,4 +84,83 @@ pub enum WalletStorageError {
     ValueNotFound(DbKey),
     #[error(msg_embedded, non_std, no_from)]
     UnexpectedResult(String),
+    EncryptionError(EncryptionError),
+}
+
+#[derive(Debug, Error, PartialEq)]
+pub enum EncryptionError {
+    /// The provided passphrase is not the wallet's passphrase
+    IncorrectPassphrase,
+    /// The wallet's secrets are encrypted and the wallet must be unlocked with its passphrase first
+    WalletLocked,
+    /// The wallet's secrets are already encrypted with a passphrase
+    AlreadyEncrypted,
+    /// The wallet's secrets are not encrypted with a passphrase
+    NotEncrypted,
+    /// An earlier change to the wallet's encryption has not been completed yet. It is completed when the wallet is
+    /// next created.
+    ChangePending,
+    CipherError(CipherError),
+    #[error(msg_embedded, no_from, non_std)]
+    KeyDerivationError(String),
+}
+
+#[derive(Debug, Error, PartialEq)]
+pub enum BackupError {
+    /// The backup was written with a version of the backup format that this wallet cannot read
+    #[error(no_from, non_std)]
+    UnsupportedVersion(u32),
+    /// The backup could not be decrypted, the passphrase is incorrect or the backup has been modified
+    DecryptionFailed,
+    /// The file is not a wallet backup
+    InvalidBackupFile,
+    /// A secure source of randomness is not available to generate the salt of the backup
+    RandomnessUnavailable,
+    #[error(msg_embedded, no_from, non_std)]
+    IoError(String),
+    #[error(msg_embedded, no_from, non_std)]
+    SerializationError(String),
+    EncryptionError(EncryptionError),
+}
+
+#[derive(Debug, Error, PartialEq)]
+pub enum PaymentRequestError {
+    /// The URI is not a Tari payment request
+    InvalidScheme,
+    /// The payment request is for a network that this wallet does not know
+    UnknownNetwork,
+    /// The payment request does not contain a valid public key
+    InvalidPublicKey,
+    /// The amount of the payment request is not a number of MicroTari
+    InvalidAmount,
+    /// The expiry time of the payment request is not a valid Unix time
+    InvalidExpiry,
+    /// A parameter of the payment request is not correctly percent encoded
+    InvalidEncoding,
+    /// A parameter appears more than once in the payment request
+    DuplicateParameter,
+    /// The payment request does not end with a checksum
+    MissingChecksum,
+    /// The checksum does not match the payment request, which has been mistyped or altered
+    InvalidChecksum,
+    /// The payment request is for another network than this wallet's
+    NetworkMismatch,
+    /// The payment request has expired
+    Expired,
+    /// The payment request does not have an amount and no amount was given to pay
+    MissingAmount,
+    /// The amount given to pay differs from the amount of the payment request
+    AmountMismatch,
+}
+
+#[derive(Debug, Error, PartialEq)]
+pub enum EmojiIdError {
+    /// The emoji ID does not have the right number of emoji
+    InvalidLength,
+    /// The emoji ID contains a character that is not one of its emoji
+    InvalidEmoji,
+    /// The checksum does not match the emoji ID, which has been mistyped
+    InvalidChecksum,
+    /// The emoji ID does not encode a valid public key
+    InvalidPublicKey,
 }
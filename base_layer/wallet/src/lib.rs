@@ -4,13 +4,21 @@
 
 #[macro_use]
 mod macros;
+pub mod atomic_swap_service;
+pub mod backup;
 pub mod contacts_service;
+pub mod emoji;
+pub mod encryption;
 pub mod error;
 pub mod output_manager_service;
+pub mod payment_request;
+pub mod recovery_service;
+pub mod shared_output_service;
 pub mod storage;
 pub mod transaction_service;
 pub mod types;
 pub mod wallet;
+pub mod watch_only_service;
 
 #[cfg(feature = "test_harness")]
 pub mod testnet_utils;
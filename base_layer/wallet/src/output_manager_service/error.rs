@@ -20,7 +20,7 @@
 // WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
 // USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
-use crate::output_manager_service::storage::database::DbKey;
+use crate::{error::EncryptionError, output_manager_service::storage::database::DbKey};
 use derive_error::Error;
 use diesel::result::Error as DieselError;
 use tari_core::transactions::transaction_protocol::TransactionProtocolError;
@@ -44,6 +44,18 @@ pub enum OutputManagerError {
     IncompleteTransaction,
     /// Not enough funds to fulfil transaction
     NotEnoughFunds,
+    /// A transaction must have at least one recipient
+    NoRecipients,
+    /// An output selected to be spent is not an unspent output of this wallet
+    SelectedOutputNotAvailable,
+    /// A coin split must create at least one output with a non-zero value
+    InvalidCoinSplit,
+    /// There are not enough unspent outputs to consolidate
+    NotEnoughOutputs,
+    /// The wallet is watch-only and holds no spending keys, so it cannot send or receive funds
+    WatchOnlyWallet,
+    /// Outputs can only be watched by an empty wallet or by a wallet that is already watch-only
+    WatchOnlyImportNotAllowed,
     /// Output already exists
     DuplicateOutput,
     /// Error sending a message to the public API
@@ -54,6 +66,14 @@ pub enum OutputManagerError {
     UnexpectedApiResponse,
     /// Invalid config provided to Output Manager
     InvalidConfig,
+    /// Received an unexpected response from the base node
+    UnexpectedBaseNodeResponse,
+    /// Outbound Service send failed
+    OutboundSendFailure,
+    /// An error has occurred reading or writing the event subscriber stream
+    EventStreamError,
+    #[error(msg_embedded, no_from, non_std)]
+    InvalidMessageError(String),
 }
 
 #[derive(Debug, Error, PartialEq)]
@@ -82,4 +102,5 @@ pub enum OutputManagerStorageError {
     DieselConnectionError(diesel::ConnectionError),
     #[error(msg_embedded, no_from, non_std)]
     DatabaseMigrationError(String),
+    EncryptionError(EncryptionError),
 }
@@ -20,15 +20,30 @@
 // WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
 // USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
-use crate::output_manager_service::{
-    error::OutputManagerError,
-    service::Balance,
-    storage::database::PendingTransactionOutputs,
+use crate::{
+    encryption::SecretCipher,
+    output_manager_service::{
+        error::OutputManagerError,
+        service::{Balance, UTXOSelectionStrategy},
+        storage::database::{
+            AtomicSwap,
+            OutputManagerBackup,
+            PendingTransactionOutputs,
+            SharedOutput,
+            WatchOnlyExport,
+            WatchOnlyState,
+            WatchedOutput,
+        },
+        TxId,
+    },
 };
+use futures::{stream::Fuse, StreamExt};
 use std::{collections::HashMap, time::Duration};
+use tari_broadcast_channel::Subscriber;
+use tari_comms::types::CommsPublicKey;
 use tari_core::transactions::{
     tari_amount::MicroTari,
-    transaction::{TransactionInput, TransactionOutput, UnblindedOutput},
+    transaction::{OutputFeatures, RewindData, Transaction, TransactionInput, TransactionOutput, UnblindedOutput},
     types::PrivateKey,
     SenderTransactionProtocol,
 };
@@ -44,13 +59,41 @@ pub enum OutputManagerRequest {
     GetCoinbaseKey((u64, MicroTari, u64)),
     ConfirmReceivedOutput((u64, TransactionOutput)),
     ConfirmSentTransaction((u64, Vec<TransactionInput>, Vec<TransactionOutput>)),
-    PrepareToSendTransaction((MicroTari, MicroTari, Option<u64>, String)),
+    PrepareToSendTransaction((MicroTari, MicroTari, Option<u64>, String, UTXOSelectionStrategy)),
+    PrepareToSendMultiRecipientTransaction((Vec<MicroTari>, MicroTari, Option<u64>, String)),
     CancelTransaction(u64),
     TimeoutTransactions(Duration),
     GetPendingTransactions,
     GetSpentOutputs,
     GetUnspentOutputs,
+    GetInvalidOutputs,
     GetSeedWords,
+    SetBaseNodePublicKey(CommsPublicKey),
+    RestoreKeyManager((PrivateKey, usize)),
+    GetRewindData,
+    ApplyEncryption(Option<SecretCipher>),
+    Unlock(SecretCipher),
+    Lock,
+    TransactionFee((MicroTari, MicroTari)),
+    CoinSplitFee((MicroTari, usize, MicroTari)),
+    CreateCoinSplit((MicroTari, usize, MicroTari, Option<u64>, String)),
+    ConsolidationFee((usize, MicroTari)),
+    ConsolidateOutputs((usize, MicroTari, Option<u64>, String)),
+    SetChainHeight(u64),
+    GetBackup,
+    RestoreBackup(OutputManagerBackup),
+    ExportWatchOnlyData,
+    ImportWatchOnlyData((WatchOnlyExport, Option<u64>)),
+    GetWatchOnlyState,
+    GetWatchedOutputs,
+    UpdateWatchedOutputs((Vec<WatchedOutput>, u64)),
+    CreateSharedOutputFunding((MicroTari, PrivateKey, MicroTari, String)),
+    GetSharedOutputs,
+    SaveSharedOutput(SharedOutput),
+    CreateHashLockedFunding((MicroTari, PrivateKey, OutputFeatures, MicroTari, String)),
+    AddPendingIncomingOutput((TxId, UnblindedOutput)),
+    GetAtomicSwaps,
+    SaveAtomicSwap(AtomicSwap),
 }
 
 /// API Reply enum
@@ -66,17 +109,63 @@ pub enum OutputManagerResponse {
     PendingTransactions(HashMap<u64, PendingTransactionOutputs>),
     SpentOutputs(Vec<UnblindedOutput>),
     UnspentOutputs(Vec<UnblindedOutput>),
+    InvalidOutputs(Vec<UnblindedOutput>),
     SeedWords(Vec<String>),
+    BaseNodePublicKeySet,
+    KeyManagerRestored,
+    RewindData(RewindData),
+    EncryptionApplied,
+    Unlocked,
+    Locked,
+    FeeEstimate(MicroTari),
+    TransactionToSelf((TxId, MicroTari, MicroTari, Transaction)),
+    ChainHeightSet,
+    Backup(OutputManagerBackup),
+    BackupRestored,
+    WatchOnlyExport(WatchOnlyExport),
+    WatchOnlyDataImported,
+    WatchOnlyState(Option<WatchOnlyState>),
+    WatchedOutputs(Vec<WatchedOutput>),
+    WatchedOutputsUpdated,
+    SharedOutputs(Vec<SharedOutput>),
+    SharedOutputSaved,
+    PendingIncomingOutputAdded,
+    AtomicSwaps(Vec<AtomicSwap>),
+    AtomicSwapSaved,
+}
+
+/// Events that can be published on the Output Manager Service Event Stream
+#[derive(Clone, Debug, Hash, PartialEq, Eq)]
+pub enum OutputManagerEvent {
+    /// The response to the UTXO validation query with the given request key has been processed
+    UtxoValidationComplete(u64),
+    /// The given number of unspent outputs could not be found in the blockchain's UTXO set and were marked as invalid
+    OutputsInvalidated(usize),
+    /// The given number of invalid outputs were found in the blockchain's UTXO set and were marked as unspent again
+    OutputsRevalidated(usize),
+    /// The outputs to be received by the given number of pending transactions were found in the blockchain's UTXO set
+    /// and the pending transactions were confirmed
+    PendingTransactionsConfirmed(usize),
+    Error(String),
 }
 
 #[derive(Clone)]
 pub struct OutputManagerHandle {
     handle: SenderService<OutputManagerRequest, Result<OutputManagerResponse, OutputManagerError>>,
+    event_stream: Subscriber<OutputManagerEvent>,
 }
 
 impl OutputManagerHandle {
-    pub fn new(handle: SenderService<OutputManagerRequest, Result<OutputManagerResponse, OutputManagerError>>) -> Self {
-        OutputManagerHandle { handle }
+    pub fn new(
+        handle: SenderService<OutputManagerRequest, Result<OutputManagerResponse, OutputManagerError>>,
+        event_stream: Subscriber<OutputManagerEvent>,
+    ) -> Self
+    {
+        OutputManagerHandle { handle, event_stream }
+    }
+
+    pub fn get_event_stream_fused(&self) -> Fuse<Subscriber<OutputManagerEvent>> {
+        self.event_stream.clone().fuse()
     }
 
     pub async fn add_output(&mut self, output: UnblindedOutput) -> Result<(), OutputManagerError> {
@@ -133,6 +222,27 @@ impl OutputManagerHandle {
         lock_height: Option<u64>,
         message: String,
     ) -> Result<SenderTransactionProtocol, OutputManagerError>
+    {
+        self.prepare_transaction_to_send_with_strategy(
+            amount,
+            fee_per_gram,
+            lock_height,
+            message,
+            UTXOSelectionStrategy::default(),
+        )
+        .await
+    }
+
+    /// Prepare a transaction that spends outputs chosen with the given selection strategy, or exactly the outputs
+    /// given with `UTXOSelectionStrategy::CoinControl`
+    pub async fn prepare_transaction_to_send_with_strategy(
+        &mut self,
+        amount: MicroTari,
+        fee_per_gram: MicroTari,
+        lock_height: Option<u64>,
+        message: String,
+        strategy: UTXOSelectionStrategy,
+    ) -> Result<SenderTransactionProtocol, OutputManagerError>
     {
         match self
             .handle
@@ -141,6 +251,31 @@ impl OutputManagerHandle {
                 fee_per_gram,
                 lock_height,
                 message,
+                strategy,
+            )))
+            .await??
+        {
+            OutputManagerResponse::TransactionToSend(stp) => Ok(stp),
+            _ => Err(OutputManagerError::UnexpectedApiResponse),
+        }
+    }
+
+    /// Prepare a transaction that pays each of the given amounts to a different recipient
+    pub async fn prepare_multi_recipient_transaction_to_send(
+        &mut self,
+        amounts: Vec<MicroTari>,
+        fee_per_gram: MicroTari,
+        lock_height: Option<u64>,
+        message: String,
+    ) -> Result<SenderTransactionProtocol, OutputManagerError>
+    {
+        match self
+            .handle
+            .call(OutputManagerRequest::PrepareToSendMultiRecipientTransaction((
+                amounts,
+                fee_per_gram,
+                lock_height,
+                message,
             )))
             .await??
         {
@@ -231,10 +366,387 @@ impl OutputManagerHandle {
         }
     }
 
+    /// Return the outputs that could not be found in the blockchain's UTXO set during the last UTXO validation
+    pub async fn get_invalid_outputs(&mut self) -> Result<Vec<UnblindedOutput>, OutputManagerError> {
+        match self.handle.call(OutputManagerRequest::GetInvalidOutputs).await?? {
+            OutputManagerResponse::InvalidOutputs(s) => Ok(s),
+            _ => Err(OutputManagerError::UnexpectedApiResponse),
+        }
+    }
+
     pub async fn get_seed_words(&mut self) -> Result<Vec<String>, OutputManagerError> {
         match self.handle.call(OutputManagerRequest::GetSeedWords).await?? {
             OutputManagerResponse::SeedWords(s) => Ok(s),
             _ => Err(OutputManagerError::UnexpectedApiResponse),
         }
     }
+
+    /// Get the rewind data used to make this wallet's outputs recoverable from their range proofs
+    pub async fn get_rewind_data(&mut self) -> Result<RewindData, OutputManagerError> {
+        match self.handle.call(OutputManagerRequest::GetRewindData).await?? {
+            OutputManagerResponse::RewindData(r) => Ok(r),
+            _ => Err(OutputManagerError::UnexpectedApiResponse),
+        }
+    }
+
+    /// Encrypt the secrets stored by the Output Manager Service with the provided cipher, or decrypt them if no cipher
+    /// is provided
+    pub async fn apply_encryption(&mut self, cipher: Option<SecretCipher>) -> Result<(), OutputManagerError> {
+        match self
+            .handle
+            .call(OutputManagerRequest::ApplyEncryption(cipher))
+            .await??
+        {
+            OutputManagerResponse::EncryptionApplied => Ok(()),
+            _ => Err(OutputManagerError::UnexpectedApiResponse),
+        }
+    }
+
+    /// Make the encrypted secrets stored by the Output Manager Service accessible with the provided cipher
+    pub async fn unlock(&mut self, cipher: SecretCipher) -> Result<(), OutputManagerError> {
+        match self.handle.call(OutputManagerRequest::Unlock(cipher)).await?? {
+            OutputManagerResponse::Unlocked => Ok(()),
+            _ => Err(OutputManagerError::UnexpectedApiResponse),
+        }
+    }
+
+    /// Discard the cipher of the encrypted secrets stored by the Output Manager Service
+    pub async fn lock(&mut self) -> Result<(), OutputManagerError> {
+        match self.handle.call(OutputManagerRequest::Lock).await?? {
+            OutputManagerResponse::Locked => Ok(()),
+            _ => Err(OutputManagerError::UnexpectedApiResponse),
+        }
+    }
+
+    /// Set the base node that the Output Manager Service validates its outputs against. This starts a UTXO
+    /// validation round immediately and then periodically at the configured interval.
+    pub async fn set_base_node_public_key(&mut self, public_key: CommsPublicKey) -> Result<(), OutputManagerError> {
+        match self
+            .handle
+            .call(OutputManagerRequest::SetBaseNodePublicKey(public_key))
+            .await??
+        {
+            OutputManagerResponse::BaseNodePublicKeySet => Ok(()),
+            _ => Err(OutputManagerError::UnexpectedApiResponse),
+        }
+    }
+
+    /// Replace the master key of the Key Manager and continue deriving keys after the provided key index. This is used
+    /// when restoring a wallet from its seed words.
+    pub async fn restore_key_manager(
+        &mut self,
+        master_key: PrivateKey,
+        primary_key_index: usize,
+    ) -> Result<(), OutputManagerError>
+    {
+        match self
+            .handle
+            .call(OutputManagerRequest::RestoreKeyManager((master_key, primary_key_index)))
+            .await??
+        {
+            OutputManagerResponse::KeyManagerRestored => Ok(()),
+            _ => Err(OutputManagerError::UnexpectedApiResponse),
+        }
+    }
+
+    /// Estimate the fee of sending `amount` to a single recipient, using the outputs that would be selected to fund the
+    /// transaction if it were sent now
+    pub async fn transaction_fee(
+        &mut self,
+        amount: MicroTari,
+        fee_per_gram: MicroTari,
+    ) -> Result<MicroTari, OutputManagerError>
+    {
+        match self
+            .handle
+            .call(OutputManagerRequest::TransactionFee((amount, fee_per_gram)))
+            .await??
+        {
+            OutputManagerResponse::FeeEstimate(fee) => Ok(fee),
+            _ => Err(OutputManagerError::UnexpectedApiResponse),
+        }
+    }
+
+    /// Estimate the fee of splitting `split_count` outputs of `amount_per_split` each from the wallet's balance
+    pub async fn coin_split_fee(
+        &mut self,
+        amount_per_split: MicroTari,
+        split_count: usize,
+        fee_per_gram: MicroTari,
+    ) -> Result<MicroTari, OutputManagerError>
+    {
+        match self
+            .handle
+            .call(OutputManagerRequest::CoinSplitFee((
+                amount_per_split,
+                split_count,
+                fee_per_gram,
+            )))
+            .await??
+        {
+            OutputManagerResponse::FeeEstimate(fee) => Ok(fee),
+            _ => Err(OutputManagerError::UnexpectedApiResponse),
+        }
+    }
+
+    /// Create a transaction that pays `split_count` outputs of `amount_per_split` each back to this wallet. Returns the
+    /// tx_id, fee, amount paid to self and finalized transaction.
+    pub async fn create_coin_split(
+        &mut self,
+        amount_per_split: MicroTari,
+        split_count: usize,
+        fee_per_gram: MicroTari,
+        lock_height: Option<u64>,
+        message: String,
+    ) -> Result<(TxId, MicroTari, MicroTari, Transaction), OutputManagerError>
+    {
+        match self
+            .handle
+            .call(OutputManagerRequest::CreateCoinSplit((
+                amount_per_split,
+                split_count,
+                fee_per_gram,
+                lock_height,
+                message,
+            )))
+            .await??
+        {
+            OutputManagerResponse::TransactionToSelf(tx) => Ok(tx),
+            _ => Err(OutputManagerError::UnexpectedApiResponse),
+        }
+    }
+
+    /// Estimate the fee of consolidating up to `max_outputs` of the smallest unspent outputs
+    pub async fn consolidation_fee(
+        &mut self,
+        max_outputs: usize,
+        fee_per_gram: MicroTari,
+    ) -> Result<MicroTari, OutputManagerError>
+    {
+        match self
+            .handle
+            .call(OutputManagerRequest::ConsolidationFee((max_outputs, fee_per_gram)))
+            .await??
+        {
+            OutputManagerResponse::FeeEstimate(fee) => Ok(fee),
+            _ => Err(OutputManagerError::UnexpectedApiResponse),
+        }
+    }
+
+    /// Create a transaction that consolidates up to `max_outputs` of the smallest unspent outputs into one. Returns the
+    /// tx_id, fee, amount paid to self and finalized transaction.
+    pub async fn consolidate_outputs(
+        &mut self,
+        max_outputs: usize,
+        fee_per_gram: MicroTari,
+        lock_height: Option<u64>,
+        message: String,
+    ) -> Result<(TxId, MicroTari, MicroTari, Transaction), OutputManagerError>
+    {
+        match self
+            .handle
+            .call(OutputManagerRequest::ConsolidateOutputs((
+                max_outputs,
+                fee_per_gram,
+                lock_height,
+                message,
+            )))
+            .await??
+        {
+            OutputManagerResponse::TransactionToSelf(tx) => Ok(tx),
+            _ => Err(OutputManagerError::UnexpectedApiResponse),
+        }
+    }
+
+    /// Tell the service the height of the longest chain, so that outputs that have not matured are not selected to be
+    /// spent and are reported in the time-locked balance
+    pub async fn set_chain_height(&mut self, height: u64) -> Result<(), OutputManagerError> {
+        match self.handle.call(OutputManagerRequest::SetChainHeight(height)).await?? {
+            OutputManagerResponse::ChainHeightSet => Ok(()),
+            _ => Err(OutputManagerError::UnexpectedApiResponse),
+        }
+    }
+
+    /// Return the key manager state and all the outputs held by the service so that they can be written to a wallet
+    /// backup
+    pub async fn get_backup(&mut self) -> Result<OutputManagerBackup, OutputManagerError> {
+        match self.handle.call(OutputManagerRequest::GetBackup).await?? {
+            OutputManagerResponse::Backup(backup) => Ok(backup),
+            _ => Err(OutputManagerError::UnexpectedApiResponse),
+        }
+    }
+
+    /// Restore the key manager state and outputs from a wallet backup. The outputs are added to those already held
+    /// by the service.
+    pub async fn restore_backup(&mut self, backup: OutputManagerBackup) -> Result<(), OutputManagerError> {
+        match self.handle.call(OutputManagerRequest::RestoreBackup(backup)).await?? {
+            OutputManagerResponse::BackupRestored => Ok(()),
+            _ => Err(OutputManagerError::UnexpectedApiResponse),
+        }
+    }
+
+    /// Export the view key and unspent outputs of this wallet so that they can be imported into a watch-only wallet
+    pub async fn export_watch_only_data(&mut self) -> Result<WatchOnlyExport, OutputManagerError> {
+        match self.handle.call(OutputManagerRequest::ExportWatchOnlyData).await?? {
+            OutputManagerResponse::WatchOnlyExport(export) => Ok(export),
+            _ => Err(OutputManagerError::UnexpectedApiResponse),
+        }
+    }
+
+    /// Import the view key and outputs exported by another wallet, making this wallet watch-only. The blockchain will
+    /// be scanned from the birthday height, or from the genesis block if it is not provided.
+    pub async fn import_watch_only_data(
+        &mut self,
+        export: WatchOnlyExport,
+        birthday_height: Option<u64>,
+    ) -> Result<(), OutputManagerError>
+    {
+        match self
+            .handle
+            .call(OutputManagerRequest::ImportWatchOnlyData((export, birthday_height)))
+            .await??
+        {
+            OutputManagerResponse::WatchOnlyDataImported => Ok(()),
+            _ => Err(OutputManagerError::UnexpectedApiResponse),
+        }
+    }
+
+    /// Return the view key and scan height of a watch-only wallet, or None if the wallet is not watch-only
+    pub async fn get_watch_only_state(&mut self) -> Result<Option<WatchOnlyState>, OutputManagerError> {
+        match self.handle.call(OutputManagerRequest::GetWatchOnlyState).await?? {
+            OutputManagerResponse::WatchOnlyState(state) => Ok(state),
+            _ => Err(OutputManagerError::UnexpectedApiResponse),
+        }
+    }
+
+    pub async fn get_watched_outputs(&mut self) -> Result<Vec<WatchedOutput>, OutputManagerError> {
+        match self.handle.call(OutputManagerRequest::GetWatchedOutputs).await?? {
+            OutputManagerResponse::WatchedOutputs(outputs) => Ok(outputs),
+            _ => Err(OutputManagerError::UnexpectedApiResponse),
+        }
+    }
+
+    /// Store the watched outputs that were found or spent in the scanned blocks and the height of the next block to be
+    /// scanned
+    pub async fn update_watched_outputs(
+        &mut self,
+        outputs: Vec<WatchedOutput>,
+        next_height: u64,
+    ) -> Result<(), OutputManagerError>
+    {
+        match self
+            .handle
+            .call(OutputManagerRequest::UpdateWatchedOutputs((outputs, next_height)))
+            .await??
+        {
+            OutputManagerResponse::WatchedOutputsUpdated => Ok(()),
+            _ => Err(OutputManagerError::UnexpectedApiResponse),
+        }
+    }
+
+    /// Create a transaction that funds a shared output of the given value with the given spending key, paying change
+    /// back to this wallet. Returns the tx_id, fee, value of the shared output and the transaction, which still has to
+    /// be broadcast.
+    pub async fn create_shared_output_funding(
+        &mut self,
+        value: MicroTari,
+        spending_key: PrivateKey,
+        fee_per_gram: MicroTari,
+        message: String,
+    ) -> Result<(TxId, MicroTari, MicroTari, Transaction), OutputManagerError>
+    {
+        match self
+            .handle
+            .call(OutputManagerRequest::CreateSharedOutputFunding((
+                value,
+                spending_key,
+                fee_per_gram,
+                message,
+            )))
+            .await??
+        {
+            OutputManagerResponse::TransactionToSelf(tx) => Ok(tx),
+            _ => Err(OutputManagerError::UnexpectedApiResponse),
+        }
+    }
+
+    pub async fn get_shared_outputs(&mut self) -> Result<Vec<SharedOutput>, OutputManagerError> {
+        match self.handle.call(OutputManagerRequest::GetSharedOutputs).await?? {
+            OutputManagerResponse::SharedOutputs(outputs) => Ok(outputs),
+            _ => Err(OutputManagerError::UnexpectedApiResponse),
+        }
+    }
+
+    /// Store a shared output, replacing the stored output with the same id if there is one
+    pub async fn save_shared_output(&mut self, output: SharedOutput) -> Result<(), OutputManagerError> {
+        match self
+            .handle
+            .call(OutputManagerRequest::SaveSharedOutput(output))
+            .await??
+        {
+            OutputManagerResponse::SharedOutputSaved => Ok(()),
+            _ => Err(OutputManagerError::UnexpectedApiResponse),
+        }
+    }
+
+    /// Create a transaction that funds a hash locked output of the given value with the given spending key and
+    /// features, paying change back to this wallet. Returns the tx_id, fee, value of the hash locked output and the
+    /// transaction, which still has to be broadcast.
+    pub async fn create_hash_locked_funding(
+        &mut self,
+        value: MicroTari,
+        spending_key: PrivateKey,
+        features: OutputFeatures,
+        fee_per_gram: MicroTari,
+        message: String,
+    ) -> Result<(TxId, MicroTari, MicroTari, Transaction), OutputManagerError>
+    {
+        match self
+            .handle
+            .call(OutputManagerRequest::CreateHashLockedFunding((
+                value,
+                spending_key,
+                features,
+                fee_per_gram,
+                message,
+            )))
+            .await??
+        {
+            OutputManagerResponse::TransactionToSelf(tx) => Ok(tx),
+            _ => Err(OutputManagerError::UnexpectedApiResponse),
+        }
+    }
+
+    /// Add an output whose spending key this wallet already holds as an output to be received by the pending
+    /// transaction with the given tx_id
+    pub async fn add_pending_incoming_output(
+        &mut self,
+        tx_id: TxId,
+        output: UnblindedOutput,
+    ) -> Result<(), OutputManagerError>
+    {
+        match self
+            .handle
+            .call(OutputManagerRequest::AddPendingIncomingOutput((tx_id, output)))
+            .await??
+        {
+            OutputManagerResponse::PendingIncomingOutputAdded => Ok(()),
+            _ => Err(OutputManagerError::UnexpectedApiResponse),
+        }
+    }
+
+    pub async fn get_atomic_swaps(&mut self) -> Result<Vec<AtomicSwap>, OutputManagerError> {
+        match self.handle.call(OutputManagerRequest::GetAtomicSwaps).await?? {
+            OutputManagerResponse::AtomicSwaps(swaps) => Ok(swaps),
+            _ => Err(OutputManagerError::UnexpectedApiResponse),
+        }
+    }
+
+    /// Store an atomic swap, replacing the stored swap with the same id if there is one
+    pub async fn save_atomic_swap(&mut self, swap: AtomicSwap) -> Result<(), OutputManagerError> {
+        match self.handle.call(OutputManagerRequest::SaveAtomicSwap(swap)).await?? {
+            OutputManagerResponse::AtomicSwapSaved => Ok(()),
+            _ => Err(OutputManagerError::UnexpectedApiResponse),
+        }
+    }
 }
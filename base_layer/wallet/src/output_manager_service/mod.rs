@@ -20,12 +20,25 @@
 // WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
 // USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
-use crate::output_manager_service::{handle::OutputManagerHandle, service::OutputManagerService};
-
-use crate::output_manager_service::storage::database::{OutputManagerBackend, OutputManagerDatabase};
-use futures::{future, Future};
+use crate::output_manager_service::{
+    config::OutputManagerServiceConfig,
+    handle::OutputManagerHandle,
+    service::OutputManagerService,
+    storage::database::{OutputManagerBackend, OutputManagerDatabase},
+};
+use futures::{future, Future, Stream, StreamExt};
 use log::*;
-use tari_core::transactions::types::CryptoFactories;
+use std::sync::Arc;
+use tari_broadcast_channel::bounded;
+use tari_comms_dht::outbound::OutboundMessageRequester;
+use tari_core::{base_node::proto::base_node::BaseNodeServiceResponse, transactions::types::CryptoFactories};
+use tari_p2p::{
+    comms_connector::PeerMessage,
+    domain_message::DomainMessage,
+    services::utils::{map_decode, ok_or_skip_result},
+    tari_message::TariMessageType,
+};
+use tari_pubsub::TopicSubscriptionFactory;
 use tari_service_framework::{
     handles::ServiceHandlesFuture,
     reply_channel,
@@ -35,6 +48,7 @@ use tari_service_framework::{
 use tari_shutdown::ShutdownSignal;
 use tokio::runtime;
 
+pub mod config;
 pub mod error;
 pub mod handle;
 pub mod service;
@@ -47,6 +61,8 @@ pub type TxId = u64;
 pub struct OutputManagerServiceInitializer<T>
 where T: OutputManagerBackend
 {
+    config: OutputManagerServiceConfig,
+    subscription_factory: Arc<TopicSubscriptionFactory<TariMessageType, Arc<PeerMessage>>>,
     backend: Option<T>,
     factories: CryptoFactories,
 }
@@ -54,12 +70,27 @@ where T: OutputManagerBackend
 impl<T> OutputManagerServiceInitializer<T>
 where T: OutputManagerBackend
 {
-    pub fn new(backend: T, factories: CryptoFactories) -> Self {
+    pub fn new(
+        config: OutputManagerServiceConfig,
+        subscription_factory: Arc<TopicSubscriptionFactory<TariMessageType, Arc<PeerMessage>>>,
+        backend: T,
+        factories: CryptoFactories,
+    ) -> Self
+    {
         Self {
+            config,
+            subscription_factory,
             backend: Some(backend),
             factories,
         }
     }
+
+    fn base_node_response_stream(&self) -> impl Stream<Item = DomainMessage<BaseNodeServiceResponse>> {
+        self.subscription_factory
+            .get_subscription(TariMessageType::BaseNodeResponse)
+            .map(map_decode::<BaseNodeServiceResponse>)
+            .filter_map(ok_or_skip_result)
+    }
 }
 
 impl<T> ServiceInitializer for OutputManagerServiceInitializer<T>
@@ -75,8 +106,11 @@ where T: OutputManagerBackend + 'static
     ) -> Self::Future
     {
         let (sender, receiver) = reply_channel::unbounded();
+        let base_node_response_stream = self.base_node_response_stream();
+
+        let (publisher, subscriber) = bounded(100);
 
-        let oms_handle = OutputManagerHandle::new(sender);
+        let oms_handle = OutputManagerHandle::new(sender, subscriber);
 
         // Register handle before waiting for handles to be ready
         handles_fut.register(oms_handle);
@@ -86,10 +120,25 @@ where T: OutputManagerBackend + 'static
             .take()
             .expect("Cannot start Output Manager Service without setting a storage backend");
         let factories = self.factories.clone();
+        let config = self.config;
         executor.spawn(async move {
-            let service = OutputManagerService::new(receiver, OutputManagerDatabase::new(backend), factories)
-                .expect("Could not initialize Output Manager Service")
-                .start();
+            let handles = handles_fut.await;
+
+            let outbound_message_service = handles
+                .get_handle::<OutboundMessageRequester>()
+                .expect("OMS handle required for Output Manager Service");
+
+            let service = OutputManagerService::new(
+                config,
+                outbound_message_service,
+                receiver,
+                base_node_response_stream,
+                OutputManagerDatabase::new(backend),
+                publisher,
+                factories,
+            )
+            .expect("Could not initialize Output Manager Service")
+            .start();
 
             futures::pin_mut!(service);
             future::select(service, shutdown).await;
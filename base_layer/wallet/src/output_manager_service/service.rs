@@ -22,57 +22,132 @@
 
 use crate::{
     output_manager_service::{
-        error::OutputManagerError,
-        handle::{OutputManagerRequest, OutputManagerResponse},
-        storage::database::{KeyManagerState, OutputManagerBackend, OutputManagerDatabase, PendingTransactionOutputs},
+        config::OutputManagerServiceConfig,
+        error::{OutputManagerError, OutputManagerStorageError},
+        handle::{OutputManagerEvent, OutputManagerRequest, OutputManagerResponse},
+        storage::database::{
+            AtomicSwap,
+            DbKey,
+            KeyManagerState,
+            OutputManagerBackend,
+            OutputManagerBackup,
+            OutputManagerDatabase,
+            PendingTransactionOutputs,
+            SharedOutput,
+            ViewKey,
+            WatchOnlyExport,
+            WatchOnlyState,
+            WatchedOutput,
+        },
         TxId,
     },
     types::{HashDigest, KeyDigest, TransactionRng},
 };
-use futures::{pin_mut, StreamExt};
+use digest::Digest;
+use futures::{pin_mut, SinkExt, Stream, StreamExt};
 use log::*;
-use std::{collections::HashMap, sync::Mutex, time::Duration};
-use tari_core::transactions::{
-    fee::Fee,
-    tari_amount::MicroTari,
-    transaction::{OutputFeatures, TransactionInput, TransactionOutput, UnblindedOutput},
-    types::{CryptoFactories, PrivateKey},
-    SenderTransactionProtocol,
+use rand::{Rng, RngCore};
+use std::{
+    cmp,
+    collections::{HashMap, HashSet},
+    convert::TryFrom,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+use tari_broadcast_channel::Publisher;
+use tari_comms::types::CommsPublicKey;
+use tari_comms_dht::{
+    domain_message::OutboundDomainMessage,
+    outbound::{OutboundEncryption, OutboundMessageRequester, SendMessageResponse},
+};
+use tari_core::{
+    base_node::proto::base_node::{
+        base_node_service_request::Request as BaseNodeRequestProto,
+        base_node_service_response::Response as BaseNodeResponseProto,
+        BaseNodeServiceRequest,
+        BaseNodeServiceResponse,
+        HashOutputs,
+    },
+    transactions::{
+        fee::Fee,
+        tari_amount::MicroTari,
+        transaction::{
+            KernelFeatures,
+            OutputFeatures,
+            RewindData,
+            Transaction,
+            TransactionInput,
+            TransactionOutput,
+            UnblindedOutput,
+        },
+        types::{Commitment, CryptoFactories, PrivateKey, PublicKey, RangeProof},
+        SenderTransactionProtocol,
+    },
+};
+use tari_crypto::{
+    commitment::HomomorphicCommitmentFactory,
+    keys::{PublicKey as PublicKeyTrait, SecretKey},
+    range_proof::REWIND_PROOF_MESSAGE_LENGTH,
 };
-use tari_crypto::keys::SecretKey;
 use tari_key_manager::{
     key_manager::KeyManager,
     mnemonic::{from_secret_key, MnemonicLanguage},
 };
+use tari_p2p::{domain_message::DomainMessage, tari_message::TariMessageType};
 use tari_service_framework::reply_channel;
+use tari_utilities::{hash::Hashable, hex::Hex, ByteArray, ByteArrayError};
+use tokio::time;
 
 const LOG_TARGET: &'static str = "base_layer::wallet::output_manager_service";
+/// The number of steps the branch and bound UTXO selection takes before giving up
+const BRANCH_AND_BOUND_MAX_TRIES: usize = 100_000;
 
 /// This service will manage a wallet's available outputs and the key manager that produces the keys for these outputs.
 /// The service will assemble transactions to be sent from the wallets available outputs and provide keys to receive
 /// outputs. When the outputs are detected on the blockchain the Transaction service will call this Service to confirm
 /// them to be moved to the spent and unspent output lists respectively.
-pub struct OutputManagerService<T>
-where T: OutputManagerBackend
+/// Once a base node public key has been set the service will periodically ask the base node whether its unspent outputs
+/// are present in the blockchain's UTXO set. Unspent outputs that are not found are marked as invalid, so that they are
+/// excluded from the balance and from transactions, and invalid outputs that are found again are marked as unspent.
+///
+/// A wallet into which the view key or outputs of another wallet have been imported is watch-only. It refuses to send
+/// or receive funds, and its balance is that of the watched outputs, which are kept up to date by the Watch-only
+/// Service.
+pub struct OutputManagerService<TBackend, TBaseNodeResponseStream>
+where TBackend: OutputManagerBackend
 {
+    config: OutputManagerServiceConfig,
     key_manager: Mutex<KeyManager<PrivateKey, KeyDigest>>,
-    db: OutputManagerDatabase<T>,
+    db: OutputManagerDatabase<TBackend>,
+    outbound_message_service: OutboundMessageRequester,
     request_stream:
         Option<reply_channel::Receiver<OutputManagerRequest, Result<OutputManagerResponse, OutputManagerError>>>,
+    base_node_response_stream: Option<TBaseNodeResponseStream>,
     factories: CryptoFactories,
+    base_node_public_key: Option<CommsPublicKey>,
+    pending_utxo_queries: HashMap<u64, Vec<Vec<u8>>>,
+    event_publisher: Publisher<OutputManagerEvent>,
+    chain_height: Option<u64>,
+    watch_only: bool,
 }
 
-impl<T> OutputManagerService<T>
-where T: OutputManagerBackend
+impl<TBackend, TBaseNodeResponseStream> OutputManagerService<TBackend, TBaseNodeResponseStream>
+where
+    TBackend: OutputManagerBackend,
+    TBaseNodeResponseStream: Stream<Item = DomainMessage<BaseNodeServiceResponse>>,
 {
     pub fn new(
+        config: OutputManagerServiceConfig,
+        outbound_message_service: OutboundMessageRequester,
         request_stream: reply_channel::Receiver<
             OutputManagerRequest,
             Result<OutputManagerResponse, OutputManagerError>,
         >,
-        mut db: OutputManagerDatabase<T>,
+        base_node_response_stream: TBaseNodeResponseStream,
+        mut db: OutputManagerDatabase<TBackend>,
+        event_publisher: Publisher<OutputManagerEvent>,
         factories: CryptoFactories,
-    ) -> Result<OutputManagerService<T>, OutputManagerError>
+    ) -> Result<OutputManagerService<TBackend, TBaseNodeResponseStream>, OutputManagerError>
     {
         let mut rng = rand::OsRng::new().unwrap();
         // Check to see if there is any persisted state, otherwise start fresh
@@ -88,16 +163,25 @@ where T: OutputManagerBackend
             },
             Some(km) => km,
         };
+        let watch_only = db.get_watch_only_state()?.is_some();
 
         Ok(OutputManagerService {
+            config,
             key_manager: Mutex::new(KeyManager::<PrivateKey, KeyDigest>::from(
                 key_manager_state.master_seed,
                 key_manager_state.branch_seed,
                 key_manager_state.primary_key_index,
             )),
             db,
+            outbound_message_service,
             request_stream: Some(request_stream),
+            base_node_response_stream: Some(base_node_response_stream),
             factories,
+            base_node_public_key: None,
+            pending_utxo_queries: HashMap::new(),
+            event_publisher,
+            chain_height: None,
+            watch_only,
         })
     }
 
@@ -108,6 +192,16 @@ where T: OutputManagerBackend
             .expect("OutputManagerService initialized without request_stream")
             .fuse();
         pin_mut!(request_stream);
+        let base_node_response_stream = self
+            .base_node_response_stream
+            .take()
+            .expect("Output Manager Service initialized without base_node_response_stream")
+            .fuse();
+        pin_mut!(base_node_response_stream);
+
+        let validation_interval = self.config.utxo_validation_interval;
+        let mut utxo_validation_tick =
+            time::interval_at((Instant::now() + validation_interval).into(), validation_interval).fuse();
 
         info!("Output Manager Service started");
         loop {
@@ -122,6 +216,27 @@ where T: OutputManagerBackend
                         Err(resp)
                     });
                 },
+                // Incoming messages from the Comms layer
+                msg = base_node_response_stream.select_next_some() => {
+                    let result = self.handle_base_node_response(msg.inner).await.or_else(|err| {
+                        error!(target: LOG_TARGET, "Failed to handle incoming Base Node response: {:?}", err);
+                        Err(err)
+                    });
+
+                    if result.is_err() {
+                        let _ = self.event_publisher
+                                .send(OutputManagerEvent::Error(
+                                    "Error handling Base Node Response message".to_string(),
+                                ))
+                                .await;
+                    }
+                },
+                _ = utxo_validation_tick.select_next_some() => {
+                    let _ = self.validate_utxos().await.or_else(|err| {
+                        error!(target: LOG_TARGET, "Failed to request UTXO validation from the base node: {:?}", err);
+                        Err(err)
+                    });
+                },
                 complete => {
                     info!(target: LOG_TARGET, "Output manager service shutting down");
                     break;
@@ -144,8 +259,23 @@ where T: OutputManagerBackend
             OutputManagerRequest::GetRecipientKey((tx_id, amount)) => self
                 .get_recipient_spending_key(tx_id, amount)
                 .map(|k| OutputManagerResponse::RecipientKeyGenerated(k)),
-            OutputManagerRequest::PrepareToSendTransaction((amount, fee_per_gram, lock_height, message)) => self
-                .prepare_transaction_to_send(amount, fee_per_gram, lock_height, message)
+            OutputManagerRequest::PrepareToSendTransaction((amount, fee_per_gram, lock_height, message, strategy)) => {
+                self.prepare_transaction_to_send(amount, fee_per_gram, lock_height, message, strategy)
+                    .map(|stp| OutputManagerResponse::TransactionToSend(stp))
+            },
+            OutputManagerRequest::PrepareToSendMultiRecipientTransaction((
+                amounts,
+                fee_per_gram,
+                lock_height,
+                message,
+            )) => self
+                .prepare_multi_recipient_transaction_to_send(
+                    amounts,
+                    fee_per_gram,
+                    lock_height,
+                    message,
+                    UTXOSelectionStrategy::default(),
+                )
                 .map(|stp| OutputManagerResponse::TransactionToSend(stp)),
             OutputManagerRequest::ConfirmReceivedOutput((tx_id, output)) => self
                 .confirm_received_transaction_output(tx_id, &output)
@@ -168,10 +298,106 @@ where T: OutputManagerBackend
             OutputManagerRequest::GetUnspentOutputs => self
                 .fetch_unspent_outputs()
                 .map(|o| OutputManagerResponse::UnspentOutputs(o)),
+            OutputManagerRequest::GetInvalidOutputs => self
+                .fetch_invalid_outputs()
+                .map(|o| OutputManagerResponse::InvalidOutputs(o)),
             OutputManagerRequest::GetSeedWords => self.get_seed_words().map(|sw| OutputManagerResponse::SeedWords(sw)),
+            OutputManagerRequest::SetBaseNodePublicKey(pk) => self
+                .set_base_node_public_key(pk)
+                .await
+                .map(|_| OutputManagerResponse::BaseNodePublicKeySet),
             OutputManagerRequest::GetCoinbaseKey((tx_id, amount, maturity_height)) => self
                 .get_coinbase_spending_key(tx_id, amount, maturity_height)
                 .map(|k| OutputManagerResponse::RecipientKeyGenerated(k)),
+            OutputManagerRequest::RestoreKeyManager((master_key, primary_key_index)) => self
+                .restore_key_manager(master_key, primary_key_index)
+                .map(|_| OutputManagerResponse::KeyManagerRestored),
+            OutputManagerRequest::GetRewindData => self.get_rewind_data().map(OutputManagerResponse::RewindData),
+            OutputManagerRequest::ApplyEncryption(cipher) => self
+                .db
+                .apply_encryption(cipher)
+                .map(|_| OutputManagerResponse::EncryptionApplied)
+                .map_err(OutputManagerError::OutputManagerStorageError),
+            OutputManagerRequest::Unlock(cipher) => self
+                .db
+                .unlock(cipher)
+                .map(|_| OutputManagerResponse::Unlocked)
+                .map_err(OutputManagerError::OutputManagerStorageError),
+            OutputManagerRequest::Lock => self
+                .db
+                .lock()
+                .map(|_| OutputManagerResponse::Locked)
+                .map_err(OutputManagerError::OutputManagerStorageError),
+            OutputManagerRequest::SetChainHeight(height) => {
+                self.chain_height = Some(height);
+                Ok(OutputManagerResponse::ChainHeightSet)
+            },
+            OutputManagerRequest::GetBackup => self.get_backup().map(OutputManagerResponse::Backup),
+            OutputManagerRequest::RestoreBackup(backup) => self
+                .restore_backup(backup)
+                .map(|_| OutputManagerResponse::BackupRestored),
+            OutputManagerRequest::TransactionFee((amount, fee_per_gram)) => self
+                .transaction_fee(amount, fee_per_gram)
+                .map(OutputManagerResponse::FeeEstimate),
+            OutputManagerRequest::CoinSplitFee((amount_per_split, split_count, fee_per_gram)) => self
+                .coin_split_fee(amount_per_split, split_count, fee_per_gram)
+                .map(OutputManagerResponse::FeeEstimate),
+            OutputManagerRequest::CreateCoinSplit((
+                amount_per_split,
+                split_count,
+                fee_per_gram,
+                lock_height,
+                message,
+            )) => self
+                .create_coin_split(amount_per_split, split_count, fee_per_gram, lock_height, message)
+                .map(OutputManagerResponse::TransactionToSelf),
+            OutputManagerRequest::ConsolidationFee((max_outputs, fee_per_gram)) => self
+                .consolidation_fee(max_outputs, fee_per_gram)
+                .map(OutputManagerResponse::FeeEstimate),
+            OutputManagerRequest::ConsolidateOutputs((max_outputs, fee_per_gram, lock_height, message)) => self
+                .consolidate_outputs(max_outputs, fee_per_gram, lock_height, message)
+                .map(OutputManagerResponse::TransactionToSelf),
+            OutputManagerRequest::ExportWatchOnlyData => self
+                .export_watch_only_data()
+                .map(OutputManagerResponse::WatchOnlyExport),
+            OutputManagerRequest::ImportWatchOnlyData((export, birthday_height)) => self
+                .import_watch_only_data(export, birthday_height)
+                .map(|_| OutputManagerResponse::WatchOnlyDataImported),
+            OutputManagerRequest::GetWatchOnlyState => {
+                Ok(OutputManagerResponse::WatchOnlyState(self.db.get_watch_only_state()?))
+            },
+            OutputManagerRequest::GetWatchedOutputs => {
+                Ok(OutputManagerResponse::WatchedOutputs(self.db.fetch_watched_outputs()?))
+            },
+            OutputManagerRequest::UpdateWatchedOutputs((outputs, next_height)) => self
+                .update_watched_outputs(outputs, next_height)
+                .map(|_| OutputManagerResponse::WatchedOutputsUpdated),
+            OutputManagerRequest::CreateSharedOutputFunding((value, spending_key, fee_per_gram, message)) => self
+                .create_shared_output_funding(value, spending_key, fee_per_gram, message)
+                .map(OutputManagerResponse::TransactionToSelf),
+            OutputManagerRequest::GetSharedOutputs => {
+                Ok(OutputManagerResponse::SharedOutputs(self.db.fetch_shared_outputs()?))
+            },
+            OutputManagerRequest::SaveSharedOutput(output) => {
+                self.check_not_watch_only()?;
+                self.db.save_shared_output(output)?;
+                Ok(OutputManagerResponse::SharedOutputSaved)
+            },
+            OutputManagerRequest::CreateHashLockedFunding((value, spending_key, features, fee_per_gram, message)) => {
+                self.create_hash_locked_funding(value, spending_key, features, fee_per_gram, message)
+                    .map(OutputManagerResponse::TransactionToSelf)
+            },
+            OutputManagerRequest::AddPendingIncomingOutput((tx_id, output)) => self
+                .add_pending_incoming_output(tx_id, output)
+                .map(|_| OutputManagerResponse::PendingIncomingOutputAdded),
+            OutputManagerRequest::GetAtomicSwaps => {
+                Ok(OutputManagerResponse::AtomicSwaps(self.db.fetch_atomic_swaps()?))
+            },
+            OutputManagerRequest::SaveAtomicSwap(swap) => {
+                self.check_not_watch_only()?;
+                self.db.save_atomic_swap(swap)?;
+                Ok(OutputManagerResponse::AtomicSwapSaved)
+            },
         }
     }
 
@@ -180,8 +406,12 @@ where T: OutputManagerBackend
         Ok(self.db.add_unspent_output(output)?)
     }
 
+    /// The balance of the wallet's outputs or, if the wallet is watch-only, of its watched outputs
     pub fn get_balance(&self) -> Result<Balance, OutputManagerError> {
-        Ok(self.db.get_balance()?)
+        if self.watch_only {
+            return Ok(self.db.get_watch_only_balance(self.chain_height)?);
+        }
+        Ok(self.db.get_balance(self.chain_height)?)
     }
 
     /// Request a spending key to be used to accept a transaction from a sender.
@@ -191,6 +421,7 @@ where T: OutputManagerBackend
         amount: MicroTari,
     ) -> Result<PrivateKey, OutputManagerError>
     {
+        self.check_not_watch_only()?;
         let mut km = acquire_lock!(self.key_manager);
 
         let key = km.next_key()?.k;
@@ -213,6 +444,7 @@ where T: OutputManagerBackend
         maturity_height: u64,
     ) -> Result<PrivateKey, OutputManagerError>
     {
+        self.check_not_watch_only()?;
         let mut km = acquire_lock!(self.key_manager);
 
         let key = km.next_key()?.k;
@@ -253,44 +485,68 @@ where T: OutputManagerBackend
         Ok(())
     }
 
-    /// Prepare a Sender Transaction Protocol for the amount and fee_per_gram specified. If required a change output
-    /// will be produced.
+    /// Prepare a Sender Transaction Protocol for the amount and fee_per_gram specified, spending outputs chosen with
+    /// the given selection strategy. If required a change output will be produced.
     pub fn prepare_transaction_to_send(
         &mut self,
         amount: MicroTari,
         fee_per_gram: MicroTari,
         lock_height: Option<u64>,
         message: String,
+        strategy: UTXOSelectionStrategy,
+    ) -> Result<SenderTransactionProtocol, OutputManagerError>
+    {
+        self.prepare_multi_recipient_transaction_to_send(vec![amount], fee_per_gram, lock_height, message, strategy)
+    }
+
+    /// Prepare a Sender Transaction Protocol that pays each of the amounts specified to a different recipient, spending
+    /// outputs chosen with the given selection strategy. If required a change output will be produced.
+    pub fn prepare_multi_recipient_transaction_to_send(
+        &mut self,
+        amounts: Vec<MicroTari>,
+        fee_per_gram: MicroTari,
+        lock_height: Option<u64>,
+        message: String,
+        strategy: UTXOSelectionStrategy,
     ) -> Result<SenderTransactionProtocol, OutputManagerError>
     {
+        self.check_not_watch_only()?;
+        if amounts.is_empty() {
+            return Err(OutputManagerError::NoRecipients);
+        }
         let mut rng = TransactionRng::new().unwrap();
-        let outputs = self.select_outputs(amount, fee_per_gram, UTXOSelectionStrategy::Smallest)?;
+        let amount = amounts.iter().fold(MicroTari::from(0), |acc, a| acc + *a);
+        let num_recipients = amounts.len();
+        let outputs = self.select_outputs(amount, fee_per_gram, num_recipients, strategy)?;
         let total = outputs.iter().fold(MicroTari::from(0), |acc, x| acc + x.value);
 
         let offset = PrivateKey::random(&mut rng);
         let nonce = PrivateKey::random(&mut rng);
 
-        let mut builder = SenderTransactionProtocol::builder(1);
+        let mut builder = SenderTransactionProtocol::builder(num_recipients);
         builder
             .with_lock_height(lock_height.unwrap_or(0))
             .with_fee_per_gram(fee_per_gram)
             .with_offset(offset.clone())
             .with_private_nonce(nonce.clone())
-            .with_amount(0, amount)
-            .with_message(message);
+            .with_message(message)
+            .with_rewindable_outputs(self.get_rewind_data()?);
+        for (i, a) in amounts.iter().enumerate() {
+            builder.with_amount(i, *a);
+        }
 
         for uo in outputs.iter() {
             builder.with_input(
-                uo.as_transaction_input(&self.factories.commitment, OutputFeatures::default()),
+                uo.as_transaction_input(&self.factories.commitment, uo.features.clone()),
                 uo.clone(),
             );
         }
 
-        let fee_without_change = Fee::calculate(fee_per_gram, outputs.len(), 1);
+        let fee_with_change = Fee::calculate(fee_per_gram, outputs.len(), num_recipients + 1);
         let mut change_key: Option<PrivateKey> = None;
-        // If the input values > the amount to be sent + fees_without_change then we will need to include a change
-        // output
-        if total > amount + fee_without_change {
+        // If the input values > the amount to be sent + fees_with_change then we will need to include a change output.
+        // A smaller excess is paid as fee by the sender protocol
+        if total > amount + fee_with_change {
             let mut km = acquire_lock!(self.key_manager);
             let key = km.next_key()?.k;
             self.db.increment_key_index()?;
@@ -319,6 +575,317 @@ where T: OutputManagerBackend
         Ok(stp)
     }
 
+    /// Estimate the fee of a transaction that pays `amount` to a single recipient, without preparing it. The outputs
+    /// are selected in the same way as when the transaction is sent.
+    pub fn transaction_fee(
+        &mut self,
+        amount: MicroTari,
+        fee_per_gram: MicroTari,
+    ) -> Result<MicroTari, OutputManagerError>
+    {
+        let inputs = self.select_outputs(amount, fee_per_gram, 1, UTXOSelectionStrategy::default())?;
+        Ok(self_payment_fee(&inputs, amount, fee_per_gram, 1))
+    }
+
+    /// Estimate the fee of a coin split with the given parameters, without creating it
+    pub fn coin_split_fee(
+        &mut self,
+        amount_per_split: MicroTari,
+        split_count: usize,
+        fee_per_gram: MicroTari,
+    ) -> Result<MicroTari, OutputManagerError>
+    {
+        let inputs = self.select_coin_split_inputs(amount_per_split, split_count, fee_per_gram)?;
+        Ok(self_payment_fee(
+            &inputs,
+            amount_per_split * split_count as u64,
+            fee_per_gram,
+            split_count,
+        ))
+    }
+
+    /// Create a transaction that pays `split_count` outputs of `amount_per_split` each back to this wallet, along with
+    /// change if required. The largest outputs are spent, so a large output can be split into smaller ones that can
+    /// be spent by transactions in parallel. Returns the tx_id, fee, amount paid into the new outputs and the
+    /// finalized transaction, which still has to be broadcast.
+    pub fn create_coin_split(
+        &mut self,
+        amount_per_split: MicroTari,
+        split_count: usize,
+        fee_per_gram: MicroTari,
+        lock_height: Option<u64>,
+        message: String,
+    ) -> Result<(TxId, MicroTari, MicroTari, Transaction), OutputManagerError>
+    {
+        self.check_not_watch_only()?;
+        let inputs = self.select_coin_split_inputs(amount_per_split, split_count, fee_per_gram)?;
+        let amount = amount_per_split * split_count as u64;
+        let total = inputs.iter().fold(MicroTari::from(0), |acc, o| acc + o.value);
+
+        let mut outputs = Vec::with_capacity(split_count);
+        for _ in 0..split_count {
+            outputs.push(UnblindedOutput::new(amount_per_split, self.next_spending_key()?, None));
+        }
+
+        let fee_with_change = Fee::calculate(fee_per_gram, inputs.len(), split_count + 1);
+        let change_key = if total > amount + fee_with_change {
+            Some(self.next_spending_key()?)
+        } else {
+            None
+        };
+
+        self.create_transaction_to_self(
+            inputs,
+            outputs,
+            Vec::new(),
+            change_key,
+            fee_per_gram,
+            lock_height,
+            message,
+        )
+    }
+
+    /// Estimate the fee of consolidating up to `max_outputs` of the smallest unspent outputs, without doing so
+    pub fn consolidation_fee(
+        &mut self,
+        max_outputs: usize,
+        fee_per_gram: MicroTari,
+    ) -> Result<MicroTari, OutputManagerError>
+    {
+        let inputs = self.select_consolidation_inputs(max_outputs)?;
+        Ok(Fee::calculate(fee_per_gram, inputs.len(), 1))
+    }
+
+    /// Create a transaction that spends up to `max_outputs` of the smallest unspent outputs and pays their value, less
+    /// the fee, into a single output back to this wallet. Returns the tx_id, fee, value of the new output and the
+    /// finalized transaction, which still has to be broadcast.
+    pub fn consolidate_outputs(
+        &mut self,
+        max_outputs: usize,
+        fee_per_gram: MicroTari,
+        lock_height: Option<u64>,
+        message: String,
+    ) -> Result<(TxId, MicroTari, MicroTari, Transaction), OutputManagerError>
+    {
+        self.check_not_watch_only()?;
+        let inputs = self.select_consolidation_inputs(max_outputs)?;
+        let total = inputs.iter().fold(MicroTari::from(0), |acc, o| acc + o.value);
+        let fee = Fee::calculate(fee_per_gram, inputs.len(), 1);
+        let value = total.checked_sub(fee).ok_or(OutputManagerError::NotEnoughFunds)?;
+        if value == MicroTari::from(0) {
+            return Err(OutputManagerError::NotEnoughFunds);
+        }
+
+        let output = UnblindedOutput::new(value, self.next_spending_key()?, None);
+        self.create_transaction_to_self(
+            inputs,
+            vec![output],
+            Vec::new(),
+            None,
+            fee_per_gram,
+            lock_height,
+            message,
+        )
+    }
+
+    /// Create a transaction that funds a shared output of the given value, with the spending key that the participants
+    /// of the shared output have agreed on, and pays any change back to this wallet. The shared output is not added to
+    /// this wallet's outputs. Returns the tx_id, fee, value of the shared output and the finalized transaction, which
+    /// still has to be broadcast.
+    pub fn create_shared_output_funding(
+        &mut self,
+        value: MicroTari,
+        spending_key: PrivateKey,
+        fee_per_gram: MicroTari,
+        message: String,
+    ) -> Result<(TxId, MicroTari, MicroTari, Transaction), OutputManagerError>
+    {
+        self.create_funding_transaction(UnblindedOutput::new(value, spending_key, None), fee_per_gram, message)
+    }
+
+    /// Create a transaction that funds a hash locked output of the given value, with the spending key that is shared
+    /// with the claimer and the features that hold the hash lock, and pays any change back to this wallet. The hash
+    /// locked output is not added to this wallet's outputs. Returns the tx_id, fee, value of the hash locked output and
+    /// the finalized transaction, which still has to be broadcast.
+    pub fn create_hash_locked_funding(
+        &mut self,
+        value: MicroTari,
+        spending_key: PrivateKey,
+        features: OutputFeatures,
+        fee_per_gram: MicroTari,
+        message: String,
+    ) -> Result<(TxId, MicroTari, MicroTari, Transaction), OutputManagerError>
+    {
+        if features.hash_lock.is_none() {
+            return Err(OutputManagerError::BuildError(
+                "The output is not hash locked".to_string(),
+            ));
+        }
+        self.create_funding_transaction(
+            UnblindedOutput::new(value, spending_key, Some(features)),
+            fee_per_gram,
+            message,
+        )
+    }
+
+    /// Add an output whose spending key this wallet already holds, such as the output of an atomic swap, as an output
+    /// to be received by the pending transaction with the given tx_id
+    pub fn add_pending_incoming_output(
+        &mut self,
+        tx_id: TxId,
+        output: UnblindedOutput,
+    ) -> Result<(), OutputManagerError>
+    {
+        self.check_not_watch_only()?;
+        self.db
+            .accept_incoming_pending_transaction(&tx_id, &output.value, &output.spending_key, output.features)?;
+        Ok(())
+    }
+
+    /// Create a transaction to self that funds an output that this wallet does not keep, paying any change back to this
+    /// wallet
+    fn create_funding_transaction(
+        &mut self,
+        output: UnblindedOutput,
+        fee_per_gram: MicroTari,
+        message: String,
+    ) -> Result<(TxId, MicroTari, MicroTari, Transaction), OutputManagerError>
+    {
+        self.check_not_watch_only()?;
+        let value = output.value;
+        let inputs = self.select_outputs(value, fee_per_gram, 1, UTXOSelectionStrategy::default())?;
+        let total = inputs.iter().fold(MicroTari::from(0), |acc, o| acc + o.value);
+        let fee_with_change = Fee::calculate(fee_per_gram, inputs.len(), 2);
+        let change_key = if total > value + fee_with_change {
+            Some(self.next_spending_key()?)
+        } else {
+            None
+        };
+
+        self.create_transaction_to_self(
+            inputs,
+            Vec::new(),
+            vec![output],
+            change_key,
+            fee_per_gram,
+            None,
+            message,
+        )
+    }
+
+    fn select_coin_split_inputs(
+        &mut self,
+        amount_per_split: MicroTari,
+        split_count: usize,
+        fee_per_gram: MicroTari,
+    ) -> Result<Vec<UnblindedOutput>, OutputManagerError>
+    {
+        if split_count == 0 || amount_per_split == MicroTari::from(0) {
+            return Err(OutputManagerError::InvalidCoinSplit);
+        }
+        self.select_outputs(
+            amount_per_split * split_count as u64,
+            fee_per_gram,
+            split_count,
+            UTXOSelectionStrategy::Largest,
+        )
+    }
+
+    fn select_consolidation_inputs(&mut self, max_outputs: usize) -> Result<Vec<UnblindedOutput>, OutputManagerError> {
+        let uo = self.fetch_spendable_outputs()?;
+        if max_outputs < 2 || uo.len() < 2 {
+            return Err(OutputManagerError::NotEnoughOutputs);
+        }
+        Ok(uo.into_iter().take(max_outputs).collect())
+    }
+
+    /// A watch-only wallet holds no spending keys, so operations that spend or receive funds are refused
+    fn check_not_watch_only(&self) -> Result<(), OutputManagerError> {
+        if self.watch_only {
+            return Err(OutputManagerError::WatchOnlyWallet);
+        }
+        Ok(())
+    }
+
+    /// Generate the spending key for a new output of this wallet
+    fn next_spending_key(&mut self) -> Result<PrivateKey, OutputManagerError> {
+        let mut km = acquire_lock!(self.key_manager);
+        let key = km.next_key()?.k;
+        self.db.increment_key_index()?;
+        Ok(key)
+    }
+
+    /// Build and finalize a transaction without recipients that spends the inputs and pays the outputs, and any
+    /// change, back to this wallet. Transactions without recipients have no tx_id of their own so a random one is
+    /// assigned, under which the inputs are encumbered and the outputs are expected. The `other_outputs` are paid
+    /// by the transaction but are not expected by this wallet.
+    fn create_transaction_to_self(
+        &mut self,
+        inputs: Vec<UnblindedOutput>,
+        outputs: Vec<UnblindedOutput>,
+        other_outputs: Vec<UnblindedOutput>,
+        change_key: Option<PrivateKey>,
+        fee_per_gram: MicroTari,
+        lock_height: Option<u64>,
+        message: String,
+    ) -> Result<(TxId, MicroTari, MicroTari, Transaction), OutputManagerError>
+    {
+        let mut rng = TransactionRng::new().unwrap();
+        let offset = PrivateKey::random(&mut rng);
+        let nonce = PrivateKey::random(&mut rng);
+
+        let mut builder = SenderTransactionProtocol::builder(0);
+        builder
+            .with_lock_height(lock_height.unwrap_or(0))
+            .with_fee_per_gram(fee_per_gram)
+            .with_offset(offset)
+            .with_private_nonce(nonce)
+            .with_message(message);
+        // Fully rewinding a range proof reveals the blinding factor of its output, so the outputs of a transaction
+        // that pays outputs this wallet must not be able to spend are not made rewindable with this wallet's keys
+        if other_outputs.is_empty() {
+            builder.with_rewindable_outputs(self.get_rewind_data()?);
+        }
+        for uo in inputs.iter() {
+            builder.with_input(
+                uo.as_transaction_input(&self.factories.commitment, uo.features.clone()),
+                uo.clone(),
+            );
+        }
+        for uo in outputs.iter().chain(other_outputs.iter()) {
+            builder.with_output(uo.clone());
+        }
+        if let Some(key) = change_key.clone() {
+            builder.with_change_secret(key);
+        }
+
+        let mut stp = builder
+            .build::<HashDigest>(&self.factories)
+            .map_err(|e| OutputManagerError::BuildError(e.message))?;
+        let fee = stp.get_fee_amount()?;
+        let amount = outputs
+            .iter()
+            .chain(other_outputs.iter())
+            .fold(MicroTari::from(0), |acc, o| acc + o.value);
+        let mut outputs_to_receive = outputs;
+        if let Some(key) = change_key {
+            outputs_to_receive.push(UnblindedOutput::new(stp.get_change_amount()?, key, None));
+        }
+
+        if !stp.finalize(KernelFeatures::empty(), &self.factories)? {
+            return Err(OutputManagerError::BuildError(format!(
+                "Transaction to self could not be finalized: {:?}",
+                stp.failure_reason()
+            )));
+        }
+        let tx = stp.take_transaction()?;
+
+        let tx_id: TxId = rng.next_u64();
+        self.db.encumber_outputs(tx_id, &inputs, outputs_to_receive)?;
+
+        Ok((tx_id, fee, amount, tx))
+    }
+
     /// Confirm that a received or sent transaction and its outputs have been detected on the base chain. This will
     /// usually be called by the Transaction Service which monitors the base chain.
     pub fn confirm_sent_transaction(
@@ -332,7 +899,7 @@ where T: OutputManagerBackend
 
         // Check that the set of TransactionInputs and TransactionOutputs provided contain all the spent and received
         // outputs in the PendingTransaction
-        // Assumption: There will only be ONE extra output which belongs to the receiver
+        // Assumption: The extra outputs belong to the recipients. A payment to self, such as a coin split, has none
         if spent_outputs.len() != pending_transaction.outputs_to_be_spent.len() ||
             !pending_transaction.outputs_to_be_spent.iter().fold(true, |acc, i| {
                 acc && spent_outputs.iter().any(|o| {
@@ -341,7 +908,7 @@ where T: OutputManagerBackend
                             .commitment
                 })
             }) ||
-            received_outputs.len() - 1 != pending_transaction.outputs_to_be_received.len() ||
+            received_outputs.len() < pending_transaction.outputs_to_be_received.len() ||
             !pending_transaction.outputs_to_be_received.iter().fold(true, |acc, i| {
                 acc && received_outputs.iter().any(|o| {
                     o.commitment ==
@@ -374,33 +941,52 @@ where T: OutputManagerBackend
         &mut self,
         amount: MicroTari,
         fee_per_gram: MicroTari,
+        num_recipients: usize,
         strategy: UTXOSelectionStrategy,
     ) -> Result<Vec<UnblindedOutput>, OutputManagerError>
     {
-        let mut outputs = Vec::new();
-        let mut total = MicroTari::from(0);
-        let mut fee_without_change = MicroTari::from(0);
-        let mut fee_with_change = MicroTari::from(0);
-
-        let uo = self.db.fetch_sorted_unspent_outputs()?;
-
-        match strategy {
-            UTXOSelectionStrategy::Smallest => {
-                for o in uo.iter() {
-                    outputs.push(o.clone());
-                    total += o.value.clone();
-                    // I am assuming that the only output will be the payment output and change if required
-                    fee_without_change = Fee::calculate(fee_per_gram, outputs.len(), 1);
-                    fee_with_change = Fee::calculate(fee_per_gram, outputs.len(), 2);
-
-                    if total == amount + fee_without_change || total >= amount + fee_with_change {
-                        break;
-                    }
+        let mut uo = self.fetch_spendable_outputs()?;
+
+        let outputs = match strategy {
+            UTXOSelectionStrategy::Smallest => accumulate_outputs(uo, amount, fee_per_gram, num_recipients),
+            UTXOSelectionStrategy::Largest => {
+                uo.reverse();
+                accumulate_outputs(uo, amount, fee_per_gram, num_recipients)
+            },
+            UTXOSelectionStrategy::BranchAndBound => {
+                uo.reverse();
+                match branch_and_bound(&uo, amount, fee_per_gram, num_recipients) {
+                    Some(outputs) => outputs,
+                    None => accumulate_outputs(uo, amount, fee_per_gram, num_recipients),
                 }
             },
-        }
+            UTXOSelectionStrategy::Random => {
+                let mut rng = TransactionRng::new().map_err(|e| OutputManagerError::BuildError(e.to_string()))?;
+                rng.shuffle(&mut uo);
+                accumulate_outputs(uo, amount, fee_per_gram, num_recipients)
+            },
+            UTXOSelectionStrategy::CoinControl(commitments) => {
+                let mut outputs = Vec::new();
+                for c in commitments.iter() {
+                    let position = uo
+                        .iter()
+                        .position(|o| {
+                            &o.as_transaction_input(&self.factories.commitment, OutputFeatures::default())
+                                .commitment ==
+                                c
+                        })
+                        .ok_or(OutputManagerError::SelectedOutputNotAvailable)?;
+                    outputs.push(uo.remove(position));
+                }
+                outputs
+            },
+        };
 
-        if (total != amount + fee_without_change) && (total < amount + fee_with_change) {
+        let total = outputs.iter().fold(MicroTari::from(0), |acc, o| acc + o.value);
+        let fee_without_change = Fee::calculate(fee_per_gram, outputs.len(), num_recipients);
+        // The sender protocol pays any excess that is too small to be worth a change output as fee, so the selection
+        // only has to cover the amount and the fee without change
+        if total < amount + fee_without_change {
             return Err(OutputManagerError::NotEnoughFunds);
         }
 
@@ -421,6 +1007,369 @@ where T: OutputManagerBackend
         Ok(self.db.fetch_sorted_unspent_outputs()?)
     }
 
+    /// The unspent outputs, sorted by value, that have matured and can be spent in the next block. Until the chain
+    /// height is known only outputs without a maturity are spendable.
+    fn fetch_spendable_outputs(&self) -> Result<Vec<UnblindedOutput>, OutputManagerError> {
+        Ok(self
+            .db
+            .fetch_sorted_unspent_outputs()?
+            .into_iter()
+            .filter(|o| is_spendable(o, self.chain_height))
+            .collect())
+    }
+
+    pub fn fetch_invalid_outputs(&self) -> Result<Vec<UnblindedOutput>, OutputManagerError> {
+        Ok(self.db.fetch_invalid_outputs()?)
+    }
+
+    /// Set the base node that outputs are validated against and start a UTXO validation round
+    async fn set_base_node_public_key(
+        &mut self,
+        base_node_public_key: CommsPublicKey,
+    ) -> Result<(), OutputManagerError>
+    {
+        self.base_node_public_key = Some(base_node_public_key);
+        self.validate_utxos().await
+    }
+
+    /// Ask the base node which of the unspent and invalid outputs are in the blockchain's UTXO set. The outputs of
+    /// pending transactions are not queried as the Transaction Service confirms them once their transaction is mined.
+    async fn validate_utxos(&mut self) -> Result<(), OutputManagerError> {
+        let base_node_public_key = match self.base_node_public_key.clone() {
+            None => return Ok(()),
+            Some(pk) => pk,
+        };
+        // Only the response to the latest query is used
+        self.pending_utxo_queries.clear();
+
+        let pending_outputs = self
+            .db
+            .fetch_all_pending_transaction_outputs()?
+            .into_iter()
+            .flat_map(|(_, p)| p.outputs_to_be_received)
+            .collect::<Vec<_>>();
+        let output_hashes = self
+            .db
+            .fetch_sorted_unspent_outputs()?
+            .iter()
+            .chain(self.db.fetch_invalid_outputs()?.iter())
+            .chain(pending_outputs.iter())
+            .map(|o| self.output_hash(o))
+            .collect::<Vec<_>>();
+        if output_hashes.is_empty() {
+            return Ok(());
+        }
+
+        let request_key = rand::OsRng::new().unwrap().next_u64();
+        let request = BaseNodeServiceRequest {
+            request_key,
+            request: Some(BaseNodeRequestProto::FetchUtxos(HashOutputs {
+                outputs: output_hashes.clone(),
+            })),
+        };
+        if let SendMessageResponse::Failed = self
+            .outbound_message_service
+            .send_direct(
+                base_node_public_key,
+                OutboundEncryption::EncryptForPeer,
+                OutboundDomainMessage::new(TariMessageType::BaseNodeRequest, request),
+            )
+            .await
+            .map_err(|_| OutputManagerError::OutboundSendFailure)?
+        {
+            return Err(OutputManagerError::OutboundSendFailure);
+        }
+        self.pending_utxo_queries.insert(request_key, output_hashes);
+
+        Ok(())
+    }
+
+    /// Handle the base node's response to a UTXO validation query. Queried unspent outputs that are not in the UTXO
+    /// set are marked as invalid and queried invalid outputs that are in the UTXO set are marked as unspent again.
+    /// Pending transactions whose outputs to be received are all in the UTXO set have been mined and are confirmed.
+    async fn handle_base_node_response(&mut self, response: BaseNodeServiceResponse) -> Result<(), OutputManagerError> {
+        let BaseNodeServiceResponse { request_key, response } = response;
+        // Responses to queries made by other wallet services are ignored
+        let queried_hashes = match self.pending_utxo_queries.remove(&request_key) {
+            None => return Ok(()),
+            Some(hashes) => hashes.into_iter().collect::<HashSet<_>>(),
+        };
+        let utxos = match response {
+            Some(BaseNodeResponseProto::TransactionOutputs(outputs)) => outputs.outputs,
+            _ => return Err(OutputManagerError::UnexpectedBaseNodeResponse),
+        };
+        let mut found_hashes = HashSet::new();
+        for utxo in utxos {
+            let utxo = TransactionOutput::try_from(utxo).map_err(OutputManagerError::InvalidMessageError)?;
+            found_hashes.insert(utxo.hash());
+        }
+
+        let mut num_invalidated = 0;
+        for output in self.db.fetch_sorted_unspent_outputs()? {
+            let hash = self.output_hash(&output);
+            if queried_hashes.contains(&hash) && !found_hashes.contains(&hash) {
+                warn!(
+                    target: LOG_TARGET,
+                    "Unspent output with value {} could not be found in the UTXO set, marking it as invalid",
+                    output.value
+                );
+                self.db.invalidate_output(&output)?;
+                num_invalidated += 1;
+            }
+        }
+
+        let mut num_revalidated = 0;
+        for output in self.db.fetch_invalid_outputs()? {
+            if found_hashes.contains(&self.output_hash(&output)) {
+                info!(
+                    target: LOG_TARGET,
+                    "Invalid output with value {} has been found in the UTXO set, marking it as unspent", output.value
+                );
+                self.db.revalidate_output(&output.spending_key)?;
+                num_revalidated += 1;
+            }
+        }
+
+        let mut num_confirmed = 0;
+        for (tx_id, pending_tx) in self.db.fetch_all_pending_transaction_outputs()? {
+            if pending_tx.outputs_to_be_received.is_empty() {
+                continue;
+            }
+            let is_mined = pending_tx.outputs_to_be_received.iter().all(|o| {
+                let hash = self.output_hash(o);
+                queried_hashes.contains(&hash) && found_hashes.contains(&hash)
+            });
+            if is_mined {
+                info!(
+                    target: LOG_TARGET,
+                    "Outputs to be received by pending transaction with TX_ID = {} have been found in the UTXO set, \
+                     confirming it",
+                    tx_id
+                );
+                self.db.confirm_pending_transaction_outputs(tx_id)?;
+                num_confirmed += 1;
+            }
+        }
+
+        if num_invalidated > 0 {
+            self.publish_event(OutputManagerEvent::OutputsInvalidated(num_invalidated))
+                .await?;
+        }
+        if num_revalidated > 0 {
+            self.publish_event(OutputManagerEvent::OutputsRevalidated(num_revalidated))
+                .await?;
+        }
+        if num_confirmed > 0 {
+            self.publish_event(OutputManagerEvent::PendingTransactionsConfirmed(num_confirmed))
+                .await?;
+        }
+        self.publish_event(OutputManagerEvent::UtxoValidationComplete(request_key))
+            .await
+    }
+
+    async fn publish_event(&mut self, event: OutputManagerEvent) -> Result<(), OutputManagerError> {
+        self.event_publisher
+            .send(event)
+            .await
+            .map_err(|_| OutputManagerError::EventStreamError)
+    }
+
+    /// The hash of the transaction output that an unblinded output corresponds to. The range proof is not part of an
+    /// output's hash so it does not need to be constructed.
+    fn output_hash(&self, output: &UnblindedOutput) -> Vec<u8> {
+        let commitment = self
+            .factories
+            .commitment
+            .commit_value(&output.spending_key, output.value.into());
+        TransactionOutput::new(output.features.clone(), commitment, RangeProof::default()).hash()
+    }
+
+    /// Replace the Key Manager with one using the provided master key that continues deriving keys after the provided
+    /// key index, so that keys that were used by outputs recovered from the blockchain are not handed out again.
+    pub fn restore_key_manager(
+        &mut self,
+        master_key: PrivateKey,
+        primary_key_index: usize,
+    ) -> Result<(), OutputManagerError>
+    {
+        let key_manager_state = KeyManagerState {
+            master_seed: master_key,
+            branch_seed: "".to_string(),
+            primary_key_index,
+        };
+        self.db.set_key_manager_state(key_manager_state.clone())?;
+        *acquire_lock!(self.key_manager) = KeyManager::<PrivateKey, KeyDigest>::from(
+            key_manager_state.master_seed,
+            key_manager_state.branch_seed,
+            key_manager_state.primary_key_index,
+        );
+
+        Ok(())
+    }
+
+    /// Collect the key manager state and every output in the database for inclusion in a wallet backup
+    pub fn get_backup(&self) -> Result<OutputManagerBackup, OutputManagerError> {
+        let key_manager_state = self
+            .db
+            .get_key_manager_state()?
+            .ok_or(OutputManagerStorageError::KeyManagerNotInitialized)?;
+
+        Ok(OutputManagerBackup {
+            key_manager_state,
+            unspent_outputs: self.db.fetch_sorted_unspent_outputs()?,
+            spent_outputs: self.db.fetch_spent_outputs()?,
+            invalid_outputs: self.db.fetch_invalid_outputs()?,
+            pending_transactions: self
+                .db
+                .fetch_all_pending_transaction_outputs()?
+                .into_iter()
+                .map(|(_, p)| p)
+                .collect(),
+            watch_only_state: self.db.get_watch_only_state()?,
+            watched_outputs: self.db.fetch_watched_outputs()?,
+            shared_outputs: self.db.fetch_shared_outputs()?,
+            atomic_swaps: self.db.fetch_atomic_swaps()?,
+        })
+    }
+
+    /// Replace the Key Manager with the one stored in a wallet backup and add the backed up outputs to the database
+    pub fn restore_backup(&mut self, backup: OutputManagerBackup) -> Result<(), OutputManagerError> {
+        self.db.set_key_manager_state(backup.key_manager_state.clone())?;
+        *acquire_lock!(self.key_manager) = KeyManager::<PrivateKey, KeyDigest>::from(
+            backup.key_manager_state.master_seed,
+            backup.key_manager_state.branch_seed,
+            backup.key_manager_state.primary_key_index,
+        );
+
+        for output in backup.unspent_outputs {
+            self.db.add_unspent_output(output)?;
+        }
+        for output in backup.spent_outputs {
+            self.db.add_spent_output(output)?;
+        }
+        for output in backup.invalid_outputs {
+            self.db.add_unspent_output(output.clone())?;
+            self.db.invalidate_output(&output)?;
+        }
+        for pending_transaction in backup.pending_transactions {
+            self.db.add_pending_transaction_outputs(pending_transaction)?;
+        }
+        if let Some(state) = backup.watch_only_state {
+            self.db.set_watch_only_state(state)?;
+            self.watch_only = true;
+        }
+        for output in backup.watched_outputs {
+            self.db.save_watched_output(output)?;
+        }
+        for output in backup.shared_outputs {
+            self.db.save_shared_output(output)?;
+        }
+        for swap in backup.atomic_swaps {
+            self.db.save_atomic_swap(swap)?;
+        }
+
+        Ok(())
+    }
+
+    /// Export the view key and the unspent outputs of this wallet, including the outputs encumbered by pending
+    /// transactions, so that they can be watched by a watch-only wallet. The export contains no spending keys.
+    pub fn export_watch_only_data(&self) -> Result<WatchOnlyExport, OutputManagerError> {
+        self.check_not_watch_only()?;
+        let mut outputs = self.db.fetch_sorted_unspent_outputs()?;
+        for (_, pending_tx) in self.db.fetch_all_pending_transaction_outputs()? {
+            outputs.extend(pending_tx.outputs_to_be_spent);
+        }
+
+        Ok(WatchOnlyExport {
+            view_key: derive_view_key(&acquire_lock!(self.key_manager).master_key)?,
+            outputs: outputs
+                .into_iter()
+                .map(|o| WatchedOutput {
+                    commitment: self.factories.commitment.commit_value(&o.spending_key, o.value.into()),
+                    value: o.value,
+                    features: o.features,
+                    mined_height: None,
+                    spent_height: None,
+                })
+                .collect(),
+        })
+    }
+
+    /// Import the view key and outputs exported by another wallet, which makes this wallet watch-only. The blockchain
+    /// is scanned for the other wallet's outputs from the birthday height, or from the genesis block if no birthday
+    /// height is provided. The imported outputs are assumed to be unspent until they are found to be spent. An
+    /// existing watch-only wallet can import more outputs, but a wallet that has outputs of its own cannot.
+    pub fn import_watch_only_data(
+        &mut self,
+        export: WatchOnlyExport,
+        birthday_height: Option<u64>,
+    ) -> Result<(), OutputManagerError>
+    {
+        let state = self.db.get_watch_only_state()?;
+        if state.is_none() &&
+            (!self.db.fetch_sorted_unspent_outputs()?.is_empty() ||
+                !self.db.fetch_spent_outputs()?.is_empty() ||
+                !self.db.fetch_invalid_outputs()?.is_empty() ||
+                !self.db.fetch_all_pending_transaction_outputs()?.is_empty())
+        {
+            return Err(OutputManagerError::WatchOnlyImportNotAllowed);
+        }
+
+        let birthday_height = birthday_height.unwrap_or(0);
+        let next_height = state
+            .map(|s| cmp::min(s.next_height, birthday_height))
+            .unwrap_or(birthday_height);
+        self.db.set_watch_only_state(WatchOnlyState {
+            view_key: Some(export.view_key),
+            next_height,
+        })?;
+        self.watch_only = true;
+
+        let watched_commitments = self
+            .db
+            .fetch_watched_outputs()?
+            .into_iter()
+            .map(|o| o.commitment)
+            .collect::<HashSet<_>>();
+        for output in export.outputs {
+            if !watched_commitments.contains(&output.commitment) {
+                self.db.save_watched_output(output)?;
+            }
+        }
+        info!(
+            target: LOG_TARGET,
+            "Watch-only data imported, the blockchain will be scanned from block #{}", next_height
+        );
+
+        Ok(())
+    }
+
+    /// Store the watched outputs that were found or spent in the blocks that have been scanned, along with the height
+    /// of the next block to be scanned
+    pub fn update_watched_outputs(
+        &mut self,
+        outputs: Vec<WatchedOutput>,
+        next_height: u64,
+    ) -> Result<(), OutputManagerError>
+    {
+        let mut state = self
+            .db
+            .get_watch_only_state()?
+            .ok_or(OutputManagerStorageError::ValueNotFound(DbKey::WatchOnlyState))?;
+        for output in outputs {
+            self.db.save_watched_output(output)?;
+        }
+        state.next_height = next_height;
+        self.db.set_watch_only_state(state)?;
+
+        Ok(())
+    }
+
+    /// Return the rewind data derived from the current Master Key set in the Key Manager
+    pub fn get_rewind_data(&self) -> Result<RewindData, OutputManagerError> {
+        Ok(derive_rewind_data(&acquire_lock!(self.key_manager).master_key)?)
+    }
+
     /// Return the Seed words for the current Master Key set in the Key Manager
     pub fn get_seed_words(&self) -> Result<Vec<String>, OutputManagerError> {
         Ok(from_secret_key(
@@ -430,12 +1379,171 @@ where T: OutputManagerBackend
     }
 }
 
+/// Derive the rewind keys for a wallet's range proofs from its master key. The keys can be derived again from the
+/// wallet's seed words, so that its outputs can be recovered from the blockchain.
+pub fn derive_rewind_data(master_key: &PrivateKey) -> Result<RewindData, ByteArrayError> {
+    let rewind_key =
+        PrivateKey::from_bytes(KeyDigest::digest(format!("{}rewind_key", master_key.to_hex()).as_bytes()).as_slice())?;
+    let rewind_blinding_key = PrivateKey::from_bytes(
+        KeyDigest::digest(format!("{}rewind_blinding_key", master_key.to_hex()).as_bytes()).as_slice(),
+    )?;
+    Ok(RewindData {
+        rewind_key,
+        rewind_blinding_key,
+        proof_message: [0u8; REWIND_PROOF_MESSAGE_LENGTH],
+    })
+}
+
+/// Derive the view key of a wallet from its master key. The view key consists of the public keys of the wallet's rewind
+/// keys.
+pub fn derive_view_key(master_key: &PrivateKey) -> Result<ViewKey, ByteArrayError> {
+    let rewind_data = derive_rewind_data(master_key)?;
+    Ok(ViewKey {
+        rewind_public_key: PublicKey::from_secret_key(&rewind_data.rewind_key),
+        rewind_blinding_public_key: PublicKey::from_secret_key(&rewind_data.rewind_blinding_key),
+    })
+}
+
+/// Whether the output has matured, so that a transaction spending it can be mined in the block after the given chain
+/// height. Outputs with a maturity are treated as time-locked while the chain height is unknown.
+pub fn is_spendable(output: &UnblindedOutput, chain_height: Option<u64>) -> bool {
+    output.features.maturity <= chain_height.map(|h| h + 1).unwrap_or(0)
+}
+
+/// The fee of a transaction that spends the inputs and pays `num_outputs` outputs, to self or to recipients, with a
+/// total value of `amount`. An excess that is too small to be worth a change output is paid as fee.
+fn self_payment_fee(
+    inputs: &[UnblindedOutput],
+    amount: MicroTari,
+    fee_per_gram: MicroTari,
+    num_outputs: usize,
+) -> MicroTari
+{
+    let total = inputs.iter().fold(MicroTari::from(0), |acc, o| acc + o.value);
+    let fee_with_change = Fee::calculate(fee_per_gram, inputs.len(), num_outputs + 1);
+    if total > amount + fee_with_change {
+        fee_with_change
+    } else {
+        total - amount
+    }
+}
+
+/// Take outputs in the given order until they cover the amount and fees. Stops early if the outputs exactly cover the
+/// amount and the fee without a change output.
+fn accumulate_outputs(
+    uo: Vec<UnblindedOutput>,
+    amount: MicroTari,
+    fee_per_gram: MicroTari,
+    num_recipients: usize,
+) -> Vec<UnblindedOutput>
+{
+    let mut outputs = Vec::new();
+    let mut total = MicroTari::from(0);
+    for o in uo.into_iter() {
+        total += o.value;
+        outputs.push(o);
+        // I am assuming that the only outputs will be the payment outputs and change if required
+        let fee_without_change = Fee::calculate(fee_per_gram, outputs.len(), num_recipients);
+        let fee_with_change = Fee::calculate(fee_per_gram, outputs.len(), num_recipients + 1);
+
+        if total == amount + fee_without_change || total >= amount + fee_with_change {
+            break;
+        }
+    }
+    outputs
+}
+
+/// Search for a set of outputs whose total covers the amount and the fee without change, but exceeds it by no more
+/// than the cost of a change output. The outputs should be sorted from largest to smallest so that the search finds
+/// sets with fewer inputs first.
+fn branch_and_bound(
+    uo: &[UnblindedOutput],
+    amount: MicroTari,
+    fee_per_gram: MicroTari,
+    num_recipients: usize,
+) -> Option<Vec<UnblindedOutput>>
+{
+    let mut search = BranchAndBoundSearch {
+        uo,
+        amount,
+        fee_per_gram,
+        num_recipients,
+        tries: BRANCH_AND_BOUND_MAX_TRIES,
+    };
+    let remaining = uo.iter().fold(MicroTari::from(0), |acc, o| acc + o.value);
+    let mut selected = Vec::new();
+    if search.search(0, &mut selected, MicroTari::from(0), remaining) {
+        Some(selected.iter().map(|i| uo[*i].clone()).collect())
+    } else {
+        None
+    }
+}
+
+struct BranchAndBoundSearch<'a> {
+    uo: &'a [UnblindedOutput],
+    amount: MicroTari,
+    fee_per_gram: MicroTari,
+    num_recipients: usize,
+    tries: usize,
+}
+
+impl<'a> BranchAndBoundSearch<'a> {
+    /// Depth first search that first includes and then excludes the output at `index`. `total` is the value of the
+    /// selected outputs and `remaining` the value of the outputs from `index` onwards.
+    fn search(&mut self, index: usize, selected: &mut Vec<usize>, total: MicroTari, remaining: MicroTari) -> bool {
+        if self.tries == 0 {
+            return false;
+        }
+        self.tries -= 1;
+
+        if !selected.is_empty() {
+            let fee_without_change = Fee::calculate(self.fee_per_gram, selected.len(), self.num_recipients);
+            let fee_with_change = Fee::calculate(self.fee_per_gram, selected.len(), self.num_recipients + 1);
+            if total >= self.amount + fee_without_change {
+                // Adding more outputs would only increase the excess
+                return total <= self.amount + fee_with_change;
+            }
+        }
+        let fee_with_next_input = Fee::calculate(self.fee_per_gram, selected.len() + 1, self.num_recipients);
+        if index == self.uo.len() || total + remaining < self.amount + fee_with_next_input {
+            return false;
+        }
+
+        let value = self.uo[index].value;
+        selected.push(index);
+        if self.search(index + 1, selected, total + value, remaining - value) {
+            return true;
+        }
+        selected.pop();
+        self.search(index + 1, selected, total, remaining - value)
+    }
+}
+
 /// Different UTXO selection strategies for choosing which UTXO's are used to fulfill a transaction
-/// TODO Investigate and implement more optimal strategies
+#[derive(Clone, Debug, PartialEq)]
 pub enum UTXOSelectionStrategy {
     // Start from the smallest UTXOs and work your way up until the amount is covered. Main benefit is removing small
     // UTXOs from the blockchain, con is that it costs more in fees
     Smallest,
+    // Start from the largest UTXOs and work your way down until the amount is covered. This uses the fewest inputs
+    // and so costs the least in fees, but leaves the small UTXOs behind
+    Largest,
+    // Search for a set of UTXOs that covers the amount without needing a change output, i.e. where the excess is less
+    // than the cost of adding a change output and can be paid as fee. If there is no such set within a limited number
+    // of tries the Largest strategy is used instead
+    BranchAndBound,
+    // Pick UTXOs in a random order until the amount is covered, so the inputs of a transaction reveal less about the
+    // wallet's UTXO set
+    Random,
+    // Spend exactly the UTXOs with the given commitments (coin control). All of them must be unspent and together
+    // they must cover the amount
+    CoinControl(Vec<Commitment>),
+}
+
+impl Default for UTXOSelectionStrategy {
+    fn default() -> Self {
+        UTXOSelectionStrategy::Smallest
+    }
 }
 
 /// This struct holds the detailed balance of the Output Manager Service.
@@ -447,4 +1555,7 @@ pub struct Balance {
     pub pending_incoming_balance: MicroTari,
     /// The current balance of funds encumbered in pending outbound transactions that have not been confirmed
     pub pending_outgoing_balance: MicroTari,
+    /// The current balance of unspent outputs that have not matured yet, such as recent coinbase outputs. These funds
+    /// are not part of the available balance until they can be spent.
+    pub time_locked_balance: MicroTari,
 }
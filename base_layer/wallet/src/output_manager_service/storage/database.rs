@@ -20,9 +20,17 @@
 // WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
 // USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
-use crate::output_manager_service::{error::OutputManagerStorageError, service::Balance, TxId};
+use crate::{
+    encryption::SecretCipher,
+    output_manager_service::{
+        error::OutputManagerStorageError,
+        service::{is_spendable, Balance},
+        TxId,
+    },
+};
 use chrono::{NaiveDateTime, Utc};
 use log::*;
+use serde::{Deserialize, Serialize};
 use std::{
     collections::HashMap,
     fmt::{Display, Error, Formatter},
@@ -31,7 +39,7 @@ use std::{
 use tari_core::transactions::{
     tari_amount::MicroTari,
     transaction::{OutputFeatures, UnblindedOutput},
-    types::{BlindingFactor, PrivateKey},
+    types::{BlindingFactor, Commitment, HashOutput, PrivateKey, PublicKey},
 };
 
 const LOG_TARGET: &'static str = "wallet::output_manager_service::database";
@@ -50,12 +58,13 @@ pub trait OutputManagerBackend: Send + Sync {
     /// `spent_outputs` collections.
     fn confirm_transaction(&mut self, tx_id: TxId) -> Result<(), OutputManagerStorageError>;
     /// This method encumbers the specified outputs into a `PendingTransactionOutputs` record. This reserves these
-    /// outputs until the transaction is confirmed or cancelled
+    /// outputs until the transaction is confirmed or cancelled. The outputs the transaction pays back to this wallet,
+    /// such as change, are recorded as `outputs_to_be_received`.
     fn encumber_outputs(
         &mut self,
         tx_id: TxId,
         outputs_to_send: &Vec<UnblindedOutput>,
-        change_output: Option<UnblindedOutput>,
+        outputs_to_receive: Vec<UnblindedOutput>,
     ) -> Result<(), OutputManagerStorageError>;
     /// This method must take all the `outputs_to_be_spent` from the specified transaction and move them back into the
     /// `UnspentOutputs` pool.
@@ -66,10 +75,26 @@ pub trait OutputManagerBackend: Send + Sync {
     /// This method will increment the currently stored key index for the key manager config. Increment this after eac
     /// key is generated
     fn increment_key_index(&mut self) -> Result<(), OutputManagerStorageError>;
+    /// This method must move the specified output from the `unspent_outputs` collection into the `invalid_outputs`
+    /// collection. Invalid outputs could not be found in the blockchain's UTXO set and do not count towards the
+    /// balance.
+    fn invalidate_unspent_output(&mut self, output: &UnblindedOutput) -> Result<(), OutputManagerStorageError>;
+    /// This method must move the output with the specified spending key from the `invalid_outputs` collection back
+    /// into the `unspent_outputs` collection.
+    fn revalidate_output(&mut self, spending_key: &BlindingFactor) -> Result<(), OutputManagerStorageError>;
+    /// This method must re-encrypt all the stored secrets with the provided cipher or, if no cipher is provided,
+    /// decrypt them and store them in plain form. The backend must not be locked.
+    fn apply_encryption(&mut self, cipher: Option<SecretCipher>) -> Result<(), OutputManagerStorageError>;
+    /// This method must make the stored secrets of an encrypted backend accessible using the provided cipher. An
+    /// incorrect cipher must be rejected.
+    fn unlock(&mut self, cipher: SecretCipher) -> Result<(), OutputManagerStorageError>;
+    /// This method must discard the cipher of an encrypted backend so that its stored secrets are no longer
+    /// accessible until it is unlocked again.
+    fn lock(&mut self) -> Result<(), OutputManagerStorageError>;
 }
 
 /// Holds the outputs that have been selected for a given pending transaction waiting for confirmation
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct PendingTransactionOutputs {
     pub tx_id: u64,
     pub outputs_to_be_spent: Vec<UnblindedOutput>,
@@ -78,13 +103,157 @@ pub struct PendingTransactionOutputs {
 }
 
 /// Holds the state of the KeyManager being used by the Output Manager Service
-#[derive(Clone, Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct KeyManagerState {
     pub master_seed: PrivateKey,
     pub branch_seed: String,
     pub primary_key_index: usize,
 }
 
+/// The public rewind keys of a wallet's range proofs. They reveal the value of the wallet's rewindable outputs but,
+/// unlike the private rewind keys, not their spending keys, so a view key can be given to a watch-only wallet.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ViewKey {
+    pub rewind_public_key: PublicKey,
+    pub rewind_blinding_public_key: PublicKey,
+}
+
+/// An output of another wallet that is watched by a watch-only wallet, which knows its value but not its spending key
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct WatchedOutput {
+    pub commitment: Commitment,
+    pub value: MicroTari,
+    pub features: OutputFeatures,
+    /// The height of the block the output was found in, if it has been found in the blockchain
+    pub mined_height: Option<u64>,
+    /// The height of the block the output was spent in, if it has been spent
+    pub spent_height: Option<u64>,
+}
+
+/// Holds the state of a watch-only wallet. A wallet becomes watch-only when the view key or outputs of another wallet
+/// are imported into it.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct WatchOnlyState {
+    pub view_key: Option<ViewKey>,
+    /// The height of the next block to be scanned for watched outputs
+    pub next_height: u64,
+}
+
+/// The public data that a wallet exports so that its outputs can be watched by a watch-only wallet
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct WatchOnlyExport {
+    pub view_key: ViewKey,
+    pub outputs: Vec<WatchedOutput>,
+}
+
+/// The lifecycle of a shared output
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum SharedOutputStatus {
+    /// This wallet has sent its key share and is waiting for the initiator to fund the output
+    Pending,
+    /// The output has been funded and has not been spent
+    Unspent,
+    /// The output has been spent with a signature of all of the participants
+    Spent,
+}
+
+/// An output that this wallet shares with other wallets. Its spends are signed with the MuSig aggregate of a key share
+/// of each participant, but its initiator is a trusted dealer that learns its whole spending key when it is created.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct SharedOutput {
+    /// The id that the initiator assigned to the shared output
+    pub output_id: u64,
+    pub value: MicroTari,
+    /// The comms public key of the participant that proposed and funded the output
+    pub initiator: PublicKey,
+    /// The comms public key of each participant and the public key of its key share, in the order of the proposal
+    pub participants: Vec<(PublicKey, PublicKey)>,
+    /// This wallet's share of the spending key
+    pub key_share: PrivateKey,
+    /// The commitment of the output, once it has been funded
+    pub commitment: Option<Commitment>,
+    pub status: SharedOutputStatus,
+}
+
+/// The side of an atomic swap that this wallet takes
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum AtomicSwapRole {
+    /// This wallet funds the hash locked output and can refund it from the refund height
+    Funder,
+    /// This wallet can claim the hash locked output with the preimage of its hash
+    Claimer,
+}
+
+/// The lifecycle of an atomic swap
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum AtomicSwapStatus {
+    /// The funder has offered the swap and is waiting for the claimer to accept it
+    Offered,
+    /// The claimer has accepted the swap and is waiting for the funder to fund it
+    Accepted,
+    /// The hash locked output has been funded and has not been claimed or refunded yet
+    Funded,
+    /// The hash locked output has been claimed with the preimage
+    Claimed,
+    /// The hash locked output has been refunded to the funder
+    Refunded,
+    /// The swap was abandoned
+    Cancelled,
+}
+
+/// The Tari side of an atomic swap, in which a hash locked output funded by the funder can be claimed by the claimer
+/// with the preimage of a hash, or refunded to the funder from a refund height
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct AtomicSwap {
+    /// The id that the funder assigned to the swap
+    pub swap_id: u64,
+    pub role: AtomicSwapRole,
+    /// The comms public key of the other side of the swap
+    pub counterparty: PublicKey,
+    /// The value of the hash locked output
+    pub value: MicroTari,
+    /// The SHA-256 hash of the preimage that unlocks the hash locked output
+    pub hash: HashOutput,
+    /// The block height from which the funder can refund the hash locked output
+    pub refund_height: u64,
+    /// The fee per gram that this wallet pays for the transaction that funds, claims or refunds the output
+    pub fee_per_gram: MicroTari,
+    /// The value of this wallet's output of the swap, being the claim output of the claimer and the refund output of
+    /// the funder
+    pub output_value: MicroTari,
+    /// The spending key of this wallet's output of the swap
+    pub output_key: PrivateKey,
+    /// The commitment of the other side's output of the swap, once it is known
+    pub counterparty_commitment: Option<Commitment>,
+    /// The spending key of the hash locked output, once it has been funded
+    pub spending_key: Option<PrivateKey>,
+    /// The preimage of the hash, once the output has been claimed
+    pub preimage: Option<Vec<u8>>,
+    pub message: String,
+    pub status: AtomicSwapStatus,
+    /// The height of the next block that the funder scans for the claim kernel of a funded swap
+    #[serde(default)]
+    pub scan_height: Option<u64>,
+}
+
+/// The complete contents of the Output Manager Service's storage, as included in a wallet backup
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct OutputManagerBackup {
+    pub key_manager_state: KeyManagerState,
+    pub unspent_outputs: Vec<UnblindedOutput>,
+    pub spent_outputs: Vec<UnblindedOutput>,
+    pub invalid_outputs: Vec<UnblindedOutput>,
+    pub pending_transactions: Vec<PendingTransactionOutputs>,
+    #[serde(default)]
+    pub watch_only_state: Option<WatchOnlyState>,
+    #[serde(default)]
+    pub watched_outputs: Vec<WatchedOutput>,
+    #[serde(default)]
+    pub shared_outputs: Vec<SharedOutput>,
+    #[serde(default)]
+    pub atomic_swaps: Vec<AtomicSwap>,
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub enum DbKey {
     SpentOutput(BlindingFactor),
@@ -92,8 +261,13 @@ pub enum DbKey {
     PendingTransactionOutputs(TxId),
     UnspentOutputs,
     SpentOutputs,
+    InvalidOutputs,
     AllPendingTransactionOutputs,
     KeyManagerState,
+    WatchOnlyState,
+    WatchedOutputs,
+    SharedOutputs,
+    AtomicSwaps,
 }
 
 #[derive(Debug)]
@@ -103,8 +277,13 @@ pub enum DbValue {
     PendingTransactionOutputs(Box<PendingTransactionOutputs>),
     UnspentOutputs(Vec<UnblindedOutput>),
     SpentOutputs(Vec<UnblindedOutput>),
+    InvalidOutputs(Vec<UnblindedOutput>),
     AllPendingTransactionOutputs(HashMap<TxId, PendingTransactionOutputs>),
     KeyManagerState(KeyManagerState),
+    WatchOnlyState(Box<WatchOnlyState>),
+    WatchedOutputs(Vec<WatchedOutput>),
+    SharedOutputs(Vec<SharedOutput>),
+    AtomicSwaps(Vec<AtomicSwap>),
 }
 
 pub enum DbKeyValuePair {
@@ -112,6 +291,10 @@ pub enum DbKeyValuePair {
     UnspentOutput(BlindingFactor, Box<UnblindedOutput>),
     PendingTransactionOutputs(TxId, Box<PendingTransactionOutputs>),
     KeyManagerState(KeyManagerState),
+    WatchOnlyState(Box<WatchOnlyState>),
+    WatchedOutput(Commitment, Box<WatchedOutput>),
+    SharedOutput(u64, Box<SharedOutput>),
+    AtomicSwap(u64, Box<AtomicSwap>),
 }
 
 pub enum WriteOperation {
@@ -177,7 +360,18 @@ where T: OutputManagerBackend
         Ok(())
     }
 
-    pub fn get_balance(&self) -> Result<Balance, OutputManagerStorageError> {
+    pub fn add_spent_output(&mut self, output: UnblindedOutput) -> Result<(), OutputManagerStorageError> {
+        self.db.write(WriteOperation::Insert(DbKeyValuePair::SpentOutput(
+            output.spending_key.clone(),
+            Box::new(output),
+        )))?;
+
+        Ok(())
+    }
+
+    /// Calculate the balance of the wallet. Unspent outputs that are not spendable at the given chain height count
+    /// towards the time-locked balance instead of the available balance.
+    pub fn get_balance(&self, chain_height: Option<u64>) -> Result<Balance, OutputManagerStorageError> {
         let pending_txs =
             self.db
                 .fetch(&DbKey::AllPendingTransactionOutputs)?
@@ -194,7 +388,10 @@ where T: OutputManagerBackend
 
         if let DbValue::UnspentOutputs(uo) = unspent_outputs {
             if let DbValue::AllPendingTransactionOutputs(pto) = pending_txs {
-                let available_balance = uo.iter().fold(MicroTari::from(0), |acc, x| acc + x.value);
+                let (spendable, time_locked): (Vec<&UnblindedOutput>, Vec<&UnblindedOutput>) =
+                    uo.iter().partition(|o| is_spendable(o, chain_height));
+                let available_balance = spendable.iter().fold(MicroTari::from(0), |acc, x| acc + x.value);
+                let time_locked_balance = time_locked.iter().fold(MicroTari::from(0), |acc, x| acc + x.value);
                 let mut pending_incoming = MicroTari::from(0);
                 let mut pending_outgoing = MicroTari::from(0);
 
@@ -213,6 +410,7 @@ where T: OutputManagerBackend
                     available_balance,
                     pending_incoming_balance: pending_incoming,
                     pending_outgoing_balance: pending_outgoing,
+                    time_locked_balance,
                 });
             }
         }
@@ -284,10 +482,10 @@ where T: OutputManagerBackend
         &mut self,
         tx_id: TxId,
         outputs_to_send: &Vec<UnblindedOutput>,
-        change_output: Option<UnblindedOutput>,
+        outputs_to_receive: Vec<UnblindedOutput>,
     ) -> Result<(), OutputManagerStorageError>
     {
-        self.db.encumber_outputs(tx_id, outputs_to_send, change_output)
+        self.db.encumber_outputs(tx_id, outputs_to_send, outputs_to_receive)
     }
 
     /// When a pending transaction is cancelled the encumbered outputs are moved back to the `unspent_outputs`
@@ -330,6 +528,146 @@ where T: OutputManagerBackend
         Ok(uo)
     }
 
+    pub fn fetch_invalid_outputs(&self) -> Result<Vec<UnblindedOutput>, OutputManagerStorageError> {
+        let uo = match self.db.fetch(&DbKey::InvalidOutputs) {
+            Ok(None) => log_error(
+                DbKey::InvalidOutputs,
+                OutputManagerStorageError::UnexpectedResult("Could not retrieve invalid outputs".to_string()),
+            ),
+            Ok(Some(DbValue::InvalidOutputs(uo))) => Ok(uo),
+            Ok(Some(other)) => unexpected_result(DbKey::InvalidOutputs, other),
+            Err(e) => log_error(DbKey::InvalidOutputs, e),
+        }?;
+        Ok(uo)
+    }
+
+    /// Move an unspent output that could not be found in the blockchain's UTXO set into the invalid outputs
+    /// collection so that it is no longer counted in the balance or selected to fund transactions.
+    pub fn invalidate_output(&mut self, output: &UnblindedOutput) -> Result<(), OutputManagerStorageError> {
+        self.db.invalidate_unspent_output(output)
+    }
+
+    /// Move an invalid output that has been found in the blockchain's UTXO set back into the unspent outputs
+    /// collection.
+    pub fn revalidate_output(&mut self, spending_key: &BlindingFactor) -> Result<(), OutputManagerStorageError> {
+        self.db.revalidate_output(spending_key)
+    }
+
+    /// Encrypt the stored secrets with the provided cipher, or decrypt them if no cipher is provided
+    pub fn apply_encryption(&mut self, cipher: Option<SecretCipher>) -> Result<(), OutputManagerStorageError> {
+        self.db.apply_encryption(cipher)
+    }
+
+    pub fn unlock(&mut self, cipher: SecretCipher) -> Result<(), OutputManagerStorageError> {
+        self.db.unlock(cipher)
+    }
+
+    pub fn lock(&mut self) -> Result<(), OutputManagerStorageError> {
+        self.db.lock()
+    }
+
+    /// The state of the wallet's watch-only mode, if the wallet is watch-only
+    pub fn get_watch_only_state(&self) -> Result<Option<WatchOnlyState>, OutputManagerStorageError> {
+        match self.db.fetch(&DbKey::WatchOnlyState) {
+            Ok(None) => Ok(None),
+            Ok(Some(DbValue::WatchOnlyState(s))) => Ok(Some(*s)),
+            Ok(Some(other)) => unexpected_result(DbKey::WatchOnlyState, other),
+            Err(e) => log_error(DbKey::WatchOnlyState, e),
+        }
+    }
+
+    pub fn set_watch_only_state(&mut self, state: WatchOnlyState) -> Result<(), OutputManagerStorageError> {
+        self.db
+            .write(WriteOperation::Insert(DbKeyValuePair::WatchOnlyState(Box::new(state))))?;
+        Ok(())
+    }
+
+    pub fn fetch_watched_outputs(&self) -> Result<Vec<WatchedOutput>, OutputManagerStorageError> {
+        match self.db.fetch(&DbKey::WatchedOutputs) {
+            Ok(None) => log_error(
+                DbKey::WatchedOutputs,
+                OutputManagerStorageError::UnexpectedResult("Could not retrieve watched outputs".to_string()),
+            ),
+            Ok(Some(DbValue::WatchedOutputs(wo))) => Ok(wo),
+            Ok(Some(other)) => unexpected_result(DbKey::WatchedOutputs, other),
+            Err(e) => log_error(DbKey::WatchedOutputs, e),
+        }
+    }
+
+    /// Store a watched output, replacing the stored output with the same commitment if there is one
+    pub fn save_watched_output(&mut self, output: WatchedOutput) -> Result<(), OutputManagerStorageError> {
+        self.db.write(WriteOperation::Insert(DbKeyValuePair::WatchedOutput(
+            output.commitment.clone(),
+            Box::new(output),
+        )))?;
+        Ok(())
+    }
+
+    /// Calculate the balance of a watch-only wallet from its watched outputs that have not been spent. Watched
+    /// outputs that are not spendable at the given chain height count towards the time-locked balance.
+    pub fn get_watch_only_balance(&self, chain_height: Option<u64>) -> Result<Balance, OutputManagerStorageError> {
+        let mut balance = Balance {
+            available_balance: MicroTari::from(0),
+            pending_incoming_balance: MicroTari::from(0),
+            pending_outgoing_balance: MicroTari::from(0),
+            time_locked_balance: MicroTari::from(0),
+        };
+        for output in self
+            .fetch_watched_outputs()?
+            .iter()
+            .filter(|o| o.spent_height.is_none())
+        {
+            if output.features.maturity <= chain_height.map(|h| h + 1).unwrap_or(0) {
+                balance.available_balance += output.value;
+            } else {
+                balance.time_locked_balance += output.value;
+            }
+        }
+        Ok(balance)
+    }
+
+    pub fn fetch_shared_outputs(&self) -> Result<Vec<SharedOutput>, OutputManagerStorageError> {
+        match self.db.fetch(&DbKey::SharedOutputs) {
+            Ok(None) => log_error(
+                DbKey::SharedOutputs,
+                OutputManagerStorageError::UnexpectedResult("Could not retrieve shared outputs".to_string()),
+            ),
+            Ok(Some(DbValue::SharedOutputs(so))) => Ok(so),
+            Ok(Some(other)) => unexpected_result(DbKey::SharedOutputs, other),
+            Err(e) => log_error(DbKey::SharedOutputs, e),
+        }
+    }
+
+    /// Store a shared output, replacing the stored output with the same id if there is one
+    pub fn save_shared_output(&mut self, output: SharedOutput) -> Result<(), OutputManagerStorageError> {
+        self.db.write(WriteOperation::Insert(DbKeyValuePair::SharedOutput(
+            output.output_id,
+            Box::new(output),
+        )))?;
+        Ok(())
+    }
+
+    pub fn fetch_atomic_swaps(&self) -> Result<Vec<AtomicSwap>, OutputManagerStorageError> {
+        match self.db.fetch(&DbKey::AtomicSwaps) {
+            Ok(None) => log_error(
+                DbKey::AtomicSwaps,
+                OutputManagerStorageError::UnexpectedResult("Could not retrieve atomic swaps".to_string()),
+            ),
+            Ok(Some(DbValue::AtomicSwaps(swaps))) => Ok(swaps),
+            Ok(Some(other)) => unexpected_result(DbKey::AtomicSwaps, other),
+            Err(e) => log_error(DbKey::AtomicSwaps, e),
+        }
+    }
+
+    /// Store an atomic swap, replacing the stored swap with the same id if there is one
+    pub fn save_atomic_swap(&mut self, swap: AtomicSwap) -> Result<(), OutputManagerStorageError> {
+        self.db.write(WriteOperation::Insert(DbKeyValuePair::AtomicSwap(
+            swap.swap_id,
+            Box::new(swap),
+        )))?;
+        Ok(())
+    }
+
     pub fn fetch_all_pending_transaction_outputs(
         &self,
     ) -> Result<HashMap<u64, PendingTransactionOutputs>, OutputManagerStorageError> {
@@ -364,8 +702,13 @@ impl Display for DbKey {
             },
             DbKey::UnspentOutputs => f.write_str(&format!("Unspent Outputs Key")),
             DbKey::SpentOutputs => f.write_str(&format!("Spent Outputs Key")),
+            DbKey::InvalidOutputs => f.write_str(&format!("Invalid Outputs Key")),
             DbKey::AllPendingTransactionOutputs => f.write_str(&format!("All Pending Transaction Outputs")),
             DbKey::KeyManagerState => f.write_str(&format!("Key Manager State")),
+            DbKey::WatchOnlyState => f.write_str(&format!("Watch-only State")),
+            DbKey::WatchedOutputs => f.write_str(&format!("Watched Outputs Key")),
+            DbKey::SharedOutputs => f.write_str(&format!("Shared Outputs Key")),
+            DbKey::AtomicSwaps => f.write_str(&format!("Atomic Swaps Key")),
         }
     }
 }
@@ -378,8 +721,13 @@ impl Display for DbValue {
             DbValue::PendingTransactionOutputs(_) => f.write_str("Pending Transaction Outputs"),
             DbValue::UnspentOutputs(_) => f.write_str("Unspent Outputs"),
             DbValue::SpentOutputs(_) => f.write_str("Spent Outputs"),
+            DbValue::InvalidOutputs(_) => f.write_str("Invalid Outputs"),
             DbValue::AllPendingTransactionOutputs(_) => f.write_str("All Pending Transaction Outputs"),
             DbValue::KeyManagerState(_) => f.write_str(&format!("Key Manager State")),
+            DbValue::WatchOnlyState(_) => f.write_str("Watch-only State"),
+            DbValue::WatchedOutputs(_) => f.write_str("Watched Outputs"),
+            DbValue::SharedOutputs(_) => f.write_str("Shared Outputs"),
+            DbValue::AtomicSwaps(_) => f.write_str("Atomic Swaps"),
         }
     }
 }
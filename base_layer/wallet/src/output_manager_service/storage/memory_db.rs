@@ -20,18 +20,25 @@
 // WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
 // USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
-use crate::output_manager_service::{
-    error::OutputManagerStorageError,
-    storage::database::{
-        DbKey,
-        DbKeyValuePair,
-        DbValue,
-        KeyManagerState,
-        OutputManagerBackend,
-        PendingTransactionOutputs,
-        WriteOperation,
+use crate::{
+    encryption::SecretCipher,
+    output_manager_service::{
+        error::OutputManagerStorageError,
+        storage::database::{
+            AtomicSwap,
+            DbKey,
+            DbKeyValuePair,
+            DbValue,
+            KeyManagerState,
+            OutputManagerBackend,
+            PendingTransactionOutputs,
+            SharedOutput,
+            WatchOnlyState,
+            WatchedOutput,
+            WriteOperation,
+        },
+        TxId,
     },
-    TxId,
 };
 use chrono::{Duration as ChronoDuration, Utc};
 use std::{
@@ -39,15 +46,20 @@ use std::{
     sync::{Arc, RwLock},
     time::Duration,
 };
-use tari_core::transactions::transaction::UnblindedOutput;
+use tari_core::transactions::{transaction::UnblindedOutput, types::BlindingFactor};
 
 /// This structure is an In-Memory database backend that implements the `OutputManagerBackend` trait and provides all
 /// the functionality required by the trait.
 pub struct InnerDatabase {
     unspent_outputs: Vec<UnblindedOutput>,
     spent_outputs: Vec<UnblindedOutput>,
+    invalid_outputs: Vec<UnblindedOutput>,
     pending_transactions: HashMap<TxId, PendingTransactionOutputs>,
     key_manager_state: Option<KeyManagerState>,
+    watch_only_state: Option<WatchOnlyState>,
+    watched_outputs: Vec<WatchedOutput>,
+    shared_outputs: Vec<SharedOutput>,
+    atomic_swaps: Vec<AtomicSwap>,
 }
 
 impl InnerDatabase {
@@ -55,8 +67,13 @@ impl InnerDatabase {
         Self {
             unspent_outputs: Vec::new(),
             spent_outputs: Vec::new(),
+            invalid_outputs: Vec::new(),
             pending_transactions: HashMap::new(),
             key_manager_state: None,
+            watch_only_state: None,
+            watched_outputs: Vec::new(),
+            shared_outputs: Vec::new(),
+            atomic_swaps: Vec::new(),
         }
     }
 }
@@ -94,6 +111,7 @@ impl OutputManagerBackend for OutputManagerMemoryDatabase {
                 .map(|v| DbValue::PendingTransactionOutputs(Box::new(v.clone()))),
             DbKey::UnspentOutputs => Some(DbValue::UnspentOutputs(db.unspent_outputs.clone())),
             DbKey::SpentOutputs => Some(DbValue::SpentOutputs(db.spent_outputs.clone())),
+            DbKey::InvalidOutputs => Some(DbValue::InvalidOutputs(db.invalid_outputs.clone())),
             DbKey::AllPendingTransactionOutputs => {
                 Some(DbValue::AllPendingTransactionOutputs(db.pending_transactions.clone()))
             },
@@ -101,6 +119,13 @@ impl OutputManagerBackend for OutputManagerMemoryDatabase {
                 .key_manager_state
                 .as_ref()
                 .map(|km| DbValue::KeyManagerState(km.clone())),
+            DbKey::WatchOnlyState => db
+                .watch_only_state
+                .as_ref()
+                .map(|s| DbValue::WatchOnlyState(Box::new(s.clone()))),
+            DbKey::WatchedOutputs => Some(DbValue::WatchedOutputs(db.watched_outputs.clone())),
+            DbKey::SharedOutputs => Some(DbValue::SharedOutputs(db.shared_outputs.clone())),
+            DbKey::AtomicSwaps => Some(DbValue::AtomicSwaps(db.atomic_swaps.clone())),
         };
 
         Ok(result)
@@ -112,7 +137,8 @@ impl OutputManagerBackend for OutputManagerMemoryDatabase {
             WriteOperation::Insert(kvp) => match kvp {
                 DbKeyValuePair::SpentOutput(k, o) => {
                     if db.spent_outputs.iter().any(|v| v.spending_key == k) ||
-                        db.unspent_outputs.iter().any(|v| v.spending_key == k)
+                        db.unspent_outputs.iter().any(|v| v.spending_key == k) ||
+                        db.invalid_outputs.iter().any(|v| v.spending_key == k)
                     {
                         return Err(OutputManagerStorageError::DuplicateOutput);
                     }
@@ -120,7 +146,8 @@ impl OutputManagerBackend for OutputManagerMemoryDatabase {
                 },
                 DbKeyValuePair::UnspentOutput(k, o) => {
                     if db.unspent_outputs.iter().any(|v| v.spending_key == k) ||
-                        db.spent_outputs.iter().any(|v| v.spending_key == k)
+                        db.spent_outputs.iter().any(|v| v.spending_key == k) ||
+                        db.invalid_outputs.iter().any(|v| v.spending_key == k)
                     {
                         return Err(OutputManagerStorageError::DuplicateOutput);
                     }
@@ -130,6 +157,21 @@ impl OutputManagerBackend for OutputManagerMemoryDatabase {
                     db.pending_transactions.insert(t, *p);
                 },
                 DbKeyValuePair::KeyManagerState(km) => db.key_manager_state = Some(km),
+                DbKeyValuePair::WatchOnlyState(s) => db.watch_only_state = Some(*s),
+                DbKeyValuePair::WatchedOutput(c, o) => {
+                    match db.watched_outputs.iter().position(|v| v.commitment == c) {
+                        None => db.watched_outputs.push(*o),
+                        Some(pos) => db.watched_outputs[pos] = *o,
+                    }
+                },
+                DbKeyValuePair::SharedOutput(id, o) => match db.shared_outputs.iter().position(|v| v.output_id == id) {
+                    None => db.shared_outputs.push(*o),
+                    Some(pos) => db.shared_outputs[pos] = *o,
+                },
+                DbKeyValuePair::AtomicSwap(id, s) => match db.atomic_swaps.iter().position(|v| v.swap_id == id) {
+                    None => db.atomic_swaps.push(*s),
+                    Some(pos) => db.atomic_swaps[pos] = *s,
+                },
             },
             WriteOperation::Remove(k) => match k {
                 DbKey::SpentOutput(k) => match db.spent_outputs.iter().position(|v| v.spending_key == k) {
@@ -155,8 +197,13 @@ impl OutputManagerBackend for OutputManagerMemoryDatabase {
                 },
                 DbKey::UnspentOutputs => return Err(OutputManagerStorageError::OperationNotSupported),
                 DbKey::SpentOutputs => return Err(OutputManagerStorageError::OperationNotSupported),
+                DbKey::InvalidOutputs => return Err(OutputManagerStorageError::OperationNotSupported),
                 DbKey::AllPendingTransactionOutputs => return Err(OutputManagerStorageError::OperationNotSupported),
                 DbKey::KeyManagerState => return Err(OutputManagerStorageError::OperationNotSupported),
+                DbKey::WatchOnlyState => return Err(OutputManagerStorageError::OperationNotSupported),
+                DbKey::WatchedOutputs => return Err(OutputManagerStorageError::OperationNotSupported),
+                DbKey::SharedOutputs => return Err(OutputManagerStorageError::OperationNotSupported),
+                DbKey::AtomicSwaps => return Err(OutputManagerStorageError::OperationNotSupported),
             },
         }
         Ok(None)
@@ -188,7 +235,7 @@ impl OutputManagerBackend for OutputManagerMemoryDatabase {
         &mut self,
         tx_id: TxId,
         outputs_to_send: &Vec<UnblindedOutput>,
-        change_output: Option<UnblindedOutput>,
+        outputs_to_receive: Vec<UnblindedOutput>,
     ) -> Result<(), OutputManagerStorageError>
     {
         let mut db = acquire_write_lock!(self.db);
@@ -201,17 +248,13 @@ impl OutputManagerBackend for OutputManagerMemoryDatabase {
             }
         }
 
-        let mut pending_transaction = PendingTransactionOutputs {
+        let pending_transaction = PendingTransactionOutputs {
             tx_id: tx_id.clone(),
             outputs_to_be_spent,
-            outputs_to_be_received: Vec::new(),
+            outputs_to_be_received: outputs_to_receive,
             timestamp: Utc::now().naive_utc(),
         };
 
-        if let Some(co) = change_output {
-            pending_transaction.outputs_to_be_received.push(co);
-        }
-
         db.pending_transactions.insert(tx_id, pending_transaction);
 
         Ok(())
@@ -261,4 +304,47 @@ impl OutputManagerBackend for OutputManagerMemoryDatabase {
 
         Ok(())
     }
+
+    fn invalidate_unspent_output(&mut self, output: &UnblindedOutput) -> Result<(), OutputManagerStorageError> {
+        let mut db = acquire_write_lock!(self.db);
+        match db
+            .unspent_outputs
+            .iter()
+            .position(|v| v.spending_key == output.spending_key)
+        {
+            None => Err(OutputManagerStorageError::ValueNotFound(DbKey::UnspentOutput(
+                output.spending_key.clone(),
+            ))),
+            Some(pos) => {
+                let invalid_output = db.unspent_outputs.remove(pos);
+                db.invalid_outputs.push(invalid_output);
+                Ok(())
+            },
+        }
+    }
+
+    fn revalidate_output(&mut self, spending_key: &BlindingFactor) -> Result<(), OutputManagerStorageError> {
+        let mut db = acquire_write_lock!(self.db);
+        match db.invalid_outputs.iter().position(|v| &v.spending_key == spending_key) {
+            None => Err(OutputManagerStorageError::ValuesNotFound),
+            Some(pos) => {
+                let output = db.invalid_outputs.remove(pos);
+                db.unspent_outputs.push(output);
+                Ok(())
+            },
+        }
+    }
+
+    // Nothing is persisted by this backend so there are no stored secrets to encrypt
+    fn apply_encryption(&mut self, _cipher: Option<SecretCipher>) -> Result<(), OutputManagerStorageError> {
+        Ok(())
+    }
+
+    fn unlock(&mut self, _cipher: SecretCipher) -> Result<(), OutputManagerStorageError> {
+        Ok(())
+    }
+
+    fn lock(&mut self) -> Result<(), OutputManagerStorageError> {
+        Ok(())
+    }
 }
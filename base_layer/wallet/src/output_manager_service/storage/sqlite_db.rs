@@ -21,20 +21,38 @@
 // USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
 use crate::{
+    encryption::{EncryptionState, SecretCipher},
+    error::EncryptionError,
     output_manager_service::{
         error::OutputManagerStorageError,
         storage::database::{
+            AtomicSwap,
+            AtomicSwapRole,
+            AtomicSwapStatus,
             DbKey,
             DbKeyValuePair,
             DbValue,
             KeyManagerState,
             OutputManagerBackend,
             PendingTransactionOutputs,
+            SharedOutput,
+            SharedOutputStatus,
+            ViewKey,
+            WatchOnlyState,
+            WatchedOutput,
             WriteOperation,
         },
         TxId,
     },
-    schema::{key_manager_states, outputs, pending_transaction_outputs},
+    schema::{
+        atomic_swaps,
+        key_manager_states,
+        outputs,
+        pending_transaction_outputs,
+        shared_outputs,
+        watch_only_states,
+        watched_outputs,
+    },
 };
 use chrono::{Duration as ChronoDuration, NaiveDateTime, Utc};
 use diesel::{
@@ -43,34 +61,43 @@ use diesel::{
     result::Error as DieselError,
     SqliteConnection,
 };
-use std::{collections::HashMap, convert::TryFrom, io, path::Path, time::Duration};
+use std::{
+    collections::HashMap,
+    convert::TryFrom,
+    io,
+    sync::{Arc, RwLock},
+    time::Duration,
+};
 use tari_core::transactions::{
     tari_amount::MicroTari,
     transaction::{OutputFeatures, OutputFlags, UnblindedOutput},
-    types::PrivateKey,
+    types::{Commitment, PrivateKey, PublicKey},
 };
-use tari_utilities::ByteArray;
+use tari_crypto::keys::PublicKey as PublicKeyTrait;
+use tari_utilities::{ByteArray, ByteArrayError};
 
 const DATABASE_CONNECTION_TIMEOUT_MS: u64 = 2000;
+/// The length of a secret key in plain form. Encrypted secrets are longer as they include a nonce and a tag.
+const PLAIN_SECRET_LENGTH: usize = 32;
 
 /// A Sqlite backend for the Output Manager Service. The Backend is accessed via a connection pool to the Sqlite file.
+/// The spending keys of the outputs and the master seed of the key manager are stored encrypted if encryption has
+/// been applied, in which case the backend must be unlocked before they can be read or written.
 #[derive(Clone)]
 pub struct OutputManagerSqliteDatabase {
     database_connection_pool: Pool<ConnectionManager<SqliteConnection>>,
+    encryption: Arc<RwLock<EncryptionState>>,
 }
 impl OutputManagerSqliteDatabase {
     pub fn new(database_path: String) -> Result<Self, OutputManagerStorageError> {
-        let db_exists = Path::new(&database_path).exists();
-
         let connection = SqliteConnection::establish(&database_path)?;
 
         connection.execute("PRAGMA foreign_keys = ON")?;
-        if !db_exists {
-            embed_migrations!("./migrations");
-            embedded_migrations::run_with_output(&connection, &mut io::stdout()).map_err(|err| {
-                OutputManagerStorageError::DatabaseMigrationError(format!("Database migration failed {}", err))
-            })?;
-        }
+        // Pending migrations are also applied to existing databases so that their outputs table has the `valid` column
+        embed_migrations!("./migrations");
+        embedded_migrations::run_with_output(&connection, &mut io::stdout()).map_err(|err| {
+            OutputManagerStorageError::DatabaseMigrationError(format!("Database migration failed {}", err))
+        })?;
         drop(connection);
 
         let manager = ConnectionManager::<SqliteConnection>::new(database_path);
@@ -80,8 +107,17 @@ impl OutputManagerSqliteDatabase {
             .build(manager)
             .map_err(|_| OutputManagerStorageError::R2d2Error)?;
 
+        // A database whose master seed is not in plain form has been encrypted and starts out locked
+        let conn = pool.get().map_err(|_| OutputManagerStorageError::R2d2Error)?;
+        let encryption = match KeyManagerStateSql::get_state(&conn) {
+            Ok(km) if km.master_seed.len() != PLAIN_SECRET_LENGTH => EncryptionState::Locked,
+            _ => EncryptionState::Unencrypted,
+        };
+        drop(conn);
+
         Ok(Self {
             database_connection_pool: pool,
+            encryption: Arc::new(RwLock::new(encryption)),
         })
     }
 }
@@ -92,10 +128,14 @@ impl OutputManagerBackend for OutputManagerSqliteDatabase {
             .clone()
             .get()
             .map_err(|_| OutputManagerStorageError::R2d2Error)?;
+        let encryption = acquire_read_lock!(self.encryption);
 
         let result = match key {
-            DbKey::SpentOutput(k) => match OutputSql::find_spent(&k.to_vec(), true, &conn) {
-                Ok(o) => Some(DbValue::SpentOutput(Box::new(UnblindedOutput::try_from(o)?))),
+            DbKey::SpentOutput(k) => match OutputSql::find_spent(&encryption.encrypt_secret(&k.to_vec())?, true, &conn)
+            {
+                Ok(o) => Some(DbValue::SpentOutput(Box::new(UnblindedOutput::try_from(
+                    o.decrypt(&encryption)?,
+                )?))),
                 Err(e) => {
                     match e {
                         OutputManagerStorageError::DieselError(DieselError::NotFound) => (),
@@ -104,21 +144,30 @@ impl OutputManagerBackend for OutputManagerSqliteDatabase {
                     None
                 },
             },
-            DbKey::UnspentOutput(k) => match OutputSql::find_spent(&k.to_vec(), false, &conn) {
-                Ok(o) => Some(DbValue::UnspentOutput(Box::new(UnblindedOutput::try_from(o)?))),
-                Err(e) => {
-                    match e {
-                        OutputManagerStorageError::DieselError(DieselError::NotFound) => (),
-                        e => return Err(e),
-                    };
-                    None
-                },
+            DbKey::UnspentOutput(k) => {
+                match OutputSql::find_spent(&encryption.encrypt_secret(&k.to_vec())?, false, &conn) {
+                    Ok(o) => Some(DbValue::UnspentOutput(Box::new(UnblindedOutput::try_from(
+                        o.decrypt(&encryption)?,
+                    )?))),
+                    Err(e) => {
+                        match e {
+                            OutputManagerStorageError::DieselError(DieselError::NotFound) => (),
+                            e => return Err(e),
+                        };
+                        None
+                    },
+                }
             },
             DbKey::PendingTransactionOutputs(tx_id) => match PendingTransactionOutputSql::find(tx_id, &conn) {
                 Ok(p) => {
                     let outputs = OutputSql::find_by_tx_id_and_encumbered(tx_id, &conn)?;
                     Some(DbValue::PendingTransactionOutputs(Box::new(
-                        pending_transaction_outputs_from_sql_outputs(&(p.tx_id.clone() as u64), &p.timestamp, outputs)?,
+                        pending_transaction_outputs_from_sql_outputs(
+                            &(p.tx_id.clone() as u64),
+                            &p.timestamp,
+                            outputs,
+                            &encryption,
+                        )?,
                     )))
                 },
                 Err(e) => {
@@ -132,13 +181,19 @@ impl OutputManagerBackend for OutputManagerSqliteDatabase {
             DbKey::UnspentOutputs => Some(DbValue::UnspentOutputs(
                 OutputSql::index_spent(false, &conn)?
                     .iter()
-                    .map(|o| UnblindedOutput::try_from(o.clone()))
+                    .map(|o| o.clone().decrypt(&encryption).and_then(UnblindedOutput::try_from))
                     .collect::<Result<Vec<_>, _>>()?,
             )),
             DbKey::SpentOutputs => Some(DbValue::SpentOutputs(
                 OutputSql::index_spent(true, &conn)?
                     .iter()
-                    .map(|o| UnblindedOutput::try_from(o.clone()))
+                    .map(|o| o.clone().decrypt(&encryption).and_then(UnblindedOutput::try_from))
+                    .collect::<Result<Vec<_>, _>>()?,
+            )),
+            DbKey::InvalidOutputs => Some(DbValue::InvalidOutputs(
+                OutputSql::index_invalid(&conn)?
+                    .iter()
+                    .map(|o| o.clone().decrypt(&encryption).and_then(UnblindedOutput::try_from))
                     .collect::<Result<Vec<_>, _>>()?,
             )),
             DbKey::AllPendingTransactionOutputs => {
@@ -152,6 +207,7 @@ impl OutputManagerBackend for OutputManagerSqliteDatabase {
                             &(p_tx.tx_id.clone() as u64),
                             &p_tx.timestamp,
                             outputs,
+                            &encryption,
                         )?,
                     );
                 }
@@ -159,8 +215,32 @@ impl OutputManagerBackend for OutputManagerSqliteDatabase {
             },
             DbKey::KeyManagerState => match KeyManagerStateSql::get_state(&conn).ok() {
                 None => None,
-                Some(km) => Some(DbValue::KeyManagerState(KeyManagerState::try_from(km)?)),
+                Some(km) => Some(DbValue::KeyManagerState(KeyManagerState::try_from(
+                    km.decrypt(&encryption)?,
+                )?)),
+            },
+            DbKey::WatchOnlyState => match WatchOnlyStateSql::get_state(&conn)? {
+                None => None,
+                Some(s) => Some(DbValue::WatchOnlyState(Box::new(WatchOnlyState::try_from(s)?))),
             },
+            DbKey::WatchedOutputs => Some(DbValue::WatchedOutputs(
+                WatchedOutputSql::index(&conn)?
+                    .into_iter()
+                    .map(WatchedOutput::try_from)
+                    .collect::<Result<Vec<_>, _>>()?,
+            )),
+            DbKey::SharedOutputs => Some(DbValue::SharedOutputs(
+                SharedOutputSql::index(&conn)?
+                    .into_iter()
+                    .map(|o| o.decrypt(&encryption).and_then(SharedOutput::try_from))
+                    .collect::<Result<Vec<_>, _>>()?,
+            )),
+            DbKey::AtomicSwaps => Some(DbValue::AtomicSwaps(
+                AtomicSwapSql::index(&conn)?
+                    .into_iter()
+                    .map(|s| s.decrypt(&encryption).and_then(AtomicSwap::try_from))
+                    .collect::<Result<Vec<_>, _>>()?,
+            )),
         };
 
         Ok(result)
@@ -172,20 +252,25 @@ impl OutputManagerBackend for OutputManagerSqliteDatabase {
             .clone()
             .get()
             .map_err(|_| OutputManagerStorageError::R2d2Error)?;
+        let encryption = acquire_read_lock!(self.encryption);
 
         match op {
             WriteOperation::Insert(kvp) => match kvp {
                 DbKeyValuePair::SpentOutput(k, o) => {
-                    if let Ok(_) = OutputSql::find(&k.to_vec(), &conn) {
+                    if let Ok(_) = OutputSql::find(&encryption.encrypt_secret(&k.to_vec())?, &conn) {
                         return Err(OutputManagerStorageError::DuplicateOutput);
                     }
-                    OutputSql::new(*o, true, false, false, None).commit(&conn)?
+                    OutputSql::new(*o, true, false, false, None)
+                        .encrypt(&encryption)?
+                        .commit(&conn)?
                 },
                 DbKeyValuePair::UnspentOutput(k, o) => {
-                    if let Ok(_) = OutputSql::find(&k.to_vec(), &conn) {
+                    if let Ok(_) = OutputSql::find(&encryption.encrypt_secret(&k.to_vec())?, &conn) {
                         return Err(OutputManagerStorageError::DuplicateOutput);
                     }
-                    OutputSql::new(*o, false, false, false, None).commit(&conn)?
+                    OutputSql::new(*o, false, false, false, None)
+                        .encrypt(&encryption)?
+                        .commit(&conn)?
                 },
                 DbKeyValuePair::PendingTransactionOutputs(tx_id, p) => {
                     if let Ok(_) = PendingTransactionOutputSql::find(&tx_id, &conn) {
@@ -193,38 +278,56 @@ impl OutputManagerBackend for OutputManagerSqliteDatabase {
                     }
                     PendingTransactionOutputSql::new(p.tx_id.clone(), p.timestamp.clone()).commit(&conn)?;
                     for o in p.outputs_to_be_spent {
-                        OutputSql::new(o.clone(), false, false, true, Some(p.tx_id.clone())).commit(&conn)?;
+                        OutputSql::new(o.clone(), false, false, true, Some(p.tx_id.clone()))
+                            .encrypt(&encryption)?
+                            .commit(&conn)?;
                     }
                     for o in p.outputs_to_be_received {
-                        OutputSql::new(o.clone(), false, true, true, Some(p.tx_id.clone())).commit(&conn)?;
+                        OutputSql::new(o.clone(), false, true, true, Some(p.tx_id.clone()))
+                            .encrypt(&encryption)?
+                            .commit(&conn)?;
                     }
                 },
-                DbKeyValuePair::KeyManagerState(km) => KeyManagerStateSql::set_state(km, &conn)?,
+                DbKeyValuePair::KeyManagerState(km) => {
+                    KeyManagerStateSql::set_state(KeyManagerStateSql::from(km).encrypt(&encryption)?, &conn)?
+                },
+                DbKeyValuePair::WatchOnlyState(s) => WatchOnlyStateSql::set_state(WatchOnlyStateSql::from(*s), &conn)?,
+                DbKeyValuePair::WatchedOutput(_, o) => WatchedOutputSql::from(*o).commit(&conn)?,
+                DbKeyValuePair::SharedOutput(_, o) => SharedOutputSql::from(*o).encrypt(&encryption)?.commit(&conn)?,
+                DbKeyValuePair::AtomicSwap(_, s) => AtomicSwapSql::from(*s).encrypt(&encryption)?.commit(&conn)?,
             },
             WriteOperation::Remove(k) => match k {
-                DbKey::SpentOutput(s) => match OutputSql::find_spent(&s.to_vec(), true, &conn) {
-                    Ok(o) => {
-                        o.clone().delete(&conn)?;
-                        return Ok(Some(DbValue::SpentOutput(Box::new(UnblindedOutput::try_from(o)?))));
-                    },
-                    Err(e) => {
-                        match e {
-                            OutputManagerStorageError::DieselError(DieselError::NotFound) => (),
-                            e => return Err(e),
-                        };
-                    },
+                DbKey::SpentOutput(s) => {
+                    match OutputSql::find_spent(&encryption.encrypt_secret(&s.to_vec())?, true, &conn) {
+                        Ok(o) => {
+                            o.clone().delete(&conn)?;
+                            return Ok(Some(DbValue::SpentOutput(Box::new(UnblindedOutput::try_from(
+                                o.decrypt(&encryption)?,
+                            )?))));
+                        },
+                        Err(e) => {
+                            match e {
+                                OutputManagerStorageError::DieselError(DieselError::NotFound) => (),
+                                e => return Err(e),
+                            };
+                        },
+                    }
                 },
-                DbKey::UnspentOutput(k) => match OutputSql::find_spent(&k.to_vec(), false, &conn) {
-                    Ok(o) => {
-                        o.clone().delete(&conn)?;
-                        return Ok(Some(DbValue::SpentOutput(Box::new(UnblindedOutput::try_from(o)?))));
-                    },
-                    Err(e) => {
-                        match e {
-                            OutputManagerStorageError::DieselError(DieselError::NotFound) => (),
-                            e => return Err(e),
-                        };
-                    },
+                DbKey::UnspentOutput(k) => {
+                    match OutputSql::find_spent(&encryption.encrypt_secret(&k.to_vec())?, false, &conn) {
+                        Ok(o) => {
+                            o.clone().delete(&conn)?;
+                            return Ok(Some(DbValue::SpentOutput(Box::new(UnblindedOutput::try_from(
+                                o.decrypt(&encryption)?,
+                            )?))));
+                        },
+                        Err(e) => {
+                            match e {
+                                OutputManagerStorageError::DieselError(DieselError::NotFound) => (),
+                                e => return Err(e),
+                            };
+                        },
+                    }
                 },
                 DbKey::PendingTransactionOutputs(tx_id) => match PendingTransactionOutputSql::find(&tx_id, &conn) {
                     Ok(p) => {
@@ -235,6 +338,7 @@ impl OutputManagerBackend for OutputManagerSqliteDatabase {
                                 &(p.tx_id.clone() as u64),
                                 &p.timestamp,
                                 outputs,
+                                &encryption,
                             )?,
                         ))));
                     },
@@ -247,8 +351,13 @@ impl OutputManagerBackend for OutputManagerSqliteDatabase {
                 },
                 DbKey::UnspentOutputs => return Err(OutputManagerStorageError::OperationNotSupported),
                 DbKey::SpentOutputs => return Err(OutputManagerStorageError::OperationNotSupported),
+                DbKey::InvalidOutputs => return Err(OutputManagerStorageError::OperationNotSupported),
                 DbKey::AllPendingTransactionOutputs => return Err(OutputManagerStorageError::OperationNotSupported),
                 DbKey::KeyManagerState => return Err(OutputManagerStorageError::OperationNotSupported),
+                DbKey::WatchOnlyState => return Err(OutputManagerStorageError::OperationNotSupported),
+                DbKey::WatchedOutputs => return Err(OutputManagerStorageError::OperationNotSupported),
+                DbKey::SharedOutputs => return Err(OutputManagerStorageError::OperationNotSupported),
+                DbKey::AtomicSwaps => return Err(OutputManagerStorageError::OperationNotSupported),
             },
         }
 
@@ -274,6 +383,7 @@ impl OutputManagerBackend for OutputManagerSqliteDatabase {
                                 received: None,
                                 encumbered: Some(false),
                                 tx_id: None,
+                                valid: None,
                             },
                             &conn,
                         )?;
@@ -284,6 +394,7 @@ impl OutputManagerBackend for OutputManagerSqliteDatabase {
                                 received: None,
                                 encumbered: Some(false),
                                 tx_id: None,
+                                valid: None,
                             },
                             &conn,
                         )?;
@@ -307,7 +418,7 @@ impl OutputManagerBackend for OutputManagerSqliteDatabase {
         &mut self,
         tx_id: u64,
         outputs_to_send: &Vec<UnblindedOutput>,
-        change_output: Option<UnblindedOutput>,
+        outputs_to_receive: Vec<UnblindedOutput>,
     ) -> Result<(), OutputManagerStorageError>
     {
         let conn = self
@@ -315,13 +426,17 @@ impl OutputManagerBackend for OutputManagerSqliteDatabase {
             .clone()
             .get()
             .map_err(|_| OutputManagerStorageError::R2d2Error)?;
+        let encryption = acquire_read_lock!(self.encryption);
 
         let mut outputs_to_be_spent = Vec::new();
         for i in outputs_to_send {
-            let output = OutputSql::find(&i.spending_key.to_vec(), &conn)?;
+            let output = OutputSql::find(&encryption.encrypt_secret(&i.spending_key.to_vec())?, &conn)?;
             if output.spent == 1 {
                 return Err(OutputManagerStorageError::OutputAlreadySpent);
             }
+            if output.valid == 0 {
+                return Err(OutputManagerStorageError::ValuesNotFound);
+            }
             outputs_to_be_spent.push(output);
         }
 
@@ -334,13 +449,16 @@ impl OutputManagerBackend for OutputManagerSqliteDatabase {
                     received: None,
                     encumbered: Some(true),
                     tx_id: Some(tx_id.clone()),
+                    valid: None,
                 },
                 &conn,
             )?;
         }
 
-        if let Some(co) = change_output {
-            OutputSql::new(co, false, true, true, Some(tx_id.clone())).commit(&conn)?;
+        for o in outputs_to_receive {
+            OutputSql::new(o, false, true, true, Some(tx_id.clone()))
+                .encrypt(&encryption)?
+                .commit(&conn)?;
         }
 
         Ok(())
@@ -367,6 +485,7 @@ impl OutputManagerBackend for OutputManagerSqliteDatabase {
                                 received: None,
                                 encumbered: Some(false),
                                 tx_id: None,
+                                valid: None,
                             },
                             &conn,
                         )?;
@@ -420,6 +539,137 @@ impl OutputManagerBackend for OutputManagerSqliteDatabase {
 
         Ok(())
     }
+
+    fn invalidate_unspent_output(&mut self, output: &UnblindedOutput) -> Result<(), OutputManagerStorageError> {
+        let conn = self
+            .database_connection_pool
+            .clone()
+            .get()
+            .map_err(|_| OutputManagerStorageError::R2d2Error)?;
+
+        let encrypted_spending_key =
+            acquire_read_lock!(self.encryption).encrypt_secret(&output.spending_key.to_vec())?;
+        let output_sql = match OutputSql::find_spent(&encrypted_spending_key, false, &conn) {
+            Ok(o) => o,
+            Err(e) => {
+                match e {
+                    OutputManagerStorageError::DieselError(DieselError::NotFound) => {
+                        return Err(OutputManagerStorageError::ValueNotFound(DbKey::UnspentOutput(
+                            output.spending_key.clone(),
+                        )))
+                    },
+                    e => return Err(e),
+                };
+            },
+        };
+        if output_sql.encumbered == 1 {
+            return Err(OutputManagerStorageError::ValueNotFound(DbKey::UnspentOutput(
+                output.spending_key.clone(),
+            )));
+        }
+        output_sql.update(
+            UpdateOutput {
+                spent: None,
+                received: None,
+                encumbered: None,
+                tx_id: None,
+                valid: Some(false),
+            },
+            &conn,
+        )?;
+
+        Ok(())
+    }
+
+    fn revalidate_output(&mut self, spending_key: &PrivateKey) -> Result<(), OutputManagerStorageError> {
+        let conn = self
+            .database_connection_pool
+            .clone()
+            .get()
+            .map_err(|_| OutputManagerStorageError::R2d2Error)?;
+
+        let encrypted_spending_key = acquire_read_lock!(self.encryption).encrypt_secret(&spending_key.to_vec())?;
+        let output = OutputSql::find(&encrypted_spending_key, &conn)?;
+        if output.valid == 1 {
+            return Err(OutputManagerStorageError::ValuesNotFound);
+        }
+        output.update(
+            UpdateOutput {
+                spent: None,
+                received: None,
+                encumbered: None,
+                tx_id: None,
+                valid: Some(true),
+            },
+            &conn,
+        )?;
+
+        Ok(())
+    }
+
+    fn apply_encryption(&mut self, cipher: Option<SecretCipher>) -> Result<(), OutputManagerStorageError> {
+        let conn = self
+            .database_connection_pool
+            .clone()
+            .get()
+            .map_err(|_| OutputManagerStorageError::R2d2Error)?;
+        let mut encryption = acquire_write_lock!(self.encryption);
+        let new_encryption = encryption.with_cipher(cipher)?;
+
+        conn.transaction::<_, OutputManagerStorageError, _>(|| {
+            for o in outputs::table.load::<OutputSql>(&conn)? {
+                let spending_key = new_encryption.encrypt_secret(&encryption.decrypt_secret(&o.spending_key)?)?;
+                diesel::update(outputs::table.filter(outputs::spending_key.eq(&o.spending_key)))
+                    .set(outputs::spending_key.eq(spending_key))
+                    .execute(&conn)?;
+            }
+            for o in SharedOutputSql::index(&conn)? {
+                o.decrypt(&encryption)?.encrypt(&new_encryption)?.commit(&conn)?;
+            }
+            for s in AtomicSwapSql::index(&conn)? {
+                s.decrypt(&encryption)?.encrypt(&new_encryption)?.commit(&conn)?;
+            }
+            if let Ok(km) = KeyManagerStateSql::get_state(&conn) {
+                KeyManagerStateSql::set_state(km.decrypt(&encryption)?.encrypt(&new_encryption)?, &conn)?;
+            }
+            Ok(())
+        })?;
+
+        *encryption = new_encryption;
+        Ok(())
+    }
+
+    fn unlock(&mut self, cipher: SecretCipher) -> Result<(), OutputManagerStorageError> {
+        let conn = self
+            .database_connection_pool
+            .clone()
+            .get()
+            .map_err(|_| OutputManagerStorageError::R2d2Error)?;
+        let mut encryption = acquire_write_lock!(self.encryption);
+        if let EncryptionState::Unencrypted = *encryption {
+            return Err(OutputManagerStorageError::EncryptionError(
+                EncryptionError::NotEncrypted,
+            ));
+        }
+
+        // The master seed can only be decrypted with the cipher it was encrypted with
+        let unlocked = EncryptionState::Unlocked(cipher);
+        if let Ok(km) = KeyManagerStateSql::get_state(&conn) {
+            if km.decrypt(&unlocked).is_err() {
+                return Err(OutputManagerStorageError::EncryptionError(
+                    EncryptionError::IncorrectPassphrase,
+                ));
+            }
+        }
+
+        *encryption = unlocked;
+        Ok(())
+    }
+
+    fn lock(&mut self) -> Result<(), OutputManagerStorageError> {
+        acquire_write_lock!(self.encryption).lock()?;
+        Ok(())
+    }
 }
 
 /// A utility function to construct a PendingTransactionOutputs structure for a TxId, set of Outputs and a Timestamp
@@ -427,15 +677,16 @@ fn pending_transaction_outputs_from_sql_outputs(
     tx_id: &TxId,
     timestamp: &NaiveDateTime,
     outputs: Vec<OutputSql>,
+    encryption: &EncryptionState,
 ) -> Result<PendingTransactionOutputs, OutputManagerStorageError>
 {
     let mut outputs_to_be_spent = Vec::new();
     let mut outputs_to_be_received = Vec::new();
     for o in outputs {
         if o.to_be_received == 1i32 {
-            outputs_to_be_received.push(UnblindedOutput::try_from(o.clone())?);
+            outputs_to_be_received.push(UnblindedOutput::try_from(o.clone().decrypt(encryption)?)?);
         } else if o.to_be_received == 0i32 {
-            outputs_to_be_spent.push(UnblindedOutput::try_from(o.clone())?);
+            outputs_to_be_spent.push(UnblindedOutput::try_from(o.clone().decrypt(encryption)?)?);
         }
     }
 
@@ -460,6 +711,7 @@ struct OutputSql {
     to_be_received: i32,
     encumbered: i32,
     tx_id: Option<i64>,
+    valid: i32,
 }
 
 impl OutputSql {
@@ -480,9 +732,22 @@ impl OutputSql {
             to_be_received: to_be_received as i32,
             encumbered: encumbered as i32,
             tx_id: tx_id.map(|i| i as i64),
+            valid: true as i32,
         }
     }
 
+    /// Convert the spending key to the form in which it is stored
+    pub fn encrypt(mut self, encryption: &EncryptionState) -> Result<Self, OutputManagerStorageError> {
+        self.spending_key = encryption.encrypt_secret(&self.spending_key)?;
+        Ok(self)
+    }
+
+    /// Convert the stored spending key back to its plain form
+    pub fn decrypt(mut self, encryption: &EncryptionState) -> Result<Self, OutputManagerStorageError> {
+        self.spending_key = encryption.decrypt_secret(&self.spending_key)?;
+        Ok(self)
+    }
+
     /// Write this struct to the database
     pub fn commit(
         &self,
@@ -503,7 +768,7 @@ impl OutputSql {
             .load::<OutputSql>(conn)?)
     }
 
-    /// Return all unencumbered outputs with the specified spent status
+    /// Return all valid unencumbered outputs with the specified spent status
     pub fn index_spent(
         spent: bool,
         conn: &PooledConnection<ConnectionManager<SqliteConnection>>,
@@ -512,6 +777,17 @@ impl OutputSql {
         Ok(outputs::table
             .filter(outputs::encumbered.eq(false as i32))
             .filter(outputs::spent.eq(spent as i32))
+            .filter(outputs::valid.eq(true as i32))
+            .load(conn)?)
+    }
+
+    /// Return all the outputs that have been marked as invalid
+    pub fn index_invalid(
+        conn: &PooledConnection<ConnectionManager<SqliteConnection>>,
+    ) -> Result<Vec<OutputSql>, OutputManagerStorageError> {
+        Ok(outputs::table
+            .filter(outputs::encumbered.eq(false as i32))
+            .filter(outputs::valid.eq(false as i32))
             .load(conn)?)
     }
 
@@ -538,7 +814,7 @@ impl OutputSql {
             .load(conn)?)
     }
 
-    /// Find a particular Output, if it exists and is in the specified Spent state
+    /// Find a particular valid Output, if it exists and is in the specified Spent state
     pub fn find_spent(
         spending_key: &Vec<u8>,
         spent: bool,
@@ -547,6 +823,7 @@ impl OutputSql {
     {
         Ok(outputs::table
             .filter(outputs::spent.eq(spent as i32))
+            .filter(outputs::valid.eq(true as i32))
             .filter(outputs::spending_key.eq(spending_key))
             .first::<OutputSql>(conn)?)
     }
@@ -618,6 +895,7 @@ impl TryFrom<OutputSql> for UnblindedOutput {
             features: OutputFeatures {
                 flags: OutputFlags::from_bits(o.flags as u8).ok_or(OutputManagerStorageError::ConversionError)?,
                 maturity: o.maturity as u64,
+                hash_lock: None,
             },
         })
     }
@@ -629,6 +907,7 @@ pub struct UpdateOutput {
     received: Option<bool>,
     encumbered: Option<bool>,
     tx_id: Option<TxId>,
+    valid: Option<bool>,
 }
 
 #[derive(AsChangeset)]
@@ -638,6 +917,7 @@ pub struct UpdateOutputSql {
     to_be_received: Option<i32>,
     encumbered: Option<i32>,
     tx_id: Option<i64>,
+    valid: Option<i32>,
 }
 
 #[derive(AsChangeset)]
@@ -656,6 +936,7 @@ impl From<UpdateOutput> for UpdateOutputSql {
             to_be_received: u.received.map(|r| r as i32),
             encumbered: u.encumbered.map(|e| e as i32),
             tx_id: u.tx_id.map(|t| t as i64),
+            valid: u.valid.map(|v| v as i32),
         }
     }
 }
@@ -772,6 +1053,18 @@ impl TryFrom<KeyManagerStateSql> for KeyManagerState {
 }
 
 impl KeyManagerStateSql {
+    /// Convert the master seed to the form in which it is stored
+    pub fn encrypt(mut self, encryption: &EncryptionState) -> Result<Self, OutputManagerStorageError> {
+        self.master_seed = encryption.encrypt_secret(&self.master_seed)?;
+        Ok(self)
+    }
+
+    /// Convert the stored master seed back to its plain form
+    pub fn decrypt(mut self, encryption: &EncryptionState) -> Result<Self, OutputManagerStorageError> {
+        self.master_seed = encryption.decrypt_secret(&self.master_seed)?;
+        Ok(self)
+    }
+
     fn commit(
         &self,
         conn: &PooledConnection<ConnectionManager<SqliteConnection>>,
@@ -792,20 +1085,20 @@ impl KeyManagerStateSql {
     }
 
     pub fn set_state(
-        key_manager_state: KeyManagerState,
+        key_manager_state: KeyManagerStateSql,
         conn: &PooledConnection<ConnectionManager<SqliteConnection>>,
     ) -> Result<(), OutputManagerStorageError>
     {
         match KeyManagerStateSql::get_state(conn) {
             Ok(km) => {
-                let update = KeyManagerStateUpdate {
+                let update = KeyManagerStateUpdateSql {
                     master_seed: Some(key_manager_state.master_seed),
                     branch_seed: Some(key_manager_state.branch_seed),
                     primary_key_index: Some(key_manager_state.primary_key_index),
                 };
 
                 let num_updated = diesel::update(key_manager_states::table.filter(key_manager_states::id.eq(&km.id)))
-                    .set(KeyManagerStateUpdateSql::from(update))
+                    .set(update)
                     .execute(conn)?;
                 if num_updated == 0 {
                     return Err(OutputManagerStorageError::UnexpectedResult(
@@ -813,7 +1106,7 @@ impl KeyManagerStateSql {
                     ));
                 }
             },
-            Err(_) => KeyManagerStateSql::from(key_manager_state).commit(conn)?,
+            Err(_) => key_manager_state.commit(conn)?,
         }
         Ok(())
     }
@@ -824,13 +1117,13 @@ impl KeyManagerStateSql {
         Ok(match KeyManagerStateSql::get_state(conn) {
             Ok(km) => {
                 let current_index = (km.primary_key_index + 1) as usize;
-                let update = KeyManagerStateUpdate {
+                let update = KeyManagerStateUpdateSql {
                     master_seed: None,
                     branch_seed: None,
-                    primary_key_index: Some(current_index),
+                    primary_key_index: Some(current_index as i64),
                 };
                 let num_updated = diesel::update(key_manager_states::table.filter(key_manager_states::id.eq(&km.id)))
-                    .set(KeyManagerStateUpdateSql::from(update))
+                    .set(update)
                     .execute(conn)?;
                 if num_updated == 0 {
                     return Err(OutputManagerStorageError::UnexpectedResult(
@@ -844,12 +1137,6 @@ impl KeyManagerStateSql {
     }
 }
 
-struct KeyManagerStateUpdate {
-    master_seed: Option<PrivateKey>,
-    branch_seed: Option<String>,
-    primary_key_index: Option<usize>,
-}
-
 #[derive(AsChangeset)]
 #[table_name = "key_manager_states"]
 struct KeyManagerStateUpdateSql {
@@ -858,16 +1145,358 @@ struct KeyManagerStateUpdateSql {
     primary_key_index: Option<i64>,
 }
 
-impl From<KeyManagerStateUpdate> for KeyManagerStateUpdateSql {
-    fn from(km: KeyManagerStateUpdate) -> Self {
+#[derive(Clone, Debug, Queryable, Insertable)]
+#[table_name = "watch_only_states"]
+struct WatchOnlyStateSql {
+    id: Option<i64>,
+    rewind_public_key: Option<Vec<u8>>,
+    rewind_blinding_public_key: Option<Vec<u8>>,
+    next_height: i64,
+}
+
+impl From<WatchOnlyState> for WatchOnlyStateSql {
+    fn from(s: WatchOnlyState) -> Self {
         Self {
-            master_seed: km.master_seed.map(|ms| ms.to_vec()),
-            branch_seed: km.branch_seed,
-            primary_key_index: km.primary_key_index.map(|i| i as i64),
+            id: None,
+            rewind_public_key: s.view_key.as_ref().map(|k| k.rewind_public_key.to_vec()),
+            rewind_blinding_public_key: s.view_key.as_ref().map(|k| k.rewind_blinding_public_key.to_vec()),
+            next_height: s.next_height as i64,
+        }
+    }
+}
+
+impl TryFrom<WatchOnlyStateSql> for WatchOnlyState {
+    type Error = OutputManagerStorageError;
+
+    fn try_from(s: WatchOnlyStateSql) -> Result<Self, Self::Error> {
+        let view_key = match (s.rewind_public_key, s.rewind_blinding_public_key) {
+            (Some(rewind_public_key), Some(rewind_blinding_public_key)) => Some(ViewKey {
+                rewind_public_key: PublicKey::from_vec(&rewind_public_key)
+                    .map_err(|_| OutputManagerStorageError::ConversionError)?,
+                rewind_blinding_public_key: PublicKey::from_vec(&rewind_blinding_public_key)
+                    .map_err(|_| OutputManagerStorageError::ConversionError)?,
+            }),
+            _ => None,
+        };
+        Ok(Self {
+            view_key,
+            next_height: s.next_height as u64,
+        })
+    }
+}
+
+impl WatchOnlyStateSql {
+    pub fn get_state(
+        conn: &PooledConnection<ConnectionManager<SqliteConnection>>,
+    ) -> Result<Option<WatchOnlyStateSql>, OutputManagerStorageError> {
+        Ok(watch_only_states::table.first::<WatchOnlyStateSql>(conn).optional()?)
+    }
+
+    /// Replace the stored state, of which there is at most one
+    pub fn set_state(
+        state: WatchOnlyStateSql,
+        conn: &PooledConnection<ConnectionManager<SqliteConnection>>,
+    ) -> Result<(), OutputManagerStorageError>
+    {
+        conn.transaction::<_, OutputManagerStorageError, _>(|| {
+            diesel::delete(watch_only_states::table).execute(conn)?;
+            diesel::insert_into(watch_only_states::table)
+                .values(state)
+                .execute(conn)?;
+            Ok(())
+        })
+    }
+}
+
+#[derive(Clone, Debug, Queryable, Insertable)]
+#[table_name = "watched_outputs"]
+struct WatchedOutputSql {
+    commitment: Vec<u8>,
+    value: i64,
+    flags: i32,
+    maturity: i64,
+    mined_height: Option<i64>,
+    spent_height: Option<i64>,
+}
+
+impl From<WatchedOutput> for WatchedOutputSql {
+    fn from(o: WatchedOutput) -> Self {
+        Self {
+            commitment: o.commitment.to_vec(),
+            value: u64::from(o.value) as i64,
+            flags: o.features.flags.bits() as i32,
+            maturity: o.features.maturity as i64,
+            mined_height: o.mined_height.map(|h| h as i64),
+            spent_height: o.spent_height.map(|h| h as i64),
+        }
+    }
+}
+
+impl TryFrom<WatchedOutputSql> for WatchedOutput {
+    type Error = OutputManagerStorageError;
+
+    fn try_from(o: WatchedOutputSql) -> Result<Self, Self::Error> {
+        Ok(Self {
+            commitment: Commitment::from_vec(&o.commitment).map_err(|_| OutputManagerStorageError::ConversionError)?,
+            value: MicroTari::from(o.value as u64),
+            features: OutputFeatures {
+                flags: OutputFlags::from_bits(o.flags as u8).ok_or(OutputManagerStorageError::ConversionError)?,
+                maturity: o.maturity as u64,
+                hash_lock: None,
+            },
+            mined_height: o.mined_height.map(|h| h as u64),
+            spent_height: o.spent_height.map(|h| h as u64),
+        })
+    }
+}
+
+impl WatchedOutputSql {
+    /// Insert the watched output, replacing the stored output with the same commitment
+    pub fn commit(
+        &self,
+        conn: &PooledConnection<ConnectionManager<SqliteConnection>>,
+    ) -> Result<(), OutputManagerStorageError>
+    {
+        diesel::replace_into(watched_outputs::table)
+            .values(self.clone())
+            .execute(conn)?;
+        Ok(())
+    }
+
+    pub fn index(
+        conn: &PooledConnection<ConnectionManager<SqliteConnection>>,
+    ) -> Result<Vec<WatchedOutputSql>, OutputManagerStorageError> {
+        Ok(watched_outputs::table.load::<WatchedOutputSql>(conn)?)
+    }
+}
+
+#[derive(Clone, Debug, Queryable, Insertable)]
+#[table_name = "shared_outputs"]
+struct SharedOutputSql {
+    output_id: i64,
+    value: i64,
+    initiator: Vec<u8>,
+    /// The comms public key and key share public key of each participant, one after the other
+    participants: Vec<u8>,
+    key_share: Vec<u8>,
+    commitment: Option<Vec<u8>>,
+    status: i32,
+}
+
+impl From<SharedOutput> for SharedOutputSql {
+    fn from(o: SharedOutput) -> Self {
+        Self {
+            output_id: o.output_id as i64,
+            value: u64::from(o.value) as i64,
+            initiator: o.initiator.to_vec(),
+            participants: o
+                .participants
+                .iter()
+                .flat_map(|(node, key)| node.as_bytes().iter().chain(key.as_bytes().iter()).cloned())
+                .collect(),
+            key_share: o.key_share.to_vec(),
+            commitment: o.commitment.map(|c| c.to_vec()),
+            status: o.status as i32,
         }
     }
 }
 
+impl TryFrom<SharedOutputSql> for SharedOutput {
+    type Error = OutputManagerStorageError;
+
+    fn try_from(o: SharedOutputSql) -> Result<Self, Self::Error> {
+        let key_length = PublicKey::key_length();
+        if o.participants.len() % (2 * key_length) != 0 {
+            return Err(OutputManagerStorageError::ConversionError);
+        }
+        let participants = o
+            .participants
+            .chunks(2 * key_length)
+            .map(|keys| -> Result<(PublicKey, PublicKey), ByteArrayError> {
+                Ok((
+                    PublicKey::from_bytes(&keys[..key_length])?,
+                    PublicKey::from_bytes(&keys[key_length..])?,
+                ))
+            })
+            .collect::<Result<Vec<_>, ByteArrayError>>()
+            .map_err(|_| OutputManagerStorageError::ConversionError)?;
+        Ok(Self {
+            output_id: o.output_id as u64,
+            value: MicroTari::from(o.value as u64),
+            initiator: PublicKey::from_vec(&o.initiator).map_err(|_| OutputManagerStorageError::ConversionError)?,
+            participants,
+            key_share: PrivateKey::from_vec(&o.key_share).map_err(|_| OutputManagerStorageError::ConversionError)?,
+            commitment: match o.commitment {
+                Some(c) => Some(Commitment::from_vec(&c).map_err(|_| OutputManagerStorageError::ConversionError)?),
+                None => None,
+            },
+            status: match o.status {
+                0 => SharedOutputStatus::Pending,
+                1 => SharedOutputStatus::Unspent,
+                2 => SharedOutputStatus::Spent,
+                _ => return Err(OutputManagerStorageError::ConversionError),
+            },
+        })
+    }
+}
+
+impl SharedOutputSql {
+    /// Encrypt the key share with the given encryption state
+    pub fn encrypt(mut self, encryption: &EncryptionState) -> Result<Self, OutputManagerStorageError> {
+        self.key_share = encryption.encrypt_secret(&self.key_share)?;
+        Ok(self)
+    }
+
+    /// Convert the stored key share back to its plain form
+    pub fn decrypt(mut self, encryption: &EncryptionState) -> Result<Self, OutputManagerStorageError> {
+        self.key_share = encryption.decrypt_secret(&self.key_share)?;
+        Ok(self)
+    }
+
+    /// Insert the shared output, replacing the stored output with the same id
+    pub fn commit(
+        &self,
+        conn: &PooledConnection<ConnectionManager<SqliteConnection>>,
+    ) -> Result<(), OutputManagerStorageError>
+    {
+        diesel::replace_into(shared_outputs::table)
+            .values(self.clone())
+            .execute(conn)?;
+        Ok(())
+    }
+
+    pub fn index(
+        conn: &PooledConnection<ConnectionManager<SqliteConnection>>,
+    ) -> Result<Vec<SharedOutputSql>, OutputManagerStorageError> {
+        Ok(shared_outputs::table.load::<SharedOutputSql>(conn)?)
+    }
+}
+
+#[derive(Clone, Debug, Queryable, Insertable)]
+#[table_name = "atomic_swaps"]
+struct AtomicSwapSql {
+    swap_id: i64,
+    role: i32,
+    counterparty: Vec<u8>,
+    value: i64,
+    hash: Vec<u8>,
+    refund_height: i64,
+    fee_per_gram: i64,
+    output_value: i64,
+    output_key: Vec<u8>,
+    counterparty_commitment: Option<Vec<u8>>,
+    spending_key: Option<Vec<u8>>,
+    preimage: Option<Vec<u8>>,
+    message: String,
+    status: i32,
+    scan_height: Option<i64>,
+}
+
+impl From<AtomicSwap> for AtomicSwapSql {
+    fn from(s: AtomicSwap) -> Self {
+        Self {
+            swap_id: s.swap_id as i64,
+            role: s.role as i32,
+            counterparty: s.counterparty.to_vec(),
+            value: u64::from(s.value) as i64,
+            hash: s.hash,
+            refund_height: s.refund_height as i64,
+            fee_per_gram: u64::from(s.fee_per_gram) as i64,
+            output_value: u64::from(s.output_value) as i64,
+            output_key: s.output_key.to_vec(),
+            counterparty_commitment: s.counterparty_commitment.map(|c| c.to_vec()),
+            spending_key: s.spending_key.map(|k| k.to_vec()),
+            preimage: s.preimage,
+            message: s.message,
+            status: s.status as i32,
+            scan_height: s.scan_height.map(|h| h as i64),
+        }
+    }
+}
+
+impl TryFrom<AtomicSwapSql> for AtomicSwap {
+    type Error = OutputManagerStorageError;
+
+    fn try_from(s: AtomicSwapSql) -> Result<Self, Self::Error> {
+        Ok(Self {
+            swap_id: s.swap_id as u64,
+            role: match s.role {
+                0 => AtomicSwapRole::Funder,
+                1 => AtomicSwapRole::Claimer,
+                _ => return Err(OutputManagerStorageError::ConversionError),
+            },
+            counterparty: PublicKey::from_vec(&s.counterparty)
+                .map_err(|_| OutputManagerStorageError::ConversionError)?,
+            value: MicroTari::from(s.value as u64),
+            hash: s.hash,
+            refund_height: s.refund_height as u64,
+            fee_per_gram: MicroTari::from(s.fee_per_gram as u64),
+            output_value: MicroTari::from(s.output_value as u64),
+            output_key: PrivateKey::from_vec(&s.output_key).map_err(|_| OutputManagerStorageError::ConversionError)?,
+            counterparty_commitment: match s.counterparty_commitment {
+                Some(c) => Some(Commitment::from_vec(&c).map_err(|_| OutputManagerStorageError::ConversionError)?),
+                None => None,
+            },
+            spending_key: match s.spending_key {
+                Some(k) => Some(PrivateKey::from_vec(&k).map_err(|_| OutputManagerStorageError::ConversionError)?),
+                None => None,
+            },
+            preimage: s.preimage,
+            message: s.message,
+            status: match s.status {
+                0 => AtomicSwapStatus::Offered,
+                1 => AtomicSwapStatus::Accepted,
+                2 => AtomicSwapStatus::Funded,
+                3 => AtomicSwapStatus::Claimed,
+                4 => AtomicSwapStatus::Refunded,
+                5 => AtomicSwapStatus::Cancelled,
+                _ => return Err(OutputManagerStorageError::ConversionError),
+            },
+            scan_height: s.scan_height.map(|h| h as u64),
+        })
+    }
+}
+
+impl AtomicSwapSql {
+    /// Encrypt the spending keys of the swap with the given encryption state
+    pub fn encrypt(mut self, encryption: &EncryptionState) -> Result<Self, OutputManagerStorageError> {
+        self.output_key = encryption.encrypt_secret(&self.output_key)?;
+        self.spending_key = match self.spending_key {
+            Some(k) => Some(encryption.encrypt_secret(&k)?),
+            None => None,
+        };
+        Ok(self)
+    }
+
+    /// Convert the stored spending keys back to their plain form
+    pub fn decrypt(mut self, encryption: &EncryptionState) -> Result<Self, OutputManagerStorageError> {
+        self.output_key = encryption.decrypt_secret(&self.output_key)?;
+        self.spending_key = match self.spending_key {
+            Some(k) => Some(encryption.decrypt_secret(&k)?),
+            None => None,
+        };
+        Ok(self)
+    }
+
+    /// Insert the atomic swap, replacing the stored swap with the same id
+    pub fn commit(
+        &self,
+        conn: &PooledConnection<ConnectionManager<SqliteConnection>>,
+    ) -> Result<(), OutputManagerStorageError>
+    {
+        diesel::replace_into(atomic_swaps::table)
+            .values(self.clone())
+            .execute(conn)?;
+        Ok(())
+    }
+
+    pub fn index(
+        conn: &PooledConnection<ConnectionManager<SqliteConnection>>,
+    ) -> Result<Vec<AtomicSwapSql>, OutputManagerStorageError> {
+        Ok(atomic_swaps::table.load::<AtomicSwapSql>(conn)?)
+    }
+}
+
 #[cfg(test)]
 mod test {
     use crate::output_manager_service::storage::{
@@ -971,6 +1600,7 @@ mod test {
                     received: None,
                     encumbered: None,
                     tx_id: Some(tx_id),
+                    valid: None,
                 },
                 &conn,
             )
@@ -1000,6 +1630,7 @@ mod test {
                     received: None,
                     encumbered: None,
                     tx_id: Some(44u64),
+                    valid: None,
                 },
                 &conn,
             )
@@ -1013,6 +1644,7 @@ mod test {
                     received: None,
                     encumbered: Some(true),
                     tx_id: Some(44u64),
+                    valid: None,
                 },
                 &conn,
             )
@@ -1068,7 +1700,7 @@ mod test {
             primary_key_index: 0,
         };
 
-        KeyManagerStateSql::set_state(state1.clone(), &conn).unwrap();
+        KeyManagerStateSql::set_state(KeyManagerStateSql::from(state1.clone()), &conn).unwrap();
 
         let state1_read = KeyManagerStateSql::get_state(&conn).unwrap();
 
@@ -1080,7 +1712,7 @@ mod test {
             primary_key_index: 0,
         };
 
-        KeyManagerStateSql::set_state(state2.clone(), &conn).unwrap();
+        KeyManagerStateSql::set_state(KeyManagerStateSql::from(state2.clone()), &conn).unwrap();
 
         let state2_read = KeyManagerStateSql::get_state(&conn).unwrap();
 
@@ -0,0 +1,333 @@
+// Copyright 2020, The Tari Project
+//
+// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+// following conditions are met:
+//
+// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+// disclaimer.
+//
+// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+// following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+// 3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+// products derived from this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+// INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+// SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+// WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+// USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+//! Payment requests that a wallet hands to a payer, as a URI that can be shared as text or in a QR code:
+//!
+//! `tari://<network>/<public key>?amount=<µT>&message=<message>&expires=<unix time>&checksum=<checksum>`
+//!
+//! The network is `mainnet` or `testnet` and the public key is the hex encoded comms public key of the payee. The
+//! amount, message and expiry time are optional and the message is percent encoded. The checksum is always last and
+//! covers everything before it, so a payment request with a mistyped or altered amount or key is rejected instead of
+//! paying the wrong amount to the wrong wallet.
+
+use crate::error::PaymentRequestError;
+use chrono::{NaiveDateTime, Utc};
+use digest::Digest;
+use std::{
+    convert::TryFrom,
+    fmt::{Display, Error, Formatter},
+    str::FromStr,
+};
+use tari_common::Network;
+use tari_comms::types::CommsPublicKey;
+use tari_core::transactions::tari_amount::MicroTari;
+use tari_crypto::common::Blake256;
+use tari_utilities::hex::{to_hex, Hex};
+
+/// The URI scheme of payment requests
+pub const PAYMENT_REQUEST_SCHEME: &str = "tari";
+/// The number of bytes of the hash of the payment request that are included as its checksum
+const CHECKSUM_LENGTH: usize = 4;
+
+/// A request for a payment to the wallet with the given public key on the given network
+#[derive(Clone, Debug, PartialEq)]
+pub struct PaymentRequest {
+    pub network: Network,
+    pub public_key: CommsPublicKey,
+    /// The amount requested. A request without an amount leaves it to the payer.
+    pub amount: Option<MicroTari>,
+    pub message: String,
+    /// The time after which the request should no longer be paid, to the second
+    pub expires: Option<NaiveDateTime>,
+}
+
+impl PaymentRequest {
+    pub fn new(network: Network, public_key: CommsPublicKey) -> Self {
+        Self {
+            network,
+            public_key,
+            amount: None,
+            message: String::new(),
+            expires: None,
+        }
+    }
+
+    pub fn with_amount(mut self, amount: MicroTari) -> Self {
+        self.amount = Some(amount);
+        self
+    }
+
+    pub fn with_message(mut self, message: String) -> Self {
+        self.message = message;
+        self
+    }
+
+    /// Set the expiry time of the request, which is truncated to the second
+    pub fn with_expiry(mut self, expires: NaiveDateTime) -> Self {
+        self.expires = Some(NaiveDateTime::from_timestamp(expires.timestamp(), 0));
+        self
+    }
+
+    /// Serialise the request as a payment request URI
+    pub fn to_uri(&self) -> String {
+        let mut params = Vec::new();
+        if let Some(amount) = self.amount {
+            params.push(format!("amount={}", u64::from(amount)));
+        }
+        if !self.message.is_empty() {
+            params.push(format!("message={}", percent_encode(&self.message)));
+        }
+        if let Some(expires) = self.expires {
+            params.push(format!("expires={}", expires.timestamp()));
+        }
+        let mut body = format!(
+            "{}://{}/{}",
+            PAYMENT_REQUEST_SCHEME,
+            self.network.to_string().to_lowercase(),
+            self.public_key.to_hex()
+        );
+        if !params.is_empty() {
+            body = format!("{}?{}", body, params.join("&"));
+        }
+        let separator = if params.is_empty() { "?" } else { "&" };
+        format!("{}{}checksum={}", body, separator, checksum(&body))
+    }
+
+    /// Parse a payment request URI, checking its checksum. Parameters that this version of the wallet does not know
+    /// are ignored.
+    pub fn from_uri(uri: &str) -> Result<Self, PaymentRequestError> {
+        let uri = uri.trim();
+        let prefix = format!("{}://", PAYMENT_REQUEST_SCHEME);
+        if !uri.to_lowercase().starts_with(&prefix) {
+            return Err(PaymentRequestError::InvalidScheme);
+        }
+
+        let checksum_start = uri.rfind("checksum=").ok_or(PaymentRequestError::MissingChecksum)?;
+        let body = &uri[..checksum_start];
+        if !body.ends_with('?') && !body.ends_with('&') {
+            return Err(PaymentRequestError::MissingChecksum);
+        }
+        let body = &body[..body.len() - 1];
+        if uri[checksum_start + "checksum=".len()..].to_lowercase() != checksum(body) {
+            return Err(PaymentRequestError::InvalidChecksum);
+        }
+
+        let rest = &body[prefix.len()..];
+        let (path, query) = match rest.find('?') {
+            Some(i) => (&rest[..i], &rest[i + 1..]),
+            None => (rest, ""),
+        };
+        let mut path = path.trim_end_matches('/').splitn(2, '/');
+        let network = path
+            .next()
+            .and_then(|n| Network::try_from(n.to_string()).ok())
+            .ok_or(PaymentRequestError::UnknownNetwork)?;
+        let public_key = path
+            .next()
+            .and_then(|k| CommsPublicKey::from_hex(k).ok())
+            .ok_or(PaymentRequestError::InvalidPublicKey)?;
+        let mut request = PaymentRequest::new(network, public_key);
+
+        let mut seen = Vec::new();
+        for param in query.split('&').filter(|p| !p.is_empty()) {
+            let mut param = param.splitn(2, '=');
+            let (key, value) = (param.next().unwrap_or(""), param.next().unwrap_or(""));
+            if seen.contains(&key) {
+                return Err(PaymentRequestError::DuplicateParameter);
+            }
+            seen.push(key);
+            match key {
+                "amount" => {
+                    let amount = value.parse::<u64>().map_err(|_| PaymentRequestError::InvalidAmount)?;
+                    request.amount = Some(MicroTari::from(amount));
+                },
+                "message" => request.message = percent_decode(value)?,
+                "expires" => {
+                    let expires = value.parse::<i64>().map_err(|_| PaymentRequestError::InvalidExpiry)?;
+                    request.expires =
+                        Some(NaiveDateTime::from_timestamp_opt(expires, 0).ok_or(PaymentRequestError::InvalidExpiry)?);
+                },
+                _ => (),
+            }
+        }
+
+        Ok(request)
+    }
+
+    pub fn is_expired(&self) -> bool {
+        self.expires.map(|e| e <= Utc::now().naive_utc()).unwrap_or(false)
+    }
+
+    /// Check that the request can be paid now by a wallet on the given network
+    pub fn check(&self, network: Network) -> Result<(), PaymentRequestError> {
+        if self.network != network {
+            return Err(PaymentRequestError::NetworkMismatch);
+        }
+        if self.is_expired() {
+            return Err(PaymentRequestError::Expired);
+        }
+        Ok(())
+    }
+
+    /// The amount to pay for the request. The payer's amount is required when the request does not have one and must
+    /// match the requested amount when it does.
+    pub fn amount_to_pay(&self, amount: Option<MicroTari>) -> Result<MicroTari, PaymentRequestError> {
+        match (self.amount, amount) {
+            (Some(requested), None) => Ok(requested),
+            (Some(requested), Some(amount)) if requested == amount => Ok(requested),
+            (Some(_), Some(_)) => Err(PaymentRequestError::AmountMismatch),
+            (None, Some(amount)) => Ok(amount),
+            (None, None) => Err(PaymentRequestError::MissingAmount),
+        }
+    }
+}
+
+impl Display for PaymentRequest {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), Error> {
+        f.write_str(&self.to_uri())
+    }
+}
+
+impl FromStr for PaymentRequest {
+    type Err = PaymentRequestError;
+
+    fn from_str(uri: &str) -> Result<Self, Self::Err> {
+        PaymentRequest::from_uri(uri)
+    }
+}
+
+/// The hex encoded checksum of the part of a payment request URI before the checksum parameter
+fn checksum(body: &str) -> String {
+    let hash = Blake256::new()
+        .chain(b"payment_request")
+        .chain(body.as_bytes())
+        .result();
+    to_hex(&hash[..CHECKSUM_LENGTH])
+}
+
+/// Percent encode all but the unreserved characters of a URI
+fn percent_encode(value: &str) -> String {
+    value
+        .bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => (b as char).to_string(),
+            _ => format!("%{:02X}", b),
+        })
+        .collect()
+}
+
+fn percent_decode(value: &str) -> Result<String, PaymentRequestError> {
+    let bytes = value.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' {
+            let hex = value
+                .get(i + 1..i + 3)
+                .filter(|h| h.bytes().all(|b| b.is_ascii_hexdigit()))
+                .ok_or(PaymentRequestError::InvalidEncoding)?;
+            decoded.push(u8::from_str_radix(hex, 16).map_err(|_| PaymentRequestError::InvalidEncoding)?);
+            i += 3;
+        } else {
+            decoded.push(bytes[i]);
+            i += 1;
+        }
+    }
+    String::from_utf8(decoded).map_err(|_| PaymentRequestError::InvalidEncoding)
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{error::PaymentRequestError, payment_request::PaymentRequest};
+    use chrono::{Duration, Utc};
+    use rand::OsRng;
+    use tari_common::Network;
+    use tari_comms::types::{CommsPublicKey, CommsSecretKey};
+    use tari_core::transactions::tari_amount::MicroTari;
+    use tari_crypto::keys::{PublicKey, SecretKey};
+
+    fn random_public_key() -> CommsPublicKey {
+        CommsPublicKey::from_secret_key(&CommsSecretKey::random(&mut OsRng::new().unwrap()))
+    }
+
+    #[test]
+    fn payment_request_round_trip() {
+        let request = PaymentRequest::new(Network::TestNet, random_public_key());
+        let uri = request.to_uri();
+        assert!(uri.starts_with("tari://testnet/"));
+        assert_eq!(PaymentRequest::from_uri(&uri).unwrap(), request);
+
+        let request = request
+            .with_amount(MicroTari::from(12345))
+            .with_message("Pizza & beer, 100% 🍕".to_string())
+            .with_expiry(Utc::now().naive_utc() + Duration::hours(1));
+        let uri = request.to_string();
+        assert!(uri.contains("amount=12345&message=Pizza%20%26%20beer%2C%20100%25%20%F0%9F%8D%95&expires="));
+        assert_eq!(uri.parse::<PaymentRequest>().unwrap(), request);
+    }
+
+    #[test]
+    fn invalid_payment_requests_are_rejected() {
+        let request = PaymentRequest::new(Network::MainNet, random_public_key()).with_amount(MicroTari::from(500));
+        let uri = request.to_uri();
+
+        assert_eq!(
+            PaymentRequest::from_uri(&uri.replace("tari://", "http://")),
+            Err(PaymentRequestError::InvalidScheme)
+        );
+        assert_eq!(
+            PaymentRequest::from_uri(&uri.replace("amount=500", "amount=5000")),
+            Err(PaymentRequestError::InvalidChecksum)
+        );
+        let without_checksum = &uri[..uri.find("&checksum=").unwrap()];
+        assert_eq!(
+            PaymentRequest::from_uri(without_checksum),
+            Err(PaymentRequestError::MissingChecksum)
+        );
+    }
+
+    #[test]
+    fn payment_requests_are_checked() {
+        let request = PaymentRequest::new(Network::TestNet, random_public_key());
+        assert_eq!(request.check(Network::TestNet), Ok(()));
+        assert_eq!(
+            request.check(Network::MainNet),
+            Err(PaymentRequestError::NetworkMismatch)
+        );
+        let expired = request
+            .clone()
+            .with_expiry(Utc::now().naive_utc() - Duration::seconds(10));
+        assert_eq!(expired.check(Network::TestNet), Err(PaymentRequestError::Expired));
+
+        assert_eq!(request.amount_to_pay(None), Err(PaymentRequestError::MissingAmount));
+        assert_eq!(
+            request.amount_to_pay(Some(MicroTari::from(10))),
+            Ok(MicroTari::from(10))
+        );
+        let request = request.with_amount(MicroTari::from(20));
+        assert_eq!(request.amount_to_pay(None), Ok(MicroTari::from(20)));
+        assert_eq!(
+            request.amount_to_pay(Some(MicroTari::from(10))),
+            Err(PaymentRequestError::AmountMismatch)
+        );
+    }
+}
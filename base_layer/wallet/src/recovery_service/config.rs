@@ -0,0 +1,49 @@
+// Copyright 2020, The Tari Project
+//
+// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+// following conditions are met:
+//
+// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+// disclaimer.
+//
+// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+// following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+// 3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+// products derived from this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+// INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+// SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+// WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+// USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+use std::time::Duration;
+use tari_core::consensus::emission::EmissionSchedule;
+
+/// Configuration for recovering a wallet's outputs from the blockchain.
+#[derive(Clone)]
+pub struct RecoveryServiceConfig {
+    /// The number of keys that are derived past the last key that was found in the blockchain. Keys that are used
+    /// after a gap larger than this will not be found. (default: 20)
+    pub gap_limit: usize,
+    /// The number of blocks that are requested from the base node at a time (default: 10)
+    pub blocks_per_request: u64,
+    /// The time after which an unanswered request to the base node is sent again (default: 30s)
+    pub request_timeout: Duration,
+    /// The emission schedule used to determine the value of coinbase outputs
+    pub emission_schedule: EmissionSchedule,
+}
+
+impl Default for RecoveryServiceConfig {
+    fn default() -> Self {
+        Self {
+            gap_limit: 20,
+            blocks_per_request: 10,
+            request_timeout: Duration::from_secs(30),
+            emission_schedule: EmissionSchedule::default(),
+        }
+    }
+}
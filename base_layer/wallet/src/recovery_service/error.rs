@@ -0,0 +1,49 @@
+// Copyright 2020, The Tari Project
+//
+// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+// following conditions are met:
+//
+// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+// disclaimer.
+//
+// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+// following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+// 3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+// products derived from this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+// INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+// SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+// WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+// USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+use crate::output_manager_service::error::OutputManagerError;
+use derive_error::Error;
+use tari_key_manager::key_manager::KeyManagerError;
+use tari_service_framework::reply_channel::TransportChannelError;
+use tari_utilities::ByteArrayError;
+
+#[derive(Debug, Error, PartialEq)]
+pub enum RecoveryServiceError {
+    OutputManagerError(OutputManagerError),
+    KeyManagerError(KeyManagerError),
+    ByteArrayError(ByteArrayError),
+    TransportChannelError(TransportChannelError),
+    /// A recovery is already in progress
+    RecoveryInProgress,
+    /// No base node public key has been set to recover from
+    NoBaseNodePublicKey,
+    /// Received an unexpected response from the base node
+    UnexpectedBaseNodeResponse,
+    /// Outbound Service send failed
+    OutboundSendFailure,
+    /// An error has occurred reading or writing the event subscriber stream
+    EventStreamError,
+    /// API returned something unexpected.
+    UnexpectedApiResponse,
+    #[error(msg_embedded, no_from, non_std)]
+    InvalidMessageError(String),
+}
@@ -0,0 +1,106 @@
+// Copyright 2020, The Tari Project
+//
+// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+// following conditions are met:
+//
+// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+// disclaimer.
+//
+// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+// following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+// 3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+// products derived from this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+// INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+// SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+// WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+// USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+use crate::recovery_service::error::RecoveryServiceError;
+use futures::{stream::Fuse, StreamExt};
+use tari_broadcast_channel::Subscriber;
+use tari_comms::types::CommsPublicKey;
+use tari_core::transactions::tari_amount::MicroTari;
+use tari_service_framework::reply_channel::SenderService;
+use tower::Service;
+
+/// API Request enum
+#[derive(Debug)]
+pub enum RecoveryServiceRequest {
+    StartRecovery((Vec<String>, Option<u64>)),
+    SetBaseNodePublicKey(CommsPublicKey),
+}
+
+/// API Reply enum
+#[derive(Debug)]
+pub enum RecoveryServiceResponse {
+    RecoveryStarted,
+    BaseNodePublicKeySet,
+}
+
+/// Events that can be published on the Recovery Service Event Stream
+#[derive(Clone, Debug, PartialEq)]
+pub enum RecoveryEvent {
+    /// The blocks up to the first height have been scanned out of a chain of the second height
+    Progress((u64, u64)),
+    /// An unspent output with the given value was found in the block at the given height
+    OutputRecovered((u64, MicroTari)),
+    /// The scan is complete and the given number of outputs with the given total value were added to the wallet
+    RecoveryComplete((usize, MicroTari)),
+    Error(String),
+}
+
+#[derive(Clone)]
+pub struct RecoveryServiceHandle {
+    handle: SenderService<RecoveryServiceRequest, Result<RecoveryServiceResponse, RecoveryServiceError>>,
+    event_stream: Subscriber<RecoveryEvent>,
+}
+
+impl RecoveryServiceHandle {
+    pub fn new(
+        handle: SenderService<RecoveryServiceRequest, Result<RecoveryServiceResponse, RecoveryServiceError>>,
+        event_stream: Subscriber<RecoveryEvent>,
+    ) -> Self
+    {
+        RecoveryServiceHandle { handle, event_stream }
+    }
+
+    pub fn get_event_stream_fused(&self) -> Fuse<Subscriber<RecoveryEvent>> {
+        self.event_stream.clone().fuse()
+    }
+
+    /// Start recovering the wallet's outputs from the provided seed words by scanning the blockchain of the configured
+    /// base node, starting at the birthday height if one is provided and at the genesis block otherwise. The progress
+    /// and the outcome of the recovery are published on the event stream.
+    pub async fn start_recovery(
+        &mut self,
+        seed_words: Vec<String>,
+        birthday_height: Option<u64>,
+    ) -> Result<(), RecoveryServiceError>
+    {
+        match self
+            .handle
+            .call(RecoveryServiceRequest::StartRecovery((seed_words, birthday_height)))
+            .await??
+        {
+            RecoveryServiceResponse::RecoveryStarted => Ok(()),
+            _ => Err(RecoveryServiceError::UnexpectedApiResponse),
+        }
+    }
+
+    /// Set the base node whose blockchain is scanned during recovery
+    pub async fn set_base_node_public_key(&mut self, public_key: CommsPublicKey) -> Result<(), RecoveryServiceError> {
+        match self
+            .handle
+            .call(RecoveryServiceRequest::SetBaseNodePublicKey(public_key))
+            .await??
+        {
+            RecoveryServiceResponse::BaseNodePublicKeySet => Ok(()),
+            _ => Err(RecoveryServiceError::UnexpectedApiResponse),
+        }
+    }
+}
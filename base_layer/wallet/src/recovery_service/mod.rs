@@ -0,0 +1,136 @@
+// Copyright 2020, The Tari Project
+//
+// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+// following conditions are met:
+//
+// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+// disclaimer.
+//
+// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+// following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+// 3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+// products derived from this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+// INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+// SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+// WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+// USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+use crate::{
+    output_manager_service::handle::OutputManagerHandle,
+    recovery_service::{config::RecoveryServiceConfig, handle::RecoveryServiceHandle, service::RecoveryService},
+};
+use futures::{future, Future, Stream, StreamExt};
+use log::*;
+use std::sync::Arc;
+use tari_broadcast_channel::bounded;
+use tari_comms_dht::outbound::OutboundMessageRequester;
+use tari_core::{base_node::proto::base_node::BaseNodeServiceResponse, transactions::types::CryptoFactories};
+use tari_p2p::{
+    comms_connector::PeerMessage,
+    domain_message::DomainMessage,
+    services::utils::{map_decode, ok_or_skip_result},
+    tari_message::TariMessageType,
+};
+use tari_pubsub::TopicSubscriptionFactory;
+use tari_service_framework::{
+    handles::ServiceHandlesFuture,
+    reply_channel,
+    ServiceInitializationError,
+    ServiceInitializer,
+};
+use tari_shutdown::ShutdownSignal;
+use tokio::runtime;
+
+pub mod config;
+pub mod error;
+pub mod handle;
+pub mod service;
+
+const LOG_TARGET: &'static str = "wallet::recovery_service::initializer";
+
+pub struct RecoveryServiceInitializer {
+    config: Option<RecoveryServiceConfig>,
+    subscription_factory: Arc<TopicSubscriptionFactory<TariMessageType, Arc<PeerMessage>>>,
+    factories: CryptoFactories,
+}
+
+impl RecoveryServiceInitializer {
+    pub fn new(
+        config: RecoveryServiceConfig,
+        subscription_factory: Arc<TopicSubscriptionFactory<TariMessageType, Arc<PeerMessage>>>,
+        factories: CryptoFactories,
+    ) -> Self
+    {
+        Self {
+            config: Some(config),
+            subscription_factory,
+            factories,
+        }
+    }
+
+    fn base_node_response_stream(&self) -> impl Stream<Item = DomainMessage<BaseNodeServiceResponse>> {
+        self.subscription_factory
+            .get_subscription(TariMessageType::BaseNodeResponse)
+            .map(map_decode::<BaseNodeServiceResponse>)
+            .filter_map(ok_or_skip_result)
+    }
+}
+
+impl ServiceInitializer for RecoveryServiceInitializer {
+    type Future = impl Future<Output = Result<(), ServiceInitializationError>>;
+
+    fn initialize(
+        &mut self,
+        executor: runtime::Handle,
+        handles_fut: ServiceHandlesFuture,
+        shutdown: ShutdownSignal,
+    ) -> Self::Future
+    {
+        let (sender, receiver) = reply_channel::unbounded();
+        let base_node_response_stream = self.base_node_response_stream();
+
+        let (publisher, subscriber) = bounded(100);
+
+        let recovery_handle = RecoveryServiceHandle::new(sender, subscriber);
+
+        // Register handle before waiting for handles to be ready
+        handles_fut.register(recovery_handle);
+
+        let config = self
+            .config
+            .take()
+            .expect("Cannot start Recovery Service without a config");
+        let factories = self.factories.clone();
+        executor.spawn(async move {
+            let handles = handles_fut.await;
+
+            let output_manager_service = handles
+                .get_handle::<OutputManagerHandle>()
+                .expect("Output Manager Service handle required for Recovery Service");
+            let outbound_message_service = handles
+                .get_handle::<OutboundMessageRequester>()
+                .expect("OMS handle required for Recovery Service");
+
+            let service = RecoveryService::new(
+                config,
+                output_manager_service,
+                outbound_message_service,
+                receiver,
+                base_node_response_stream,
+                publisher,
+                factories,
+            )
+            .start();
+
+            futures::pin_mut!(service);
+            future::select(service, shutdown).await;
+            info!(target: LOG_TARGET, "Recovery service shutdown");
+        });
+        future::ready(Ok(()))
+    }
+}
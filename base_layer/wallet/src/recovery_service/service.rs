@@ -0,0 +1,506 @@
+// Copyright 2020, The Tari Project
+//
+// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+// following conditions are met:
+//
+// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+// disclaimer.
+//
+// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+// following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+// 3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+// products derived from this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+// INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+// SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+// WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+// USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+use crate::{
+    output_manager_service::{
+        error::{OutputManagerError, OutputManagerStorageError},
+        handle::OutputManagerHandle,
+        service::derive_rewind_data,
+    },
+    recovery_service::{
+        config::RecoveryServiceConfig,
+        error::RecoveryServiceError,
+        handle::{RecoveryEvent, RecoveryServiceRequest, RecoveryServiceResponse},
+    },
+    types::KeyDigest,
+};
+use futures::{pin_mut, SinkExt, Stream, StreamExt};
+use log::*;
+use rand::RngCore;
+use std::{cmp, collections::HashMap, convert::TryFrom, time::Instant};
+use tari_broadcast_channel::Publisher;
+use tari_comms::types::CommsPublicKey;
+use tari_comms_dht::{
+    domain_message::OutboundDomainMessage,
+    outbound::{OutboundEncryption, OutboundMessageRequester, SendMessageResponse},
+};
+use tari_core::{
+    base_node::proto::base_node::{
+        base_node_service_request::Request as BaseNodeRequestProto,
+        base_node_service_response::Response as BaseNodeResponseProto,
+        BaseNodeServiceRequest,
+        BaseNodeServiceResponse,
+        BlockHeights,
+    },
+    proto::core::HistoricalBlock as HistoricalBlockProto,
+    transactions::{
+        aggregated_body::AggregateBody,
+        tari_amount::MicroTari,
+        transaction::{OutputFlags, RewindData, UnblindedOutput},
+        types::{Commitment, CryptoFactories, PrivateKey, PublicKey},
+    },
+};
+use tari_crypto::{commitment::HomomorphicCommitmentFactory, keys::PublicKey as PublicKeyTrait};
+use tari_key_manager::key_manager::KeyManager;
+use tari_p2p::{domain_message::DomainMessage, tari_message::TariMessageType};
+use tari_service_framework::reply_channel;
+use tokio::time;
+
+const LOG_TARGET: &'static str = "base_layer::wallet::recovery_service";
+
+/// This service recovers the outputs of a wallet from its seed words. The Key Manager is rebuilt from the seed words
+/// and the blockchain of the configured base node is scanned, block by block, for unspent outputs that were created
+/// with keys derived from the recovered master key. Keys are derived up to a gap limit past the last key that was
+/// found. Once the scan reaches the chain tip the recovered outputs are added to the Output Manager Service and its
+/// Key Manager is restored so that new keys follow on from the recovered ones.
+///
+/// Outputs are found by rewinding their range proofs with the rewind keys derived from the master key, which yields
+/// their value and spending key. Coinbase outputs without rewindable range proofs are still found by matching their
+/// commitment against the derived keys, since their value is the block reward plus the fees of the block.
+pub struct RecoveryService<BNResponseStream>
+where BNResponseStream: Stream<Item = DomainMessage<BaseNodeServiceResponse>>
+{
+    config: RecoveryServiceConfig,
+    output_manager_service: OutputManagerHandle,
+    outbound_message_service: OutboundMessageRequester,
+    request_stream:
+        Option<reply_channel::Receiver<RecoveryServiceRequest, Result<RecoveryServiceResponse, RecoveryServiceError>>>,
+    base_node_response_stream: Option<BNResponseStream>,
+    base_node_public_key: Option<CommsPublicKey>,
+    recovery: Option<RecoveryState>,
+    event_publisher: Publisher<RecoveryEvent>,
+    factories: CryptoFactories,
+}
+
+impl<BNResponseStream> RecoveryService<BNResponseStream>
+where BNResponseStream: Stream<Item = DomainMessage<BaseNodeServiceResponse>>
+{
+    pub fn new(
+        config: RecoveryServiceConfig,
+        output_manager_service: OutputManagerHandle,
+        outbound_message_service: OutboundMessageRequester,
+        request_stream: reply_channel::Receiver<
+            RecoveryServiceRequest,
+            Result<RecoveryServiceResponse, RecoveryServiceError>,
+        >,
+        base_node_response_stream: BNResponseStream,
+        event_publisher: Publisher<RecoveryEvent>,
+        factories: CryptoFactories,
+    ) -> Self
+    {
+        RecoveryService {
+            config,
+            output_manager_service,
+            outbound_message_service,
+            request_stream: Some(request_stream),
+            base_node_response_stream: Some(base_node_response_stream),
+            base_node_public_key: None,
+            recovery: None,
+            event_publisher,
+            factories,
+        }
+    }
+
+    pub async fn start(mut self) -> Result<(), RecoveryServiceError> {
+        let request_stream = self
+            .request_stream
+            .take()
+            .expect("Recovery Service initialized without request_stream")
+            .fuse();
+        pin_mut!(request_stream);
+        let base_node_response_stream = self
+            .base_node_response_stream
+            .take()
+            .expect("Recovery Service initialized without base_node_response_stream")
+            .fuse();
+        pin_mut!(base_node_response_stream);
+
+        let request_timeout = self.config.request_timeout;
+        let mut retry_tick = time::interval_at((Instant::now() + request_timeout).into(), request_timeout).fuse();
+
+        info!(target: LOG_TARGET, "Recovery Service started");
+        loop {
+            futures::select! {
+                request_context = request_stream.select_next_some() => {
+                    let (request, reply_tx) = request_context.split();
+                    let _ = reply_tx.send(self.handle_request(request).await.or_else(|resp| {
+                        error!(target: LOG_TARGET, "Error handling request: {:?}", resp);
+                        Err(resp)
+                    })).or_else(|resp| {
+                        error!(target: LOG_TARGET, "Failed to send reply");
+                        Err(resp)
+                    });
+                },
+                // Incoming messages from the Comms layer
+                msg = base_node_response_stream.select_next_some() => {
+                    let result = self.handle_base_node_response(msg.inner).await.or_else(|err| {
+                        error!(target: LOG_TARGET, "Failed to handle incoming Base Node response: {:?}", err);
+                        Err(err)
+                    });
+
+                    if let Err(e) = result {
+                        self.abort_recovery(e).await;
+                    }
+                },
+                _ = retry_tick.select_next_some() => {
+                    let result = self.resend_pending_request().await.or_else(|err| {
+                        error!(target: LOG_TARGET, "Failed to resend request to the base node: {:?}", err);
+                        Err(err)
+                    });
+
+                    if let Err(e) = result {
+                        self.abort_recovery(e).await;
+                    }
+                },
+                complete => {
+                    info!(target: LOG_TARGET, "Recovery service shutting down");
+                    break;
+                }
+            }
+        }
+        info!(target: LOG_TARGET, "Recovery Service ended");
+        Ok(())
+    }
+
+    /// This handler is called when the Service executor loops receives an API request
+    async fn handle_request(
+        &mut self,
+        request: RecoveryServiceRequest,
+    ) -> Result<RecoveryServiceResponse, RecoveryServiceError>
+    {
+        match request {
+            RecoveryServiceRequest::StartRecovery((seed_words, birthday_height)) => self
+                .start_recovery(seed_words, birthday_height)
+                .await
+                .map(|_| RecoveryServiceResponse::RecoveryStarted),
+            RecoveryServiceRequest::SetBaseNodePublicKey(public_key) => {
+                self.base_node_public_key = Some(public_key);
+                Ok(RecoveryServiceResponse::BaseNodePublicKeySet)
+            },
+        }
+    }
+
+    /// Rebuild the Key Manager from the seed words and start scanning the blockchain by asking the base node for the
+    /// height of its chain.
+    async fn start_recovery(
+        &mut self,
+        seed_words: Vec<String>,
+        birthday_height: Option<u64>,
+    ) -> Result<(), RecoveryServiceError>
+    {
+        if self.recovery.is_some() {
+            return Err(RecoveryServiceError::RecoveryInProgress);
+        }
+        if self.base_node_public_key.is_none() {
+            return Err(RecoveryServiceError::NoBaseNodePublicKey);
+        }
+
+        let key_manager = KeyManager::<PrivateKey, KeyDigest>::from_mnemonic(&seed_words, "".to_string(), 0)?;
+        let mut recovery = RecoveryState::new(key_manager, birthday_height.unwrap_or(0))?;
+        recovery.extend_key_window(self.config.gap_limit)?;
+        self.recovery = Some(recovery);
+
+        info!(
+            target: LOG_TARGET,
+            "Starting wallet recovery from block #{}",
+            birthday_height.unwrap_or(0)
+        );
+        let result = self.send_request(BaseNodeRequestProto::GetChainMetadata(true)).await;
+        if result.is_err() {
+            self.recovery = None;
+        }
+        result
+    }
+
+    /// Handle the base node's responses to the chain metadata and block requests made during a recovery
+    async fn handle_base_node_response(
+        &mut self,
+        response: BaseNodeServiceResponse,
+    ) -> Result<(), RecoveryServiceError>
+    {
+        let BaseNodeServiceResponse { request_key, response } = response;
+        let recovery = match self.recovery.as_mut() {
+            None => return Ok(()),
+            Some(r) => r,
+        };
+        match recovery.pending_request {
+            Some(ref request) if request.request_key == request_key => (),
+            // Responses to queries made by other wallet services are ignored
+            _ => return Ok(()),
+        }
+        recovery.pending_request = None;
+
+        match response {
+            Some(BaseNodeResponseProto::ChainMetadata(metadata)) => {
+                recovery.chain_height = metadata.height_of_longest_chain;
+            },
+            Some(BaseNodeResponseProto::HistoricalBlocks(historical_blocks)) => {
+                let mut recovered = Vec::new();
+                for block in historical_blocks.blocks {
+                    recovered.append(&mut recovery.scan_block(block, &self.config, &self.factories)?);
+                }
+                let progress = (recovery.next_height - 1, recovery.chain_height.unwrap_or(0));
+
+                for (height, value) in recovered {
+                    debug!(
+                        target: LOG_TARGET,
+                        "Recovered output with value {} in block #{}", value, height
+                    );
+                    self.publish_event(RecoveryEvent::OutputRecovered((height, value)))
+                        .await?;
+                }
+                self.publish_event(RecoveryEvent::Progress(progress)).await?;
+            },
+            _ => return Err(RecoveryServiceError::UnexpectedBaseNodeResponse),
+        }
+
+        self.request_next_blocks().await
+    }
+
+    /// Request the next range of blocks from the base node, or complete the recovery once the chain tip is reached
+    async fn request_next_blocks(&mut self) -> Result<(), RecoveryServiceError> {
+        let blocks_per_request = cmp::max(self.config.blocks_per_request, 1);
+        let heights = match self.recovery.as_mut() {
+            None => return Ok(()),
+            Some(recovery) => match recovery.chain_height {
+                Some(chain_height) if recovery.next_height <= chain_height => {
+                    let last_height = cmp::min(recovery.next_height + blocks_per_request - 1, chain_height);
+                    let heights = (recovery.next_height..=last_height).collect::<Vec<_>>();
+                    recovery.next_height = last_height + 1;
+                    heights
+                },
+                _ => return self.complete_recovery().await,
+            },
+        };
+
+        self.send_request(BaseNodeRequestProto::FetchBlocks(BlockHeights { heights }))
+            .await
+    }
+
+    /// Add the recovered outputs to the Output Manager Service and restore its Key Manager
+    async fn complete_recovery(&mut self) -> Result<(), RecoveryServiceError> {
+        let recovery = match self.recovery.take() {
+            None => return Ok(()),
+            Some(r) => r,
+        };
+
+        self.output_manager_service
+            .restore_key_manager(recovery.key_manager.master_key.clone(), recovery.highest_found_index)
+            .await?;
+
+        let mut num_recovered = 0;
+        let mut total_value = MicroTari::from(0);
+        for output in recovery.recovered_outputs {
+            let value = output.value;
+            match self.output_manager_service.add_output(output).await {
+                Ok(()) => {
+                    num_recovered += 1;
+                    total_value += value;
+                },
+                Err(OutputManagerError::OutputManagerStorageError(OutputManagerStorageError::DuplicateOutput)) => {
+                    debug!(
+                        target: LOG_TARGET,
+                        "Recovered output with value {} is already in the wallet", value
+                    );
+                },
+                Err(e) => return Err(e.into()),
+            }
+        }
+
+        info!(
+            target: LOG_TARGET,
+            "Wallet recovery complete, {} outputs with a total value of {} were recovered", num_recovered, total_value
+        );
+        self.publish_event(RecoveryEvent::RecoveryComplete((num_recovered, total_value)))
+            .await
+    }
+
+    /// Stop the recovery that is in progress and publish the reason on the event stream
+    async fn abort_recovery(&mut self, error: RecoveryServiceError) {
+        if self.recovery.take().is_some() {
+            let _ = self
+                .publish_event(RecoveryEvent::Error(format!("Wallet recovery failed: {:?}", error)))
+                .await;
+        }
+    }
+
+    /// Send the request to the base node again if it has not been answered by the time the retry interval elapses
+    async fn resend_pending_request(&mut self) -> Result<(), RecoveryServiceError> {
+        let request = match self.recovery.as_ref().and_then(|r| r.pending_request.clone()) {
+            None => return Ok(()),
+            Some(request) => request,
+        };
+        warn!(
+            target: LOG_TARGET,
+            "Base node did not respond to request (key: {}), sending it again", request.request_key
+        );
+        self.send_base_node_request(request).await
+    }
+
+    async fn send_request(&mut self, request: BaseNodeRequestProto) -> Result<(), RecoveryServiceError> {
+        let request = BaseNodeServiceRequest {
+            request_key: rand::OsRng::new().unwrap().next_u64(),
+            request: Some(request),
+        };
+        if let Some(recovery) = self.recovery.as_mut() {
+            recovery.pending_request = Some(request.clone());
+        }
+        self.send_base_node_request(request).await
+    }
+
+    async fn send_base_node_request(&mut self, request: BaseNodeServiceRequest) -> Result<(), RecoveryServiceError> {
+        let base_node_public_key = self
+            .base_node_public_key
+            .clone()
+            .ok_or(RecoveryServiceError::NoBaseNodePublicKey)?;
+        if let SendMessageResponse::Failed = self
+            .outbound_message_service
+            .send_direct(
+                base_node_public_key,
+                OutboundEncryption::EncryptForPeer,
+                OutboundDomainMessage::new(TariMessageType::BaseNodeRequest, request),
+            )
+            .await
+            .map_err(|_| RecoveryServiceError::OutboundSendFailure)?
+        {
+            return Err(RecoveryServiceError::OutboundSendFailure);
+        }
+
+        Ok(())
+    }
+
+    async fn publish_event(&mut self, event: RecoveryEvent) -> Result<(), RecoveryServiceError> {
+        self.event_publisher
+            .send(event)
+            .await
+            .map_err(|_| RecoveryServiceError::EventStreamError)
+    }
+}
+
+/// The progress of a recovery that is in progress
+struct RecoveryState {
+    key_manager: KeyManager<PrivateKey, KeyDigest>,
+    rewind_data: RewindData,
+    /// The public keys of the derived keys that have not been found yet, mapped to their key index
+    key_window: HashMap<PublicKey, usize>,
+    highest_found_index: usize,
+    next_height: u64,
+    chain_height: Option<u64>,
+    pending_request: Option<BaseNodeServiceRequest>,
+    recovered_outputs: Vec<UnblindedOutput>,
+}
+
+impl RecoveryState {
+    fn new(key_manager: KeyManager<PrivateKey, KeyDigest>, start_height: u64) -> Result<Self, RecoveryServiceError> {
+        let rewind_data = derive_rewind_data(&key_manager.master_key)?;
+        Ok(Self {
+            key_manager,
+            rewind_data,
+            key_window: HashMap::new(),
+            highest_found_index: 0,
+            next_height: start_height,
+            chain_height: None,
+            pending_request: None,
+            recovered_outputs: Vec::new(),
+        })
+    }
+
+    /// Derive keys until there are `gap_limit` keys past the highest key that has been found
+    fn extend_key_window(&mut self, gap_limit: usize) -> Result<(), RecoveryServiceError> {
+        while self.key_manager.primary_key_index < self.highest_found_index + gap_limit {
+            let derived_key = self.key_manager.next_key()?;
+            self.key_window
+                .insert(PublicKey::from_secret_key(&derived_key.k), derived_key.key_index);
+        }
+        Ok(())
+    }
+
+    /// Find the unspent outputs in the block that were created with a derived key. Returns the height and value of
+    /// each output that was recovered.
+    fn scan_block(
+        &mut self,
+        historical_block: HistoricalBlockProto,
+        config: &RecoveryServiceConfig,
+        factories: &CryptoFactories,
+    ) -> Result<Vec<(u64, MicroTari)>, RecoveryServiceError>
+    {
+        let spent_commitments = historical_block
+            .spent_commitments
+            .into_iter()
+            .map(Commitment::try_from)
+            .collect::<Result<Vec<_>, _>>()?;
+        let block = historical_block
+            .block
+            .ok_or_else(|| RecoveryServiceError::InvalidMessageError("Historical block has no block".to_string()))?;
+        let height = block
+            .header
+            .map(|h| h.height)
+            .ok_or_else(|| RecoveryServiceError::InvalidMessageError("Block has no header".to_string()))?;
+        let body = block
+            .body
+            .ok_or_else(|| RecoveryServiceError::InvalidMessageError("Block has no body".to_string()))
+            .and_then(|b| AggregateBody::try_from(b).map_err(RecoveryServiceError::InvalidMessageError))?;
+
+        let coinbase_value = config.emission_schedule.block_reward(height) + body.get_total_fee();
+        let coinbase_value_commitment = factories
+            .commitment
+            .commit_value(&PrivateKey::default(), coinbase_value.into());
+
+        let mut recovered = Vec::new();
+        for output in body.outputs() {
+            if spent_commitments.contains(&output.commitment) {
+                continue;
+            }
+            let found = match output.full_rewind_range_proof(
+                &factories.range_proof,
+                &self.rewind_data.rewind_key,
+                &self.rewind_data.rewind_blinding_key,
+            ) {
+                Ok(rewind_result) => {
+                    let public_key = PublicKey::from_secret_key(&rewind_result.blinding_factor);
+                    self.key_window
+                        .remove(&public_key)
+                        .map(|key_index| (key_index, MicroTari::from(rewind_result.committed_value)))
+                },
+                Err(_) if output.features.flags.contains(OutputFlags::COINBASE_OUTPUT) => {
+                    // The commitment of an output created with a derived key k is k.G + v.H, so removing the known
+                    // value leaves the public key of the derived key
+                    let blinding_commitment = &output.commitment - &coinbase_value_commitment;
+                    self.key_window
+                        .remove(blinding_commitment.as_public_key())
+                        .map(|key_index| (key_index, coinbase_value))
+                },
+                Err(_) => None,
+            };
+            if let Some((key_index, value)) = found {
+                let spending_key = self.key_manager.derive_key(key_index)?.k;
+                self.highest_found_index = cmp::max(self.highest_found_index, key_index);
+                self.recovered_outputs
+                    .push(UnblindedOutput::new(value, spending_key, Some(output.features.clone())));
+                recovered.push((height, value));
+            }
+        }
+        self.extend_key_window(config.gap_limit)?;
+
+        Ok(recovered)
+    }
+}
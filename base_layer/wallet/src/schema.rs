@@ -1,3 +1,23 @@
+table! {
+    atomic_swaps (swap_id) {
+        swap_id -> BigInt,
+        role -> Integer,
+        counterparty -> Binary,
+        value -> BigInt,
+        hash -> Binary,
+        refund_height -> BigInt,
+        fee_per_gram -> BigInt,
+        output_value -> BigInt,
+        output_key -> Binary,
+        counterparty_commitment -> Nullable<Binary>,
+        spending_key -> Nullable<Binary>,
+        preimage -> Nullable<Binary>,
+        message -> Text,
+        status -> Integer,
+        scan_height -> Nullable<BigInt>,
+    }
+}
+
 table! {
     coinbase_transactions (tx_id) {
         tx_id -> BigInt,
@@ -18,6 +38,7 @@ table! {
         status -> Integer,
         message -> Text,
         timestamp -> Timestamp,
+        mined_height -> Nullable<BigInt>,
     }
 }
 
@@ -36,6 +57,7 @@ table! {
         receiver_protocol -> Text,
         message -> Text,
         timestamp -> Timestamp,
+        cancelled -> Integer,
     }
 }
 
@@ -49,6 +71,13 @@ table! {
     }
 }
 
+table! {
+    outbound_transaction_recipients (recipient_tx_id) {
+        recipient_tx_id -> BigInt,
+        tx_id -> BigInt,
+    }
+}
+
 table! {
     outbound_transactions (tx_id) {
         tx_id -> BigInt,
@@ -58,6 +87,8 @@ table! {
         sender_protocol -> Text,
         message -> Text,
         timestamp -> Timestamp,
+        recipients -> Text,
+        cancelled -> Integer,
     }
 }
 
@@ -71,6 +102,7 @@ table! {
         to_be_received -> Integer,
         encumbered -> Integer,
         tx_id -> Nullable<BigInt>,
+        valid -> Integer,
     }
 }
 
@@ -88,16 +120,70 @@ table! {
     }
 }
 
+table! {
+    relayed_offline_transactions (tx_id) {
+        tx_id -> BigInt,
+        offline_transaction -> Text,
+        reply -> Nullable<Text>,
+    }
+}
+
+table! {
+    shared_outputs (output_id) {
+        output_id -> BigInt,
+        value -> BigInt,
+        initiator -> Binary,
+        participants -> Binary,
+        key_share -> Binary,
+        commitment -> Nullable<Binary>,
+        status -> Integer,
+    }
+}
+
+table! {
+    wallet_settings (key) {
+        key -> Text,
+        value -> Binary,
+    }
+}
+
+table! {
+    watch_only_states (id) {
+        id -> Nullable<BigInt>,
+        rewind_public_key -> Nullable<Binary>,
+        rewind_blinding_public_key -> Nullable<Binary>,
+        next_height -> BigInt,
+    }
+}
+
+table! {
+    watched_outputs (commitment) {
+        commitment -> Binary,
+        value -> BigInt,
+        flags -> Integer,
+        maturity -> BigInt,
+        mined_height -> Nullable<BigInt>,
+        spent_height -> Nullable<BigInt>,
+    }
+}
+
 joinable!(outputs -> pending_transaction_outputs (tx_id));
 
 allow_tables_to_appear_in_same_query!(
+    atomic_swaps,
     coinbase_transactions,
     completed_transactions,
     contacts,
     inbound_transactions,
     key_manager_states,
+    outbound_transaction_recipients,
     outbound_transactions,
     outputs,
     peers,
     pending_transaction_outputs,
+    relayed_offline_transactions,
+    shared_outputs,
+    wallet_settings,
+    watch_only_states,
+    watched_outputs,
 );
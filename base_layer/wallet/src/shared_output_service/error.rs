@@ -0,0 +1,55 @@
+// Copyright 2020, The Tari Project
+//
+// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+// following conditions are met:
+//
+// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+// disclaimer.
+//
+// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+// following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+// 3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+// products derived from this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+// INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+// SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+// WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+// USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+use crate::{output_manager_service::error::OutputManagerError, transaction_service::error::TransactionServiceError};
+use derive_error::Error;
+use tari_core::transactions::{transaction::TransactionError, transaction_protocol::TransactionProtocolError};
+use tari_service_framework::reply_channel::TransportChannelError;
+
+#[derive(Debug, Error)]
+pub enum SharedOutputServiceError {
+    OutputManagerError(OutputManagerError),
+    TransactionServiceError(TransactionServiceError),
+    TransactionProtocolError(TransactionProtocolError),
+    TransactionError(TransactionError),
+    TransportChannelError(TransportChannelError),
+    /// A shared output needs at least one other participant, and every participant can only be listed once
+    InvalidParticipants,
+    /// The shared output is not known to this wallet
+    SharedOutputNotFound,
+    /// The shared output cannot be spent in its current state
+    SharedOutputNotSpendable,
+    /// The fee of the spending transaction is not less than the value of the shared output
+    FeeExceedsValue,
+    /// A round of the protocol is already in progress for the shared output
+    RoundInProgress,
+    /// There is no round of the protocol in progress for the shared output
+    RoundNotFound,
+    /// Outbound Service send failed
+    OutboundSendFailure,
+    /// An error has occurred reading or writing the event subscriber stream
+    EventStreamError,
+    /// API returned something unexpected.
+    UnexpectedApiResponse,
+    #[error(msg_embedded, no_from, non_std)]
+    InvalidMessageError(String),
+}
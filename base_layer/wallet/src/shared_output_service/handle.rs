@@ -0,0 +1,193 @@
+// Copyright 2020, The Tari Project
+//
+// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+// following conditions are met:
+//
+// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+// disclaimer.
+//
+// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+// following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+// 3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+// products derived from this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+// INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+// SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+// WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+// USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+use crate::{
+    output_manager_service::storage::database::SharedOutput,
+    shared_output_service::error::SharedOutputServiceError,
+};
+use futures::{stream::Fuse, StreamExt};
+use tari_broadcast_channel::Subscriber;
+use tari_comms::types::CommsPublicKey;
+use tari_core::transactions::tari_amount::MicroTari;
+use tari_service_framework::reply_channel::SenderService;
+use tower::Service;
+
+/// API Request enum
+#[derive(Debug)]
+pub enum SharedOutputServiceRequest {
+    ProposeSharedOutput((Vec<CommsPublicKey>, MicroTari, MicroTari, String)),
+    AcceptProposal(u64),
+    SpendSharedOutput((u64, MicroTari, String)),
+    ApproveSpend(u64),
+    CancelRound(u64),
+    GetSharedOutputs,
+}
+
+/// API Reply enum
+#[derive(Debug)]
+pub enum SharedOutputServiceResponse {
+    ProposalSent(u64),
+    ProposalAccepted,
+    SpendStarted,
+    SpendApproved,
+    RoundCancelled,
+    SharedOutputs(Vec<SharedOutput>),
+}
+
+/// Events that can be published on the Shared Output Service Event Stream
+#[derive(Clone, Debug, PartialEq)]
+pub enum SharedOutputEvent {
+    /// The wallet with the given public key proposes to share an output with the given id and value with this wallet.
+    /// The proposal has to be accepted before this wallet joins the output. The initiator of the output learns its
+    /// whole spending key, so the output is only as safe as the initiator is trustworthy.
+    ProposalReceived((u64, CommsPublicKey, MicroTari)),
+    /// The shared output with the given id has been funded
+    OutputCreated(u64),
+    /// Another participant asks to spend the shared output with the given id, paying the given fee. The spend has to
+    /// be approved before this wallet signs it.
+    SpendRequested((u64, MicroTari)),
+    /// The shared output with the given id has been spent
+    OutputSpent(u64),
+    /// The round of the protocol in progress for the shared output with the given id was cancelled
+    RoundCancelled(u64),
+    Error(String),
+}
+
+#[derive(Clone)]
+pub struct SharedOutputServiceHandle {
+    handle: SenderService<SharedOutputServiceRequest, Result<SharedOutputServiceResponse, SharedOutputServiceError>>,
+    event_stream: Subscriber<SharedOutputEvent>,
+}
+
+impl SharedOutputServiceHandle {
+    pub fn new(
+        handle: SenderService<
+            SharedOutputServiceRequest,
+            Result<SharedOutputServiceResponse, SharedOutputServiceError>,
+        >,
+        event_stream: Subscriber<SharedOutputEvent>,
+    ) -> Self
+    {
+        SharedOutputServiceHandle { handle, event_stream }
+    }
+
+    pub fn get_event_stream_fused(&self) -> Fuse<Subscriber<SharedOutputEvent>> {
+        self.event_stream.clone().fuse()
+    }
+
+    /// Propose an output of the given value that this wallet funds and shares with the wallets with the given public
+    /// keys. Returns the id of the shared output. The output is created once all of the participants have joined, which
+    /// is published on the event stream. This wallet acts as the trusted dealer of the output: it combines the key
+    /// shares of the participants into the spending key of the output, and discards the key once the output is funded.
+    pub async fn propose_shared_output(
+        &mut self,
+        participants: Vec<CommsPublicKey>,
+        value: MicroTari,
+        fee_per_gram: MicroTari,
+        message: String,
+    ) -> Result<u64, SharedOutputServiceError>
+    {
+        match self
+            .handle
+            .call(SharedOutputServiceRequest::ProposeSharedOutput((
+                participants,
+                value,
+                fee_per_gram,
+                message,
+            )))
+            .await??
+        {
+            SharedOutputServiceResponse::ProposalSent(output_id) => Ok(output_id),
+            _ => Err(SharedOutputServiceError::UnexpectedApiResponse),
+        }
+    }
+
+    /// Accept a shared output that another wallet has proposed, so that this wallet joins it. The proposing wallet is
+    /// the trusted dealer of the output and learns its whole spending key, so only accept proposals from wallets that
+    /// are trusted with the value of the output. A proposal is declined by cancelling its round.
+    pub async fn accept_proposal(&mut self, output_id: u64) -> Result<(), SharedOutputServiceError> {
+        match self
+            .handle
+            .call(SharedOutputServiceRequest::AcceptProposal(output_id))
+            .await??
+        {
+            SharedOutputServiceResponse::ProposalAccepted => Ok(()),
+            _ => Err(SharedOutputServiceError::UnexpectedApiResponse),
+        }
+    }
+
+    /// Ask the other participants of a shared output to sign a transaction that spends it to this wallet. The spend
+    /// is completed once all of them have approved and signed it.
+    pub async fn spend_shared_output(
+        &mut self,
+        output_id: u64,
+        fee_per_gram: MicroTari,
+        message: String,
+    ) -> Result<(), SharedOutputServiceError>
+    {
+        match self
+            .handle
+            .call(SharedOutputServiceRequest::SpendSharedOutput((
+                output_id,
+                fee_per_gram,
+                message,
+            )))
+            .await??
+        {
+            SharedOutputServiceResponse::SpendStarted => Ok(()),
+            _ => Err(SharedOutputServiceError::UnexpectedApiResponse),
+        }
+    }
+
+    /// Approve the spend of a shared output that another participant has requested, so that this wallet takes part in
+    /// signing it
+    pub async fn approve_spend(&mut self, output_id: u64) -> Result<(), SharedOutputServiceError> {
+        match self
+            .handle
+            .call(SharedOutputServiceRequest::ApproveSpend(output_id))
+            .await??
+        {
+            SharedOutputServiceResponse::SpendApproved => Ok(()),
+            _ => Err(SharedOutputServiceError::UnexpectedApiResponse),
+        }
+    }
+
+    /// Cancel the round of the protocol in progress for a shared output, which also declines a proposal or a requested
+    /// spend
+    pub async fn cancel_round(&mut self, output_id: u64) -> Result<(), SharedOutputServiceError> {
+        match self
+            .handle
+            .call(SharedOutputServiceRequest::CancelRound(output_id))
+            .await??
+        {
+            SharedOutputServiceResponse::RoundCancelled => Ok(()),
+            _ => Err(SharedOutputServiceError::UnexpectedApiResponse),
+        }
+    }
+
+    pub async fn get_shared_outputs(&mut self) -> Result<Vec<SharedOutput>, SharedOutputServiceError> {
+        match self.handle.call(SharedOutputServiceRequest::GetSharedOutputs).await?? {
+            SharedOutputServiceResponse::SharedOutputs(outputs) => Ok(outputs),
+            _ => Err(SharedOutputServiceError::UnexpectedApiResponse),
+        }
+    }
+}
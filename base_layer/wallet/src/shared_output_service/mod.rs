@@ -0,0 +1,151 @@
+// Copyright 2020, The Tari Project
+//
+// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+// following conditions are met:
+//
+// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+// disclaimer.
+//
+// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+// following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+// 3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+// products derived from this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+// INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+// SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+// WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+// USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+use crate::{
+    output_manager_service::handle::OutputManagerHandle,
+    shared_output_service::{
+        config::SharedOutputServiceConfig,
+        handle::SharedOutputServiceHandle,
+        service::SharedOutputService,
+    },
+    transaction_service::handle::TransactionServiceHandle,
+};
+use futures::{future, Future, Stream, StreamExt};
+use log::*;
+use std::sync::Arc;
+use tari_broadcast_channel::bounded;
+use tari_comms::peer_manager::NodeIdentity;
+use tari_comms_dht::outbound::OutboundMessageRequester;
+use tari_core::transactions::{transaction_protocol::proto, types::CryptoFactories};
+use tari_p2p::{
+    comms_connector::PeerMessage,
+    domain_message::DomainMessage,
+    services::utils::{map_decode, ok_or_skip_result},
+    tari_message::TariMessageType,
+};
+use tari_pubsub::TopicSubscriptionFactory;
+use tari_service_framework::{
+    handles::ServiceHandlesFuture,
+    reply_channel,
+    ServiceInitializationError,
+    ServiceInitializer,
+};
+use tari_shutdown::ShutdownSignal;
+use tokio::runtime;
+
+pub mod config;
+pub mod error;
+pub mod handle;
+pub mod service;
+
+const LOG_TARGET: &'static str = "wallet::shared_output_service::initializer";
+
+pub struct SharedOutputServiceInitializer {
+    config: Option<SharedOutputServiceConfig>,
+    subscription_factory: Arc<TopicSubscriptionFactory<TariMessageType, Arc<PeerMessage>>>,
+    node_identity: Arc<NodeIdentity>,
+    factories: CryptoFactories,
+}
+
+impl SharedOutputServiceInitializer {
+    pub fn new(
+        config: SharedOutputServiceConfig,
+        subscription_factory: Arc<TopicSubscriptionFactory<TariMessageType, Arc<PeerMessage>>>,
+        node_identity: Arc<NodeIdentity>,
+        factories: CryptoFactories,
+    ) -> Self
+    {
+        Self {
+            config: Some(config),
+            subscription_factory,
+            node_identity,
+            factories,
+        }
+    }
+
+    fn shared_output_message_stream(&self) -> impl Stream<Item = DomainMessage<proto::SharedOutputMessage>> {
+        self.subscription_factory
+            .get_subscription(TariMessageType::SharedOutput)
+            .map(map_decode::<proto::SharedOutputMessage>)
+            .filter_map(ok_or_skip_result)
+    }
+}
+
+impl ServiceInitializer for SharedOutputServiceInitializer {
+    type Future = impl Future<Output = Result<(), ServiceInitializationError>>;
+
+    fn initialize(
+        &mut self,
+        executor: runtime::Handle,
+        handles_fut: ServiceHandlesFuture,
+        shutdown: ShutdownSignal,
+    ) -> Self::Future
+    {
+        let (sender, receiver) = reply_channel::unbounded();
+        let shared_output_message_stream = self.shared_output_message_stream();
+
+        let (publisher, subscriber) = bounded(100);
+
+        let shared_output_handle = SharedOutputServiceHandle::new(sender, subscriber);
+
+        // Register handle before waiting for handles to be ready
+        handles_fut.register(shared_output_handle);
+
+        let config = self
+            .config
+            .take()
+            .expect("Cannot start Shared Output Service without a config");
+        let node_identity = self.node_identity.clone();
+        let factories = self.factories.clone();
+        executor.spawn(async move {
+            let handles = handles_fut.await;
+
+            let output_manager_service = handles
+                .get_handle::<OutputManagerHandle>()
+                .expect("Output Manager Service handle required for Shared Output Service");
+            let transaction_service = handles
+                .get_handle::<TransactionServiceHandle>()
+                .expect("Transaction Service handle required for Shared Output Service");
+            let outbound_message_service = handles
+                .get_handle::<OutboundMessageRequester>()
+                .expect("OMS handle required for Shared Output Service");
+
+            let service = SharedOutputService::new(
+                config,
+                output_manager_service,
+                transaction_service,
+                outbound_message_service,
+                receiver,
+                shared_output_message_stream,
+                publisher,
+                node_identity,
+                factories,
+            )
+            .start();
+
+            futures::pin_mut!(service);
+            future::select(service, shutdown).await;
+            info!(target: LOG_TARGET, "Shared Output service shutdown");
+        });
+        future::ready(Ok(()))
+    }
+}
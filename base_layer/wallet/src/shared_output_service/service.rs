@@ -0,0 +1,1273 @@
+// Copyright 2020, The Tari Project
+//
+// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+// following conditions are met:
+//
+// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+// disclaimer.
+//
+// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+// following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+// 3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+// products derived from this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+// INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+// SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+// WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+// USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+use crate::{
+    output_manager_service::{
+        handle::OutputManagerHandle,
+        storage::database::{SharedOutput, SharedOutputStatus},
+    },
+    shared_output_service::{
+        config::SharedOutputServiceConfig,
+        error::SharedOutputServiceError,
+        handle::{SharedOutputEvent, SharedOutputServiceRequest, SharedOutputServiceResponse},
+    },
+    transaction_service::handle::TransactionServiceHandle,
+};
+use futures::{pin_mut, SinkExt, Stream, StreamExt};
+use log::*;
+use rand::RngCore;
+use std::{
+    collections::{HashMap, HashSet},
+    convert::TryFrom,
+    sync::Arc,
+    time::Instant,
+};
+use tari_broadcast_channel::Publisher;
+use tari_comms::{peer_manager::NodeIdentity, types::CommsPublicKey};
+use tari_comms_dht::{
+    domain_message::OutboundDomainMessage,
+    outbound::{OutboundEncryption, OutboundMessageRequester, SendMessageResponse},
+};
+use tari_core::transactions::{
+    fee::Fee,
+    tari_amount::MicroTari,
+    transaction::{OutputFeatures, RewindData, Transaction, TransactionInput, UnblindedOutput},
+    transaction_protocol::{
+        proto,
+        shared_output::{
+            build_joint_key,
+            dealer_blinding_factor,
+            nonce_commitment,
+            partial_signature,
+            shared_output_commitment,
+            verify_weighted_key_share,
+            weighted_key_share,
+            SharedOutputJointKey,
+            SharedOutputMessage,
+            SharedOutputSpend,
+            SharedOutputStep,
+        },
+        TransactionMetadata,
+    },
+    types::{Commitment, CryptoFactories, MessageHash, PrivateKey, PublicKey, Signature},
+};
+use tari_crypto::{
+    commitment::HomomorphicCommitmentFactory,
+    keys::{PublicKey as PublicKeyTrait, SecretKey},
+};
+use tari_p2p::{domain_message::DomainMessage, tari_message::TariMessageType};
+use tari_service_framework::reply_channel;
+use tokio::time;
+
+const LOG_TARGET: &'static str = "base_layer::wallet::shared_output_service";
+
+/// This service runs the protocol with which several wallets create and spend shared outputs, whose spends are signed
+/// with the MuSig aggregate of a key share held by each of the participants. Shared outputs are not N-of-N custody: the
+/// initiator of a shared output is its trusted dealer.
+///
+/// The initiator of a shared output proposes it to the other participants, collects the public keys of their key
+/// shares and then their key shares weighted by their MuSig coefficients, and funds the output from its own balance.
+/// The other participants check that the commitment of the funded output matches the joint key.
+///
+/// **Warning:** because the range proof of the output needs its whole blinding factor, the initiator learns the
+/// spending key of the output and is trusted to discard it once the output has been created. An initiator that keeps
+/// it can spend the output alone. This service discards it as soon as the funding transaction has been built, but the
+/// other participants cannot check that, so this wallet only joins a shared output once its proposal has been
+/// accepted, and a user should only accept proposals from wallets that they trust with the whole value of the output.
+///
+/// Any participant can coordinate a spend of a shared output to an output of its own. The other participants have to
+/// approve the spend, after which the kernel is signed in nonce commitment, nonce and partial signature rounds. A
+/// round of the protocol that is not completed within the configured timeout is cancelled.
+pub struct SharedOutputService<SOStream>
+where SOStream: Stream<Item = DomainMessage<proto::SharedOutputMessage>>
+{
+    config: SharedOutputServiceConfig,
+    output_manager_service: OutputManagerHandle,
+    transaction_service: TransactionServiceHandle,
+    outbound_message_service: OutboundMessageRequester,
+    request_stream: Option<
+        reply_channel::Receiver<
+            SharedOutputServiceRequest,
+            Result<SharedOutputServiceResponse, SharedOutputServiceError>,
+        >,
+    >,
+    shared_output_message_stream: Option<SOStream>,
+    rounds: HashMap<u64, SharedOutputRound>,
+    event_publisher: Publisher<SharedOutputEvent>,
+    node_identity: Arc<NodeIdentity>,
+    factories: CryptoFactories,
+}
+
+impl<SOStream> SharedOutputService<SOStream>
+where SOStream: Stream<Item = DomainMessage<proto::SharedOutputMessage>>
+{
+    pub fn new(
+        config: SharedOutputServiceConfig,
+        output_manager_service: OutputManagerHandle,
+        transaction_service: TransactionServiceHandle,
+        outbound_message_service: OutboundMessageRequester,
+        request_stream: reply_channel::Receiver<
+            SharedOutputServiceRequest,
+            Result<SharedOutputServiceResponse, SharedOutputServiceError>,
+        >,
+        shared_output_message_stream: SOStream,
+        event_publisher: Publisher<SharedOutputEvent>,
+        node_identity: Arc<NodeIdentity>,
+        factories: CryptoFactories,
+    ) -> Self
+    {
+        SharedOutputService {
+            config,
+            output_manager_service,
+            transaction_service,
+            outbound_message_service,
+            request_stream: Some(request_stream),
+            shared_output_message_stream: Some(shared_output_message_stream),
+            rounds: HashMap::new(),
+            event_publisher,
+            node_identity,
+            factories,
+        }
+    }
+
+    pub async fn start(mut self) -> Result<(), SharedOutputServiceError> {
+        let request_stream = self
+            .request_stream
+            .take()
+            .expect("Shared Output Service initialized without request_stream")
+            .fuse();
+        pin_mut!(request_stream);
+        let shared_output_message_stream = self
+            .shared_output_message_stream
+            .take()
+            .expect("Shared Output Service initialized without shared_output_message_stream")
+            .fuse();
+        pin_mut!(shared_output_message_stream);
+
+        let round_timeout = self.config.round_timeout;
+        let mut timeout_tick = time::interval_at((Instant::now() + round_timeout).into(), round_timeout).fuse();
+
+        info!(target: LOG_TARGET, "Shared Output Service started");
+        loop {
+            futures::select! {
+                request_context = request_stream.select_next_some() => {
+                    let (request, reply_tx) = request_context.split();
+                    let _ = reply_tx.send(self.handle_request(request).await.or_else(|resp| {
+                        error!(target: LOG_TARGET, "Error handling request: {:?}", resp);
+                        Err(resp)
+                    })).or_else(|resp| {
+                        error!(target: LOG_TARGET, "Failed to send reply");
+                        Err(resp)
+                    });
+                },
+                // Incoming messages from the Comms layer
+                msg = shared_output_message_stream.select_next_some() => {
+                    let output_id = msg.inner.output_id;
+                    let result = self.handle_message(msg.dht_header.origin_public_key, msg.inner).await.or_else(|err| {
+                        error!(target: LOG_TARGET, "Failed to handle incoming Shared Output message: {:?}", err);
+                        Err(err)
+                    });
+
+                    if let Err(e) = result {
+                        self.abort_round(output_id, e).await;
+                    }
+                },
+                _ = timeout_tick.select_next_some() => {
+                    self.cancel_timed_out_rounds().await;
+                },
+                complete => {
+                    info!(target: LOG_TARGET, "Shared Output service shutting down");
+                    break;
+                }
+            }
+        }
+        info!(target: LOG_TARGET, "Shared Output Service ended");
+        Ok(())
+    }
+
+    /// This handler is called when the Service executor loops receives an API request
+    async fn handle_request(
+        &mut self,
+        request: SharedOutputServiceRequest,
+    ) -> Result<SharedOutputServiceResponse, SharedOutputServiceError>
+    {
+        match request {
+            SharedOutputServiceRequest::ProposeSharedOutput((participants, value, fee_per_gram, message)) => self
+                .propose_shared_output(participants, value, fee_per_gram, message)
+                .await
+                .map(SharedOutputServiceResponse::ProposalSent),
+            SharedOutputServiceRequest::AcceptProposal(output_id) => self
+                .accept_proposal(output_id)
+                .await
+                .map(|_| SharedOutputServiceResponse::ProposalAccepted),
+            SharedOutputServiceRequest::SpendSharedOutput((output_id, fee_per_gram, message)) => self
+                .spend_shared_output(output_id, fee_per_gram, message)
+                .await
+                .map(|_| SharedOutputServiceResponse::SpendStarted),
+            SharedOutputServiceRequest::ApproveSpend(output_id) => self
+                .approve_spend(output_id)
+                .await
+                .map(|_| SharedOutputServiceResponse::SpendApproved),
+            SharedOutputServiceRequest::CancelRound(output_id) => {
+                if self
+                    .cancel_round(output_id, "Cancelled by participant".to_string())
+                    .await
+                {
+                    Ok(SharedOutputServiceResponse::RoundCancelled)
+                } else {
+                    Err(SharedOutputServiceError::RoundNotFound)
+                }
+            },
+            SharedOutputServiceRequest::GetSharedOutputs => Ok(SharedOutputServiceResponse::SharedOutputs(
+                self.output_manager_service.get_shared_outputs().await?,
+            )),
+        }
+    }
+
+    /// Handle a message of the protocol from another wallet. Messages from wallets that do not take part in the round
+    /// they refer to, or that do not fit the state of the round, are ignored.
+    async fn handle_message(
+        &mut self,
+        source: CommsPublicKey,
+        message: proto::SharedOutputMessage,
+    ) -> Result<(), SharedOutputServiceError>
+    {
+        let SharedOutputMessage { output_id, step } =
+            SharedOutputMessage::try_from(message).map_err(SharedOutputServiceError::InvalidMessageError)?;
+        match step {
+            SharedOutputStep::Proposal((value, participants)) => {
+                self.record_proposal(source, output_id, value, participants).await
+            },
+            SharedOutputStep::PublicKeyShare(public_key) => {
+                self.accept_public_key_share(source, output_id, public_key).await
+            },
+            SharedOutputStep::PublicKeys(public_keys) => self.accept_public_keys(source, output_id, public_keys).await,
+            SharedOutputStep::KeyShare(key_share) => self.accept_key_share(source, output_id, key_share).await,
+            SharedOutputStep::Created(commitment) => self.accept_created_output(source, output_id, commitment).await,
+            SharedOutputStep::SpendRequest(metadata) => self.accept_spend_request(source, output_id, metadata).await,
+            SharedOutputStep::NonceCommitment(commitment) => {
+                self.accept_nonce_commitment(source, output_id, commitment).await
+            },
+            SharedOutputStep::RevealNonces => self.reveal_nonce(source, output_id).await,
+            SharedOutputStep::PublicNonce(nonce) => self.accept_public_nonce(source, output_id, nonce).await,
+            SharedOutputStep::SignRequest(nonces) => self.sign_spend(source, output_id, nonces).await,
+            SharedOutputStep::PartialSignature(signature) => {
+                self.accept_partial_signature(source, output_id, signature).await
+            },
+            SharedOutputStep::Spent => self.accept_spent_output(source, output_id).await,
+            SharedOutputStep::Cancelled(reason) => {
+                self.accept_cancellation(source, output_id, reason).await;
+                Ok(())
+            },
+        }
+    }
+
+    /// Propose a shared output that this wallet funds to the wallets with the given public keys
+    async fn propose_shared_output(
+        &mut self,
+        others: Vec<CommsPublicKey>,
+        value: MicroTari,
+        fee_per_gram: MicroTari,
+        message: String,
+    ) -> Result<u64, SharedOutputServiceError>
+    {
+        let mut participants = vec![self.node_identity.public_key().clone()];
+        for participant in others {
+            if participants.contains(&participant) {
+                return Err(SharedOutputServiceError::InvalidParticipants);
+            }
+            participants.push(participant);
+        }
+        if participants.len() < 2 {
+            return Err(SharedOutputServiceError::InvalidParticipants);
+        }
+
+        let mut rng = rand::OsRng::new().unwrap();
+        let output_id = rng.next_u64();
+        let key_share = PrivateKey::random(&mut rng);
+        let mut public_key_shares = vec![None; participants.len()];
+        public_key_shares[0] = Some(PublicKey::from_secret_key(&key_share));
+        let recipients = participants[1..].to_vec();
+        let proposal = SharedOutputStep::Proposal((value, participants.clone()));
+        self.rounds.insert(
+            output_id,
+            SharedOutputRound::Proposal(ProposalRound {
+                value,
+                fee_per_gram,
+                message,
+                weighted_shares: vec![None; participants.len()],
+                participants,
+                key_share,
+                public_key_shares,
+                joint_key: None,
+                started: Instant::now(),
+            }),
+        );
+
+        if let Err(e) = self.send_to_all(recipients, output_id, proposal).await {
+            self.cancel_round(output_id, "The proposal could not be sent".to_string())
+                .await;
+            return Err(e);
+        }
+        info!(
+            target: LOG_TARGET,
+            "Shared output (Id: {}) of {} proposed", output_id, value
+        );
+
+        Ok(output_id)
+    }
+
+    /// Record a shared output proposed by another wallet. This wallet only joins it once the proposal has been
+    /// accepted, because the wallet that proposes a shared output is its trusted dealer. A proposal has to be sent by
+    /// its initiator, the first participant.
+    async fn record_proposal(
+        &mut self,
+        source: CommsPublicKey,
+        output_id: u64,
+        value: MicroTari,
+        participants: Vec<CommsPublicKey>,
+    ) -> Result<(), SharedOutputServiceError>
+    {
+        let own_public_key = self.node_identity.public_key().clone();
+        if participants.first() != Some(&source) {
+            warn!(
+                target: LOG_TARGET,
+                "Rejecting shared output proposal (Id: {}) that was not sent by its initiator", output_id
+            );
+            return Ok(());
+        }
+        if source == own_public_key || !participants.contains(&own_public_key) {
+            debug!(
+                target: LOG_TARGET,
+                "Ignoring shared output proposal (Id: {}) that does not include us", output_id
+            );
+            return Ok(());
+        }
+        if participants.iter().collect::<HashSet<_>>().len() != participants.len() {
+            return Err(SharedOutputServiceError::InvalidMessageError(
+                "A participant is listed more than once".to_string(),
+            ));
+        }
+        if self.rounds.contains_key(&output_id) ||
+            self.output_manager_service
+                .get_shared_outputs()
+                .await?
+                .iter()
+                .any(|o| o.output_id == output_id)
+        {
+            debug!(
+                target: LOG_TARGET,
+                "Ignoring shared output proposal with known Id: {}", output_id
+            );
+            return Ok(());
+        }
+
+        self.rounds.insert(
+            output_id,
+            SharedOutputRound::Joining(JoiningRound {
+                initiator: source.clone(),
+                value,
+                participants,
+                key_share: None,
+                started: Instant::now(),
+            }),
+        );
+
+        self.publish_event(SharedOutputEvent::ProposalReceived((output_id, source, value)))
+            .await
+    }
+
+    /// Accept a shared output proposed by another wallet and join it by sending the initiator the public key of a new
+    /// key share
+    async fn accept_proposal(&mut self, output_id: u64) -> Result<(), SharedOutputServiceError> {
+        let (initiator, public_key_share) = match self.rounds.get_mut(&output_id) {
+            Some(SharedOutputRound::Joining(round)) => {
+                if round.key_share.is_some() {
+                    return Ok(());
+                }
+                let key_share = PrivateKey::random(&mut rand::OsRng::new().unwrap());
+                let public_key_share = PublicKey::from_secret_key(&key_share);
+                round.key_share = Some(key_share);
+                (round.initiator.clone(), public_key_share)
+            },
+            _ => return Err(SharedOutputServiceError::RoundNotFound),
+        };
+
+        if let Err(e) = self
+            .send_message(initiator, output_id, SharedOutputStep::PublicKeyShare(public_key_share))
+            .await
+        {
+            self.cancel_round(output_id, "The public key share could not be sent".to_string())
+                .await;
+            return Err(e);
+        }
+
+        Ok(())
+    }
+
+    /// Record the public key share of a participant of a proposed shared output. Once every participant has sent one,
+    /// all of the public key shares are sent to the participants.
+    async fn accept_public_key_share(
+        &mut self,
+        source: CommsPublicKey,
+        output_id: u64,
+        public_key: PublicKey,
+    ) -> Result<(), SharedOutputServiceError>
+    {
+        let (recipients, public_keys) = {
+            let round = match self.rounds.get_mut(&output_id) {
+                Some(SharedOutputRound::Proposal(round)) if round.joint_key.is_none() => round,
+                _ => return Ok(()),
+            };
+            let index = match round.participants.iter().position(|p| p == &source) {
+                Some(index) if index > 0 => index,
+                _ => return Ok(()),
+            };
+            if round
+                .public_key_shares
+                .iter()
+                .enumerate()
+                .any(|(i, k)| i != index && k.as_ref() == Some(&public_key))
+            {
+                return Err(SharedOutputServiceError::InvalidMessageError(
+                    "Public key share is not unique".to_string(),
+                ));
+            }
+            round.public_key_shares[index] = Some(public_key);
+
+            let public_keys = match round.public_key_shares.iter().cloned().collect::<Option<Vec<_>>>() {
+                None => return Ok(()),
+                Some(public_keys) => public_keys,
+            };
+            round.joint_key = Some(build_joint_key(&public_keys)?);
+            (round.participants[1..].to_vec(), public_keys)
+        };
+
+        self.send_to_all(recipients, output_id, SharedOutputStep::PublicKeys(public_keys))
+            .await
+    }
+
+    /// Store the shared output that this wallet has joined once the public key shares of all participants are known,
+    /// and send the initiator this wallet's weighted key share
+    async fn accept_public_keys(
+        &mut self,
+        source: CommsPublicKey,
+        output_id: u64,
+        public_keys: Vec<PublicKey>,
+    ) -> Result<(), SharedOutputServiceError>
+    {
+        let own_public_key = self.node_identity.public_key().clone();
+        let (output, weighted_share) = {
+            let (round, key_share) = match self.rounds.get(&output_id) {
+                Some(SharedOutputRound::Joining(round)) if round.initiator == source => {
+                    match round.key_share.as_ref() {
+                        Some(key_share) => (round, key_share),
+                        None => return Ok(()),
+                    }
+                },
+                _ => return Ok(()),
+            };
+            let public_key_share = PublicKey::from_secret_key(key_share);
+            if public_keys.len() != round.participants.len() ||
+                round
+                    .participants
+                    .iter()
+                    .zip(public_keys.iter())
+                    .any(|(p, k)| p == &own_public_key && k != &public_key_share)
+            {
+                return Err(SharedOutputServiceError::InvalidMessageError(
+                    "The public key shares do not include ours".to_string(),
+                ));
+            }
+            let joint_key = build_joint_key(&public_keys)?;
+            let weighted_share = weighted_key_share(&joint_key, &public_key_share, key_share)?;
+            let output = SharedOutput {
+                output_id,
+                value: round.value,
+                initiator: source.clone(),
+                participants: round
+                    .participants
+                    .iter()
+                    .cloned()
+                    .zip(public_keys.into_iter())
+                    .collect(),
+                key_share: key_share.clone(),
+                commitment: None,
+                status: SharedOutputStatus::Pending,
+            };
+            (output, weighted_share)
+        };
+
+        self.output_manager_service.save_shared_output(output).await?;
+        self.send_message(source, output_id, SharedOutputStep::KeyShare(weighted_share))
+            .await?;
+        self.rounds.remove(&output_id);
+
+        Ok(())
+    }
+
+    /// Record the weighted key share of a participant of a proposed shared output, after checking it against the
+    /// participant's public key share. Once every participant has sent one, the shared output is funded.
+    async fn accept_key_share(
+        &mut self,
+        source: CommsPublicKey,
+        output_id: u64,
+        weighted_share: PrivateKey,
+    ) -> Result<(), SharedOutputServiceError>
+    {
+        {
+            let round = match self.rounds.get_mut(&output_id) {
+                Some(SharedOutputRound::Proposal(round)) => round,
+                _ => return Ok(()),
+            };
+            let index = match round.participants.iter().position(|p| p == &source) {
+                Some(index) if index > 0 => index,
+                _ => return Ok(()),
+            };
+            match (round.joint_key.as_ref(), round.public_key_shares[index].as_ref()) {
+                (Some(joint_key), Some(public_key)) => {
+                    if !verify_weighted_key_share(joint_key, public_key, &weighted_share)? {
+                        return Err(SharedOutputServiceError::InvalidMessageError(format!(
+                            "Invalid key share from participant {}",
+                            index
+                        )));
+                    }
+                },
+                _ => return Ok(()),
+            }
+            round.weighted_shares[index] = Some(weighted_share);
+            if !round.weighted_shares[1..].iter().all(Option::is_some) {
+                return Ok(());
+            }
+        }
+
+        self.fund_shared_output(output_id).await
+    }
+
+    /// Fund a proposed shared output once the weighted key shares of all participants have been received, and let the
+    /// participants know its commitment. The blinding factor that the key shares add up to is only used to create the
+    /// funding transaction.
+    async fn fund_shared_output(&mut self, output_id: u64) -> Result<(), SharedOutputServiceError> {
+        let (output, commitment, blinding_factor, fee_per_gram, message) = {
+            let round = match self.rounds.get(&output_id) {
+                Some(SharedOutputRound::Proposal(round)) => round,
+                _ => return Ok(()),
+            };
+            let (joint_key, public_keys) = match (
+                round.joint_key.as_ref(),
+                round.public_key_shares.iter().cloned().collect::<Option<Vec<_>>>(),
+            ) {
+                (Some(joint_key), Some(public_keys)) => (joint_key, public_keys),
+                _ => return Ok(()),
+            };
+            let mut weighted_shares = vec![weighted_key_share(joint_key, &public_keys[0], &round.key_share)?];
+            weighted_shares.extend(round.weighted_shares.iter().filter_map(|s| s.clone()));
+            let blinding_factor = dealer_blinding_factor(joint_key, &weighted_shares)?;
+            let commitment = self
+                .factories
+                .commitment
+                .commit_value(&blinding_factor, round.value.into());
+            let output = SharedOutput {
+                output_id,
+                value: round.value,
+                initiator: self.node_identity.public_key().clone(),
+                participants: round
+                    .participants
+                    .iter()
+                    .cloned()
+                    .zip(public_keys.into_iter())
+                    .collect(),
+                key_share: round.key_share.clone(),
+                commitment: Some(commitment.clone()),
+                status: SharedOutputStatus::Unspent,
+            };
+            (
+                output,
+                commitment,
+                blinding_factor,
+                round.fee_per_gram,
+                round.message.clone(),
+            )
+        };
+
+        let (tx_id, fee, amount, transaction) = self
+            .output_manager_service
+            .create_shared_output_funding(output.value, blinding_factor, fee_per_gram, message.clone())
+            .await?;
+        self.transaction_service
+            .submit_transaction_to_self(tx_id, amount, fee, transaction, message)
+            .await?;
+        self.rounds.remove(&output_id);
+        info!(
+            target: LOG_TARGET,
+            "Shared output (Id: {}) funded by transaction (TxId: {})", output_id, tx_id
+        );
+
+        let recipients = self.other_participants(&output);
+        self.output_manager_service.save_shared_output(output).await?;
+        self.publish_event(SharedOutputEvent::OutputCreated(output_id)).await?;
+
+        self.send_to_all(recipients, output_id, SharedOutputStep::Created(commitment))
+            .await
+    }
+
+    /// Mark a shared output that this wallet has joined as funded, after checking that its commitment matches the
+    /// joint key and value
+    async fn accept_created_output(
+        &mut self,
+        source: CommsPublicKey,
+        output_id: u64,
+        commitment: Commitment,
+    ) -> Result<(), SharedOutputServiceError>
+    {
+        let mut output = match self.fetch_shared_output(output_id).await {
+            Ok(output) if output.initiator == source && output.status == SharedOutputStatus::Pending => output,
+            _ => return Ok(()),
+        };
+        let joint_key = build_joint_key(&key_share_public_keys(&output))?;
+        if shared_output_commitment(&joint_key, output.value, &self.factories.commitment) != commitment {
+            return Err(SharedOutputServiceError::InvalidMessageError(
+                "The commitment does not match the joint key and value of the shared output".to_string(),
+            ));
+        }
+        output.commitment = Some(commitment);
+        output.status = SharedOutputStatus::Unspent;
+        self.output_manager_service.save_shared_output(output).await?;
+
+        self.publish_event(SharedOutputEvent::OutputCreated(output_id)).await
+    }
+
+    /// Start a spend of a shared output to this wallet by asking the other participants to sign it
+    async fn spend_shared_output(
+        &mut self,
+        output_id: u64,
+        fee_per_gram: MicroTari,
+        message: String,
+    ) -> Result<(), SharedOutputServiceError>
+    {
+        if self.rounds.contains_key(&output_id) {
+            return Err(SharedOutputServiceError::RoundInProgress);
+        }
+        let output = self.fetch_shared_output(output_id).await?;
+        if output.status != SharedOutputStatus::Unspent || output.commitment.is_none() {
+            return Err(SharedOutputServiceError::SharedOutputNotSpendable);
+        }
+        let fee = Fee::calculate(fee_per_gram, 1, 1);
+        if fee >= output.value {
+            return Err(SharedOutputServiceError::FeeExceedsValue);
+        }
+
+        let metadata = TransactionMetadata {
+            fee,
+            ..Default::default()
+        };
+        let public_key_share = PublicKey::from_secret_key(&output.key_share);
+        let joint_key = build_joint_key(&key_share_public_keys(&output))?;
+        let mut spend = SharedOutputSpend::new(joint_key, public_key_share.clone(), metadata.clone())?;
+        let nonce = PrivateKey::random(&mut rand::OsRng::new().unwrap());
+        spend.add_nonce_commitment(&public_key_share, nonce_commitment(&PublicKey::from_secret_key(&nonce)))?;
+
+        let recipients = self.other_participants(&output);
+        self.rounds.insert(
+            output_id,
+            SharedOutputRound::Spend(SpendRound {
+                output,
+                spend,
+                nonce,
+                message,
+                started: Instant::now(),
+            }),
+        );
+        if let Err(e) = self
+            .send_to_all(recipients, output_id, SharedOutputStep::SpendRequest(metadata))
+            .await
+        {
+            self.cancel_round(output_id, "The spend request could not be sent".to_string())
+                .await;
+            return Err(e);
+        }
+
+        Ok(())
+    }
+
+    /// Record a request by another participant to spend a shared output. This wallet only signs the spend once it has
+    /// been approved.
+    async fn accept_spend_request(
+        &mut self,
+        source: CommsPublicKey,
+        output_id: u64,
+        metadata: TransactionMetadata,
+    ) -> Result<(), SharedOutputServiceError>
+    {
+        let output = match self.fetch_shared_output(output_id).await {
+            Ok(output) if self.other_participants(&output).contains(&source) => output,
+            _ => return Ok(()),
+        };
+        if self.rounds.contains_key(&output_id) {
+            return self
+                .send_message(
+                    source,
+                    output_id,
+                    SharedOutputStep::Cancelled("Another round is in progress".to_string()),
+                )
+                .await;
+        }
+        if output.status != SharedOutputStatus::Unspent || metadata.fee >= output.value {
+            return self
+                .send_message(
+                    source,
+                    output_id,
+                    SharedOutputStep::Cancelled("The shared output cannot be spent".to_string()),
+                )
+                .await;
+        }
+
+        let fee = metadata.fee;
+        self.rounds.insert(
+            output_id,
+            SharedOutputRound::Signing(SigningRound {
+                coordinator: source,
+                metadata,
+                nonce: None,
+                started: Instant::now(),
+            }),
+        );
+
+        self.publish_event(SharedOutputEvent::SpendRequested((output_id, fee)))
+            .await
+    }
+
+    /// Approve a requested spend of a shared output by committing to a nonce to sign it with
+    async fn approve_spend(&mut self, output_id: u64) -> Result<(), SharedOutputServiceError> {
+        let (coordinator, public_nonce) = match self.rounds.get_mut(&output_id) {
+            Some(SharedOutputRound::Signing(round)) => {
+                if round.nonce.is_some() {
+                    return Ok(());
+                }
+                let nonce = PrivateKey::random(&mut rand::OsRng::new().unwrap());
+                let public_nonce = PublicKey::from_secret_key(&nonce);
+                round.nonce = Some(nonce);
+                (round.coordinator.clone(), public_nonce)
+            },
+            _ => return Err(SharedOutputServiceError::RoundNotFound),
+        };
+
+        if let Err(e) = self
+            .send_message(
+                coordinator,
+                output_id,
+                SharedOutputStep::NonceCommitment(nonce_commitment(&public_nonce)),
+            )
+            .await
+        {
+            self.cancel_round(output_id, "The nonce commitment could not be sent".to_string())
+                .await;
+            return Err(e);
+        }
+
+        Ok(())
+    }
+
+    /// Record the nonce commitment of a participant of a spend. Once every participant has sent one, the participants
+    /// are asked to reveal their nonces.
+    async fn accept_nonce_commitment(
+        &mut self,
+        source: CommsPublicKey,
+        output_id: u64,
+        commitment: MessageHash,
+    ) -> Result<(), SharedOutputServiceError>
+    {
+        let own_public_key = self.node_identity.public_key().clone();
+        let recipients = {
+            let round = match self.rounds.get_mut(&output_id) {
+                Some(SharedOutputRound::Spend(round)) if !round.spend.has_all_nonce_commitments() => round,
+                _ => return Ok(()),
+            };
+            let public_key = match key_share_public_key_of(&round.output, &source) {
+                Some(public_key) if source != own_public_key => public_key,
+                _ => return Ok(()),
+            };
+            round.spend.add_nonce_commitment(&public_key, commitment)?;
+            if !round.spend.has_all_nonce_commitments() {
+                return Ok(());
+            }
+            round.spend.add_public_nonce(
+                &PublicKey::from_secret_key(&round.output.key_share),
+                PublicKey::from_secret_key(&round.nonce),
+            )?;
+            round.counterparties(&own_public_key)
+        };
+
+        self.send_to_all(recipients, output_id, SharedOutputStep::RevealNonces)
+            .await
+    }
+
+    /// Reveal the nonce of an approved spend to its coordinator
+    async fn reveal_nonce(&mut self, source: CommsPublicKey, output_id: u64) -> Result<(), SharedOutputServiceError> {
+        let public_nonce = match self.rounds.get(&output_id) {
+            Some(SharedOutputRound::Signing(SigningRound {
+                coordinator,
+                nonce: Some(nonce),
+                ..
+            })) if coordinator == &source => PublicKey::from_secret_key(nonce),
+            _ => return Ok(()),
+        };
+
+        self.send_message(source, output_id, SharedOutputStep::PublicNonce(public_nonce))
+            .await
+    }
+
+    /// Record the public nonce of a participant of a spend, which has to match its commitment. Once every participant
+    /// has revealed its nonce, the participants are asked to sign the spend.
+    async fn accept_public_nonce(
+        &mut self,
+        source: CommsPublicKey,
+        output_id: u64,
+        public_nonce: PublicKey,
+    ) -> Result<(), SharedOutputServiceError>
+    {
+        let own_public_key = self.node_identity.public_key().clone();
+        let (recipients, public_nonces) = {
+            let round = match self.rounds.get_mut(&output_id) {
+                Some(SharedOutputRound::Spend(round)) if round.spend.public_nonces().is_none() => round,
+                _ => return Ok(()),
+            };
+            let public_key = match key_share_public_key_of(&round.output, &source) {
+                Some(public_key) if source != own_public_key => public_key,
+                _ => return Ok(()),
+            };
+            round.spend.add_public_nonce(&public_key, public_nonce)?;
+            match round.spend.public_nonces() {
+                None => return Ok(()),
+                Some(public_nonces) => (round.counterparties(&own_public_key), public_nonces),
+            }
+        };
+
+        self.send_to_all(recipients, output_id, SharedOutputStep::SignRequest(public_nonces))
+            .await
+    }
+
+    /// Send the coordinator of an approved spend this wallet's partial signature. The nonce is discarded, so that it
+    /// can never sign anything else.
+    async fn sign_spend(
+        &mut self,
+        source: CommsPublicKey,
+        output_id: u64,
+        public_nonces: Vec<PublicKey>,
+    ) -> Result<(), SharedOutputServiceError>
+    {
+        let (metadata, nonce) = match self.rounds.get(&output_id) {
+            Some(SharedOutputRound::Signing(SigningRound {
+                coordinator,
+                metadata,
+                nonce: Some(nonce),
+                ..
+            })) if coordinator == &source => (metadata.clone(), nonce.clone()),
+            _ => return Ok(()),
+        };
+        let output = self.fetch_shared_output(output_id).await?;
+        if public_nonces.len() != output.participants.len() {
+            return Err(SharedOutputServiceError::InvalidMessageError(
+                "Expected a public nonce for every participant".to_string(),
+            ));
+        }
+        let joint_key = build_joint_key(&key_share_public_keys(&output))?;
+        let signature = partial_signature(
+            &joint_key,
+            &PublicKey::from_secret_key(&output.key_share),
+            &output.key_share,
+            &nonce,
+            &public_nonces,
+            &metadata,
+            None,
+        )?;
+        self.rounds.remove(&output_id);
+
+        self.send_message(source, output_id, SharedOutputStep::PartialSignature(signature))
+            .await
+    }
+
+    /// Record the partial signature of a participant of a spend. Once every participant has sent one, the spending
+    /// transaction is built and broadcast.
+    async fn accept_partial_signature(
+        &mut self,
+        source: CommsPublicKey,
+        output_id: u64,
+        signature: Signature,
+    ) -> Result<(), SharedOutputServiceError>
+    {
+        {
+            let round = match self.rounds.get_mut(&output_id) {
+                Some(SharedOutputRound::Spend(round)) => round,
+                _ => return Ok(()),
+            };
+            let public_key = match key_share_public_key_of(&round.output, &source) {
+                Some(public_key) => public_key,
+                None => return Ok(()),
+            };
+            round.spend.add_partial_signature(&public_key, signature)?;
+            if !round.spend.has_all_partial_signatures() {
+                return Ok(());
+            }
+        }
+
+        self.complete_spend(output_id).await
+    }
+
+    /// Build the transaction that spends a shared output to a new output of this wallet, submit it and let the other
+    /// participants know that the shared output has been spent
+    async fn complete_spend(&mut self, output_id: u64) -> Result<(), SharedOutputServiceError> {
+        let (value, fee, message) = match self.rounds.get(&output_id) {
+            Some(SharedOutputRound::Spend(round)) => {
+                (round.output.value, round.spend.metadata().fee, round.message.clone())
+            },
+            _ => return Ok(()),
+        };
+        let mut rng = rand::OsRng::new().unwrap();
+        let tx_id = rng.next_u64();
+        let amount = value - fee;
+        let spending_key = self
+            .output_manager_service
+            .get_recipient_spending_key(tx_id, amount)
+            .await?;
+        let transaction = match self.output_manager_service.get_rewind_data().await {
+            Ok(rewind_data) => self.build_spend_transaction(
+                output_id,
+                UnblindedOutput::new(amount, spending_key, None),
+                &rewind_data,
+                PrivateKey::random(&mut rng),
+            ),
+            Err(e) => Err(e.into()),
+        };
+        let transaction = match transaction {
+            Ok(transaction) => transaction,
+            Err(e) => {
+                let _ = self.output_manager_service.cancel_transaction(tx_id).await;
+                return Err(e);
+            },
+        };
+        self.transaction_service
+            .submit_transaction_to_self(tx_id, amount, fee, transaction, message)
+            .await?;
+        info!(
+            target: LOG_TARGET,
+            "Shared output (Id: {}) spent by transaction (TxId: {})", output_id, tx_id
+        );
+
+        let mut output = match self.rounds.remove(&output_id) {
+            Some(SharedOutputRound::Spend(round)) => round.output,
+            _ => return Ok(()),
+        };
+        output.status = SharedOutputStatus::Spent;
+        let recipients = self.other_participants(&output);
+        self.output_manager_service.save_shared_output(output).await?;
+        self.publish_event(SharedOutputEvent::OutputSpent(output_id)).await?;
+
+        self.send_to_all(recipients, output_id, SharedOutputStep::Spent).await
+    }
+
+    /// Sign the kernel of a spend with this wallet's key share and the excess key of the output it receives, and
+    /// combine the signature with those of the other participants into the spending transaction
+    fn build_spend_transaction(
+        &self,
+        output_id: u64,
+        output: UnblindedOutput,
+        rewind_data: &RewindData,
+        offset: PrivateKey,
+    ) -> Result<Transaction, SharedOutputServiceError>
+    {
+        let round = match self.rounds.get(&output_id) {
+            Some(SharedOutputRound::Spend(round)) => round,
+            _ => return Err(SharedOutputServiceError::RoundNotFound),
+        };
+        let commitment = round
+            .output
+            .commitment
+            .clone()
+            .ok_or(SharedOutputServiceError::SharedOutputNotSpendable)?;
+        let public_nonces = round
+            .spend
+            .public_nonces()
+            .ok_or(SharedOutputServiceError::RoundNotFound)?;
+        let excess_key = &output.spending_key - &offset;
+        let joint_key = build_joint_key(&key_share_public_keys(&round.output))?;
+        let own_signature = partial_signature(
+            &joint_key,
+            &PublicKey::from_secret_key(&round.output.key_share),
+            &round.output.key_share,
+            &round.nonce,
+            &public_nonces,
+            round.spend.metadata(),
+            Some(&excess_key),
+        )?;
+        let output = output.as_rewindable_transaction_output(&self.factories, rewind_data)?;
+
+        Ok(round.spend.build_transaction(
+            own_signature,
+            TransactionInput::new(OutputFeatures::default(), commitment),
+            output,
+            offset,
+            &excess_key,
+            &self.factories,
+        )?)
+    }
+
+    /// Mark a shared output as spent when another participant reports that it has broadcast the spending transaction
+    async fn accept_spent_output(
+        &mut self,
+        source: CommsPublicKey,
+        output_id: u64,
+    ) -> Result<(), SharedOutputServiceError>
+    {
+        let mut output = match self.fetch_shared_output(output_id).await {
+            Ok(output)
+                if output.status == SharedOutputStatus::Unspent &&
+                    self.other_participants(&output).contains(&source) =>
+            {
+                output
+            },
+            _ => return Ok(()),
+        };
+        output.status = SharedOutputStatus::Spent;
+        self.output_manager_service.save_shared_output(output).await?;
+
+        self.publish_event(SharedOutputEvent::OutputSpent(output_id)).await
+    }
+
+    /// Drop the round in progress for a shared output if the wallet that cancelled it takes part in it
+    async fn accept_cancellation(&mut self, source: CommsPublicKey, output_id: u64, reason: String) {
+        let own_public_key = self.node_identity.public_key().clone();
+        match self.rounds.get(&output_id) {
+            Some(round) if round.counterparties(&own_public_key).contains(&source) => (),
+            _ => return,
+        }
+        self.rounds.remove(&output_id);
+        info!(
+            target: LOG_TARGET,
+            "Shared output round (Id: {}) cancelled by another participant: {}", output_id, reason
+        );
+        let _ = self.publish_event(SharedOutputEvent::RoundCancelled(output_id)).await;
+    }
+
+    /// Drop the round in progress for a shared output and let the other wallets taking part in it know. Returns whether
+    /// there was a round in progress.
+    async fn cancel_round(&mut self, output_id: u64, reason: String) -> bool {
+        let round = match self.rounds.remove(&output_id) {
+            None => return false,
+            Some(round) => round,
+        };
+        for participant in round.counterparties(self.node_identity.public_key()) {
+            if let Err(e) = self
+                .send_message(participant, output_id, SharedOutputStep::Cancelled(reason.clone()))
+                .await
+            {
+                warn!(
+                    target: LOG_TARGET,
+                    "Could not send cancellation of shared output round (Id: {}): {:?}", output_id, e
+                );
+            }
+        }
+        let _ = self.publish_event(SharedOutputEvent::RoundCancelled(output_id)).await;
+        true
+    }
+
+    /// Cancel the round in progress for a shared output after a failure, and publish the failure on the event stream
+    async fn abort_round(&mut self, output_id: u64, error: SharedOutputServiceError) {
+        self.cancel_round(output_id, "The round failed".to_string()).await;
+        let _ = self
+            .publish_event(SharedOutputEvent::Error(format!(
+                "Shared output round (Id: {}) failed: {:?}",
+                output_id, error
+            )))
+            .await;
+    }
+
+    /// Cancel the rounds that have not been completed within the round timeout
+    async fn cancel_timed_out_rounds(&mut self) {
+        let timed_out = self
+            .rounds
+            .iter()
+            .filter(|(_, round)| round.started().elapsed() > self.config.round_timeout)
+            .map(|(output_id, _)| *output_id)
+            .collect::<Vec<_>>();
+        for output_id in timed_out {
+            warn!(target: LOG_TARGET, "Shared output round (Id: {}) timed out", output_id);
+            self.cancel_round(output_id, "Timed out".to_string()).await;
+        }
+    }
+
+    async fn fetch_shared_output(&mut self, output_id: u64) -> Result<SharedOutput, SharedOutputServiceError> {
+        self.output_manager_service
+            .get_shared_outputs()
+            .await?
+            .into_iter()
+            .find(|o| o.output_id == output_id)
+            .ok_or(SharedOutputServiceError::SharedOutputNotFound)
+    }
+
+    /// The comms public keys of the participants of a shared output other than this wallet
+    fn other_participants(&self, output: &SharedOutput) -> Vec<CommsPublicKey> {
+        output
+            .participants
+            .iter()
+            .map(|(p, _)| p.clone())
+            .filter(|p| p != self.node_identity.public_key())
+            .collect()
+    }
+
+    async fn send_to_all(
+        &mut self,
+        recipients: Vec<CommsPublicKey>,
+        output_id: u64,
+        step: SharedOutputStep,
+    ) -> Result<(), SharedOutputServiceError>
+    {
+        let mut result = Ok(());
+        for recipient in recipients {
+            if let Err(e) = self.send_message(recipient, output_id, step.clone()).await {
+                result = Err(e);
+            }
+        }
+        result
+    }
+
+    async fn send_message(
+        &mut self,
+        recipient: CommsPublicKey,
+        output_id: u64,
+        step: SharedOutputStep,
+    ) -> Result<(), SharedOutputServiceError>
+    {
+        let message = proto::SharedOutputMessage::from(SharedOutputMessage::new(output_id, step));
+        if let SendMessageResponse::Failed = self
+            .outbound_message_service
+            .send_direct(
+                recipient,
+                OutboundEncryption::EncryptForPeer,
+                OutboundDomainMessage::new(TariMessageType::SharedOutput, message),
+            )
+            .await
+            .map_err(|_| SharedOutputServiceError::OutboundSendFailure)?
+        {
+            return Err(SharedOutputServiceError::OutboundSendFailure);
+        }
+
+        Ok(())
+    }
+
+    async fn publish_event(&mut self, event: SharedOutputEvent) -> Result<(), SharedOutputServiceError> {
+        self.event_publisher
+            .send(event)
+            .await
+            .map_err(|_| SharedOutputServiceError::EventStreamError)
+    }
+}
+
+/// The public keys of the key shares of the participants of a shared output, in the order of the proposal
+fn key_share_public_keys(output: &SharedOutput) -> Vec<PublicKey> {
+    output.participants.iter().map(|(_, k)| k.clone()).collect()
+}
+
+/// The public key of the key share of the participant of a shared output with the given comms public key
+fn key_share_public_key_of(output: &SharedOutput, participant: &CommsPublicKey) -> Option<PublicKey> {
+    output
+        .participants
+        .iter()
+        .find(|(p, _)| p == participant)
+        .map(|(_, k)| k.clone())
+}
+
+/// A round of the protocol in progress for a shared output. Only one round can be in progress for an output at a time.
+enum SharedOutputRound {
+    Proposal(ProposalRound),
+    Joining(JoiningRound),
+    Spend(SpendRound),
+    Signing(SigningRound),
+}
+
+impl SharedOutputRound {
+    fn started(&self) -> Instant {
+        match self {
+            SharedOutputRound::Proposal(round) => round.started,
+            SharedOutputRound::Joining(round) => round.started,
+            SharedOutputRound::Spend(round) => round.started,
+            SharedOutputRound::Signing(round) => round.started,
+        }
+    }
+
+    /// The comms public keys of the other wallets taking part in the round
+    fn counterparties(&self, own_public_key: &CommsPublicKey) -> Vec<CommsPublicKey> {
+        match self {
+            SharedOutputRound::Proposal(round) => round.participants[1..].to_vec(),
+            SharedOutputRound::Joining(round) => vec![round.initiator.clone()],
+            SharedOutputRound::Spend(round) => round
+                .output
+                .participants
+                .iter()
+                .map(|(p, _)| p.clone())
+                .filter(|p| p != own_public_key)
+                .collect(),
+            SharedOutputRound::Signing(round) => vec![round.coordinator.clone()],
+        }
+    }
+}
+
+/// A shared output proposed by this wallet, which is funded once all of the participants have sent their key shares.
+/// This wallet is the first participant.
+struct ProposalRound {
+    value: MicroTari,
+    fee_per_gram: MicroTari,
+    message: String,
+    participants: Vec<CommsPublicKey>,
+    key_share: PrivateKey,
+    public_key_shares: Vec<Option<PublicKey>>,
+    joint_key: Option<SharedOutputJointKey>,
+    weighted_shares: Vec<Option<PrivateKey>>,
+    started: Instant,
+}
+
+/// A shared output proposed by another wallet. The key share is chosen once the proposal is accepted, after which the
+/// round waits for the public key shares of all of the participants.
+struct JoiningRound {
+    initiator: CommsPublicKey,
+    value: MicroTari,
+    participants: Vec<CommsPublicKey>,
+    key_share: Option<PrivateKey>,
+    started: Instant,
+}
+
+/// A spend of a shared output that this wallet coordinates
+struct SpendRound {
+    output: SharedOutput,
+    spend: SharedOutputSpend,
+    nonce: PrivateKey,
+    message: String,
+    started: Instant,
+}
+
+/// A spend of a shared output requested by another participant. The nonce is chosen once the spend is approved.
+struct SigningRound {
+    coordinator: CommsPublicKey,
+    metadata: TransactionMetadata,
+    nonce: Option<PrivateKey>,
+    started: Instant,
+}
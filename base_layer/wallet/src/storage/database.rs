@@ -20,10 +20,16 @@
 // WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
 // USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
-use crate::error::WalletStorageError;
+use crate::{
+    encryption::{EncryptionSettings, PendingEncryption, SecretCipher},
+    error::WalletStorageError,
+};
 use log::*;
 use std::fmt::{Display, Error, Formatter};
-use tari_comms::{peer_manager::Peer, types::CommsPublicKey};
+use tari_comms::{
+    peer_manager::Peer,
+    types::{CommsPublicKey, CommsSecretKey},
+};
 
 const LOG_TARGET: &'static str = "wallet::contacts_service::database";
 
@@ -33,21 +39,40 @@ pub trait WalletBackend: Send + Sync {
     fn fetch(&self, key: &DbKey) -> Result<Option<DbValue>, WalletStorageError>;
     /// Modify the state the of the backend with a write operation
     fn write(&mut self, op: WriteOperation) -> Result<Option<DbValue>, WalletStorageError>;
+    /// This method must complete the pending encryption change in a single write: re-encrypt all the stored secrets
+    /// with the provided cipher or, if no cipher is provided, remove them rather than store them in plain form,
+    /// replace the encryption settings with those of the pending change and remove the pending change. The backend
+    /// must not be locked.
+    fn apply_encryption(&mut self, cipher: Option<SecretCipher>) -> Result<(), WalletStorageError>;
+    /// This method must make the stored secrets of an encrypted backend accessible using the provided cipher. An
+    /// incorrect cipher must be rejected.
+    fn unlock(&mut self, cipher: SecretCipher) -> Result<(), WalletStorageError>;
+    /// This method must discard the cipher of an encrypted backend so that its stored secrets are no longer
+    /// accessible until it is unlocked again.
+    fn lock(&mut self) -> Result<(), WalletStorageError>;
 }
 
 #[derive(Debug, Clone, PartialEq)]
 pub enum DbKey {
     Peer(CommsPublicKey),
     Peers,
+    EncryptionSettings,
+    PendingEncryption,
+    CommsSecretKey,
 }
 
 pub enum DbValue {
     Peer(Box<Peer>),
     Peers(Vec<Peer>),
+    EncryptionSettings(Box<EncryptionSettings>),
+    PendingEncryption(Box<PendingEncryption>),
+    CommsSecretKey(Box<CommsSecretKey>),
 }
 
 pub enum DbKeyValuePair {
     Peer(CommsPublicKey, Peer),
+    PendingEncryption(PendingEncryption),
+    CommsSecretKey(CommsSecretKey),
 }
 
 pub enum WriteOperation {
@@ -113,11 +138,74 @@ where T: WalletBackend
             .ok_or(WalletStorageError::ValueNotFound(DbKey::Peer(pub_key.clone())))?
         {
             DbValue::Peer(c) => Ok(*c),
-            DbValue::Peers(_) => Err(WalletStorageError::UnexpectedResult(
+            _ => Err(WalletStorageError::UnexpectedResult(
                 "Incorrect response from backend.".to_string(),
             )),
         }
     }
+
+    /// The settings of the passphrase the wallet's secrets are encrypted with, if they are encrypted
+    pub fn get_encryption_settings(&self) -> Result<Option<EncryptionSettings>, WalletStorageError> {
+        match self.db.fetch(&DbKey::EncryptionSettings) {
+            Ok(None) => Ok(None),
+            Ok(Some(DbValue::EncryptionSettings(s))) => Ok(Some(*s)),
+            Ok(Some(other)) => unexpected_result(DbKey::EncryptionSettings, other),
+            Err(e) => log_error(DbKey::EncryptionSettings, e),
+        }
+    }
+
+    /// The encryption change that has been started but not completed, if there is one
+    pub fn get_pending_encryption(&self) -> Result<Option<PendingEncryption>, WalletStorageError> {
+        match self.db.fetch(&DbKey::PendingEncryption) {
+            Ok(None) => Ok(None),
+            Ok(Some(DbValue::PendingEncryption(p))) => Ok(Some(*p)),
+            Ok(Some(other)) => unexpected_result(DbKey::PendingEncryption, other),
+            Err(e) => log_error(DbKey::PendingEncryption, e),
+        }
+    }
+
+    /// Store an encryption change before any secret is re-encrypted. The change takes effect when it is completed
+    /// with `apply_encryption`.
+    pub fn set_pending_encryption(&mut self, pending: PendingEncryption) -> Result<(), WalletStorageError> {
+        self.db
+            .write(WriteOperation::Insert(DbKeyValuePair::PendingEncryption(pending)))?;
+        Ok(())
+    }
+
+    /// The secret key of the wallet's comms node identity, if it has been stored
+    pub fn get_comms_secret_key(&self) -> Result<Option<CommsSecretKey>, WalletStorageError> {
+        match self.db.fetch(&DbKey::CommsSecretKey) {
+            Ok(None) => Ok(None),
+            Ok(Some(DbValue::CommsSecretKey(k))) => Ok(Some(*k)),
+            Ok(Some(other)) => unexpected_result(DbKey::CommsSecretKey, other),
+            Err(e) => log_error(DbKey::CommsSecretKey, e),
+        }
+    }
+
+    pub fn set_comms_secret_key(&mut self, secret_key: CommsSecretKey) -> Result<(), WalletStorageError> {
+        self.db
+            .write(WriteOperation::Insert(DbKeyValuePair::CommsSecretKey(secret_key)))?;
+        Ok(())
+    }
+
+    pub fn remove_comms_secret_key(&mut self) -> Result<(), WalletStorageError> {
+        self.db.write(WriteOperation::Remove(DbKey::CommsSecretKey))?;
+        Ok(())
+    }
+
+    /// Complete the pending encryption change by encrypting the stored secrets with the provided cipher, or removing
+    /// them if no cipher is provided, and replacing the encryption settings with those of the change
+    pub fn apply_encryption(&mut self, cipher: Option<SecretCipher>) -> Result<(), WalletStorageError> {
+        self.db.apply_encryption(cipher)
+    }
+
+    pub fn unlock(&mut self, cipher: SecretCipher) -> Result<(), WalletStorageError> {
+        self.db.unlock(cipher)
+    }
+
+    pub fn lock(&mut self) -> Result<(), WalletStorageError> {
+        self.db.lock()
+    }
 }
 
 fn unexpected_result<T>(req: DbKey, res: DbValue) -> Result<T, WalletStorageError> {
@@ -131,6 +219,9 @@ impl Display for DbKey {
         match self {
             DbKey::Peer(c) => f.write_str(&format!("Peer: {:?}", c)),
             DbKey::Peers => f.write_str(&format!("Peers")),
+            DbKey::EncryptionSettings => f.write_str(&format!("Encryption Settings")),
+            DbKey::PendingEncryption => f.write_str(&format!("Pending Encryption")),
+            DbKey::CommsSecretKey => f.write_str(&format!("Comms Secret Key")),
         }
     }
 }
@@ -140,6 +231,9 @@ impl Display for DbValue {
         match self {
             DbValue::Peer(_) => f.write_str(&format!("Peer")),
             DbValue::Peers(_) => f.write_str(&format!("Peers")),
+            DbValue::EncryptionSettings(_) => f.write_str(&format!("Encryption Settings")),
+            DbValue::PendingEncryption(_) => f.write_str(&format!("Pending Encryption")),
+            DbValue::CommsSecretKey(_) => f.write_str(&format!("Comms Secret Key")),
         }
     }
 }
@@ -157,7 +251,8 @@ fn log_error<T>(req: DbKey, err: WalletStorageError) -> Result<T, WalletStorageE
 #[cfg(test)]
 mod test {
     use crate::{
-        error::WalletStorageError,
+        encryption::{EncryptionSettings, PendingEncryption},
+        error::{EncryptionError, WalletStorageError},
         storage::{
             database::{DbKey, WalletBackend, WalletDatabase},
             memory_db::WalletMemoryDatabase,
@@ -244,4 +339,73 @@ mod test {
 
         test_database_crud(WalletSqliteDatabase::new(format!("{}{}", db_folder, db_name).to_string()).unwrap());
     }
+
+    #[test]
+    fn test_encryption_sqlite_db() {
+        let mut rng = rand::OsRng::new().unwrap();
+        let db_name = format!("{}.sqlite3", string(8).as_str());
+        let temp_dir = TempDir::new(string(8).as_str()).unwrap();
+        let db_path = format!("{}/{}", temp_dir.path().to_str().unwrap(), db_name);
+
+        let mut db = WalletDatabase::new(WalletSqliteDatabase::new(db_path.clone()).unwrap());
+        let (secret_key, _public_key): (CommsSecretKey, CommsPublicKey) = PublicKey::random_keypair(&mut rng);
+        db.set_comms_secret_key(secret_key.clone()).unwrap();
+        assert_eq!(db.get_encryption_settings().unwrap(), None);
+
+        // Encryption can only be applied once the change has been stored
+        let settings = EncryptionSettings::new("correct horse").unwrap();
+        let cipher = settings.cipher("correct horse").unwrap();
+        match db.apply_encryption(Some(cipher.clone())) {
+            Err(WalletStorageError::ValueNotFound(DbKey::PendingEncryption)) => (),
+            _ => assert!(false),
+        }
+        let pending = PendingEncryption {
+            settings: Some(settings.clone()),
+            new_key: None,
+            current_key: None,
+        };
+        db.set_pending_encryption(pending.clone()).unwrap();
+        assert_eq!(db.get_pending_encryption().unwrap(), Some(pending));
+        assert_eq!(db.get_encryption_settings().unwrap(), None);
+        db.apply_encryption(Some(cipher.clone())).unwrap();
+        assert_eq!(db.get_pending_encryption().unwrap(), None);
+        assert_eq!(db.get_encryption_settings().unwrap(), Some(settings.clone()));
+        assert_eq!(db.get_comms_secret_key().unwrap(), Some(secret_key.clone()));
+
+        db.lock().unwrap();
+        match db.get_comms_secret_key() {
+            Err(WalletStorageError::EncryptionError(EncryptionError::WalletLocked)) => (),
+            _ => assert!(false),
+        }
+
+        // An encrypted database is locked when it is opened
+        drop(db);
+        let mut db = WalletDatabase::new(WalletSqliteDatabase::new(db_path.clone()).unwrap());
+        assert_eq!(db.get_encryption_settings().unwrap(), Some(settings));
+        assert!(db.get_comms_secret_key().is_err());
+        let wrong_cipher = EncryptionSettings::new("battery staple")
+            .unwrap()
+            .cipher("battery staple")
+            .unwrap();
+        match db.unlock(wrong_cipher) {
+            Err(WalletStorageError::EncryptionError(EncryptionError::IncorrectPassphrase)) => (),
+            _ => assert!(false),
+        }
+        db.unlock(cipher).unwrap();
+        assert_eq!(db.get_comms_secret_key().unwrap(), Some(secret_key.clone()));
+
+        // The comms secret key is removed rather than stored in plain text when encryption is removed
+        db.set_pending_encryption(PendingEncryption {
+            settings: None,
+            new_key: None,
+            current_key: None,
+        })
+        .unwrap();
+        db.apply_encryption(None).unwrap();
+        drop(db);
+        let db = WalletDatabase::new(WalletSqliteDatabase::new(db_path).unwrap());
+        assert_eq!(db.get_encryption_settings().unwrap(), None);
+        assert_eq!(db.get_pending_encryption().unwrap(), None);
+        assert_eq!(db.get_comms_secret_key().unwrap(), None);
+    }
 }
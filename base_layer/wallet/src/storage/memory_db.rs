@@ -21,19 +21,28 @@
 // USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
 use crate::{
+    encryption::{EncryptionSettings, PendingEncryption, SecretCipher},
     error::WalletStorageError,
     storage::database::{DbKey, DbKeyValuePair, DbValue, WalletBackend, WriteOperation},
 };
 use std::sync::{Arc, RwLock};
-use tari_comms::peer_manager::Peer;
+use tari_comms::{peer_manager::Peer, types::CommsSecretKey};
 
 pub struct InnerDatabase {
     peers: Vec<Peer>,
+    encryption_settings: Option<EncryptionSettings>,
+    pending_encryption: Option<PendingEncryption>,
+    comms_secret_key: Option<CommsSecretKey>,
 }
 
 impl InnerDatabase {
     pub fn new() -> Self {
-        Self { peers: Vec::new() }
+        Self {
+            peers: Vec::new(),
+            encryption_settings: None,
+            pending_encryption: None,
+            comms_secret_key: None,
+        }
     }
 }
 
@@ -59,6 +68,18 @@ impl WalletBackend for WalletMemoryDatabase {
                 .find(|v| &v.public_key == pk)
                 .map(|p| DbValue::Peer(Box::new(p.clone()))),
             DbKey::Peers => Some(DbValue::Peers(db.peers.clone())),
+            DbKey::EncryptionSettings => db
+                .encryption_settings
+                .clone()
+                .map(|s| DbValue::EncryptionSettings(Box::new(s))),
+            DbKey::PendingEncryption => db
+                .pending_encryption
+                .clone()
+                .map(|p| DbValue::PendingEncryption(Box::new(p))),
+            DbKey::CommsSecretKey => db
+                .comms_secret_key
+                .clone()
+                .map(|k| DbValue::CommsSecretKey(Box::new(k))),
         };
 
         Ok(result)
@@ -74,18 +95,46 @@ impl WalletBackend for WalletMemoryDatabase {
                     }
                     db.peers.push(p)
                 },
+                DbKeyValuePair::PendingEncryption(p) => db.pending_encryption = Some(p),
+                DbKeyValuePair::CommsSecretKey(k) => db.comms_secret_key = Some(k),
             },
             WriteOperation::Remove(k) => match k {
                 DbKey::Peer(pk) => match db.peers.iter().position(|p| p.public_key == pk) {
                     None => return Err(WalletStorageError::ValueNotFound(DbKey::Peer(pk))),
                     Some(pos) => return Ok(Some(DbValue::Peer(Box::new(db.peers.remove(pos))))),
                 },
-                DbKey::Peers => {
+                DbKey::Peers | DbKey::EncryptionSettings | DbKey::PendingEncryption => {
                     return Err(WalletStorageError::OperationNotSupported);
                 },
+                DbKey::CommsSecretKey => {
+                    return Ok(db.comms_secret_key.take().map(|k| DbValue::CommsSecretKey(Box::new(k))))
+                },
             },
         }
 
         Ok(None)
     }
+
+    // Nothing is persisted by this backend so there are no stored secrets to encrypt, but the pending change is
+    // completed in the same way
+    fn apply_encryption(&mut self, cipher: Option<SecretCipher>) -> Result<(), WalletStorageError> {
+        let mut db = acquire_write_lock!(self.db);
+        let pending = db
+            .pending_encryption
+            .take()
+            .ok_or(WalletStorageError::ValueNotFound(DbKey::PendingEncryption))?;
+        if cipher.is_none() {
+            db.comms_secret_key = None;
+        }
+        db.encryption_settings = pending.settings;
+        Ok(())
+    }
+
+    fn unlock(&mut self, _cipher: SecretCipher) -> Result<(), WalletStorageError> {
+        Ok(())
+    }
+
+    fn lock(&mut self) -> Result<(), WalletStorageError> {
+        Ok(())
+    }
 }
@@ -21,8 +21,9 @@
 // USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
 use crate::{
-    error::WalletStorageError,
-    schema::peers,
+    encryption::{EncryptionState, PendingEncryption, SecretCipher},
+    error::{EncryptionError, WalletStorageError},
+    schema::{peers, wallet_settings},
     storage::database::{DbKey, DbKeyValuePair, DbValue, WalletBackend, WriteOperation},
 };
 use diesel::{
@@ -31,29 +32,31 @@ use diesel::{
     result::Error as DieselError,
     SqliteConnection,
 };
-use std::{convert::TryFrom, io, path::Path, time::Duration};
-use tari_comms::peer_manager::Peer;
+use std::{convert::TryFrom, io, time::Duration};
+use tari_comms::{peer_manager::Peer, types::CommsSecretKey};
 use tari_utilities::ByteArray;
 
 const DATABASE_CONNECTION_TIMEOUT_MS: u64 = 2000;
+const ENCRYPTION_SETTINGS_KEY: &str = "encryption_settings";
+const PENDING_ENCRYPTION_KEY: &str = "pending_encryption";
+const COMMS_SECRET_KEY_KEY: &str = "comms_secret_key";
 
-/// A Sqlite backend for the Output Manager Service. The Backend is accessed via a connection pool to the Sqlite file.
+/// A Sqlite backend for the Wallet. The Backend is accessed via a connection pool to the Sqlite file. The comms
+/// secret key is stored encrypted if encryption has been applied, in which case the backend must be unlocked before it
+/// can be read or written.
 pub struct WalletSqliteDatabase {
     database_connection_pool: Pool<ConnectionManager<SqliteConnection>>,
+    encryption: EncryptionState,
 }
 impl WalletSqliteDatabase {
     pub fn new(database_path: String) -> Result<Self, WalletStorageError> {
-        let db_exists = Path::new(&database_path).exists();
-
         let connection = SqliteConnection::establish(&database_path)?;
 
         connection.execute("PRAGMA foreign_keys = ON")?;
-        if !db_exists {
-            embed_migrations!("./migrations");
-            embedded_migrations::run_with_output(&connection, &mut io::stdout()).map_err(|err| {
-                WalletStorageError::DatabaseMigrationError(format!("Database migration failed {}", err))
-            })?;
-        }
+        // Pending migrations are also applied to existing databases so that they have the `wallet_settings` table
+        embed_migrations!("./migrations");
+        embedded_migrations::run_with_output(&connection, &mut io::stdout())
+            .map_err(|err| WalletStorageError::DatabaseMigrationError(format!("Database migration failed {}", err)))?;
         drop(connection);
 
         let manager = ConnectionManager::<SqliteConnection>::new(database_path);
@@ -63,8 +66,17 @@ impl WalletSqliteDatabase {
             .build(manager)
             .map_err(|_| WalletStorageError::R2d2Error)?;
 
+        // A database with encryption settings has been encrypted and starts out locked
+        let conn = pool.get().map_err(|_| WalletStorageError::R2d2Error)?;
+        let encryption = match WalletSettingSql::find(ENCRYPTION_SETTINGS_KEY, &conn)? {
+            Some(_) => EncryptionState::Locked,
+            None => EncryptionState::Unencrypted,
+        };
+        drop(conn);
+
         Ok(Self {
             database_connection_pool: pool,
+            encryption,
         })
     }
 }
@@ -89,6 +101,21 @@ impl WalletBackend for WalletSqliteDatabase {
                     .map(|c| Peer::try_from(c.clone()))
                     .collect::<Result<Vec<_>, _>>()?,
             )),
+            DbKey::EncryptionSettings => match WalletSettingSql::find(ENCRYPTION_SETTINGS_KEY, &conn)? {
+                Some(s) => Some(DbValue::EncryptionSettings(Box::new(serde_json::from_slice(&s.value)?))),
+                None => None,
+            },
+            DbKey::PendingEncryption => match WalletSettingSql::find(PENDING_ENCRYPTION_KEY, &conn)? {
+                Some(s) => Some(DbValue::PendingEncryption(Box::new(serde_json::from_slice(&s.value)?))),
+                None => None,
+            },
+            DbKey::CommsSecretKey => match WalletSettingSql::find(COMMS_SECRET_KEY_KEY, &conn)? {
+                Some(s) => Some(DbValue::CommsSecretKey(Box::new(
+                    CommsSecretKey::from_bytes(&self.encryption.decrypt_secret(&s.value)?)
+                        .map_err(|_| WalletStorageError::ConversionError)?,
+                ))),
+                None => None,
+            },
         };
 
         Ok(result)
@@ -110,6 +137,13 @@ impl WalletBackend for WalletSqliteDatabase {
 
                     PeerSql::try_from(p)?.commit(&conn)?;
                 },
+                DbKeyValuePair::PendingEncryption(p) => {
+                    WalletSettingSql::new(PENDING_ENCRYPTION_KEY, serde_json::to_vec(&p)?).set(&conn)?
+                },
+                DbKeyValuePair::CommsSecretKey(k) => {
+                    WalletSettingSql::new(COMMS_SECRET_KEY_KEY, self.encryption.encrypt_secret(k.as_bytes())?)
+                        .set(&conn)?
+                },
             },
             WriteOperation::Remove(k) => match k {
                 DbKey::Peer(k) => match PeerSql::find(&k.to_vec(), &conn) {
@@ -120,12 +154,92 @@ impl WalletBackend for WalletSqliteDatabase {
                     Err(WalletStorageError::DieselError(DieselError::NotFound)) => (),
                     Err(e) => return Err(e),
                 },
-                DbKey::Peers => return Err(WalletStorageError::OperationNotSupported),
+                // The encryption settings are only changed by completing a pending encryption change
+                DbKey::Peers | DbKey::EncryptionSettings | DbKey::PendingEncryption => {
+                    return Err(WalletStorageError::OperationNotSupported)
+                },
+                DbKey::CommsSecretKey => {
+                    if let Some(s) = WalletSettingSql::find(COMMS_SECRET_KEY_KEY, &conn)? {
+                        let secret_key = CommsSecretKey::from_bytes(&self.encryption.decrypt_secret(&s.value)?)
+                            .map_err(|_| WalletStorageError::ConversionError)?;
+                        s.delete(&conn)?;
+                        return Ok(Some(DbValue::CommsSecretKey(Box::new(secret_key))));
+                    }
+                },
             },
         }
 
         Ok(None)
     }
+
+    fn apply_encryption(&mut self, cipher: Option<SecretCipher>) -> Result<(), WalletStorageError> {
+        let conn = self
+            .database_connection_pool
+            .clone()
+            .get()
+            .map_err(|_| WalletStorageError::R2d2Error)?;
+        let encryption = self.encryption.with_cipher(cipher)?;
+        let pending = WalletSettingSql::find(PENDING_ENCRYPTION_KEY, &conn)?
+            .ok_or(WalletStorageError::ValueNotFound(DbKey::PendingEncryption))?;
+        let pending_encryption: PendingEncryption = serde_json::from_slice(&pending.value)?;
+
+        // The secrets and the settings are changed together so that the settings always match the stored secrets
+        conn.transaction::<_, WalletStorageError, _>(|| {
+            if let Some(s) = WalletSettingSql::find(COMMS_SECRET_KEY_KEY, &conn)? {
+                match encryption {
+                    EncryptionState::Unlocked(_) => {
+                        let secret_key = self.encryption.decrypt_secret(&s.value)?;
+                        WalletSettingSql::new(COMMS_SECRET_KEY_KEY, encryption.encrypt_secret(&secret_key)?)
+                            .set(&conn)?
+                    },
+                    _ => s.delete(&conn)?,
+                }
+            }
+            match pending_encryption.settings {
+                Some(settings) => {
+                    WalletSettingSql::new(ENCRYPTION_SETTINGS_KEY, serde_json::to_vec(&settings)?).set(&conn)?
+                },
+                None => {
+                    if let Some(s) = WalletSettingSql::find(ENCRYPTION_SETTINGS_KEY, &conn)? {
+                        s.delete(&conn)?;
+                    }
+                },
+            }
+            pending.delete(&conn)
+        })?;
+
+        self.encryption = encryption;
+        Ok(())
+    }
+
+    fn unlock(&mut self, cipher: SecretCipher) -> Result<(), WalletStorageError> {
+        let conn = self
+            .database_connection_pool
+            .clone()
+            .get()
+            .map_err(|_| WalletStorageError::R2d2Error)?;
+        if let EncryptionState::Unencrypted = self.encryption {
+            return Err(WalletStorageError::EncryptionError(EncryptionError::NotEncrypted));
+        }
+
+        // The comms secret key can only be decrypted with the cipher it was encrypted with
+        let unlocked = EncryptionState::Unlocked(cipher);
+        if let Some(s) = WalletSettingSql::find(COMMS_SECRET_KEY_KEY, &conn)? {
+            if unlocked.decrypt_secret(&s.value).is_err() {
+                return Err(WalletStorageError::EncryptionError(
+                    EncryptionError::IncorrectPassphrase,
+                ));
+            }
+        }
+
+        self.encryption = unlocked;
+        Ok(())
+    }
+
+    fn lock(&mut self) -> Result<(), WalletStorageError> {
+        self.encryption.lock()?;
+        Ok(())
+    }
 }
 
 /// A Sql version of the Peer struct
@@ -200,3 +314,55 @@ impl TryFrom<Peer> for PeerSql {
         })
     }
 }
+
+/// A Sql version of a wallet setting, which is stored as a key and an opaque value
+#[derive(Clone, Debug, Queryable, Insertable, PartialEq)]
+#[table_name = "wallet_settings"]
+struct WalletSettingSql {
+    key: String,
+    value: Vec<u8>,
+}
+
+impl WalletSettingSql {
+    pub fn new(key: &str, value: Vec<u8>) -> Self {
+        Self {
+            key: key.to_string(),
+            value,
+        }
+    }
+
+    /// Write this setting to the database, replacing the current value if there is one
+    pub fn set(&self, conn: &PooledConnection<ConnectionManager<SqliteConnection>>) -> Result<(), WalletStorageError> {
+        diesel::replace_into(wallet_settings::table)
+            .values(self.clone())
+            .execute(conn)?;
+        Ok(())
+    }
+
+    /// Find a particular setting, if it exists
+    pub fn find(
+        key: &str,
+        conn: &PooledConnection<ConnectionManager<SqliteConnection>>,
+    ) -> Result<Option<WalletSettingSql>, WalletStorageError>
+    {
+        Ok(wallet_settings::table
+            .filter(wallet_settings::key.eq(key))
+            .first::<WalletSettingSql>(conn)
+            .optional()?)
+    }
+
+    pub fn delete(
+        &self,
+        conn: &PooledConnection<ConnectionManager<SqliteConnection>>,
+    ) -> Result<(), WalletStorageError>
+    {
+        let num_deleted =
+            diesel::delete(wallet_settings::table.filter(wallet_settings::key.eq(&self.key))).execute(conn)?;
+
+        if num_deleted == 0 {
+            return Err(WalletStorageError::ValuesNotFound);
+        }
+
+        Ok(())
+    }
+}
@@ -141,6 +141,7 @@ pub fn create_wallet(
     let config = WalletConfig {
         comms_config,
         logging_path: None,
+        passphrase: None,
         factories,
     };
 
@@ -614,6 +615,7 @@ pub fn complete_sent_transaction<
                 message: p.message.clone(),
                 status: TransactionStatus::Completed,
                 timestamp: Utc::now().naive_utc(),
+                mined_height: None,
             };
             wallet.runtime.block_on(
                 wallet
@@ -0,0 +1,59 @@
+// Copyright 2019. The Tari Project
+//
+// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+// following conditions are met:
+//
+// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+// disclaimer.
+//
+// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+// following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+// 3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+// products derived from this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+// INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+// SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+// WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+// USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+use std::time::Duration;
+use tari_common::Network;
+
+/// Configuration of the Transaction Service, mostly of the base node monitoring that it performs.
+#[derive(Clone, Copy, Debug)]
+pub struct TransactionServiceConfig {
+    /// The interval at which the Transaction Service will query the configured base node for the chain state and the
+    /// status of the transactions it is monitoring. (default: 30s)
+    pub base_node_monitoring_interval: Duration,
+    /// The number of blocks that need to be mined on top of the block containing a transaction before its outputs
+    /// are confirmed in the Output Manager Service. (default: 3)
+    pub num_confirmations_required: u64,
+    /// The number of times a transaction that is not found in the base node mempool will be rebroadcast before it is
+    /// flagged as having dropped out of the mempool. (default: 3)
+    pub max_rebroadcast_attempts: usize,
+    /// The network of the wallet. Payment requests for other networks are rejected. (default: TestNet)
+    pub network: Network,
+    /// The time after which the messages of a pending transaction are sent to the counterparty again, in case the
+    /// counterparty was offline or the message was lost. The time doubles after every resend of the same
+    /// transaction. (default: 5 minutes)
+    pub resend_interval: Duration,
+    /// The longest time between two resends of the messages of the same pending transaction. (default: 1 hour)
+    pub max_resend_interval: Duration,
+}
+
+impl Default for TransactionServiceConfig {
+    fn default() -> Self {
+        Self {
+            base_node_monitoring_interval: Duration::from_secs(30),
+            num_confirmations_required: 3,
+            max_rebroadcast_attempts: 3,
+            network: Network::TestNet,
+            resend_interval: Duration::from_secs(5 * 60),
+            max_resend_interval: Duration::from_secs(60 * 60),
+        }
+    }
+}
@@ -21,6 +21,7 @@
 // USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
 use crate::{
+    error::{EncryptionError, PaymentRequestError},
     output_manager_service::{error::OutputManagerError, TxId},
     transaction_service::storage::database::DbKey,
 };
@@ -59,6 +60,10 @@ pub enum TransactionServiceError {
     InvalidSourcePublicKey,
     /// The transaction does not contain the receivers output
     ReceiverOutputNotFound,
+    /// The transaction has been cancelled
+    TransactionCancelled,
+    /// No transaction created by an offline wallet has been sent with this tx_id
+    OfflineTransactionNotFound,
     /// Outbound Service send failed
     OutboundSendFailure,
     /// Outbound Service Discovery process needed to be conducted before message could be sent. The result of the
@@ -70,6 +75,10 @@ pub enum TransactionServiceError {
     DiscoveryProcessFailed(TxId),
     /// Invalid Completed Transaction provided
     InvalidCompletedTransaction,
+    /// No base node public key has been set for the Transaction Service to submit transactions to
+    NoBaseNodePublicKey,
+    /// Received an unexpected response from the base node
+    UnexpectedBaseNodeResponse,
     DhtOutboundError(DhtOutboundError),
     OutputManagerError(OutputManagerError),
     TransportChannelError(TransportChannelError),
@@ -80,6 +89,8 @@ pub enum TransactionServiceError {
     #[error(msg_embedded, no_from, non_std)]
     TestHarnessError(String),
     TransactionError(TransactionError),
+    PaymentRequestError(PaymentRequestError),
+    SerdeJsonError(SerdeJsonError),
 }
 
 #[derive(Debug, Error)]
@@ -105,4 +116,5 @@ pub enum TransactionStorageError {
     DieselConnectionError(diesel::ConnectionError),
     #[error(msg_embedded, no_from, non_std)]
     DatabaseMigrationError(String),
+    EncryptionError(EncryptionError),
 }
@@ -21,18 +21,32 @@
 // USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
 use crate::{
+    encryption::SecretCipher,
     output_manager_service::TxId,
+    payment_request::PaymentRequest,
     transaction_service::{
         error::TransactionServiceError,
+        history::{TransactionHistoryEntry, TransactionHistoryFormat},
         service::PendingCoinbaseSpendingKey,
-        storage::database::{CompletedTransaction, InboundTransaction, OutboundTransaction},
+        storage::database::{
+            CompletedTransaction,
+            InboundTransaction,
+            OfflineTransaction,
+            OutboundTransaction,
+            TransactionHistoryQuery,
+            TransactionServiceBackup,
+        },
     },
 };
 use futures::{stream::Fuse, StreamExt};
 use std::collections::HashMap;
 use tari_broadcast_channel::Subscriber;
 use tari_comms::types::CommsPublicKey;
-use tari_core::transactions::{tari_amount::MicroTari, transaction::Transaction};
+use tari_core::transactions::{
+    tari_amount::MicroTari,
+    transaction::Transaction,
+    transaction_protocol::recipient::RecipientSignedMessage,
+};
 use tari_service_framework::reply_channel::SenderService;
 use tower::Service;
 
@@ -42,10 +56,30 @@ pub enum TransactionServiceRequest {
     GetPendingInboundTransactions,
     GetPendingOutboundTransactions,
     GetCompletedTransactions,
-    SendTransaction((CommsPublicKey, MicroTari, MicroTari, String)),
+    GetTransactionHistory(TransactionHistoryQuery),
+    ExportTransactionHistory((TransactionHistoryQuery, TransactionHistoryFormat)),
+    SendTransaction((CommsPublicKey, MicroTari, MicroTari, Option<u64>, String)),
+    SendTransactionToMultipleRecipients((Vec<(CommsPublicKey, MicroTari)>, MicroTari, String)),
+    PayPaymentRequest((PaymentRequest, Option<MicroTari>, MicroTari)),
+    CreateCoinSplit((MicroTari, usize, MicroTari, String)),
+    ConsolidateOutputs((usize, MicroTari, String)),
     RequestCoinbaseSpendingKey((MicroTari, u64)),
     CompleteCoinbaseTransaction((TxId, Transaction)),
     CancelPendingCoinbaseTransaction(TxId),
+    CancelTransaction(TxId),
+    SetBaseNodePublicKey(CommsPublicKey),
+    GetBackup,
+    RestoreBackup(TransactionServiceBackup),
+    ApplyEncryption(Option<SecretCipher>),
+    Unlock(SecretCipher),
+    Lock,
+    CreateOfflineTransaction((CommsPublicKey, MicroTari, MicroTari, Option<u64>, String)),
+    SendOfflineTransaction(OfflineTransaction),
+    GetOfflineTransactionReply(TxId),
+    FinalizeOfflineTransaction(RecipientSignedMessage),
+    SubmitOfflineTransaction((TxId, Transaction)),
+    SubmitTransactionToSelf((TxId, MicroTari, MicroTari, Transaction, String)),
+    ResendPendingTransactions,
     #[cfg(feature = "test_harness")]
     CompletePendingOutboundTransaction(CompletedTransaction),
     #[cfg(feature = "test_harness")]
@@ -61,13 +95,29 @@ pub enum TransactionServiceRequest {
 /// API Response enum
 #[derive(Debug)]
 pub enum TransactionServiceResponse {
-    TransactionSent,
+    TransactionSent(TxId),
+    TransactionToSelfCreated(TxId),
     PendingInboundTransactions(HashMap<u64, InboundTransaction>),
     PendingOutboundTransactions(HashMap<u64, OutboundTransaction>),
     CompletedTransactions(HashMap<u64, CompletedTransaction>),
+    TransactionHistory(Vec<TransactionHistoryEntry>),
+    TransactionHistoryExport(String),
     CoinbaseKey(PendingCoinbaseSpendingKey),
     CompletedCoinbaseTransactionReceived,
     CoinbaseTransactionCancelled,
+    TransactionCancelled,
+    BaseNodePublicKeySet,
+    Backup(TransactionServiceBackup),
+    BackupRestored,
+    EncryptionApplied,
+    Unlocked,
+    Locked,
+    OfflineTransactionCreated(OfflineTransaction),
+    OfflineTransactionSent,
+    OfflineTransactionReply(Option<RecipientSignedMessage>),
+    OfflineTransactionFinalized(Transaction),
+    OfflineTransactionSubmitted,
+    PendingTransactionsResent(usize),
     #[cfg(feature = "test_harness")]
     CompletedPendingTransaction,
     #[cfg(feature = "test_harness")]
@@ -86,11 +136,21 @@ pub enum TransactionEvent {
     ReceivedTransaction(TxId),
     ReceivedTransactionReply(TxId),
     ReceivedFinalizedTransaction(TxId),
+    /// The Discovery process for the recipient of a transaction has completed. The flag is false only if the message
+    /// could not be sent at all, in which case it is sent again later.
     TransactionSendDiscoveryComplete(TxId, bool),
+    TransactionCancelled(TxId),
     TransactionBroadcast(TxId),
     TransactionMined(TxId),
+    TransactionConfirmations(TxId, u64),
+    TransactionDroppedFromMempool(TxId),
     TransactionSendDiscoverySuccess(TxId),
     TransactionSendDiscoveryFailure(TxId),
+    /// The recipient of a transaction created by an offline wallet has replied to it
+    ReceivedOfflineTransactionReply(TxId),
+    /// A transaction message could not be sent directly to its recipient and was sent to the recipient's neighbours
+    /// to be stored and forwarded when the recipient comes online
+    TransactionStoreAndForwardSend(TxId),
     Error(String),
 }
 
@@ -121,7 +181,21 @@ impl TransactionServiceHandle {
         amount: MicroTari,
         fee_per_gram: MicroTari,
         message: String,
-    ) -> Result<(), TransactionServiceError>
+    ) -> Result<TxId, TransactionServiceError>
+    {
+        self.send_transaction_with_lock_height(dest_pubkey, amount, fee_per_gram, None, message)
+            .await
+    }
+
+    /// Send a transaction that cannot be mined before the given block height
+    pub async fn send_transaction_with_lock_height(
+        &mut self,
+        dest_pubkey: CommsPublicKey,
+        amount: MicroTari,
+        fee_per_gram: MicroTari,
+        lock_height: Option<u64>,
+        message: String,
+    ) -> Result<TxId, TransactionServiceError>
     {
         match self
             .handle
@@ -129,11 +203,106 @@ impl TransactionServiceHandle {
                 dest_pubkey,
                 amount,
                 fee_per_gram,
+                lock_height,
                 message,
             )))
             .await??
         {
-            TransactionServiceResponse::TransactionSent => Ok(()),
+            TransactionServiceResponse::TransactionSent(tx_id) => Ok(tx_id),
+            _ => Err(TransactionServiceError::UnexpectedApiResponse),
+        }
+    }
+
+    /// Send a transaction that pays a payment request, after checking that the request is for this wallet's network
+    /// and has not expired. The amount is required when the request does not have one and must match it when it does.
+    /// The message of the request is the message of the transaction.
+    pub async fn pay_payment_request(
+        &mut self,
+        request: PaymentRequest,
+        amount: Option<MicroTari>,
+        fee_per_gram: MicroTari,
+    ) -> Result<TxId, TransactionServiceError>
+    {
+        match self
+            .handle
+            .call(TransactionServiceRequest::PayPaymentRequest((
+                request,
+                amount,
+                fee_per_gram,
+            )))
+            .await??
+        {
+            TransactionServiceResponse::TransactionSent(tx_id) => Ok(tx_id),
+            _ => Err(TransactionServiceError::UnexpectedApiResponse),
+        }
+    }
+
+    /// Send a single transaction that pays each recipient the amount given for it
+    pub async fn send_transaction_to_multiple_recipients(
+        &mut self,
+        recipients: Vec<(CommsPublicKey, MicroTari)>,
+        fee_per_gram: MicroTari,
+        message: String,
+    ) -> Result<TxId, TransactionServiceError>
+    {
+        match self
+            .handle
+            .call(TransactionServiceRequest::SendTransactionToMultipleRecipients((
+                recipients,
+                fee_per_gram,
+                message,
+            )))
+            .await??
+        {
+            TransactionServiceResponse::TransactionSent(tx_id) => Ok(tx_id),
+            _ => Err(TransactionServiceError::UnexpectedApiResponse),
+        }
+    }
+
+    /// Create, store and broadcast a transaction that splits `split_count` outputs of `amount_per_split` each from the
+    /// wallet's balance. Returns the tx_id of the transaction.
+    pub async fn create_coin_split(
+        &mut self,
+        amount_per_split: MicroTari,
+        split_count: usize,
+        fee_per_gram: MicroTari,
+        message: String,
+    ) -> Result<TxId, TransactionServiceError>
+    {
+        match self
+            .handle
+            .call(TransactionServiceRequest::CreateCoinSplit((
+                amount_per_split,
+                split_count,
+                fee_per_gram,
+                message,
+            )))
+            .await??
+        {
+            TransactionServiceResponse::TransactionToSelfCreated(tx_id) => Ok(tx_id),
+            _ => Err(TransactionServiceError::UnexpectedApiResponse),
+        }
+    }
+
+    /// Create, store and broadcast a transaction that consolidates up to `max_outputs` of the wallet's smallest
+    /// outputs into one. Returns the tx_id of the transaction.
+    pub async fn consolidate_outputs(
+        &mut self,
+        max_outputs: usize,
+        fee_per_gram: MicroTari,
+        message: String,
+    ) -> Result<TxId, TransactionServiceError>
+    {
+        match self
+            .handle
+            .call(TransactionServiceRequest::ConsolidateOutputs((
+                max_outputs,
+                fee_per_gram,
+                message,
+            )))
+            .await??
+        {
+            TransactionServiceResponse::TransactionToSelfCreated(tx_id) => Ok(tx_id),
             _ => Err(TransactionServiceError::UnexpectedApiResponse),
         }
     }
@@ -177,6 +346,39 @@ impl TransactionServiceHandle {
         }
     }
 
+    /// Get a page of the completed transactions in the time range of the query, in the order they were completed in
+    pub async fn get_transaction_history(
+        &mut self,
+        query: TransactionHistoryQuery,
+    ) -> Result<Vec<TransactionHistoryEntry>, TransactionServiceError>
+    {
+        match self
+            .handle
+            .call(TransactionServiceRequest::GetTransactionHistory(query))
+            .await??
+        {
+            TransactionServiceResponse::TransactionHistory(h) => Ok(h),
+            _ => Err(TransactionServiceError::UnexpectedApiResponse),
+        }
+    }
+
+    /// Export a page of the transaction history, as returned by `get_transaction_history`, in the given format
+    pub async fn export_transaction_history(
+        &mut self,
+        query: TransactionHistoryQuery,
+        format: TransactionHistoryFormat,
+    ) -> Result<String, TransactionServiceError>
+    {
+        match self
+            .handle
+            .call(TransactionServiceRequest::ExportTransactionHistory((query, format)))
+            .await??
+        {
+            TransactionServiceResponse::TransactionHistoryExport(e) => Ok(e),
+            _ => Err(TransactionServiceError::UnexpectedApiResponse),
+        }
+    }
+
     pub async fn request_coinbase_key(
         &mut self,
         amount: MicroTari,
@@ -226,6 +428,223 @@ impl TransactionServiceHandle {
         }
     }
 
+    /// Cancel a pending inbound or outbound transaction. Its outputs are released and its counterparty is told that
+    /// it has been cancelled.
+    pub async fn cancel_transaction(&mut self, tx_id: TxId) -> Result<(), TransactionServiceError> {
+        match self
+            .handle
+            .call(TransactionServiceRequest::CancelTransaction(tx_id))
+            .await??
+        {
+            TransactionServiceResponse::TransactionCancelled => Ok(()),
+            _ => Err(TransactionServiceError::UnexpectedApiResponse),
+        }
+    }
+
+    /// Set the base node that the Transaction Service will submit completed transactions to and monitor for their
+    /// inclusion in the blockchain
+    pub async fn set_base_node_public_key(
+        &mut self,
+        public_key: CommsPublicKey,
+    ) -> Result<(), TransactionServiceError>
+    {
+        match self
+            .handle
+            .call(TransactionServiceRequest::SetBaseNodePublicKey(public_key))
+            .await??
+        {
+            TransactionServiceResponse::BaseNodePublicKeySet => Ok(()),
+            _ => Err(TransactionServiceError::UnexpectedApiResponse),
+        }
+    }
+
+    /// Return the complete transaction history of the wallet so that it can be written to a wallet backup
+    pub async fn get_backup(&mut self) -> Result<TransactionServiceBackup, TransactionServiceError> {
+        match self.handle.call(TransactionServiceRequest::GetBackup).await?? {
+            TransactionServiceResponse::Backup(backup) => Ok(backup),
+            _ => Err(TransactionServiceError::UnexpectedApiResponse),
+        }
+    }
+
+    /// Add the transactions stored in a wallet backup to the transaction history of the wallet
+    pub async fn restore_backup(&mut self, backup: TransactionServiceBackup) -> Result<(), TransactionServiceError> {
+        match self
+            .handle
+            .call(TransactionServiceRequest::RestoreBackup(backup))
+            .await??
+        {
+            TransactionServiceResponse::BackupRestored => Ok(()),
+            _ => Err(TransactionServiceError::UnexpectedApiResponse),
+        }
+    }
+
+    /// Encrypt the secrets stored by the Transaction Service with the provided cipher, or decrypt them if no cipher is
+    /// provided
+    pub async fn apply_encryption(&mut self, cipher: Option<SecretCipher>) -> Result<(), TransactionServiceError> {
+        match self
+            .handle
+            .call(TransactionServiceRequest::ApplyEncryption(cipher))
+            .await??
+        {
+            TransactionServiceResponse::EncryptionApplied => Ok(()),
+            _ => Err(TransactionServiceError::UnexpectedApiResponse),
+        }
+    }
+
+    /// Make the encrypted secrets stored by the Transaction Service accessible with the provided cipher
+    pub async fn unlock(&mut self, cipher: SecretCipher) -> Result<(), TransactionServiceError> {
+        match self.handle.call(TransactionServiceRequest::Unlock(cipher)).await?? {
+            TransactionServiceResponse::Unlocked => Ok(()),
+            _ => Err(TransactionServiceError::UnexpectedApiResponse),
+        }
+    }
+
+    /// Discard the cipher of the encrypted secrets stored by the Transaction Service
+    pub async fn lock(&mut self) -> Result<(), TransactionServiceError> {
+        match self.handle.call(TransactionServiceRequest::Lock).await?? {
+            TransactionServiceResponse::Locked => Ok(()),
+            _ => Err(TransactionServiceError::UnexpectedApiResponse),
+        }
+    }
+
+    /// Create a transaction on an offline wallet without sending it. The returned offline transaction must be carried
+    /// to an online wallet to be sent to the recipient.
+    pub async fn create_offline_transaction(
+        &mut self,
+        dest_pubkey: CommsPublicKey,
+        amount: MicroTari,
+        fee_per_gram: MicroTari,
+        lock_height: Option<u64>,
+        message: String,
+    ) -> Result<OfflineTransaction, TransactionServiceError>
+    {
+        match self
+            .handle
+            .call(TransactionServiceRequest::CreateOfflineTransaction((
+                dest_pubkey,
+                amount,
+                fee_per_gram,
+                lock_height,
+                message,
+            )))
+            .await??
+        {
+            TransactionServiceResponse::OfflineTransactionCreated(offline_tx) => Ok(offline_tx),
+            _ => Err(TransactionServiceError::UnexpectedApiResponse),
+        }
+    }
+
+    /// Send a transaction that was created by an offline wallet to its recipient. The recipient's reply is kept until
+    /// it is fetched with `get_offline_transaction_reply`.
+    pub async fn send_offline_transaction(
+        &mut self,
+        offline_tx: OfflineTransaction,
+    ) -> Result<(), TransactionServiceError>
+    {
+        match self
+            .handle
+            .call(TransactionServiceRequest::SendOfflineTransaction(offline_tx))
+            .await??
+        {
+            TransactionServiceResponse::OfflineTransactionSent => Ok(()),
+            _ => Err(TransactionServiceError::UnexpectedApiResponse),
+        }
+    }
+
+    /// Return the recipient's reply to a transaction that was created by an offline wallet, or None if the recipient
+    /// has not replied yet
+    pub async fn get_offline_transaction_reply(
+        &mut self,
+        tx_id: TxId,
+    ) -> Result<Option<RecipientSignedMessage>, TransactionServiceError>
+    {
+        match self
+            .handle
+            .call(TransactionServiceRequest::GetOfflineTransactionReply(tx_id))
+            .await??
+        {
+            TransactionServiceResponse::OfflineTransactionReply(reply) => Ok(reply),
+            _ => Err(TransactionServiceError::UnexpectedApiResponse),
+        }
+    }
+
+    /// Apply the recipient's reply to a transaction that was created by this offline wallet and sign it. The returned
+    /// transaction must be carried to the online wallet to be submitted.
+    pub async fn finalize_offline_transaction(
+        &mut self,
+        recipient_reply: RecipientSignedMessage,
+    ) -> Result<Transaction, TransactionServiceError>
+    {
+        match self
+            .handle
+            .call(TransactionServiceRequest::FinalizeOfflineTransaction(recipient_reply))
+            .await??
+        {
+            TransactionServiceResponse::OfflineTransactionFinalized(tx) => Ok(tx),
+            _ => Err(TransactionServiceError::UnexpectedApiResponse),
+        }
+    }
+
+    /// Send a transaction that was finalized by an offline wallet to its recipient and submit it to the base node
+    pub async fn submit_offline_transaction(
+        &mut self,
+        tx_id: TxId,
+        transaction: Transaction,
+    ) -> Result<(), TransactionServiceError>
+    {
+        match self
+            .handle
+            .call(TransactionServiceRequest::SubmitOfflineTransaction((
+                tx_id,
+                transaction,
+            )))
+            .await??
+        {
+            TransactionServiceResponse::OfflineTransactionSubmitted => Ok(()),
+            _ => Err(TransactionServiceError::UnexpectedApiResponse),
+        }
+    }
+
+    /// Store a finalized transaction that was built by another wallet service, and whose outputs the Output Manager
+    /// Service expects under the given tx_id, and submit it to the base node like any other completed transaction
+    pub async fn submit_transaction_to_self(
+        &mut self,
+        tx_id: TxId,
+        amount: MicroTari,
+        fee: MicroTari,
+        transaction: Transaction,
+        message: String,
+    ) -> Result<TxId, TransactionServiceError>
+    {
+        match self
+            .handle
+            .call(TransactionServiceRequest::SubmitTransactionToSelf((
+                tx_id,
+                amount,
+                fee,
+                transaction,
+                message,
+            )))
+            .await??
+        {
+            TransactionServiceResponse::TransactionToSelfCreated(tx_id) => Ok(tx_id),
+            _ => Err(TransactionServiceError::UnexpectedApiResponse),
+        }
+    }
+
+    /// Send the messages of all the pending transactions to their counterparties again, e.g. after the wallet has
+    /// reconnected to the network. Returns the number of transactions whose messages were resent.
+    pub async fn resend_pending_transactions(&mut self) -> Result<usize, TransactionServiceError> {
+        match self
+            .handle
+            .call(TransactionServiceRequest::ResendPendingTransactions)
+            .await??
+        {
+            TransactionServiceResponse::PendingTransactionsResent(n) => Ok(n),
+            _ => Err(TransactionServiceError::UnexpectedApiResponse),
+        }
+    }
+
     #[cfg(feature = "test_harness")]
     pub async fn test_complete_pending_transaction(
         &mut self,
@@ -0,0 +1,255 @@
+// Copyright 2020. The Tari Project
+//
+// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+// following conditions are met:
+//
+// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+// disclaimer.
+//
+// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+// following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+// 3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+// products derived from this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+// INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+// SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+// WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+// USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+use crate::{
+    output_manager_service::TxId,
+    transaction_service::{
+        error::TransactionServiceError,
+        storage::database::{CompletedTransaction, TransactionStatus},
+    },
+};
+use chrono::NaiveDateTime;
+use serde_json::json;
+use std::fmt::{Display, Error, Formatter};
+use tari_comms::types::CommsPublicKey;
+use tari_core::transactions::tari_amount::MicroTari;
+use tari_utilities::hex::Hex;
+
+const TIMESTAMP_FORMAT: &str = "%Y-%m-%d %H:%M:%S";
+const CSV_HEADER: &str = "tx_id,direction,counterparty,amount,fee,status,block_height,timestamp,message";
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TransactionDirection {
+    /// The transaction pays this wallet, coinbase transactions and payments to self included
+    Inbound,
+    /// The transaction pays another wallet
+    Outbound,
+}
+
+impl Display for TransactionDirection {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), Error> {
+        match self {
+            TransactionDirection::Inbound => f.write_str("Inbound"),
+            TransactionDirection::Outbound => f.write_str("Outbound"),
+        }
+    }
+}
+
+/// The format that the transaction history is exported in
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TransactionHistoryFormat {
+    /// Comma separated values with a header line, amounts in µT
+    Csv,
+    /// An array of JSON objects, amounts in µT
+    Json,
+}
+
+/// A completed transaction as it appears in the transaction history of a wallet
+#[derive(Debug, Clone, PartialEq)]
+pub struct TransactionHistoryEntry {
+    pub tx_id: TxId,
+    pub direction: TransactionDirection,
+    /// The wallet that the transaction is with, this wallet itself for coinbase transactions and payments to self
+    pub counterparty: CommsPublicKey,
+    pub amount: MicroTari,
+    pub fee: MicroTari,
+    pub status: TransactionStatus,
+    /// The height of the block containing the kernels of the transaction, if it has been mined
+    pub block_height: Option<u64>,
+    pub timestamp: NaiveDateTime,
+    pub message: String,
+}
+
+impl TransactionHistoryEntry {
+    /// Describe a completed transaction from the point of view of the wallet with the given public key
+    pub fn new(transaction: CompletedTransaction, own_public_key: &CommsPublicKey) -> Self {
+        let (direction, counterparty) = if &transaction.source_public_key == own_public_key &&
+            &transaction.destination_public_key != own_public_key
+        {
+            (TransactionDirection::Outbound, transaction.destination_public_key)
+        } else {
+            (TransactionDirection::Inbound, transaction.source_public_key)
+        };
+        Self {
+            tx_id: transaction.tx_id,
+            direction,
+            counterparty,
+            amount: transaction.amount,
+            fee: transaction.fee,
+            status: transaction.status,
+            block_height: transaction.mined_height,
+            timestamp: transaction.timestamp,
+            message: transaction.message,
+        }
+    }
+}
+
+/// Export transaction history entries in the given format
+pub fn export_transaction_history(
+    entries: &[TransactionHistoryEntry],
+    format: TransactionHistoryFormat,
+) -> Result<String, TransactionServiceError>
+{
+    match format {
+        TransactionHistoryFormat::Csv => Ok(to_csv(entries)),
+        TransactionHistoryFormat::Json => to_json(entries),
+    }
+}
+
+fn to_csv(entries: &[TransactionHistoryEntry]) -> String {
+    let mut csv = String::from(CSV_HEADER);
+    csv.push('\n');
+    for e in entries {
+        csv.push_str(&format!(
+            "{},{},{},{},{},{:?},{},{},{}\n",
+            e.tx_id,
+            e.direction,
+            e.counterparty.to_hex(),
+            u64::from(e.amount),
+            u64::from(e.fee),
+            e.status,
+            e.block_height.map(|h| h.to_string()).unwrap_or_default(),
+            e.timestamp.format(TIMESTAMP_FORMAT),
+            csv_field(&e.message)
+        ));
+    }
+    csv
+}
+
+/// Quote a field that contains a separator, a quote or a line break, doubling the quotes in it
+fn csv_field(field: &str) -> String {
+    if field.contains(|c| c == ',' || c == '"' || c == '\n' || c == '\r') {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+fn to_json(entries: &[TransactionHistoryEntry]) -> Result<String, TransactionServiceError> {
+    let entries: Vec<_> = entries
+        .iter()
+        .map(|e| {
+            json!({
+                "tx_id": e.tx_id,
+                "direction": e.direction.to_string(),
+                "counterparty": e.counterparty.to_hex(),
+                "amount": u64::from(e.amount),
+                "fee": u64::from(e.fee),
+                "status": format!("{:?}", e.status),
+                "block_height": e.block_height,
+                "timestamp": e.timestamp.format(TIMESTAMP_FORMAT).to_string(),
+                "message": e.message,
+            })
+        })
+        .collect();
+    Ok(serde_json::to_string_pretty(&entries)?)
+}
+
+#[cfg(test)]
+mod test {
+    use crate::transaction_service::{
+        history::{
+            export_transaction_history,
+            TransactionDirection,
+            TransactionHistoryEntry,
+            TransactionHistoryFormat,
+        },
+        storage::database::{CompletedTransaction, TransactionStatus},
+    };
+    use chrono::NaiveDateTime;
+    use tari_core::transactions::{
+        tari_amount::MicroTari,
+        transaction::Transaction,
+        types::{PrivateKey, PublicKey},
+    };
+    use tari_crypto::keys::{PublicKey as PublicKeyTrait, SecretKey as SecretKeyTrait};
+    use tari_utilities::hex::Hex;
+
+    #[test]
+    fn history_export() {
+        let mut rng = rand::OsRng::new().unwrap();
+        let own_public_key = PublicKey::from_secret_key(&PrivateKey::random(&mut rng));
+        let other_public_key = PublicKey::from_secret_key(&PrivateKey::random(&mut rng));
+        let sent = CompletedTransaction {
+            tx_id: 1,
+            source_public_key: own_public_key.clone(),
+            destination_public_key: other_public_key.clone(),
+            amount: MicroTari::from(5000),
+            fee: MicroTari::from(100),
+            transaction: Transaction::new(vec![], vec![], vec![], PrivateKey::random(&mut rng)),
+            status: TransactionStatus::Mined,
+            message: "Invoice 42, \"paid\"".to_string(),
+            timestamp: NaiveDateTime::from_timestamp(1_584_000_000, 0),
+            mined_height: Some(1234),
+        };
+        let received = CompletedTransaction {
+            tx_id: 2,
+            source_public_key: other_public_key.clone(),
+            destination_public_key: own_public_key.clone(),
+            status: TransactionStatus::Completed,
+            message: "Refund".to_string(),
+            mined_height: None,
+            ..sent.clone()
+        };
+        let entries = vec![
+            TransactionHistoryEntry::new(sent, &own_public_key),
+            TransactionHistoryEntry::new(received, &own_public_key),
+        ];
+        assert_eq!(entries[0].direction, TransactionDirection::Outbound);
+        assert_eq!(entries[0].counterparty, other_public_key);
+        assert_eq!(entries[1].direction, TransactionDirection::Inbound);
+        assert_eq!(entries[1].counterparty, other_public_key);
+
+        let csv = export_transaction_history(&entries, TransactionHistoryFormat::Csv).unwrap();
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(lines.len(), 3);
+        assert_eq!(
+            lines[0],
+            "tx_id,direction,counterparty,amount,fee,status,block_height,timestamp,message"
+        );
+        assert_eq!(
+            lines[1],
+            format!(
+                "1,Outbound,{},5000,100,Mined,1234,2020-03-12 08:00:00,\"Invoice 42, \"\"paid\"\"\"",
+                other_public_key.to_hex()
+            )
+        );
+        assert_eq!(
+            lines[2],
+            format!(
+                "2,Inbound,{},5000,100,Completed,,2020-03-12 08:00:00,Refund",
+                other_public_key.to_hex()
+            )
+        );
+
+        let json = export_transaction_history(&entries, TransactionHistoryFormat::Json).unwrap();
+        let json: serde_json::Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(json[0]["tx_id"], 1);
+        assert_eq!(json[0]["direction"], "Outbound");
+        assert_eq!(json[0]["counterparty"], other_public_key.to_hex());
+        assert_eq!(json[0]["block_height"], 1234);
+        assert_eq!(json[0]["message"], "Invoice 42, \"paid\"");
+        assert_eq!(json[1]["status"], "Completed");
+        assert!(json[1]["block_height"].is_null());
+        assert_eq!(json[1]["timestamp"], "2020-03-12 08:00:00");
+    }
+}
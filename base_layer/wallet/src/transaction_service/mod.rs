@@ -21,14 +21,17 @@
 // USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 #[cfg(feature = "c_integration")]
 pub mod callback_handler;
+pub mod config;
 pub mod error;
 pub mod handle;
+pub mod history;
 pub mod service;
 pub mod storage;
 
 use crate::{
     output_manager_service::handle::OutputManagerHandle,
     transaction_service::{
+        config::TransactionServiceConfig,
         handle::TransactionServiceHandle,
         service::TransactionService,
         storage::database::{TransactionBackend, TransactionDatabase},
@@ -40,7 +43,11 @@ use std::sync::Arc;
 use tari_broadcast_channel::bounded;
 use tari_comms::peer_manager::NodeIdentity;
 use tari_comms_dht::outbound::OutboundMessageRequester;
-use tari_core::transactions::{transaction_protocol::proto, types::CryptoFactories};
+use tari_core::{
+    base_node::proto::base_node::BaseNodeServiceResponse,
+    mempool::proto::mempool::MempoolServiceResponse,
+    transactions::{transaction_protocol::proto, types::CryptoFactories},
+};
 use tari_p2p::{
     comms_connector::PeerMessage,
     domain_message::DomainMessage,
@@ -62,6 +69,7 @@ const LOG_TARGET: &'static str = "base_layer::wallet::transaction_service";
 pub struct TransactionServiceInitializer<T>
 where T: TransactionBackend
 {
+    config: TransactionServiceConfig,
     subscription_factory: Arc<TopicSubscriptionFactory<TariMessageType, Arc<PeerMessage>>>,
     backend: Option<T>,
     node_identity: Arc<NodeIdentity>,
@@ -72,6 +80,7 @@ impl<T> TransactionServiceInitializer<T>
 where T: TransactionBackend
 {
     pub fn new(
+        config: TransactionServiceConfig,
         subscription_factory: Arc<TopicSubscriptionFactory<TariMessageType, Arc<PeerMessage>>>,
         backend: T,
         node_identity: Arc<NodeIdentity>,
@@ -79,6 +88,7 @@ where T: TransactionBackend
     ) -> Self
     {
         Self {
+            config,
             subscription_factory,
             backend: Some(backend),
             node_identity,
@@ -107,6 +117,27 @@ where T: TransactionBackend
             .map(map_decode::<proto::TransactionFinalizedMessage>)
             .filter_map(ok_or_skip_result)
     }
+
+    fn transaction_cancelled_stream(&self) -> impl Stream<Item = DomainMessage<proto::TransactionCancelledMessage>> {
+        self.subscription_factory
+            .get_subscription(TariMessageType::TransactionCancelled)
+            .map(map_decode::<proto::TransactionCancelledMessage>)
+            .filter_map(ok_or_skip_result)
+    }
+
+    fn mempool_response_stream(&self) -> impl Stream<Item = DomainMessage<MempoolServiceResponse>> {
+        self.subscription_factory
+            .get_subscription(TariMessageType::MempoolResponse)
+            .map(map_decode::<MempoolServiceResponse>)
+            .filter_map(ok_or_skip_result)
+    }
+
+    fn base_node_response_stream(&self) -> impl Stream<Item = DomainMessage<BaseNodeServiceResponse>> {
+        self.subscription_factory
+            .get_subscription(TariMessageType::BaseNodeResponse)
+            .map(map_decode::<BaseNodeServiceResponse>)
+            .filter_map(ok_or_skip_result)
+    }
 }
 
 impl<T> ServiceInitializer for TransactionServiceInitializer<T>
@@ -125,6 +156,9 @@ where T: TransactionBackend + Clone + 'static
         let transaction_stream = self.transaction_stream();
         let transaction_reply_stream = self.transaction_reply_stream();
         let transaction_finalized_stream = self.transaction_finalized_stream();
+        let transaction_cancelled_stream = self.transaction_cancelled_stream();
+        let mempool_response_stream = self.mempool_response_stream();
+        let base_node_response_stream = self.base_node_response_stream();
 
         let (publisher, subscriber) = bounded(100);
 
@@ -140,6 +174,7 @@ where T: TransactionBackend + Clone + 'static
 
         let node_identity = self.node_identity.clone();
         let factories = self.factories.clone();
+        let config = self.config;
         executor.spawn(async move {
             let handles = handles_fut.await;
 
@@ -151,11 +186,15 @@ where T: TransactionBackend + Clone + 'static
                 .expect("Output Manager Service handle required for TransactionService");
 
             let service = TransactionService::new(
+                config,
                 TransactionDatabase::new(backend),
                 receiver,
                 transaction_stream,
                 transaction_reply_stream,
                 transaction_finalized_stream,
+                transaction_cancelled_stream,
+                mempool_response_stream,
+                base_node_response_stream,
                 output_manager_service,
                 outbound_message_service,
                 publisher,
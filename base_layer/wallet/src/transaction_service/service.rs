@@ -22,16 +22,24 @@
 
 use crate::{
     output_manager_service::{handle::OutputManagerHandle, TxId},
+    payment_request::PaymentRequest,
     transaction_service::{
-        error::TransactionServiceError,
+        config::TransactionServiceConfig,
+        error::{TransactionServiceError, TransactionStorageError},
         handle::{TransactionEvent, TransactionServiceRequest, TransactionServiceResponse},
+        history::{export_transaction_history, TransactionHistoryEntry},
         storage::database::{
             CompletedTransaction,
             InboundTransaction,
+            OfflineTransaction,
             OutboundTransaction,
             PendingCoinbaseTransaction,
+            RelayedOfflineTransaction,
             TransactionBackend,
             TransactionDatabase,
+            TransactionHistoryQuery,
+            TransactionRecipient,
+            TransactionServiceBackup,
             TransactionStatus,
         },
     },
@@ -49,29 +57,57 @@ use futures::{
 };
 use log::*;
 use rand::RngCore;
-use std::{collections::HashMap, convert::TryInto, sync::Arc};
+use std::{
+    collections::{HashMap, HashSet},
+    convert::TryInto,
+    sync::Arc,
+    time::{Duration, Instant},
+};
 use tari_broadcast_channel::Publisher;
-use tari_comms::{peer_manager::NodeIdentity, types::CommsPublicKey};
+use tari_comms::{
+    peer_manager::{NodeId, NodeIdentity},
+    types::CommsPublicKey,
+};
 use tari_comms_dht::{
     domain_message::OutboundDomainMessage,
+    envelope::NodeDestination,
     outbound::{OutboundEncryption, OutboundMessageRequester, SendMessageResponse},
 };
-use tari_core::transactions::{
-    tari_amount::MicroTari,
-    transaction::{KernelFeatures, OutputFeatures, OutputFlags, Transaction},
-    transaction_protocol::{
-        proto,
-        recipient::{RecipientSignedMessage, RecipientState},
-        sender::TransactionSenderMessage,
-    },
-    types::{CryptoFactories, PrivateKey},
-    ReceiverTransactionProtocol,
-};
 #[cfg(feature = "test_harness")]
 use tari_core::transactions::{tari_amount::T, types::BlindingFactor};
+use tari_core::{
+    base_node::proto::base_node::{
+        base_node_service_request::Request as BaseNodeRequestProto,
+        base_node_service_response::Response as BaseNodeResponseProto,
+        BaseNodeServiceRequest,
+        BaseNodeServiceResponse,
+        HashOutputs,
+    },
+    mempool::proto::mempool::{
+        mempool_service_request::Request as MempoolRequestProto,
+        mempool_service_response::Response as MempoolResponseProto,
+        MempoolServiceRequest,
+        MempoolServiceResponse,
+        TxStorageResponse,
+    },
+    transactions::{
+        proto::types::Transaction as ProtoTransaction,
+        tari_amount::MicroTari,
+        transaction::{KernelFeatures, OutputFeatures, OutputFlags, Transaction},
+        transaction_protocol::{
+            proto,
+            recipient::{RecipientSignedMessage, RecipientState},
+            sender::TransactionSenderMessage,
+        },
+        types::{CryptoFactories, PrivateKey},
+        ReceiverTransactionProtocol,
+    },
+};
 use tari_crypto::{commitment::HomomorphicCommitmentFactory, keys::SecretKey};
 use tari_p2p::{domain_message::DomainMessage, tari_message::TariMessageType};
 use tari_service_framework::{reply_channel, reply_channel::Receiver};
+use tari_utilities::hash::Hashable;
+use tokio::time;
 
 const LOG_TARGET: &'static str = "base_layer::wallet::transaction_service::service";
 
@@ -82,6 +118,19 @@ pub struct PendingCoinbaseSpendingKey {
     pub spending_key: PrivateKey,
 }
 
+/// How a transaction negotiation message was sent to its recipient
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum MessageSendStatus {
+    /// The message was sent directly to the recipient
+    Direct,
+    /// The message was sent to the recipient's neighbours to be stored and forwarded when the recipient comes online
+    StoreAndForward,
+    /// The recipient has to be discovered before the message can be sent
+    PendingDiscovery,
+    /// The message could not be sent
+    Failed,
+}
+
 /// TransactionService allows for the management of multiple inbound and outbound transaction protocols
 /// which are uniquely identified by a tx_id. The TransactionService generates and accepts the various protocol
 /// messages and applies them to the appropriate protocol instances based on the tx_id.
@@ -89,42 +138,83 @@ pub struct PendingCoinbaseSpendingKey {
 /// response is handled the transaction is completed and moved to the completed_transaction buffer.
 /// The TransactionService will accept inbound transactions and generate a reply. Received transactions will remain
 /// in the pending_inbound_transactions buffer.
-/// TODO Allow for inbound transactions that are detected on the blockchain to be marked as complete in the
-/// OutputManagerService
-/// TODO Detect Completed Transactions on the blockchain before marking them as completed in OutputManagerService
+/// Once a base node public key has been set, completed transactions are submitted to that base node's mempool and the
+/// base node is periodically polled for the kernels of the monitored transactions. Transactions are marked as mined
+/// when their kernels are found in the blockchain and their outputs are confirmed in the OutputManagerService once
+/// they have the configured number of confirmations.
 /// # Fields
 /// `pending_outbound_transactions` - List of transaction protocols sent by this client and waiting response from the
 /// recipient
 /// `pending_inbound_transactions` - List of transaction protocols that have been received and responded to.
 /// `completed_transaction` - List of sent transactions that have been responded to and are completed.
-
-pub struct TransactionService<TTxStream, TTxReplyStream, TTxFinalizedStream, TBackend>
-where TBackend: TransactionBackend + Clone + 'static
+/// `resend_schedule` - The time at which the messages of each pending transaction are next sent again, and the current
+/// backoff between resends of that transaction.
+
+pub struct TransactionService<
+    TTxStream,
+    TTxReplyStream,
+    TTxFinalizedStream,
+    TTxCancelledStream,
+    TMempoolResponseStream,
+    TBaseNodeResponseStream,
+    TBackend,
+> where TBackend: TransactionBackend + Clone + 'static
 {
+    config: TransactionServiceConfig,
     db: TransactionDatabase<TBackend>,
     outbound_message_service: OutboundMessageRequester,
     output_manager_service: OutputManagerHandle,
     transaction_stream: Option<TTxStream>,
     transaction_reply_stream: Option<TTxReplyStream>,
     transaction_finalized_stream: Option<TTxFinalizedStream>,
+    transaction_cancelled_stream: Option<TTxCancelledStream>,
+    mempool_response_stream: Option<TMempoolResponseStream>,
+    base_node_response_stream: Option<TBaseNodeResponseStream>,
     request_stream: Option<
         reply_channel::Receiver<TransactionServiceRequest, Result<TransactionServiceResponse, TransactionServiceError>>,
     >,
     event_publisher: Publisher<TransactionEvent>,
     node_identity: Arc<NodeIdentity>,
     factories: CryptoFactories,
-    discovery_process_futures: FuturesUnordered<BoxFuture<'static, Result<TxId, TransactionServiceError>>>,
+    discovery_process_futures: FuturesUnordered<BoxFuture<'static, (TxId, MessageSendStatus)>>,
+    base_node_public_key: Option<CommsPublicKey>,
+    chain_height: Option<u64>,
+    chain_metadata_request_key: Option<u64>,
+    pending_mempool_queries: HashMap<u64, TxId>,
+    pending_kernel_queries: HashMap<u64, Vec<TxId>>,
+    rebroadcast_attempts: HashMap<TxId, usize>,
+    resend_schedule: HashMap<TxId, (Instant, Duration)>,
 }
 
-impl<TTxStream, TTxReplyStream, TTxFinalizedStream, TBackend>
-    TransactionService<TTxStream, TTxReplyStream, TTxFinalizedStream, TBackend>
+impl<
+        TTxStream,
+        TTxReplyStream,
+        TTxFinalizedStream,
+        TTxCancelledStream,
+        TMempoolResponseStream,
+        TBaseNodeResponseStream,
+        TBackend,
+    >
+    TransactionService<
+        TTxStream,
+        TTxReplyStream,
+        TTxFinalizedStream,
+        TTxCancelledStream,
+        TMempoolResponseStream,
+        TBaseNodeResponseStream,
+        TBackend,
+    >
 where
     TTxStream: Stream<Item = DomainMessage<proto::TransactionSenderMessage>>,
     TTxReplyStream: Stream<Item = DomainMessage<proto::RecipientSignedMessage>>,
     TTxFinalizedStream: Stream<Item = DomainMessage<proto::TransactionFinalizedMessage>>,
+    TTxCancelledStream: Stream<Item = DomainMessage<proto::TransactionCancelledMessage>>,
+    TMempoolResponseStream: Stream<Item = DomainMessage<MempoolServiceResponse>>,
+    TBaseNodeResponseStream: Stream<Item = DomainMessage<BaseNodeServiceResponse>>,
     TBackend: TransactionBackend + Clone + 'static,
 {
     pub fn new(
+        config: TransactionServiceConfig,
         db: TransactionDatabase<TBackend>,
         request_stream: Receiver<
             TransactionServiceRequest,
@@ -133,6 +223,9 @@ where
         transaction_stream: TTxStream,
         transaction_reply_stream: TTxReplyStream,
         transaction_finalized_stream: TTxFinalizedStream,
+        transaction_cancelled_stream: TTxCancelledStream,
+        mempool_response_stream: TMempoolResponseStream,
+        base_node_response_stream: TBaseNodeResponseStream,
         output_manager_service: OutputManagerHandle,
         outbound_message_service: OutboundMessageRequester,
         event_publisher: Publisher<TransactionEvent>,
@@ -141,17 +234,28 @@ where
     ) -> Self
     {
         TransactionService {
+            config,
             db,
             outbound_message_service,
             output_manager_service,
             transaction_stream: Some(transaction_stream),
             transaction_reply_stream: Some(transaction_reply_stream),
             transaction_finalized_stream: Some(transaction_finalized_stream),
+            transaction_cancelled_stream: Some(transaction_cancelled_stream),
+            mempool_response_stream: Some(mempool_response_stream),
+            base_node_response_stream: Some(base_node_response_stream),
             request_stream: Some(request_stream),
             event_publisher,
             node_identity,
             factories,
             discovery_process_futures: FuturesUnordered::new(),
+            base_node_public_key: None,
+            chain_height: None,
+            chain_metadata_request_key: None,
+            pending_mempool_queries: HashMap::new(),
+            pending_kernel_queries: HashMap::new(),
+            rebroadcast_attempts: HashMap::new(),
+            resend_schedule: HashMap::new(),
         }
     }
 
@@ -181,6 +285,37 @@ where
             .expect("Transaction Service initialized without transaction_finalized_stream")
             .fuse();
         pin_mut!(transaction_finalized_stream);
+        let transaction_cancelled_stream = self
+            .transaction_cancelled_stream
+            .take()
+            .expect("Transaction Service initialized without transaction_cancelled_stream")
+            .fuse();
+        pin_mut!(transaction_cancelled_stream);
+        let mempool_response_stream = self
+            .mempool_response_stream
+            .take()
+            .expect("Transaction Service initialized without mempool_response_stream")
+            .fuse();
+        pin_mut!(mempool_response_stream);
+        let base_node_response_stream = self
+            .base_node_response_stream
+            .take()
+            .expect("Transaction Service initialized without base_node_response_stream")
+            .fuse();
+        pin_mut!(base_node_response_stream);
+
+        let monitoring_interval = self.config.base_node_monitoring_interval;
+        let mut base_node_monitoring_tick =
+            time::interval_at((Instant::now() + monitoring_interval).into(), monitoring_interval).fuse();
+        let resend_interval = self.config.resend_interval;
+        let mut resend_tick = time::interval_at((Instant::now() + resend_interval).into(), resend_interval).fuse();
+
+        // The negotiation state of the pending transactions is kept in the database, so the negotiations that were
+        // interrupted when the wallet was last shut down are resumed by sending their messages again
+        let _ = self.resend_pending_transactions().await.or_else(|err| {
+            error!(target: LOG_TARGET, "Error resending pending transactions: {:?}", err);
+            Err(err)
+        });
 
         loop {
             futures::select! {
@@ -240,23 +375,62 @@ where
                                 .await;
                     }
                 },
-                response = self.discovery_process_futures.select_next_some() => {
-                    match response {
-                        Ok(tx_id) => {
-                            let _ = self.event_publisher
-                                .send(TransactionEvent::TransactionSendDiscoveryComplete(tx_id, true))
-                                .await;
-                        },
-                        Err(TransactionServiceError::DiscoveryProcessFailed(tx_id)) => {
-                            if let Err(e) = self.output_manager_service.cancel_transaction(tx_id).await {
-                                error!(target: LOG_TARGET, "Failed to Cancel TX_ID: {} after failed sending attempt", tx_id);
-                            }
-                            error!(target: LOG_TARGET, "Discovery and Send failed for TX_ID: {}", tx_id);
-                            let _ = self.event_publisher
-                                .send(TransactionEvent::TransactionSendDiscoveryComplete(tx_id, false))
+                // Incoming messages from the Comms layer
+                msg = transaction_cancelled_stream.select_next_some() => {
+                    let result = self.accept_transaction_cancellation(msg.dht_header.origin_public_key, msg.inner).await.or_else(|err| {
+                        error!(target: LOG_TARGET, "Failed to handle incoming message: {:?}", err);
+                        Err(err)
+                    });
+
+                    if result.is_err() {
+                        let _ = self.event_publisher
+                                .send(TransactionEvent::Error(
+                                    "Error handling Transaction Cancelled message".to_string(),
+                                ))
                                 .await;
-                        }
-                        Err(e) => error!(target: LOG_TARGET, "Discovery and Send failed with Error: {:?}", e),
+                    }
+                },
+                // Incoming messages from the Comms layer
+                msg = mempool_response_stream.select_next_some() => {
+                    let _ = self.handle_mempool_response(msg.inner).await.or_else(|err| {
+                        error!(target: LOG_TARGET, "Failed to handle incoming Mempool response: {:?}", err);
+                        Err(err)
+                    });
+                },
+                // Incoming messages from the Comms layer
+                msg = base_node_response_stream.select_next_some() => {
+                    let _ = self.handle_base_node_response(msg.inner).await.or_else(|err| {
+                        error!(target: LOG_TARGET, "Failed to handle incoming Base Node response: {:?}", err);
+                        Err(err)
+                    });
+                },
+                _ = base_node_monitoring_tick.select_next_some() => {
+                    let _ = self.monitor_transactions().await.or_else(|err| {
+                        error!(target: LOG_TARGET, "Error monitoring transactions with the base node: {:?}", err);
+                        Err(err)
+                    });
+                },
+                _ = resend_tick.select_next_some() => {
+                    let _ = self.resend_due_transactions().await.or_else(|err| {
+                        error!(target: LOG_TARGET, "Error resending pending transactions: {:?}", err);
+                        Err(err)
+                    });
+                },
+                response = self.discovery_process_futures.select_next_some() => {
+                    // The transaction is kept whatever the outcome, its messages are sent again later if they could
+                    // not be sent at all. A message that was sent to be stored and forwarded has been sent, which is
+                    // told apart from a direct send by the `TransactionStoreAndForwardSend` event.
+                    let (tx_id, status) = response;
+                    if status == MessageSendStatus::Failed {
+                        error!(target: LOG_TARGET, "Discovery and Send failed for TX_ID: {}", tx_id);
+                    }
+                    let _ = self.event_publisher
+                        .send(TransactionEvent::TransactionSendDiscoveryComplete(tx_id, status != MessageSendStatus::Failed))
+                        .await;
+                    if status == MessageSendStatus::StoreAndForward {
+                        let _ = self.event_publisher
+                            .send(TransactionEvent::TransactionStoreAndForwardSend(tx_id))
+                            .await;
                     }
                 },
 
@@ -276,10 +450,27 @@ where
     ) -> Result<TransactionServiceResponse, TransactionServiceError>
     {
         match request {
-            TransactionServiceRequest::SendTransaction((dest_pubkey, amount, fee_per_gram, message)) => self
-                .send_transaction(dest_pubkey, amount, fee_per_gram, message)
+            TransactionServiceRequest::SendTransaction((dest_pubkey, amount, fee_per_gram, lock_height, message)) => {
+                self.send_transaction(dest_pubkey, amount, fee_per_gram, lock_height, message)
+                    .await
+                    .map(TransactionServiceResponse::TransactionSent)
+            },
+            TransactionServiceRequest::PayPaymentRequest((request, amount, fee_per_gram)) => self
+                .pay_payment_request(request, amount, fee_per_gram)
+                .await
+                .map(TransactionServiceResponse::TransactionSent),
+            TransactionServiceRequest::SendTransactionToMultipleRecipients((recipients, fee_per_gram, message)) => self
+                .send_transaction_to_multiple_recipients(recipients, fee_per_gram, message)
                 .await
-                .map(|_| TransactionServiceResponse::TransactionSent),
+                .map(TransactionServiceResponse::TransactionSent),
+            TransactionServiceRequest::CreateCoinSplit((amount_per_split, split_count, fee_per_gram, message)) => self
+                .create_coin_split(amount_per_split, split_count, fee_per_gram, message)
+                .await
+                .map(TransactionServiceResponse::TransactionToSelfCreated),
+            TransactionServiceRequest::ConsolidateOutputs((max_outputs, fee_per_gram, message)) => self
+                .consolidate_outputs(max_outputs, fee_per_gram, message)
+                .await
+                .map(TransactionServiceResponse::TransactionToSelfCreated),
             TransactionServiceRequest::GetPendingInboundTransactions => Ok(
                 TransactionServiceResponse::PendingInboundTransactions(self.get_pending_inbound_transactions()?),
             ),
@@ -289,6 +480,15 @@ where
             TransactionServiceRequest::GetCompletedTransactions => Ok(
                 TransactionServiceResponse::CompletedTransactions(self.get_completed_transactions()?),
             ),
+            TransactionServiceRequest::GetTransactionHistory(query) => Ok(
+                TransactionServiceResponse::TransactionHistory(self.get_transaction_history(query)?),
+            ),
+            TransactionServiceRequest::ExportTransactionHistory((query, format)) => {
+                let history = self.get_transaction_history(query)?;
+                Ok(TransactionServiceResponse::TransactionHistoryExport(
+                    export_transaction_history(&history, format)?,
+                ))
+            },
             TransactionServiceRequest::RequestCoinbaseSpendingKey((amount, maturity_height)) => Ok(
                 TransactionServiceResponse::CoinbaseKey(self.request_coinbase_key(amount, maturity_height).await?),
             ),
@@ -300,6 +500,66 @@ where
                 self.cancel_pending_coinbase_transaction(tx_id).await?;
                 Ok(TransactionServiceResponse::CoinbaseTransactionCancelled)
             },
+            TransactionServiceRequest::CancelTransaction(tx_id) => {
+                self.cancel_transaction(tx_id).await?;
+                Ok(TransactionServiceResponse::TransactionCancelled)
+            },
+            TransactionServiceRequest::SetBaseNodePublicKey(public_key) => {
+                self.set_base_node_public_key(public_key).await?;
+                Ok(TransactionServiceResponse::BaseNodePublicKeySet)
+            },
+            TransactionServiceRequest::GetBackup => self.get_backup().map(TransactionServiceResponse::Backup),
+            TransactionServiceRequest::RestoreBackup(backup) => {
+                self.restore_backup(backup)?;
+                Ok(TransactionServiceResponse::BackupRestored)
+            },
+            TransactionServiceRequest::ApplyEncryption(cipher) => self
+                .db
+                .apply_encryption(cipher)
+                .map(|_| TransactionServiceResponse::EncryptionApplied)
+                .map_err(TransactionServiceError::TransactionStorageError),
+            TransactionServiceRequest::Unlock(cipher) => self
+                .db
+                .unlock(cipher)
+                .map(|_| TransactionServiceResponse::Unlocked)
+                .map_err(TransactionServiceError::TransactionStorageError),
+            TransactionServiceRequest::Lock => self
+                .db
+                .lock()
+                .map(|_| TransactionServiceResponse::Locked)
+                .map_err(TransactionServiceError::TransactionStorageError),
+            TransactionServiceRequest::CreateOfflineTransaction((
+                dest_pubkey,
+                amount,
+                fee_per_gram,
+                lock_height,
+                message,
+            )) => self
+                .create_offline_transaction(dest_pubkey, amount, fee_per_gram, lock_height, message)
+                .await
+                .map(TransactionServiceResponse::OfflineTransactionCreated),
+            TransactionServiceRequest::SendOfflineTransaction(offline_tx) => {
+                self.send_offline_transaction(offline_tx).await?;
+                Ok(TransactionServiceResponse::OfflineTransactionSent)
+            },
+            TransactionServiceRequest::GetOfflineTransactionReply(tx_id) => self
+                .get_offline_transaction_reply(tx_id)
+                .map(TransactionServiceResponse::OfflineTransactionReply),
+            TransactionServiceRequest::FinalizeOfflineTransaction(recipient_reply) => self
+                .finalize_offline_transaction(recipient_reply)
+                .map(TransactionServiceResponse::OfflineTransactionFinalized),
+            TransactionServiceRequest::SubmitOfflineTransaction((tx_id, transaction)) => {
+                self.submit_offline_transaction(tx_id, transaction).await?;
+                Ok(TransactionServiceResponse::OfflineTransactionSubmitted)
+            },
+            TransactionServiceRequest::SubmitTransactionToSelf((tx_id, amount, fee, transaction, message)) => self
+                .complete_transaction_to_self(tx_id, amount, fee, transaction, message)
+                .await
+                .map(TransactionServiceResponse::TransactionToSelfCreated),
+            TransactionServiceRequest::ResendPendingTransactions => self
+                .resend_pending_transactions()
+                .await
+                .map(TransactionServiceResponse::PendingTransactionsResent),
             #[cfg(feature = "test_harness")]
             TransactionServiceRequest::CompletePendingOutboundTransaction(completed_transaction) => {
                 self.complete_pending_outbound_transaction(completed_transaction)
@@ -329,22 +589,42 @@ where
         }
     }
 
+    /// Pay a payment request that is for this wallet's network and has not expired
+    pub async fn pay_payment_request(
+        &mut self,
+        request: PaymentRequest,
+        amount: Option<MicroTari>,
+        fee_per_gram: MicroTari,
+    ) -> Result<TxId, TransactionServiceError>
+    {
+        request.check(self.config.network)?;
+        let amount = request.amount_to_pay(amount)?;
+        info!(
+            target: LOG_TARGET,
+            "Paying payment request of {} to {}", amount, request.public_key
+        );
+        self.send_transaction(request.public_key, amount, fee_per_gram, None, request.message)
+            .await
+    }
+
     /// Sends a new transaction to a recipient
     /// # Arguments
     /// 'dest_pubkey': The Comms pubkey of the recipient node
     /// 'amount': The amount of Tari to send to the recipient
     /// 'fee_per_gram': The amount of fee per transaction gram to be included in transaction
+    /// 'lock_height': The block height before which the transaction cannot be mined, if any
     pub async fn send_transaction(
         &mut self,
         dest_pubkey: CommsPublicKey,
         amount: MicroTari,
         fee_per_gram: MicroTari,
+        lock_height: Option<u64>,
         message: String,
-    ) -> Result<(), TransactionServiceError>
+    ) -> Result<TxId, TransactionServiceError>
     {
         let mut sender_protocol = self
             .output_manager_service
-            .prepare_transaction_to_send(amount, fee_per_gram, None, message.clone())
+            .prepare_transaction_to_send(amount, fee_per_gram, lock_height, message.clone())
             .await?;
 
         if !sender_protocol.is_single_round_message_ready() {
@@ -355,56 +635,132 @@ where
         let tx_id = msg.tx_id;
         let proto_message = proto::TransactionSenderMessage::single(msg.into());
 
+        // The transaction is stored before the message is sent, so that the negotiation can be resumed by sending the
+        // message again if the recipient does not reply
+        self.db.add_pending_outbound_transaction(tx_id, OutboundTransaction {
+            tx_id,
+            destination_public_key: dest_pubkey.clone(),
+            amount,
+            fee: sender_protocol.get_fee_amount()?,
+            sender_protocol,
+            message,
+            timestamp: Utc::now().naive_utc(),
+            recipients: Vec::new(),
+            cancelled: false,
+        })?;
+
         match self
-            .outbound_message_service
-            .send_direct(
+            .send_transaction_message(
+                tx_id,
                 dest_pubkey.clone(),
-                OutboundEncryption::EncryptForPeer,
-                OutboundDomainMessage::new(TariMessageType::SenderPartialTransaction, proto_message),
+                TariMessageType::SenderPartialTransaction,
+                proto_message,
             )
-            .await?
+            .await
         {
-            SendMessageResponse::Ok(_) => (),
-            SendMessageResponse::Failed => return Err(TransactionServiceError::OutboundSendFailure),
-            SendMessageResponse::PendingDiscovery(r) => {
-                // The sending of the message resulted in a long running Discovery process being performed by the Comms
-                // layer. This can take minutes so we will spawn a task to wait for the result and then act
-                // appropriately on it
-                let db_clone = self.db.clone();
-                let tx_id_clone = tx_id.clone();
-                let outbound_tx_clone = OutboundTransaction {
-                    tx_id,
-                    destination_public_key: dest_pubkey.clone(),
-                    amount,
-                    fee: sender_protocol.get_fee_amount()?,
-                    sender_protocol: sender_protocol.clone(),
-                    message: message.clone(),
-                    timestamp: Utc::now().naive_utc(),
-                };
-                let discovery_future = async move {
-                    transaction_send_discovery_process_completion(r, db_clone, tx_id_clone, outbound_tx_clone).await
-                };
-                self.discovery_process_futures.push(discovery_future.boxed());
-                return Err(TransactionServiceError::OutboundSendDiscoveryInProgress(tx_id.clone()));
+            MessageSendStatus::PendingDiscovery => {
+                return Err(TransactionServiceError::OutboundSendDiscoveryInProgress(tx_id));
             },
+            MessageSendStatus::Failed => warn!(
+                target: LOG_TARGET,
+                "Transaction with TX_ID = {} could not be sent to {}, it will be sent again later", tx_id, dest_pubkey
+            ),
+            _ => info!(
+                target: LOG_TARGET,
+                "Transaction with TX_ID = {} sent to {}", tx_id, dest_pubkey
+            ),
+        }
+
+        Ok(tx_id)
+    }
+
+    /// Sends a new transaction that pays several recipients. Each recipient replies to its own part of the transaction,
+    /// and the transaction is completed once all of them have replied. If the part of any recipient cannot be sent the
+    /// transaction is cancelled and all of its recipients are told so.
+    /// # Arguments
+    /// 'recipients': The Comms pubkey of each recipient node and the amount of Tari to send to it
+    /// 'fee_per_gram': The amount of fee per transaction gram to be included in transaction
+    pub async fn send_transaction_to_multiple_recipients(
+        &mut self,
+        recipients: Vec<(CommsPublicKey, MicroTari)>,
+        fee_per_gram: MicroTari,
+        message: String,
+    ) -> Result<TxId, TransactionServiceError>
+    {
+        let amounts = recipients.iter().map(|(_, amount)| *amount).collect();
+        let mut sender_protocol = self
+            .output_manager_service
+            .prepare_multi_recipient_transaction_to_send(amounts, fee_per_gram, None, message.clone())
+            .await?;
+
+        if !sender_protocol.is_multi_recipient_messages_ready() {
+            return Err(TransactionServiceError::InvalidStateError);
         }
 
+        let msgs = sender_protocol.build_multi_recipient_messages()?;
+        let tx_id = sender_protocol.get_tx_id()?;
+        let transaction_recipients = recipients
+            .iter()
+            .zip(msgs.iter())
+            .map(|((public_key, amount), msg)| TransactionRecipient {
+                tx_id: msg.tx_id,
+                public_key: public_key.clone(),
+                amount: *amount,
+            })
+            .collect::<Vec<_>>();
+
+        // The transaction is stored before any of the messages are sent, so that it is available to the replies of the
+        // recipients that receive them first
         self.db.add_pending_outbound_transaction(tx_id, OutboundTransaction {
             tx_id,
-            destination_public_key: dest_pubkey.clone(),
-            amount,
+            destination_public_key: recipients[0].0.clone(),
+            amount: sender_protocol.get_total_amount()?,
             fee: sender_protocol.get_fee_amount()?,
             sender_protocol,
             message,
             timestamp: Utc::now().naive_utc(),
+            recipients: transaction_recipients.clone(),
+            cancelled: false,
         })?;
 
+        let mut failed_recipients = Vec::new();
+        for (recipient, msg) in transaction_recipients.iter().zip(msgs.into_iter()) {
+            let proto_message = proto::TransactionSenderMessage::single(msg.into());
+            let status = self
+                .send_transaction_message(
+                    tx_id,
+                    recipient.public_key.clone(),
+                    TariMessageType::SenderPartialTransaction,
+                    proto_message,
+                )
+                .await;
+            if status == MessageSendStatus::Failed {
+                failed_recipients.push(recipient.public_key.clone());
+            }
+        }
+
+        // The transaction cannot complete without every recipient, so if any of them could not be sent its part the
+        // transaction is cancelled, which releases its outputs and tells the recipients that did receive theirs
+        if !failed_recipients.is_empty() {
+            warn!(
+                target: LOG_TARGET,
+                "Transaction with TX_ID = {} could not be sent to {} of its {} recipients, it will be cancelled",
+                tx_id,
+                failed_recipients.len(),
+                transaction_recipients.len()
+            );
+            self.cancel_transaction(tx_id).await?;
+            return Err(TransactionServiceError::OutboundSendFailure);
+        }
+
         info!(
             target: LOG_TARGET,
-            "Transaction with TX_ID = {} sent to {}", tx_id, dest_pubkey
+            "Transaction with TX_ID = {} sent to {} recipients",
+            tx_id,
+            transaction_recipients.len()
         );
 
-        Ok(())
+        Ok(tx_id)
     }
 
     /// Accept the public reply from a recipient and apply the reply to the relevant transaction protocol
@@ -420,10 +776,72 @@ where
             .try_into()
             .map_err(TransactionServiceError::InvalidMessageError)?;
 
-        let mut outbound_tx = self
+        if self.find_relayed_offline_transaction(recipient_reply.tx_id)?.is_some() {
+            return self
+                .accept_offline_transaction_reply(source_pubkey, recipient_reply)
+                .await;
+        }
+
+        let outbound_tx = match self
             .db
-            .get_pending_outbound_transaction(recipient_reply.tx_id.clone())?;
+            .find_pending_outbound_transaction_for_recipient(recipient_reply.tx_id.clone())
+        {
+            Ok(tx) => tx,
+            Err(TransactionStorageError::ValueNotFound(_)) => {
+                return self
+                    .resend_finalized_transaction(source_pubkey, recipient_reply.tx_id)
+                    .await;
+            },
+            Err(e) => return Err(e.into()),
+        };
+        if outbound_tx.cancelled {
+            return Err(TransactionServiceError::TransactionCancelled);
+        }
+
+        if !outbound_tx.recipients.is_empty() {
+            return self
+                .accept_multi_recipient_reply(source_pubkey, outbound_tx, recipient_reply)
+                .await;
+        }
+
+        let tx_id = recipient_reply.tx_id.clone();
+        let completed_transaction = self.complete_outbound_transaction(outbound_tx, recipient_reply)?;
+        info!(
+            target: LOG_TARGET,
+            "Transaction Recipient Reply for TX_ID = {} received", tx_id,
+        );
+
+        let finalized_transaction_message = proto::TransactionFinalizedMessage {
+            tx_id,
+            transaction: Some(completed_transaction.transaction.clone().into()),
+        };
+        self.send_transaction_message(
+            tx_id,
+            source_pubkey.clone(),
+            TariMessageType::TransactionFinalized,
+            finalized_transaction_message,
+        )
+        .await;
+
+        self.submit_completed_transaction_if_monitoring(completed_transaction)
+            .await;
+
+        self.event_publisher
+            .send(TransactionEvent::ReceivedTransactionReply(tx_id))
+            .await
+            .map_err(|_| TransactionServiceError::EventStreamError)?;
 
+        Ok(())
+    }
+
+    /// Apply the recipient's reply to a single recipient outbound transaction, finalize the transaction and move it to
+    /// the completed transactions
+    fn complete_outbound_transaction(
+        &mut self,
+        mut outbound_tx: OutboundTransaction,
+        recipient_reply: RecipientSignedMessage,
+    ) -> Result<CompletedTransaction, TransactionServiceError>
+    {
         let tx_id = recipient_reply.tx_id.clone();
         if !outbound_tx.sender_protocol.check_tx_id(tx_id.clone()) ||
             !outbound_tx.sender_protocol.is_collecting_single_signature()
@@ -439,7 +857,6 @@ where
             .finalize(KernelFeatures::empty(), &self.factories)?;
         let tx = outbound_tx.sender_protocol.get_transaction()?;
 
-        // TODO Broadcast this to the chain
         let completed_transaction = CompletedTransaction {
             tx_id: tx_id.clone(),
             source_public_key: self.node_identity.public_key().clone(),
@@ -450,26 +867,99 @@ where
             status: TransactionStatus::Completed,
             message: outbound_tx.message.clone(),
             timestamp: Utc::now().naive_utc(),
+            mined_height: None,
         };
         self.db
             .complete_outbound_transaction(tx_id.clone(), completed_transaction.clone())?;
+
+        Ok(completed_transaction)
+    }
+
+    /// Apply the reply from one of the recipients of a multi-recipient transaction. The transaction is finalized and
+    /// sent to every recipient once all of them have replied.
+    async fn accept_multi_recipient_reply(
+        &mut self,
+        source_pubkey: CommsPublicKey,
+        mut outbound_tx: OutboundTransaction,
+        recipient_reply: RecipientSignedMessage,
+    ) -> Result<(), TransactionServiceError>
+    {
+        let recipient_tx_id = recipient_reply.tx_id;
+        match outbound_tx.recipients.iter().find(|r| r.tx_id == recipient_tx_id) {
+            Some(r) if r.public_key == source_pubkey => (),
+            _ => {
+                error!(
+                    target: LOG_TARGET,
+                    "Recipient Reply Source Public Key does not correspond to the recipient of TX_ID = {}",
+                    recipient_tx_id
+                );
+                return Err(TransactionServiceError::InvalidSourcePublicKey);
+            },
+        }
+        if !outbound_tx.sender_protocol.is_collecting_multiple_signatures() {
+            return Err(TransactionServiceError::InvalidStateError);
+        }
+
+        outbound_tx
+            .sender_protocol
+            .add_multi_recipient_info(recipient_reply, &self.factories.range_proof)?;
+        let tx_id = outbound_tx.tx_id;
         info!(
             target: LOG_TARGET,
-            "Transaction Recipient Reply for TX_ID = {} received", tx_id,
+            "Transaction Recipient Reply for TX_ID = {} received for multi-recipient TX_ID = {}",
+            recipient_tx_id,
+            tx_id,
         );
 
-        let finalized_transaction_message = proto::TransactionFinalizedMessage {
+        if !outbound_tx.sender_protocol.is_finalizing() {
+            // Wait for the rest of the recipients to reply
+            self.db.update_pending_outbound_transaction(tx_id, outbound_tx)?;
+            return Ok(());
+        }
+
+        outbound_tx
+            .sender_protocol
+            .finalize(KernelFeatures::empty(), &self.factories)?;
+        let tx = outbound_tx.sender_protocol.get_transaction()?;
+
+        let completed_transaction = CompletedTransaction {
             tx_id,
-            transaction: Some(tx.clone().into()),
+            source_public_key: self.node_identity.public_key().clone(),
+            destination_public_key: outbound_tx.destination_public_key,
+            amount: outbound_tx.amount,
+            fee: outbound_tx.fee,
+            transaction: tx.clone(),
+            status: TransactionStatus::Completed,
+            message: outbound_tx.message.clone(),
+            timestamp: Utc::now().naive_utc(),
+            mined_height: None,
         };
+        self.db
+            .complete_outbound_transaction(tx_id, completed_transaction.clone())?;
+        info!(
+            target: LOG_TARGET,
+            "All {} recipients of TX_ID = {} have replied",
+            outbound_tx.recipients.len(),
+            tx_id
+        );
 
-        self.outbound_message_service
-            .send_direct(
-                source_pubkey.clone(),
-                OutboundEncryption::EncryptForPeer,
-                OutboundDomainMessage::new(TariMessageType::TransactionFinalized, finalized_transaction_message),
+        // Each recipient knows the transaction by the tx_id of its own part of it
+        for recipient in outbound_tx.recipients.iter() {
+            let finalized_transaction_message = proto::TransactionFinalizedMessage {
+                tx_id: recipient.tx_id,
+                transaction: Some(tx.clone().into()),
+            };
+            self.send_transaction_message(
+                tx_id,
+                recipient.public_key.clone(),
+                TariMessageType::TransactionFinalized,
+                finalized_transaction_message,
             )
-            .await?;
+            .await;
+        }
+
+        self.submit_completed_transaction_if_monitoring(completed_transaction)
+            .await;
 
         self.event_publisher
             .send(TransactionEvent::ReceivedTransactionReply(tx_id))
@@ -495,41 +985,36 @@ where
 
         // Currently we will only reply to a Single sender transaction protocol
         if let TransactionSenderMessage::Single(data) = sender_message.clone() {
+            // Check this is not a repeat message i.e. tx_id doesn't already exist in our pending or completed
+            // transactions. The sender sends its message again when it has not received the reply, so the reply is
+            // sent again too.
+            if self.db.transaction_exists(&data.tx_id)? {
+                return self.resend_transaction_reply(source_pubkey, data.tx_id).await;
+            }
+
             let amount = data.amount.clone();
 
             let spending_key = self
                 .output_manager_service
                 .get_recipient_spending_key(data.tx_id, data.amount)
                 .await?;
+            let rewind_data = self.output_manager_service.get_rewind_data().await?;
             let mut rng = TransactionRng::new().unwrap();
             let nonce = PrivateKey::random(&mut rng);
 
-            let rtp = ReceiverTransactionProtocol::new(
+            let rtp = ReceiverTransactionProtocol::new_with_rewindable_output(
                 sender_message,
                 nonce,
                 spending_key,
                 OutputFeatures::default(),
                 &self.factories,
+                &rewind_data,
             );
             let recipient_reply = rtp.get_signed_data()?.clone();
-
-            // Check this is not a repeat message i.e. tx_id doesn't already exist in our pending or completed
-            // transactions
-            if self.db.transaction_exists(&recipient_reply.tx_id)? {
-                return Err(TransactionServiceError::RepeatedMessageError);
-            }
-
             let tx_id = recipient_reply.tx_id;
-            let proto_message: proto::RecipientSignedMessage = recipient_reply.into();
-            self.outbound_message_service
-                .send_direct(
-                    source_pubkey.clone(),
-                    OutboundEncryption::EncryptForPeer,
-                    OutboundDomainMessage::new(TariMessageType::ReceiverPartialTransactionReply, proto_message),
-                )
-                .await?;
 
-            // Otherwise add it to our pending transaction list and return reply
+            // The transaction is stored before the reply is sent, so that the reply can be sent again if the sender
+            // does not receive it
             let inbound_transaction = InboundTransaction {
                 tx_id,
                 source_public_key: source_pubkey.clone(),
@@ -537,10 +1022,20 @@ where
                 receiver_protocol: rtp.clone(),
                 message: data.message,
                 timestamp: Utc::now().naive_utc(),
+                cancelled: false,
             };
             self.db
                 .add_pending_inbound_transaction(tx_id, inbound_transaction.clone())?;
 
+            let proto_message: proto::RecipientSignedMessage = recipient_reply.into();
+            self.send_transaction_message(
+                tx_id,
+                source_pubkey.clone(),
+                TariMessageType::ReceiverPartialTransactionReply,
+                proto_message,
+            )
+            .await;
+
             info!(
                 target: LOG_TARGET,
                 "Transaction with TX_ID = {} received from {}. Reply Sent",
@@ -601,6 +1096,9 @@ where
             );
             return Err(TransactionServiceError::InvalidSourcePublicKey);
         }
+        if inbound_tx.cancelled {
+            return Err(TransactionServiceError::TransactionCancelled);
+        }
 
         let rtp_output = match inbound_tx.receiver_protocol.state {
             RecipientState::Finalized(s) => s.output.clone(),
@@ -627,12 +1125,14 @@ where
             status: TransactionStatus::Completed,
             message: inbound_tx.message.clone(),
             timestamp: inbound_tx.timestamp.clone(),
+            mined_height: None,
         };
 
         self.db
             .complete_inbound_transaction(tx_id.clone(), completed_transaction.clone())?;
 
-        // TODO Actually Broadcast this Transaction to a base node
+        self.submit_completed_transaction_if_monitoring(completed_transaction)
+            .await;
 
         self.event_publisher
             .send(TransactionEvent::ReceivedFinalizedTransaction(tx_id))
@@ -649,34 +1149,316 @@ where
         Ok(())
     }
 
-    /// Request a tx_id and spending_key for a coinbase output to be mined
-    pub async fn request_coinbase_key(
+    /// Create a transaction on an offline wallet. The sender protocol is stored as a pending outbound transaction, but
+    /// nothing is sent. The returned offline transaction only contains the public sender message, which is carried to
+    /// an online wallet to be sent to the recipient.
+    pub async fn create_offline_transaction(
         &mut self,
+        dest_pubkey: CommsPublicKey,
         amount: MicroTari,
-        maturity_height: u64,
-    ) -> Result<PendingCoinbaseSpendingKey, TransactionServiceError>
+        fee_per_gram: MicroTari,
+        lock_height: Option<u64>,
+        message: String,
+    ) -> Result<OfflineTransaction, TransactionServiceError>
     {
-        let mut rng = rand::OsRng::new().unwrap();
-        let tx_id: TxId = rng.next_u64();
-
-        let spending_key = self
+        let mut sender_protocol = self
             .output_manager_service
-            .get_coinbase_spending_key(tx_id.clone(), amount.clone(), maturity_height)
+            .prepare_transaction_to_send(amount, fee_per_gram, lock_height, message.clone())
             .await?;
 
-        self.db
-            .add_pending_coinbase_transaction(tx_id.clone(), PendingCoinbaseTransaction {
-                tx_id,
-                amount,
-                commitment: self
-                    .factories
-                    .commitment
-                    .commit_value(&spending_key, u64::from(amount.clone())),
-                timestamp: Utc::now().naive_utc(),
-            })?;
-
-        Ok(PendingCoinbaseSpendingKey { tx_id, spending_key })
-    }
+        if !sender_protocol.is_single_round_message_ready() {
+            return Err(TransactionServiceError::InvalidStateError);
+        }
+
+        let sender_message = sender_protocol.build_single_round_message()?;
+        let tx_id = sender_message.tx_id;
+        let fee = sender_protocol.get_fee_amount()?;
+        self.db.add_pending_outbound_transaction(tx_id, OutboundTransaction {
+            tx_id,
+            destination_public_key: dest_pubkey.clone(),
+            amount,
+            fee,
+            sender_protocol,
+            message: message.clone(),
+            timestamp: Utc::now().naive_utc(),
+            recipients: Vec::new(),
+            cancelled: false,
+        })?;
+
+        info!(
+            target: LOG_TARGET,
+            "Offline Transaction with TX_ID = {} created for {}", tx_id, dest_pubkey
+        );
+
+        Ok(OfflineTransaction {
+            tx_id,
+            destination_public_key: dest_pubkey,
+            amount,
+            fee,
+            message,
+            sender_message,
+        })
+    }
+
+    /// Send a transaction that was created by an offline wallet to its recipient on behalf of the offline wallet
+    pub async fn send_offline_transaction(
+        &mut self,
+        offline_tx: OfflineTransaction,
+    ) -> Result<(), TransactionServiceError>
+    {
+        let tx_id = offline_tx.tx_id;
+        let dest_pubkey = offline_tx.destination_public_key.clone();
+        let proto_message = proto::TransactionSenderMessage::single(offline_tx.sender_message.clone().into());
+        // The transaction is stored before the message is sent, so that it is available to the recipient's reply
+        self.db
+            .save_relayed_offline_transaction(tx_id, RelayedOfflineTransaction {
+                offline_transaction: offline_tx,
+                reply: None,
+            })?;
+
+        self.send_transaction_message(
+            tx_id,
+            dest_pubkey.clone(),
+            TariMessageType::SenderPartialTransaction,
+            proto_message,
+        )
+        .await;
+
+        info!(
+            target: LOG_TARGET,
+            "Offline Transaction with TX_ID = {} sent to {}", tx_id, dest_pubkey
+        );
+
+        Ok(())
+    }
+
+    /// Keep the recipient's reply to a transaction that was sent on behalf of an offline wallet, so that it can be
+    /// carried back to the offline wallet to be signed
+    async fn accept_offline_transaction_reply(
+        &mut self,
+        source_pubkey: CommsPublicKey,
+        recipient_reply: RecipientSignedMessage,
+    ) -> Result<(), TransactionServiceError>
+    {
+        let tx_id = recipient_reply.tx_id;
+        let mut relayed_tx = self
+            .find_relayed_offline_transaction(tx_id)?
+            .ok_or(TransactionServiceError::OfflineTransactionNotFound)?;
+        if relayed_tx.offline_transaction.destination_public_key != source_pubkey {
+            return Err(TransactionServiceError::InvalidSourcePublicKey);
+        }
+        relayed_tx.reply = Some(recipient_reply);
+        self.db.save_relayed_offline_transaction(tx_id, relayed_tx)?;
+        info!(
+            target: LOG_TARGET,
+            "Transaction Recipient Reply for Offline Transaction with TX_ID = {} received", tx_id
+        );
+
+        self.event_publisher
+            .send(TransactionEvent::ReceivedOfflineTransactionReply(tx_id))
+            .await
+            .map_err(|_| TransactionServiceError::EventStreamError)?;
+
+        Ok(())
+    }
+
+    pub fn get_offline_transaction_reply(
+        &self,
+        tx_id: TxId,
+    ) -> Result<Option<RecipientSignedMessage>, TransactionServiceError>
+    {
+        self.find_relayed_offline_transaction(tx_id)?
+            .map(|relayed_tx| relayed_tx.reply)
+            .ok_or(TransactionServiceError::OfflineTransactionNotFound)
+    }
+
+    /// The transaction with the given tx_id that this wallet relayed on behalf of an offline wallet, if there is one
+    fn find_relayed_offline_transaction(
+        &self,
+        tx_id: TxId,
+    ) -> Result<Option<RelayedOfflineTransaction>, TransactionServiceError>
+    {
+        match self.db.get_relayed_offline_transaction(tx_id) {
+            Ok(relayed_tx) => Ok(Some(relayed_tx)),
+            Err(TransactionStorageError::ValueNotFound(_)) => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Apply the recipient's reply to a transaction that was created by this offline wallet and finalize it. The
+    /// transaction is completed but not submitted, it is returned to be carried to the online wallet.
+    pub fn finalize_offline_transaction(
+        &mut self,
+        recipient_reply: RecipientSignedMessage,
+    ) -> Result<Transaction, TransactionServiceError>
+    {
+        let tx_id = recipient_reply.tx_id;
+        let outbound_tx = self.db.get_pending_outbound_transaction(tx_id)?;
+        if outbound_tx.cancelled {
+            return Err(TransactionServiceError::TransactionCancelled);
+        }
+        let completed_transaction = self.complete_outbound_transaction(outbound_tx, recipient_reply)?;
+        info!(
+            target: LOG_TARGET,
+            "Offline Transaction with TX_ID = {} finalized", tx_id
+        );
+
+        Ok(completed_transaction.transaction)
+    }
+
+    /// Send a transaction that was finalized by an offline wallet to its recipient and submit it to the base node. The
+    /// transaction is added to the completed transactions of this wallet so that it is monitored until it is mined.
+    pub async fn submit_offline_transaction(
+        &mut self,
+        tx_id: TxId,
+        transaction: Transaction,
+    ) -> Result<(), TransactionServiceError>
+    {
+        let offline_tx = match self.find_relayed_offline_transaction(tx_id)? {
+            Some(RelayedOfflineTransaction {
+                offline_transaction,
+                reply: Some(_),
+            }) => offline_transaction,
+            Some(_) => return Err(TransactionServiceError::InvalidStateError),
+            None => return Err(TransactionServiceError::OfflineTransactionNotFound),
+        };
+
+        let finalized_transaction_message = proto::TransactionFinalizedMessage {
+            tx_id,
+            transaction: Some(transaction.clone().into()),
+        };
+        self.send_transaction_message(
+            tx_id,
+            offline_tx.destination_public_key.clone(),
+            TariMessageType::TransactionFinalized,
+            finalized_transaction_message,
+        )
+        .await;
+
+        let completed_transaction = CompletedTransaction {
+            tx_id,
+            source_public_key: self.node_identity.public_key().clone(),
+            destination_public_key: offline_tx.destination_public_key,
+            amount: offline_tx.amount,
+            fee: offline_tx.fee,
+            transaction,
+            status: TransactionStatus::Completed,
+            message: offline_tx.message,
+            timestamp: Utc::now().naive_utc(),
+            mined_height: None,
+        };
+        self.db
+            .add_completed_transaction(tx_id, completed_transaction.clone())?;
+        self.db.remove_relayed_offline_transaction(tx_id)?;
+        info!(
+            target: LOG_TARGET,
+            "Offline Transaction with TX_ID = {} submitted", tx_id
+        );
+
+        self.submit_completed_transaction_if_monitoring(completed_transaction)
+            .await;
+
+        Ok(())
+    }
+
+    /// Create a coin split transaction with the Output Manager Service and submit it to the base node like any other
+    /// completed transaction
+    pub async fn create_coin_split(
+        &mut self,
+        amount_per_split: MicroTari,
+        split_count: usize,
+        fee_per_gram: MicroTari,
+        message: String,
+    ) -> Result<TxId, TransactionServiceError>
+    {
+        let (tx_id, fee, amount, tx) = self
+            .output_manager_service
+            .create_coin_split(amount_per_split, split_count, fee_per_gram, None, message.clone())
+            .await?;
+        self.complete_transaction_to_self(tx_id, amount, fee, tx, message).await
+    }
+
+    /// Create a transaction that consolidates the wallet's smallest outputs with the Output Manager Service and submit
+    /// it to the base node like any other completed transaction
+    pub async fn consolidate_outputs(
+        &mut self,
+        max_outputs: usize,
+        fee_per_gram: MicroTari,
+        message: String,
+    ) -> Result<TxId, TransactionServiceError>
+    {
+        let (tx_id, fee, amount, tx) = self
+            .output_manager_service
+            .consolidate_outputs(max_outputs, fee_per_gram, None, message.clone())
+            .await?;
+        self.complete_transaction_to_self(tx_id, amount, fee, tx, message).await
+    }
+
+    /// Store a transaction that this wallet pays to itself as completed and submit it to the base node
+    async fn complete_transaction_to_self(
+        &mut self,
+        tx_id: TxId,
+        amount: MicroTari,
+        fee: MicroTari,
+        transaction: Transaction,
+        message: String,
+    ) -> Result<TxId, TransactionServiceError>
+    {
+        let completed_transaction = CompletedTransaction {
+            tx_id,
+            source_public_key: self.node_identity.public_key().clone(),
+            destination_public_key: self.node_identity.public_key().clone(),
+            amount,
+            fee,
+            transaction,
+            status: TransactionStatus::Completed,
+            message,
+            timestamp: Utc::now().naive_utc(),
+            mined_height: None,
+        };
+        if let Err(e) = self.db.add_completed_transaction(tx_id, completed_transaction.clone()) {
+            let _ = self.output_manager_service.cancel_transaction(tx_id).await;
+            return Err(e.into());
+        }
+        info!(
+            target: LOG_TARGET,
+            "Transaction to self with TX_ID = {} completed", tx_id
+        );
+
+        self.submit_completed_transaction_if_monitoring(completed_transaction)
+            .await;
+
+        Ok(tx_id)
+    }
+
+    /// Request a tx_id and spending_key for a coinbase output to be mined
+    pub async fn request_coinbase_key(
+        &mut self,
+        amount: MicroTari,
+        maturity_height: u64,
+    ) -> Result<PendingCoinbaseSpendingKey, TransactionServiceError>
+    {
+        let mut rng = rand::OsRng::new().unwrap();
+        let tx_id: TxId = rng.next_u64();
+
+        let spending_key = self
+            .output_manager_service
+            .get_coinbase_spending_key(tx_id.clone(), amount.clone(), maturity_height)
+            .await?;
+
+        self.db
+            .add_pending_coinbase_transaction(tx_id.clone(), PendingCoinbaseTransaction {
+                tx_id,
+                amount,
+                commitment: self
+                    .factories
+                    .commitment
+                    .commit_value(&spending_key, u64::from(amount.clone())),
+                timestamp: Utc::now().naive_utc(),
+            })?;
+
+        Ok(PendingCoinbaseSpendingKey { tx_id, spending_key })
+    }
 
     /// Once the miner has constructed the completed Coinbase transaction they will submit it to the Transaction Service
     /// which will monitor the chain to see when it has been mined.
@@ -717,34 +1499,486 @@ where
             return Err(TransactionServiceError::InvalidCompletedTransaction);
         }
 
-        self.db.complete_coinbase_transaction(tx_id, CompletedTransaction {
+        self.db.complete_coinbase_transaction(tx_id, CompletedTransaction {
+            tx_id,
+            source_public_key: self.node_identity.public_key().clone(),
+            destination_public_key: self.node_identity.public_key().clone(),
+            amount: coinbase_tx.amount,
+            fee: MicroTari::from(0),
+            transaction: completed_transaction,
+            status: TransactionStatus::Completed,
+            message: "Coinbase Transaction".to_string(),
+            timestamp: Utc::now().naive_utc(),
+            mined_height: None,
+        })?;
+
+        Ok(())
+    }
+
+    /// If a specific coinbase transaction will not be mined then the Miner can cancel it
+    pub async fn cancel_pending_coinbase_transaction(&mut self, tx_id: TxId) -> Result<(), TransactionServiceError> {
+        let _ = self.db.get_pending_coinbase_transaction(tx_id.clone()).map_err(|e| {
+            error!(
+                target: LOG_TARGET,
+                "Finalized coinbase transaction TxId does not exist in Pending Inbound Transactions"
+            );
+            e
+        })?;
+
+        self.output_manager_service.cancel_transaction(tx_id).await?;
+
+        self.db.cancel_coinbase_transaction(tx_id)?;
+
+        Ok(())
+    }
+
+    /// Cancel a pending inbound or outbound transaction. The outputs it encumbered, or was going to receive, are
+    /// released in the Output Manager Service and each counterparty is sent a cancellation message for the tx_id by
+    /// which it knows the transaction.
+    pub async fn cancel_transaction(&mut self, tx_id: TxId) -> Result<(), TransactionServiceError> {
+        let counterparties = self.pending_transaction_counterparties(tx_id)?;
+
+        self.cancel_pending_transaction(tx_id).await?;
+
+        self.send_transaction_cancelled_messages(tx_id, counterparties).await;
+
+        info!(target: LOG_TARGET, "Transaction with TX_ID = {} cancelled", tx_id);
+
+        Ok(())
+    }
+
+    /// Accept a cancellation message from the counterparty of a pending transaction. The message is accepted if it
+    /// was sent by the sender of an inbound transaction or by one of the recipients of an outbound transaction, which
+    /// know the transaction by the tx_id of their part of it. The other recipients of a cancelled multi-recipient
+    /// transaction are told that it has been cancelled.
+    pub async fn accept_transaction_cancellation(
+        &mut self,
+        source_pubkey: CommsPublicKey,
+        transaction_cancelled: proto::TransactionCancelledMessage,
+    ) -> Result<(), TransactionServiceError>
+    {
+        let counterparty_tx_id = transaction_cancelled.tx_id;
+        let tx_id = match self.db.get_pending_inbound_transaction(counterparty_tx_id) {
+            Ok(_) => counterparty_tx_id,
+            Err(TransactionStorageError::ValueNotFound(_)) => {
+                self.db
+                    .find_pending_outbound_transaction_for_recipient(counterparty_tx_id)?
+                    .tx_id
+            },
+            Err(e) => return Err(e.into()),
+        };
+
+        let counterparties = self.pending_transaction_counterparties(tx_id)?;
+        if !counterparties
+            .iter()
+            .any(|(id, public_key)| *id == counterparty_tx_id && *public_key == source_pubkey)
+        {
+            error!(
+                target: LOG_TARGET,
+                "Transaction Cancelled Source Public Key does not correspond to the counterparty of TX_ID = {}", tx_id
+            );
+            return Err(TransactionServiceError::InvalidSourcePublicKey);
+        }
+
+        self.cancel_pending_transaction(tx_id).await?;
+
+        let other_counterparties = counterparties
+            .into_iter()
+            .filter(|(id, _)| *id != counterparty_tx_id)
+            .collect();
+        self.send_transaction_cancelled_messages(tx_id, other_counterparties)
+            .await;
+
+        info!(
+            target: LOG_TARGET,
+            "Transaction with TX_ID = {} cancelled by {}", tx_id, source_pubkey
+        );
+
+        Ok(())
+    }
+
+    /// The tx_id by which each counterparty of a pending transaction knows it, with the counterparty's public key
+    fn pending_transaction_counterparties(
+        &self,
+        tx_id: TxId,
+    ) -> Result<Vec<(TxId, CommsPublicKey)>, TransactionServiceError>
+    {
+        match self.db.get_pending_outbound_transaction(tx_id) {
+            Ok(outbound_tx) => {
+                if outbound_tx.cancelled {
+                    return Err(TransactionServiceError::TransactionCancelled);
+                }
+                if outbound_tx.recipients.is_empty() {
+                    return Ok(vec![(tx_id, outbound_tx.destination_public_key)]);
+                }
+                return Ok(outbound_tx
+                    .recipients
+                    .into_iter()
+                    .map(|r| (r.tx_id, r.public_key))
+                    .collect());
+            },
+            Err(TransactionStorageError::ValueNotFound(_)) => (),
+            Err(e) => return Err(e.into()),
+        }
+
+        let inbound_tx = match self.db.get_pending_inbound_transaction(tx_id) {
+            Ok(tx) => tx,
+            Err(TransactionStorageError::ValueNotFound(_)) => {
+                return Err(TransactionServiceError::TransactionDoesNotExistError)
+            },
+            Err(e) => return Err(e.into()),
+        };
+        if inbound_tx.cancelled {
+            return Err(TransactionServiceError::TransactionCancelled);
+        }
+        Ok(vec![(tx_id, inbound_tx.source_public_key)])
+    }
+
+    /// Release the outputs of a pending transaction and mark it as cancelled
+    async fn cancel_pending_transaction(&mut self, tx_id: TxId) -> Result<(), TransactionServiceError> {
+        self.output_manager_service.cancel_transaction(tx_id).await?;
+        self.db.cancel_pending_transaction(tx_id)?;
+
+        self.event_publisher
+            .send(TransactionEvent::TransactionCancelled(tx_id))
+            .await
+            .map_err(|_| TransactionServiceError::EventStreamError)?;
+
+        Ok(())
+    }
+
+    /// Tell each counterparty of a cancelled transaction that it has been cancelled. The transaction is already
+    /// cancelled in this wallet, so a message that cannot be sent is logged and the other counterparties are still told
+    async fn send_transaction_cancelled_messages(&mut self, tx_id: TxId, counterparties: Vec<(TxId, CommsPublicKey)>) {
+        for (counterparty_tx_id, public_key) in counterparties {
+            if let Err(e) = self
+                .send_transaction_cancelled_message(counterparty_tx_id, public_key.clone())
+                .await
+            {
+                warn!(
+                    target: LOG_TARGET,
+                    "Could not tell {} that the transaction with TX_ID = {} was cancelled: {:?}", public_key, tx_id, e
+                );
+            }
+        }
+    }
+
+    /// Tell the counterparty of a pending transaction that it has been cancelled. The message is signed by the DHT
+    /// layer with this node's identity, which is how the counterparty knows that it came from us.
+    async fn send_transaction_cancelled_message(
+        &mut self,
+        tx_id: TxId,
+        public_key: CommsPublicKey,
+    ) -> Result<(), TransactionServiceError>
+    {
+        let transaction_cancelled_message = proto::TransactionCancelledMessage { tx_id };
+        match self
+            .outbound_message_service
+            .send_direct(
+                public_key,
+                OutboundEncryption::EncryptForPeer,
+                OutboundDomainMessage::new(TariMessageType::TransactionCancelled, transaction_cancelled_message),
+            )
+            .await?
+        {
+            SendMessageResponse::Failed => Err(TransactionServiceError::OutboundSendFailure),
+            _ => Ok(()),
+        }
+    }
+
+    /// Send a transaction negotiation message to its recipient. The message is sent directly if the recipient can be
+    /// reached, otherwise it is sent to be stored and forwarded. If the recipient has to be discovered first, the
+    /// outcome is published with a `TransactionSendDiscoveryComplete` event once the Discovery process completes.
+    async fn send_transaction_message<M>(
+        &mut self,
+        tx_id: TxId,
+        dest_pubkey: CommsPublicKey,
+        message_type: TariMessageType,
+        message: M,
+    ) -> MessageSendStatus
+    where
+        M: prost::Message + Clone + Send + 'static,
+    {
+        let response = self
+            .outbound_message_service
+            .send_direct(
+                dest_pubkey.clone(),
+                OutboundEncryption::EncryptForPeer,
+                OutboundDomainMessage::new(message_type, message.clone()),
+            )
+            .await;
+        match response {
+            Ok(SendMessageResponse::Ok(_)) => return MessageSendStatus::Direct,
+            Ok(SendMessageResponse::PendingDiscovery(r)) => {
+                // The sending of the message resulted in a long running Discovery process being performed by the Comms
+                // layer. This can take minutes so we will spawn a task to wait for the result and fall back to store
+                // and forward if the recipient could not be discovered
+                let outbound_message_service = self.outbound_message_service.clone();
+                let discovery_future = async move {
+                    transaction_send_discovery_process_completion(
+                        r,
+                        outbound_message_service,
+                        tx_id,
+                        dest_pubkey,
+                        message_type,
+                        message,
+                    )
+                    .await
+                };
+                self.discovery_process_futures.push(discovery_future.boxed());
+                return MessageSendStatus::PendingDiscovery;
+            },
+            Ok(SendMessageResponse::Failed) => (),
+            Err(e) => error!(
+                target: LOG_TARGET,
+                "Error sending message for TX_ID = {} directly: {:?}", tx_id, e
+            ),
+        }
+
+        let status = send_store_and_forward_message(
+            &mut self.outbound_message_service,
+            tx_id,
+            dest_pubkey,
+            message_type,
+            message,
+        )
+        .await;
+        if status == MessageSendStatus::StoreAndForward {
+            let _ = self
+                .event_publisher
+                .send(TransactionEvent::TransactionStoreAndForwardSend(tx_id))
+                .await;
+        }
+        status
+    }
+
+    /// Send the reply to a pending inbound transaction again, in response to a repeated message from its sender
+    async fn resend_transaction_reply(
+        &mut self,
+        source_pubkey: CommsPublicKey,
+        tx_id: TxId,
+    ) -> Result<(), TransactionServiceError>
+    {
+        let inbound_tx = match self.db.get_pending_inbound_transaction(tx_id) {
+            Ok(tx) => tx,
+            // Once the transaction is finalized the sender has stopped waiting for the reply
+            Err(TransactionStorageError::ValueNotFound(_)) => {
+                return Err(TransactionServiceError::RepeatedMessageError)
+            },
+            Err(e) => return Err(e.into()),
+        };
+        if inbound_tx.source_public_key != source_pubkey {
+            return Err(TransactionServiceError::RepeatedMessageError);
+        }
+        if inbound_tx.cancelled {
+            return Err(TransactionServiceError::TransactionCancelled);
+        }
+
+        info!(
+            target: LOG_TARGET,
+            "Repeated Transaction with TX_ID = {} received from {}, resending reply", tx_id, source_pubkey
+        );
+        let proto_message: proto::RecipientSignedMessage =
+            inbound_tx.receiver_protocol.get_signed_data()?.clone().into();
+        self.send_transaction_message(
+            tx_id,
+            source_pubkey,
+            TariMessageType::ReceiverPartialTransactionReply,
+            proto_message,
+        )
+        .await;
+
+        Ok(())
+    }
+
+    /// Send a finalized transaction to its recipient again, in response to a repeated reply from the recipient
+    async fn resend_finalized_transaction(
+        &mut self,
+        source_pubkey: CommsPublicKey,
+        tx_id: TxId,
+    ) -> Result<(), TransactionServiceError>
+    {
+        let completed_tx = self.db.get_completed_transaction(tx_id)?;
+        if completed_tx.source_public_key != *self.node_identity.public_key() ||
+            completed_tx.destination_public_key != source_pubkey
+        {
+            return Err(TransactionServiceError::InvalidSourcePublicKey);
+        }
+
+        info!(
+            target: LOG_TARGET,
+            "Repeated Transaction Recipient Reply for TX_ID = {} received, resending finalized transaction", tx_id
+        );
+        let finalized_transaction_message = proto::TransactionFinalizedMessage {
+            tx_id,
+            transaction: Some(completed_tx.transaction.into()),
+        };
+        self.send_transaction_message(
+            tx_id,
+            source_pubkey,
+            TariMessageType::TransactionFinalized,
+            finalized_transaction_message,
+        )
+        .await;
+
+        Ok(())
+    }
+
+    /// The tx_ids of the transactions that are waiting for a message from their counterparty
+    fn pending_negotiations(&self) -> Result<HashSet<TxId>, TransactionServiceError> {
+        let mut tx_ids = HashSet::new();
+        tx_ids.extend(
+            self.db
+                .get_pending_outbound_transactions()?
+                .into_iter()
+                .filter(|(_, tx)| !tx.cancelled)
+                .map(|(tx_id, _)| tx_id),
+        );
+        tx_ids.extend(
+            self.db
+                .get_pending_inbound_transactions()?
+                .into_iter()
+                .filter(|(_, tx)| !tx.cancelled)
+                .map(|(tx_id, _)| tx_id),
+        );
+        tx_ids.extend(
+            self.db
+                .get_relayed_offline_transactions()?
+                .into_iter()
+                .filter(|(_, relayed_tx)| relayed_tx.reply.is_none())
+                .map(|(tx_id, _)| tx_id),
+        );
+        Ok(tx_ids)
+    }
+
+    /// Send the last message of this wallet in the negotiation of a pending transaction to the counterparty again. The
+    /// sender message of an outbound transaction is sent to every recipient that has not replied yet, and the reply to
+    /// an inbound transaction is sent to its sender.
+    async fn resend_transaction(&mut self, tx_id: TxId) -> Result<(), TransactionServiceError> {
+        if let Some(RelayedOfflineTransaction {
+            offline_transaction,
+            reply: None,
+        }) = self.find_relayed_offline_transaction(tx_id)?
+        {
+            let dest_pubkey = offline_transaction.destination_public_key;
+            let proto_message = proto::TransactionSenderMessage::single(offline_transaction.sender_message.into());
+            self.send_transaction_message(
+                tx_id,
+                dest_pubkey,
+                TariMessageType::SenderPartialTransaction,
+                proto_message,
+            )
+            .await;
+            return Ok(());
+        }
+
+        match self.db.get_pending_outbound_transaction(tx_id) {
+            Ok(outbound_tx) => {
+                let messages = if outbound_tx.recipients.is_empty() {
+                    vec![(
+                        outbound_tx.destination_public_key.clone(),
+                        outbound_tx.sender_protocol.get_single_round_message()?,
+                    )]
+                } else {
+                    let awaiting_reply = outbound_tx.sender_protocol.get_recipients_awaiting_reply()?;
+                    outbound_tx
+                        .recipients
+                        .iter()
+                        .zip(outbound_tx.sender_protocol.get_multi_recipient_messages()?.into_iter())
+                        .filter(|(recipient, _)| awaiting_reply.contains(&recipient.tx_id))
+                        .map(|(recipient, msg)| (recipient.public_key.clone(), msg))
+                        .collect()
+                };
+                for (dest_pubkey, msg) in messages {
+                    let proto_message = proto::TransactionSenderMessage::single(msg.into());
+                    self.send_transaction_message(
+                        tx_id,
+                        dest_pubkey,
+                        TariMessageType::SenderPartialTransaction,
+                        proto_message,
+                    )
+                    .await;
+                }
+                return Ok(());
+            },
+            Err(TransactionStorageError::ValueNotFound(_)) => (),
+            Err(e) => return Err(e.into()),
+        }
+
+        let inbound_tx = self.db.get_pending_inbound_transaction(tx_id)?;
+        let proto_message: proto::RecipientSignedMessage =
+            inbound_tx.receiver_protocol.get_signed_data()?.clone().into();
+        self.send_transaction_message(
             tx_id,
-            source_public_key: self.node_identity.public_key().clone(),
-            destination_public_key: self.node_identity.public_key().clone(),
-            amount: coinbase_tx.amount,
-            fee: MicroTari::from(0),
-            transaction: completed_transaction,
-            status: TransactionStatus::Completed,
-            message: "Coinbase Transaction".to_string(),
-            timestamp: Utc::now().naive_utc(),
-        })?;
+            inbound_tx.source_public_key,
+            TariMessageType::ReceiverPartialTransactionReply,
+            proto_message,
+        )
+        .await;
 
         Ok(())
     }
 
-    /// If a specific coinbase transaction will not be mined then the Miner can cancel it
-    pub async fn cancel_pending_coinbase_transaction(&mut self, tx_id: TxId) -> Result<(), TransactionServiceError> {
-        let _ = self.db.get_pending_coinbase_transaction(tx_id.clone()).map_err(|e| {
-            error!(
-                target: LOG_TARGET,
-                "Finalized coinbase transaction TxId does not exist in Pending Inbound Transactions"
-            );
-            e
-        })?;
+    /// Send the messages of all the pending transactions to their counterparties again and restart their resend
+    /// backoff. This is done when the service starts, and can be requested when the wallet has reconnected to the
+    /// network.
+    pub async fn resend_pending_transactions(&mut self) -> Result<usize, TransactionServiceError> {
+        let tx_ids = self.pending_negotiations()?;
+        let resend_interval = self.config.resend_interval;
+        let next_resend = Instant::now() + resend_interval;
+        self.resend_schedule = tx_ids
+            .iter()
+            .map(|tx_id| (*tx_id, (next_resend, resend_interval)))
+            .collect();
 
-        self.output_manager_service.cancel_transaction(tx_id).await?;
+        for tx_id in tx_ids.iter() {
+            if let Err(e) = self.resend_transaction(*tx_id).await {
+                error!(
+                    target: LOG_TARGET,
+                    "Error resending messages for TX_ID = {}: {:?}", tx_id, e
+                );
+            }
+        }
+        info!(
+            target: LOG_TARGET,
+            "Messages of {} pending transactions resent",
+            tx_ids.len()
+        );
 
-        self.db.cancel_coinbase_transaction(tx_id)?;
+        Ok(tx_ids.len())
+    }
+
+    /// Send the messages of the pending transactions whose resend time has passed to their counterparties again. The
+    /// time until the next resend of a transaction doubles with every resend, up to the configured maximum.
+    async fn resend_due_transactions(&mut self) -> Result<(), TransactionServiceError> {
+        let tx_ids = self.pending_negotiations()?;
+        self.resend_schedule.retain(|tx_id, _| tx_ids.contains(tx_id));
+
+        let now = Instant::now();
+        let resend_interval = self.config.resend_interval;
+        let max_resend_interval = self.config.max_resend_interval;
+        let mut due_tx_ids = Vec::new();
+        for tx_id in tx_ids {
+            let (next_resend, backoff) = self
+                .resend_schedule
+                .entry(tx_id)
+                .or_insert((now + resend_interval, resend_interval));
+            if *next_resend <= now {
+                *backoff = (*backoff * 2).min(max_resend_interval);
+                *next_resend = now + *backoff;
+                due_tx_ids.push(tx_id);
+            }
+        }
+
+        for tx_id in due_tx_ids {
+            debug!(target: LOG_TARGET, "Resending messages for TX_ID = {}", tx_id);
+            if let Err(e) = self.resend_transaction(tx_id).await {
+                error!(
+                    target: LOG_TARGET,
+                    "Error resending messages for TX_ID = {}: {:?}", tx_id, e
+                );
+            }
+        }
 
         Ok(())
     }
@@ -765,6 +1999,436 @@ where
         Ok(self.db.get_completed_transactions()?)
     }
 
+    /// Get a page of the completed transactions, described from the point of view of this wallet
+    pub fn get_transaction_history(
+        &self,
+        query: TransactionHistoryQuery,
+    ) -> Result<Vec<TransactionHistoryEntry>, TransactionServiceError>
+    {
+        let own_public_key = self.node_identity.public_key();
+        Ok(self
+            .db
+            .get_completed_transaction_history(query)?
+            .into_iter()
+            .map(|tx| TransactionHistoryEntry::new(tx, own_public_key))
+            .collect())
+    }
+
+    /// Collect the pending, cancelled and completed transactions in the database for inclusion in a wallet backup
+    pub fn get_backup(&self) -> Result<TransactionServiceBackup, TransactionServiceError> {
+        let mut pending_inbound_transactions: Vec<InboundTransaction> = self
+            .db
+            .get_pending_inbound_transactions()?
+            .into_iter()
+            .map(|(_, t)| t)
+            .collect();
+        pending_inbound_transactions.extend(
+            self.db
+                .get_cancelled_pending_inbound_transactions()?
+                .into_iter()
+                .map(|(_, t)| t),
+        );
+        let mut pending_outbound_transactions: Vec<OutboundTransaction> = self
+            .db
+            .get_pending_outbound_transactions()?
+            .into_iter()
+            .map(|(_, t)| t)
+            .collect();
+        pending_outbound_transactions.extend(
+            self.db
+                .get_cancelled_pending_outbound_transactions()?
+                .into_iter()
+                .map(|(_, t)| t),
+        );
+
+        Ok(TransactionServiceBackup {
+            pending_inbound_transactions,
+            pending_outbound_transactions,
+            completed_transactions: self
+                .db
+                .get_completed_transactions()?
+                .into_iter()
+                .map(|(_, t)| t)
+                .collect(),
+        })
+    }
+
+    /// Add the transactions from a wallet backup to the database. Cancelled transactions keep their cancelled state.
+    pub fn restore_backup(&mut self, backup: TransactionServiceBackup) -> Result<(), TransactionServiceError> {
+        for tx in backup.pending_inbound_transactions {
+            self.db.add_pending_inbound_transaction(tx.tx_id, tx)?;
+        }
+        for tx in backup.pending_outbound_transactions {
+            self.db.add_pending_outbound_transaction(tx.tx_id, tx)?;
+        }
+        for tx in backup.completed_transactions {
+            self.db.add_completed_transaction(tx.tx_id, tx)?;
+        }
+
+        Ok(())
+    }
+
+    /// Set the base node that completed transactions will be submitted to and monitored with, and start a monitoring
+    /// round for the transactions that have not been confirmed yet.
+    pub async fn set_base_node_public_key(
+        &mut self,
+        public_key: CommsPublicKey,
+    ) -> Result<(), TransactionServiceError>
+    {
+        if self.base_node_public_key.as_ref() != Some(&public_key) {
+            info!(target: LOG_TARGET, "Setting base node public key to {}", public_key);
+            self.base_node_public_key = Some(public_key);
+            self.chain_height = None;
+        }
+        self.monitor_transactions().await
+    }
+
+    /// Submit the completed transactions that have not been broadcast yet to the base node and query the base node
+    /// for the chain tip and the status of all the transactions whose outputs have not been confirmed yet. Queries
+    /// that are still outstanding from the previous round are abandoned.
+    async fn monitor_transactions(&mut self) -> Result<(), TransactionServiceError> {
+        let base_node_public_key = match self.base_node_public_key.clone() {
+            Some(pk) => pk,
+            None => return Ok(()),
+        };
+        self.pending_mempool_queries.clear();
+        self.pending_kernel_queries.clear();
+
+        let pending_tx_outputs = self.output_manager_service.get_pending_transactions().await?;
+        let mut monitored_tx_ids = Vec::new();
+        let mut kernel_hashes = Vec::new();
+        for (tx_id, completed_tx) in self.db.get_completed_transactions()? {
+            if completed_tx.transaction.body.kernels().is_empty() {
+                continue;
+            }
+            match completed_tx.status {
+                // Coinbase transactions are included in a block by the miner and never pass through the mempool
+                TransactionStatus::Completed if is_coinbase(&completed_tx) => (),
+                TransactionStatus::Completed => self.submit_transaction(completed_tx.clone()).await?,
+                TransactionStatus::Broadcast => self.query_mempool(&completed_tx).await?,
+                TransactionStatus::Mined => {
+                    if !pending_tx_outputs.contains_key(&tx_id) {
+                        continue;
+                    }
+                },
+            }
+            monitored_tx_ids.push(tx_id);
+            kernel_hashes.extend(completed_tx.transaction.body.kernels().iter().map(|k| k.hash()));
+        }
+
+        let mut rng = rand::OsRng::new().unwrap();
+        let request_key = rng.next_u64();
+        self.chain_metadata_request_key = Some(request_key);
+        self.send_base_node_request(base_node_public_key.clone(), BaseNodeServiceRequest {
+            request_key,
+            request: Some(BaseNodeRequestProto::GetChainMetadata(true)),
+        })
+        .await?;
+
+        if monitored_tx_ids.is_empty() {
+            return Ok(());
+        }
+        let request_key = rng.next_u64();
+        self.pending_kernel_queries.insert(request_key, monitored_tx_ids);
+        self.send_base_node_request(base_node_public_key, BaseNodeServiceRequest {
+            request_key,
+            request: Some(BaseNodeRequestProto::FetchKernelHeights(HashOutputs {
+                outputs: kernel_hashes,
+            })),
+        })
+        .await
+    }
+
+    async fn send_base_node_request(
+        &mut self,
+        base_node_public_key: CommsPublicKey,
+        request: BaseNodeServiceRequest,
+    ) -> Result<(), TransactionServiceError>
+    {
+        self.outbound_message_service
+            .send_direct(
+                base_node_public_key,
+                OutboundEncryption::EncryptForPeer,
+                OutboundDomainMessage::new(TariMessageType::BaseNodeRequest, request),
+            )
+            .await?;
+        Ok(())
+    }
+
+    /// Submit a completed transaction to the mempool of the base node and mark it as `Broadcast`
+    async fn submit_transaction(&mut self, completed_tx: CompletedTransaction) -> Result<(), TransactionServiceError> {
+        let base_node_public_key = self
+            .base_node_public_key
+            .clone()
+            .ok_or(TransactionServiceError::NoBaseNodePublicKey)?;
+        let tx_id = completed_tx.tx_id;
+        if let SendMessageResponse::Failed = self
+            .outbound_message_service
+            .send_direct(
+                base_node_public_key,
+                OutboundEncryption::EncryptForPeer,
+                OutboundDomainMessage::new(
+                    TariMessageType::NewTransaction,
+                    ProtoTransaction::from(completed_tx.transaction),
+                ),
+            )
+            .await?
+        {
+            return Err(TransactionServiceError::OutboundSendFailure);
+        }
+
+        if completed_tx.status != TransactionStatus::Broadcast {
+            self.db.broadcast_completed_transaction(tx_id)?;
+            self.event_publisher
+                .send(TransactionEvent::TransactionBroadcast(tx_id))
+                .await
+                .map_err(|_| TransactionServiceError::EventStreamError)?;
+        }
+        info!(
+            target: LOG_TARGET,
+            "Transaction with TX_ID = {} submitted to the base node", tx_id
+        );
+
+        Ok(())
+    }
+
+    /// Submit a newly completed transaction to the base node if one has been set. A failure is only logged as the
+    /// transaction will be submitted again during the next monitoring round.
+    async fn submit_completed_transaction_if_monitoring(&mut self, completed_tx: CompletedTransaction) {
+        if self.base_node_public_key.is_none() || is_coinbase(&completed_tx) {
+            return;
+        }
+        let tx_id = completed_tx.tx_id;
+        if let Err(e) = self.submit_transaction(completed_tx).await {
+            error!(
+                target: LOG_TARGET,
+                "Failed to submit Transaction with TX_ID = {} to the base node: {:?}", tx_id, e
+            );
+        }
+    }
+
+    /// Ask the base node mempool whether it still contains a broadcast transaction
+    async fn query_mempool(&mut self, completed_tx: &CompletedTransaction) -> Result<(), TransactionServiceError> {
+        let base_node_public_key = self
+            .base_node_public_key
+            .clone()
+            .ok_or(TransactionServiceError::NoBaseNodePublicKey)?;
+        let excess_sig = match completed_tx.transaction.body.kernels().first() {
+            Some(kernel) => kernel.excess_sig.clone(),
+            None => return Ok(()),
+        };
+        let mut rng = rand::OsRng::new().unwrap();
+        let request_key = rng.next_u64();
+        self.pending_mempool_queries.insert(request_key, completed_tx.tx_id);
+        self.outbound_message_service
+            .send_direct(
+                base_node_public_key,
+                OutboundEncryption::EncryptForPeer,
+                OutboundDomainMessage::new(TariMessageType::MempoolRequest, MempoolServiceRequest {
+                    request_key,
+                    request: Some(MempoolRequestProto::GetTxStateWithExcessSig(excess_sig.into())),
+                }),
+            )
+            .await?;
+        Ok(())
+    }
+
+    /// Handle the response to a mempool query. Transactions that are no longer in the mempool and have not been mined
+    /// are rebroadcast until the configured number of attempts is exhausted, after which they are flagged.
+    async fn handle_mempool_response(
+        &mut self,
+        response: MempoolServiceResponse,
+    ) -> Result<(), TransactionServiceError>
+    {
+        let MempoolServiceResponse { request_key, response } = response;
+        let tx_id = match self.pending_mempool_queries.remove(&request_key) {
+            Some(tx_id) => tx_id,
+            None => return Ok(()),
+        };
+        let tx_storage = match response {
+            Some(MempoolResponseProto::TxStorage(s)) => {
+                TxStorageResponse::from_i32(s).ok_or(TransactionServiceError::UnexpectedBaseNodeResponse)?
+            },
+            _ => return Err(TransactionServiceError::UnexpectedBaseNodeResponse),
+        };
+
+        let completed_tx = self.db.get_completed_transaction(tx_id)?;
+        if completed_tx.status != TransactionStatus::Broadcast {
+            return Ok(());
+        }
+        if tx_storage != TxStorageResponse::NotStored {
+            self.rebroadcast_attempts.remove(&tx_id);
+            return Ok(());
+        }
+
+        let attempts = self.rebroadcast_attempts.entry(tx_id).or_insert(0);
+        *attempts += 1;
+        let attempts = *attempts;
+        if attempts <= self.config.max_rebroadcast_attempts {
+            info!(
+                target: LOG_TARGET,
+                "Transaction with TX_ID = {} not found in the base node mempool, rebroadcasting (attempt {} of {})",
+                tx_id,
+                attempts,
+                self.config.max_rebroadcast_attempts
+            );
+            self.submit_transaction(completed_tx).await?;
+        } else if attempts == self.config.max_rebroadcast_attempts + 1 {
+            warn!(
+                target: LOG_TARGET,
+                "Transaction with TX_ID = {} has dropped out of the base node mempool", tx_id
+            );
+            self.event_publisher
+                .send(TransactionEvent::TransactionDroppedFromMempool(tx_id))
+                .await
+                .map_err(|_| TransactionServiceError::EventStreamError)?;
+        }
+
+        Ok(())
+    }
+
+    /// Handle the responses to the chain metadata and kernel queries sent to the base node
+    async fn handle_base_node_response(
+        &mut self,
+        response: BaseNodeServiceResponse,
+    ) -> Result<(), TransactionServiceError>
+    {
+        let BaseNodeServiceResponse { request_key, response } = response;
+        match response {
+            Some(BaseNodeResponseProto::ChainMetadata(metadata)) => {
+                if self.chain_metadata_request_key == Some(request_key) {
+                    self.chain_metadata_request_key = None;
+                    self.chain_height = metadata.height_of_longest_chain;
+                    // The Output Manager Service needs the chain height to know which outputs have matured
+                    if let Some(height) = self.chain_height {
+                        self.output_manager_service.set_chain_height(height).await?;
+                    }
+                }
+            },
+            Some(BaseNodeResponseProto::KernelHeights(kernel_heights)) => {
+                if let Some(tx_ids) = self.pending_kernel_queries.remove(&request_key) {
+                    let kernel_heights = kernel_heights
+                        .kernel_heights
+                        .into_iter()
+                        .map(|kh| (kh.hash, kh.height))
+                        .collect();
+                    self.update_mined_transactions(tx_ids, kernel_heights).await?;
+                }
+            },
+            // Responses to queries made by other wallet services are ignored
+            _ => (),
+        }
+        Ok(())
+    }
+
+    /// Update the status of the monitored transactions using the heights of the blocks that contain their kernels.
+    /// Newly found transactions are marked as mined at the height of the block that contains them, and the outputs of
+    /// mined transactions are confirmed in the Output Manager Service once they have the required number of
+    /// confirmations. A mined transaction whose kernels have disappeared from the chain has been reorged out and is
+    /// submitted to the mempool again.
+    async fn update_mined_transactions(
+        &mut self,
+        tx_ids: Vec<TxId>,
+        kernel_heights: HashMap<Vec<u8>, u64>,
+    ) -> Result<(), TransactionServiceError>
+    {
+        for tx_id in tx_ids {
+            let completed_tx = self.db.get_completed_transaction(tx_id)?;
+            let mined_height = completed_tx
+                .transaction
+                .body
+                .kernels()
+                .iter()
+                .map(|k| kernel_heights.get(&k.hash()).cloned())
+                .collect::<Option<Vec<u64>>>()
+                .and_then(|heights| heights.into_iter().max());
+
+            let mined_height = match mined_height {
+                Some(h) => h,
+                None => {
+                    if completed_tx.status == TransactionStatus::Mined {
+                        warn!(
+                            target: LOG_TARGET,
+                            "Mined Transaction with TX_ID = {} is no longer in the blockchain, resubmitting it", tx_id
+                        );
+                        self.submit_transaction(completed_tx).await?;
+                    }
+                    continue;
+                },
+            };
+
+            if completed_tx.status != TransactionStatus::Mined || completed_tx.mined_height != Some(mined_height) {
+                // A transaction that was reorged into a different block is recorded at its new height
+                self.db.mine_completed_transaction(tx_id, Some(mined_height))?;
+            }
+            if completed_tx.status != TransactionStatus::Mined {
+                self.rebroadcast_attempts.remove(&tx_id);
+                info!(
+                    target: LOG_TARGET,
+                    "Transaction with TX_ID = {} has been mined at height {}", tx_id, mined_height
+                );
+                self.event_publisher
+                    .send(TransactionEvent::TransactionMined(tx_id))
+                    .await
+                    .map_err(|_| TransactionServiceError::EventStreamError)?;
+            }
+
+            if let Some(tip) = self.chain_height {
+                let confirmations = tip.max(mined_height) - mined_height + 1;
+                self.event_publisher
+                    .send(TransactionEvent::TransactionConfirmations(tx_id, confirmations))
+                    .await
+                    .map_err(|_| TransactionServiceError::EventStreamError)?;
+                if confirmations >= self.config.num_confirmations_required {
+                    self.confirm_transaction_outputs(&completed_tx).await?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Confirm the outputs of a mined transaction in the Output Manager Service
+    async fn confirm_transaction_outputs(
+        &mut self,
+        completed_tx: &CompletedTransaction,
+    ) -> Result<(), TransactionServiceError>
+    {
+        let tx_id = completed_tx.tx_id;
+        let pending_tx_outputs = self.output_manager_service.get_pending_transactions().await?;
+        let pending_tx = match pending_tx_outputs.get(&tx_id) {
+            Some(p) => p,
+            None => return Ok(()),
+        };
+        let body = &completed_tx.transaction.body;
+
+        if pending_tx.outputs_to_be_spent.is_empty() {
+            // This is a received or coinbase transaction so only our single output needs to be confirmed
+            let output = pending_tx
+                .outputs_to_be_received
+                .first()
+                .map(|o| {
+                    o.as_transaction_input(&self.factories.commitment, OutputFeatures::default())
+                        .commitment
+                })
+                .and_then(|commitment| body.outputs().iter().find(|o| o.commitment == commitment))
+                .ok_or(TransactionServiceError::ReceiverOutputNotFound)?;
+            self.output_manager_service
+                .confirm_received_output(tx_id, output.clone())
+                .await?;
+        } else {
+            self.output_manager_service
+                .confirm_sent_transaction(tx_id, body.inputs().clone(), body.outputs().clone())
+                .await?;
+        }
+        info!(
+            target: LOG_TARGET,
+            "Outputs of Transaction with TX_ID = {} confirmed", tx_id
+        );
+
+        Ok(())
+    }
+
     /// This function is only available for testing by the client of LibWallet. It simulates a receiver accepting and
     /// replying to a Pending Outbound Transaction. This results in that transaction being "completed" and it's status
     /// set to `Broadcast` which indicated it is in a base_layer mempool.
@@ -841,7 +2505,7 @@ where
             .confirm_sent_transaction(tx_id.clone(), outputs_to_be_spent, outputs_to_be_received)
             .await?;
 
-        self.db.mine_completed_transaction(tx_id)?;
+        self.db.mine_completed_transaction(tx_id, self.chain_height)?;
 
         self.event_publisher
             .send(TransactionEvent::TransactionMined(tx_id))
@@ -862,16 +2526,24 @@ where
     ) -> Result<(), TransactionServiceError>
     {
         use crate::output_manager_service::{
-            service::OutputManagerService,
+            service::{OutputManagerService, UTXOSelectionStrategy},
             storage::{database::OutputManagerDatabase, memory_db::OutputManagerMemoryDatabase},
         };
+        use futures::{channel::mpsc, stream};
+        use tari_broadcast_channel::bounded;
 
         let (_sender, receiver) = reply_channel::unbounded();
+        let (outbound_sender, _outbound_receiver) = mpsc::channel(1);
+        let (event_publisher, _event_subscriber) = bounded(1);
         let mut rng = rand::OsRng::new().unwrap();
 
         let mut fake_oms = OutputManagerService::new(
+            Default::default(),
+            OutboundMessageRequester::new(outbound_sender),
             receiver,
+            stream::empty::<DomainMessage<BaseNodeServiceResponse>>(),
             OutputManagerDatabase::new(OutputManagerMemoryDatabase::new()),
+            event_publisher,
             self.factories.clone(),
         )?;
 
@@ -880,7 +2552,13 @@ where
 
         fake_oms.add_output(uo)?;
 
-        let mut stp = fake_oms.prepare_transaction_to_send(amount, MicroTari::from(100), None, "".to_string())?;
+        let mut stp = fake_oms.prepare_transaction_to_send(
+            amount,
+            MicroTari::from(100),
+            None,
+            "".to_string(),
+            UTXOSelectionStrategy::default(),
+        )?;
 
         let msg = stp.build_single_round_message()?;
         let proto_msg = proto::TransactionSenderMessage::single(msg.into());
@@ -908,6 +2586,7 @@ where
             receiver_protocol: rtp,
             message: "".to_string(),
             timestamp: Utc::now().naive_utc(),
+            cancelled: false,
         };
 
         self.db
@@ -943,6 +2622,7 @@ where
             status: TransactionStatus::Completed,
             message: found_tx.message.clone(),
             timestamp: found_tx.timestamp.clone(),
+            mined_height: None,
         };
 
         self.db
@@ -955,64 +2635,102 @@ where
     }
 }
 
-async fn transaction_send_discovery_process_completion<TBackend: TransactionBackend + Clone + 'static>(
+/// Coinbase transactions are identified by their kernel features
+fn is_coinbase(completed_tx: &CompletedTransaction) -> bool {
+    completed_tx
+        .transaction
+        .body
+        .kernels()
+        .iter()
+        .any(|k| k.features.contains(KernelFeatures::COINBASE_KERNEL))
+}
+
+/// Wait for the Discovery process that was started to send a transaction negotiation message. If the recipient could
+/// not be discovered, the message is sent to be stored and forwarded instead.
+async fn transaction_send_discovery_process_completion<M: prost::Message>(
     response_channel: oneshot::Receiver<SendMessageResponse>,
-    mut db: TransactionDatabase<TBackend>,
+    mut outbound_message_service: OutboundMessageRequester,
     tx_id: TxId,
-    outbound_tx: OutboundTransaction,
-) -> Result<TxId, TransactionServiceError>
+    dest_pubkey: CommsPublicKey,
+    message_type: TariMessageType,
+    message: M,
+) -> (TxId, MessageSendStatus)
 {
-    let mut success = false;
     match response_channel.await {
-        Ok(response) => match response {
-            SendMessageResponse::Ok(n) => {
-                if n == 0 {
-                    error!(
-                        target: LOG_TARGET,
-                        "Send Discovery process for TX_ID: {} was unsuccessful and no message was sent", tx_id
-                    );
-                } else {
-                    info!(
-                        target: LOG_TARGET,
-                        "Transaction (TxId: {}) Send Discovery process successful? {}", tx_id, n
-                    );
-                    success = true;
-                }
-            },
-            _ => {
-                error!(
-                    target: LOG_TARGET,
-                    "Transaction (TxId: {}) Send Discovery process failed", tx_id
-                );
-            },
+        Ok(SendMessageResponse::Ok(n)) if n > 0 => {
+            info!(
+                target: LOG_TARGET,
+                "Transaction (TxId: {}) message sent to {} after Discovery process completed", tx_id, dest_pubkey
+            );
+            (tx_id, MessageSendStatus::Direct)
         },
-        Err(_) => {
-            error!(
+        _ => {
+            warn!(
                 target: LOG_TARGET,
-                "Transaction (TxId: {}) Send Response One-shot channel dropped", tx_id
+                "Transaction (TxId: {}) Send Discovery process failed, sending message via store and forward", tx_id
             );
+            let status = send_store_and_forward_message(
+                &mut outbound_message_service,
+                tx_id,
+                dest_pubkey,
+                message_type,
+                message,
+            )
+            .await;
+            (tx_id, status)
         },
     }
+}
 
-    if success {
-        let updated_outbound_tx = OutboundTransaction {
-            timestamp: Utc::now().naive_utc(),
-            ..outbound_tx
-        };
-        if let Err(_) = db.add_pending_outbound_transaction(tx_id, updated_outbound_tx.clone()) {
-            success = false;
-        }
-        info!(
-            target: LOG_TARGET,
-            "Transaction with TX_ID = {} sent to {} after Discovery process completed",
-            tx_id,
-            updated_outbound_tx.destination_public_key.clone()
-        );
-    }
-
-    if !success {
-        return Err(TransactionServiceError::DiscoveryProcessFailed(tx_id));
+/// Send a transaction negotiation message to the network, addressed to the recipient's node id, so that the nodes
+/// close to the recipient store it and forward it once the recipient comes online. The message is encrypted for the
+/// recipient so that only the recipient can read it.
+async fn send_store_and_forward_message<M: prost::Message>(
+    outbound_message_service: &mut OutboundMessageRequester,
+    tx_id: TxId,
+    dest_pubkey: CommsPublicKey,
+    message_type: TariMessageType,
+    message: M,
+) -> MessageSendStatus
+{
+    let node_id = match NodeId::from_key(&dest_pubkey) {
+        Ok(node_id) => node_id,
+        Err(e) => {
+            error!(
+                target: LOG_TARGET,
+                "Invalid recipient for store and forward message of TX_ID = {}: {:?}", tx_id, e
+            );
+            return MessageSendStatus::Failed;
+        },
+    };
+    match outbound_message_service
+        .propagate(
+            NodeDestination::NodeId(node_id),
+            OutboundEncryption::EncryptFor(dest_pubkey.clone()),
+            Vec::new(),
+            OutboundDomainMessage::new(message_type, message),
+        )
+        .await
+    {
+        Ok(SendMessageResponse::Ok(n)) if n > 0 => {
+            info!(
+                target: LOG_TARGET,
+                "Transaction (TxId: {}) message for {} sent to {} peers to be stored and forwarded",
+                tx_id,
+                dest_pubkey,
+                n
+            );
+            MessageSendStatus::StoreAndForward
+        },
+        response => {
+            error!(
+                target: LOG_TARGET,
+                "Transaction (TxId: {}) message for {} could not be sent to be stored and forwarded: {:?}",
+                tx_id,
+                dest_pubkey,
+                response
+            );
+            MessageSendStatus::Failed
+        },
     }
-
-    Ok(tx_id)
 }
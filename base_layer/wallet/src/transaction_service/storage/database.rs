@@ -20,7 +20,11 @@
 // WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
 // USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
-use crate::{output_manager_service::TxId, transaction_service::error::TransactionStorageError};
+use crate::{
+    encryption::SecretCipher,
+    output_manager_service::TxId,
+    transaction_service::error::TransactionStorageError,
+};
 use chrono::NaiveDateTime;
 use log::*;
 use serde::{Deserialize, Serialize};
@@ -32,6 +36,7 @@ use tari_comms::types::CommsPublicKey;
 use tari_core::transactions::{
     tari_amount::MicroTari,
     transaction::Transaction,
+    transaction_protocol::{recipient::RecipientSignedMessage, sender::SingleRoundSenderData},
     types::Commitment,
     ReceiverTransactionProtocol,
     SenderTransactionProtocol,
@@ -57,6 +62,13 @@ pub trait TransactionBackend: Send + Sync {
         tx_id: TxId,
         completed_transaction: CompletedTransaction,
     ) -> Result<(), TransactionStorageError>;
+    /// Update a pending outbound transaction, this operation must replace the `OutboundTransaction` with the provided
+    /// `TxId` with the provided one in a single write, so that the transaction is never missing from the backend.
+    fn update_pending_outbound_transaction(
+        &mut self,
+        tx_id: TxId,
+        outbound_transaction: OutboundTransaction,
+    ) -> Result<(), TransactionStorageError>;
     /// Complete inbound transaction, this operation must delete the `InboundTransaction` with the provided
     /// `TxId` and insert the provided `CompletedTransaction` into `CompletedTransactions`.
     fn complete_inbound_transaction(
@@ -71,12 +83,19 @@ pub trait TransactionBackend: Send + Sync {
         tx_id: TxId,
         completed_transaction: CompletedTransaction,
     ) -> Result<(), TransactionStorageError>;
+    /// Cancel a pending inbound or outbound transaction, this operation must mark the `InboundTransaction` or
+    /// `OutboundTransaction` with the provided `TxId` as cancelled. Cancelled transactions are only returned by the
+    /// `CancelledPending...Transactions` keys.
+    fn cancel_pending_transaction(&mut self, tx_id: TxId) -> Result<(), TransactionStorageError>;
     /// Indicated that a completed transaction has been broadcast to the mempools
-    #[cfg(feature = "test_harness")]
     fn broadcast_completed_transaction(&mut self, tx_id: TxId) -> Result<(), TransactionStorageError>;
-    /// Indicated that a completed transaction has been detected as mined on the base layer
-    #[cfg(feature = "test_harness")]
-    fn mine_completed_transaction(&mut self, tx_id: TxId) -> Result<(), TransactionStorageError>;
+    /// Indicated that a completed transaction has been detected as mined on the base layer, at the given block height
+    /// if it is known
+    fn mine_completed_transaction(
+        &mut self,
+        tx_id: TxId,
+        mined_height: Option<u64>,
+    ) -> Result<(), TransactionStorageError>;
     /// Update a completed transactions timestamp for use in test data generation
     #[cfg(feature = "test_harness")]
     fn update_completed_transaction_timestamp(
@@ -84,6 +103,16 @@ pub trait TransactionBackend: Send + Sync {
         tx_id: TxId,
         timestamp: NaiveDateTime,
     ) -> Result<(), TransactionStorageError>;
+    /// This method must re-encrypt the stored sender and receiver protocols of pending transactions, which hold their
+    /// secret nonces and blinding factors, with the provided cipher or, if no cipher is provided, decrypt them and
+    /// store them in plain form. The backend must not be locked.
+    fn apply_encryption(&mut self, cipher: Option<SecretCipher>) -> Result<(), TransactionStorageError>;
+    /// This method must make the stored protocols of an encrypted backend accessible using the provided cipher. An
+    /// incorrect cipher must be rejected.
+    fn unlock(&mut self, cipher: SecretCipher) -> Result<(), TransactionStorageError>;
+    /// This method must discard the cipher of an encrypted backend so that its stored protocols are no longer
+    /// accessible until it is unlocked again.
+    fn lock(&mut self) -> Result<(), TransactionStorageError>;
 }
 
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
@@ -105,8 +134,13 @@ pub struct InboundTransaction {
     pub receiver_protocol: ReceiverTransactionProtocol,
     pub message: String,
     pub timestamp: NaiveDateTime,
+    pub cancelled: bool,
 }
 
+/// An outbound transaction. A transaction that pays more than one party lists each of them, with the tx_id of its part
+/// of the transaction, in `recipients`; the transaction takes the tx_id of the first recipient, which is also its
+/// `destination_public_key`, and its `amount` is the total paid to all of them. `recipients` is empty for a transaction
+/// with a single recipient.
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct OutboundTransaction {
     pub tx_id: TxId,
@@ -116,6 +150,16 @@ pub struct OutboundTransaction {
     pub sender_protocol: SenderTransactionProtocol,
     pub message: String,
     pub timestamp: NaiveDateTime,
+    pub recipients: Vec<TransactionRecipient>,
+    pub cancelled: bool,
+}
+
+/// One of the recipients of a multi-recipient outbound transaction
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct TransactionRecipient {
+    pub tx_id: TxId,
+    pub public_key: CommsPublicKey,
+    pub amount: MicroTari,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
@@ -137,11 +181,66 @@ pub struct CompletedTransaction {
     pub status: TransactionStatus,
     pub message: String,
     pub timestamp: NaiveDateTime,
+    /// The height of the block containing the kernels of the transaction, if it has been mined
+    #[serde(default)]
+    pub mined_height: Option<u64>,
+}
+
+/// A page of the completed transactions, in the order they were completed in. Only the transactions completed at or
+/// after `from` and before `to` are included, when these are given.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TransactionHistoryQuery {
+    pub from: Option<NaiveDateTime>,
+    pub to: Option<NaiveDateTime>,
+    /// The number of matching transactions to skip
+    pub offset: usize,
+    /// The maximum number of transactions to return, all of them if none is given
+    pub limit: Option<usize>,
+}
+
+impl TransactionHistoryQuery {
+    /// Check if a transaction completed at the given time is in the time range of the query
+    pub fn contains(&self, timestamp: &NaiveDateTime) -> bool {
+        self.from.map_or(true, |from| *timestamp >= from) && self.to.map_or(true, |to| *timestamp < to)
+    }
+}
+
+/// The transaction history of the Transaction Service, including cancelled pending transactions, as included in a
+/// wallet backup
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct TransactionServiceBackup {
+    pub pending_inbound_transactions: Vec<InboundTransaction>,
+    pub pending_outbound_transactions: Vec<OutboundTransaction>,
+    pub completed_transactions: Vec<CompletedTransaction>,
+}
+
+/// A transaction created by an offline wallet that is relayed to its recipient by an online wallet. Only the public
+/// sender message is included, the sender protocol with its secrets stays with the offline wallet as a pending outbound
+/// transaction until the recipient's reply is brought back to be signed.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OfflineTransaction {
+    pub tx_id: TxId,
+    pub destination_public_key: CommsPublicKey,
+    pub amount: MicroTari,
+    pub fee: MicroTari,
+    pub message: String,
+    pub sender_message: SingleRoundSenderData,
+}
+
+/// A transaction created by an offline wallet that this wallet has sent to its recipient on behalf of the offline
+/// wallet, along with the recipient's reply once it has been received. It is kept until the offline wallet's finalized
+/// transaction is submitted.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RelayedOfflineTransaction {
+    pub offline_transaction: OfflineTransaction,
+    pub reply: Option<RecipientSignedMessage>,
 }
 
 #[derive(Debug, Clone, PartialEq)]
 pub enum DbKey {
     PendingOutboundTransaction(TxId),
+    /// The pending outbound transaction with a recipient that knows its part of the transaction by this tx_id
+    PendingOutboundTransactionForRecipient(TxId),
     PendingInboundTransaction(TxId),
     CompletedTransaction(TxId),
     PendingCoinbaseTransaction(TxId),
@@ -149,6 +248,11 @@ pub enum DbKey {
     PendingInboundTransactions,
     PendingCoinbaseTransactions,
     CompletedTransactions,
+    CancelledPendingOutboundTransactions,
+    CancelledPendingInboundTransactions,
+    CompletedTransactionHistory(TransactionHistoryQuery),
+    RelayedOfflineTransaction(TxId),
+    RelayedOfflineTransactions,
 }
 
 #[derive(Debug)]
@@ -161,6 +265,9 @@ pub enum DbValue {
     PendingInboundTransactions(HashMap<TxId, InboundTransaction>),
     PendingCoinbaseTransactions(HashMap<TxId, PendingCoinbaseTransaction>),
     CompletedTransactions(HashMap<TxId, CompletedTransaction>),
+    CompletedTransactionHistory(Vec<CompletedTransaction>),
+    RelayedOfflineTransaction(Box<RelayedOfflineTransaction>),
+    RelayedOfflineTransactions(HashMap<TxId, RelayedOfflineTransaction>),
 }
 
 pub enum DbKeyValuePair {
@@ -168,6 +275,8 @@ pub enum DbKeyValuePair {
     PendingInboundTransaction(TxId, Box<InboundTransaction>),
     PendingCoinbaseTransaction(TxId, Box<PendingCoinbaseTransaction>),
     CompletedTransaction(TxId, Box<CompletedTransaction>),
+    /// Inserting a relayed offline transaction replaces the one with the same `TxId`, so that its reply can be added
+    RelayedOfflineTransaction(TxId, Box<RelayedOfflineTransaction>),
 }
 
 pub enum WriteOperation {
@@ -232,6 +341,44 @@ where T: TransactionBackend
         Ok(())
     }
 
+    /// Replace a pending outbound transaction with an updated version of it, such as one holding a reply from another
+    /// recipient
+    pub fn update_pending_outbound_transaction(
+        &mut self,
+        tx_id: TxId,
+        outbound_tx: OutboundTransaction,
+    ) -> Result<(), TransactionStorageError>
+    {
+        self.db.update_pending_outbound_transaction(tx_id, outbound_tx)
+    }
+
+    pub fn remove_pending_outbound_transaction(&mut self, tx_id: TxId) -> Result<(), TransactionStorageError> {
+        self.db
+            .write(WriteOperation::Remove(DbKey::PendingOutboundTransaction(tx_id)))?;
+        Ok(())
+    }
+
+    /// Find the pending outbound transaction that a recipient's reply with the given tx_id belongs to. This is the
+    /// transaction with that tx_id, or the multi-recipient transaction with a recipient that has it.
+    pub fn find_pending_outbound_transaction_for_recipient(
+        &self,
+        tx_id: TxId,
+    ) -> Result<OutboundTransaction, TransactionStorageError>
+    {
+        if self.db.contains(&DbKey::PendingOutboundTransaction(tx_id))? {
+            return self.get_pending_outbound_transaction(tx_id);
+        }
+        let key = DbKey::PendingOutboundTransactionForRecipient(tx_id);
+        match self.db.fetch(&key) {
+            Ok(None) => Err(TransactionStorageError::ValueNotFound(
+                DbKey::PendingOutboundTransaction(tx_id),
+            )),
+            Ok(Some(DbValue::PendingOutboundTransaction(tx))) => Ok(*tx),
+            Ok(Some(other)) => unexpected_result(key, other),
+            Err(e) => log_error(key, e),
+        }
+    }
+
     pub fn add_pending_coinbase_transaction(
         &mut self,
         tx_id: TxId,
@@ -346,6 +493,43 @@ where T: TransactionBackend
         Ok(t)
     }
 
+    /// Fetch a page of the completed transactions, ordered by the time they were completed
+    pub fn get_completed_transaction_history(
+        &self,
+        query: TransactionHistoryQuery,
+    ) -> Result<Vec<CompletedTransaction>, TransactionStorageError>
+    {
+        let key = DbKey::CompletedTransactionHistory(query);
+        let t = match self.db.fetch(&key) {
+            Ok(None) => log_error(
+                key,
+                TransactionStorageError::UnexpectedResult(
+                    "Could not retrieve the completed transaction history".to_string(),
+                ),
+            ),
+            Ok(Some(DbValue::CompletedTransactionHistory(pt))) => Ok(pt),
+            Ok(Some(other)) => unexpected_result(key, other),
+            Err(e) => log_error(key, e),
+        }?;
+        Ok(t)
+    }
+
+    /// Add a transaction that was completed without a counterparty, such as a payment to self, directly to the
+    /// `CompleteTransaction` collection.
+    pub fn add_completed_transaction(
+        &mut self,
+        tx_id: TxId,
+        transaction: CompletedTransaction,
+    ) -> Result<(), TransactionStorageError>
+    {
+        self.db
+            .write(WriteOperation::Insert(DbKeyValuePair::CompletedTransaction(
+                tx_id,
+                Box::new(transaction),
+            )))?;
+        Ok(())
+    }
+
     /// This method moves a `PendingOutboundTransaction` to the `CompleteTransaction` collection.
     pub fn complete_outbound_transaction(
         &mut self,
@@ -376,6 +560,46 @@ where T: TransactionBackend
         self.db.complete_coinbase_transaction(tx_id, transaction)
     }
 
+    pub fn get_cancelled_pending_inbound_transactions(
+        &self,
+    ) -> Result<HashMap<TxId, InboundTransaction>, TransactionStorageError> {
+        let t = match self.db.fetch(&DbKey::CancelledPendingInboundTransactions) {
+            Ok(None) => log_error(
+                DbKey::CancelledPendingInboundTransactions,
+                TransactionStorageError::UnexpectedResult(
+                    "Could not retrieve cancelled pending inbound transactions".to_string(),
+                ),
+            ),
+            Ok(Some(DbValue::PendingInboundTransactions(pt))) => Ok(pt),
+            Ok(Some(other)) => unexpected_result(DbKey::CancelledPendingInboundTransactions, other),
+            Err(e) => log_error(DbKey::CancelledPendingInboundTransactions, e),
+        }?;
+        Ok(t)
+    }
+
+    pub fn get_cancelled_pending_outbound_transactions(
+        &self,
+    ) -> Result<HashMap<TxId, OutboundTransaction>, TransactionStorageError> {
+        let t = match self.db.fetch(&DbKey::CancelledPendingOutboundTransactions) {
+            Ok(None) => log_error(
+                DbKey::CancelledPendingOutboundTransactions,
+                TransactionStorageError::UnexpectedResult(
+                    "Could not retrieve cancelled pending outbound transactions".to_string(),
+                ),
+            ),
+            Ok(Some(DbValue::PendingOutboundTransactions(pt))) => Ok(pt),
+            Ok(Some(other)) => unexpected_result(DbKey::CancelledPendingOutboundTransactions, other),
+            Err(e) => log_error(DbKey::CancelledPendingOutboundTransactions, e),
+        }?;
+        Ok(t)
+    }
+
+    /// This method marks a `PendingInboundTransaction` or `PendingOutboundTransaction` as cancelled. It remains
+    /// available by its `TxId` but is no longer returned with the pending transactions.
+    pub fn cancel_pending_transaction(&mut self, tx_id: TxId) -> Result<(), TransactionStorageError> {
+        self.db.cancel_pending_transaction(tx_id)
+    }
+
     pub fn cancel_coinbase_transaction(&mut self, tx_id: TxId) -> Result<(), TransactionStorageError> {
         self.db
             .write(WriteOperation::Remove(DbKey::PendingCoinbaseTransaction(tx_id)))?;
@@ -383,15 +607,79 @@ where T: TransactionBackend
     }
 
     /// Indicated that the specified completed transaction has been broadcast into the mempool
-    #[cfg(feature = "test_harness")]
     pub fn broadcast_completed_transaction(&mut self, tx_id: TxId) -> Result<(), TransactionStorageError> {
         self.db.broadcast_completed_transaction(tx_id)
     }
 
-    /// Indicated that the specified completed transaction has been detected as mined on the base layer
-    #[cfg(feature = "test_harness")]
-    pub fn mine_completed_transaction(&mut self, tx_id: TxId) -> Result<(), TransactionStorageError> {
-        self.db.mine_completed_transaction(tx_id)
+    /// Indicated that the specified completed transaction has been detected as mined on the base layer, at the given
+    /// block height if it is known
+    pub fn mine_completed_transaction(
+        &mut self,
+        tx_id: TxId,
+        mined_height: Option<u64>,
+    ) -> Result<(), TransactionStorageError>
+    {
+        self.db.mine_completed_transaction(tx_id, mined_height)
+    }
+
+    /// Store a transaction that was relayed on behalf of an offline wallet, replacing the stored version of it
+    pub fn save_relayed_offline_transaction(
+        &mut self,
+        tx_id: TxId,
+        relayed_tx: RelayedOfflineTransaction,
+    ) -> Result<(), TransactionStorageError>
+    {
+        self.db
+            .write(WriteOperation::Insert(DbKeyValuePair::RelayedOfflineTransaction(
+                tx_id,
+                Box::new(relayed_tx),
+            )))?;
+        Ok(())
+    }
+
+    pub fn get_relayed_offline_transaction(
+        &self,
+        tx_id: TxId,
+    ) -> Result<RelayedOfflineTransaction, TransactionStorageError>
+    {
+        let result = fetch!(self, tx_id, RelayedOfflineTransaction)?;
+        Ok(result)
+    }
+
+    pub fn get_relayed_offline_transactions(
+        &self,
+    ) -> Result<HashMap<TxId, RelayedOfflineTransaction>, TransactionStorageError> {
+        let t = match self.db.fetch(&DbKey::RelayedOfflineTransactions) {
+            Ok(None) => log_error(
+                DbKey::RelayedOfflineTransactions,
+                TransactionStorageError::UnexpectedResult(
+                    "Could not retrieve relayed offline transactions".to_string(),
+                ),
+            ),
+            Ok(Some(DbValue::RelayedOfflineTransactions(pt))) => Ok(pt),
+            Ok(Some(other)) => unexpected_result(DbKey::RelayedOfflineTransactions, other),
+            Err(e) => log_error(DbKey::RelayedOfflineTransactions, e),
+        }?;
+        Ok(t)
+    }
+
+    pub fn remove_relayed_offline_transaction(&mut self, tx_id: TxId) -> Result<(), TransactionStorageError> {
+        self.db
+            .write(WriteOperation::Remove(DbKey::RelayedOfflineTransaction(tx_id)))?;
+        Ok(())
+    }
+
+    /// Encrypt the stored protocols with the provided cipher, or decrypt them if no cipher is provided
+    pub fn apply_encryption(&mut self, cipher: Option<SecretCipher>) -> Result<(), TransactionStorageError> {
+        self.db.apply_encryption(cipher)
+    }
+
+    pub fn unlock(&mut self, cipher: SecretCipher) -> Result<(), TransactionStorageError> {
+        self.db.unlock(cipher)
+    }
+
+    pub fn lock(&mut self) -> Result<(), TransactionStorageError> {
+        self.db.lock()
     }
 }
 
@@ -399,6 +687,9 @@ impl Display for DbKey {
     fn fmt(&self, f: &mut Formatter) -> Result<(), Error> {
         match self {
             DbKey::PendingOutboundTransaction(_) => f.write_str(&format!("Pending Outbound Transaction")),
+            DbKey::PendingOutboundTransactionForRecipient(_) => {
+                f.write_str(&format!("Pending Outbound Transaction For Recipient"))
+            },
             DbKey::PendingInboundTransaction(_) => f.write_str(&format!("Pending Inbound Transaction")),
             DbKey::PendingCoinbaseTransaction(_) => f.write_str(&format!("Pending Pending Coinbase Transaction")),
             DbKey::CompletedTransaction(_) => f.write_str(&format!("Completed Transaction")),
@@ -406,6 +697,15 @@ impl Display for DbKey {
             DbKey::PendingInboundTransactions => f.write_str(&format!("All Pending Inbound Transactions")),
             DbKey::CompletedTransactions => f.write_str(&format!("All Complete Transactions")),
             DbKey::PendingCoinbaseTransactions => f.write_str(&format!("All Pending Coinbase Transactions")),
+            DbKey::CancelledPendingOutboundTransactions => {
+                f.write_str(&format!("All Cancelled Pending Outbound Transactions"))
+            },
+            DbKey::CancelledPendingInboundTransactions => {
+                f.write_str(&format!("All Cancelled Pending Inbound Transactions"))
+            },
+            DbKey::CompletedTransactionHistory(_) => f.write_str(&format!("Completed Transaction History")),
+            DbKey::RelayedOfflineTransaction(_) => f.write_str(&format!("Relayed Offline Transaction")),
+            DbKey::RelayedOfflineTransactions => f.write_str(&format!("All Relayed Offline Transactions")),
         }
     }
 }
@@ -421,6 +721,9 @@ impl Display for DbValue {
             DbValue::PendingInboundTransactions(_) => f.write_str(&format!("All Pending Inbound Transactions")),
             DbValue::CompletedTransactions(_) => f.write_str(&format!("All Complete Transactions")),
             DbValue::PendingCoinbaseTransactions(_) => f.write_str(&format!("All Pending Coinbase Transactions")),
+            DbValue::CompletedTransactionHistory(_) => f.write_str(&format!("Completed Transaction History")),
+            DbValue::RelayedOfflineTransaction(_) => f.write_str(&format!("Relayed Offline Transaction")),
+            DbValue::RelayedOfflineTransactions(_) => f.write_str(&format!("All Relayed Offline Transactions")),
         }
     }
 }
@@ -20,9 +20,8 @@
 // WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
 // USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
-#[cfg(feature = "test_harness")]
-use crate::transaction_service::storage::database::TransactionStatus;
 use crate::{
+    encryption::SecretCipher,
     output_manager_service::TxId,
     transaction_service::{
         error::TransactionStorageError,
@@ -34,7 +33,9 @@ use crate::{
             InboundTransaction,
             OutboundTransaction,
             PendingCoinbaseTransaction,
+            RelayedOfflineTransaction,
             TransactionBackend,
+            TransactionStatus,
             WriteOperation,
         },
     },
@@ -48,19 +49,39 @@ use std::{
 
 struct InnerDatabase {
     pending_outbound_transactions: HashMap<TxId, OutboundTransaction>,
+    /// The tx_id of the pending outbound transaction that each recipient's tx_id belongs to
+    outbound_transaction_recipients: HashMap<TxId, TxId>,
     pending_inbound_transactions: HashMap<TxId, InboundTransaction>,
     pending_coinbase_transactions: HashMap<TxId, PendingCoinbaseTransaction>,
     completed_transactions: HashMap<TxId, CompletedTransaction>,
+    relayed_offline_transactions: HashMap<TxId, RelayedOfflineTransaction>,
 }
 
 impl InnerDatabase {
     pub fn new() -> Self {
         Self {
             pending_outbound_transactions: HashMap::new(),
+            outbound_transaction_recipients: HashMap::new(),
             pending_inbound_transactions: HashMap::new(),
             pending_coinbase_transactions: HashMap::new(),
             completed_transactions: HashMap::new(),
+            relayed_offline_transactions: HashMap::new(),
+        }
+    }
+
+    fn insert_pending_outbound_transaction(&mut self, tx_id: TxId, outbound_tx: OutboundTransaction) {
+        for recipient in outbound_tx.recipients.iter() {
+            self.outbound_transaction_recipients.insert(recipient.tx_id, tx_id);
         }
+        self.pending_outbound_transactions.insert(tx_id, outbound_tx);
+    }
+
+    fn remove_pending_outbound_transaction(&mut self, tx_id: &TxId) -> Option<OutboundTransaction> {
+        let outbound_tx = self.pending_outbound_transactions.remove(tx_id)?;
+        for recipient in outbound_tx.recipients.iter() {
+            self.outbound_transaction_recipients.remove(&recipient.tx_id);
+        }
+        Some(outbound_tx)
     }
 }
 
@@ -85,6 +106,11 @@ impl TransactionBackend for TransactionMemoryDatabase {
                 .pending_outbound_transactions
                 .get(t)
                 .map(|v| DbValue::PendingOutboundTransaction(Box::new(v.clone()))),
+            DbKey::PendingOutboundTransactionForRecipient(t) => db
+                .outbound_transaction_recipients
+                .get(t)
+                .and_then(|tx_id| db.pending_outbound_transactions.get(tx_id))
+                .map(|v| DbValue::PendingOutboundTransaction(Box::new(v.clone()))),
             DbKey::PendingInboundTransaction(t) => db
                 .pending_inbound_transactions
                 .get(t)
@@ -98,15 +124,60 @@ impl TransactionBackend for TransactionMemoryDatabase {
                 .get(t)
                 .map(|v| DbValue::PendingCoinbaseTransaction(Box::new(v.clone()))),
             DbKey::PendingOutboundTransactions => Some(DbValue::PendingOutboundTransactions(
-                db.pending_outbound_transactions.clone(),
+                db.pending_outbound_transactions
+                    .iter()
+                    .filter(|(_, v)| !v.cancelled)
+                    .map(|(k, v)| (*k, v.clone()))
+                    .collect(),
             )),
             DbKey::PendingInboundTransactions => Some(DbValue::PendingInboundTransactions(
-                db.pending_inbound_transactions.clone(),
+                db.pending_inbound_transactions
+                    .iter()
+                    .filter(|(_, v)| !v.cancelled)
+                    .map(|(k, v)| (*k, v.clone()))
+                    .collect(),
+            )),
+            DbKey::CancelledPendingOutboundTransactions => Some(DbValue::PendingOutboundTransactions(
+                db.pending_outbound_transactions
+                    .iter()
+                    .filter(|(_, v)| v.cancelled)
+                    .map(|(k, v)| (*k, v.clone()))
+                    .collect(),
+            )),
+            DbKey::CancelledPendingInboundTransactions => Some(DbValue::PendingInboundTransactions(
+                db.pending_inbound_transactions
+                    .iter()
+                    .filter(|(_, v)| v.cancelled)
+                    .map(|(k, v)| (*k, v.clone()))
+                    .collect(),
             )),
             DbKey::PendingCoinbaseTransactions => Some(DbValue::PendingCoinbaseTransactions(
                 db.pending_coinbase_transactions.clone(),
             )),
             DbKey::CompletedTransactions => Some(DbValue::CompletedTransactions(db.completed_transactions.clone())),
+            DbKey::CompletedTransactionHistory(query) => {
+                let mut history: Vec<CompletedTransaction> = db
+                    .completed_transactions
+                    .values()
+                    .filter(|tx| query.contains(&tx.timestamp))
+                    .cloned()
+                    .collect();
+                history.sort_by(|a, b| a.timestamp.cmp(&b.timestamp).then(a.tx_id.cmp(&b.tx_id)));
+                Some(DbValue::CompletedTransactionHistory(
+                    history
+                        .into_iter()
+                        .skip(query.offset)
+                        .take(query.limit.unwrap_or(usize::max_value()))
+                        .collect(),
+                ))
+            },
+            DbKey::RelayedOfflineTransaction(t) => db
+                .relayed_offline_transactions
+                .get(t)
+                .map(|v| DbValue::RelayedOfflineTransaction(Box::new(v.clone()))),
+            DbKey::RelayedOfflineTransactions => Some(DbValue::RelayedOfflineTransactions(
+                db.relayed_offline_transactions.clone(),
+            )),
         };
 
         Ok(result)
@@ -116,6 +187,7 @@ impl TransactionBackend for TransactionMemoryDatabase {
         let db = acquire_read_lock!(self.db);
         let result = match key {
             DbKey::PendingOutboundTransaction(k) => db.pending_outbound_transactions.contains_key(k),
+            DbKey::PendingOutboundTransactionForRecipient(k) => db.outbound_transaction_recipients.contains_key(k),
             DbKey::PendingInboundTransaction(k) => db.pending_inbound_transactions.contains_key(k),
             DbKey::CompletedTransaction(k) => db.completed_transactions.contains_key(k),
             DbKey::PendingCoinbaseTransaction(k) => db.pending_coinbase_transactions.contains_key(k),
@@ -123,6 +195,11 @@ impl TransactionBackend for TransactionMemoryDatabase {
             DbKey::PendingInboundTransactions => false,
             DbKey::CompletedTransactions => false,
             DbKey::PendingCoinbaseTransactions => false,
+            DbKey::CancelledPendingOutboundTransactions => false,
+            DbKey::CancelledPendingInboundTransactions => false,
+            DbKey::CompletedTransactionHistory(_) => false,
+            DbKey::RelayedOfflineTransaction(k) => db.relayed_offline_transactions.contains_key(k),
+            DbKey::RelayedOfflineTransactions => false,
         };
 
         Ok(result)
@@ -136,7 +213,7 @@ impl TransactionBackend for TransactionMemoryDatabase {
                     if db.pending_outbound_transactions.contains_key(&k) {
                         return Err(TransactionStorageError::DuplicateOutput);
                     }
-                    db.pending_outbound_transactions.insert(k, *v);
+                    db.insert_pending_outbound_transaction(k, *v);
                 },
                 DbKeyValuePair::PendingInboundTransaction(k, v) => {
                     if db.pending_inbound_transactions.contains_key(&k) {
@@ -157,10 +234,13 @@ impl TransactionBackend for TransactionMemoryDatabase {
                     }
                     db.completed_transactions.insert(k, *v);
                 },
+                DbKeyValuePair::RelayedOfflineTransaction(k, v) => {
+                    db.relayed_offline_transactions.insert(k, *v);
+                },
             },
             WriteOperation::Remove(k) => match k {
                 DbKey::PendingOutboundTransaction(k) => {
-                    if let Some(p) = db.pending_outbound_transactions.remove(&k) {
+                    if let Some(p) = db.remove_pending_outbound_transaction(&k) {
                         return Ok(Some(DbValue::PendingOutboundTransaction(Box::new(p))));
                     } else {
                         return Err(TransactionStorageError::ValueNotFound(
@@ -195,8 +275,28 @@ impl TransactionBackend for TransactionMemoryDatabase {
                 },
                 DbKey::PendingInboundTransactions => return Err(TransactionStorageError::OperationNotSupported),
                 DbKey::PendingOutboundTransactions => return Err(TransactionStorageError::OperationNotSupported),
+                DbKey::PendingOutboundTransactionForRecipient(_) => {
+                    return Err(TransactionStorageError::OperationNotSupported)
+                },
                 DbKey::CompletedTransactions => return Err(TransactionStorageError::OperationNotSupported),
                 DbKey::PendingCoinbaseTransactions => return Err(TransactionStorageError::OperationNotSupported),
+                DbKey::CancelledPendingOutboundTransactions => {
+                    return Err(TransactionStorageError::OperationNotSupported)
+                },
+                DbKey::CancelledPendingInboundTransactions => {
+                    return Err(TransactionStorageError::OperationNotSupported)
+                },
+                DbKey::RelayedOfflineTransaction(k) => {
+                    if let Some(p) = db.relayed_offline_transactions.remove(&k) {
+                        return Ok(Some(DbValue::RelayedOfflineTransaction(Box::new(p))));
+                    } else {
+                        return Err(TransactionStorageError::ValueNotFound(
+                            DbKey::RelayedOfflineTransaction(k),
+                        ));
+                    }
+                },
+                DbKey::CompletedTransactionHistory(_) => return Err(TransactionStorageError::OperationNotSupported),
+                DbKey::RelayedOfflineTransactions => return Err(TransactionStorageError::OperationNotSupported),
             },
         }
 
@@ -216,8 +316,7 @@ impl TransactionBackend for TransactionMemoryDatabase {
         }
 
         let _ = db
-            .pending_outbound_transactions
-            .remove(&tx_id)
+            .remove_pending_outbound_transaction(&tx_id)
             .ok_or(TransactionStorageError::ValueNotFound(
                 DbKey::PendingOutboundTransaction(tx_id.clone()),
             ))?;
@@ -227,6 +326,23 @@ impl TransactionBackend for TransactionMemoryDatabase {
         Ok(())
     }
 
+    fn update_pending_outbound_transaction(
+        &mut self,
+        tx_id: TxId,
+        outbound_transaction: OutboundTransaction,
+    ) -> Result<(), TransactionStorageError>
+    {
+        let mut db = acquire_write_lock!(self.db);
+
+        db.remove_pending_outbound_transaction(&tx_id)
+            .ok_or(TransactionStorageError::ValueNotFound(
+                DbKey::PendingOutboundTransaction(tx_id),
+            ))?;
+        db.insert_pending_outbound_transaction(tx_id, outbound_transaction);
+
+        Ok(())
+    }
+
     fn complete_inbound_transaction(
         &mut self,
         tx_id: TxId,
@@ -271,7 +387,22 @@ impl TransactionBackend for TransactionMemoryDatabase {
         Ok(())
     }
 
-    #[cfg(feature = "test_harness")]
+    fn cancel_pending_transaction(&mut self, tx_id: TxId) -> Result<(), TransactionStorageError> {
+        let mut db = acquire_write_lock!(self.db);
+
+        if let Some(outbound_tx) = db.pending_outbound_transactions.get_mut(&tx_id) {
+            outbound_tx.cancelled = true;
+        } else if let Some(inbound_tx) = db.pending_inbound_transactions.get_mut(&tx_id) {
+            inbound_tx.cancelled = true;
+        } else {
+            return Err(TransactionStorageError::ValueNotFound(
+                DbKey::PendingOutboundTransaction(tx_id),
+            ));
+        }
+
+        Ok(())
+    }
+
     fn broadcast_completed_transaction(&mut self, tx_id: TxId) -> Result<(), TransactionStorageError> {
         let mut db = acquire_write_lock!(self.db);
 
@@ -286,8 +417,12 @@ impl TransactionBackend for TransactionMemoryDatabase {
         Ok(())
     }
 
-    #[cfg(feature = "test_harness")]
-    fn mine_completed_transaction(&mut self, tx_id: TxId) -> Result<(), TransactionStorageError> {
+    fn mine_completed_transaction(
+        &mut self,
+        tx_id: TxId,
+        mined_height: Option<u64>,
+    ) -> Result<(), TransactionStorageError>
+    {
         let mut db = acquire_write_lock!(self.db);
 
         let mut completed_tx =
@@ -297,6 +432,7 @@ impl TransactionBackend for TransactionMemoryDatabase {
                     tx_id.clone(),
                 )))?;
         completed_tx.status = TransactionStatus::Mined;
+        completed_tx.mined_height = mined_height;
 
         Ok(())
     }
@@ -316,4 +452,16 @@ impl TransactionBackend for TransactionMemoryDatabase {
 
         Ok(())
     }
+
+    fn apply_encryption(&mut self, _cipher: Option<SecretCipher>) -> Result<(), TransactionStorageError> {
+        Ok(())
+    }
+
+    fn unlock(&mut self, _cipher: SecretCipher) -> Result<(), TransactionStorageError> {
+        Ok(())
+    }
+
+    fn lock(&mut self) -> Result<(), TransactionStorageError> {
+        Ok(())
+    }
 }
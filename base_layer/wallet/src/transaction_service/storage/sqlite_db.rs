@@ -21,8 +21,17 @@
 // USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
 use crate::{
+    encryption::{EncryptionState, SecretCipher},
+    error::EncryptionError,
     output_manager_service::TxId,
-    schema::{coinbase_transactions, completed_transactions, inbound_transactions, outbound_transactions},
+    schema::{
+        coinbase_transactions,
+        completed_transactions,
+        inbound_transactions,
+        outbound_transaction_recipients,
+        outbound_transactions,
+        relayed_offline_transactions,
+    },
     transaction_service::{
         error::TransactionStorageError,
         storage::database::{
@@ -33,7 +42,10 @@ use crate::{
             InboundTransaction,
             OutboundTransaction,
             PendingCoinbaseTransaction,
+            RelayedOfflineTransaction,
             TransactionBackend,
+            TransactionHistoryQuery,
+            TransactionRecipient,
             TransactionStatus,
             WriteOperation,
         },
@@ -46,19 +58,33 @@ use diesel::{
     result::Error as DieselError,
     SqliteConnection,
 };
-use std::{collections::HashMap, convert::TryFrom, io, path::Path, time::Duration};
+use std::{
+    collections::HashMap,
+    convert::TryFrom,
+    io,
+    path::Path,
+    sync::{Arc, RwLock},
+    time::Duration,
+};
 use tari_core::transactions::{
     tari_amount::MicroTari,
     types::{Commitment, PublicKey},
 };
-use tari_utilities::ByteArray;
+use tari_utilities::{
+    hex::{from_hex, to_hex},
+    ByteArray,
+};
 
 const DATABASE_CONNECTION_TIMEOUT_MS: u64 = 2000;
 
 /// A Sqlite backend for the Transaction Service. The Backend is accessed via a connection pool to the Sqlite file.
+/// The sender and receiver protocols of pending transactions, which hold their secret nonces and blinding factors, are
+/// stored encrypted if encryption has been applied, in which case the backend must be unlocked before they can be read
+/// or written.
 #[derive(Clone)]
 pub struct TransactionServiceSqliteDatabase {
     database_connection_pool: Pool<ConnectionManager<SqliteConnection>>,
+    encryption: Arc<RwLock<EncryptionState>>,
 }
 impl TransactionServiceSqliteDatabase {
     pub fn new(database_path: String) -> Result<Self, TransactionStorageError> {
@@ -82,8 +108,17 @@ impl TransactionServiceSqliteDatabase {
             .build(manager)
             .map_err(|_| TransactionStorageError::R2d2Error)?;
 
+        // A database whose stored protocols are not in plain form has been encrypted and starts out locked
+        let conn = pool.get().map_err(|_| TransactionStorageError::R2d2Error)?;
+        let encryption = match find_stored_protocol(&conn)? {
+            Some(p) if is_encrypted_protocol(&p) => EncryptionState::Locked,
+            _ => EncryptionState::Unencrypted,
+        };
+        drop(conn);
+
         Ok(Self {
             database_connection_pool: pool,
+            encryption: Arc::new(RwLock::new(encryption)),
         })
     }
 }
@@ -95,18 +130,28 @@ impl TransactionBackend for TransactionServiceSqliteDatabase {
             .clone()
             .get()
             .map_err(|_| TransactionStorageError::R2d2Error)?;
+        let encryption = acquire_read_lock!(self.encryption);
 
         let result = match key {
             DbKey::PendingOutboundTransaction(t) => match OutboundTransactionSql::find(t, &conn) {
                 Ok(o) => Some(DbValue::PendingOutboundTransaction(Box::new(
-                    OutboundTransaction::try_from(o)?,
+                    OutboundTransaction::try_from(o.decrypt(&encryption)?)?,
                 ))),
                 Err(TransactionStorageError::DieselError(DieselError::NotFound)) => None,
                 Err(e) => return Err(e),
             },
+            DbKey::PendingOutboundTransactionForRecipient(t) => {
+                match OutboundTransactionSql::find_by_recipient(t, &conn) {
+                    Ok(o) => Some(DbValue::PendingOutboundTransaction(Box::new(
+                        OutboundTransaction::try_from(o.decrypt(&encryption)?)?,
+                    ))),
+                    Err(TransactionStorageError::DieselError(DieselError::NotFound)) => None,
+                    Err(e) => return Err(e),
+                }
+            },
             DbKey::PendingInboundTransaction(t) => match InboundTransactionSql::find(t, &conn) {
                 Ok(o) => Some(DbValue::PendingInboundTransaction(Box::new(
-                    InboundTransaction::try_from(o)?,
+                    InboundTransaction::try_from(o.decrypt(&encryption)?)?,
                 ))),
                 Err(TransactionStorageError::DieselError(DieselError::NotFound)) => None,
                 Err(e) => return Err(e),
@@ -127,24 +172,28 @@ impl TransactionBackend for TransactionServiceSqliteDatabase {
                 Err(e) => return Err(e),
             },
             DbKey::PendingOutboundTransactions => Some(DbValue::PendingOutboundTransactions(
-                OutboundTransactionSql::index(&conn)?
-                    .iter()
-                    .fold(HashMap::new(), |mut acc, x| {
-                        if let Ok(v) = OutboundTransaction::try_from((*x).clone()) {
-                            acc.insert(x.tx_id as u64, v);
-                        }
-                        acc
-                    }),
+                OutboundTransactionSql::index_by_cancelled(false, &conn)?
+                    .into_iter()
+                    .map(|x| Ok((x.tx_id as u64, OutboundTransaction::try_from(x.decrypt(&encryption)?)?)))
+                    .collect::<Result<HashMap<_, _>, TransactionStorageError>>()?,
             )),
             DbKey::PendingInboundTransactions => Some(DbValue::PendingInboundTransactions(
-                InboundTransactionSql::index(&conn)?
-                    .iter()
-                    .fold(HashMap::new(), |mut acc, x| {
-                        if let Ok(v) = InboundTransaction::try_from((*x).clone()) {
-                            acc.insert(x.tx_id as u64, v);
-                        }
-                        acc
-                    }),
+                InboundTransactionSql::index_by_cancelled(false, &conn)?
+                    .into_iter()
+                    .map(|x| Ok((x.tx_id as u64, InboundTransaction::try_from(x.decrypt(&encryption)?)?)))
+                    .collect::<Result<HashMap<_, _>, TransactionStorageError>>()?,
+            )),
+            DbKey::CancelledPendingOutboundTransactions => Some(DbValue::PendingOutboundTransactions(
+                OutboundTransactionSql::index_by_cancelled(true, &conn)?
+                    .into_iter()
+                    .map(|x| Ok((x.tx_id as u64, OutboundTransaction::try_from(x.decrypt(&encryption)?)?)))
+                    .collect::<Result<HashMap<_, _>, TransactionStorageError>>()?,
+            )),
+            DbKey::CancelledPendingInboundTransactions => Some(DbValue::PendingInboundTransactions(
+                InboundTransactionSql::index_by_cancelled(true, &conn)?
+                    .into_iter()
+                    .map(|x| Ok((x.tx_id as u64, InboundTransaction::try_from(x.decrypt(&encryption)?)?)))
+                    .collect::<Result<HashMap<_, _>, TransactionStorageError>>()?,
             )),
             DbKey::PendingCoinbaseTransactions => Some(DbValue::PendingCoinbaseTransactions(
                 PendingCoinbaseTransactionSql::index(&conn)?
@@ -166,6 +215,25 @@ impl TransactionBackend for TransactionServiceSqliteDatabase {
                         acc
                     }),
             )),
+            DbKey::CompletedTransactionHistory(query) => Some(DbValue::CompletedTransactionHistory(
+                CompletedTransactionSql::index_by_query(query, &conn)?
+                    .into_iter()
+                    .map(CompletedTransaction::try_from)
+                    .collect::<Result<Vec<_>, _>>()?,
+            )),
+            DbKey::RelayedOfflineTransaction(t) => match RelayedOfflineTransactionSql::find(t, &conn) {
+                Ok(o) => Some(DbValue::RelayedOfflineTransaction(Box::new(
+                    RelayedOfflineTransaction::try_from(o)?,
+                ))),
+                Err(TransactionStorageError::DieselError(DieselError::NotFound)) => None,
+                Err(e) => return Err(e),
+            },
+            DbKey::RelayedOfflineTransactions => Some(DbValue::RelayedOfflineTransactions(
+                RelayedOfflineTransactionSql::index(&conn)?
+                    .into_iter()
+                    .map(|x| Ok((x.tx_id as u64, RelayedOfflineTransaction::try_from(x)?)))
+                    .collect::<Result<HashMap<_, _>, TransactionStorageError>>()?,
+            )),
         };
 
         Ok(result)
@@ -180,6 +248,9 @@ impl TransactionBackend for TransactionServiceSqliteDatabase {
 
         let result = match key {
             DbKey::PendingOutboundTransaction(k) => OutboundTransactionSql::find(k, &conn).is_ok(),
+            DbKey::PendingOutboundTransactionForRecipient(k) => {
+                OutboundTransactionSql::find_by_recipient(k, &conn).is_ok()
+            },
             DbKey::PendingInboundTransaction(k) => InboundTransactionSql::find(k, &conn).is_ok(),
             DbKey::PendingCoinbaseTransaction(k) => PendingCoinbaseTransactionSql::find(k, &conn).is_ok(),
             DbKey::CompletedTransaction(k) => CompletedTransactionSql::find(k, &conn).is_ok(),
@@ -187,6 +258,11 @@ impl TransactionBackend for TransactionServiceSqliteDatabase {
             DbKey::PendingInboundTransactions => false,
             DbKey::CompletedTransactions => false,
             DbKey::PendingCoinbaseTransactions => false,
+            DbKey::CancelledPendingOutboundTransactions => false,
+            DbKey::CancelledPendingInboundTransactions => false,
+            DbKey::CompletedTransactionHistory(_) => false,
+            DbKey::RelayedOfflineTransaction(k) => RelayedOfflineTransactionSql::find(k, &conn).is_ok(),
+            DbKey::RelayedOfflineTransactions => false,
         };
 
         Ok(result)
@@ -198,6 +274,7 @@ impl TransactionBackend for TransactionServiceSqliteDatabase {
             .clone()
             .get()
             .map_err(|_| TransactionStorageError::R2d2Error)?;
+        let encryption = acquire_read_lock!(self.encryption);
 
         match op {
             WriteOperation::Insert(kvp) => match kvp {
@@ -205,13 +282,17 @@ impl TransactionBackend for TransactionServiceSqliteDatabase {
                     if let Ok(_) = OutboundTransactionSql::find(&k, &conn) {
                         return Err(TransactionStorageError::DuplicateOutput);
                     }
-                    OutboundTransactionSql::try_from(*v)?.commit(&conn)?;
+                    OutboundTransactionSql::try_from(*v)?
+                        .encrypt(&encryption)?
+                        .commit(&conn)?;
                 },
                 DbKeyValuePair::PendingInboundTransaction(k, v) => {
                     if let Ok(_) = InboundTransactionSql::find(&k, &conn) {
                         return Err(TransactionStorageError::DuplicateOutput);
                     }
-                    InboundTransactionSql::try_from(*v)?.commit(&conn)?;
+                    InboundTransactionSql::try_from(*v)?
+                        .encrypt(&encryption)?
+                        .commit(&conn)?;
                 },
                 DbKeyValuePair::PendingCoinbaseTransaction(k, v) => {
                     if let Ok(_) = PendingCoinbaseTransactionSql::find(&k, &conn) {
@@ -225,14 +306,16 @@ impl TransactionBackend for TransactionServiceSqliteDatabase {
                     }
                     CompletedTransactionSql::try_from(*v)?.commit(&conn)?;
                 },
+                DbKeyValuePair::RelayedOfflineTransaction(_, v) => {
+                    RelayedOfflineTransactionSql::try_from(*v)?.commit(&conn)?;
+                },
             },
             WriteOperation::Remove(kvp) => match kvp {
                 DbKey::PendingOutboundTransaction(k) => match OutboundTransactionSql::find(&k, &conn) {
                     Ok(v) => {
+                        let outbound_tx = OutboundTransaction::try_from(v.clone().decrypt(&encryption)?)?;
                         v.delete(&conn)?;
-                        return Ok(Some(DbValue::PendingOutboundTransaction(Box::new(
-                            OutboundTransaction::try_from(v)?,
-                        ))));
+                        return Ok(Some(DbValue::PendingOutboundTransaction(Box::new(outbound_tx))));
                     },
                     Err(TransactionStorageError::DieselError(DieselError::NotFound)) => {
                         return Err(TransactionStorageError::ValueNotFound(
@@ -243,10 +326,9 @@ impl TransactionBackend for TransactionServiceSqliteDatabase {
                 },
                 DbKey::PendingInboundTransaction(k) => match InboundTransactionSql::find(&k, &conn) {
                     Ok(v) => {
+                        let inbound_tx = InboundTransaction::try_from(v.clone().decrypt(&encryption)?)?;
                         v.delete(&conn)?;
-                        return Ok(Some(DbValue::PendingInboundTransaction(Box::new(
-                            InboundTransaction::try_from(v)?,
-                        ))));
+                        return Ok(Some(DbValue::PendingInboundTransaction(Box::new(inbound_tx))));
                     },
                     Err(TransactionStorageError::DieselError(DieselError::NotFound)) => {
                         return Err(TransactionStorageError::ValueNotFound(
@@ -281,10 +363,35 @@ impl TransactionBackend for TransactionServiceSqliteDatabase {
                     },
                     Err(e) => return Err(e),
                 },
+                DbKey::PendingOutboundTransactionForRecipient(_) => {
+                    return Err(TransactionStorageError::OperationNotSupported)
+                },
                 DbKey::PendingOutboundTransactions => return Err(TransactionStorageError::OperationNotSupported),
                 DbKey::PendingInboundTransactions => return Err(TransactionStorageError::OperationNotSupported),
                 DbKey::CompletedTransactions => return Err(TransactionStorageError::OperationNotSupported),
                 DbKey::PendingCoinbaseTransactions => return Err(TransactionStorageError::OperationNotSupported),
+                DbKey::CancelledPendingOutboundTransactions => {
+                    return Err(TransactionStorageError::OperationNotSupported)
+                },
+                DbKey::CancelledPendingInboundTransactions => {
+                    return Err(TransactionStorageError::OperationNotSupported)
+                },
+                DbKey::RelayedOfflineTransaction(k) => match RelayedOfflineTransactionSql::find(&k, &conn) {
+                    Ok(v) => {
+                        v.delete(&conn)?;
+                        return Ok(Some(DbValue::RelayedOfflineTransaction(Box::new(
+                            RelayedOfflineTransaction::try_from(v)?,
+                        ))));
+                    },
+                    Err(TransactionStorageError::DieselError(DieselError::NotFound)) => {
+                        return Err(TransactionStorageError::ValueNotFound(
+                            DbKey::RelayedOfflineTransaction(k),
+                        ))
+                    },
+                    Err(e) => return Err(e),
+                },
+                DbKey::CompletedTransactionHistory(_) => return Err(TransactionStorageError::OperationNotSupported),
+                DbKey::RelayedOfflineTransactions => return Err(TransactionStorageError::OperationNotSupported),
             },
         }
         Ok(None)
@@ -322,6 +429,31 @@ impl TransactionBackend for TransactionServiceSqliteDatabase {
         Ok(())
     }
 
+    fn update_pending_outbound_transaction(
+        &mut self,
+        tx_id: TxId,
+        outbound_transaction: OutboundTransaction,
+    ) -> Result<(), TransactionStorageError>
+    {
+        let conn = self
+            .database_connection_pool
+            .clone()
+            .get()
+            .map_err(|_| TransactionStorageError::R2d2Error)?;
+
+        let encryption = acquire_read_lock!(self.encryption);
+
+        match OutboundTransactionSql::find(&tx_id, &conn) {
+            Ok(_) => OutboundTransactionSql::try_from(outbound_transaction)?
+                .encrypt(&encryption)?
+                .update(&conn),
+            Err(TransactionStorageError::DieselError(DieselError::NotFound)) => Err(
+                TransactionStorageError::ValueNotFound(DbKey::PendingOutboundTransaction(tx_id)),
+            ),
+            Err(e) => Err(e),
+        }
+    }
+
     fn complete_inbound_transaction(
         &mut self,
         tx_id: u64,
@@ -386,7 +518,27 @@ impl TransactionBackend for TransactionServiceSqliteDatabase {
         Ok(())
     }
 
-    #[cfg(feature = "test_harness")]
+    fn cancel_pending_transaction(&mut self, tx_id: u64) -> Result<(), TransactionStorageError> {
+        let conn = self
+            .database_connection_pool
+            .clone()
+            .get()
+            .map_err(|_| TransactionStorageError::R2d2Error)?;
+
+        match OutboundTransactionSql::find(&tx_id, &conn) {
+            Ok(v) => return v.cancel(&conn),
+            Err(TransactionStorageError::DieselError(DieselError::NotFound)) => (),
+            Err(e) => return Err(e),
+        };
+        match InboundTransactionSql::find(&tx_id, &conn) {
+            Ok(v) => v.cancel(&conn),
+            Err(TransactionStorageError::DieselError(DieselError::NotFound)) => Err(
+                TransactionStorageError::ValueNotFound(DbKey::PendingOutboundTransaction(tx_id)),
+            ),
+            Err(e) => Err(e),
+        }
+    }
+
     fn broadcast_completed_transaction(&mut self, tx_id: u64) -> Result<(), TransactionStorageError> {
         let conn = self
             .database_connection_pool
@@ -400,6 +552,7 @@ impl TransactionBackend for TransactionServiceSqliteDatabase {
                     UpdateCompletedTransaction {
                         status: Some(TransactionStatus::Broadcast),
                         timestamp: None,
+                        mined_height: None,
                     },
                     &conn,
                 )?;
@@ -414,8 +567,12 @@ impl TransactionBackend for TransactionServiceSqliteDatabase {
         Ok(())
     }
 
-    #[cfg(feature = "test_harness")]
-    fn mine_completed_transaction(&mut self, tx_id: u64) -> Result<(), TransactionStorageError> {
+    fn mine_completed_transaction(
+        &mut self,
+        tx_id: u64,
+        mined_height: Option<u64>,
+    ) -> Result<(), TransactionStorageError>
+    {
         let conn = self
             .database_connection_pool
             .clone()
@@ -428,6 +585,7 @@ impl TransactionBackend for TransactionServiceSqliteDatabase {
                     UpdateCompletedTransaction {
                         status: Some(TransactionStatus::Mined),
                         timestamp: None,
+                        mined_height,
                     },
                     &conn,
                 )?;
@@ -460,6 +618,7 @@ impl TransactionBackend for TransactionServiceSqliteDatabase {
                 UpdateCompletedTransaction {
                     status: None,
                     timestamp: Some(timestamp),
+                    mined_height: None,
                 },
                 &conn,
             );
@@ -467,6 +626,112 @@ impl TransactionBackend for TransactionServiceSqliteDatabase {
 
         Ok(())
     }
+
+    fn apply_encryption(&mut self, cipher: Option<SecretCipher>) -> Result<(), TransactionStorageError> {
+        let conn = self
+            .database_connection_pool
+            .clone()
+            .get()
+            .map_err(|_| TransactionStorageError::R2d2Error)?;
+        let mut encryption = acquire_write_lock!(self.encryption);
+        let new_encryption = encryption.with_cipher(cipher)?;
+
+        conn.transaction::<_, TransactionStorageError, _>(|| {
+            for o in outbound_transactions::table.load::<OutboundTransactionSql>(&conn)? {
+                let sender_protocol =
+                    encrypt_protocol(&decrypt_protocol(&o.sender_protocol, &encryption)?, &new_encryption)?;
+                diesel::update(outbound_transactions::table.filter(outbound_transactions::tx_id.eq(o.tx_id)))
+                    .set(outbound_transactions::sender_protocol.eq(sender_protocol))
+                    .execute(&conn)?;
+            }
+            for i in inbound_transactions::table.load::<InboundTransactionSql>(&conn)? {
+                let receiver_protocol =
+                    encrypt_protocol(&decrypt_protocol(&i.receiver_protocol, &encryption)?, &new_encryption)?;
+                diesel::update(inbound_transactions::table.filter(inbound_transactions::tx_id.eq(i.tx_id)))
+                    .set(inbound_transactions::receiver_protocol.eq(receiver_protocol))
+                    .execute(&conn)?;
+            }
+            Ok(())
+        })?;
+
+        *encryption = new_encryption;
+        Ok(())
+    }
+
+    fn unlock(&mut self, cipher: SecretCipher) -> Result<(), TransactionStorageError> {
+        let conn = self
+            .database_connection_pool
+            .clone()
+            .get()
+            .map_err(|_| TransactionStorageError::R2d2Error)?;
+        let mut encryption = acquire_write_lock!(self.encryption);
+
+        // Whether the backend is encrypted can only be told from its stored protocols, so a backend without any is
+        // unlocked with the cipher it is given. Otherwise the stored protocols can only be decrypted with the cipher
+        // they were encrypted with.
+        let unlocked = EncryptionState::Unlocked(cipher);
+        if let Some(p) = find_stored_protocol(&conn)? {
+            if !is_encrypted_protocol(&p) {
+                return Err(TransactionStorageError::EncryptionError(EncryptionError::NotEncrypted));
+            }
+            if decrypt_protocol(&p, &unlocked).is_err() {
+                return Err(TransactionStorageError::EncryptionError(
+                    EncryptionError::IncorrectPassphrase,
+                ));
+            }
+        }
+
+        *encryption = unlocked;
+        Ok(())
+    }
+
+    fn lock(&mut self) -> Result<(), TransactionStorageError> {
+        acquire_write_lock!(self.encryption).lock()?;
+        Ok(())
+    }
+}
+
+/// A stored sender or receiver protocol, if there are any, from which it can be told whether the stored protocols are
+/// encrypted
+fn find_stored_protocol(
+    conn: &PooledConnection<ConnectionManager<SqliteConnection>>,
+) -> Result<Option<String>, TransactionStorageError> {
+    if let Some(p) = outbound_transactions::table
+        .select(outbound_transactions::sender_protocol)
+        .first::<String>(conn)
+        .optional()?
+    {
+        return Ok(Some(p));
+    }
+    Ok(inbound_transactions::table
+        .select(inbound_transactions::receiver_protocol)
+        .first::<String>(conn)
+        .optional()?)
+}
+
+/// A protocol in plain form is stored as a JSON object, while an encrypted protocol is stored as hex
+fn is_encrypted_protocol(stored_protocol: &str) -> bool {
+    !stored_protocol.starts_with('{')
+}
+
+/// Convert a serialized sender or receiver protocol to the form in which it is stored
+fn encrypt_protocol(protocol: &str, encryption: &EncryptionState) -> Result<String, TransactionStorageError> {
+    match encryption {
+        EncryptionState::Unencrypted => Ok(protocol.to_string()),
+        _ => Ok(to_hex(&encryption.encrypt_secret(protocol.as_bytes())?)),
+    }
+}
+
+/// Convert a stored sender or receiver protocol back to its serialized plain form
+fn decrypt_protocol(stored_protocol: &str, encryption: &EncryptionState) -> Result<String, TransactionStorageError> {
+    match encryption {
+        EncryptionState::Unencrypted => Ok(stored_protocol.to_string()),
+        _ => {
+            let cipher_text = from_hex(stored_protocol).map_err(|_| TransactionStorageError::ConversionError)?;
+            String::from_utf8(encryption.decrypt_secret(&cipher_text)?)
+                .map_err(|_| TransactionStorageError::ConversionError)
+        },
+    }
 }
 
 #[derive(Clone, Debug, Queryable, Insertable, PartialEq)]
@@ -478,9 +743,22 @@ struct InboundTransactionSql {
     receiver_protocol: String,
     message: String,
     timestamp: NaiveDateTime,
+    cancelled: i32,
 }
 
 impl InboundTransactionSql {
+    /// Convert the receiver protocol to the form in which it is stored
+    pub fn encrypt(mut self, encryption: &EncryptionState) -> Result<Self, TransactionStorageError> {
+        self.receiver_protocol = encrypt_protocol(&self.receiver_protocol, encryption)?;
+        Ok(self)
+    }
+
+    /// Convert the stored receiver protocol back to its plain form
+    pub fn decrypt(mut self, encryption: &EncryptionState) -> Result<Self, TransactionStorageError> {
+        self.receiver_protocol = decrypt_protocol(&self.receiver_protocol, encryption)?;
+        Ok(self)
+    }
+
     pub fn commit(
         &self,
         conn: &PooledConnection<ConnectionManager<SqliteConnection>>,
@@ -492,10 +770,14 @@ impl InboundTransactionSql {
         Ok(())
     }
 
-    pub fn index(
+    pub fn index_by_cancelled(
+        cancelled: bool,
         conn: &PooledConnection<ConnectionManager<SqliteConnection>>,
-    ) -> Result<Vec<InboundTransactionSql>, TransactionStorageError> {
-        Ok(inbound_transactions::table.load::<InboundTransactionSql>(conn)?)
+    ) -> Result<Vec<InboundTransactionSql>, TransactionStorageError>
+    {
+        Ok(inbound_transactions::table
+            .filter(inbound_transactions::cancelled.eq(cancelled as i32))
+            .load::<InboundTransactionSql>(conn)?)
     }
 
     pub fn find(
@@ -523,6 +805,23 @@ impl InboundTransactionSql {
 
         Ok(())
     }
+
+    pub fn cancel(
+        &self,
+        conn: &PooledConnection<ConnectionManager<SqliteConnection>>,
+    ) -> Result<(), TransactionStorageError>
+    {
+        let num_updated =
+            diesel::update(inbound_transactions::table.filter(inbound_transactions::tx_id.eq(&self.tx_id)))
+                .set(inbound_transactions::cancelled.eq(1))
+                .execute(conn)?;
+
+        if num_updated == 0 {
+            return Err(TransactionStorageError::ValuesNotFound);
+        }
+
+        Ok(())
+    }
 }
 
 impl TryFrom<InboundTransaction> for InboundTransactionSql {
@@ -536,6 +835,7 @@ impl TryFrom<InboundTransaction> for InboundTransactionSql {
             receiver_protocol: serde_json::to_string(&i.receiver_protocol)?,
             message: i.message,
             timestamp: i.timestamp,
+            cancelled: i.cancelled as i32,
         })
     }
 }
@@ -552,6 +852,7 @@ impl TryFrom<InboundTransactionSql> for InboundTransaction {
             receiver_protocol: serde_json::from_str(&i.receiver_protocol)?,
             message: i.message,
             timestamp: i.timestamp,
+            cancelled: i.cancelled != 0,
         })
     }
 }
@@ -567,24 +868,103 @@ struct OutboundTransactionSql {
     sender_protocol: String,
     message: String,
     timestamp: NaiveDateTime,
+    recipients: String,
+    cancelled: i32,
 }
 
 impl OutboundTransactionSql {
+    /// Convert the sender protocol to the form in which it is stored
+    pub fn encrypt(mut self, encryption: &EncryptionState) -> Result<Self, TransactionStorageError> {
+        self.sender_protocol = encrypt_protocol(&self.sender_protocol, encryption)?;
+        Ok(self)
+    }
+
+    /// Convert the stored sender protocol back to its plain form
+    pub fn decrypt(mut self, encryption: &EncryptionState) -> Result<Self, TransactionStorageError> {
+        self.sender_protocol = decrypt_protocol(&self.sender_protocol, encryption)?;
+        Ok(self)
+    }
+
     pub fn commit(
         &self,
         conn: &PooledConnection<ConnectionManager<SqliteConnection>>,
     ) -> Result<(), TransactionStorageError>
     {
-        diesel::insert_into(outbound_transactions::table)
-            .values(self.clone())
-            .execute(conn)?;
+        conn.transaction::<_, TransactionStorageError, _>(|| {
+            diesel::insert_into(outbound_transactions::table)
+                .values(self.clone())
+                .execute(conn)?;
+            self.commit_recipients(conn)
+        })
+    }
+
+    /// Replace the stored transaction with this one, including its recipient index, in a single database transaction
+    pub fn update(
+        &self,
+        conn: &PooledConnection<ConnectionManager<SqliteConnection>>,
+    ) -> Result<(), TransactionStorageError>
+    {
+        conn.transaction::<_, TransactionStorageError, _>(|| {
+            let num_updated =
+                diesel::update(outbound_transactions::table.filter(outbound_transactions::tx_id.eq(&self.tx_id)))
+                    .set((
+                        outbound_transactions::destination_public_key.eq(self.destination_public_key.clone()),
+                        outbound_transactions::amount.eq(self.amount),
+                        outbound_transactions::fee.eq(self.fee),
+                        outbound_transactions::sender_protocol.eq(self.sender_protocol.clone()),
+                        outbound_transactions::message.eq(self.message.clone()),
+                        outbound_transactions::timestamp.eq(self.timestamp),
+                        outbound_transactions::recipients.eq(self.recipients.clone()),
+                        outbound_transactions::cancelled.eq(self.cancelled),
+                    ))
+                    .execute(conn)?;
+
+            if num_updated == 0 {
+                return Err(TransactionStorageError::ValuesNotFound);
+            }
+
+            self.delete_recipients(conn)?;
+            self.commit_recipients(conn)
+        })
+    }
+
+    fn commit_recipients(
+        &self,
+        conn: &PooledConnection<ConnectionManager<SqliteConnection>>,
+    ) -> Result<(), TransactionStorageError>
+    {
+        let recipients: Vec<TransactionRecipient> = serde_json::from_str(&self.recipients)?;
+        for recipient in recipients {
+            diesel::insert_into(outbound_transaction_recipients::table)
+                .values(OutboundTransactionRecipientSql {
+                    recipient_tx_id: recipient.tx_id as i64,
+                    tx_id: self.tx_id,
+                })
+                .execute(conn)?;
+        }
         Ok(())
     }
 
-    pub fn index(
+    fn delete_recipients(
+        &self,
+        conn: &PooledConnection<ConnectionManager<SqliteConnection>>,
+    ) -> Result<(), TransactionStorageError>
+    {
+        diesel::delete(
+            outbound_transaction_recipients::table.filter(outbound_transaction_recipients::tx_id.eq(&self.tx_id)),
+        )
+        .execute(conn)?;
+        Ok(())
+    }
+
+    pub fn index_by_cancelled(
+        cancelled: bool,
         conn: &PooledConnection<ConnectionManager<SqliteConnection>>,
-    ) -> Result<Vec<OutboundTransactionSql>, TransactionStorageError> {
-        Ok(outbound_transactions::table.load::<OutboundTransactionSql>(conn)?)
+    ) -> Result<Vec<OutboundTransactionSql>, TransactionStorageError>
+    {
+        Ok(outbound_transactions::table
+            .filter(outbound_transactions::cancelled.eq(cancelled as i32))
+            .load::<OutboundTransactionSql>(conn)?)
     }
 
     pub fn find(
@@ -597,16 +977,48 @@ impl OutboundTransactionSql {
             .first::<OutboundTransactionSql>(conn)?)
     }
 
+    /// Find the transaction that has a recipient with the provided `TxId`
+    pub fn find_by_recipient(
+        recipient_tx_id: &TxId,
+        conn: &PooledConnection<ConnectionManager<SqliteConnection>>,
+    ) -> Result<OutboundTransactionSql, TransactionStorageError>
+    {
+        let tx_id = outbound_transaction_recipients::table
+            .filter(outbound_transaction_recipients::recipient_tx_id.eq(*recipient_tx_id as i64))
+            .select(outbound_transaction_recipients::tx_id)
+            .first::<i64>(conn)?;
+        OutboundTransactionSql::find(&(tx_id as u64), conn)
+    }
+
     pub fn delete(
         &self,
         conn: &PooledConnection<ConnectionManager<SqliteConnection>>,
     ) -> Result<(), TransactionStorageError>
     {
-        let num_deleted =
-            diesel::delete(outbound_transactions::table.filter(outbound_transactions::tx_id.eq(&self.tx_id)))
+        conn.transaction::<_, TransactionStorageError, _>(|| {
+            let num_deleted =
+                diesel::delete(outbound_transactions::table.filter(outbound_transactions::tx_id.eq(&self.tx_id)))
+                    .execute(conn)?;
+
+            if num_deleted == 0 {
+                return Err(TransactionStorageError::ValuesNotFound);
+            }
+
+            self.delete_recipients(conn)
+        })
+    }
+
+    pub fn cancel(
+        &self,
+        conn: &PooledConnection<ConnectionManager<SqliteConnection>>,
+    ) -> Result<(), TransactionStorageError>
+    {
+        let num_updated =
+            diesel::update(outbound_transactions::table.filter(outbound_transactions::tx_id.eq(&self.tx_id)))
+                .set(outbound_transactions::cancelled.eq(1))
                 .execute(conn)?;
 
-        if num_deleted == 0 {
+        if num_updated == 0 {
             return Err(TransactionStorageError::ValuesNotFound);
         }
 
@@ -626,6 +1038,8 @@ impl TryFrom<OutboundTransaction> for OutboundTransactionSql {
             sender_protocol: serde_json::to_string(&i.sender_protocol)?,
             message: i.message,
             timestamp: i.timestamp,
+            recipients: serde_json::to_string(&i.recipients)?,
+            cancelled: i.cancelled as i32,
         })
     }
 }
@@ -643,10 +1057,20 @@ impl TryFrom<OutboundTransactionSql> for OutboundTransaction {
             sender_protocol: serde_json::from_str(&i.sender_protocol)?,
             message: i.message,
             timestamp: i.timestamp,
+            recipients: serde_json::from_str(&i.recipients)?,
+            cancelled: i.cancelled != 0,
         })
     }
 }
 
+/// An index row mapping the `TxId` a recipient uses for its part of an outbound transaction to that transaction
+#[derive(Clone, Debug, Queryable, Insertable, PartialEq)]
+#[table_name = "outbound_transaction_recipients"]
+struct OutboundTransactionRecipientSql {
+    recipient_tx_id: i64,
+    tx_id: i64,
+}
+
 #[derive(Clone, Debug, Queryable, Insertable, PartialEq)]
 #[table_name = "coinbase_transactions"]
 struct PendingCoinbaseTransactionSql {
@@ -725,6 +1149,90 @@ impl TryFrom<PendingCoinbaseTransactionSql> for PendingCoinbaseTransaction {
     }
 }
 
+#[derive(Clone, Debug, Queryable, Insertable, PartialEq)]
+#[table_name = "relayed_offline_transactions"]
+struct RelayedOfflineTransactionSql {
+    tx_id: i64,
+    offline_transaction: String,
+    reply: Option<String>,
+}
+
+impl RelayedOfflineTransactionSql {
+    /// Insert the relayed transaction, replacing the stored transaction with the same tx_id
+    pub fn commit(
+        &self,
+        conn: &PooledConnection<ConnectionManager<SqliteConnection>>,
+    ) -> Result<(), TransactionStorageError>
+    {
+        diesel::replace_into(relayed_offline_transactions::table)
+            .values(self.clone())
+            .execute(conn)?;
+        Ok(())
+    }
+
+    pub fn index(
+        conn: &PooledConnection<ConnectionManager<SqliteConnection>>,
+    ) -> Result<Vec<RelayedOfflineTransactionSql>, TransactionStorageError> {
+        Ok(relayed_offline_transactions::table.load::<RelayedOfflineTransactionSql>(conn)?)
+    }
+
+    pub fn find(
+        tx_id: &TxId,
+        conn: &PooledConnection<ConnectionManager<SqliteConnection>>,
+    ) -> Result<RelayedOfflineTransactionSql, TransactionStorageError>
+    {
+        Ok(relayed_offline_transactions::table
+            .filter(relayed_offline_transactions::tx_id.eq(*tx_id as i64))
+            .first::<RelayedOfflineTransactionSql>(conn)?)
+    }
+
+    pub fn delete(
+        &self,
+        conn: &PooledConnection<ConnectionManager<SqliteConnection>>,
+    ) -> Result<(), TransactionStorageError>
+    {
+        let num_deleted = diesel::delete(
+            relayed_offline_transactions::table.filter(relayed_offline_transactions::tx_id.eq(&self.tx_id)),
+        )
+        .execute(conn)?;
+
+        if num_deleted == 0 {
+            return Err(TransactionStorageError::ValuesNotFound);
+        }
+
+        Ok(())
+    }
+}
+
+impl TryFrom<RelayedOfflineTransaction> for RelayedOfflineTransactionSql {
+    type Error = TransactionStorageError;
+
+    fn try_from(r: RelayedOfflineTransaction) -> Result<Self, Self::Error> {
+        Ok(Self {
+            tx_id: r.offline_transaction.tx_id as i64,
+            offline_transaction: serde_json::to_string(&r.offline_transaction)?,
+            reply: match r.reply {
+                Some(reply) => Some(serde_json::to_string(&reply)?),
+                None => None,
+            },
+        })
+    }
+}
+
+impl TryFrom<RelayedOfflineTransactionSql> for RelayedOfflineTransaction {
+    type Error = TransactionStorageError;
+
+    fn try_from(r: RelayedOfflineTransactionSql) -> Result<Self, Self::Error> {
+        Ok(Self {
+            offline_transaction: serde_json::from_str(&r.offline_transaction)?,
+            reply: match r.reply {
+                Some(reply) => Some(serde_json::from_str(&reply)?),
+                None => None,
+            },
+        })
+    }
+}
+
 /// A structure to represent a Sql compatible version of the CompletedTransaction struct
 #[derive(Clone, Debug, Queryable, Insertable, PartialEq)]
 #[table_name = "completed_transactions"]
@@ -738,6 +1246,7 @@ struct CompletedTransactionSql {
     status: i32,
     message: String,
     timestamp: NaiveDateTime,
+    mined_height: Option<i64>,
 }
 
 impl CompletedTransactionSql {
@@ -758,6 +1267,31 @@ impl CompletedTransactionSql {
         Ok(completed_transactions::table.load::<CompletedTransactionSql>(conn)?)
     }
 
+    /// Load a page of the completed transactions in the time range of the query, using the timestamp index
+    pub fn index_by_query(
+        query: &TransactionHistoryQuery,
+        conn: &PooledConnection<ConnectionManager<SqliteConnection>>,
+    ) -> Result<Vec<CompletedTransactionSql>, TransactionStorageError>
+    {
+        let mut request = completed_transactions::table.into_boxed();
+        if let Some(from) = query.from {
+            request = request.filter(completed_transactions::timestamp.ge(from));
+        }
+        if let Some(to) = query.to {
+            request = request.filter(completed_transactions::timestamp.lt(to));
+        }
+        // SQLite only accepts an offset together with a limit, a negative limit being no limit
+        let limit = query.limit.map(|l| l as i64).unwrap_or(-1);
+        Ok(request
+            .order((
+                completed_transactions::timestamp.asc(),
+                completed_transactions::tx_id.asc(),
+            ))
+            .limit(limit)
+            .offset(query.offset as i64)
+            .load::<CompletedTransactionSql>(conn)?)
+    }
+
     pub fn find(
         tx_id: &TxId,
         conn: &PooledConnection<ConnectionManager<SqliteConnection>>,
@@ -784,7 +1318,6 @@ impl CompletedTransactionSql {
         Ok(())
     }
 
-    #[cfg(feature = "test_harness")]
     pub fn update(
         &self,
         updated_tx: UpdateCompletedTransaction,
@@ -820,6 +1353,7 @@ impl TryFrom<CompletedTransaction> for CompletedTransactionSql {
             status: c.status as i32,
             message: c.message,
             timestamp: c.timestamp,
+            mined_height: c.mined_height.map(|h| h as i64),
         })
     }
 }
@@ -845,6 +1379,7 @@ impl TryFrom<CompletedTransactionSql> for CompletedTransaction {
             },
             message: c.message,
             timestamp: c.timestamp,
+            mined_height: c.mined_height.map(|h| h as u64),
         })
     }
 }
@@ -853,6 +1388,7 @@ impl TryFrom<CompletedTransactionSql> for CompletedTransaction {
 pub struct UpdateCompletedTransaction {
     status: Option<TransactionStatus>,
     timestamp: Option<NaiveDateTime>,
+    mined_height: Option<u64>,
 }
 
 #[derive(AsChangeset)]
@@ -860,6 +1396,7 @@ pub struct UpdateCompletedTransaction {
 pub struct UpdateCompletedTransactionSql {
     status: Option<i32>,
     timestamp: Option<NaiveDateTime>,
+    mined_height: Option<i64>,
 }
 
 /// Map a Rust friendly UpdateCompletedTransaction to the Sql data type form
@@ -868,20 +1405,20 @@ impl From<UpdateCompletedTransaction> for UpdateCompletedTransactionSql {
         Self {
             status: u.status.map(|s| s as i32),
             timestamp: u.timestamp,
+            mined_height: u.mined_height.map(|h| h as i64),
         }
     }
 }
 
 #[cfg(test)]
 mod test {
-    #[cfg(feature = "test_harness")]
-    use crate::transaction_service::storage::sqlite_db::UpdateCompletedTransaction;
     use crate::transaction_service::storage::{
         database::{
             CompletedTransaction,
             InboundTransaction,
             OutboundTransaction,
             PendingCoinbaseTransaction,
+            TransactionRecipient,
             TransactionStatus,
         },
         sqlite_db::{
@@ -889,6 +1426,7 @@ mod test {
             InboundTransactionSql,
             OutboundTransactionSql,
             PendingCoinbaseTransactionSql,
+            UpdateCompletedTransaction,
         },
     };
     use chrono::Utc;
@@ -955,6 +1493,12 @@ mod test {
             sender_protocol: stp.clone(),
             message: "Yo!".to_string(),
             timestamp: Utc::now().naive_utc(),
+            recipients: vec![TransactionRecipient {
+                tx_id: 3u64,
+                public_key: PublicKey::from_secret_key(&PrivateKey::random(&mut rng)),
+                amount,
+            }],
+            cancelled: false,
         };
 
         let outbound_tx2 = OutboundTransactionSql::try_from(OutboundTransaction {
@@ -965,6 +1509,8 @@ mod test {
             sender_protocol: stp.clone(),
             message: "Hey!".to_string(),
             timestamp: Utc::now().naive_utc(),
+            recipients: Vec::new(),
+            cancelled: false,
         })
         .unwrap();
 
@@ -975,7 +1521,7 @@ mod test {
             .commit(&conn)
             .unwrap();
 
-        let outbound_txs = OutboundTransactionSql::index(&conn).unwrap();
+        let outbound_txs = OutboundTransactionSql::index_by_cancelled(false, &conn).unwrap();
         assert_eq!(outbound_txs.len(), 2);
 
         let returned_outbound_tx =
@@ -985,6 +1531,19 @@ mod test {
             OutboundTransactionSql::try_from(outbound_tx1.clone()).unwrap()
         );
 
+        outbound_tx2.cancel(&conn).unwrap();
+        assert_eq!(
+            OutboundTransactionSql::index_by_cancelled(false, &conn).unwrap().len(),
+            1
+        );
+        let cancelled_outbound_txs = OutboundTransactionSql::index_by_cancelled(true, &conn).unwrap();
+        assert_eq!(cancelled_outbound_txs.len(), 1);
+        assert!(
+            OutboundTransaction::try_from(cancelled_outbound_txs[0].clone())
+                .unwrap()
+                .cancelled
+        );
+
         let rtp = ReceiverTransactionProtocol::new(
             TransactionSenderMessage::Single(Box::new(stp.clone().build_single_round_message().unwrap())),
             PrivateKey::random(&mut rng),
@@ -1000,6 +1559,7 @@ mod test {
             receiver_protocol: rtp.clone(),
             message: "Yo!".to_string(),
             timestamp: Utc::now().naive_utc(),
+            cancelled: false,
         };
         let inbound_tx2 = InboundTransaction {
             tx_id: 3,
@@ -1008,6 +1568,7 @@ mod test {
             receiver_protocol: rtp.clone(),
             message: "Hey!".to_string(),
             timestamp: Utc::now().naive_utc(),
+            cancelled: false,
         };
 
         InboundTransactionSql::try_from(inbound_tx1.clone())
@@ -1019,7 +1580,7 @@ mod test {
             .commit(&conn)
             .unwrap();
 
-        let inbound_txs = InboundTransactionSql::index(&conn).unwrap();
+        let inbound_txs = InboundTransactionSql::index_by_cancelled(false, &conn).unwrap();
         assert_eq!(inbound_txs.len(), 2);
 
         let returned_inbound_tx =
@@ -1041,6 +1602,7 @@ mod test {
             status: TransactionStatus::Mined,
             message: "Yo!".to_string(),
             timestamp: Utc::now().naive_utc(),
+            mined_height: None,
         };
         let completed_tx2 = CompletedTransaction {
             tx_id: 3,
@@ -1052,6 +1614,7 @@ mod test {
             status: TransactionStatus::Broadcast,
             message: "Hey!".to_string(),
             timestamp: Utc::now().naive_utc(),
+            mined_height: None,
         };
 
         CompletedTransactionSql::try_from(completed_tx1.clone())
@@ -1132,18 +1695,18 @@ mod test {
             .unwrap();
         assert!(PendingCoinbaseTransactionSql::find(&44u64, &conn).is_err());
 
-        #[cfg(feature = "test_harness")]
         let updated_tx = CompletedTransactionSql::find(&completed_tx2.tx_id, &conn)
             .unwrap()
             .update(
                 UpdateCompletedTransaction {
                     status: Some(TransactionStatus::Mined),
                     timestamp: None,
+                    mined_height: Some(42),
                 },
                 &conn,
             )
             .unwrap();
-        #[cfg(feature = "test_harness")]
         assert_eq!(updated_tx.status, 2);
+        assert_eq!(updated_tx.mined_height, Some(42));
     }
 }
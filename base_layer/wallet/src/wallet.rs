@@ -21,6 +21,8 @@
 // USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 #[cfg(feature = "c_integration")]
 use crate::output_manager_service::TxId;
+#[cfg(feature = "trusted_dealer_shared_outputs")]
+use crate::shared_output_service::{handle::SharedOutputServiceHandle, SharedOutputServiceInitializer};
 #[cfg(feature = "c_integration")]
 use crate::transaction_service::callback_handler::CallbackHandler;
 #[cfg(feature = "c_integration")]
@@ -28,19 +30,26 @@ use crate::transaction_service::storage::database::TransactionDatabase;
 #[cfg(feature = "c_integration")]
 use crate::transaction_service::storage::database::{CompletedTransaction, InboundTransaction};
 use crate::{
+    atomic_swap_service::{handle::AtomicSwapServiceHandle, AtomicSwapServiceInitializer},
+    backup::WalletBackup,
     contacts_service::{handle::ContactsServiceHandle, storage::database::ContactsBackend, ContactsServiceInitializer},
-    error::WalletError,
+    encryption::{clear_passphrase, EncryptionSettings, PendingEncryption, SecretCipher},
+    error::{EncryptionError, WalletError},
     output_manager_service::{
+        error::{OutputManagerError, OutputManagerStorageError},
         handle::OutputManagerHandle,
-        storage::database::OutputManagerBackend,
+        storage::database::{OutputManagerBackend, WatchOnlyExport},
         OutputManagerServiceInitializer,
     },
+    recovery_service::{handle::RecoveryServiceHandle, RecoveryServiceInitializer},
     storage::database::{WalletBackend, WalletDatabase},
     transaction_service::{
+        error::{TransactionServiceError, TransactionStorageError},
         handle::TransactionServiceHandle,
-        storage::database::TransactionBackend,
+        storage::database::{TransactionBackend, TransactionServiceBackup},
         TransactionServiceInitializer,
     },
+    watch_only_service::{error::WatchOnlyServiceError, handle::WatchOnlyServiceHandle, WatchOnlyServiceInitializer},
 };
 use log::LevelFilter;
 use log4rs::{
@@ -49,12 +58,12 @@ use log4rs::{
     encode::pattern::PatternEncoder,
     Handle as LogHandle,
 };
-use std::{marker::PhantomData, sync::Arc};
+use std::{marker::PhantomData, path::Path, sync::Arc};
 use tari_comms::{
     builder::CommsNode,
     multiaddr::Multiaddr,
-    peer_manager::{NodeId, Peer, PeerFeatures, PeerFlags},
-    types::CommsPublicKey,
+    peer_manager::{NodeId, NodeIdentity, Peer, PeerFeatures, PeerFlags},
+    types::{CommsPublicKey, CommsSecretKey},
 };
 use tari_comms_dht::Dht;
 use tari_core::transactions::types::CryptoFactories;
@@ -74,10 +83,19 @@ pub struct WalletConfig {
     pub comms_config: CommsConfig,
     pub logging_path: Option<String>,
     pub factories: CryptoFactories,
+    /// The passphrase used to unlock the wallet's secrets if they have been encrypted. It is cleared from memory once
+    /// the wallet has been unlocked.
+    pub passphrase: Option<String>,
 }
 
 /// A structure containing the config and services that a Wallet application will require. This struct will start up all
 /// the services and provide the APIs that applications will use to interact with the services
+///
+/// The wallet's secrets, being the spending keys of its outputs, the master seed of its key manager, the sender and
+/// receiver protocols of its pending transactions and the secret key of its comms node identity, can be encrypted at
+/// rest with a passphrase. An encrypted wallet is unlocked when it is created with the passphrase in its config. While
+/// it is locked, operations that require its secrets, such as fetching the balance or sending and receiving
+/// transactions, will fail.
 pub struct Wallet<T, U, V, W>
 where
     T: WalletBackend,
@@ -91,6 +109,13 @@ where
     pub output_manager_service: OutputManagerHandle,
     pub transaction_service: TransactionServiceHandle,
     pub contacts_service: ContactsServiceHandle,
+    pub recovery_service: RecoveryServiceHandle,
+    pub watch_only_service: WatchOnlyServiceHandle,
+    /// Shared outputs are created by a trusted dealer rather than held in N-of-N custody, so the service is only
+    /// started when the `trusted_dealer_shared_outputs` feature is enabled
+    #[cfg(feature = "trusted_dealer_shared_outputs")]
+    pub shared_output_service: SharedOutputServiceHandle,
+    pub atomic_swap_service: AtomicSwapServiceHandle,
     pub db: WalletDatabase<T>,
     pub runtime: Runtime,
     pub log_handle: Option<LogHandle>,
@@ -109,11 +134,11 @@ where
     W: ContactsBackend + 'static,
 {
     pub fn new(
-        config: WalletConfig,
+        mut config: WalletConfig,
         mut runtime: Runtime,
         wallet_backend: T,
-        transaction_backend: U,
-        output_manager_backend: V,
+        mut transaction_backend: U,
+        mut output_manager_backend: V,
         contacts_backend: W,
     ) -> Result<Wallet<T, U, V, W>, WalletError>
     {
@@ -135,6 +160,35 @@ where
             log_handle = Some(log4rs::init_config(config)?);
         }
 
+        // The secrets of an encrypted wallet must be unlocked before the services that use them are started
+        let mut db = WalletDatabase::new(wallet_backend);
+        let passphrase = config.passphrase.take();
+        let cipher = unlock_secrets(
+            &mut db,
+            &mut transaction_backend,
+            &mut output_manager_backend,
+            passphrase.as_ref().map(String::as_str),
+        );
+        clear_passphrase(passphrase);
+        match cipher? {
+            Some(_) => {
+                // The node identity of an encrypted wallet is the one stored with its secrets
+                match db.get_comms_secret_key()? {
+                    Some(secret_key) => {
+                        let node_identity = &config.comms_config.node_identity;
+                        config.comms_config.node_identity = Arc::new(NodeIdentity::new(
+                            secret_key,
+                            node_identity.control_service_address(),
+                            *node_identity.features(),
+                        )?);
+                    },
+                    None => db.set_comms_secret_key(config.comms_config.node_identity.secret_key().clone())?,
+                }
+            },
+            // The secret key of an unencrypted wallet is kept by the application, never in plain text in the database
+            None => db.remove_comms_secret_key()?,
+        }
+
         #[cfg(feature = "test_harness")]
         let transaction_backend_handle = transaction_backend.clone();
 
@@ -145,7 +199,7 @@ where
 
         let (comms, dht) = initialize_comms(runtime.handle().clone(), config.comms_config.clone(), publisher)?;
 
-        let fut = StackBuilder::new(runtime.handle().clone(), comms.shutdown_signal())
+        let stack = StackBuilder::new(runtime.handle().clone(), comms.shutdown_signal())
             .add_initializer(CommsOutboundServiceInitializer::new(dht.outbound_requester()))
             .add_initializer(LivenessInitializer::new(
                 Default::default(),
@@ -153,17 +207,42 @@ where
                 dht.dht_requester(),
             ))
             .add_initializer(OutputManagerServiceInitializer::new(
+                Default::default(),
+                subscription_factory.clone(),
                 output_manager_backend,
                 factories.clone(),
             ))
             .add_initializer(TransactionServiceInitializer::new(
+                Default::default(),
                 subscription_factory.clone(),
                 transaction_backend,
                 comms.node_identity().clone(),
                 factories.clone(),
             ))
             .add_initializer(ContactsServiceInitializer::new(contacts_backend))
-            .finish();
+            .add_initializer(RecoveryServiceInitializer::new(
+                Default::default(),
+                subscription_factory.clone(),
+                factories.clone(),
+            ))
+            .add_initializer(WatchOnlyServiceInitializer::new(
+                Default::default(),
+                subscription_factory.clone(),
+                factories.clone(),
+            ))
+            .add_initializer(AtomicSwapServiceInitializer::new(
+                Default::default(),
+                subscription_factory.clone(),
+                factories.clone(),
+            ));
+        #[cfg(feature = "trusted_dealer_shared_outputs")]
+        let stack = stack.add_initializer(SharedOutputServiceInitializer::new(
+            Default::default(),
+            subscription_factory.clone(),
+            comms.node_identity().clone(),
+            factories.clone(),
+        ));
+        let fut = stack.finish();
 
         let handles = runtime.block_on(fut).expect("Service initialization failed");
 
@@ -179,6 +258,19 @@ where
         let contacts_handle = handles
             .get_handle::<ContactsServiceHandle>()
             .expect("Could not get Contacts Service Handle");
+        let recovery_handle = handles
+            .get_handle::<RecoveryServiceHandle>()
+            .expect("Could not get Recovery Service Handle");
+        let watch_only_handle = handles
+            .get_handle::<WatchOnlyServiceHandle>()
+            .expect("Could not get Watch-only Service Handle");
+        #[cfg(feature = "trusted_dealer_shared_outputs")]
+        let shared_output_handle = handles
+            .get_handle::<SharedOutputServiceHandle>()
+            .expect("Could not get Shared Output Service Handle");
+        let atomic_swap_handle = handles
+            .get_handle::<AtomicSwapServiceHandle>()
+            .expect("Could not get Atomic Swap Service Handle");
 
         Ok(Wallet {
             comms,
@@ -187,7 +279,12 @@ where
             output_manager_service: output_manager_handle,
             transaction_service: transaction_service_handle,
             contacts_service: contacts_handle,
-            db: WalletDatabase::new(wallet_backend),
+            recovery_service: recovery_handle,
+            watch_only_service: watch_only_handle,
+            #[cfg(feature = "trusted_dealer_shared_outputs")]
+            shared_output_service: shared_output_handle,
+            atomic_swap_service: atomic_swap_handle,
+            db,
             runtime,
             log_handle,
             #[cfg(feature = "test_harness")]
@@ -233,7 +330,11 @@ where
         Ok(())
     }
 
-    /// This function will add a base_node
+    /// This function will add a base_node and set it as the base node that the Transaction Service submits
+    /// transactions to and monitors them with, that the Output Manager Service validates its outputs against, that
+    /// the Recovery and Watch-only Services scan the blockchain of and that the Atomic Swap Service watches for swap
+    /// claims. A peer that has been saved before is not saved again, so the saved base node peers can be added again
+    /// whenever the wallet is started.
     pub fn add_base_node_peer(&mut self, public_key: CommsPublicKey, net_address: String) -> Result<(), WalletError> {
         let address = net_address.parse::<Multiaddr>()?;
         let peer = Peer::new(
@@ -246,8 +347,315 @@ where
 
         self.comms.peer_manager().add_peer(peer.clone())?;
 
-        self.db.save_peer(peer)?;
+        if !self.db.get_peers()?.iter().any(|p| p.public_key == public_key) {
+            self.db.save_peer(peer)?;
+        }
+
+        self.runtime
+            .block_on(self.transaction_service.set_base_node_public_key(public_key.clone()))?;
+        self.runtime
+            .block_on(self.output_manager_service.set_base_node_public_key(public_key.clone()))?;
+        self.runtime
+            .block_on(self.recovery_service.set_base_node_public_key(public_key.clone()))?;
+        self.runtime
+            .block_on(self.watch_only_service.set_base_node_public_key(public_key.clone()))?;
+        self.runtime
+            .block_on(self.atomic_swap_service.set_base_node_public_key(public_key))?;
+
+        Ok(())
+    }
+
+    /// Start recovering the wallet's funds from the provided seed words by scanning the blockchain of the base node,
+    /// starting at the optional birthday height. A base node peer must have been added first. The progress and the
+    /// outcome of the recovery are published on the Recovery Service event stream.
+    pub fn recover_from_seed_words(
+        &mut self,
+        seed_words: Vec<String>,
+        birthday_height: Option<u64>,
+    ) -> Result<(), WalletError>
+    {
+        self.runtime
+            .block_on(self.recovery_service.start_recovery(seed_words, birthday_height))?;
 
         Ok(())
     }
+
+    /// Export the view key and unspent outputs of this wallet so that its balance and history can be followed by a
+    /// watch-only wallet. The export contains no spending keys.
+    pub fn export_watch_only_data(&mut self) -> Result<WatchOnlyExport, WalletError> {
+        Ok(self
+            .runtime
+            .block_on(self.output_manager_service.export_watch_only_data())?)
+    }
+
+    /// Import the view key and outputs exported by another wallet, which makes this wallet a watch-only wallet that
+    /// refuses to send or receive funds. The blockchain of the base node is scanned for the other wallet's outputs
+    /// from the optional birthday height, and then again at every scan interval. The outputs that are found or spent
+    /// are published on the Watch-only Service event stream.
+    pub fn import_watch_only_data(
+        &mut self,
+        export: WatchOnlyExport,
+        birthday_height: Option<u64>,
+    ) -> Result<(), WalletError>
+    {
+        self.runtime.block_on(
+            self.output_manager_service
+                .import_watch_only_data(export, birthday_height),
+        )?;
+        match self.runtime.block_on(self.watch_only_service.scan()) {
+            // The blockchain is scanned once a base node peer has been added
+            Ok(()) | Err(WatchOnlyServiceError::NoBaseNodePublicKey) => Ok(()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Encrypt the wallet's secrets with the provided passphrase. The passphrase must then be provided to unlock the
+    /// wallet whenever it is created. The secret key of the node identity is stored with the encrypted secrets, and the
+    /// wallet is started with it from then on.
+    ///
+    /// If the change is interrupted it is completed when the wallet is next created with the new passphrase.
+    pub fn apply_encryption(&mut self, passphrase: &str) -> Result<(), WalletError> {
+        self.check_no_pending_encryption()?;
+        if self.db.get_encryption_settings()?.is_some() {
+            return Err(WalletError::EncryptionError(EncryptionError::AlreadyEncrypted));
+        }
+        let settings = EncryptionSettings::new(passphrase)?;
+        let cipher = settings.cipher(passphrase)?;
+
+        self.db.set_pending_encryption(PendingEncryption {
+            settings: Some(settings),
+            new_key: None,
+            current_key: None,
+        })?;
+        self.re_encrypt_secrets(Some(cipher))?;
+        self.db
+            .set_comms_secret_key(self.comms.node_identity().secret_key().clone())?;
+
+        Ok(())
+    }
+
+    /// Decrypt the wallet's secrets so that a passphrase is no longer required. The wallet must be unlocked. The secret
+    /// key of the node identity is removed from the database rather than being stored in plain text, so the
+    /// application must keep it from then on.
+    ///
+    /// If the change is interrupted it is completed when the wallet is next created with the passphrase.
+    pub fn remove_encryption(&mut self, passphrase: &str) -> Result<(), WalletError> {
+        self.check_no_pending_encryption()?;
+        self.verify_passphrase(passphrase)?;
+
+        self.db.set_pending_encryption(PendingEncryption {
+            settings: None,
+            new_key: None,
+            current_key: None,
+        })?;
+        self.re_encrypt_secrets(None)
+    }
+
+    /// Re-encrypt the wallet's secrets with a new passphrase. The wallet must be unlocked.
+    ///
+    /// If the change is interrupted it is completed when the wallet is next created with either passphrase.
+    pub fn change_passphrase(&mut self, passphrase: &str, new_passphrase: &str) -> Result<(), WalletError> {
+        self.check_no_pending_encryption()?;
+        let cipher = self.verify_passphrase(passphrase)?;
+        let settings = EncryptionSettings::new(new_passphrase)?;
+        let new_cipher = settings.cipher(new_passphrase)?;
+
+        self.db.set_pending_encryption(PendingEncryption {
+            settings: Some(settings),
+            new_key: Some(cipher.encrypt_key(&new_cipher)?),
+            current_key: Some(new_cipher.encrypt_key(&cipher)?),
+        })?;
+        self.re_encrypt_secrets(Some(new_cipher))
+    }
+
+    /// Re-encrypt the secrets of every service for the pending encryption change and then complete the change. The
+    /// change is stored before this is called, and the wallet's own secrets are re-encrypted last, in the same write
+    /// that replaces the encryption settings, so the stored settings never refer to a passphrase that has not been
+    /// applied to every secret.
+    fn re_encrypt_secrets(&mut self, cipher: Option<SecretCipher>) -> Result<(), WalletError> {
+        self.runtime
+            .block_on(self.output_manager_service.apply_encryption(cipher.clone()))?;
+        self.runtime
+            .block_on(self.transaction_service.apply_encryption(cipher.clone()))?;
+        self.db.apply_encryption(cipher)?;
+
+        Ok(())
+    }
+
+    /// A new encryption change cannot be started until an interrupted one has been completed
+    fn check_no_pending_encryption(&self) -> Result<(), WalletError> {
+        match self.db.get_pending_encryption()? {
+            Some(_) => Err(WalletError::EncryptionError(EncryptionError::ChangePending)),
+            None => Ok(()),
+        }
+    }
+
+    /// Make the wallet's encrypted secrets accessible again after the wallet has been locked
+    pub fn unlock(&mut self, passphrase: &str) -> Result<(), WalletError> {
+        let cipher = self.verify_passphrase(passphrase)?;
+
+        self.runtime
+            .block_on(self.output_manager_service.unlock(cipher.clone()))?;
+        self.runtime.block_on(self.transaction_service.unlock(cipher.clone()))?;
+        self.db.unlock(cipher)?;
+
+        Ok(())
+    }
+
+    /// Discard the cipher of the wallet's encrypted secrets so that they cannot be accessed until the wallet is
+    /// unlocked with its passphrase
+    pub fn lock(&mut self) -> Result<(), WalletError> {
+        self.runtime.block_on(self.output_manager_service.lock())?;
+        self.runtime.block_on(self.transaction_service.lock())?;
+        self.db.lock()?;
+
+        Ok(())
+    }
+
+    /// Write an encrypted backup of the wallet to the file at the given path. The backup holds the node identity, the
+    /// saved base node peers, the key manager state, every output, the transaction history and the contacts of the
+    /// wallet, and is encrypted with the provided passphrase, which need not be the wallet's own passphrase. The
+    /// wallet must be unlocked.
+    pub fn backup_to(&mut self, path: &Path, passphrase: &str) -> Result<(), WalletError> {
+        let output_manager = self.runtime.block_on(self.output_manager_service.get_backup())?;
+        let transactions = self.runtime.block_on(self.transaction_service.get_backup())?;
+        let contacts = self.runtime.block_on(self.contacts_service.get_contacts())?;
+        let backup = WalletBackup::new(
+            self.comms.node_identity().secret_key().clone(),
+            self.db.get_peers()?,
+            output_manager,
+            transactions,
+            contacts,
+        );
+
+        backup.write_to_file(path, passphrase)?;
+
+        Ok(())
+    }
+
+    /// Restore the backup in the file at the given path, which was encrypted with the provided passphrase, into this
+    /// wallet. The wallet must be unlocked and must not hold any outputs or transactions yet. The key manager state,
+    /// outputs, transactions, contacts and base node peers are restored immediately. The secret key of the backup's
+    /// node identity is returned, and it is used when the wallet is next started: an encrypted wallet saves it with its
+    /// other secrets, while the application of an unencrypted wallet must keep it.
+    pub fn restore_from(&mut self, path: &Path, passphrase: &str) -> Result<CommsSecretKey, WalletError> {
+        let backup = WalletBackup::read_from_file(path, passphrase)?;
+
+        let current_outputs = self.runtime.block_on(self.output_manager_service.get_backup())?;
+        let current_transactions = self.runtime.block_on(self.transaction_service.get_backup())?;
+        if !current_outputs.unspent_outputs.is_empty() ||
+            !current_outputs.spent_outputs.is_empty() ||
+            !current_outputs.invalid_outputs.is_empty() ||
+            !current_outputs.pending_transactions.is_empty() ||
+            current_transactions != TransactionServiceBackup::default()
+        {
+            return Err(WalletError::WalletNotEmpty);
+        }
+
+        self.runtime
+            .block_on(self.output_manager_service.restore_backup(backup.output_manager))?;
+        self.runtime
+            .block_on(self.transaction_service.restore_backup(backup.transactions))?;
+        for contact in backup.contacts {
+            self.runtime.block_on(self.contacts_service.save_contact(contact))?;
+        }
+        let saved_peers = self.db.get_peers()?;
+        for peer in backup.peers {
+            if &peer.public_key == self.comms.node_identity().public_key() {
+                continue;
+            }
+            self.comms.peer_manager().add_peer(peer.clone())?;
+            if !saved_peers.iter().any(|p| p.public_key == peer.public_key) {
+                self.db.save_peer(peer)?;
+            }
+        }
+        if self.db.get_encryption_settings()?.is_some() {
+            self.db.set_comms_secret_key(backup.comms_secret_key.clone())?;
+        }
+
+        Ok(backup.comms_secret_key)
+    }
+
+    /// Check that the provided passphrase is the one the wallet's secrets are encrypted with and derive its cipher
+    fn verify_passphrase(&self, passphrase: &str) -> Result<SecretCipher, WalletError> {
+        let settings = self
+            .db
+            .get_encryption_settings()?
+            .ok_or(WalletError::EncryptionError(EncryptionError::NotEncrypted))?;
+
+        Ok(settings.cipher(passphrase)?)
+    }
+}
+
+// Private macro that brings a storage backend whose secrets may or may not have been re-encrypted before an encryption
+// change was interrupted to the encryption of the change. A backend that the new cipher unlocks, or that is no longer
+// encrypted when encryption is being removed, had already been re-encrypted.
+macro_rules! complete_encryption_change {
+    ($backend:expr, $cipher:expr, $new_cipher:expr, $error:ident) => {{
+        match (&$cipher, &$new_cipher) {
+            (cipher, Some(new_cipher)) => match $backend.unlock(new_cipher.clone()) {
+                Err($error::EncryptionError(EncryptionError::NotEncrypted)) => {
+                    $backend.apply_encryption(Some(new_cipher.clone()))
+                },
+                Err($error::EncryptionError(EncryptionError::IncorrectPassphrase)) if cipher.is_some() => $backend
+                    .unlock(cipher.clone().expect("Checked above"))
+                    .and_then(|_| $backend.apply_encryption(Some(new_cipher.clone()))),
+                result => result,
+            },
+            (Some(cipher), None) => match $backend.unlock(cipher.clone()) {
+                Ok(()) => $backend.apply_encryption(None),
+                Err($error::EncryptionError(EncryptionError::NotEncrypted)) => Ok(()),
+                result => result,
+            },
+            (None, None) => Err($error::EncryptionError(EncryptionError::NotEncrypted)),
+        }
+    }};
+}
+
+/// Unlock the wallet's secrets with the passphrase, if they are encrypted, and return their cipher. An encryption
+/// change that was interrupted is completed first, after which the secrets are encrypted with the cipher of the change.
+fn unlock_secrets<T, U, V>(
+    db: &mut WalletDatabase<T>,
+    transaction_backend: &mut U,
+    output_manager_backend: &mut V,
+    passphrase: Option<&str>,
+) -> Result<Option<SecretCipher>, WalletError>
+where
+    T: WalletBackend,
+    U: TransactionBackend,
+    V: OutputManagerBackend,
+{
+    let settings = db.get_encryption_settings()?;
+    let pending = db.get_pending_encryption()?;
+    if settings.is_none() && pending.is_none() {
+        return Ok(None);
+    }
+    let passphrase = passphrase.ok_or(WalletError::EncryptionError(EncryptionError::WalletLocked))?;
+
+    let pending = match pending {
+        Some(pending) => pending,
+        None => {
+            let cipher = settings.expect("Checked above").cipher(passphrase)?;
+            output_manager_backend
+                .unlock(cipher.clone())
+                .map_err(OutputManagerError::OutputManagerStorageError)?;
+            transaction_backend
+                .unlock(cipher.clone())
+                .map_err(TransactionServiceError::TransactionStorageError)?;
+            db.unlock(cipher.clone())?;
+            return Ok(Some(cipher));
+        },
+    };
+
+    let (cipher, new_cipher) = pending.ciphers(settings.as_ref(), passphrase)?;
+    complete_encryption_change!(output_manager_backend, cipher, new_cipher, OutputManagerStorageError)
+        .map_err(OutputManagerError::OutputManagerStorageError)?;
+    complete_encryption_change!(transaction_backend, cipher, new_cipher, TransactionStorageError)
+        .map_err(TransactionServiceError::TransactionStorageError)?;
+    if let Some(cipher) = cipher {
+        db.unlock(cipher)?;
+    }
+    db.apply_encryption(new_cipher.clone())?;
+
+    Ok(new_cipher)
 }
@@ -0,0 +1,96 @@
+// Copyright 2020, The Tari Project
+//
+// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+// following conditions are met:
+//
+// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+// disclaimer.
+//
+// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+// following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+// 3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+// products derived from this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+// INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+// SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+// WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+// USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+use crate::watch_only_service::error::WatchOnlyServiceError;
+use futures::{stream::Fuse, StreamExt};
+use tari_broadcast_channel::Subscriber;
+use tari_comms::types::CommsPublicKey;
+use tari_core::transactions::tari_amount::MicroTari;
+use tari_service_framework::reply_channel::SenderService;
+use tower::Service;
+
+/// API Request enum
+#[derive(Debug)]
+pub enum WatchOnlyServiceRequest {
+    Scan,
+    SetBaseNodePublicKey(CommsPublicKey),
+}
+
+/// API Reply enum
+#[derive(Debug)]
+pub enum WatchOnlyServiceResponse {
+    ScanStarted,
+    BaseNodePublicKeySet,
+}
+
+/// Events that can be published on the Watch-only Service Event Stream
+#[derive(Clone, Debug, PartialEq)]
+pub enum WatchOnlyEvent {
+    /// An output with the given value was found in the block at the given height
+    OutputReceived((u64, MicroTari)),
+    /// A watched output with the given value was spent in the block at the given height
+    OutputSpent((u64, MicroTari)),
+    /// The blocks up to the given height have been scanned
+    Synced(u64),
+    Error(String),
+}
+
+#[derive(Clone)]
+pub struct WatchOnlyServiceHandle {
+    handle: SenderService<WatchOnlyServiceRequest, Result<WatchOnlyServiceResponse, WatchOnlyServiceError>>,
+    event_stream: Subscriber<WatchOnlyEvent>,
+}
+
+impl WatchOnlyServiceHandle {
+    pub fn new(
+        handle: SenderService<WatchOnlyServiceRequest, Result<WatchOnlyServiceResponse, WatchOnlyServiceError>>,
+        event_stream: Subscriber<WatchOnlyEvent>,
+    ) -> Self
+    {
+        WatchOnlyServiceHandle { handle, event_stream }
+    }
+
+    pub fn get_event_stream_fused(&self) -> Fuse<Subscriber<WatchOnlyEvent>> {
+        self.event_stream.clone().fuse()
+    }
+
+    /// Scan the blocks that have been added to the blockchain of the configured base node since the last scan,
+    /// without waiting for the scan interval to elapse. The outcome is published on the event stream.
+    pub async fn scan(&mut self) -> Result<(), WatchOnlyServiceError> {
+        match self.handle.call(WatchOnlyServiceRequest::Scan).await?? {
+            WatchOnlyServiceResponse::ScanStarted => Ok(()),
+            _ => Err(WatchOnlyServiceError::UnexpectedApiResponse),
+        }
+    }
+
+    /// Set the base node whose blockchain is followed
+    pub async fn set_base_node_public_key(&mut self, public_key: CommsPublicKey) -> Result<(), WatchOnlyServiceError> {
+        match self
+            .handle
+            .call(WatchOnlyServiceRequest::SetBaseNodePublicKey(public_key))
+            .await??
+        {
+            WatchOnlyServiceResponse::BaseNodePublicKeySet => Ok(()),
+            _ => Err(WatchOnlyServiceError::UnexpectedApiResponse),
+        }
+    }
+}
@@ -0,0 +1,453 @@
+// Copyright 2020, The Tari Project
+//
+// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+// following conditions are met:
+//
+// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+// disclaimer.
+//
+// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+// following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+// 3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+// products derived from this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+// INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+// SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+// WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+// USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+use crate::{
+    output_manager_service::{
+        handle::OutputManagerHandle,
+        storage::database::{ViewKey, WatchedOutput},
+    },
+    watch_only_service::{
+        config::WatchOnlyServiceConfig,
+        error::WatchOnlyServiceError,
+        handle::{WatchOnlyEvent, WatchOnlyServiceRequest, WatchOnlyServiceResponse},
+    },
+};
+use futures::{pin_mut, SinkExt, Stream, StreamExt};
+use log::*;
+use rand::RngCore;
+use std::{cmp, collections::HashMap, convert::TryFrom, time::Instant};
+use tari_broadcast_channel::Publisher;
+use tari_comms::types::CommsPublicKey;
+use tari_comms_dht::{
+    domain_message::OutboundDomainMessage,
+    outbound::{OutboundEncryption, OutboundMessageRequester, SendMessageResponse},
+};
+use tari_core::{
+    base_node::proto::base_node::{
+        base_node_service_request::Request as BaseNodeRequestProto,
+        base_node_service_response::Response as BaseNodeResponseProto,
+        BaseNodeServiceRequest,
+        BaseNodeServiceResponse,
+        BlockHeights,
+    },
+    proto::core::HistoricalBlock as HistoricalBlockProto,
+    transactions::{
+        aggregated_body::AggregateBody,
+        tari_amount::MicroTari,
+        types::{Commitment, CryptoFactories},
+    },
+};
+use tari_p2p::{domain_message::DomainMessage, tari_message::TariMessageType};
+use tari_service_framework::reply_channel;
+use tokio::time;
+
+const LOG_TARGET: &'static str = "base_layer::wallet::watch_only_service";
+
+/// This service follows the blockchain of the configured base node on behalf of a watch-only wallet. At every scan
+/// interval the blocks that were added since the last scan are requested from the base node and checked for outputs
+/// that belong to the watched wallet, and for inputs that spend them. The outputs that are found or spent are stored
+/// by the Output Manager Service, along with the height of the next block to be scanned.
+///
+/// Outputs that were imported from the watched wallet are recognised by their commitment. New outputs are found by
+/// rewinding the value of their range proofs with the view key, which cannot reveal their spending keys.
+pub struct WatchOnlyService<BNResponseStream>
+where BNResponseStream: Stream<Item = DomainMessage<BaseNodeServiceResponse>>
+{
+    config: WatchOnlyServiceConfig,
+    output_manager_service: OutputManagerHandle,
+    outbound_message_service: OutboundMessageRequester,
+    request_stream: Option<
+        reply_channel::Receiver<WatchOnlyServiceRequest, Result<WatchOnlyServiceResponse, WatchOnlyServiceError>>,
+    >,
+    base_node_response_stream: Option<BNResponseStream>,
+    base_node_public_key: Option<CommsPublicKey>,
+    scan: Option<ScanState>,
+    event_publisher: Publisher<WatchOnlyEvent>,
+    factories: CryptoFactories,
+}
+
+impl<BNResponseStream> WatchOnlyService<BNResponseStream>
+where BNResponseStream: Stream<Item = DomainMessage<BaseNodeServiceResponse>>
+{
+    pub fn new(
+        config: WatchOnlyServiceConfig,
+        output_manager_service: OutputManagerHandle,
+        outbound_message_service: OutboundMessageRequester,
+        request_stream: reply_channel::Receiver<
+            WatchOnlyServiceRequest,
+            Result<WatchOnlyServiceResponse, WatchOnlyServiceError>,
+        >,
+        base_node_response_stream: BNResponseStream,
+        event_publisher: Publisher<WatchOnlyEvent>,
+        factories: CryptoFactories,
+    ) -> Self
+    {
+        WatchOnlyService {
+            config,
+            output_manager_service,
+            outbound_message_service,
+            request_stream: Some(request_stream),
+            base_node_response_stream: Some(base_node_response_stream),
+            base_node_public_key: None,
+            scan: None,
+            event_publisher,
+            factories,
+        }
+    }
+
+    pub async fn start(mut self) -> Result<(), WatchOnlyServiceError> {
+        let request_stream = self
+            .request_stream
+            .take()
+            .expect("Watch-only Service initialized without request_stream")
+            .fuse();
+        pin_mut!(request_stream);
+        let base_node_response_stream = self
+            .base_node_response_stream
+            .take()
+            .expect("Watch-only Service initialized without base_node_response_stream")
+            .fuse();
+        pin_mut!(base_node_response_stream);
+
+        let request_timeout = self.config.request_timeout;
+        let mut retry_tick = time::interval_at((Instant::now() + request_timeout).into(), request_timeout).fuse();
+        let scan_interval = self.config.scan_interval;
+        let mut scan_tick = time::interval_at((Instant::now() + scan_interval).into(), scan_interval).fuse();
+
+        info!(target: LOG_TARGET, "Watch-only Service started");
+        loop {
+            futures::select! {
+                request_context = request_stream.select_next_some() => {
+                    let (request, reply_tx) = request_context.split();
+                    let _ = reply_tx.send(self.handle_request(request).await.or_else(|resp| {
+                        error!(target: LOG_TARGET, "Error handling request: {:?}", resp);
+                        Err(resp)
+                    })).or_else(|resp| {
+                        error!(target: LOG_TARGET, "Failed to send reply");
+                        Err(resp)
+                    });
+                },
+                // Incoming messages from the Comms layer
+                msg = base_node_response_stream.select_next_some() => {
+                    let result = self.handle_base_node_response(msg.inner).await.or_else(|err| {
+                        error!(target: LOG_TARGET, "Failed to handle incoming Base Node response: {:?}", err);
+                        Err(err)
+                    });
+
+                    if let Err(e) = result {
+                        self.abort_scan(e).await;
+                    }
+                },
+                _ = retry_tick.select_next_some() => {
+                    let result = self.resend_pending_request().await.or_else(|err| {
+                        error!(target: LOG_TARGET, "Failed to resend request to the base node: {:?}", err);
+                        Err(err)
+                    });
+
+                    if let Err(e) = result {
+                        self.abort_scan(e).await;
+                    }
+                },
+                _ = scan_tick.select_next_some() => {
+                    match self.start_scan().await {
+                        // Wallets that are not watch-only, or that have no base node yet, are not scanned for
+                        Ok(()) | Err(WatchOnlyServiceError::NotWatchOnly) |
+                        Err(WatchOnlyServiceError::NoBaseNodePublicKey) => (),
+                        Err(e) => {
+                            error!(target: LOG_TARGET, "Failed to start scanning the blockchain: {:?}", e);
+                            self.abort_scan(e).await;
+                        },
+                    }
+                },
+                complete => {
+                    info!(target: LOG_TARGET, "Watch-only service shutting down");
+                    break;
+                }
+            }
+        }
+        info!(target: LOG_TARGET, "Watch-only Service ended");
+        Ok(())
+    }
+
+    /// This handler is called when the Service executor loops receives an API request
+    async fn handle_request(
+        &mut self,
+        request: WatchOnlyServiceRequest,
+    ) -> Result<WatchOnlyServiceResponse, WatchOnlyServiceError>
+    {
+        match request {
+            WatchOnlyServiceRequest::Scan => self.start_scan().await.map(|_| WatchOnlyServiceResponse::ScanStarted),
+            WatchOnlyServiceRequest::SetBaseNodePublicKey(public_key) => {
+                self.base_node_public_key = Some(public_key);
+                Ok(WatchOnlyServiceResponse::BaseNodePublicKeySet)
+            },
+        }
+    }
+
+    /// Load the view key and watched outputs from the Output Manager Service and start scanning the blockchain by
+    /// asking the base node for the height of its chain. Nothing is done if a scan is already in progress.
+    async fn start_scan(&mut self) -> Result<(), WatchOnlyServiceError> {
+        if self.scan.is_some() {
+            return Ok(());
+        }
+        if self.base_node_public_key.is_none() {
+            return Err(WatchOnlyServiceError::NoBaseNodePublicKey);
+        }
+        let (view_key, next_height) = match self.output_manager_service.get_watch_only_state().await? {
+            Some(state) => (state.view_key, state.next_height),
+            None => return Err(WatchOnlyServiceError::NotWatchOnly),
+        };
+        let watched_outputs = self
+            .output_manager_service
+            .get_watched_outputs()
+            .await?
+            .into_iter()
+            .map(|o| (o.commitment.clone(), o))
+            .collect();
+
+        self.scan = Some(ScanState {
+            view_key,
+            watched_outputs,
+            next_height,
+            chain_height: None,
+            pending_request: None,
+        });
+
+        debug!(
+            target: LOG_TARGET,
+            "Scanning the blockchain from block #{}", next_height
+        );
+        let result = self.send_request(BaseNodeRequestProto::GetChainMetadata(true)).await;
+        if result.is_err() {
+            self.scan = None;
+        }
+        result
+    }
+
+    /// Handle the base node's responses to the chain metadata and block requests made during a scan
+    async fn handle_base_node_response(
+        &mut self,
+        response: BaseNodeServiceResponse,
+    ) -> Result<(), WatchOnlyServiceError>
+    {
+        let BaseNodeServiceResponse { request_key, response } = response;
+        let scan = match self.scan.as_mut() {
+            None => return Ok(()),
+            Some(s) => s,
+        };
+        match scan.pending_request {
+            Some(ref request) if request.request_key == request_key => (),
+            // Responses to queries made by other wallet services are ignored
+            _ => return Ok(()),
+        }
+        scan.pending_request = None;
+
+        match response {
+            Some(BaseNodeResponseProto::ChainMetadata(metadata)) => {
+                scan.chain_height = metadata.height_of_longest_chain;
+                if let Some(height) = scan.chain_height {
+                    self.output_manager_service.set_chain_height(height).await?;
+                }
+            },
+            Some(BaseNodeResponseProto::HistoricalBlocks(historical_blocks)) => {
+                let mut events = Vec::new();
+                let mut updated = HashMap::new();
+                for block in historical_blocks.blocks {
+                    for (event, output) in scan.scan_block(block, &self.factories)? {
+                        events.push(event);
+                        updated.insert(output.commitment.clone(), output);
+                    }
+                }
+                let next_height = scan.next_height;
+
+                self.output_manager_service
+                    .update_watched_outputs(updated.drain().map(|(_, o)| o).collect(), next_height)
+                    .await?;
+                for event in events {
+                    self.publish_event(event).await?;
+                }
+            },
+            _ => return Err(WatchOnlyServiceError::UnexpectedBaseNodeResponse),
+        }
+
+        self.request_next_blocks().await
+    }
+
+    /// Request the next range of blocks from the base node, or complete the scan once the chain tip is reached
+    async fn request_next_blocks(&mut self) -> Result<(), WatchOnlyServiceError> {
+        let blocks_per_request = cmp::max(self.config.blocks_per_request, 1);
+        let heights = match self.scan.as_mut() {
+            None => return Ok(()),
+            Some(scan) => match scan.chain_height {
+                Some(chain_height) if scan.next_height <= chain_height => {
+                    let last_height = cmp::min(scan.next_height + blocks_per_request - 1, chain_height);
+                    let heights = (scan.next_height..=last_height).collect::<Vec<_>>();
+                    scan.next_height = last_height + 1;
+                    heights
+                },
+                _ => {
+                    let synced_height = scan.next_height.saturating_sub(1);
+                    self.scan = None;
+                    return self.publish_event(WatchOnlyEvent::Synced(synced_height)).await;
+                },
+            },
+        };
+
+        self.send_request(BaseNodeRequestProto::FetchBlocks(BlockHeights { heights }))
+            .await
+    }
+
+    /// Stop the scan that is in progress and publish the reason on the event stream. The blocks that were not scanned
+    /// are scanned again at the next scan interval.
+    async fn abort_scan(&mut self, error: WatchOnlyServiceError) {
+        if self.scan.take().is_some() {
+            let _ = self
+                .publish_event(WatchOnlyEvent::Error(format!("Blockchain scan failed: {:?}", error)))
+                .await;
+        }
+    }
+
+    /// Send the request to the base node again if it has not been answered by the time the retry interval elapses
+    async fn resend_pending_request(&mut self) -> Result<(), WatchOnlyServiceError> {
+        let request = match self.scan.as_ref().and_then(|s| s.pending_request.clone()) {
+            None => return Ok(()),
+            Some(request) => request,
+        };
+        warn!(
+            target: LOG_TARGET,
+            "Base node did not respond to request (key: {}), sending it again", request.request_key
+        );
+        self.send_base_node_request(request).await
+    }
+
+    async fn send_request(&mut self, request: BaseNodeRequestProto) -> Result<(), WatchOnlyServiceError> {
+        let request = BaseNodeServiceRequest {
+            request_key: rand::OsRng::new().unwrap().next_u64(),
+            request: Some(request),
+        };
+        if let Some(scan) = self.scan.as_mut() {
+            scan.pending_request = Some(request.clone());
+        }
+        self.send_base_node_request(request).await
+    }
+
+    async fn send_base_node_request(&mut self, request: BaseNodeServiceRequest) -> Result<(), WatchOnlyServiceError> {
+        let base_node_public_key = self
+            .base_node_public_key
+            .clone()
+            .ok_or(WatchOnlyServiceError::NoBaseNodePublicKey)?;
+        if let SendMessageResponse::Failed = self
+            .outbound_message_service
+            .send_direct(
+                base_node_public_key,
+                OutboundEncryption::EncryptForPeer,
+                OutboundDomainMessage::new(TariMessageType::BaseNodeRequest, request),
+            )
+            .await
+            .map_err(|_| WatchOnlyServiceError::OutboundSendFailure)?
+        {
+            return Err(WatchOnlyServiceError::OutboundSendFailure);
+        }
+
+        Ok(())
+    }
+
+    async fn publish_event(&mut self, event: WatchOnlyEvent) -> Result<(), WatchOnlyServiceError> {
+        self.event_publisher
+            .send(event)
+            .await
+            .map_err(|_| WatchOnlyServiceError::EventStreamError)
+    }
+}
+
+/// The progress of a scan that is in progress
+struct ScanState {
+    view_key: Option<ViewKey>,
+    watched_outputs: HashMap<Commitment, WatchedOutput>,
+    next_height: u64,
+    chain_height: Option<u64>,
+    pending_request: Option<BaseNodeServiceRequest>,
+}
+
+impl ScanState {
+    /// Find the outputs of the watched wallet that were created or spent in the block. Returns the event to publish
+    /// and the updated output for each of them.
+    fn scan_block(
+        &mut self,
+        historical_block: HistoricalBlockProto,
+        factories: &CryptoFactories,
+    ) -> Result<Vec<(WatchOnlyEvent, WatchedOutput)>, WatchOnlyServiceError>
+    {
+        let block = historical_block
+            .block
+            .ok_or_else(|| WatchOnlyServiceError::InvalidMessageError("Historical block has no block".to_string()))?;
+        let height = block
+            .header
+            .map(|h| h.height)
+            .ok_or_else(|| WatchOnlyServiceError::InvalidMessageError("Block has no header".to_string()))?;
+        let body = block
+            .body
+            .ok_or_else(|| WatchOnlyServiceError::InvalidMessageError("Block has no body".to_string()))
+            .and_then(|b| AggregateBody::try_from(b).map_err(WatchOnlyServiceError::InvalidMessageError))?;
+
+        let mut found = Vec::new();
+        for output in body.outputs() {
+            if let Some(watched) = self.watched_outputs.get_mut(&output.commitment) {
+                if watched.mined_height.is_none() {
+                    watched.mined_height = Some(height);
+                    found.push((WatchOnlyEvent::OutputReceived((height, watched.value)), watched.clone()));
+                }
+                continue;
+            }
+            let rewind_result = self.view_key.as_ref().and_then(|view_key| {
+                output
+                    .rewind_range_proof_value_only(
+                        &factories.range_proof,
+                        &view_key.rewind_public_key,
+                        &view_key.rewind_blinding_public_key,
+                    )
+                    .ok()
+            });
+            if let Some(rewind_result) = rewind_result {
+                let watched = WatchedOutput {
+                    commitment: output.commitment.clone(),
+                    value: MicroTari::from(rewind_result.committed_value),
+                    features: output.features.clone(),
+                    mined_height: Some(height),
+                    spent_height: None,
+                };
+                self.watched_outputs.insert(watched.commitment.clone(), watched.clone());
+                found.push((WatchOnlyEvent::OutputReceived((height, watched.value)), watched));
+            }
+        }
+        for input in body.inputs() {
+            if let Some(watched) = self.watched_outputs.get_mut(&input.commitment) {
+                if watched.spent_height.is_none() {
+                    watched.spent_height = Some(height);
+                    found.push((WatchOnlyEvent::OutputSpent((height, watched.value)), watched.clone()));
+                }
+            }
+        }
+
+        Ok(found)
+    }
+}
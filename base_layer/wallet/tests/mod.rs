@@ -25,6 +25,7 @@
 #[macro_use]
 extern crate lazy_static;
 pub mod output_manager_service;
+pub mod recovery_service;
 pub mod support;
 // pub mod text_message_service;
 pub mod contacts_service;
@@ -20,29 +20,59 @@
 // WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
 // USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
-use crate::support::utils::{make_input, random_string, TestParams};
+use crate::support::{
+    comms_and_services::create_dummy_message,
+    utils::{make_input, random_string, TestParams},
+};
+use futures::{
+    channel::{mpsc, mpsc::Sender},
+    SinkExt,
+    StreamExt,
+};
+use prost::Message;
 use rand::RngCore;
 use std::{thread, time::Duration};
-use tari_core::transactions::{
-    fee::Fee,
-    tari_amount::MicroTari,
-    transaction::{KernelFeatures, OutputFeatures, TransactionOutput, UnblindedOutput},
-    transaction_protocol::single_receiver::SingleReceiverTransactionProtocol,
-    types::{CryptoFactories, PrivateKey, RangeProof},
+use tari_broadcast_channel::bounded;
+use tari_comms::message::EnvelopeBody;
+use tari_comms_dht::{
+    domain_message::MessageHeader,
+    outbound::mock::{create_outbound_service_mock, OutboundServiceMockState},
+};
+use tari_core::{
+    base_node::proto::base_node::{
+        base_node_service_request::Request as BaseNodeRequestProto,
+        base_node_service_response::Response as BaseNodeResponseProto,
+        BaseNodeServiceRequest,
+        BaseNodeServiceResponse,
+        TransactionOutputs,
+    },
+    transactions::{
+        fee::Fee,
+        tari_amount::MicroTari,
+        transaction::{KernelFeatures, OutputFeatures, TransactionOutput, UnblindedOutput},
+        transaction_protocol::single_receiver::SingleReceiverTransactionProtocol,
+        types::{CryptoFactories, PrivateKey, PublicKey, RangeProof},
+    },
+};
+use tari_crypto::{
+    commitment::HomomorphicCommitmentFactory,
+    keys::{PublicKey as PublicKeyTrait, SecretKey},
+    range_proof::RangeProofService,
 };
-use tari_crypto::{commitment::HomomorphicCommitmentFactory, keys::SecretKey, range_proof::RangeProofService};
-use tari_service_framework::StackBuilder;
-use tari_shutdown::Shutdown;
+use tari_p2p::{domain_message::DomainMessage, tari_message::TariMessageType};
+use tari_service_framework::reply_channel;
+use tari_test_utils::collect_stream;
 use tari_utilities::ByteArray;
 use tari_wallet::output_manager_service::{
+    config::OutputManagerServiceConfig,
     error::{OutputManagerError, OutputManagerStorageError},
-    handle::OutputManagerHandle,
+    handle::{OutputManagerEvent, OutputManagerHandle},
+    service::{OutputManagerService, UTXOSelectionStrategy},
     storage::{
-        database::{DbKey, DbValue, OutputManagerBackend},
+        database::{DbKey, DbValue, OutputManagerBackend, OutputManagerDatabase},
         memory_db::OutputManagerMemoryDatabase,
         sqlite_db::OutputManagerSqliteDatabase,
     },
-    OutputManagerServiceInitializer,
 };
 use tempdir::TempDir;
 use tokio::runtime::Runtime;
@@ -50,19 +80,40 @@ use tokio::runtime::Runtime;
 pub fn setup_output_manager_service<T: OutputManagerBackend + 'static>(
     runtime: &mut Runtime,
     backend: T,
-) -> (OutputManagerHandle, Shutdown)
+) -> (
+    OutputManagerHandle,
+    OutboundServiceMockState,
+    Sender<DomainMessage<BaseNodeServiceResponse>>,
+)
 {
-    let shutdown = Shutdown::new();
     let factories = CryptoFactories::default();
-    let fut = StackBuilder::new(runtime.handle().clone(), shutdown.to_signal())
-        .add_initializer(OutputManagerServiceInitializer::new(backend, factories))
-        .finish();
-
-    let handles = runtime.block_on(fut).expect("Service initialization failed");
-
-    let oms_api = handles.get_handle::<OutputManagerHandle>().unwrap();
-
-    (oms_api, shutdown)
+    let (oms_request_sender, oms_request_receiver) = reply_channel::unbounded();
+    let (oms_event_publisher, oms_event_subscriber) = bounded(100);
+    let (base_node_response_sender, base_node_response_receiver) = mpsc::channel(20);
+
+    let (outbound_message_requester, mock_outbound_service) = create_outbound_service_mock(20);
+    let outbound_mock_state = mock_outbound_service.get_state();
+    runtime.spawn(mock_outbound_service.run());
+
+    let output_manager_service = OutputManagerService::new(
+        OutputManagerServiceConfig::default(),
+        outbound_message_requester,
+        oms_request_receiver,
+        base_node_response_receiver,
+        OutputManagerDatabase::new(backend),
+        oms_event_publisher,
+        factories,
+    )
+    .unwrap();
+    let output_manager_service_handle = OutputManagerHandle::new(oms_request_sender, oms_event_subscriber);
+
+    runtime.spawn(async move { output_manager_service.start().await.unwrap() });
+
+    (
+        output_manager_service_handle,
+        outbound_mock_state,
+        base_node_response_sender,
+    )
 }
 
 fn sending_transaction_and_confirmation<T: Clone + OutputManagerBackend + 'static>(backend: T) {
@@ -71,7 +122,7 @@ fn sending_transaction_and_confirmation<T: Clone + OutputManagerBackend + 'stati
 
     let mut runtime = Runtime::new().unwrap();
 
-    let (mut oms, _shutdown) = setup_output_manager_service(&mut runtime, backend.clone());
+    let (mut oms, _, _) = setup_output_manager_service(&mut runtime, backend.clone());
 
     let (_ti, uo) = make_input(
         &mut rng.clone(),
@@ -95,9 +146,13 @@ fn sending_transaction_and_confirmation<T: Clone + OutputManagerBackend + 'stati
         runtime.block_on(oms.add_output(uo)).unwrap();
     }
 
+    let fee_estimate = runtime
+        .block_on(oms.transaction_fee(MicroTari::from(1000), MicroTari::from(20)))
+        .unwrap();
     let mut stp = runtime
         .block_on(oms.prepare_transaction_to_send(MicroTari::from(1000), MicroTari::from(20), None, "".to_string()))
         .unwrap();
+    assert_eq!(stp.get_fee_amount().unwrap(), fee_estimate);
 
     let sender_tx_id = stp.get_tx_id().unwrap();
     let mut num_change = 0;
@@ -162,13 +217,71 @@ fn sending_transaction_and_confirmation_sqlite_db() {
     sending_transaction_and_confirmation(OutputManagerSqliteDatabase::new(db_path).unwrap());
 }
 
+#[test]
+fn sending_multi_recipient_transaction_and_confirmation() {
+    let mut rng = rand::OsRng::new().unwrap();
+    let factories = CryptoFactories::default();
+
+    let mut runtime = Runtime::new().unwrap();
+
+    let (mut oms, _, _) = setup_output_manager_service(&mut runtime, OutputManagerMemoryDatabase::new());
+    for _i in 0..10 {
+        let (_ti, uo) = make_input(&mut rng.clone(), MicroTari::from(1000), &factories.commitment);
+        runtime.block_on(oms.add_output(uo)).unwrap();
+    }
+
+    assert_eq!(
+        runtime.block_on(oms.prepare_multi_recipient_transaction_to_send(
+            Vec::new(),
+            MicroTari::from(20),
+            None,
+            "".to_string()
+        )),
+        Err(OutputManagerError::NoRecipients)
+    );
+
+    let mut stp = runtime
+        .block_on(oms.prepare_multi_recipient_transaction_to_send(
+            vec![MicroTari::from(1500), MicroTari::from(2500)],
+            MicroTari::from(20),
+            None,
+            "".to_string(),
+        ))
+        .unwrap();
+    assert!(stp.is_multi_recipient_messages_ready());
+    assert_eq!(stp.get_total_amount().unwrap(), MicroTari::from(4000));
+    let sender_tx_id = stp.get_tx_id().unwrap();
+    assert_eq!(runtime.block_on(oms.get_pending_transactions()).unwrap().len(), 1);
+
+    let msgs = stp.build_multi_recipient_messages().unwrap();
+    for msg in msgs.iter() {
+        let b = TestParams::new(&mut rng);
+        let recv_info =
+            SingleReceiverTransactionProtocol::create(msg, b.nonce, b.spend_key, OutputFeatures::default(), &factories)
+                .unwrap();
+        stp.add_multi_recipient_info(recv_info, &factories.range_proof).unwrap();
+    }
+    stp.finalize(KernelFeatures::empty(), &factories).unwrap();
+    let tx = stp.get_transaction().unwrap();
+    assert_eq!(tx.body.kernels().len(), 2);
+
+    runtime
+        .block_on(oms.confirm_sent_transaction(sender_tx_id, tx.body.inputs().clone(), tx.body.outputs().clone()))
+        .unwrap();
+    assert_eq!(runtime.block_on(oms.get_pending_transactions()).unwrap().len(), 0);
+    assert_eq!(
+        runtime.block_on(oms.get_spent_outputs()).unwrap().len(),
+        tx.body.inputs().len()
+    );
+}
+
 fn send_not_enough_funds<T: OutputManagerBackend + 'static>(backend: T) {
     let mut rng = rand::OsRng::new().unwrap();
     let factories = CryptoFactories::default();
 
     let mut runtime = Runtime::new().unwrap();
 
-    let (mut oms, _shutdown) = setup_output_manager_service(&mut runtime, backend);
+    let (mut oms, _, _) = setup_output_manager_service(&mut runtime, backend);
     let num_outputs = 20;
     for _i in 0..num_outputs {
         let (_ti, uo) = make_input(
@@ -210,7 +323,7 @@ fn send_no_change<T: OutputManagerBackend + 'static>(backend: T) {
 
     let mut runtime = Runtime::new().unwrap();
 
-    let (mut oms, _shutdown) = setup_output_manager_service(&mut runtime, backend);
+    let (mut oms, _, _) = setup_output_manager_service(&mut runtime, backend);
 
     let fee_per_gram = MicroTari::from(20);
     let fee_without_change = Fee::calculate(fee_per_gram, 2, 1);
@@ -284,7 +397,7 @@ fn send_not_enough_for_change<T: OutputManagerBackend + 'static>(backend: T) {
 
     let mut runtime = Runtime::new().unwrap();
 
-    let (mut oms, _shutdown) = setup_output_manager_service(&mut runtime, backend);
+    let (mut oms, _, _) = setup_output_manager_service(&mut runtime, backend);
 
     let fee_per_gram = MicroTari::from(20);
     let fee_without_change = Fee::calculate(fee_per_gram, 2, 1);
@@ -324,12 +437,290 @@ fn send_not_enough_for_change_sqlite_db() {
     send_not_enough_for_change(OutputManagerSqliteDatabase::new(db_path).unwrap());
 }
 
+/// Prepares a transaction using the selection strategy, returns the values of the outputs it spends and its change and
+/// then cancels it
+fn spent_values(
+    runtime: &mut Runtime,
+    oms: &mut OutputManagerHandle,
+    amount: MicroTari,
+    strategy: UTXOSelectionStrategy,
+) -> Result<(Vec<MicroTari>, MicroTari), OutputManagerError>
+{
+    let stp = runtime.block_on(oms.prepare_transaction_to_send_with_strategy(
+        amount,
+        MicroTari::from(20),
+        None,
+        "".to_string(),
+        strategy,
+    ))?;
+    let tx_id = stp.get_tx_id().unwrap();
+    let pending = runtime.block_on(oms.get_pending_transactions()).unwrap();
+    let mut values = pending
+        .get(&tx_id)
+        .unwrap()
+        .outputs_to_be_spent
+        .iter()
+        .map(|o| o.value)
+        .collect::<Vec<MicroTari>>();
+    values.sort();
+    runtime.block_on(oms.cancel_transaction(tx_id)).unwrap();
+    Ok((values, stp.get_amount_to_self().unwrap()))
+}
+
+fn send_with_selection_strategies<T: OutputManagerBackend + 'static>(backend: T) {
+    let mut rng = rand::OsRng::new().unwrap();
+    let factories = CryptoFactories::default();
+
+    let mut runtime = Runtime::new().unwrap();
+
+    let (mut oms, _, _) = setup_output_manager_service(&mut runtime, backend);
+
+    let fee_per_gram = MicroTari::from(20);
+    let mut commitments = Vec::new();
+    for value in [1000, 2000, 5000, 10000].iter() {
+        let uo = UnblindedOutput::new(MicroTari::from(*value), PrivateKey::random(&mut rng), None);
+        commitments.push(
+            uo.as_transaction_input(&factories.commitment, OutputFeatures::default())
+                .commitment,
+        );
+        runtime.block_on(oms.add_output(uo)).unwrap();
+    }
+
+    let (values, _) = spent_values(
+        &mut runtime,
+        &mut oms,
+        MicroTari::from(3000),
+        UTXOSelectionStrategy::Smallest,
+    )
+    .unwrap();
+    assert_eq!(values, vec![
+        MicroTari::from(1000),
+        MicroTari::from(2000),
+        MicroTari::from(5000)
+    ]);
+
+    let (values, _) = spent_values(
+        &mut runtime,
+        &mut oms,
+        MicroTari::from(3000),
+        UTXOSelectionStrategy::Largest,
+    )
+    .unwrap();
+    assert_eq!(values, vec![MicroTari::from(10000)]);
+
+    // The 5000 output pays the amount and fee exactly, so no change output is needed
+    let amount = MicroTari::from(5000) - Fee::calculate(fee_per_gram, 1, 1);
+    let (values, change) = spent_values(&mut runtime, &mut oms, amount, UTXOSelectionStrategy::BranchAndBound).unwrap();
+    assert_eq!(values, vec![MicroTari::from(5000)]);
+    assert_eq!(change, MicroTari::from(0));
+    // Neither does an excess smaller than the cost of a change output, which is paid as fee
+    let amount = MicroTari::from(7000) - Fee::calculate(fee_per_gram, 2, 1) - MicroTari::from(10);
+    let (values, change) = spent_values(&mut runtime, &mut oms, amount, UTXOSelectionStrategy::BranchAndBound).unwrap();
+    assert_eq!(values, vec![MicroTari::from(2000), MicroTari::from(5000)]);
+    assert_eq!(change, MicroTari::from(0));
+
+    let (values, _) = spent_values(
+        &mut runtime,
+        &mut oms,
+        MicroTari::from(3000),
+        UTXOSelectionStrategy::Random,
+    )
+    .unwrap();
+    assert!(values.iter().fold(MicroTari::from(0), |acc, v| acc + *v) > MicroTari::from(3000));
+
+    let (values, change) = spent_values(
+        &mut runtime,
+        &mut oms,
+        MicroTari::from(3000),
+        UTXOSelectionStrategy::CoinControl(vec![commitments[1].clone(), commitments[2].clone()]),
+    )
+    .unwrap();
+    assert_eq!(values, vec![MicroTari::from(2000), MicroTari::from(5000)]);
+    assert_eq!(
+        change,
+        MicroTari::from(7000) - MicroTari::from(3000) - Fee::calculate(fee_per_gram, 2, 2)
+    );
+    assert_eq!(
+        spent_values(
+            &mut runtime,
+            &mut oms,
+            MicroTari::from(3000),
+            UTXOSelectionStrategy::CoinControl(vec![commitments[0].clone()])
+        ),
+        Err(OutputManagerError::NotEnoughFunds)
+    );
+    let (unknown, _) = make_input(&mut rng, MicroTari::from(5000), &factories.commitment);
+    assert_eq!(
+        spent_values(
+            &mut runtime,
+            &mut oms,
+            MicroTari::from(3000),
+            UTXOSelectionStrategy::CoinControl(vec![unknown.commitment])
+        ),
+        Err(OutputManagerError::SelectedOutputNotAvailable)
+    );
+}
+
+#[test]
+fn send_with_selection_strategies_memory_db() {
+    send_with_selection_strategies(OutputManagerMemoryDatabase::new());
+}
+
+#[test]
+fn send_with_selection_strategies_sqlite_db() {
+    let db_name = format!("{}.sqlite3", random_string(8).as_str());
+    let db_tempdir = TempDir::new(random_string(8).as_str()).unwrap();
+    let db_folder = db_tempdir.path().to_str().unwrap().to_string();
+    let db_path = format!("{}/{}", db_folder, db_name);
+    send_with_selection_strategies(OutputManagerSqliteDatabase::new(db_path).unwrap());
+}
+
+fn coin_split_and_consolidation<T: OutputManagerBackend + 'static>(backend: T) {
+    let mut rng = rand::OsRng::new().unwrap();
+    let factories = CryptoFactories::default();
+
+    let mut runtime = Runtime::new().unwrap();
+
+    let (mut oms, _, _) = setup_output_manager_service(&mut runtime, backend);
+
+    let fee_per_gram = MicroTari::from(20);
+    let (_ti, uo) = make_input(&mut rng, MicroTari::from(100_000), &factories.commitment);
+    runtime.block_on(oms.add_output(uo)).unwrap();
+
+    assert_eq!(
+        runtime.block_on(oms.coin_split_fee(MicroTari::from(0), 5, fee_per_gram)),
+        Err(OutputManagerError::InvalidCoinSplit)
+    );
+    assert_eq!(
+        runtime.block_on(oms.coin_split_fee(MicroTari::from(100_000), 5, fee_per_gram)),
+        Err(OutputManagerError::NotEnoughFunds)
+    );
+
+    // Split the output into 5 outputs and change
+    let estimate = runtime
+        .block_on(oms.coin_split_fee(MicroTari::from(10_000), 5, fee_per_gram))
+        .unwrap();
+    assert_eq!(estimate, Fee::calculate(fee_per_gram, 1, 6));
+    let (tx_id, fee, amount, tx) = runtime
+        .block_on(oms.create_coin_split(MicroTari::from(10_000), 5, fee_per_gram, None, "split".to_string()))
+        .unwrap();
+    assert_eq!(fee, estimate);
+    assert_eq!(amount, MicroTari::from(50_000));
+    assert_eq!(tx.body.inputs().len(), 1);
+    assert_eq!(tx.body.outputs().len(), 6);
+    assert!(tx.validate_internal_consistency(&factories, None).is_ok());
+
+    let pending = runtime.block_on(oms.get_pending_transactions()).unwrap();
+    assert_eq!(pending.get(&tx_id).unwrap().outputs_to_be_received.len(), 6);
+    let balance = runtime.block_on(oms.get_balance()).unwrap();
+    assert_eq!(balance.available_balance, MicroTari::from(0));
+    assert_eq!(balance.pending_incoming_balance, MicroTari::from(100_000) - fee);
+
+    runtime
+        .block_on(oms.confirm_sent_transaction(tx_id, tx.body.inputs().clone(), tx.body.outputs().clone()))
+        .unwrap();
+    assert_eq!(runtime.block_on(oms.get_unspent_outputs()).unwrap().len(), 6);
+
+    // Consolidate the 4 smallest outputs into one
+    assert_eq!(
+        runtime.block_on(oms.consolidation_fee(1, fee_per_gram)),
+        Err(OutputManagerError::NotEnoughOutputs)
+    );
+    let estimate = runtime.block_on(oms.consolidation_fee(4, fee_per_gram)).unwrap();
+    assert_eq!(estimate, Fee::calculate(fee_per_gram, 4, 1));
+    let (tx_id, fee, amount, tx) = runtime
+        .block_on(oms.consolidate_outputs(4, fee_per_gram, None, "consolidate".to_string()))
+        .unwrap();
+    assert_eq!(fee, estimate);
+    assert_eq!(amount, MicroTari::from(40_000) - fee);
+    assert_eq!(tx.body.inputs().len(), 4);
+    assert_eq!(tx.body.outputs().len(), 1);
+
+    runtime
+        .block_on(oms.confirm_sent_transaction(tx_id, tx.body.inputs().clone(), tx.body.outputs().clone()))
+        .unwrap();
+    let unspent = runtime.block_on(oms.get_unspent_outputs()).unwrap();
+    assert_eq!(unspent.len(), 3);
+    assert!(unspent.iter().any(|o| o.value == amount));
+}
+
+#[test]
+fn coin_split_and_consolidation_memory_db() {
+    coin_split_and_consolidation(OutputManagerMemoryDatabase::new());
+}
+
+#[test]
+fn coin_split_and_consolidation_sqlite_db() {
+    let db_name = format!("{}.sqlite3", random_string(8).as_str());
+    let db_tempdir = TempDir::new(random_string(8).as_str()).unwrap();
+    let db_folder = db_tempdir.path().to_str().unwrap().to_string();
+    let db_path = format!("{}/{}", db_folder, db_name);
+    coin_split_and_consolidation(OutputManagerSqliteDatabase::new(db_path).unwrap());
+}
+
+fn immature_outputs_are_time_locked<T: OutputManagerBackend + 'static>(backend: T) {
+    let mut rng = rand::OsRng::new().unwrap();
+
+    let mut runtime = Runtime::new().unwrap();
+
+    let (mut oms, _, _) = setup_output_manager_service(&mut runtime, backend);
+
+    let spendable = UnblindedOutput::new(MicroTari::from(2000), PrivateKey::random(&mut rng), None);
+    runtime.block_on(oms.add_output(spendable)).unwrap();
+    let immature = UnblindedOutput::new(
+        MicroTari::from(5000),
+        PrivateKey::random(&mut rng),
+        Some(OutputFeatures::create_coinbase(10)),
+    );
+    runtime.block_on(oms.add_output(immature)).unwrap();
+
+    // Until the chain height is known the coinbase output is time-locked
+    let balance = runtime.block_on(oms.get_balance()).unwrap();
+    assert_eq!(balance.available_balance, MicroTari::from(2000));
+    assert_eq!(balance.time_locked_balance, MicroTari::from(5000));
+    assert_eq!(
+        runtime
+            .block_on(oms.prepare_transaction_to_send(MicroTari::from(3000), MicroTari::from(20), None, "".to_string()))
+            .unwrap_err(),
+        OutputManagerError::NotEnoughFunds
+    );
+
+    runtime.block_on(oms.set_chain_height(8)).unwrap();
+    let balance = runtime.block_on(oms.get_balance()).unwrap();
+    assert_eq!(balance.time_locked_balance, MicroTari::from(5000));
+
+    // The output can be spent in the block at its maturity height
+    runtime.block_on(oms.set_chain_height(9)).unwrap();
+    let balance = runtime.block_on(oms.get_balance()).unwrap();
+    assert_eq!(balance.available_balance, MicroTari::from(7000));
+    assert_eq!(balance.time_locked_balance, MicroTari::from(0));
+    let mut stp = runtime
+        .block_on(oms.prepare_transaction_to_send(MicroTari::from(3000), MicroTari::from(20), Some(20), "".to_string()))
+        .unwrap();
+    let msg = stp.build_single_round_message().unwrap();
+    assert_eq!(msg.metadata.lock_height, 20);
+}
+
+#[test]
+fn immature_outputs_are_time_locked_memory_db() {
+    immature_outputs_are_time_locked(OutputManagerMemoryDatabase::new());
+}
+
+#[test]
+fn immature_outputs_are_time_locked_sqlite_db() {
+    let db_name = format!("{}.sqlite3", random_string(8).as_str());
+    let db_tempdir = TempDir::new(random_string(8).as_str()).unwrap();
+    let db_folder = db_tempdir.path().to_str().unwrap().to_string();
+    let db_path = format!("{}/{}", db_folder, db_name);
+    immature_outputs_are_time_locked(OutputManagerSqliteDatabase::new(db_path).unwrap());
+}
+
 fn receiving_and_confirmation<T: OutputManagerBackend + 'static>(backend: T) {
     let factories = CryptoFactories::default();
 
     let mut runtime = Runtime::new().unwrap();
 
-    let (mut oms, _shutdown) = setup_output_manager_service(&mut runtime, backend);
+    let (mut oms, _, _) = setup_output_manager_service(&mut runtime, backend);
 
     let value = MicroTari::from(5000);
     let recv_key = runtime.block_on(oms.get_recipient_spending_key(1, value)).unwrap();
@@ -370,7 +761,7 @@ fn cancel_transaction<T: OutputManagerBackend + 'static>(backend: T) {
 
     let mut runtime = Runtime::new().unwrap();
 
-    let (mut oms, _shutdown) = setup_output_manager_service(&mut runtime, backend);
+    let (mut oms, _, _) = setup_output_manager_service(&mut runtime, backend);
 
     let num_outputs = 20;
     for _i in 0..num_outputs {
@@ -418,7 +809,7 @@ fn timeout_transaction<T: OutputManagerBackend + 'static>(backend: T) {
     let factories = CryptoFactories::default();
 
     let mut runtime = Runtime::new().unwrap();
-    let (mut oms, _shutdown) = setup_output_manager_service(&mut runtime, backend);
+    let (mut oms, _, _) = setup_output_manager_service(&mut runtime, backend);
 
     let num_outputs = 20;
     for _i in 0..num_outputs {
@@ -472,7 +863,7 @@ fn test_get_balance<T: OutputManagerBackend + 'static>(backend: T) {
     let rng = rand::OsRng::new().unwrap();
     let mut runtime = Runtime::new().unwrap();
 
-    let (mut oms, _shutdown) = setup_output_manager_service(&mut runtime, backend);
+    let (mut oms, _, _) = setup_output_manager_service(&mut runtime, backend);
 
     let balance = runtime.block_on(oms.get_balance()).unwrap();
 
@@ -524,7 +915,7 @@ fn test_confirming_received_output<T: OutputManagerBackend + 'static>(backend: T
 
     let mut runtime = Runtime::new().unwrap();
 
-    let (mut oms, _shutdown) = setup_output_manager_service(&mut runtime, backend);
+    let (mut oms, _, _) = setup_output_manager_service(&mut runtime, backend);
 
     let value = MicroTari::from(5000);
     let recv_key = runtime.block_on(oms.get_recipient_spending_key(1, value)).unwrap();
@@ -553,3 +944,272 @@ fn test_confirming_received_output_sqlite_db() {
     let db_path = format!("{}/{}", db_folder, db_name);
     test_confirming_received_output(OutputManagerSqliteDatabase::new(db_path).unwrap());
 }
+
+/// Return the request key and queried output hashes of the UTXO validation query sent to the base node
+fn utxo_validation_request<P>(calls: &[(P, Vec<u8>)]) -> (u64, Vec<Vec<u8>>) {
+    assert_eq!(calls.len(), 1);
+    let envelope_body = EnvelopeBody::decode(calls[0].1.as_slice()).unwrap();
+    let header = envelope_body.decode_part::<MessageHeader>(0).unwrap().unwrap();
+    assert_eq!(header.message_type, TariMessageType::BaseNodeRequest as i32);
+    let request = envelope_body.decode_part::<BaseNodeServiceRequest>(1).unwrap().unwrap();
+    match request.request {
+        Some(BaseNodeRequestProto::FetchUtxos(hash_outputs)) => (request.request_key, hash_outputs.outputs),
+        _ => panic!("Unexpected base node request"),
+    }
+}
+
+fn utxo_validation<T: OutputManagerBackend + 'static>(backend: T) {
+    let mut rng = rand::OsRng::new().unwrap();
+    let factories = CryptoFactories::default();
+
+    let mut runtime = Runtime::new().unwrap();
+
+    let (mut oms, outbound_service, mut base_node_response_sender) =
+        setup_output_manager_service(&mut runtime, backend);
+    let event_stream = oms.get_event_stream_fused();
+    let base_node_public_key = PublicKey::from_secret_key(&PrivateKey::random(&mut rng));
+
+    let mut outputs = Vec::new();
+    for _ in 0..4 {
+        let (_ti, uo) = make_input(
+            &mut rng.clone(),
+            MicroTari::from(100 + rng.next_u64() % 1000),
+            &factories.commitment,
+        );
+        runtime.block_on(oms.add_output(uo.clone())).unwrap();
+        outputs.push(uo);
+    }
+    let total = outputs.iter().fold(MicroTari::from(0), |acc, x| acc + x.value);
+
+    // An output that is still to be received by a pending transaction is validated too
+    let recv_value = MicroTari::from(5000);
+    let recv_key = runtime.block_on(oms.get_recipient_spending_key(1, recv_value)).unwrap();
+    let received_output = UnblindedOutput::new(recv_value, recv_key, None);
+
+    // Setting the base node triggers a validation round
+    runtime
+        .block_on(oms.set_base_node_public_key(base_node_public_key.clone()))
+        .unwrap();
+    outbound_service.wait_call_count(1, Duration::from_secs(10)).unwrap();
+    let (request_key, hashes) = utxo_validation_request(&outbound_service.take_calls());
+    assert_eq!(hashes.len(), outputs.len() + 1);
+
+    // The base node only knows about the first two outputs
+    let response = BaseNodeServiceResponse {
+        request_key,
+        response: Some(BaseNodeResponseProto::TransactionOutputs(TransactionOutputs {
+            outputs: outputs[0..2]
+                .iter()
+                .map(|o| o.as_transaction_output(&factories).unwrap().into())
+                .collect(),
+        })),
+    };
+    runtime
+        .block_on(base_node_response_sender.send(create_dummy_message(response, &base_node_public_key)))
+        .unwrap();
+
+    let events = collect_stream!(
+        runtime,
+        event_stream.map(|i| (*i).clone()),
+        take = 2,
+        timeout = Duration::from_secs(10)
+    );
+    assert_eq!(events, vec![
+        OutputManagerEvent::OutputsInvalidated(2),
+        OutputManagerEvent::UtxoValidationComplete(request_key)
+    ]);
+
+    let mut invalid_outputs = runtime.block_on(oms.get_invalid_outputs()).unwrap();
+    invalid_outputs.sort();
+    let mut expected_invalid = outputs[2..].to_vec();
+    expected_invalid.sort();
+    assert_eq!(invalid_outputs, expected_invalid);
+    assert_eq!(
+        runtime.block_on(oms.get_balance()).unwrap().available_balance,
+        outputs[0].value + outputs[1].value
+    );
+
+    // Responses to unknown queries are ignored
+    let response = BaseNodeServiceResponse {
+        request_key: request_key.wrapping_add(1),
+        response: Some(BaseNodeResponseProto::TransactionOutputs(TransactionOutputs {
+            outputs: vec![],
+        })),
+    };
+    runtime
+        .block_on(base_node_response_sender.send(create_dummy_message(response, &base_node_public_key)))
+        .unwrap();
+
+    // The invalid outputs are queried again and marked as unspent once the base node reports them
+    let event_stream = oms.get_event_stream_fused();
+    runtime
+        .block_on(oms.set_base_node_public_key(base_node_public_key.clone()))
+        .unwrap();
+    outbound_service.wait_call_count(1, Duration::from_secs(10)).unwrap();
+    let (request_key, hashes) = utxo_validation_request(&outbound_service.take_calls());
+    assert_eq!(hashes.len(), outputs.len() + 1);
+
+    let response = BaseNodeServiceResponse {
+        request_key,
+        response: Some(BaseNodeResponseProto::TransactionOutputs(TransactionOutputs {
+            outputs: outputs
+                .iter()
+                .chain(Some(&received_output))
+                .map(|o| o.as_transaction_output(&factories).unwrap().into())
+                .collect(),
+        })),
+    };
+    runtime
+        .block_on(base_node_response_sender.send(create_dummy_message(response, &base_node_public_key)))
+        .unwrap();
+
+    let events = collect_stream!(
+        runtime,
+        event_stream.map(|i| (*i).clone()),
+        take = 3,
+        timeout = Duration::from_secs(10)
+    );
+    assert_eq!(events, vec![
+        OutputManagerEvent::OutputsRevalidated(2),
+        OutputManagerEvent::PendingTransactionsConfirmed(1),
+        OutputManagerEvent::UtxoValidationComplete(request_key)
+    ]);
+
+    assert!(runtime.block_on(oms.get_invalid_outputs()).unwrap().is_empty());
+    let balance = runtime.block_on(oms.get_balance()).unwrap();
+    assert_eq!(balance.available_balance, total + recv_value);
+    assert_eq!(balance.pending_incoming_balance, MicroTari::from(0));
+    assert!(runtime.block_on(oms.get_pending_transactions()).unwrap().is_empty());
+}
+
+#[test]
+fn utxo_validation_memory_db() {
+    utxo_validation(OutputManagerMemoryDatabase::new());
+}
+
+#[test]
+fn utxo_validation_sqlite_db() {
+    let db_name = format!("{}.sqlite3", random_string(8).as_str());
+    let db_tempdir = TempDir::new(random_string(8).as_str()).unwrap();
+    let db_folder = db_tempdir.path().to_str().unwrap().to_string();
+    let db_path = format!("{}/{}", db_folder, db_name);
+    utxo_validation(OutputManagerSqliteDatabase::new(db_path).unwrap());
+}
+
+fn watch_only_export_and_import<T: OutputManagerBackend + 'static>(backend: T) {
+    let factories = CryptoFactories::default();
+    let mut rng = rand::OsRng::new().unwrap();
+    let mut runtime = Runtime::new().unwrap();
+
+    let (mut oms, _, _) = setup_output_manager_service(&mut runtime, OutputManagerMemoryDatabase::new());
+    let (mut watch_oms, _, _) = setup_output_manager_service(&mut runtime, backend);
+
+    let mut total = MicroTari::from(0);
+    for _ in 0..3 {
+        let (_ti, uo) = make_input(
+            &mut rng.clone(),
+            MicroTari::from(1000 + rng.next_u64() % 1000),
+            &factories.commitment,
+        );
+        total += uo.value;
+        runtime.block_on(oms.add_output(uo)).unwrap();
+    }
+    // Outputs encumbered by a pending transaction are still unspent in the blockchain, so they are exported too
+    runtime
+        .block_on(oms.prepare_transaction_to_send(MicroTari::from(500), MicroTari::from(20), None, "".to_string()))
+        .unwrap();
+
+    let export = runtime.block_on(oms.export_watch_only_data()).unwrap();
+    assert_eq!(export.outputs.len(), 3);
+    assert_eq!(runtime.block_on(watch_oms.get_watch_only_state()).unwrap(), None);
+
+    runtime
+        .block_on(watch_oms.import_watch_only_data(export.clone(), Some(10)))
+        .unwrap();
+    let state = runtime.block_on(watch_oms.get_watch_only_state()).unwrap().unwrap();
+    assert_eq!(state.view_key, Some(export.view_key.clone()));
+    assert_eq!(state.next_height, 10);
+    assert_eq!(runtime.block_on(watch_oms.get_watched_outputs()).unwrap().len(), 3);
+    assert_eq!(
+        runtime.block_on(watch_oms.get_balance()).unwrap().available_balance,
+        total
+    );
+
+    // Importing the same outputs again does not count them twice, but an earlier birthday height is used
+    runtime
+        .block_on(watch_oms.import_watch_only_data(export.clone(), Some(5)))
+        .unwrap();
+    assert_eq!(runtime.block_on(watch_oms.get_watched_outputs()).unwrap().len(), 3);
+    assert_eq!(
+        runtime.block_on(watch_oms.get_balance()).unwrap().available_balance,
+        total
+    );
+    assert_eq!(
+        runtime
+            .block_on(watch_oms.get_watch_only_state())
+            .unwrap()
+            .unwrap()
+            .next_height,
+        5
+    );
+
+    // A wallet with outputs of its own cannot become watch-only
+    assert_eq!(
+        runtime.block_on(oms.import_watch_only_data(export.clone(), None)),
+        Err(OutputManagerError::WatchOnlyImportNotAllowed)
+    );
+
+    // A watch-only wallet refuses to send or receive funds
+    assert_eq!(
+        runtime
+            .block_on(watch_oms.prepare_transaction_to_send(
+                MicroTari::from(500),
+                MicroTari::from(20),
+                None,
+                "".to_string()
+            ))
+            .err(),
+        Some(OutputManagerError::WatchOnlyWallet)
+    );
+    assert_eq!(
+        runtime.block_on(watch_oms.get_recipient_spending_key(1, MicroTari::from(500))),
+        Err(OutputManagerError::WatchOnlyWallet)
+    );
+    assert_eq!(
+        runtime.block_on(watch_oms.export_watch_only_data()),
+        Err(OutputManagerError::WatchOnlyWallet)
+    );
+
+    let mut spent_output = export.outputs[0].clone();
+    spent_output.mined_height = Some(3);
+    spent_output.spent_height = Some(12);
+    runtime
+        .block_on(watch_oms.update_watched_outputs(vec![spent_output.clone()], 13))
+        .unwrap();
+    assert_eq!(
+        runtime
+            .block_on(watch_oms.get_watch_only_state())
+            .unwrap()
+            .unwrap()
+            .next_height,
+        13
+    );
+    assert_eq!(
+        runtime.block_on(watch_oms.get_balance()).unwrap().available_balance,
+        total - spent_output.value
+    );
+}
+
+#[test]
+fn watch_only_export_and_import_memory_db() {
+    watch_only_export_and_import(OutputManagerMemoryDatabase::new());
+}
+
+#[test]
+fn watch_only_export_and_import_sqlite_db() {
+    let db_name = format!("{}.sqlite3", random_string(8).as_str());
+    let db_tempdir = TempDir::new(random_string(8).as_str()).unwrap();
+    let db_folder = db_tempdir.path().to_str().unwrap().to_string();
+    let db_path = format!("{}/{}", db_folder, db_name);
+    watch_only_export_and_import(OutputManagerSqliteDatabase::new(db_path).unwrap());
+}
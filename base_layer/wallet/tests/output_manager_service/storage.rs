@@ -27,15 +27,36 @@ use std::time::Duration;
 use tari_core::transactions::{
     tari_amount::MicroTari,
     transaction::OutputFeatures,
-    types::{CryptoFactories, PrivateKey},
+    types::{CryptoFactories, PrivateKey, PublicKey},
 };
-use tari_crypto::keys::SecretKey;
-use tari_wallet::output_manager_service::{
-    service::Balance,
-    storage::{
-        database::{KeyManagerState, OutputManagerBackend, OutputManagerDatabase, PendingTransactionOutputs},
-        memory_db::OutputManagerMemoryDatabase,
-        sqlite_db::OutputManagerSqliteDatabase,
+use tari_crypto::{
+    commitment::HomomorphicCommitmentFactory,
+    keys::{PublicKey as PublicKeyTrait, SecretKey},
+};
+use tari_wallet::{
+    encryption::EncryptionSettings,
+    error::EncryptionError,
+    output_manager_service::{
+        error::OutputManagerStorageError,
+        service::Balance,
+        storage::{
+            database::{
+                AtomicSwap,
+                AtomicSwapRole,
+                AtomicSwapStatus,
+                KeyManagerState,
+                OutputManagerBackend,
+                OutputManagerDatabase,
+                PendingTransactionOutputs,
+                SharedOutput,
+                SharedOutputStatus,
+                ViewKey,
+                WatchOnlyState,
+                WatchedOutput,
+            },
+            memory_db::OutputManagerMemoryDatabase,
+            sqlite_db::OutputManagerSqliteDatabase,
+        },
     },
 };
 use tempdir::TempDir;
@@ -116,11 +137,12 @@ pub fn test_db_backend<T: OutputManagerBackend>(backend: T) {
             .fold(MicroTari::from(0), |acc, x| acc + x.value);
     }
 
-    let balance = db.get_balance().unwrap();
+    let balance = db.get_balance(None).unwrap();
     assert_eq!(balance, Balance {
         available_balance,
         pending_incoming_balance,
-        pending_outgoing_balance
+        pending_outgoing_balance,
+        time_locked_balance: MicroTari::from(0),
     });
 
     db.confirm_pending_transaction_outputs(pending_txs[0].tx_id).unwrap();
@@ -140,11 +162,12 @@ pub fn test_db_backend<T: OutputManagerBackend>(backend: T) {
         .iter()
         .fold(MicroTari::from(0), |acc, x| acc + x.value);
 
-    let balance = db.get_balance().unwrap();
+    let balance = db.get_balance(None).unwrap();
     assert_eq!(balance, Balance {
         available_balance,
         pending_incoming_balance,
-        pending_outgoing_balance
+        pending_outgoing_balance,
+        time_locked_balance: MicroTari::from(0),
     });
 
     let spent_outputs = db.fetch_spent_outputs().unwrap();
@@ -165,18 +188,19 @@ pub fn test_db_backend<T: OutputManagerBackend>(backend: T) {
     );
     let outputs_to_encumber = vec![outputs[0].clone(), outputs[1].clone()];
     let total_encumbered = outputs[0].clone().value + outputs[1].clone().value;
-    db.encumber_outputs(2, &outputs_to_encumber, Some(uo_change.clone()))
+    db.encumber_outputs(2, &outputs_to_encumber, vec![uo_change.clone()])
         .unwrap();
 
     available_balance -= total_encumbered;
     pending_incoming_balance += uo_change.clone().value;
     pending_outgoing_balance += total_encumbered;
 
-    let balance = db.get_balance().unwrap();
+    let balance = db.get_balance(None).unwrap();
     assert_eq!(balance, Balance {
         available_balance,
         pending_incoming_balance,
-        pending_outgoing_balance
+        pending_outgoing_balance,
+        time_locked_balance: MicroTari::from(0),
     });
 
     let (_ti, uo_incoming) = make_input(
@@ -194,11 +218,12 @@ pub fn test_db_backend<T: OutputManagerBackend>(backend: T) {
 
     pending_incoming_balance += uo_incoming.clone().value;
 
-    let balance = db.get_balance().unwrap();
+    let balance = db.get_balance(None).unwrap();
     assert_eq!(balance, Balance {
         available_balance,
         pending_incoming_balance,
-        pending_outgoing_balance
+        pending_outgoing_balance,
+        time_locked_balance: MicroTari::from(0),
     });
 
     db.cancel_pending_transaction_outputs(pending_txs[1].tx_id).unwrap();
@@ -219,11 +244,12 @@ pub fn test_db_backend<T: OutputManagerBackend>(backend: T) {
     pending_incoming_balance -= cancelled_incoming;
     pending_outgoing_balance -= cancelled_outgoing;
 
-    let balance = db.get_balance().unwrap();
+    let balance = db.get_balance(None).unwrap();
     assert_eq!(balance, Balance {
         available_balance,
         pending_incoming_balance,
-        pending_outgoing_balance
+        pending_outgoing_balance,
+        time_locked_balance: MicroTari::from(0),
     });
 
     let remaining_p_tx = db.fetch_all_pending_transaction_outputs().unwrap();
@@ -263,6 +289,67 @@ pub fn test_output_manager_sqlite_db() {
     test_db_backend(OutputManagerSqliteDatabase::new(format!("{}/{}", db_folder, db_name).to_string()).unwrap());
 }
 
+pub fn test_output_validation<T: OutputManagerBackend>(backend: T) {
+    let mut db = OutputManagerDatabase::new(backend);
+    let factories = CryptoFactories::default();
+    let mut rng = rand::OsRng::new().unwrap();
+
+    let mut unspent_outputs = Vec::new();
+    for _ in 0..3 {
+        let (_ti, uo) = make_input(
+            &mut rng.clone(),
+            MicroTari::from(100 + rng.next_u64() % 1000),
+            &factories.commitment,
+        );
+        db.add_unspent_output(uo.clone()).unwrap();
+        unspent_outputs.push(uo);
+    }
+    unspent_outputs.sort();
+    let total = unspent_outputs.iter().fold(MicroTari::from(0), |acc, x| acc + x.value);
+
+    assert!(db.fetch_invalid_outputs().unwrap().is_empty());
+
+    db.invalidate_output(&unspent_outputs[0]).unwrap();
+
+    assert_eq!(db.fetch_invalid_outputs().unwrap(), vec![unspent_outputs[0].clone()]);
+    assert_eq!(
+        db.fetch_sorted_unspent_outputs().unwrap(),
+        unspent_outputs[1..].to_vec()
+    );
+    assert_eq!(
+        db.get_balance(None).unwrap().available_balance,
+        total - unspent_outputs[0].value
+    );
+
+    // Invalid outputs cannot be spent
+    assert!(db
+        .encumber_outputs(1, &vec![unspent_outputs[0].clone()], Vec::new())
+        .is_err());
+    // Only unspent outputs can be invalidated
+    assert!(db.invalidate_output(&unspent_outputs[0]).is_err());
+
+    db.revalidate_output(&unspent_outputs[0].spending_key).unwrap();
+
+    assert!(db.fetch_invalid_outputs().unwrap().is_empty());
+    assert_eq!(db.fetch_sorted_unspent_outputs().unwrap(), unspent_outputs);
+    assert_eq!(db.get_balance(None).unwrap().available_balance, total);
+    // Only invalid outputs can be revalidated
+    assert!(db.revalidate_output(&unspent_outputs[0].spending_key).is_err());
+}
+
+#[test]
+pub fn test_output_validation_memory_db() {
+    test_output_validation(OutputManagerMemoryDatabase::new());
+}
+
+#[test]
+pub fn test_output_validation_sqlite_db() {
+    let db_name = format!("{}.sqlite3", random_string(8).as_str());
+    let temp_dir = TempDir::new(random_string(8).as_str()).unwrap();
+    let db_folder = temp_dir.path().to_str().unwrap().to_string();
+    test_output_validation(OutputManagerSqliteDatabase::new(format!("{}/{}", db_folder, db_name).to_string()).unwrap());
+}
+
 pub fn test_key_manager_crud<T: OutputManagerBackend>(backend: T) {
     let mut db = OutputManagerDatabase::new(backend);
     let mut rng = rand::OsRng::new().unwrap();
@@ -310,3 +397,283 @@ pub fn test_key_manager_crud_sqlite_db() {
     let db_folder = temp_dir.path().to_str().unwrap().to_string();
     test_key_manager_crud(OutputManagerSqliteDatabase::new(format!("{}/{}", db_folder, db_name).to_string()).unwrap());
 }
+
+pub fn test_watched_outputs<T: OutputManagerBackend>(backend: T) {
+    let mut db = OutputManagerDatabase::new(backend);
+    let factories = CryptoFactories::default();
+    let mut rng = rand::OsRng::new().unwrap();
+
+    assert_eq!(db.get_watch_only_state().unwrap(), None);
+    assert!(db.fetch_watched_outputs().unwrap().is_empty());
+
+    let state = WatchOnlyState {
+        view_key: Some(ViewKey {
+            rewind_public_key: PublicKey::from_secret_key(&PrivateKey::random(&mut rng)),
+            rewind_blinding_public_key: PublicKey::from_secret_key(&PrivateKey::random(&mut rng)),
+        }),
+        next_height: 5,
+    };
+    db.set_watch_only_state(state.clone()).unwrap();
+    assert_eq!(db.get_watch_only_state().unwrap(), Some(state.clone()));
+
+    let updated_state = WatchOnlyState {
+        next_height: 10,
+        ..state
+    };
+    db.set_watch_only_state(updated_state.clone()).unwrap();
+    assert_eq!(db.get_watch_only_state().unwrap(), Some(updated_state));
+
+    let mut outputs = Vec::new();
+    for i in 0..3 {
+        let value = MicroTari::from(100 * (i + 1));
+        let output = WatchedOutput {
+            commitment: factories
+                .commitment
+                .commit_value(&PrivateKey::random(&mut rng), value.into()),
+            value,
+            features: OutputFeatures::with_maturity(i * 10),
+            mined_height: Some(i),
+            spent_height: None,
+        };
+        db.save_watched_output(output.clone()).unwrap();
+        outputs.push(output);
+    }
+    let watched_outputs = db.fetch_watched_outputs().unwrap();
+    assert_eq!(watched_outputs.len(), 3);
+    assert!(outputs.iter().all(|o| watched_outputs.contains(o)));
+
+    let balance = db.get_watch_only_balance(Some(10)).unwrap();
+    assert_eq!(balance.available_balance, MicroTari::from(300));
+    assert_eq!(balance.time_locked_balance, MicroTari::from(300));
+    assert_eq!(balance.pending_incoming_balance, MicroTari::from(0));
+    assert_eq!(balance.pending_outgoing_balance, MicroTari::from(0));
+
+    // Saving an output with the same commitment replaces it
+    outputs[0].spent_height = Some(8);
+    db.save_watched_output(outputs[0].clone()).unwrap();
+    let watched_outputs = db.fetch_watched_outputs().unwrap();
+    assert_eq!(watched_outputs.len(), 3);
+    assert!(watched_outputs.contains(&outputs[0]));
+
+    let balance = db.get_watch_only_balance(Some(10)).unwrap();
+    assert_eq!(balance.available_balance, MicroTari::from(200));
+    assert_eq!(balance.time_locked_balance, MicroTari::from(300));
+}
+
+#[test]
+pub fn test_watched_outputs_memory_db() {
+    test_watched_outputs(OutputManagerMemoryDatabase::new());
+}
+
+#[test]
+pub fn test_watched_outputs_sqlite_db() {
+    let db_name = format!("{}.sqlite3", random_string(8).as_str());
+    let temp_dir = TempDir::new(random_string(8).as_str()).unwrap();
+    let db_folder = temp_dir.path().to_str().unwrap().to_string();
+    test_watched_outputs(OutputManagerSqliteDatabase::new(format!("{}/{}", db_folder, db_name).to_string()).unwrap());
+}
+
+pub fn test_shared_outputs<T: OutputManagerBackend>(backend: T) {
+    let mut db = OutputManagerDatabase::new(backend);
+    let factories = CryptoFactories::default();
+    let mut rng = rand::OsRng::new().unwrap();
+
+    assert!(db.fetch_shared_outputs().unwrap().is_empty());
+
+    let mut outputs = Vec::new();
+    for i in 0..3 {
+        let participants = (0..(i + 2))
+            .map(|_| {
+                (
+                    PublicKey::from_secret_key(&PrivateKey::random(&mut rng)),
+                    PublicKey::from_secret_key(&PrivateKey::random(&mut rng)),
+                )
+            })
+            .collect::<Vec<_>>();
+        let output = SharedOutput {
+            output_id: rng.next_u64(),
+            value: MicroTari::from(1000 * (i + 1)),
+            initiator: participants[0].0.clone(),
+            participants,
+            key_share: PrivateKey::random(&mut rng),
+            commitment: None,
+            status: SharedOutputStatus::Pending,
+        };
+        db.save_shared_output(output.clone()).unwrap();
+        outputs.push(output);
+    }
+    let shared_outputs = db.fetch_shared_outputs().unwrap();
+    assert_eq!(shared_outputs.len(), 3);
+    assert!(outputs.iter().all(|o| shared_outputs.contains(o)));
+
+    // Saving an output with the same id replaces it
+    outputs[1].commitment = Some(
+        factories
+            .commitment
+            .commit_value(&PrivateKey::random(&mut rng), outputs[1].value.into()),
+    );
+    outputs[1].status = SharedOutputStatus::Unspent;
+    db.save_shared_output(outputs[1].clone()).unwrap();
+    let shared_outputs = db.fetch_shared_outputs().unwrap();
+    assert_eq!(shared_outputs.len(), 3);
+    assert!(shared_outputs.contains(&outputs[1]));
+}
+
+#[test]
+pub fn test_shared_outputs_memory_db() {
+    test_shared_outputs(OutputManagerMemoryDatabase::new());
+}
+
+#[test]
+pub fn test_shared_outputs_sqlite_db() {
+    let db_name = format!("{}.sqlite3", random_string(8).as_str());
+    let temp_dir = TempDir::new(random_string(8).as_str()).unwrap();
+    let db_folder = temp_dir.path().to_str().unwrap().to_string();
+    test_shared_outputs(OutputManagerSqliteDatabase::new(format!("{}/{}", db_folder, db_name).to_string()).unwrap());
+}
+
+pub fn test_atomic_swaps<T: OutputManagerBackend>(backend: T) {
+    let mut db = OutputManagerDatabase::new(backend);
+    let factories = CryptoFactories::default();
+    let mut rng = rand::OsRng::new().unwrap();
+
+    assert!(db.fetch_atomic_swaps().unwrap().is_empty());
+
+    let mut swaps = Vec::new();
+    for i in 0..3 {
+        let swap = AtomicSwap {
+            swap_id: rng.next_u64(),
+            role: if i % 2 == 0 {
+                AtomicSwapRole::Funder
+            } else {
+                AtomicSwapRole::Claimer
+            },
+            counterparty: PublicKey::from_secret_key(&PrivateKey::random(&mut rng)),
+            value: MicroTari::from(1000 * (i + 1)),
+            hash: vec![i as u8; 32],
+            refund_height: 100 + i,
+            fee_per_gram: MicroTari::from(20),
+            output_value: MicroTari::from(1000 * (i + 1) - 100),
+            output_key: PrivateKey::random(&mut rng),
+            counterparty_commitment: None,
+            spending_key: None,
+            preimage: None,
+            message: format!("Swap {}", i),
+            status: AtomicSwapStatus::Offered,
+            scan_height: None,
+        };
+        db.save_atomic_swap(swap.clone()).unwrap();
+        swaps.push(swap);
+    }
+    let stored_swaps = db.fetch_atomic_swaps().unwrap();
+    assert_eq!(stored_swaps.len(), 3);
+    assert!(swaps.iter().all(|s| stored_swaps.contains(s)));
+
+    // Saving a swap with the same id replaces it
+    swaps[1].counterparty_commitment = Some(
+        factories
+            .commitment
+            .commit_value(&PrivateKey::random(&mut rng), swaps[1].value.into()),
+    );
+    swaps[1].spending_key = Some(PrivateKey::random(&mut rng));
+    swaps[1].preimage = Some(vec![7u8; 32]);
+    swaps[1].status = AtomicSwapStatus::Claimed;
+    swaps[1].scan_height = Some(123);
+    db.save_atomic_swap(swaps[1].clone()).unwrap();
+    let stored_swaps = db.fetch_atomic_swaps().unwrap();
+    assert_eq!(stored_swaps.len(), 3);
+    assert!(stored_swaps.contains(&swaps[1]));
+}
+
+#[test]
+pub fn test_atomic_swaps_memory_db() {
+    test_atomic_swaps(OutputManagerMemoryDatabase::new());
+}
+
+#[test]
+pub fn test_atomic_swaps_sqlite_db() {
+    let db_name = format!("{}.sqlite3", random_string(8).as_str());
+    let temp_dir = TempDir::new(random_string(8).as_str()).unwrap();
+    let db_folder = temp_dir.path().to_str().unwrap().to_string();
+    test_atomic_swaps(OutputManagerSqliteDatabase::new(format!("{}/{}", db_folder, db_name).to_string()).unwrap());
+}
+
+#[test]
+pub fn test_output_manager_sqlite_db_encryption() {
+    let db_name = format!("{}.sqlite3", random_string(8).as_str());
+    let temp_dir = TempDir::new(random_string(8).as_str()).unwrap();
+    let db_folder = temp_dir.path().to_str().unwrap().to_string();
+    let db_path = format!("{}/{}", db_folder, db_name);
+    let factories = CryptoFactories::default();
+    let mut rng = rand::OsRng::new().unwrap();
+
+    let mut db = OutputManagerDatabase::new(OutputManagerSqliteDatabase::new(db_path.clone()).unwrap());
+    let state = KeyManagerState {
+        master_seed: PrivateKey::random(&mut rng),
+        branch_seed: "blah".to_string(),
+        primary_key_index: 0,
+    };
+    db.set_key_manager_state(state.clone()).unwrap();
+    let mut unspent_outputs = Vec::new();
+    for _ in 0..3 {
+        let (_ti, uo) = make_input(
+            &mut rng.clone(),
+            MicroTari::from(100 + rng.next_u64() % 1000),
+            &factories.commitment,
+        );
+        db.add_unspent_output(uo.clone()).unwrap();
+        unspent_outputs.push(uo);
+    }
+
+    let settings = EncryptionSettings::new("correct horse").unwrap();
+    let cipher = settings.cipher("correct horse").unwrap();
+    db.apply_encryption(Some(cipher.clone())).unwrap();
+
+    // Outputs added after encryption is applied are encrypted too
+    let (_ti, uo) = make_input(
+        &mut rng.clone(),
+        MicroTari::from(100 + rng.next_u64() % 1000),
+        &factories.commitment,
+    );
+    db.add_unspent_output(uo.clone()).unwrap();
+    unspent_outputs.push(uo);
+    unspent_outputs.sort();
+
+    assert_eq!(db.get_key_manager_state().unwrap().unwrap(), state);
+    assert_eq!(db.fetch_sorted_unspent_outputs().unwrap(), unspent_outputs);
+    db.invalidate_output(&unspent_outputs[0]).unwrap();
+    db.revalidate_output(&unspent_outputs[0].spending_key).unwrap();
+
+    db.lock().unwrap();
+    assert_eq!(
+        db.get_key_manager_state(),
+        Err(OutputManagerStorageError::EncryptionError(
+            EncryptionError::WalletLocked
+        ))
+    );
+    assert!(db.fetch_sorted_unspent_outputs().is_err());
+
+    // An encrypted database is locked when it is opened
+    drop(db);
+    let mut db = OutputManagerDatabase::new(OutputManagerSqliteDatabase::new(db_path.clone()).unwrap());
+    assert!(db.get_key_manager_state().is_err());
+    let wrong_cipher = EncryptionSettings::new("battery staple")
+        .unwrap()
+        .cipher("battery staple")
+        .unwrap();
+    assert_eq!(
+        db.unlock(wrong_cipher),
+        Err(OutputManagerStorageError::EncryptionError(
+            EncryptionError::IncorrectPassphrase
+        ))
+    );
+    db.unlock(cipher).unwrap();
+    assert_eq!(db.get_key_manager_state().unwrap().unwrap(), state);
+    assert_eq!(db.fetch_sorted_unspent_outputs().unwrap(), unspent_outputs);
+
+    db.apply_encryption(None).unwrap();
+    drop(db);
+    let db = OutputManagerDatabase::new(OutputManagerSqliteDatabase::new(db_path).unwrap());
+    assert_eq!(db.get_key_manager_state().unwrap().unwrap(), state);
+    assert_eq!(db.fetch_sorted_unspent_outputs().unwrap(), unspent_outputs);
+}
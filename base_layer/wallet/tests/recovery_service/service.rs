@@ -0,0 +1,342 @@
+// Copyright 2020, The Tari Project
+//
+// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+// following conditions are met:
+//
+// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+// disclaimer.
+//
+// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+// following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+// 3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+// products derived from this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+// INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+// SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+// WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+// USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+use crate::{
+    output_manager_service::service::setup_output_manager_service,
+    support::comms_and_services::create_dummy_message,
+};
+use futures::{
+    channel::{mpsc, mpsc::Sender},
+    SinkExt,
+    StreamExt,
+};
+use prost::Message;
+use std::time::Duration;
+use tari_broadcast_channel::bounded;
+use tari_comms::message::EnvelopeBody;
+use tari_comms_dht::outbound::mock::{create_outbound_service_mock, OutboundServiceMockState};
+use tari_core::{
+    base_node::proto::base_node::{
+        base_node_service_request::Request as BaseNodeRequestProto,
+        base_node_service_response::Response as BaseNodeResponseProto,
+        BaseNodeServiceRequest,
+        BaseNodeServiceResponse,
+        ChainMetadata,
+        HistoricalBlocks,
+    },
+    consensus::emission::EmissionSchedule,
+    proto::core::{Block as ProtoBlock, BlockHeader as ProtoBlockHeader, HistoricalBlock as ProtoHistoricalBlock},
+    transactions::{
+        proto::types::AggregateBody as ProtoAggregateBody,
+        tari_amount::MicroTari,
+        transaction::{OutputFeatures, TransactionOutput, UnblindedOutput},
+        types::{CryptoFactories, PrivateKey, PublicKey},
+    },
+};
+use tari_crypto::keys::{PublicKey as PublicKeyTrait, SecretKey};
+use tari_key_manager::{
+    key_manager::KeyManager,
+    mnemonic::{from_secret_key, MnemonicLanguage},
+};
+use tari_p2p::domain_message::DomainMessage;
+use tari_service_framework::reply_channel;
+use tari_test_utils::collect_stream;
+use tari_wallet::{
+    output_manager_service::{
+        handle::OutputManagerHandle,
+        service::derive_rewind_data,
+        storage::memory_db::OutputManagerMemoryDatabase,
+    },
+    recovery_service::{
+        config::RecoveryServiceConfig,
+        error::RecoveryServiceError,
+        handle::{RecoveryEvent, RecoveryServiceHandle},
+        service::RecoveryService,
+    },
+    types::KeyDigest,
+};
+use tokio::runtime::Runtime;
+
+pub fn setup_recovery_service(
+    runtime: &mut Runtime,
+    output_manager_service: OutputManagerHandle,
+) -> (
+    RecoveryServiceHandle,
+    OutboundServiceMockState,
+    Sender<DomainMessage<BaseNodeServiceResponse>>,
+)
+{
+    let (request_sender, request_receiver) = reply_channel::unbounded();
+    let (event_publisher, event_subscriber) = bounded(100);
+    let (base_node_response_sender, base_node_response_receiver) = mpsc::channel(20);
+
+    let (outbound_message_requester, mock_outbound_service) = create_outbound_service_mock(20);
+    let outbound_mock_state = mock_outbound_service.get_state();
+    runtime.spawn(mock_outbound_service.run());
+
+    let service = RecoveryService::new(
+        RecoveryServiceConfig::default(),
+        output_manager_service,
+        outbound_message_requester,
+        request_receiver,
+        base_node_response_receiver,
+        event_publisher,
+        CryptoFactories::default(),
+    );
+    runtime.spawn(async move { service.start().await.unwrap() });
+
+    (
+        RecoveryServiceHandle::new(request_sender, event_subscriber),
+        outbound_mock_state,
+        base_node_response_sender,
+    )
+}
+
+/// Wait for the next request sent to the base node and return its request key and content
+fn next_base_node_request(outbound_service: &OutboundServiceMockState) -> (u64, BaseNodeRequestProto) {
+    outbound_service.wait_call_count(1, Duration::from_secs(10)).unwrap();
+    let (_, body) = outbound_service.pop_call().unwrap();
+    let envelope_body = EnvelopeBody::decode(body.as_slice()).unwrap();
+    let request = envelope_body.decode_part::<BaseNodeServiceRequest>(1).unwrap().unwrap();
+    (request.request_key, request.request.unwrap())
+}
+
+/// Answer the chain metadata request and the request for all the blocks of the chain made by a recovery
+fn respond_with_blocks(
+    runtime: &mut Runtime,
+    outbound_service: &OutboundServiceMockState,
+    base_node_response_sender: &mut Sender<DomainMessage<BaseNodeServiceResponse>>,
+    base_node_public_key: &PublicKey,
+    blocks: Vec<ProtoHistoricalBlock>,
+)
+{
+    let (request_key, request) = next_base_node_request(outbound_service);
+    assert_eq!(request, BaseNodeRequestProto::GetChainMetadata(true));
+    let response = BaseNodeServiceResponse {
+        request_key,
+        response: Some(BaseNodeResponseProto::ChainMetadata(ChainMetadata {
+            height_of_longest_chain: Some(blocks.len() as u64),
+            best_block: None,
+            pruning_horizon: 0,
+            accumulated_difficulty: None,
+        })),
+    };
+    runtime
+        .block_on(base_node_response_sender.send(create_dummy_message(response, base_node_public_key)))
+        .unwrap();
+
+    let (request_key, request) = next_base_node_request(outbound_service);
+    match request {
+        BaseNodeRequestProto::FetchBlocks(block_heights) => {
+            assert_eq!(block_heights.heights, (1..=blocks.len() as u64).collect::<Vec<_>>())
+        },
+        _ => panic!("Unexpected base node request"),
+    }
+    let response = BaseNodeServiceResponse {
+        request_key,
+        response: Some(BaseNodeResponseProto::HistoricalBlocks(HistoricalBlocks { blocks })),
+    };
+    runtime
+        .block_on(base_node_response_sender.send(create_dummy_message(response, base_node_public_key)))
+        .unwrap();
+}
+
+fn historical_block(
+    height: u64,
+    outputs: Vec<TransactionOutput>,
+    spent: Vec<&TransactionOutput>,
+) -> ProtoHistoricalBlock
+{
+    ProtoHistoricalBlock {
+        confirmations: 1,
+        spent_commitments: spent.into_iter().map(|o| o.commitment.clone().into()).collect(),
+        block: Some(ProtoBlock {
+            header: Some(ProtoBlockHeader {
+                height,
+                ..Default::default()
+            }),
+            body: Some(ProtoAggregateBody {
+                inputs: vec![],
+                outputs: outputs.into_iter().map(Into::into).collect(),
+                kernels: vec![],
+            }),
+        }),
+    }
+}
+
+fn coinbase_output(height: u64, key: PrivateKey, factories: &CryptoFactories) -> TransactionOutput {
+    let value = EmissionSchedule::default().block_reward(height);
+    UnblindedOutput::new(value, key, Some(OutputFeatures::create_coinbase(height + 1)))
+        .as_transaction_output(factories)
+        .unwrap()
+}
+
+#[test]
+fn recover_coinbase_outputs_from_seed_words() {
+    let mut rng = rand::OsRng::new().unwrap();
+    let factories = CryptoFactories::default();
+    let mut runtime = Runtime::new().unwrap();
+
+    let (mut oms, _, _) = setup_output_manager_service(&mut runtime, OutputManagerMemoryDatabase::new());
+    let (mut recovery_service, outbound_service, mut base_node_response_sender) =
+        setup_recovery_service(&mut runtime, oms.clone());
+    let event_stream = recovery_service.get_event_stream_fused();
+
+    let key_manager = KeyManager::<PrivateKey, KeyDigest>::new(&mut rng);
+    let seed_words = from_secret_key(&key_manager.master_key, &MnemonicLanguage::English).unwrap();
+    let derived_key = |index| key_manager.derive_key(index).unwrap().k;
+
+    // The output of the key at index 2 is spent and the key at index 4 was never used
+    let output1 = coinbase_output(1, derived_key(1), &factories);
+    let output2 = coinbase_output(2, derived_key(2), &factories);
+    let output3 = coinbase_output(2, derived_key(3), &factories);
+    let output5 = coinbase_output(3, derived_key(5), &factories);
+    let other_output = coinbase_output(3, PrivateKey::random(&mut rng), &factories);
+    let blocks = vec![
+        historical_block(1, vec![output1], vec![]),
+        historical_block(2, vec![output2.clone(), output3], vec![&output2]),
+        historical_block(3, vec![output5, other_output], vec![]),
+    ];
+    let expected_value = EmissionSchedule::default().block_reward(1) +
+        EmissionSchedule::default().block_reward(2) +
+        EmissionSchedule::default().block_reward(3);
+
+    let base_node_public_key = PublicKey::from_secret_key(&PrivateKey::random(&mut rng));
+    assert_eq!(
+        runtime.block_on(recovery_service.start_recovery(seed_words.clone(), Some(1))),
+        Err(RecoveryServiceError::NoBaseNodePublicKey)
+    );
+    runtime
+        .block_on(recovery_service.set_base_node_public_key(base_node_public_key.clone()))
+        .unwrap();
+    runtime
+        .block_on(recovery_service.start_recovery(seed_words.clone(), Some(1)))
+        .unwrap();
+    assert_eq!(
+        runtime.block_on(recovery_service.start_recovery(seed_words, Some(1))),
+        Err(RecoveryServiceError::RecoveryInProgress)
+    );
+
+    respond_with_blocks(
+        &mut runtime,
+        &outbound_service,
+        &mut base_node_response_sender,
+        &base_node_public_key,
+        blocks,
+    );
+
+    let events = collect_stream!(
+        runtime,
+        event_stream.map(|i| (*i).clone()),
+        take = 5,
+        timeout = Duration::from_secs(10)
+    );
+    assert_eq!(events, vec![
+        RecoveryEvent::OutputRecovered((1, EmissionSchedule::default().block_reward(1))),
+        RecoveryEvent::OutputRecovered((2, EmissionSchedule::default().block_reward(2))),
+        RecoveryEvent::OutputRecovered((3, EmissionSchedule::default().block_reward(3))),
+        RecoveryEvent::Progress((3, 3)),
+        RecoveryEvent::RecoveryComplete((3, expected_value)),
+    ]);
+
+    assert_eq!(runtime.block_on(oms.get_unspent_outputs()).unwrap().len(), 3);
+    assert_eq!(
+        runtime.block_on(oms.get_balance()).unwrap().available_balance,
+        expected_value
+    );
+    assert_eq!(
+        runtime.block_on(oms.get_seed_words()).unwrap(),
+        from_secret_key(&key_manager.master_key, &MnemonicLanguage::English).unwrap()
+    );
+    // New keys follow on from the highest recovered key
+    assert_eq!(
+        runtime
+            .block_on(oms.get_recipient_spending_key(1, MicroTari::from(100)))
+            .unwrap(),
+        derived_key(6)
+    );
+}
+
+#[test]
+fn recover_rewindable_outputs_from_seed_words() {
+    let mut rng = rand::OsRng::new().unwrap();
+    let factories = CryptoFactories::default();
+    let mut runtime = Runtime::new().unwrap();
+
+    let (mut oms, _, _) = setup_output_manager_service(&mut runtime, OutputManagerMemoryDatabase::new());
+    let (mut recovery_service, outbound_service, mut base_node_response_sender) =
+        setup_recovery_service(&mut runtime, oms.clone());
+    let event_stream = recovery_service.get_event_stream_fused();
+
+    let key_manager = KeyManager::<PrivateKey, KeyDigest>::new(&mut rng);
+    let seed_words = from_secret_key(&key_manager.master_key, &MnemonicLanguage::English).unwrap();
+    let rewind_data = derive_rewind_data(&key_manager.master_key).unwrap();
+    let rewindable_output = |value: u64, index| {
+        UnblindedOutput::new(MicroTari::from(value), key_manager.derive_key(index).unwrap().k, None)
+            .as_rewindable_transaction_output(&factories, &rewind_data)
+            .unwrap()
+    };
+
+    // Outputs with arbitrary values can be found because their range proofs are rewound. An output rewindable by
+    // another wallet is ignored.
+    let other_rewind_data = derive_rewind_data(&PrivateKey::random(&mut rng)).unwrap();
+    let other_output = UnblindedOutput::new(MicroTari::from(300), PrivateKey::random(&mut rng), None)
+        .as_rewindable_transaction_output(&factories, &other_rewind_data)
+        .unwrap();
+    let blocks = vec![
+        historical_block(1, vec![rewindable_output(1234, 1)], vec![]),
+        historical_block(2, vec![rewindable_output(5678, 2), other_output], vec![]),
+    ];
+
+    let base_node_public_key = PublicKey::from_secret_key(&PrivateKey::random(&mut rng));
+    runtime
+        .block_on(recovery_service.set_base_node_public_key(base_node_public_key.clone()))
+        .unwrap();
+    runtime
+        .block_on(recovery_service.start_recovery(seed_words, Some(1)))
+        .unwrap();
+    respond_with_blocks(
+        &mut runtime,
+        &outbound_service,
+        &mut base_node_response_sender,
+        &base_node_public_key,
+        blocks,
+    );
+
+    let events = collect_stream!(
+        runtime,
+        event_stream.map(|i| (*i).clone()),
+        take = 4,
+        timeout = Duration::from_secs(10)
+    );
+    assert_eq!(events, vec![
+        RecoveryEvent::OutputRecovered((1, MicroTari::from(1234))),
+        RecoveryEvent::OutputRecovered((2, MicroTari::from(5678))),
+        RecoveryEvent::Progress((2, 2)),
+        RecoveryEvent::RecoveryComplete((2, MicroTari::from(1234 + 5678))),
+    ]);
+    assert_eq!(
+        runtime.block_on(oms.get_balance()).unwrap().available_balance,
+        MicroTari::from(1234 + 5678)
+    );
+    assert_eq!(runtime.block_on(oms.get_rewind_data()).unwrap(), rewind_data);
+}
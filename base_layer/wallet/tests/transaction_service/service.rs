@@ -24,30 +24,64 @@ use crate::support::{
     comms_and_services::{create_dummy_message, setup_comms_services},
     utils::{make_input, random_string, TestParams},
 };
+use chrono::{Duration as ChronoDuration, Utc};
 use futures::{
     channel::{mpsc, mpsc::Sender},
+    stream,
     SinkExt,
 };
 use prost::Message;
 use rand::OsRng;
-use std::{convert::TryInto, sync::Arc, time::Duration};
+use std::{convert::TryInto, sync::Arc, thread, time::Duration};
 use tari_broadcast_channel::bounded;
+use tari_common::Network;
 use tari_comms::{
     builder::CommsNode,
     message::EnvelopeBody,
     peer_manager::{NodeIdentity, PeerFeatures},
 };
-use tari_comms_dht::outbound::mock::{create_outbound_service_mock, OutboundServiceMockState};
-use tari_core::transactions::{
-    tari_amount::*,
-    transaction::{KernelBuilder, KernelFeatures, OutputFeatures, Transaction, TransactionOutput},
-    transaction_protocol::{
-        proto,
-        recipient::{RecipientSignedMessage, RecipientState},
-        sender::TransactionSenderMessage,
+use tari_comms_dht::{
+    domain_message::MessageHeader,
+    outbound::{
+        mock::{create_outbound_service_mock, OutboundServiceMockState},
+        SendMessageResponse,
+    },
+};
+use tari_core::{
+    base_node::proto::base_node::{
+        base_node_service_request::Request as BaseNodeRequestProto,
+        base_node_service_response::Response as BaseNodeResponseProto,
+        BaseNodeServiceRequest,
+        BaseNodeServiceResponse,
+        ChainMetadata,
+        KernelHeight,
+        KernelHeights,
+    },
+    mempool::proto::mempool::{
+        mempool_service_response::Response as MempoolResponseProto,
+        MempoolServiceRequest,
+        MempoolServiceResponse,
+        TxStorageResponse,
+    },
+    transactions::{
+        proto::types::Transaction as ProtoTransaction,
+        tari_amount::*,
+        transaction::{
+            KernelBuilder,
+            KernelFeatures,
+            OutputFeatures,
+            Transaction,
+            TransactionKernel,
+            TransactionOutput,
+        },
+        transaction_protocol::{
+            proto,
+            recipient::{RecipientSignedMessage, RecipientState},
+            sender::TransactionSenderMessage,
+        },
+        types::{CryptoFactories, PrivateKey, PublicKey, RangeProof, Signature},
+        ReceiverTransactionProtocol,
     },
-    types::{CryptoFactories, PrivateKey, PublicKey, RangeProof, Signature},
-    ReceiverTransactionProtocol,
 };
 use tari_crypto::{
     commitment::HomomorphicCommitmentFactory,
@@ -57,22 +91,28 @@ use tari_p2p::{
     comms_connector::pubsub_connector,
     domain_message::DomainMessage,
     services::comms_outbound::CommsOutboundServiceInitializer,
+    tari_message::TariMessageType,
 };
 use tari_service_framework::{reply_channel, StackBuilder};
 use tari_test_utils::{collect_stream, paths::with_temp_dir};
+use tari_utilities::hash::Hashable;
 use tari_wallet::{
+    error::PaymentRequestError,
     output_manager_service::{
+        config::OutputManagerServiceConfig,
         handle::OutputManagerHandle,
         service::OutputManagerService,
         storage::{database::OutputManagerDatabase, memory_db::OutputManagerMemoryDatabase},
         OutputManagerServiceInitializer,
     },
+    payment_request::PaymentRequest,
     transaction_service::{
+        config::TransactionServiceConfig,
         error::TransactionServiceError,
         handle::{TransactionEvent, TransactionServiceHandle},
         service::TransactionService,
         storage::{
-            database::{TransactionBackend, TransactionDatabase},
+            database::{OfflineTransaction, TransactionBackend, TransactionDatabase, TransactionStatus},
             memory_db::TransactionMemoryDatabase,
             sqlite_db::TransactionServiceSqliteDatabase,
         },
@@ -115,10 +155,13 @@ pub fn setup_transaction_service<T: TransactionBackend + Clone + 'static>(
     let fut = StackBuilder::new(runtime.handle().clone(), comms.shutdown_signal())
         .add_initializer(CommsOutboundServiceInitializer::new(dht.outbound_requester()))
         .add_initializer(OutputManagerServiceInitializer::new(
+            OutputManagerServiceConfig::default(),
+            subscription_factory.clone(),
             OutputManagerMemoryDatabase::new(),
             factories.clone(),
         ))
         .add_initializer(TransactionServiceInitializer::new(
+            TransactionServiceConfig::default(),
             subscription_factory,
             backend,
             comms.node_identity().clone(),
@@ -147,16 +190,49 @@ pub fn setup_transaction_service_no_comms<T: TransactionBackend + Clone + 'stati
     Sender<DomainMessage<proto::TransactionSenderMessage>>,
     Sender<DomainMessage<proto::RecipientSignedMessage>>,
     Sender<DomainMessage<proto::TransactionFinalizedMessage>>,
+    Sender<DomainMessage<proto::TransactionCancelledMessage>>,
+    Sender<DomainMessage<MempoolServiceResponse>>,
+    Sender<DomainMessage<BaseNodeServiceResponse>>,
+)
+{
+    setup_transaction_service_no_comms_with_config(runtime, factories, backend, TransactionServiceConfig::default())
+}
+
+/// As `setup_transaction_service_no_comms` but with a custom `TransactionServiceConfig`
+pub fn setup_transaction_service_no_comms_with_config<T: TransactionBackend + Clone + 'static>(
+    runtime: &Runtime,
+    factories: CryptoFactories,
+    backend: T,
+    config: TransactionServiceConfig,
+) -> (
+    TransactionServiceHandle,
+    OutputManagerHandle,
+    OutboundServiceMockState,
+    Sender<DomainMessage<proto::TransactionSenderMessage>>,
+    Sender<DomainMessage<proto::RecipientSignedMessage>>,
+    Sender<DomainMessage<proto::TransactionFinalizedMessage>>,
+    Sender<DomainMessage<proto::TransactionCancelledMessage>>,
+    Sender<DomainMessage<MempoolServiceResponse>>,
+    Sender<DomainMessage<BaseNodeServiceResponse>>,
 )
 {
+    let (outbound_message_requester, mock_outbound_service) = create_outbound_service_mock(20);
+    let outbound_mock_state = mock_outbound_service.get_state();
+    runtime.spawn(mock_outbound_service.run());
+
     let (oms_request_sender, oms_request_receiver) = reply_channel::unbounded();
+    let (oms_event_publisher, oms_event_subscriber) = bounded(100);
     let output_manager_service = OutputManagerService::new(
+        OutputManagerServiceConfig::default(),
+        outbound_message_requester.clone(),
         oms_request_receiver,
+        stream::empty::<DomainMessage<BaseNodeServiceResponse>>(),
         OutputManagerDatabase::new(OutputManagerMemoryDatabase::new()),
+        oms_event_publisher,
         factories.clone(),
     )
     .unwrap();
-    let output_manager_service_handle = OutputManagerHandle::new(oms_request_sender);
+    let output_manager_service_handle = OutputManagerHandle::new(oms_request_sender, oms_event_subscriber);
 
     let (ts_request_sender, ts_request_receiver) = reply_channel::unbounded();
     let (event_publisher, event_subscriber) = bounded(100);
@@ -164,17 +240,20 @@ pub fn setup_transaction_service_no_comms<T: TransactionBackend + Clone + 'stati
     let (tx_sender, tx_receiver) = mpsc::channel(20);
     let (tx_ack_sender, tx_ack_receiver) = mpsc::channel(20);
     let (tx_finalized_sender, tx_finalized_receiver) = mpsc::channel(20);
-
-    let (outbound_message_requester, mock_outbound_service) = create_outbound_service_mock(20);
-    let outbound_mock_state = mock_outbound_service.get_state();
-    runtime.spawn(mock_outbound_service.run());
+    let (tx_cancelled_sender, tx_cancelled_receiver) = mpsc::channel(20);
+    let (mempool_response_sender, mempool_response_receiver) = mpsc::channel(20);
+    let (base_node_response_sender, base_node_response_receiver) = mpsc::channel(20);
 
     let ts_service = TransactionService::new(
+        config,
         TransactionDatabase::new(backend),
         ts_request_receiver,
         tx_receiver,
         tx_ack_receiver,
         tx_finalized_receiver,
+        tx_cancelled_receiver,
+        mempool_response_receiver,
+        base_node_response_receiver,
         output_manager_service_handle.clone(),
         outbound_message_requester.clone(),
         event_publisher,
@@ -197,6 +276,9 @@ pub fn setup_transaction_service_no_comms<T: TransactionBackend + Clone + 'stati
         tx_sender,
         tx_ack_sender,
         tx_finalized_sender,
+        tx_cancelled_sender,
+        mempool_response_sender,
+        base_node_response_sender,
     )
 }
 
@@ -563,9 +645,18 @@ fn test_sending_repeated_tx_ids<T: TransactionBackend + Clone + 'static>(alice_b
     )
     .unwrap();
 
-    let (alice_ts, _alice_output_manager, alice_outbound_service, mut alice_tx_sender, _alice_tx_ack_sender, _) =
-        setup_transaction_service_no_comms(&runtime, factories.clone(), alice_backend);
-    let (_bob_ts, mut bob_output_manager, _bob_outbound_service, _bob_tx_sender, _bob_tx_ack_sender, _) =
+    let (
+        alice_ts,
+        _alice_output_manager,
+        alice_outbound_service,
+        mut alice_tx_sender,
+        _alice_tx_ack_sender,
+        _,
+        _,
+        _,
+        _,
+    ) = setup_transaction_service_no_comms(&runtime, factories.clone(), alice_backend);
+    let (_bob_ts, mut bob_output_manager, _bob_outbound_service, _bob_tx_sender, _bob_tx_ack_sender, _, _, _, _) =
         setup_transaction_service_no_comms(&runtime, factories.clone(), bob_backend);
     let alice_event_stream = alice_ts.get_event_stream_fused();
 
@@ -590,6 +681,33 @@ fn test_sending_repeated_tx_ids<T: TransactionBackend + Clone + 'static>(alice_b
     runtime.block_on(alice_tx_sender.send(tx_message.clone())).unwrap();
     runtime.block_on(alice_tx_sender.send(tx_message.clone())).unwrap();
 
+    // A repeated message from the sender means that it has not received the reply, so the same reply is sent again
+    alice_outbound_service
+        .wait_call_count(2, Duration::from_secs(10))
+        .unwrap();
+    let replies = alice_outbound_service
+        .take_calls()
+        .into_iter()
+        .map(|(_, body)| {
+            EnvelopeBody::decode(body.as_slice())
+                .unwrap()
+                .decode_part::<proto::RecipientSignedMessage>(1)
+                .unwrap()
+                .unwrap()
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(replies.len(), 2);
+    assert_eq!(replies[0], replies[1]);
+    assert_eq!(replies[0].tx_id, msg.tx_id);
+
+    // The same tx_id from anyone else is rejected
+    let (_p, other_pub_key) = PublicKey::random_keypair(&mut rng);
+    let other_tx_message = create_dummy_message(
+        TransactionSenderMessage::Single(Box::new(msg.clone())).into(),
+        &other_pub_key,
+    );
+    runtime.block_on(alice_tx_sender.send(other_tx_message)).unwrap();
+
     let result = collect_stream!(
         runtime,
         alice_event_stream.map(|i| (*i).clone()),
@@ -597,19 +715,18 @@ fn test_sending_repeated_tx_ids<T: TransactionBackend + Clone + 'static>(alice_b
         timeout = Duration::from_secs(10)
     );
 
-    alice_outbound_service
-        .wait_call_count(1, Duration::from_secs(10))
-        .unwrap();
-
     assert_eq!(result.len(), 2);
-    assert!(result
-        .iter()
-        .find(|i| if let TransactionEvent::ReceivedTransaction(_) = i {
-            true
-        } else {
-            false
-        })
-        .is_some());
+    assert_eq!(
+        result
+            .iter()
+            .filter(|i| if let TransactionEvent::ReceivedTransaction(_) = i {
+                true
+            } else {
+                false
+            })
+            .count(),
+        1
+    );
     assert!(result
         .iter()
         .find(|i| if let TransactionEvent::Error(s) = i {
@@ -618,6 +735,7 @@ fn test_sending_repeated_tx_ids<T: TransactionBackend + Clone + 'static>(alice_b
             false
         })
         .is_some());
+    assert_eq!(alice_outbound_service.call_count(), 0);
 }
 
 #[test]
@@ -640,6 +758,85 @@ fn test_sending_repeated_tx_ids_sqlite_db() {
     });
 }
 
+fn test_resend_pending_transactions<T: TransactionBackend + Clone + 'static>(alice_backend: T) {
+    let mut runtime = create_runtime();
+    let mut rng = OsRng::new().unwrap();
+    let factories = CryptoFactories::default();
+
+    let (_p, bob_public_key) = PublicKey::random_keypair(&mut rng);
+    let (
+        mut alice_ts,
+        mut alice_output_manager,
+        alice_outbound_service,
+        _alice_tx_sender,
+        _alice_tx_ack_sender,
+        _,
+        _,
+        _,
+        _,
+    ) = setup_transaction_service_no_comms(&runtime, factories.clone(), alice_backend);
+
+    let (_utxo, uo) = make_input(&mut rng, MicroTari(250000), &factories.commitment);
+    runtime.block_on(alice_output_manager.add_output(uo)).unwrap();
+
+    assert_eq!(runtime.block_on(alice_ts.resend_pending_transactions()).unwrap(), 0);
+
+    let tx_id = runtime
+        .block_on(alice_ts.send_transaction(
+            bob_public_key.clone(),
+            MicroTari::from(500),
+            MicroTari::from(1000),
+            "".to_string(),
+        ))
+        .unwrap();
+    alice_outbound_service
+        .wait_call_count(1, Duration::from_secs(10))
+        .unwrap();
+
+    assert_eq!(runtime.block_on(alice_ts.resend_pending_transactions()).unwrap(), 1);
+    alice_outbound_service
+        .wait_call_count(2, Duration::from_secs(10))
+        .unwrap();
+    let sender_messages = alice_outbound_service
+        .take_calls()
+        .into_iter()
+        .map(|(_, body)| {
+            EnvelopeBody::decode(body.as_slice())
+                .unwrap()
+                .decode_part::<proto::TransactionSenderMessage>(1)
+                .unwrap()
+                .unwrap()
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(sender_messages.len(), 2);
+    assert_eq!(sender_messages[0], sender_messages[1]);
+    let sender_message: TransactionSenderMessage = sender_messages[0].clone().try_into().unwrap();
+    match sender_message {
+        TransactionSenderMessage::Single(data) => assert_eq!(data.tx_id, tx_id),
+        _ => assert!(false, "Expected a single round sender message"),
+    }
+
+    // A cancelled transaction is no longer resent
+    runtime.block_on(alice_ts.cancel_transaction(tx_id)).unwrap();
+    let _ = alice_outbound_service.take_calls();
+    assert_eq!(runtime.block_on(alice_ts.resend_pending_transactions()).unwrap(), 0);
+}
+
+#[test]
+fn test_resend_pending_transactions_memory_db() {
+    test_resend_pending_transactions(TransactionMemoryDatabase::new());
+}
+
+#[test]
+fn test_resend_pending_transactions_sqlite_db() {
+    with_temp_dir(|dir_path| {
+        let path_string = dir_path.to_str().unwrap().to_string();
+        let alice_db_name = format!("{}.sqlite3", random_string(8).as_str());
+        let alice_db_path = format!("{}/{}", path_string, alice_db_name);
+        test_resend_pending_transactions(TransactionServiceSqliteDatabase::new(alice_db_path).unwrap());
+    });
+}
+
 fn test_accepting_unknown_tx_id_and_malformed_reply<T: TransactionBackend + Clone + 'static>(alice_backend: T) {
     let mut runtime = create_runtime();
     let mut rng = OsRng::new().unwrap();
@@ -651,8 +848,17 @@ fn test_accepting_unknown_tx_id_and_malformed_reply<T: TransactionBackend + Clon
         PeerFeatures::COMMUNICATION_NODE,
     )
     .unwrap();
-    let (mut alice_ts, mut alice_output_manager, alice_outbound_service, _alice_tx_sender, mut alice_tx_ack_sender, _) =
-        setup_transaction_service_no_comms(&runtime, factories.clone(), alice_backend);
+    let (
+        mut alice_ts,
+        mut alice_output_manager,
+        alice_outbound_service,
+        _alice_tx_sender,
+        mut alice_tx_ack_sender,
+        _,
+        _,
+        _,
+        _,
+    ) = setup_transaction_service_no_comms(&runtime, factories.clone(), alice_backend);
 
     let alice_event_stream = alice_ts.get_event_stream_fused();
 
@@ -746,6 +952,9 @@ fn finalize_tx_with_nonexistent_txid<T: TransactionBackend + Clone + 'static>(al
         _alice_tx_sender,
         _alice_tx_ack_sender,
         mut alice_tx_finalized,
+        _,
+        _,
+        _,
     ) = setup_transaction_service_no_comms(&runtime, factories.clone(), alice_backend);
     let alice_event_stream = alice_ts.get_event_stream_fused();
 
@@ -804,6 +1013,9 @@ fn finalize_tx_with_incorrect_pubkey<T: TransactionBackend + Clone + 'static>(al
         mut alice_tx_sender,
         _alice_tx_ack_sender,
         mut alice_tx_finalized,
+        _,
+        _,
+        _,
     ) = setup_transaction_service_no_comms(&runtime, factories.clone(), alice_backend);
     let alice_event_stream = alice_ts.get_event_stream_fused();
 
@@ -813,7 +1025,7 @@ fn finalize_tx_with_incorrect_pubkey<T: TransactionBackend + Clone + 'static>(al
         PeerFeatures::COMMUNICATION_NODE,
     )
     .unwrap();
-    let (_bob_ts, mut bob_output_manager, _bob_outbound_service, _bob_tx_sender, _bob_tx_ack_sender, _) =
+    let (_bob_ts, mut bob_output_manager, _bob_outbound_service, _bob_tx_sender, _bob_tx_ack_sender, _, _, _, _) =
         setup_transaction_service_no_comms(&runtime, factories.clone(), bob_backend);
 
     let (_utxo, uo) = make_input(&mut rng, MicroTari(250000), &factories.commitment);
@@ -912,6 +1124,9 @@ fn finalize_tx_with_missing_output<T: TransactionBackend + Clone + 'static>(alic
         mut alice_tx_sender,
         _alice_tx_ack_sender,
         mut alice_tx_finalized,
+        _,
+        _,
+        _,
     ) = setup_transaction_service_no_comms(&runtime, factories.clone(), alice_backend);
     let alice_event_stream = alice_ts.get_event_stream_fused();
 
@@ -921,7 +1136,7 @@ fn finalize_tx_with_missing_output<T: TransactionBackend + Clone + 'static>(alic
         PeerFeatures::COMMUNICATION_NODE,
     )
     .unwrap();
-    let (_bob_ts, mut bob_output_manager, _bob_outbound_service, _bob_tx_sender, _bob_tx_ack_sender, _) =
+    let (_bob_ts, mut bob_output_manager, _bob_outbound_service, _bob_tx_sender, _bob_tx_ack_sender, _, _, _, _) =
         setup_transaction_service_no_comms(&runtime, factories.clone(), bob_backend);
 
     let (_utxo, uo) = make_input(&mut rng, MicroTari(250000), &factories.commitment);
@@ -1007,125 +1222,732 @@ fn finalize_tx_with_missing_output_sqlite_db() {
     });
 }
 
-#[test]
-fn discovery_async_return_test() {
-    let db_tempdir = TempDir::new(random_string(8).as_str()).unwrap();
-    let db_folder = db_tempdir.path().to_str().unwrap().to_string();
-
-    let mut runtime = Runtime::new().unwrap();
-    let mut rng = OsRng::new().unwrap();
+fn send_transaction_to_multiple_recipients<T: TransactionBackend + Clone + 'static>(alice_backend: T) {
+    let mut runtime = create_runtime();
     let factories = CryptoFactories::default();
+    let mut rng = OsRng::new().unwrap();
 
-    let alice_backend = TransactionMemoryDatabase::new();
-    let bob_backend = TransactionMemoryDatabase::new();
-    let dave_backend = TransactionMemoryDatabase::new();
-    let port_offset = 1;
-
-    // Alice's parameters
-    let alice_port = 30484 + port_offset;
-    let alice_node_identity = NodeIdentity::random(
-        &mut rng,
-        format!("/ip4/127.0.0.1/tcp/{}", alice_port).parse().unwrap(),
-        PeerFeatures::COMMUNICATION_NODE,
-    )
-    .unwrap();
-
-    // Bob's parameters
-    let bob_port = 30475 + port_offset;
-    let bob_node_identity = NodeIdentity::random(
-        &mut rng,
-        format!("/ip4/127.0.0.1/tcp/{}", bob_port).parse().unwrap(),
-        PeerFeatures::COMMUNICATION_NODE,
-    )
-    .unwrap();
+    let (mut alice_ts, mut alice_output_manager, alice_outbound_service, _, mut alice_tx_ack_sender, _, _, _, _) =
+        setup_transaction_service_no_comms(&runtime, factories.clone(), alice_backend);
 
-    // Carols's parameters
-    let carol_port = 30488 + port_offset;
-    let carol_node_identity = NodeIdentity::random(
-        &mut rng,
-        format!("/ip4/127.0.0.1/tcp/{}", carol_port).parse().unwrap(),
-        PeerFeatures::COMMUNICATION_NODE,
-    )
-    .unwrap();
+    let (_utxo, uo) = make_input(&mut rng, MicroTari(250000), &factories.commitment);
+    runtime.block_on(alice_output_manager.add_output(uo)).unwrap();
 
-    // Dave's parameters
-    let dave_port = 30498 + port_offset;
-    let dave_node_identity = NodeIdentity::random(
-        &mut rng,
-        format!("/ip4/127.0.0.1/tcp/{}", dave_port).parse().unwrap(),
-        PeerFeatures::COMMUNICATION_NODE,
-    )
-    .unwrap();
+    let recipients = (0..2)
+        .map(|i| {
+            NodeIdentity::random(
+                &mut rng,
+                format!("/ip4/127.0.0.1/tcp/{}", 31590 + i).parse().unwrap(),
+                PeerFeatures::COMMUNICATION_NODE,
+            )
+            .unwrap()
+        })
+        .collect::<Vec<_>>();
+    let amounts = vec![MicroTari::from(500), MicroTari::from(700)];
+    runtime
+        .block_on(
+            alice_ts.send_transaction_to_multiple_recipients(
+                recipients
+                    .iter()
+                    .map(|n| n.public_key().clone())
+                    .zip(amounts.iter().cloned())
+                    .collect(),
+                MicroTari::from(1000),
+                "".to_string(),
+            ),
+        )
+        .unwrap();
 
-    let (mut alice_ts, mut alice_oms, _alice_comms) = setup_transaction_service(
-        &mut runtime,
-        alice_node_identity.clone(),
-        vec![bob_node_identity.clone()],
-        factories.clone(),
-        alice_backend,
-        db_folder.clone(),
-        Duration::from_secs(1),
+    alice_outbound_service
+        .wait_call_count(2, Duration::from_secs(10))
+        .unwrap();
+    let sender_messages = decode_outbound_messages::<proto::TransactionSenderMessage, _>(
+        &alice_outbound_service.take_calls(),
+        TariMessageType::SenderPartialTransaction,
     );
-    let alice_event_stream = alice_ts.get_event_stream_fused();
+    assert_eq!(sender_messages.len(), 2);
+
+    let pending_outbound_txs = runtime.block_on(alice_ts.get_pending_outbound_transactions()).unwrap();
+    assert_eq!(pending_outbound_txs.len(), 1);
+    let (tx_id, outbound_tx) = pending_outbound_txs.into_iter().next().unwrap();
+    assert_eq!(outbound_tx.amount, MicroTari::from(1200));
+    assert_eq!(outbound_tx.recipients.len(), 2);
+
+    // Each recipient replies to its own part of the transaction
+    let replies = sender_messages
+        .into_iter()
+        .map(|msg| {
+            let params = TestParams::new(&mut rng);
+            let rtp = ReceiverTransactionProtocol::new(
+                msg.try_into().unwrap(),
+                params.nonce,
+                params.spend_key,
+                OutputFeatures::default(),
+                &factories,
+            );
+            rtp.get_signed_data().unwrap().clone()
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(replies[0].tx_id, tx_id);
+    assert_eq!(replies[1].tx_id, outbound_tx.recipients[1].tx_id);
 
-    let (_bob_ts, _bob_oms, _bob_comms) = setup_transaction_service(
-        &mut runtime,
-        bob_node_identity.clone(),
-        vec![
-            alice_node_identity.clone(),
-            carol_node_identity.clone(),
-            dave_node_identity.clone(),
-        ],
-        factories.clone(),
-        bob_backend,
-        db_folder.clone(),
-        Duration::from_secs(1),
-    );
+    // A reply from a peer that is not the recipient of that part of the transaction is rejected
+    runtime
+        .block_on(alice_tx_ack_sender.send(create_dummy_message(
+            replies[1].clone().into(),
+            recipients[0].public_key(),
+        )))
+        .unwrap();
+    runtime
+        .block_on(alice_tx_ack_sender.send(create_dummy_message(
+            replies[0].clone().into(),
+            recipients[0].public_key(),
+        )))
+        .unwrap();
+    thread::sleep(Duration::from_secs(2));
 
-    let (_utxo, uo1a) = make_input(&mut rng, MicroTari(5500), &factories.commitment);
-    runtime.block_on(alice_oms.add_output(uo1a)).unwrap();
-    let (_utxo, uo1b) = make_input(&mut rng, MicroTari(3000), &factories.commitment);
-    runtime.block_on(alice_oms.add_output(uo1b)).unwrap();
-    let (_utxo, uo1c) = make_input(&mut rng, MicroTari(3000), &factories.commitment);
-    runtime.block_on(alice_oms.add_output(uo1c)).unwrap();
+    // The transaction waits for the second recipient
+    let pending_outbound_txs = runtime.block_on(alice_ts.get_pending_outbound_transactions()).unwrap();
+    assert!(pending_outbound_txs
+        .get(&tx_id)
+        .unwrap()
+        .sender_protocol
+        .is_collecting_multiple_signatures());
+    assert!(runtime
+        .block_on(alice_ts.get_completed_transactions())
+        .unwrap()
+        .is_empty());
 
-    let initial_balance = runtime.block_on(alice_oms.get_balance()).unwrap();
+    runtime
+        .block_on(alice_tx_ack_sender.send(create_dummy_message(
+            replies[1].clone().into(),
+            recipients[1].public_key(),
+        )))
+        .unwrap();
 
-    let value_a_to_c_1 = MicroTari::from(1400);
+    // Each recipient is sent the finalized transaction under the tx_id of its part of it
+    alice_outbound_service
+        .wait_call_count(2, Duration::from_secs(10))
+        .unwrap();
+    let finalized_messages = decode_outbound_messages::<proto::TransactionFinalizedMessage, _>(
+        &alice_outbound_service.take_calls(),
+        TariMessageType::TransactionFinalized,
+    );
+    assert_eq!(
+        finalized_messages.iter().map(|m| m.tx_id).collect::<Vec<_>>(),
+        replies.iter().map(|r| r.tx_id).collect::<Vec<_>>()
+    );
 
-    let tx_id = match runtime.block_on(alice_ts.send_transaction(
-        carol_node_identity.public_key().clone(),
-        value_a_to_c_1,
-        MicroTari::from(20),
-        "Discovery Tx!".to_string(),
-    )) {
-        Err(TransactionServiceError::OutboundSendDiscoveryInProgress(tx_id)) => tx_id,
-        _ => {
-            assert!(false, "Send should not succeed as Peer is not known");
-            0u64
-        },
-    };
-    assert_ne!(initial_balance, runtime.block_on(alice_oms.get_balance()).unwrap());
+    assert!(runtime
+        .block_on(alice_ts.get_pending_outbound_transactions())
+        .unwrap()
+        .is_empty());
+    let completed_txs = runtime.block_on(alice_ts.get_completed_transactions()).unwrap();
+    let completed_tx = completed_txs.get(&tx_id).unwrap();
+    assert_eq!(completed_tx.amount, MicroTari::from(1200));
+    assert_eq!(completed_tx.transaction.body.kernels().len(), 2);
+    for reply in replies.iter() {
+        assert!(completed_tx.transaction.body.outputs().contains(&reply.output));
+    }
+}
 
-    assert!(collect_stream!(
-        runtime,
-        alice_event_stream.map(|i| (*i).clone()),
-        take = 1,
-        timeout = Duration::from_secs(10)
-    )
-    .iter()
-    .find(
-        |i| if let TransactionEvent::TransactionSendDiscoveryComplete(t, result) = i {
-            t == &tx_id && !(*result)
-        } else {
-            false
-        }
-    )
-    .is_some());
+#[test]
+fn send_transaction_to_multiple_recipients_memory_db() {
+    send_transaction_to_multiple_recipients(TransactionMemoryDatabase::new());
+}
 
-    assert_eq!(initial_balance, runtime.block_on(alice_oms.get_balance()).unwrap());
+#[test]
+fn send_transaction_to_multiple_recipients_sqlite_db() {
+    with_temp_dir(|dir_path| {
+        let path_string = dir_path.to_str().unwrap().to_string();
+        let alice_db_name = format!("{}.sqlite3", random_string(8).as_str());
+        let alice_db_path = format!("{}/{}", path_string, alice_db_name);
+        send_transaction_to_multiple_recipients(TransactionServiceSqliteDatabase::new(alice_db_path).unwrap());
+    });
+}
 
-    let (_dave_ts, _dave_oms, _dave_comms) = setup_transaction_service(
+fn send_transaction_to_multiple_recipients_failure<T: TransactionBackend + Clone + 'static>(alice_backend: T) {
+    let mut runtime = create_runtime();
+    let factories = CryptoFactories::default();
+    let mut rng = OsRng::new().unwrap();
+
+    let (mut alice_ts, mut alice_output_manager, alice_outbound_service, _, _, _, _, _, _) =
+        setup_transaction_service_no_comms(&runtime, factories.clone(), alice_backend);
+
+    let (_utxo, uo) = make_input(&mut rng, MicroTari(250000), &factories.commitment);
+    runtime.block_on(alice_output_manager.add_output(uo)).unwrap();
+
+    let recipients = (0..2)
+        .map(|i| {
+            NodeIdentity::random(
+                &mut rng,
+                format!("/ip4/127.0.0.1/tcp/{}", 31600 + i).parse().unwrap(),
+                PeerFeatures::COMMUNICATION_NODE,
+            )
+            .unwrap()
+        })
+        .collect::<Vec<_>>();
+
+    // The direct send to the first recipient fails and no peers take its message to be stored and forwarded
+    alice_outbound_service.set_next_response(SendMessageResponse::Failed);
+    match runtime.block_on(
+        alice_ts.send_transaction_to_multiple_recipients(
+            recipients
+                .iter()
+                .map(|n| n.public_key().clone())
+                .zip(vec![MicroTari::from(500), MicroTari::from(700)])
+                .collect(),
+            MicroTari::from(1000),
+            "".to_string(),
+        ),
+    ) {
+        Err(TransactionServiceError::OutboundSendFailure) => (),
+        r => assert!(false, "Unexpected result: {:?}", r),
+    }
+
+    // Both recipients are told that the transaction has been cancelled
+    alice_outbound_service
+        .wait_call_count(5, Duration::from_secs(10))
+        .unwrap();
+    let calls = alice_outbound_service.take_calls();
+    let sender_messages = decode_outbound_messages::<proto::TransactionSenderMessage, _>(
+        &calls,
+        TariMessageType::SenderPartialTransaction,
+    );
+    assert_eq!(sender_messages.len(), 3);
+    let cancelled_messages = decode_outbound_messages::<proto::TransactionCancelledMessage, _>(
+        &calls,
+        TariMessageType::TransactionCancelled,
+    );
+    assert_eq!(cancelled_messages.len(), 2);
+
+    assert!(runtime
+        .block_on(alice_ts.get_pending_outbound_transactions())
+        .unwrap()
+        .is_empty());
+    let balance = runtime.block_on(alice_output_manager.get_balance()).unwrap();
+    assert_eq!(balance.available_balance, MicroTari(250000));
+    assert_eq!(balance.pending_outgoing_balance, MicroTari::from(0));
+}
+
+#[test]
+fn send_transaction_to_multiple_recipients_failure_memory_db() {
+    send_transaction_to_multiple_recipients_failure(TransactionMemoryDatabase::new());
+}
+
+#[test]
+fn send_transaction_to_multiple_recipients_failure_sqlite_db() {
+    with_temp_dir(|dir_path| {
+        let path_string = dir_path.to_str().unwrap().to_string();
+        let alice_db_name = format!("{}.sqlite3", random_string(8).as_str());
+        let alice_db_path = format!("{}/{}", path_string, alice_db_name);
+        send_transaction_to_multiple_recipients_failure(TransactionServiceSqliteDatabase::new(alice_db_path).unwrap());
+    });
+}
+
+#[test]
+fn pay_payment_request() {
+    let mut runtime = create_runtime();
+    let factories = CryptoFactories::default();
+    let mut rng = OsRng::new().unwrap();
+
+    let (mut alice_ts, mut alice_output_manager, alice_outbound_service, _, _, _, _, _, _) =
+        setup_transaction_service_no_comms(&runtime, factories.clone(), TransactionMemoryDatabase::new());
+
+    let (_utxo, uo) = make_input(&mut rng, MicroTari(250000), &factories.commitment);
+    runtime.block_on(alice_output_manager.add_output(uo)).unwrap();
+
+    let bob_public_key = PublicKey::from_secret_key(&PrivateKey::random(&mut rng));
+    let request = PaymentRequest::new(Network::TestNet, bob_public_key)
+        .with_amount(MicroTari::from(5000))
+        .with_message("Invoice 42".to_string());
+
+    // Requests for another network, expired requests and other amounts than the requested one are rejected
+    let mut mainnet_request = request.clone();
+    mainnet_request.network = Network::MainNet;
+    match runtime.block_on(alice_ts.pay_payment_request(mainnet_request, None, MicroTari::from(20))) {
+        Err(TransactionServiceError::PaymentRequestError(PaymentRequestError::NetworkMismatch)) => (),
+        r => assert!(false, "Unexpected result: {:?}", r),
+    }
+    let expired_request = request
+        .clone()
+        .with_expiry(Utc::now().naive_utc() - ChronoDuration::seconds(10));
+    match runtime.block_on(alice_ts.pay_payment_request(expired_request, None, MicroTari::from(20))) {
+        Err(TransactionServiceError::PaymentRequestError(PaymentRequestError::Expired)) => (),
+        r => assert!(false, "Unexpected result: {:?}", r),
+    }
+    match runtime.block_on(alice_ts.pay_payment_request(
+        request.clone(),
+        Some(MicroTari::from(4000)),
+        MicroTari::from(20),
+    )) {
+        Err(TransactionServiceError::PaymentRequestError(PaymentRequestError::AmountMismatch)) => (),
+        r => assert!(false, "Unexpected result: {:?}", r),
+    }
+    assert!(runtime
+        .block_on(alice_ts.get_pending_outbound_transactions())
+        .unwrap()
+        .is_empty());
+
+    let tx_id = runtime
+        .block_on(alice_ts.pay_payment_request(request, None, MicroTari::from(20)))
+        .unwrap();
+    alice_outbound_service
+        .wait_call_count(1, Duration::from_secs(10))
+        .unwrap();
+    let pending_outbound_txs = runtime.block_on(alice_ts.get_pending_outbound_transactions()).unwrap();
+    let outbound_tx = pending_outbound_txs.get(&tx_id).unwrap();
+    assert_eq!(outbound_tx.amount, MicroTari::from(5000));
+    assert_eq!(outbound_tx.message, "Invoice 42".to_string());
+}
+
+fn offline_transaction_signing<T: TransactionBackend + Clone + 'static>(offline_backend: T, online_backend: T) {
+    let mut runtime = create_runtime();
+    let factories = CryptoFactories::default();
+    let mut rng = OsRng::new().unwrap();
+
+    let (mut offline_ts, mut offline_output_manager, offline_outbound_service, _, _, _, _, _, _) =
+        setup_transaction_service_no_comms(&runtime, factories.clone(), offline_backend);
+    let (mut online_ts, _, online_outbound_service, _, mut online_tx_ack_sender, _, _, _, _) =
+        setup_transaction_service_no_comms(&runtime, factories.clone(), online_backend);
+
+    let (_utxo, uo) = make_input(&mut rng, MicroTari(250000), &factories.commitment);
+    runtime.block_on(offline_output_manager.add_output(uo)).unwrap();
+
+    let bob_node_identity = NodeIdentity::random(
+        &mut rng,
+        "/ip4/127.0.0.1/tcp/31595".parse().unwrap(),
+        PeerFeatures::COMMUNICATION_NODE,
+    )
+    .unwrap();
+    let value = MicroTari::from(5000);
+
+    // The offline wallet creates the transaction without sending anything
+    let offline_tx = runtime
+        .block_on(offline_ts.create_offline_transaction(
+            bob_node_identity.public_key().clone(),
+            value,
+            MicroTari::from(20),
+            None,
+            "Offline".to_string(),
+        ))
+        .unwrap();
+    let tx_id = offline_tx.tx_id;
+    assert_eq!(offline_tx.amount, value);
+    assert_eq!(offline_outbound_service.call_count(), 0);
+    assert!(runtime
+        .block_on(offline_ts.get_pending_outbound_transactions())
+        .unwrap()
+        .contains_key(&tx_id));
+
+    // The offline transaction is carried to the online wallet, which sends it to the recipient
+    let offline_tx: OfflineTransaction = serde_json::from_str(&serde_json::to_string(&offline_tx).unwrap()).unwrap();
+    runtime
+        .block_on(online_ts.send_offline_transaction(offline_tx))
+        .unwrap();
+    online_outbound_service
+        .wait_call_count(1, Duration::from_secs(10))
+        .unwrap();
+    let sender_messages = decode_outbound_messages::<proto::TransactionSenderMessage, _>(
+        &online_outbound_service.take_calls(),
+        TariMessageType::SenderPartialTransaction,
+    );
+    assert_eq!(sender_messages.len(), 1);
+    assert_eq!(
+        runtime
+            .block_on(online_ts.get_offline_transaction_reply(tx_id))
+            .unwrap(),
+        None
+    );
+    assert!(runtime
+        .block_on(online_ts.get_offline_transaction_reply(tx_id + 1))
+        .is_err());
+
+    let params = TestParams::new(&mut rng);
+    let rtp = ReceiverTransactionProtocol::new(
+        sender_messages[0].clone().try_into().unwrap(),
+        params.nonce,
+        params.spend_key,
+        OutputFeatures::default(),
+        &factories,
+    );
+    let reply = rtp.get_signed_data().unwrap().clone();
+    runtime
+        .block_on(online_tx_ack_sender.send(create_dummy_message(
+            reply.clone().into(),
+            bob_node_identity.public_key(),
+        )))
+        .unwrap();
+    thread::sleep(Duration::from_secs(2));
+
+    // The reply is carried back to the offline wallet to be signed
+    let online_reply = runtime
+        .block_on(online_ts.get_offline_transaction_reply(tx_id))
+        .unwrap()
+        .unwrap();
+    assert_eq!(online_reply, reply);
+    let online_reply: RecipientSignedMessage =
+        serde_json::from_str(&serde_json::to_string(&online_reply).unwrap()).unwrap();
+    let transaction = runtime
+        .block_on(offline_ts.finalize_offline_transaction(online_reply))
+        .unwrap();
+    assert!(transaction.body.outputs().contains(&reply.output));
+    assert!(runtime
+        .block_on(offline_ts.get_pending_outbound_transactions())
+        .unwrap()
+        .is_empty());
+    assert!(runtime
+        .block_on(offline_ts.get_completed_transactions())
+        .unwrap()
+        .contains_key(&tx_id));
+    assert_eq!(offline_outbound_service.call_count(), 0);
+
+    // The signed transaction is carried back to the online wallet, which sends it to the recipient and submits it
+    runtime
+        .block_on(online_ts.submit_offline_transaction(tx_id, transaction.clone()))
+        .unwrap();
+    online_outbound_service
+        .wait_call_count(1, Duration::from_secs(10))
+        .unwrap();
+    let finalized_messages = decode_outbound_messages::<proto::TransactionFinalizedMessage, _>(
+        &online_outbound_service.take_calls(),
+        TariMessageType::TransactionFinalized,
+    );
+    assert_eq!(finalized_messages.len(), 1);
+    assert_eq!(finalized_messages[0].tx_id, tx_id);
+
+    let completed_txs = runtime.block_on(online_ts.get_completed_transactions()).unwrap();
+    let completed_tx = completed_txs.get(&tx_id).unwrap();
+    assert_eq!(completed_tx.amount, value);
+    assert_eq!(completed_tx.transaction, transaction);
+    assert!(runtime
+        .block_on(online_ts.get_offline_transaction_reply(tx_id))
+        .is_err());
+}
+
+#[test]
+fn offline_transaction_signing_memory_db() {
+    offline_transaction_signing(TransactionMemoryDatabase::new(), TransactionMemoryDatabase::new());
+}
+
+#[test]
+fn offline_transaction_signing_sqlite_db() {
+    with_temp_dir(|dir_path| {
+        let path_string = dir_path.to_str().unwrap().to_string();
+        let offline_db_path = format!("{}/{}.sqlite3", path_string, random_string(8).as_str());
+        let online_db_path = format!("{}/{}.sqlite3", path_string, random_string(8).as_str());
+        offline_transaction_signing(
+            TransactionServiceSqliteDatabase::new(offline_db_path).unwrap(),
+            TransactionServiceSqliteDatabase::new(online_db_path).unwrap(),
+        );
+    });
+}
+
+fn cancel_pending_transactions<T: TransactionBackend + Clone + 'static>(alice_backend: T, bob_backend: T) {
+    let mut runtime = create_runtime();
+    let factories = CryptoFactories::default();
+    let mut rng = OsRng::new().unwrap();
+
+    let (
+        mut alice_ts,
+        mut alice_output_manager,
+        alice_outbound_service,
+        mut alice_tx_sender,
+        mut alice_tx_ack_sender,
+        _,
+        mut alice_tx_cancelled_sender,
+        _,
+        _,
+    ) = setup_transaction_service_no_comms(&runtime, factories.clone(), alice_backend);
+    let alice_event_stream = alice_ts.get_event_stream_fused();
+    let (_bob_ts, mut bob_output_manager, _bob_outbound_service, _bob_tx_sender, _bob_tx_ack_sender, _, _, _, _) =
+        setup_transaction_service_no_comms(&runtime, factories.clone(), bob_backend);
+
+    let bob_node_identity = NodeIdentity::random(
+        &mut rng,
+        "/ip4/127.0.0.1/tcp/55743".parse().unwrap(),
+        PeerFeatures::COMMUNICATION_NODE,
+    )
+    .unwrap();
+
+    let (_utxo, uo) = make_input(&mut rng, MicroTari(250000), &factories.commitment);
+    runtime.block_on(alice_output_manager.add_output(uo)).unwrap();
+
+    // Cancel an outbound transaction
+    runtime
+        .block_on(alice_ts.send_transaction(
+            bob_node_identity.public_key().clone(),
+            MicroTari::from(5000),
+            MicroTari::from(20),
+            "".to_string(),
+        ))
+        .unwrap();
+    alice_outbound_service
+        .wait_call_count(1, Duration::from_secs(10))
+        .unwrap();
+    let sender_message = decode_outbound_messages::<proto::TransactionSenderMessage, _>(
+        &alice_outbound_service.take_calls(),
+        TariMessageType::SenderPartialTransaction,
+    )
+    .remove(0);
+    let tx_id = *runtime
+        .block_on(alice_ts.get_pending_outbound_transactions())
+        .unwrap()
+        .keys()
+        .next()
+        .unwrap();
+
+    runtime.block_on(alice_ts.cancel_transaction(tx_id)).unwrap();
+
+    alice_outbound_service
+        .wait_call_count(1, Duration::from_secs(10))
+        .unwrap();
+    let cancelled_messages = decode_outbound_messages::<proto::TransactionCancelledMessage, _>(
+        &alice_outbound_service.take_calls(),
+        TariMessageType::TransactionCancelled,
+    );
+    assert_eq!(cancelled_messages, vec![proto::TransactionCancelledMessage { tx_id }]);
+    assert!(runtime
+        .block_on(alice_ts.get_pending_outbound_transactions())
+        .unwrap()
+        .is_empty());
+    let balance = runtime.block_on(alice_output_manager.get_balance()).unwrap();
+    assert_eq!(balance.available_balance, MicroTari(250000));
+    assert_eq!(balance.pending_outgoing_balance, MicroTari::from(0));
+
+    // A transaction cannot be cancelled twice
+    assert!(runtime.block_on(alice_ts.cancel_transaction(tx_id)).is_err());
+
+    // The reply to a cancelled transaction is rejected
+    let params = TestParams::new(&mut rng);
+    let rtp = ReceiverTransactionProtocol::new(
+        sender_message.try_into().unwrap(),
+        params.nonce,
+        params.spend_key,
+        OutputFeatures::default(),
+        &factories,
+    );
+    runtime
+        .block_on(alice_tx_ack_sender.send(create_dummy_message(
+            rtp.get_signed_data().unwrap().clone().into(),
+            bob_node_identity.public_key(),
+        )))
+        .unwrap();
+
+    // Cancel an inbound transaction from its sender
+    let (_utxo, uo) = make_input(&mut rng, MicroTari(250000), &factories.commitment);
+    runtime.block_on(bob_output_manager.add_output(uo)).unwrap();
+    let mut stp = runtime
+        .block_on(bob_output_manager.prepare_transaction_to_send(
+            MicroTari::from(500),
+            MicroTari::from(1000),
+            None,
+            "".to_string(),
+        ))
+        .unwrap();
+    let msg = stp.build_single_round_message().unwrap();
+    let inbound_tx_id = msg.tx_id;
+    runtime
+        .block_on(alice_tx_sender.send(create_dummy_message(
+            TransactionSenderMessage::Single(Box::new(msg)).into(),
+            bob_node_identity.public_key(),
+        )))
+        .unwrap();
+    alice_outbound_service
+        .wait_call_count(1, Duration::from_secs(10))
+        .unwrap();
+    let _ = alice_outbound_service.take_calls();
+
+    // Only the sender of the transaction can cancel it
+    runtime
+        .block_on(alice_tx_cancelled_sender.send(create_dummy_message(
+            proto::TransactionCancelledMessage { tx_id: inbound_tx_id },
+            &PublicKey::from_secret_key(&PrivateKey::random(&mut rng)),
+        )))
+        .unwrap();
+    runtime
+        .block_on(alice_tx_cancelled_sender.send(create_dummy_message(
+            proto::TransactionCancelledMessage { tx_id: inbound_tx_id },
+            bob_node_identity.public_key(),
+        )))
+        .unwrap();
+
+    let events = collect_stream!(
+        runtime,
+        alice_event_stream.map(|i| (*i).clone()),
+        take = 5,
+        timeout = Duration::from_secs(10)
+    );
+    assert!(events.contains(&TransactionEvent::TransactionCancelled(tx_id)));
+    assert!(events.contains(&TransactionEvent::TransactionCancelled(inbound_tx_id)));
+    assert!(events.contains(&TransactionEvent::Error(
+        "Error handling Transaction Recipient Reply message".to_string()
+    )));
+    assert!(events.contains(&TransactionEvent::Error(
+        "Error handling Transaction Cancelled message".to_string()
+    )));
+
+    assert!(runtime
+        .block_on(alice_ts.get_pending_inbound_transactions())
+        .unwrap()
+        .is_empty());
+    assert!(runtime
+        .block_on(alice_ts.get_completed_transactions())
+        .unwrap()
+        .is_empty());
+    let balance = runtime.block_on(alice_output_manager.get_balance()).unwrap();
+    assert_eq!(balance.pending_incoming_balance, MicroTari::from(0));
+    assert_eq!(alice_outbound_service.call_count(), 0);
+}
+
+#[test]
+fn cancel_pending_transactions_memory_db() {
+    cancel_pending_transactions(TransactionMemoryDatabase::new(), TransactionMemoryDatabase::new());
+}
+
+#[test]
+fn cancel_pending_transactions_sqlite_db() {
+    with_temp_dir(|dir_path| {
+        let path_string = dir_path.to_str().unwrap().to_string();
+        let alice_db_name = format!("{}.sqlite3", random_string(8).as_str());
+        let alice_db_path = format!("{}/{}", path_string, alice_db_name);
+        let bob_db_name = format!("{}.sqlite3", random_string(8).as_str());
+        let bob_db_path = format!("{}/{}", path_string, bob_db_name);
+        cancel_pending_transactions(
+            TransactionServiceSqliteDatabase::new(alice_db_path).unwrap(),
+            TransactionServiceSqliteDatabase::new(bob_db_path).unwrap(),
+        );
+    });
+}
+
+#[test]
+fn discovery_async_return_test() {
+    let db_tempdir = TempDir::new(random_string(8).as_str()).unwrap();
+    let db_folder = db_tempdir.path().to_str().unwrap().to_string();
+
+    let mut runtime = Runtime::new().unwrap();
+    let mut rng = OsRng::new().unwrap();
+    let factories = CryptoFactories::default();
+
+    let alice_backend = TransactionMemoryDatabase::new();
+    let bob_backend = TransactionMemoryDatabase::new();
+    let dave_backend = TransactionMemoryDatabase::new();
+    let port_offset = 1;
+
+    // Alice's parameters
+    let alice_port = 30484 + port_offset;
+    let alice_node_identity = NodeIdentity::random(
+        &mut rng,
+        format!("/ip4/127.0.0.1/tcp/{}", alice_port).parse().unwrap(),
+        PeerFeatures::COMMUNICATION_NODE,
+    )
+    .unwrap();
+
+    // Bob's parameters
+    let bob_port = 30475 + port_offset;
+    let bob_node_identity = NodeIdentity::random(
+        &mut rng,
+        format!("/ip4/127.0.0.1/tcp/{}", bob_port).parse().unwrap(),
+        PeerFeatures::COMMUNICATION_NODE,
+    )
+    .unwrap();
+
+    // Carols's parameters
+    let carol_port = 30488 + port_offset;
+    let carol_node_identity = NodeIdentity::random(
+        &mut rng,
+        format!("/ip4/127.0.0.1/tcp/{}", carol_port).parse().unwrap(),
+        PeerFeatures::COMMUNICATION_NODE,
+    )
+    .unwrap();
+
+    // Dave's parameters
+    let dave_port = 30498 + port_offset;
+    let dave_node_identity = NodeIdentity::random(
+        &mut rng,
+        format!("/ip4/127.0.0.1/tcp/{}", dave_port).parse().unwrap(),
+        PeerFeatures::COMMUNICATION_NODE,
+    )
+    .unwrap();
+
+    let (mut alice_ts, mut alice_oms, _alice_comms) = setup_transaction_service(
+        &mut runtime,
+        alice_node_identity.clone(),
+        vec![bob_node_identity.clone()],
+        factories.clone(),
+        alice_backend,
+        db_folder.clone(),
+        Duration::from_secs(1),
+    );
+    let alice_event_stream = alice_ts.get_event_stream_fused();
+
+    let (_bob_ts, _bob_oms, _bob_comms) = setup_transaction_service(
+        &mut runtime,
+        bob_node_identity.clone(),
+        vec![
+            alice_node_identity.clone(),
+            carol_node_identity.clone(),
+            dave_node_identity.clone(),
+        ],
+        factories.clone(),
+        bob_backend,
+        db_folder.clone(),
+        Duration::from_secs(1),
+    );
+
+    let (_utxo, uo1a) = make_input(&mut rng, MicroTari(5500), &factories.commitment);
+    runtime.block_on(alice_oms.add_output(uo1a)).unwrap();
+    let (_utxo, uo1b) = make_input(&mut rng, MicroTari(3000), &factories.commitment);
+    runtime.block_on(alice_oms.add_output(uo1b)).unwrap();
+    let (_utxo, uo1c) = make_input(&mut rng, MicroTari(3000), &factories.commitment);
+    runtime.block_on(alice_oms.add_output(uo1c)).unwrap();
+
+    let initial_balance = runtime.block_on(alice_oms.get_balance()).unwrap();
+
+    let value_a_to_c_1 = MicroTari::from(1400);
+
+    let tx_id = match runtime.block_on(alice_ts.send_transaction(
+        carol_node_identity.public_key().clone(),
+        value_a_to_c_1,
+        MicroTari::from(20),
+        "Discovery Tx!".to_string(),
+    )) {
+        Err(TransactionServiceError::OutboundSendDiscoveryInProgress(tx_id)) => tx_id,
+        _ => {
+            assert!(false, "Send should not succeed as Peer is not known");
+            0u64
+        },
+    };
+    assert_ne!(initial_balance, runtime.block_on(alice_oms.get_balance()).unwrap());
+
+    let result = collect_stream!(
+        runtime,
+        alice_event_stream.map(|i| (*i).clone()),
+        take = 2,
+        timeout = Duration::from_secs(10)
+    );
+    assert!(result
+        .iter()
+        .find(
+            |i| if let TransactionEvent::TransactionSendDiscoveryComplete(t, result) = i {
+                t == &tx_id && *result
+            } else {
+                false
+            }
+        )
+        .is_some());
+    // Carol could not be discovered, so the message was left with Bob to be forwarded to Carol when she comes online
+    assert!(result
+        .iter()
+        .find(|i| **i == TransactionEvent::TransactionStoreAndForwardSend(tx_id))
+        .is_some());
+
+    // The transaction is still pending, so its funds remain encumbered until Carol replies or it is cancelled
+    assert_ne!(initial_balance, runtime.block_on(alice_oms.get_balance()).unwrap());
+    let pending_outbound = runtime.block_on(alice_ts.get_pending_outbound_transactions()).unwrap();
+    assert!(pending_outbound.contains_key(&tx_id));
+
+    let (_dave_ts, _dave_oms, _dave_comms) = setup_transaction_service(
         &mut runtime,
         dave_node_identity.clone(),
         vec![bob_node_identity.clone()],
@@ -1170,8 +1992,17 @@ fn test_coinbase<T: TransactionBackend + Clone + 'static>(backend: T) {
     let mut runtime = create_runtime();
     let factories = CryptoFactories::default();
 
-    let (mut alice_ts, mut alice_output_manager, _alice_outbound_service, _alice_tx_sender, _alice_tx_ack_sender, _) =
-        setup_transaction_service_no_comms(&runtime, factories.clone(), backend);
+    let (
+        mut alice_ts,
+        mut alice_output_manager,
+        _alice_outbound_service,
+        _alice_tx_sender,
+        _alice_tx_ack_sender,
+        _,
+        _,
+        _,
+        _,
+    ) = setup_transaction_service_no_comms(&runtime, factories.clone(), backend);
 
     let balance = runtime.block_on(alice_output_manager.get_balance()).unwrap();
     assert_eq!(balance.pending_incoming_balance, MicroTari(0));
@@ -1272,3 +2103,410 @@ fn test_coinbase_sqlite_db() {
         test_coinbase(TransactionServiceSqliteDatabase::new(alice_db_path).unwrap());
     });
 }
+
+/// Decode all the messages of the given type that were sent to the outbound service mock
+fn decode_outbound_messages<T: Message + Default, P>(calls: &[(P, Vec<u8>)], message_type: TariMessageType) -> Vec<T> {
+    calls
+        .iter()
+        .filter_map(|(_, body)| {
+            let envelope_body = EnvelopeBody::decode(body.as_slice()).unwrap();
+            let header = envelope_body.decode_part::<MessageHeader>(0).unwrap().unwrap();
+            if header.message_type == message_type as i32 {
+                Some(envelope_body.decode_part::<T>(1).unwrap().unwrap())
+            } else {
+                None
+            }
+        })
+        .collect()
+}
+
+/// Return the request keys of the chain metadata and kernel queries sent to the base node in a monitoring round
+fn base_node_request_keys<P>(calls: &[(P, Vec<u8>)]) -> (u64, u64) {
+    let requests = decode_outbound_messages::<BaseNodeServiceRequest, _>(calls, TariMessageType::BaseNodeRequest);
+    assert_eq!(requests.len(), 2);
+    let mut metadata_key = None;
+    let mut kernels_key = None;
+    for request in requests {
+        match request.request {
+            Some(BaseNodeRequestProto::GetChainMetadata(_)) => metadata_key = Some(request.request_key),
+            Some(BaseNodeRequestProto::FetchKernelHeights(_)) => kernels_key = Some(request.request_key),
+            _ => panic!("Unexpected base node request"),
+        }
+    }
+    (metadata_key.unwrap(), kernels_key.unwrap())
+}
+
+/// Respond to a monitoring round's chain metadata and kernel queries, reporting the kernels as mined at the given
+/// height. The chain metadata response is sent first so that the confirmations are counted from the new chain tip.
+fn send_base_node_responses(
+    runtime: &mut Runtime,
+    base_node_response_sender: &mut Sender<DomainMessage<BaseNodeServiceResponse>>,
+    base_node_public_key: &PublicKey,
+    (metadata_key, kernels_key): (u64, u64),
+    chain_height: u64,
+    kernels: Vec<TransactionKernel>,
+    mined_height: u64,
+)
+{
+    let metadata_response = BaseNodeServiceResponse {
+        request_key: metadata_key,
+        response: Some(BaseNodeResponseProto::ChainMetadata(ChainMetadata {
+            height_of_longest_chain: Some(chain_height),
+            best_block: None,
+            pruning_horizon: 0,
+            accumulated_difficulty: None,
+        })),
+    };
+    runtime
+        .block_on(base_node_response_sender.send(create_dummy_message(metadata_response, base_node_public_key)))
+        .unwrap();
+    thread::sleep(Duration::from_millis(500));
+
+    let kernels_response = BaseNodeServiceResponse {
+        request_key: kernels_key,
+        response: Some(BaseNodeResponseProto::KernelHeights(KernelHeights {
+            kernel_heights: kernels
+                .iter()
+                .map(|k| KernelHeight {
+                    hash: k.hash(),
+                    height: mined_height,
+                })
+                .collect(),
+        })),
+    };
+    runtime
+        .block_on(base_node_response_sender.send(create_dummy_message(kernels_response, base_node_public_key)))
+        .unwrap();
+}
+
+/// Poll the transaction service until the completed transaction reaches the given status
+fn wait_for_completed_transaction_status(
+    runtime: &mut Runtime,
+    alice_ts: &mut TransactionServiceHandle,
+    tx_id: u64,
+    status: TransactionStatus,
+)
+{
+    for _ in 0..20 {
+        let completed_txs = runtime.block_on(alice_ts.get_completed_transactions()).unwrap();
+        if completed_txs.get(&tx_id).unwrap().status == status {
+            return;
+        }
+        thread::sleep(Duration::from_millis(500));
+    }
+    panic!("Completed transaction did not reach status {:?}", status);
+}
+
+/// Send a transaction from Alice and reply to it on behalf of the recipient so that Alice holds a completed
+/// transaction.
+fn complete_outbound_transaction(
+    runtime: &mut Runtime,
+    factories: &CryptoFactories,
+    alice_ts: &mut TransactionServiceHandle,
+    alice_output_manager: &mut OutputManagerHandle,
+    alice_outbound_service: &OutboundServiceMockState,
+    alice_tx_ack_sender: &mut Sender<DomainMessage<proto::RecipientSignedMessage>>,
+) -> u64
+{
+    let mut rng = OsRng::new().unwrap();
+    let bob_node_identity = NodeIdentity::random(
+        &mut rng,
+        "/ip4/127.0.0.1/tcp/31586".parse().unwrap(),
+        PeerFeatures::COMMUNICATION_NODE,
+    )
+    .unwrap();
+
+    let (_utxo, uo) = make_input(&mut rng, MicroTari(250000), &factories.commitment);
+    runtime.block_on(alice_output_manager.add_output(uo)).unwrap();
+
+    runtime
+        .block_on(alice_ts.send_transaction(
+            bob_node_identity.public_key().clone(),
+            MicroTari::from(500),
+            MicroTari::from(1000),
+            "".to_string(),
+        ))
+        .unwrap();
+    alice_outbound_service
+        .wait_call_count(1, Duration::from_secs(10))
+        .unwrap();
+    let (_, body) = alice_outbound_service.pop_call().unwrap();
+    let envelope_body = EnvelopeBody::decode(body.as_slice()).unwrap();
+    let sender_message = envelope_body
+        .decode_part::<proto::TransactionSenderMessage>(1)
+        .unwrap()
+        .unwrap();
+
+    let params = TestParams::new(&mut rng);
+    let rtp = ReceiverTransactionProtocol::new(
+        sender_message.try_into().unwrap(),
+        params.nonce,
+        params.spend_key,
+        OutputFeatures::default(),
+        factories,
+    );
+    let tx_reply = rtp.get_signed_data().unwrap().clone();
+    let tx_id = tx_reply.tx_id;
+    runtime
+        .block_on(alice_tx_ack_sender.send(create_dummy_message(tx_reply.into(), &bob_node_identity.public_key())))
+        .unwrap();
+
+    // Wait for the finalized transaction to be sent back to the recipient
+    alice_outbound_service
+        .wait_call_count(1, Duration::from_secs(10))
+        .unwrap();
+    alice_outbound_service.take_calls();
+
+    tx_id
+}
+
+fn transaction_base_node_monitoring<T: TransactionBackend + Clone + 'static>(alice_backend: T) {
+    let mut runtime = create_runtime();
+    let mut rng = OsRng::new().unwrap();
+    let factories = CryptoFactories::default();
+    let config = TransactionServiceConfig {
+        base_node_monitoring_interval: Duration::from_secs(60),
+        num_confirmations_required: 2,
+        max_rebroadcast_attempts: 1,
+        ..Default::default()
+    };
+
+    let (
+        mut alice_ts,
+        mut alice_output_manager,
+        alice_outbound_service,
+        _alice_tx_sender,
+        mut alice_tx_ack_sender,
+        _,
+        _,
+        _,
+        mut alice_base_node_response_sender,
+    ) = setup_transaction_service_no_comms_with_config(&runtime, factories.clone(), alice_backend, config);
+    let alice_event_stream = alice_ts.get_event_stream_fused();
+
+    let tx_id = complete_outbound_transaction(
+        &mut runtime,
+        &factories,
+        &mut alice_ts,
+        &mut alice_output_manager,
+        &alice_outbound_service,
+        &mut alice_tx_ack_sender,
+    );
+    let completed_tx = runtime
+        .block_on(alice_ts.get_completed_transactions())
+        .unwrap()
+        .remove(&tx_id)
+        .unwrap();
+    assert_eq!(completed_tx.status, TransactionStatus::Completed);
+    let kernels = completed_tx.transaction.body.kernels().clone();
+
+    // Setting the base node submits the completed transaction and starts the first monitoring round
+    let (_, base_node_public_key) = PublicKey::random_keypair(&mut rng);
+    runtime
+        .block_on(alice_ts.set_base_node_public_key(base_node_public_key.clone()))
+        .unwrap();
+    alice_outbound_service
+        .wait_call_count(3, Duration::from_secs(10))
+        .unwrap();
+    let calls = alice_outbound_service.take_calls();
+    let submitted_txs = decode_outbound_messages::<ProtoTransaction, _>(&calls, TariMessageType::NewTransaction);
+    assert_eq!(submitted_txs.len(), 1);
+    let request_keys = base_node_request_keys(&calls);
+
+    wait_for_completed_transaction_status(&mut runtime, &mut alice_ts, tx_id, TransactionStatus::Broadcast);
+
+    send_base_node_responses(
+        &mut runtime,
+        &mut alice_base_node_response_sender,
+        &base_node_public_key,
+        request_keys,
+        10,
+        kernels.clone(),
+        10,
+    );
+    wait_for_completed_transaction_status(&mut runtime, &mut alice_ts, tx_id, TransactionStatus::Mined);
+    let completed_tx = runtime
+        .block_on(alice_ts.get_completed_transactions())
+        .unwrap()
+        .remove(&tx_id)
+        .unwrap();
+    assert_eq!(completed_tx.mined_height, Some(10));
+
+    // One confirmation is not enough to release the pending outputs
+    let balance = runtime.block_on(alice_output_manager.get_balance()).unwrap();
+    assert_ne!(balance.pending_outgoing_balance, MicroTari(0));
+
+    // Re-setting the same base node triggers another monitoring round without resetting the chain height
+    runtime
+        .block_on(alice_ts.set_base_node_public_key(base_node_public_key.clone()))
+        .unwrap();
+    alice_outbound_service
+        .wait_call_count(2, Duration::from_secs(10))
+        .unwrap();
+    let calls = alice_outbound_service.take_calls();
+    let request_keys = base_node_request_keys(&calls);
+    send_base_node_responses(
+        &mut runtime,
+        &mut alice_base_node_response_sender,
+        &base_node_public_key,
+        request_keys,
+        11,
+        kernels,
+        10,
+    );
+
+    let events = collect_stream!(
+        runtime,
+        alice_event_stream.map(|i| (*i).clone()),
+        take = 5,
+        timeout = Duration::from_secs(10)
+    );
+    assert!(events
+        .iter()
+        .any(|e| if let TransactionEvent::TransactionBroadcast(id) = e {
+            *id == tx_id
+        } else {
+            false
+        }));
+    assert!(events
+        .iter()
+        .any(|e| if let TransactionEvent::TransactionMined(id) = e {
+            *id == tx_id
+        } else {
+            false
+        }));
+    assert!(events
+        .iter()
+        .any(|e| if let TransactionEvent::TransactionConfirmations(id, 1) = e {
+            *id == tx_id
+        } else {
+            false
+        }));
+    assert!(events
+        .iter()
+        .any(|e| if let TransactionEvent::TransactionConfirmations(id, 2) = e {
+            *id == tx_id
+        } else {
+            false
+        }));
+
+    let balance = runtime.block_on(alice_output_manager.get_balance()).unwrap();
+    assert_eq!(balance.pending_outgoing_balance, MicroTari(0));
+    assert_eq!(balance.pending_incoming_balance, MicroTari(0));
+}
+
+#[test]
+fn transaction_base_node_monitoring_memory_db() {
+    transaction_base_node_monitoring(TransactionMemoryDatabase::new());
+}
+
+#[test]
+fn transaction_base_node_monitoring_sqlite_db() {
+    with_temp_dir(|dir_path| {
+        let path_string = dir_path.to_str().unwrap().to_string();
+        let alice_db_name = format!("{}.sqlite3", random_string(8).as_str());
+        let alice_db_path = format!("{}/{}", path_string, alice_db_name);
+        transaction_base_node_monitoring(TransactionServiceSqliteDatabase::new(alice_db_path).unwrap());
+    });
+}
+
+fn transaction_base_node_rebroadcast<T: TransactionBackend + Clone + 'static>(alice_backend: T) {
+    let mut runtime = create_runtime();
+    let mut rng = OsRng::new().unwrap();
+    let factories = CryptoFactories::default();
+    let config = TransactionServiceConfig {
+        base_node_monitoring_interval: Duration::from_secs(60),
+        num_confirmations_required: 2,
+        max_rebroadcast_attempts: 1,
+        ..Default::default()
+    };
+
+    let (
+        mut alice_ts,
+        mut alice_output_manager,
+        alice_outbound_service,
+        _alice_tx_sender,
+        mut alice_tx_ack_sender,
+        _,
+        _,
+        mut alice_mempool_response_sender,
+        _,
+    ) = setup_transaction_service_no_comms_with_config(&runtime, factories.clone(), alice_backend, config);
+    let alice_event_stream = alice_ts.get_event_stream_fused();
+
+    let tx_id = complete_outbound_transaction(
+        &mut runtime,
+        &factories,
+        &mut alice_ts,
+        &mut alice_output_manager,
+        &alice_outbound_service,
+        &mut alice_tx_ack_sender,
+    );
+
+    let (_, base_node_public_key) = PublicKey::random_keypair(&mut rng);
+    runtime
+        .block_on(alice_ts.set_base_node_public_key(base_node_public_key.clone()))
+        .unwrap();
+    alice_outbound_service
+        .wait_call_count(3, Duration::from_secs(10))
+        .unwrap();
+    alice_outbound_service.take_calls();
+    wait_for_completed_transaction_status(&mut runtime, &mut alice_ts, tx_id, TransactionStatus::Broadcast);
+
+    // The base node reports that the transaction is not in its mempool, so it is resubmitted once and then dropped
+    // when the rebroadcast attempts are exhausted
+    for expected_resubmissions in &[1, 0] {
+        runtime
+            .block_on(alice_ts.set_base_node_public_key(base_node_public_key.clone()))
+            .unwrap();
+        alice_outbound_service
+            .wait_call_count(3, Duration::from_secs(10))
+            .unwrap();
+        let calls = alice_outbound_service.take_calls();
+        let mempool_requests =
+            decode_outbound_messages::<MempoolServiceRequest, _>(&calls, TariMessageType::MempoolRequest);
+        assert_eq!(mempool_requests.len(), 1);
+
+        let mempool_response = MempoolServiceResponse {
+            request_key: mempool_requests[0].request_key,
+            response: Some(MempoolResponseProto::TxStorage(TxStorageResponse::NotStored as i32)),
+        };
+        runtime
+            .block_on(alice_mempool_response_sender.send(create_dummy_message(mempool_response, &base_node_public_key)))
+            .unwrap();
+        thread::sleep(Duration::from_millis(500));
+
+        let calls = alice_outbound_service.take_calls();
+        let submitted_txs = decode_outbound_messages::<ProtoTransaction, _>(&calls, TariMessageType::NewTransaction);
+        assert_eq!(submitted_txs.len(), *expected_resubmissions);
+    }
+
+    let events = collect_stream!(
+        runtime,
+        alice_event_stream.map(|i| (*i).clone()),
+        take = 3,
+        timeout = Duration::from_secs(10)
+    );
+    assert!(events
+        .iter()
+        .any(|e| if let TransactionEvent::TransactionDroppedFromMempool(id) = e {
+            *id == tx_id
+        } else {
+            false
+        }));
+}
+
+#[test]
+fn transaction_base_node_rebroadcast_memory_db() {
+    transaction_base_node_rebroadcast(TransactionMemoryDatabase::new());
+}
+
+#[test]
+fn transaction_base_node_rebroadcast_sqlite_db() {
+    with_temp_dir(|dir_path| {
+        let path_string = dir_path.to_str().unwrap().to_string();
+        let alice_db_name = format!("{}.sqlite3", random_string(8).as_str());
+        let alice_db_path = format!("{}/{}", path_string, alice_db_name);
+        transaction_base_node_rebroadcast(TransactionServiceSqliteDatabase::new(alice_db_path).unwrap());
+    });
+}
@@ -21,7 +21,7 @@
 // USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
 use crate::support::utils::random_string;
-use chrono::Utc;
+use chrono::{Duration as ChronoDuration, Utc};
 use tari_core::transactions::{
     tari_amount::MicroTari,
     transaction::{OutputFeatures, Transaction, UnblindedOutput},
@@ -34,18 +34,29 @@ use tari_crypto::{
     commitment::HomomorphicCommitmentFactory,
     keys::{PublicKey as PublicKeyTrait, SecretKey as SecretKeyTrait},
 };
-use tari_wallet::transaction_service::storage::{
-    database::{
-        CompletedTransaction,
-        InboundTransaction,
-        OutboundTransaction,
-        PendingCoinbaseTransaction,
-        TransactionBackend,
-        TransactionDatabase,
-        TransactionStatus,
+use tari_wallet::{
+    encryption::EncryptionSettings,
+    error::EncryptionError,
+    transaction_service::{
+        error::TransactionStorageError,
+        storage::{
+            database::{
+                CompletedTransaction,
+                InboundTransaction,
+                OfflineTransaction,
+                OutboundTransaction,
+                PendingCoinbaseTransaction,
+                RelayedOfflineTransaction,
+                TransactionBackend,
+                TransactionDatabase,
+                TransactionHistoryQuery,
+                TransactionRecipient,
+                TransactionStatus,
+            },
+            memory_db::TransactionMemoryDatabase,
+            sqlite_db::TransactionServiceSqliteDatabase,
+        },
     },
-    memory_db::TransactionMemoryDatabase,
-    sqlite_db::TransactionServiceSqliteDatabase,
 };
 use tempdir::TempDir;
 
@@ -86,6 +97,8 @@ pub fn test_db_backend<T: TransactionBackend>(backend: T) {
             sender_protocol: stp.clone(),
             message: messages[i].clone(),
             timestamp: Utc::now().naive_utc(),
+            recipients: Vec::new(),
+            cancelled: false,
         });
         assert!(
             !db.transaction_exists(&((i + 10) as u64)).unwrap(),
@@ -109,6 +122,37 @@ pub fn test_db_backend<T: TransactionBackend>(backend: T) {
         );
     }
 
+    outbound_txs[0].recipients = vec![TransactionRecipient {
+        tx_id: 100,
+        public_key: PublicKey::from_secret_key(&PrivateKey::random(&mut rng)),
+        amount: amounts[0].clone(),
+    }];
+    db.update_pending_outbound_transaction(outbound_txs[0].tx_id, outbound_txs[0].clone())
+        .unwrap();
+    assert_eq!(
+        db.find_pending_outbound_transaction_for_recipient(100).unwrap(),
+        outbound_txs[0]
+    );
+    assert_eq!(
+        db.find_pending_outbound_transaction_for_recipient(outbound_txs[1].tx_id)
+            .unwrap(),
+        outbound_txs[1]
+    );
+    assert!(db.find_pending_outbound_transaction_for_recipient(101).is_err());
+
+    // Updating the recipients of a transaction replaces the tx_ids it can be found by
+    outbound_txs[0].recipients[0].tx_id = 101;
+    db.update_pending_outbound_transaction(outbound_txs[0].tx_id, outbound_txs[0].clone())
+        .unwrap();
+    assert!(db.find_pending_outbound_transaction_for_recipient(100).is_err());
+    assert_eq!(
+        db.find_pending_outbound_transaction_for_recipient(101).unwrap(),
+        outbound_txs[0]
+    );
+    assert!(db
+        .update_pending_outbound_transaction(102, outbound_txs[0].clone())
+        .is_err());
+
     let rtp = ReceiverTransactionProtocol::new(
         TransactionSenderMessage::Single(Box::new(stp.clone().build_single_round_message().unwrap())),
         PrivateKey::random(&mut rng),
@@ -127,6 +171,7 @@ pub fn test_db_backend<T: TransactionBackend>(backend: T) {
             receiver_protocol: rtp.clone(),
             message: messages[i].clone(),
             timestamp: Utc::now().naive_utc(),
+            cancelled: false,
         });
         assert!(!db.transaction_exists(&(i as u64)).unwrap(), "TxId should not exist");
         db.add_pending_inbound_transaction(i as u64, inbound_txs[i].clone())
@@ -143,6 +188,48 @@ pub fn test_db_backend<T: TransactionBackend>(backend: T) {
         );
     }
 
+    let cancelled_outbound_tx = OutboundTransaction {
+        tx_id: 50,
+        ..outbound_txs[1].clone()
+    };
+    db.add_pending_outbound_transaction(50, cancelled_outbound_tx.clone())
+        .unwrap();
+    let cancelled_inbound_tx = InboundTransaction {
+        tx_id: 51,
+        ..inbound_txs[1].clone()
+    };
+    db.add_pending_inbound_transaction(51, cancelled_inbound_tx.clone())
+        .unwrap();
+    db.cancel_pending_transaction(50).unwrap();
+    db.cancel_pending_transaction(51).unwrap();
+    assert!(db.cancel_pending_transaction(52).is_err());
+
+    assert!(!db.get_pending_outbound_transactions().unwrap().contains_key(&50));
+    assert!(!db.get_pending_inbound_transactions().unwrap().contains_key(&51));
+    assert_eq!(
+        db.get_cancelled_pending_outbound_transactions()
+            .unwrap()
+            .get(&50)
+            .unwrap(),
+        &OutboundTransaction {
+            cancelled: true,
+            ..cancelled_outbound_tx
+        }
+    );
+    assert_eq!(
+        db.get_cancelled_pending_inbound_transactions()
+            .unwrap()
+            .get(&51)
+            .unwrap(),
+        &InboundTransaction {
+            cancelled: true,
+            ..cancelled_inbound_tx
+        }
+    );
+    assert!(db.get_pending_outbound_transaction(50).unwrap().cancelled);
+    assert!(db.get_pending_inbound_transaction(51).unwrap().cancelled);
+    assert!(db.transaction_exists(&50).unwrap());
+
     let mut coinbases = Vec::new();
     for i in 0..messages.len() {
         coinbases.push(PendingCoinbaseTransaction {
@@ -192,6 +279,7 @@ pub fn test_db_backend<T: TransactionBackend>(backend: T) {
             },
             message: messages[i].clone(),
             timestamp: Utc::now().naive_utc(),
+            mined_height: None,
         });
         db.complete_outbound_transaction(outbound_txs[i].tx_id, completed_txs[i].clone())
             .unwrap();
@@ -250,7 +338,8 @@ pub fn test_db_backend<T: TransactionBackend>(backend: T) {
         );
 
         #[cfg(feature = "test_harness")]
-        db.mine_completed_transaction(completed_txs[0].tx_id.clone()).unwrap();
+        db.mine_completed_transaction(completed_txs[0].tx_id.clone(), Some(10))
+            .unwrap();
         let retrieved_completed_txs = db.get_completed_transactions().unwrap();
 
         assert!(retrieved_completed_txs.contains_key(&completed_txs[0].tx_id));
@@ -258,7 +347,86 @@ pub fn test_db_backend<T: TransactionBackend>(backend: T) {
             retrieved_completed_txs.get(&completed_txs[0].tx_id).unwrap().status,
             TransactionStatus::Mined
         );
+        assert_eq!(
+            retrieved_completed_txs
+                .get(&completed_txs[0].tx_id)
+                .unwrap()
+                .mined_height,
+            Some(10)
+        );
     }
+
+    let history = db
+        .get_completed_transaction_history(TransactionHistoryQuery::default())
+        .unwrap();
+    assert_eq!(history.len(), 3 * messages.len());
+    assert!(history
+        .windows(2)
+        .all(|w| (w[0].timestamp, w[0].tx_id) < (w[1].timestamp, w[1].tx_id)));
+
+    let page = db
+        .get_completed_transaction_history(TransactionHistoryQuery {
+            offset: 1,
+            limit: Some(2),
+            ..Default::default()
+        })
+        .unwrap();
+    assert_eq!(page, history[1..3].to_vec());
+
+    let first = history.first().unwrap().timestamp;
+    let last = history.last().unwrap().timestamp;
+    let in_range = db
+        .get_completed_transaction_history(TransactionHistoryQuery {
+            from: Some(first),
+            to: Some(last + ChronoDuration::seconds(1)),
+            ..Default::default()
+        })
+        .unwrap();
+    assert_eq!(in_range, history);
+    let before = db
+        .get_completed_transaction_history(TransactionHistoryQuery {
+            to: Some(first),
+            ..Default::default()
+        })
+        .unwrap();
+    assert!(before.is_empty());
+    let after = db
+        .get_completed_transaction_history(TransactionHistoryQuery {
+            from: Some(last + ChronoDuration::seconds(1)),
+            ..Default::default()
+        })
+        .unwrap();
+    assert!(after.is_empty());
+
+    let relayed_tx_id = 9999;
+    let mut relayed_tx = RelayedOfflineTransaction {
+        offline_transaction: OfflineTransaction {
+            tx_id: relayed_tx_id,
+            destination_public_key: PublicKey::from_secret_key(&PrivateKey::random(&mut rng)),
+            amount,
+            fee: stp.clone().get_fee_amount().unwrap(),
+            message: "Relayed".to_string(),
+            sender_message: stp.get_single_round_message().unwrap(),
+        },
+        reply: None,
+    };
+    assert!(db.get_relayed_offline_transaction(relayed_tx_id).is_err());
+    db.save_relayed_offline_transaction(relayed_tx_id, relayed_tx.clone())
+        .unwrap();
+    assert_eq!(db.get_relayed_offline_transaction(relayed_tx_id).unwrap(), relayed_tx);
+    let relayed_txs = db.get_relayed_offline_transactions().unwrap();
+    assert_eq!(relayed_txs.len(), 1);
+    assert_eq!(relayed_txs.get(&relayed_tx_id), Some(&relayed_tx));
+
+    relayed_tx.reply = Some(rtp.get_signed_data().unwrap().clone());
+    db.save_relayed_offline_transaction(relayed_tx_id, relayed_tx.clone())
+        .unwrap();
+    assert_eq!(db.get_relayed_offline_transaction(relayed_tx_id).unwrap(), relayed_tx);
+    assert_eq!(db.get_relayed_offline_transactions().unwrap().len(), 1);
+
+    db.remove_relayed_offline_transaction(relayed_tx_id).unwrap();
+    assert!(db.get_relayed_offline_transaction(relayed_tx_id).is_err());
+    assert!(db.get_relayed_offline_transactions().unwrap().is_empty());
 }
 
 #[test]
@@ -274,3 +442,104 @@ pub fn test_transaction_service_sqlite_db() {
     let db_path = format!("{}/{}", db_folder, db_name);
     test_db_backend(TransactionServiceSqliteDatabase::new(db_path).unwrap());
 }
+
+#[test]
+pub fn test_transaction_service_sqlite_db_encryption() {
+    let db_name = format!("{}.sqlite3", random_string(8).as_str());
+    let db_tempdir = TempDir::new(random_string(8).as_str()).unwrap();
+    let db_folder = db_tempdir.path().to_str().unwrap().to_string();
+    let db_path = format!("{}/{}", db_folder, db_name);
+    let factories = CryptoFactories::default();
+    let mut rng = rand::OsRng::new().unwrap();
+
+    let mut db = TransactionDatabase::new(TransactionServiceSqliteDatabase::new(db_path.clone()).unwrap());
+    let mut builder = SenderTransactionProtocol::builder(1);
+    let input = UnblindedOutput::new(MicroTari::from(100_000), PrivateKey::random(&mut rng), None);
+    builder
+        .with_lock_height(0)
+        .with_fee_per_gram(MicroTari::from(177))
+        .with_offset(PrivateKey::random(&mut rng))
+        .with_private_nonce(PrivateKey::random(&mut rng))
+        .with_amount(0, MicroTari::from(10_000))
+        .with_message("Yo!".to_string())
+        .with_input(
+            input.as_transaction_input(&factories.commitment, OutputFeatures::default()),
+            input.clone(),
+        )
+        .with_change_secret(PrivateKey::random(&mut rng));
+    let stp = builder.build::<HashDigest>(&factories).unwrap();
+    let outbound_tx = OutboundTransaction {
+        tx_id: 1,
+        destination_public_key: PublicKey::from_secret_key(&PrivateKey::random(&mut rng)),
+        amount: MicroTari::from(10_000),
+        fee: stp.clone().get_fee_amount().unwrap(),
+        sender_protocol: stp.clone(),
+        message: "Yo!".to_string(),
+        timestamp: Utc::now().naive_utc(),
+        recipients: Vec::new(),
+        cancelled: false,
+    };
+    db.add_pending_outbound_transaction(outbound_tx.tx_id, outbound_tx.clone())
+        .unwrap();
+
+    let settings = EncryptionSettings::new("correct horse").unwrap();
+    let cipher = settings.cipher("correct horse").unwrap();
+    db.apply_encryption(Some(cipher.clone())).unwrap();
+
+    // Transactions added after encryption is applied are encrypted too
+    let inbound_tx = InboundTransaction {
+        tx_id: 2,
+        source_public_key: PublicKey::from_secret_key(&PrivateKey::random(&mut rng)),
+        amount: MicroTari::from(10_000),
+        receiver_protocol: ReceiverTransactionProtocol::new(
+            TransactionSenderMessage::Single(Box::new(stp.clone().build_single_round_message().unwrap())),
+            PrivateKey::random(&mut rng),
+            PrivateKey::random(&mut rng),
+            OutputFeatures::default(),
+            &factories,
+        ),
+        message: "Yo!".to_string(),
+        timestamp: Utc::now().naive_utc(),
+        cancelled: false,
+    };
+    db.add_pending_inbound_transaction(inbound_tx.tx_id, inbound_tx.clone())
+        .unwrap();
+
+    assert_eq!(db.get_pending_outbound_transaction(1).unwrap(), outbound_tx);
+    assert_eq!(db.get_pending_inbound_transaction(2).unwrap(), inbound_tx);
+
+    db.lock().unwrap();
+    match db.get_pending_outbound_transactions() {
+        Err(TransactionStorageError::EncryptionError(EncryptionError::WalletLocked)) => (),
+        _ => assert!(false),
+    }
+    assert!(db.get_pending_inbound_transaction(2).is_err());
+
+    // An encrypted database is locked when it is opened
+    drop(db);
+    let mut db = TransactionDatabase::new(TransactionServiceSqliteDatabase::new(db_path.clone()).unwrap());
+    assert!(db.get_pending_outbound_transaction(1).is_err());
+    let wrong_cipher = EncryptionSettings::new("battery staple")
+        .unwrap()
+        .cipher("battery staple")
+        .unwrap();
+    match db.unlock(wrong_cipher) {
+        Err(TransactionStorageError::EncryptionError(EncryptionError::IncorrectPassphrase)) => (),
+        _ => assert!(false),
+    }
+    db.unlock(cipher).unwrap();
+    assert_eq!(
+        db.get_pending_outbound_transactions().unwrap().get(&1),
+        Some(&outbound_tx)
+    );
+    assert_eq!(
+        db.get_pending_inbound_transactions().unwrap().get(&2),
+        Some(&inbound_tx)
+    );
+
+    db.apply_encryption(None).unwrap();
+    drop(db);
+    let db = TransactionDatabase::new(TransactionServiceSqliteDatabase::new(db_path).unwrap());
+    assert_eq!(db.get_pending_outbound_transaction(1).unwrap(), outbound_tx);
+    assert_eq!(db.get_pending_inbound_transaction(2).unwrap(), inbound_tx);
+}
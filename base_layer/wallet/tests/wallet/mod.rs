@@ -22,19 +22,23 @@
 
 use crate::support::utils::{make_input, random_string};
 use std::{
-    sync::{Arc, Mutex},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+        Mutex,
+    },
     time::Duration,
 };
 use tari_comms::{
     control_service::ControlServiceConfig,
     multiaddr::Multiaddr,
     peer_manager::{peer::PeerFlags, NodeId, NodeIdentity, Peer, PeerFeatures},
-    types::CommsPublicKey,
+    types::{CommsPublicKey, CommsSecretKey},
 };
 #[cfg(feature = "test_harness")]
 use tari_comms_dht::DhtConfig;
 use tari_core::transactions::{tari_amount::MicroTari, types::CryptoFactories};
-use tari_crypto::keys::PublicKey;
+use tari_crypto::keys::{PublicKey, SecretKey};
 use tari_p2p::initialization::CommsConfig;
 use tari_test_utils::{collect_stream, paths::with_temp_dir};
 #[cfg(feature = "test_harness")]
@@ -45,9 +49,18 @@ use tari_wallet::testnet_utils::finalize_received_transaction;
 use tari_wallet::transaction_service::storage::database::{CompletedTransaction, InboundTransaction};
 use tari_wallet::{
     contacts_service::storage::{database::Contact, memory_db::ContactsServiceMemoryDatabase},
-    output_manager_service::storage::memory_db::OutputManagerMemoryDatabase,
-    storage::memory_db::WalletMemoryDatabase,
-    transaction_service::{handle::TransactionEvent, storage::memory_db::TransactionMemoryDatabase},
+    encryption::SecretCipher,
+    error::{BackupError, EncryptionError, WalletError, WalletStorageError},
+    output_manager_service::storage::{memory_db::OutputManagerMemoryDatabase, sqlite_db::OutputManagerSqliteDatabase},
+    storage::{
+        database::{DbKey, DbValue, WalletBackend, WriteOperation},
+        memory_db::WalletMemoryDatabase,
+        sqlite_db::WalletSqliteDatabase,
+    },
+    transaction_service::{
+        handle::TransactionEvent,
+        storage::{memory_db::TransactionMemoryDatabase, sqlite_db::TransactionServiceSqliteDatabase},
+    },
     wallet::WalletConfig,
     Wallet,
 };
@@ -122,11 +135,13 @@ fn test_wallet() {
         let config1 = WalletConfig {
             comms_config: comms_config1,
             logging_path: None,
+            passphrase: None,
             factories: factories.clone(),
         };
         let config2 = WalletConfig {
             comms_config: comms_config2,
             logging_path: None,
+            passphrase: None,
             factories: factories.clone(),
         };
         let runtime_node1 = Runtime::new().unwrap();
@@ -220,6 +235,365 @@ fn test_wallet() {
     });
 }
 
+fn create_encryption_test_config(dir_path: &str, port: u16, passphrase: Option<String>) -> WalletConfig {
+    let mut rng = rand::OsRng::new().unwrap();
+    let identity = NodeIdentity::random(
+        &mut rng,
+        format!("/ip4/127.0.0.1/tcp/{}", port).parse().unwrap(),
+        PeerFeatures::COMMUNICATION_NODE,
+    )
+    .unwrap();
+    let comms_config = CommsConfig {
+        node_identity: Arc::new(identity.clone()),
+        peer_connection_listening_address: "/ip4/127.0.0.1/tcp/0".parse().unwrap(),
+        socks_proxy_address: None,
+        control_service: ControlServiceConfig {
+            listening_address: identity.control_service_address(),
+            socks_proxy_address: None,
+            public_peer_address: None,
+            requested_connection_timeout: Duration::from_millis(2000),
+        },
+        datastore_path: dir_path.to_string(),
+        establish_connection_timeout: Duration::from_secs(10),
+        peer_database_name: random_string(8),
+        inbound_buffer_size: 100,
+        outbound_buffer_size: 100,
+        dht: Default::default(),
+    };
+
+    WalletConfig {
+        comms_config,
+        logging_path: None,
+        factories: CryptoFactories::default(),
+        passphrase,
+    }
+}
+
+#[test]
+fn test_wallet_encryption() {
+    with_temp_dir(|dir_path| {
+        let mut rng = rand::OsRng::new().unwrap();
+        let factories = CryptoFactories::default();
+        let db_path = format!("{}/wallet.sqlite3", dir_path.to_str().unwrap());
+
+        let mut wallet = Wallet::new(
+            create_encryption_test_config(dir_path.to_str().unwrap(), 22714, None),
+            Runtime::new().unwrap(),
+            WalletSqliteDatabase::new(db_path.clone()).unwrap(),
+            TransactionMemoryDatabase::new(),
+            OutputManagerSqliteDatabase::new(db_path.clone()).unwrap(),
+            ContactsServiceMemoryDatabase::new(),
+        )
+        .unwrap();
+
+        // The secret key of an unencrypted wallet's node identity is not stored in plain text
+        assert_eq!(wallet.db.get_comms_secret_key().unwrap(), None);
+        let secret_key = wallet.comms.node_identity().secret_key().clone();
+
+        let value = MicroTari::from(1000);
+        let (_ti, uo) = make_input(&mut rng, value, &factories.commitment);
+        wallet
+            .runtime
+            .block_on(wallet.output_manager_service.add_output(uo))
+            .unwrap();
+
+        wallet.apply_encryption("correct horse").unwrap();
+        match wallet.apply_encryption("correct horse") {
+            Err(WalletError::EncryptionError(EncryptionError::AlreadyEncrypted)) => (),
+            _ => assert!(false),
+        }
+        assert_eq!(wallet.db.get_comms_secret_key().unwrap(), Some(secret_key.clone()));
+        let balance = wallet
+            .runtime
+            .block_on(wallet.output_manager_service.get_balance())
+            .unwrap();
+        assert_eq!(balance.available_balance, value);
+
+        wallet.lock().unwrap();
+        assert!(wallet
+            .runtime
+            .block_on(wallet.output_manager_service.get_balance())
+            .is_err());
+        match wallet.unlock("battery staple") {
+            Err(WalletError::EncryptionError(EncryptionError::IncorrectPassphrase)) => (),
+            _ => assert!(false),
+        }
+        wallet.unlock("correct horse").unwrap();
+        let balance = wallet
+            .runtime
+            .block_on(wallet.output_manager_service.get_balance())
+            .unwrap();
+        assert_eq!(balance.available_balance, value);
+
+        wallet.change_passphrase("correct horse", "battery staple").unwrap();
+        wallet.shutdown().unwrap();
+
+        // An encrypted wallet cannot be created without its passphrase
+        match Wallet::new(
+            create_encryption_test_config(dir_path.to_str().unwrap(), 22715, None),
+            Runtime::new().unwrap(),
+            WalletSqliteDatabase::new(db_path.clone()).unwrap(),
+            TransactionMemoryDatabase::new(),
+            OutputManagerSqliteDatabase::new(db_path.clone()).unwrap(),
+            ContactsServiceMemoryDatabase::new(),
+        ) {
+            Err(WalletError::EncryptionError(EncryptionError::WalletLocked)) => (),
+            _ => assert!(false),
+        }
+
+        let mut wallet = Wallet::new(
+            create_encryption_test_config(dir_path.to_str().unwrap(), 22716, Some("battery staple".to_string())),
+            Runtime::new().unwrap(),
+            WalletSqliteDatabase::new(db_path.clone()).unwrap(),
+            TransactionMemoryDatabase::new(),
+            OutputManagerSqliteDatabase::new(db_path.clone()).unwrap(),
+            ContactsServiceMemoryDatabase::new(),
+        )
+        .unwrap();
+        // The node identity is built from the stored secret key rather than the one in the config
+        assert_eq!(wallet.comms.node_identity().secret_key(), &secret_key);
+        let balance = wallet
+            .runtime
+            .block_on(wallet.output_manager_service.get_balance())
+            .unwrap();
+        assert_eq!(balance.available_balance, value);
+
+        wallet.remove_encryption("battery staple").unwrap();
+        match wallet.unlock("battery staple") {
+            Err(WalletError::EncryptionError(EncryptionError::NotEncrypted)) => (),
+            _ => assert!(false),
+        }
+        assert_eq!(wallet.db.get_comms_secret_key().unwrap(), None);
+        wallet.shutdown().unwrap();
+    });
+}
+
+/// A wallet backend that fails to complete the next encryption change when asked to, as if the wallet had been
+/// interrupted after the secrets of its services were re-encrypted
+struct InterruptedWalletDatabase {
+    db: WalletSqliteDatabase,
+    interrupt: Arc<AtomicBool>,
+}
+
+impl WalletBackend for InterruptedWalletDatabase {
+    fn fetch(&self, key: &DbKey) -> Result<Option<DbValue>, WalletStorageError> {
+        self.db.fetch(key)
+    }
+
+    fn write(&mut self, op: WriteOperation) -> Result<Option<DbValue>, WalletStorageError> {
+        self.db.write(op)
+    }
+
+    fn apply_encryption(&mut self, cipher: Option<SecretCipher>) -> Result<(), WalletStorageError> {
+        if self.interrupt.swap(false, Ordering::SeqCst) {
+            return Err(WalletStorageError::OperationNotSupported);
+        }
+        self.db.apply_encryption(cipher)
+    }
+
+    fn unlock(&mut self, cipher: SecretCipher) -> Result<(), WalletStorageError> {
+        self.db.unlock(cipher)
+    }
+
+    fn lock(&mut self) -> Result<(), WalletStorageError> {
+        self.db.lock()
+    }
+}
+
+#[test]
+fn test_wallet_interrupted_encryption_change() {
+    with_temp_dir(|dir_path| {
+        let mut rng = rand::OsRng::new().unwrap();
+        let factories = CryptoFactories::default();
+        let db_path = format!("{}/wallet.sqlite3", dir_path.to_str().unwrap());
+        let interrupt = Arc::new(AtomicBool::new(false));
+        let create_wallet = |port: u16, passphrase: Option<&str>| {
+            Wallet::new(
+                create_encryption_test_config(dir_path.to_str().unwrap(), port, passphrase.map(|p| p.to_string())),
+                Runtime::new().unwrap(),
+                InterruptedWalletDatabase {
+                    db: WalletSqliteDatabase::new(db_path.clone()).unwrap(),
+                    interrupt: interrupt.clone(),
+                },
+                TransactionServiceSqliteDatabase::new(db_path.clone()).unwrap(),
+                OutputManagerSqliteDatabase::new(db_path.clone()).unwrap(),
+                ContactsServiceMemoryDatabase::new(),
+            )
+        };
+
+        let mut wallet = create_wallet(22719, None).unwrap();
+        let value = MicroTari::from(1000);
+        let (_ti, uo) = make_input(&mut rng, value, &factories.commitment);
+        wallet
+            .runtime
+            .block_on(wallet.output_manager_service.add_output(uo))
+            .unwrap();
+
+        // Applying encryption is interrupted after the outputs have been re-encrypted but before the settings are
+        // stored, and no other change can be started until it has been completed
+        interrupt.store(true, Ordering::SeqCst);
+        assert!(wallet.apply_encryption("correct horse").is_err());
+        assert_eq!(wallet.db.get_encryption_settings().unwrap(), None);
+        match wallet.apply_encryption("correct horse") {
+            Err(WalletError::EncryptionError(EncryptionError::ChangePending)) => (),
+            _ => assert!(false),
+        }
+        wallet.shutdown().unwrap();
+
+        // The change is completed when the wallet is next created with the new passphrase
+        match create_wallet(22720, None) {
+            Err(WalletError::EncryptionError(EncryptionError::WalletLocked)) => (),
+            _ => assert!(false),
+        }
+        let mut wallet = create_wallet(22721, Some("correct horse")).unwrap();
+        assert!(wallet.db.get_encryption_settings().unwrap().is_some());
+        assert_eq!(wallet.db.get_pending_encryption().unwrap(), None);
+        let secret_key = wallet.comms.node_identity().secret_key().clone();
+        assert_eq!(wallet.db.get_comms_secret_key().unwrap(), Some(secret_key.clone()));
+        let balance = wallet
+            .runtime
+            .block_on(wallet.output_manager_service.get_balance())
+            .unwrap();
+        assert_eq!(balance.available_balance, value);
+
+        // An interrupted passphrase change can be completed with either passphrase
+        for (port, passphrase) in [(22722, "correct horse"), (22724, "battery staple")].iter() {
+            interrupt.store(true, Ordering::SeqCst);
+            assert!(wallet.change_passphrase("correct horse", "battery staple").is_err());
+            wallet.shutdown().unwrap();
+
+            wallet = create_wallet(*port, Some(*passphrase)).unwrap();
+            assert_eq!(wallet.db.get_pending_encryption().unwrap(), None);
+            assert_eq!(wallet.comms.node_identity().secret_key(), &secret_key);
+            let balance = wallet
+                .runtime
+                .block_on(wallet.output_manager_service.get_balance())
+                .unwrap();
+            assert_eq!(balance.available_balance, value);
+            wallet.shutdown().unwrap();
+
+            // The wallet now only opens with the new passphrase
+            match create_wallet(*port + 1, Some("correct horse")) {
+                Err(WalletError::EncryptionError(EncryptionError::IncorrectPassphrase)) => (),
+                _ => assert!(false),
+            }
+            wallet = create_wallet(*port + 1, Some("battery staple")).unwrap();
+            wallet.change_passphrase("battery staple", "correct horse").unwrap();
+        }
+
+        // Removing encryption is completed with the passphrase
+        interrupt.store(true, Ordering::SeqCst);
+        assert!(wallet.remove_encryption("correct horse").is_err());
+        wallet.shutdown().unwrap();
+        let mut wallet = create_wallet(22726, Some("correct horse")).unwrap();
+        assert_eq!(wallet.db.get_encryption_settings().unwrap(), None);
+        assert_eq!(wallet.db.get_pending_encryption().unwrap(), None);
+        assert_eq!(wallet.db.get_comms_secret_key().unwrap(), None);
+        let balance = wallet
+            .runtime
+            .block_on(wallet.output_manager_service.get_balance())
+            .unwrap();
+        assert_eq!(balance.available_balance, value);
+        wallet.shutdown().unwrap();
+    });
+}
+
+#[test]
+fn test_wallet_backup_and_restore() {
+    with_temp_dir(|dir_path| {
+        let mut rng = rand::OsRng::new().unwrap();
+        let factories = CryptoFactories::default();
+        let alice_db_path = format!("{}/alice.sqlite3", dir_path.to_str().unwrap());
+        let bob_db_path = format!("{}/bob.sqlite3", dir_path.to_str().unwrap());
+        let backup_path = dir_path.join("alice.backup");
+
+        let mut alice_wallet = Wallet::new(
+            create_encryption_test_config(dir_path.to_str().unwrap(), 22717, None),
+            Runtime::new().unwrap(),
+            WalletSqliteDatabase::new(alice_db_path.clone()).unwrap(),
+            TransactionMemoryDatabase::new(),
+            OutputManagerSqliteDatabase::new(alice_db_path.clone()).unwrap(),
+            ContactsServiceMemoryDatabase::new(),
+        )
+        .unwrap();
+
+        let mut total = MicroTari::from(0);
+        for i in 1..4 {
+            let value = MicroTari::from(1000 * i);
+            let (_ti, uo) = make_input(&mut rng, value, &factories.commitment);
+            alice_wallet
+                .runtime
+                .block_on(alice_wallet.output_manager_service.add_output(uo))
+                .unwrap();
+            total += value;
+        }
+        let contact = Contact {
+            alias: random_string(8),
+            public_key: CommsPublicKey::from_secret_key(&CommsSecretKey::random(&mut rng)),
+        };
+        alice_wallet
+            .runtime
+            .block_on(alice_wallet.contacts_service.save_contact(contact.clone()))
+            .unwrap();
+        let seed_words = alice_wallet
+            .runtime
+            .block_on(alice_wallet.output_manager_service.get_seed_words())
+            .unwrap();
+
+        // The wallet's own encryption does not affect the backup, which has its own passphrase
+        alice_wallet.apply_encryption("correct horse").unwrap();
+        alice_wallet.backup_to(&backup_path, "battery staple").unwrap();
+        let alice_secret_key = alice_wallet.comms.node_identity().secret_key().clone();
+        alice_wallet.shutdown().unwrap();
+
+        let mut bob_wallet = Wallet::new(
+            create_encryption_test_config(dir_path.to_str().unwrap(), 22718, None),
+            Runtime::new().unwrap(),
+            WalletSqliteDatabase::new(bob_db_path.clone()).unwrap(),
+            TransactionMemoryDatabase::new(),
+            OutputManagerSqliteDatabase::new(bob_db_path.clone()).unwrap(),
+            ContactsServiceMemoryDatabase::new(),
+        )
+        .unwrap();
+
+        match bob_wallet.restore_from(&backup_path, "correct horse") {
+            Err(WalletError::BackupError(BackupError::DecryptionFailed)) => (),
+            _ => assert!(false),
+        }
+        match bob_wallet.restore_from(&dir_path.join("missing.backup"), "battery staple") {
+            Err(WalletError::BackupError(BackupError::IoError(_))) => (),
+            _ => assert!(false),
+        }
+
+        let restored_secret_key = bob_wallet.restore_from(&backup_path, "battery staple").unwrap();
+        assert_eq!(restored_secret_key, alice_secret_key);
+        let balance = bob_wallet
+            .runtime
+            .block_on(bob_wallet.output_manager_service.get_balance())
+            .unwrap();
+        assert_eq!(balance.available_balance, total);
+        let restored_seed_words = bob_wallet
+            .runtime
+            .block_on(bob_wallet.output_manager_service.get_seed_words())
+            .unwrap();
+        assert_eq!(restored_seed_words, seed_words);
+        let contacts = bob_wallet
+            .runtime
+            .block_on(bob_wallet.contacts_service.get_contacts())
+            .unwrap();
+        assert_eq!(contacts, vec![contact]);
+        // Bob's wallet is not encrypted, so the restored secret key is returned but not stored
+        assert_eq!(bob_wallet.db.get_comms_secret_key().unwrap(), None);
+
+        // A backup can only be restored into an empty wallet
+        match bob_wallet.restore_from(&backup_path, "battery staple") {
+            Err(WalletError::WalletNotEmpty) => (),
+            _ => assert!(false),
+        }
+        bob_wallet.shutdown().unwrap();
+    });
+}
+
 #[cfg(feature = "test_harness")]
 #[test]
 fn test_data_generation() {
@@ -261,6 +635,7 @@ fn test_data_generation() {
         comms_config,
         factories,
         logging_path: None,
+        passphrase: None,
     };
 
     let transaction_backend = TransactionMemoryDatabase::new();
@@ -429,6 +804,7 @@ fn test_test_harness() {
         comms_config: comms_config1,
         factories: factories.clone(),
         logging_path: None,
+        passphrase: None,
     };
 
     let runtime = Runtime::new().unwrap();
@@ -28,8 +28,9 @@ use tari_comms::{
 use tari_utilities::{hex::HexError, ByteArrayError};
 use tari_wallet::{
     contacts_service::error::{ContactsServiceError, ContactsServiceStorageError},
-    error::WalletError,
+    error::{BackupError, EmojiIdError, EncryptionError, PaymentRequestError, WalletError, WalletStorageError},
     output_manager_service::error::{OutputManagerError, OutputManagerStorageError},
+    recovery_service::error::RecoveryServiceError,
     transaction_service::error::{TransactionServiceError, TransactionStorageError},
 };
 
@@ -130,6 +131,28 @@ impl From<WalletError> for LibWalletError {
                 code: 108,
                 message: format!("{:?}", w),
             },
+            WalletError::OutputManagerError(OutputManagerError::NotEnoughOutputs) => Self {
+                code: 109,
+                message: format!("{:?}", w),
+            },
+            WalletError::OutputManagerError(OutputManagerError::InvalidCoinSplit) |
+            WalletError::TransactionServiceError(TransactionServiceError::OutputManagerError(
+                OutputManagerError::InvalidCoinSplit,
+            )) => Self {
+                code: 110,
+                message: format!("{:?}", w),
+            },
+            WalletError::OutputManagerError(OutputManagerError::WatchOnlyWallet) |
+            WalletError::TransactionServiceError(TransactionServiceError::OutputManagerError(
+                OutputManagerError::WatchOnlyWallet,
+            )) => Self {
+                code: 111,
+                message: format!("{:?}", w),
+            },
+            WalletError::OutputManagerError(OutputManagerError::WatchOnlyImportNotAllowed) => Self {
+                code: 112,
+                message: format!("{:?}", w),
+            },
             // Transaction Service Errors
             WalletError::TransactionServiceError(TransactionServiceError::InvalidStateError) => Self {
                 code: 201,
@@ -177,6 +200,14 @@ impl From<WalletError> for LibWalletError {
                 code: 210,
                 message: format!("{:?}", w),
             },
+            WalletError::TransactionServiceError(TransactionServiceError::TransactionCancelled) => Self {
+                code: 211,
+                message: format!("{:?}", w),
+            },
+            WalletError::TransactionServiceError(TransactionServiceError::NoBaseNodePublicKey) => Self {
+                code: 212,
+                message: format!("{:?}", w),
+            },
             // Comms Stack errors
             WalletError::MultiaddrError(_) => Self {
                 code: 301,
@@ -210,6 +241,73 @@ impl From<WalletError> for LibWalletError {
                 code: 405,
                 message: format!("{:?}", w),
             },
+            // Encryption errors, which can also be encountered by the storage backends
+            WalletError::EncryptionError(EncryptionError::IncorrectPassphrase) |
+            WalletError::WalletStorageError(WalletStorageError::EncryptionError(
+                EncryptionError::IncorrectPassphrase,
+            )) |
+            WalletError::OutputManagerError(OutputManagerError::OutputManagerStorageError(
+                OutputManagerStorageError::EncryptionError(EncryptionError::IncorrectPassphrase),
+            )) => Self {
+                code: 901,
+                message: format!("{:?}", w),
+            },
+            WalletError::EncryptionError(EncryptionError::WalletLocked) |
+            WalletError::WalletStorageError(WalletStorageError::EncryptionError(EncryptionError::WalletLocked)) |
+            WalletError::OutputManagerError(OutputManagerError::OutputManagerStorageError(
+                OutputManagerStorageError::EncryptionError(EncryptionError::WalletLocked),
+            )) => Self {
+                code: 902,
+                message: format!("{:?}", w),
+            },
+            WalletError::EncryptionError(EncryptionError::AlreadyEncrypted) => Self {
+                code: 903,
+                message: format!("{:?}", w),
+            },
+            WalletError::EncryptionError(EncryptionError::NotEncrypted) |
+            WalletError::WalletStorageError(WalletStorageError::EncryptionError(EncryptionError::NotEncrypted)) |
+            WalletError::OutputManagerError(OutputManagerError::OutputManagerStorageError(
+                OutputManagerStorageError::EncryptionError(EncryptionError::NotEncrypted),
+            )) => Self {
+                code: 904,
+                message: format!("{:?}", w),
+            },
+            // Backup errors
+            WalletError::BackupError(BackupError::UnsupportedVersion(_)) => Self {
+                code: 905,
+                message: format!("{:?}", w),
+            },
+            WalletError::BackupError(BackupError::DecryptionFailed) => Self {
+                code: 906,
+                message: format!("{:?}", w),
+            },
+            WalletError::BackupError(BackupError::InvalidBackupFile) => Self {
+                code: 907,
+                message: format!("{:?}", w),
+            },
+            WalletError::WalletNotEmpty => Self {
+                code: 908,
+                message: format!("{:?}", w),
+            },
+            WalletError::BackupError(BackupError::IoError(_)) => Self {
+                code: 909,
+                message: format!("{:?}", w),
+            },
+            // Recovery Service errors
+            WalletError::RecoveryServiceError(RecoveryServiceError::RecoveryInProgress) => Self {
+                code: 1001,
+                message: format!("{:?}", w),
+            },
+            WalletError::RecoveryServiceError(RecoveryServiceError::NoBaseNodePublicKey) => Self {
+                code: 1002,
+                message: format!("{:?}", w),
+            },
+            WalletError::RecoveryServiceError(RecoveryServiceError::KeyManagerError(_)) => Self {
+                code: 1003,
+                message: format!("{:?}", w),
+            },
+            // Payment request errors
+            WalletError::TransactionServiceError(TransactionServiceError::PaymentRequestError(e)) => Self::from(e),
             // This is the catch all error code. Any error that is not explicitly mapped above will be given this code
             _ => Self {
                 code: 999,
@@ -279,6 +377,92 @@ impl From<NodeIdentityError> for LibWalletError {
     }
 }
 
+/// This implementation maps the internal PaymentRequestError to a set of LibWalletErrors, which are returned both when
+/// a payment request is parsed and when it is paid
+impl From<PaymentRequestError> for LibWalletError {
+    fn from(p: PaymentRequestError) -> Self {
+        error!(target: LOG_TARGET, "{}", format!("{:?}", p));
+        match p {
+            PaymentRequestError::InvalidScheme => Self {
+                code: 1101,
+                message: format!("{:?}", p).to_string(),
+            },
+            PaymentRequestError::UnknownNetwork => Self {
+                code: 1102,
+                message: format!("{:?}", p).to_string(),
+            },
+            PaymentRequestError::InvalidPublicKey => Self {
+                code: 1103,
+                message: format!("{:?}", p).to_string(),
+            },
+            PaymentRequestError::InvalidAmount => Self {
+                code: 1104,
+                message: format!("{:?}", p).to_string(),
+            },
+            PaymentRequestError::InvalidExpiry => Self {
+                code: 1105,
+                message: format!("{:?}", p).to_string(),
+            },
+            PaymentRequestError::InvalidEncoding => Self {
+                code: 1106,
+                message: format!("{:?}", p).to_string(),
+            },
+            PaymentRequestError::DuplicateParameter => Self {
+                code: 1107,
+                message: format!("{:?}", p).to_string(),
+            },
+            PaymentRequestError::MissingChecksum => Self {
+                code: 1108,
+                message: format!("{:?}", p).to_string(),
+            },
+            PaymentRequestError::InvalidChecksum => Self {
+                code: 1109,
+                message: format!("{:?}", p).to_string(),
+            },
+            PaymentRequestError::NetworkMismatch => Self {
+                code: 1110,
+                message: format!("{:?}", p).to_string(),
+            },
+            PaymentRequestError::Expired => Self {
+                code: 1111,
+                message: format!("{:?}", p).to_string(),
+            },
+            PaymentRequestError::MissingAmount => Self {
+                code: 1112,
+                message: format!("{:?}", p).to_string(),
+            },
+            PaymentRequestError::AmountMismatch => Self {
+                code: 1113,
+                message: format!("{:?}", p).to_string(),
+            },
+        }
+    }
+}
+
+impl From<EmojiIdError> for LibWalletError {
+    fn from(e: EmojiIdError) -> Self {
+        error!(target: LOG_TARGET, "{}", format!("{:?}", e));
+        match e {
+            EmojiIdError::InvalidLength => Self {
+                code: 1201,
+                message: format!("{:?}", e).to_string(),
+            },
+            EmojiIdError::InvalidEmoji => Self {
+                code: 1202,
+                message: format!("{:?}", e).to_string(),
+            },
+            EmojiIdError::InvalidChecksum => Self {
+                code: 1203,
+                message: format!("{:?}", e).to_string(),
+            },
+            EmojiIdError::InvalidPublicKey => Self {
+                code: 1204,
+                message: format!("{:?}", e).to_string(),
+            },
+        }
+    }
+}
+
 impl From<multiaddr::Error> for LibWalletError {
     fn from(err: multiaddr::Error) -> Self {
         error!(target: LOG_TARGET, "{}", format!("{:?}", err));
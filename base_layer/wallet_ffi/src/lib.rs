@@ -114,10 +114,12 @@ extern crate tari_wallet;
 mod error;
 use error::LibWalletError;
 
+use chrono::NaiveDateTime;
 use libc::{c_char, c_int, c_longlong, c_uchar, c_uint, c_ulonglong};
 use std::{
     boxed::Box,
     ffi::{CStr, CString},
+    path::Path,
     slice,
 };
 use tari_comms::peer_manager::NodeIdentity;
@@ -136,6 +138,7 @@ use tari_crypto::keys::PublicKey;
 use tari_utilities::hex::Hex;
 use tari_wallet::{
     contacts_service::storage::{database::Contact, sqlite_db::ContactsServiceSqliteDatabase},
+    emoji::EmojiId,
     error::WalletError,
     output_manager_service::storage::sqlite_db::OutputManagerSqliteDatabase,
     storage::sqlite_db::WalletSqliteDatabase,
@@ -147,7 +150,10 @@ use tari_wallet::{
         mine_transaction,
         receive_test_transaction,
     },
-    transaction_service::storage::sqlite_db::TransactionServiceSqliteDatabase,
+    transaction_service::{
+        history::TransactionHistoryFormat,
+        storage::{database::TransactionHistoryQuery, sqlite_db::TransactionServiceSqliteDatabase},
+    },
 };
 use tokio::runtime::Runtime;
 
@@ -169,6 +175,10 @@ pub type TariPendingInboundTransaction = tari_wallet::transaction_service::stora
 pub struct TariPendingInboundTransactions(Vec<TariPendingInboundTransaction>);
 pub type TariPendingOutboundTransaction = tari_wallet::transaction_service::storage::database::OutboundTransaction;
 pub struct TariPendingOutboundTransactions(Vec<TariPendingOutboundTransaction>);
+pub struct TariSeedWords(Vec<String>);
+pub type TariUnblindedOutput = tari_core::transactions::transaction::UnblindedOutput;
+pub struct TariUnblindedOutputs(Vec<TariUnblindedOutput>);
+pub type TariPaymentRequest = tari_wallet::payment_request::PaymentRequest;
 pub struct ByteVector(Vec<c_uchar>); // declared like this so that it can be exposed to external header
 
 /// -------------------------------- Strings ------------------------------------------------ ///
@@ -428,6 +438,65 @@ pub unsafe extern "C" fn public_key_from_hex(key: *const c_char, error_out: *mut
         },
     }
 }
+/// Encodes a TariPublicKey as an emoji ID, a string of emoji with a checksum for users to compare when they check who
+/// they are paying
+///
+/// ## Arguments
+/// `pk` - The pointer to a TariPublicKey
+/// `error_out` - Pointer to an int which will be modified to an error code should one occur, may not be null. Functions
+/// as an out parameter.
+///
+/// ## Returns
+/// `*mut c_char` - Returns a pointer to a char array of the emoji ID. Note that it returns an empty char array if pk is
+/// null
+#[no_mangle]
+pub unsafe extern "C" fn public_key_to_emoji_id(pk: *mut TariPublicKey, error_out: *mut c_int) -> *mut c_char {
+    let mut error = 0;
+    ptr::swap(error_out, &mut error as *mut c_int);
+    let mut result = CString::new("").unwrap();
+    if pk.is_null() {
+        error = LibWalletError::from(InterfaceError::NullError("pk".to_string())).code;
+        ptr::swap(error_out, &mut error as *mut c_int);
+        return result.into_raw();
+    }
+
+    result = CString::new(EmojiId::from_pubkey(&(*pk)).to_string()).unwrap();
+    result.into_raw()
+}
+
+/// Creates a TariPublicKey from an emoji ID, checking its checksum
+///
+/// ## Arguments
+/// `emoji` - The pointer to a char array of the emoji ID
+/// `error_out` - Pointer to an int which will be modified to an error code should one occur, may not be null. Functions
+/// as an out parameter.
+///
+/// ## Returns
+/// `*mut TariPublicKey` - Returns a pointer to a TariPublicKey. Note that it returns ptr::null_mut()
+/// if emoji is null or if it is not a valid emoji ID
+#[no_mangle]
+pub unsafe extern "C" fn emoji_id_to_public_key(emoji: *const c_char, error_out: *mut c_int) -> *mut TariPublicKey {
+    let mut error = 0;
+    ptr::swap(error_out, &mut error as *mut c_int);
+    let emoji_str;
+    if emoji.is_null() {
+        error = LibWalletError::from(InterfaceError::NullError("emoji".to_string())).code;
+        ptr::swap(error_out, &mut error as *mut c_int);
+        return ptr::null_mut();
+    } else {
+        emoji_str = CStr::from_ptr(emoji).to_str().unwrap().to_owned();
+    }
+
+    match EmojiId::from_emoji_string(emoji_str.as_str()) {
+        Ok(emoji_id) => Box::into_raw(Box::new(emoji_id.to_pubkey())),
+        Err(e) => {
+            error = LibWalletError::from(e).code;
+            ptr::swap(error_out, &mut error as *mut c_int);
+            ptr::null_mut()
+        },
+    }
+}
+
 /// -------------------------------------------------------------------------------------------- ///
 
 /// -------------------------------- Private Key ----------------------------------------------- ///
@@ -1167,6 +1236,32 @@ pub unsafe extern "C" fn completed_transaction_get_timestamp(
     (*transaction).timestamp.timestamp() as c_longlong
 }
 
+/// Gets the height of the chain when a TariCompletedTransaction was detected as mined
+///
+/// ## Arguments
+/// `transaction` - The pointer to a TariCompletedTransaction
+/// `error_out` - Pointer to an int which will be modified to an error code should one occur, may not be null. Functions
+/// as an out parameter.
+///
+/// ## Returns
+/// `c_ulonglong` - Returns the block height, note that it will be zero if the transaction has not been mined or if
+/// transaction is null
+#[no_mangle]
+pub unsafe extern "C" fn completed_transaction_get_mined_height(
+    transaction: *mut TariCompletedTransaction,
+    error_out: *mut c_int,
+) -> c_ulonglong
+{
+    let mut error = 0;
+    ptr::swap(error_out, &mut error as *mut c_int);
+    if transaction.is_null() {
+        error = LibWalletError::from(InterfaceError::NullError("transaction".to_string())).code;
+        ptr::swap(error_out, &mut error as *mut c_int);
+        return 0;
+    }
+    (*transaction).mined_height.unwrap_or(0) as c_ulonglong
+}
+
 /// Gets the message of a TariCompletedTransaction
 ///
 /// ## Arguments
@@ -1512,195 +1607,653 @@ pub unsafe extern "C" fn pending_inbound_transaction_destroy(transaction: *mut T
 }
 /// -------------------------------------------------------------------------------------------- ///
 
-/// ----------------------------------- CommsConfig ---------------------------------------------///
+/// ----------------------------------- SeedWords ----------------------------------------------///
 
-/// Creates a TariCommsConfig. The result from this function is required when initializing a TariWallet.
+/// Creates an empty TariSeedWords, to which the words of a mnemonic can be pushed to recover a wallet
 ///
 /// ## Arguments
-/// `control_service_address` - The control service address char array pointer. This is the address that the wallet
-/// listens for initial connections on
-/// `listener_address` - The listener address char array pointer. This is the address that inbound peer connections
-/// are moved to after initial connection. Default if null is 0.0.0.0:7898 which will accept connections from all IP
-/// address on port 7898
-/// `database_name` - The database name char array pointer. This is the unique name of this
-/// wallet's database `database_path` - The database path char array pointer which. This is the folder path where the
-/// database files will be created and the application has write access to
-/// `secret_key` - The TariSecretKey pointer. This is the secret key corresponding to the Public key that represents
-/// this node on the Tari comms network
+/// `()` - Does not take any arguments
+///
+/// ## Returns
+/// `*mut TariSeedWords` - Returns a pointer to an empty TariSeedWords
+#[no_mangle]
+pub unsafe extern "C" fn seed_words_create() -> *mut TariSeedWords {
+    Box::into_raw(Box::new(TariSeedWords(Vec::new())))
+}
+
+/// Gets the number of words in a TariSeedWords
+///
+/// ## Arguments
+/// `seed_words` - The pointer to a TariSeedWords
 /// `error_out` - Pointer to an int which will be modified to an error code should one occur, may not be null. Functions
 /// as an out parameter.
 ///
 /// ## Returns
-/// `*mut TariCommsConfig` - Returns a pointer to a TariCommsConfig, if any of the parameters are
-/// null or a problem is encountered when constructing the NetAddress a ptr::null_mut() is returned
+/// `c_uint` - Returns the number of words, zero if seed_words is null
 #[no_mangle]
-pub unsafe extern "C" fn comms_config_create(
-    control_service_address: *const c_char,
-    listener_address: *const c_char,
-    database_name: *const c_char,
-    datastore_path: *const c_char,
-    secret_key: *mut TariPrivateKey,
-    error_out: *mut c_int,
-) -> *mut TariCommsConfig
-{
+pub unsafe extern "C" fn seed_words_get_length(seed_words: *const TariSeedWords, error_out: *mut c_int) -> c_uint {
     let mut error = 0;
     ptr::swap(error_out, &mut error as *mut c_int);
-    let control_service_address_string;
-    if !control_service_address.is_null() {
-        control_service_address_string = CStr::from_ptr(control_service_address).to_str().unwrap().to_owned();
-    } else {
-        error = LibWalletError::from(InterfaceError::NullError("control_service_address".to_string())).code;
+    let mut len = 0;
+    if seed_words.is_null() {
+        error = LibWalletError::from(InterfaceError::NullError("seed_words".to_string())).code;
         ptr::swap(error_out, &mut error as *mut c_int);
-        return ptr::null_mut();
+    } else {
+        len = (*seed_words).0.len();
     }
+    len as c_uint
+}
 
-    let listener_address_string;
-    if !listener_address.is_null() {
-        listener_address_string = CStr::from_ptr(listener_address).to_str().unwrap().to_owned();
+/// Gets the word of a TariSeedWords at position
+///
+/// ## Arguments
+/// `seed_words` - The pointer to a TariSeedWords
+/// `position` - The integer position
+/// `error_out` - Pointer to an int which will be modified to an error code should one occur, may not be null. Functions
+/// as an out parameter.
+///
+/// ## Returns
+/// `*mut c_char` - Returns a pointer to a char array. Note that it returns an empty char array if seed_words is null
+/// or position is invalid
+#[no_mangle]
+pub unsafe extern "C" fn seed_words_get_at(
+    seed_words: *mut TariSeedWords,
+    position: c_uint,
+    error_out: *mut c_int,
+) -> *mut c_char
+{
+    let mut error = 0;
+    ptr::swap(error_out, &mut error as *mut c_int);
+    let mut word = CString::new("").unwrap();
+    if seed_words.is_null() {
+        error = LibWalletError::from(InterfaceError::NullError("seed_words".to_string())).code;
+        ptr::swap(error_out, &mut error as *mut c_int);
     } else {
-        listener_address_string = "/ip4/0.0.0.0/tcp/7898".to_string();
+        let len = (*seed_words).0.len();
+        if position as usize >= len {
+            error = LibWalletError::from(InterfaceError::PositionInvalidError).code;
+            ptr::swap(error_out, &mut error as *mut c_int);
+        } else {
+            word = CString::new((*seed_words).0[position as usize].clone()).unwrap();
+        }
     }
+    CString::into_raw(word)
+}
 
-    let database_name_string;
-    if !database_name.is_null() {
-        database_name_string = CStr::from_ptr(database_name).to_str().unwrap().to_owned();
-    } else {
-        error = LibWalletError::from(InterfaceError::NullError("database_name".to_string())).code;
+/// Adds a word to the end of a TariSeedWords
+///
+/// ## Arguments
+/// `seed_words` - The pointer to a TariSeedWords
+/// `word` - The pointer to a char array containing the word
+/// `error_out` - Pointer to an int which will be modified to an error code should one occur, may not be null. Functions
+/// as an out parameter.
+///
+/// ## Returns
+/// `bool` - Returns if successful or not
+#[no_mangle]
+pub unsafe extern "C" fn seed_words_push_word(
+    seed_words: *mut TariSeedWords,
+    word: *const c_char,
+    error_out: *mut c_int,
+) -> bool
+{
+    let mut error = 0;
+    ptr::swap(error_out, &mut error as *mut c_int);
+    if seed_words.is_null() {
+        error = LibWalletError::from(InterfaceError::NullError("seed_words".to_string())).code;
         ptr::swap(error_out, &mut error as *mut c_int);
-        return ptr::null_mut();
+        return false;
     }
 
-    let datastore_path_string;
-    if !datastore_path.is_null() {
-        datastore_path_string = CStr::from_ptr(datastore_path).to_str().unwrap().to_owned();
-    } else {
-        error = LibWalletError::from(InterfaceError::NullError("datastore_path".to_string())).code;
+    if word.is_null() {
+        error = LibWalletError::from(InterfaceError::NullError("word".to_string())).code;
         ptr::swap(error_out, &mut error as *mut c_int);
-        return ptr::null_mut();
+        return false;
     }
 
-    let listener_address = listener_address_string.parse::<Multiaddr>();
-    let control_service_address = control_service_address_string.parse::<Multiaddr>();
-
-    match listener_address {
-        Ok(listener_address) => match control_service_address {
-            Ok(control_service_address) => {
-                let ni = NodeIdentity::new(
-                    (*secret_key).clone(),
-                    control_service_address,
-                    PeerFeatures::COMMUNICATION_CLIENT,
-                );
-                match ni {
-                    Ok(ni) => {
-                        let config = TariCommsConfig {
-                            node_identity: Arc::new(ni.clone()),
-                            peer_connection_listening_address: listener_address,
-                            socks_proxy_address: None,
-                            control_service: ControlServiceConfig {
-                                listening_address: ni.control_service_address(),
-                                socks_proxy_address: None,
-                                public_peer_address: None,
-                                requested_connection_timeout: Duration::from_millis(2000),
-                            },
-                            establish_connection_timeout: Duration::from_secs(10),
-                            datastore_path: datastore_path_string,
-                            peer_database_name: database_name_string,
-                            inbound_buffer_size: 100,
-                            outbound_buffer_size: 100,
-                            dht: DhtConfig::default(),
-                        };
-
-                        Box::into_raw(Box::new(config))
-                    },
-                    Err(e) => {
-                        error = LibWalletError::from(e).code;
-                        ptr::swap(error_out, &mut error as *mut c_int);
-                        ptr::null_mut()
-                    },
-                }
-            },
-            Err(e) => {
-                error = LibWalletError::from(e).code;
-                ptr::swap(error_out, &mut error as *mut c_int);
-                return ptr::null_mut();
-            },
-        },
-        Err(e) => {
-            error = LibWalletError::from(e).code;
-            ptr::swap(error_out, &mut error as *mut c_int);
-            return ptr::null_mut();
-        },
-    }
+    (*seed_words).0.push(CStr::from_ptr(word).to_str().unwrap().to_owned());
+    true
 }
 
-/// Frees memory for a TariCommsConfig
+/// Frees memory for a TariSeedWords
 ///
 /// ## Arguments
-/// `wc` - The TariCommsConfig pointer
+/// `seed_words` - The pointer to a TariSeedWords
 ///
 /// ## Returns
 /// `()` - Does not return a value, equivalent to void in C
 #[no_mangle]
-pub unsafe extern "C" fn comms_config_destroy(wc: *mut TariCommsConfig) {
-    if !wc.is_null() {
-        Box::from_raw(wc);
+pub unsafe extern "C" fn seed_words_destroy(seed_words: *mut TariSeedWords) {
+    if !seed_words.is_null() {
+        Box::from_raw(seed_words);
     }
 }
 
-/// ---------------------------------------------------------------------------------------------- ///
+/// -------------------------------------------------------------------------------------------- ///
 
-/// ------------------------------------- Wallet -------------------------------------------------///
+/// ----------------------------------- UnblindedOutputs ----------------------------------------///
 
-/// Creates a TariWallet
+/// Gets the number of outputs in a TariUnblindedOutputs
 ///
 /// ## Arguments
-/// `config` - The TariCommsConfig pointer
-/// `log_path` - An optional file path to the file where the logs will be written. If no log is required pass *null*
-/// pointer.
-/// `callback_received_transaction` - The callback function pointer matching the function signature
-/// `callback_received_transaction_reply` - The callback function pointer matching the function signature
-/// `callback_received_finalized_transaction` - The callback function pointer matching the function signature
-/// `callback_transaction_broadcast` - The callback function pointer matching the function signature
-/// `callback_transaction_mined` - The callback function pointer matching the function signature
-/// `callback_discovery_process_complete` - The callback function pointer matching the function signature
+/// `outputs` - The pointer to a TariUnblindedOutputs
 /// `error_out` - Pointer to an int which will be modified to an error code should one occur, may not be null. Functions
 /// as an out parameter.
+///
 /// ## Returns
-/// `*mut TariWallet` - Returns a pointer to a TariWallet, note that it returns ptr::null_mut()
-/// if config is null, a wallet error was encountered or if the runtime could not be created
+/// `c_uint` - Returns the number of elements in a TariUnblindedOutputs, zero if outputs is null
 #[no_mangle]
-pub unsafe extern "C" fn wallet_create(
-    config: *mut TariCommsConfig,
-    log_path: *const c_char,
-    callback_received_transaction: unsafe extern "C" fn(*mut TariPendingInboundTransaction),
-    callback_received_transaction_reply: unsafe extern "C" fn(*mut TariCompletedTransaction),
-    callback_received_finalized_transaction: unsafe extern "C" fn(*mut TariCompletedTransaction),
-    callback_transaction_broadcast: unsafe extern "C" fn(*mut TariCompletedTransaction),
-    callback_transaction_mined: unsafe extern "C" fn(*mut TariCompletedTransaction),
-    callback_discovery_process_complete: unsafe extern "C" fn(c_ulonglong, bool),
+pub unsafe extern "C" fn unblinded_outputs_get_length(
+    outputs: *mut TariUnblindedOutputs,
     error_out: *mut c_int,
-) -> *mut TariWallet
+) -> c_uint
 {
     let mut error = 0;
     ptr::swap(error_out, &mut error as *mut c_int);
-    if config.is_null() {
-        error = LibWalletError::from(InterfaceError::NullError("config".to_string())).code;
+    let mut len = 0;
+    if outputs.is_null() {
+        error = LibWalletError::from(InterfaceError::NullError("outputs".to_string())).code;
         ptr::swap(error_out, &mut error as *mut c_int);
-        return ptr::null_mut();
-    }
-
-    let mut logging_path_string = None;
-    if !log_path.is_null() {
-        logging_path_string = Some(CStr::from_ptr(log_path).to_str().unwrap().to_owned());
+    } else {
+        len = (*outputs).0.len();
     }
+    len as c_uint
+}
 
-    let runtime = Runtime::new();
-    let factories = CryptoFactories::default();
-    let w;
-    match runtime {
-        Ok(runtime) => {
-            let sql_database_path = format!(
-                "{}/{}.sqlite3",
+/// Gets a TariUnblindedOutput from a TariUnblindedOutputs at position
+///
+/// ## Arguments
+/// `outputs` - The pointer to a TariUnblindedOutputs
+/// `position` - The integer position
+/// `error_out` - Pointer to an int which will be modified to an error code should one occur, may not be null. Functions
+/// as an out parameter.
+///
+/// ## Returns
+/// `*mut TariUnblindedOutput` - Returns a TariUnblindedOutput, note that it returns ptr::null_mut() if outputs is
+/// null or position is invalid
+#[no_mangle]
+pub unsafe extern "C" fn unblinded_outputs_get_at(
+    outputs: *mut TariUnblindedOutputs,
+    position: c_uint,
+    error_out: *mut c_int,
+) -> *mut TariUnblindedOutput
+{
+    let mut error = 0;
+    ptr::swap(error_out, &mut error as *mut c_int);
+    if outputs.is_null() {
+        error = LibWalletError::from(InterfaceError::NullError("outputs".to_string())).code;
+        ptr::swap(error_out, &mut error as *mut c_int);
+        return ptr::null_mut();
+    }
+    let len = unblinded_outputs_get_length(outputs, error_out) as c_int - 1;
+    if len < 0 || position > len as c_uint {
+        error = LibWalletError::from(InterfaceError::PositionInvalidError).code;
+        ptr::swap(error_out, &mut error as *mut c_int);
+        return ptr::null_mut();
+    }
+    Box::into_raw(Box::new((*outputs).0[position as usize].clone()))
+}
+
+/// Frees memory for a TariUnblindedOutputs
+///
+/// ## Arguments
+/// `outputs` - The pointer to a TariUnblindedOutputs
+///
+/// ## Returns
+/// `()` - Does not return a value, equivalent to void in C
+#[no_mangle]
+pub unsafe extern "C" fn unblinded_outputs_destroy(outputs: *mut TariUnblindedOutputs) {
+    if !outputs.is_null() {
+        Box::from_raw(outputs);
+    }
+}
+
+/// -------------------------------------------------------------------------------------------- ///
+
+/// ----------------------------------- UnblindedOutput -----------------------------------------///
+
+/// Gets the value of a TariUnblindedOutput
+///
+/// ## Arguments
+/// `output` - The pointer to a TariUnblindedOutput
+/// `error_out` - Pointer to an int which will be modified to an error code should one occur, may not be null. Functions
+/// as an out parameter.
+///
+/// ## Returns
+/// `c_ulonglong` - Returns the value of the output, zero if output is null
+#[no_mangle]
+pub unsafe extern "C" fn unblinded_output_get_value(
+    output: *mut TariUnblindedOutput,
+    error_out: *mut c_int,
+) -> c_ulonglong
+{
+    let mut error = 0;
+    ptr::swap(error_out, &mut error as *mut c_int);
+    if output.is_null() {
+        error = LibWalletError::from(InterfaceError::NullError("output".to_string())).code;
+        ptr::swap(error_out, &mut error as *mut c_int);
+        return 0;
+    }
+    c_ulonglong::from((*output).value)
+}
+
+/// Gets the maturity of a TariUnblindedOutput, the block height before which it cannot be spent
+///
+/// ## Arguments
+/// `output` - The pointer to a TariUnblindedOutput
+/// `error_out` - Pointer to an int which will be modified to an error code should one occur, may not be null. Functions
+/// as an out parameter.
+///
+/// ## Returns
+/// `c_ulonglong` - Returns the maturity of the output, zero if output is null
+#[no_mangle]
+pub unsafe extern "C" fn unblinded_output_get_maturity(
+    output: *mut TariUnblindedOutput,
+    error_out: *mut c_int,
+) -> c_ulonglong
+{
+    let mut error = 0;
+    ptr::swap(error_out, &mut error as *mut c_int);
+    if output.is_null() {
+        error = LibWalletError::from(InterfaceError::NullError("output".to_string())).code;
+        ptr::swap(error_out, &mut error as *mut c_int);
+        return 0;
+    }
+    (*output).features.maturity as c_ulonglong
+}
+
+/// Frees memory for a TariUnblindedOutput
+///
+/// ## Arguments
+/// `output` - The pointer to a TariUnblindedOutput
+///
+/// ## Returns
+/// `()` - Does not return a value, equivalent to void in C
+#[no_mangle]
+pub unsafe extern "C" fn unblinded_output_destroy(output: *mut TariUnblindedOutput) {
+    if !output.is_null() {
+        Box::from_raw(output);
+    }
+}
+
+/// -------------------------------------------------------------------------------------------- ///
+
+/// ----------------------------------- PaymentRequest ------------------------------------------///
+
+/// Parses a payment request URI of the form `tari://<network>/<public key>?amount=..&message=..&expires=..`, checking
+/// its checksum
+///
+/// ## Arguments
+/// `uri` - The pointer to a char array of the payment request URI
+/// `error_out` - Pointer to an int which will be modified to an error code should one occur, may not be null. Functions
+/// as an out parameter.
+///
+/// ## Returns
+/// `*mut TariPaymentRequest` - Returns a pointer to a TariPaymentRequest. Note that it returns ptr::null_mut()
+/// if uri is null or if it is not a valid payment request
+#[no_mangle]
+pub unsafe extern "C" fn payment_request_from_uri(
+    uri: *const c_char,
+    error_out: *mut c_int,
+) -> *mut TariPaymentRequest
+{
+    let mut error = 0;
+    ptr::swap(error_out, &mut error as *mut c_int);
+    let uri_str;
+    if uri.is_null() {
+        error = LibWalletError::from(InterfaceError::NullError("uri".to_string())).code;
+        ptr::swap(error_out, &mut error as *mut c_int);
+        return ptr::null_mut();
+    } else {
+        uri_str = CStr::from_ptr(uri).to_str().unwrap().to_owned();
+    }
+
+    match TariPaymentRequest::from_uri(uri_str.as_str()) {
+        Ok(request) => Box::into_raw(Box::new(request)),
+        Err(e) => {
+            error = LibWalletError::from(e).code;
+            ptr::swap(error_out, &mut error as *mut c_int);
+            ptr::null_mut()
+        },
+    }
+}
+
+/// Gets the TariPublicKey of the payee of a TariPaymentRequest
+///
+/// ## Arguments
+/// `request` - The pointer to a TariPaymentRequest
+/// `error_out` - Pointer to an int which will be modified to an error code should one occur, may not be null. Functions
+/// as an out parameter.
+///
+/// ## Returns
+/// `*mut TariPublicKey` - Returns a pointer to a TariPublicKey. Note that it returns ptr::null_mut() if request is null
+#[no_mangle]
+pub unsafe extern "C" fn payment_request_get_public_key(
+    request: *mut TariPaymentRequest,
+    error_out: *mut c_int,
+) -> *mut TariPublicKey
+{
+    let mut error = 0;
+    ptr::swap(error_out, &mut error as *mut c_int);
+    if request.is_null() {
+        error = LibWalletError::from(InterfaceError::NullError("request".to_string())).code;
+        ptr::swap(error_out, &mut error as *mut c_int);
+        return ptr::null_mut();
+    }
+    Box::into_raw(Box::new((*request).public_key.clone()))
+}
+
+/// Gets the amount of a TariPaymentRequest
+///
+/// ## Arguments
+/// `request` - The pointer to a TariPaymentRequest
+/// `error_out` - Pointer to an int which will be modified to an error code should one occur, may not be null. Functions
+/// as an out parameter.
+///
+/// ## Returns
+/// `c_ulonglong` - Returns the amount, zero if the request does not have an amount or if request is null
+#[no_mangle]
+pub unsafe extern "C" fn payment_request_get_amount(
+    request: *mut TariPaymentRequest,
+    error_out: *mut c_int,
+) -> c_ulonglong
+{
+    let mut error = 0;
+    ptr::swap(error_out, &mut error as *mut c_int);
+    if request.is_null() {
+        error = LibWalletError::from(InterfaceError::NullError("request".to_string())).code;
+        ptr::swap(error_out, &mut error as *mut c_int);
+        return 0;
+    }
+    (*request).amount.map(c_ulonglong::from).unwrap_or(0)
+}
+
+/// Gets the message of a TariPaymentRequest
+///
+/// ## Arguments
+/// `request` - The pointer to a TariPaymentRequest
+/// `error_out` - Pointer to an int which will be modified to an error code should one occur, may not be null. Functions
+/// as an out parameter.
+///
+/// ## Returns
+/// `*mut c_char` - Returns the pointer to the char array, note that it will return a pointer to an empty char array if
+/// request is null
+#[no_mangle]
+pub unsafe extern "C" fn payment_request_get_message(
+    request: *mut TariPaymentRequest,
+    error_out: *mut c_int,
+) -> *mut c_char
+{
+    let mut error = 0;
+    ptr::swap(error_out, &mut error as *mut c_int);
+    if request.is_null() {
+        error = LibWalletError::from(InterfaceError::NullError("request".to_string())).code;
+        ptr::swap(error_out, &mut error as *mut c_int);
+        return CString::new("").unwrap().into_raw();
+    }
+    CString::new((*request).message.clone()).unwrap().into_raw()
+}
+
+/// Gets the expiry time of a TariPaymentRequest as a Unix timestamp
+///
+/// ## Arguments
+/// `request` - The pointer to a TariPaymentRequest
+/// `error_out` - Pointer to an int which will be modified to an error code should one occur, may not be null. Functions
+/// as an out parameter.
+///
+/// ## Returns
+/// `c_longlong` - Returns the expiry time, zero if the request does not expire or if request is null
+#[no_mangle]
+pub unsafe extern "C" fn payment_request_get_expiry(
+    request: *mut TariPaymentRequest,
+    error_out: *mut c_int,
+) -> c_longlong
+{
+    let mut error = 0;
+    ptr::swap(error_out, &mut error as *mut c_int);
+    if request.is_null() {
+        error = LibWalletError::from(InterfaceError::NullError("request".to_string())).code;
+        ptr::swap(error_out, &mut error as *mut c_int);
+        return 0;
+    }
+    (*request).expires.map(|e| e.timestamp() as c_longlong).unwrap_or(0)
+}
+
+/// Checks whether a TariPaymentRequest has expired
+///
+/// ## Arguments
+/// `request` - The pointer to a TariPaymentRequest
+/// `error_out` - Pointer to an int which will be modified to an error code should one occur, may not be null. Functions
+/// as an out parameter.
+///
+/// ## Returns
+/// `bool` - Returns whether the request has expired, false if request is null
+#[no_mangle]
+pub unsafe extern "C" fn payment_request_is_expired(request: *mut TariPaymentRequest, error_out: *mut c_int) -> bool {
+    let mut error = 0;
+    ptr::swap(error_out, &mut error as *mut c_int);
+    if request.is_null() {
+        error = LibWalletError::from(InterfaceError::NullError("request".to_string())).code;
+        ptr::swap(error_out, &mut error as *mut c_int);
+        return false;
+    }
+    (*request).is_expired()
+}
+
+/// Frees memory for a TariPaymentRequest
+///
+/// ## Arguments
+/// `request` - The pointer to a TariPaymentRequest
+///
+/// ## Returns
+/// `()` - Does not return a value, equivalent to void in C
+#[no_mangle]
+pub unsafe extern "C" fn payment_request_destroy(request: *mut TariPaymentRequest) {
+    if !request.is_null() {
+        Box::from_raw(request);
+    }
+}
+
+/// -------------------------------------------------------------------------------------------- ///
+
+/// ----------------------------------- CommsConfig ---------------------------------------------///
+
+/// Creates a TariCommsConfig. The result from this function is required when initializing a TariWallet.
+///
+/// ## Arguments
+/// `control_service_address` - The control service address char array pointer. This is the address that the wallet
+/// listens for initial connections on
+/// `listener_address` - The listener address char array pointer. This is the address that inbound peer connections
+/// are moved to after initial connection. Default if null is 0.0.0.0:7898 which will accept connections from all IP
+/// address on port 7898
+/// `database_name` - The database name char array pointer. This is the unique name of this
+/// wallet's database `database_path` - The database path char array pointer which. This is the folder path where the
+/// database files will be created and the application has write access to
+/// `secret_key` - The TariSecretKey pointer. This is the secret key corresponding to the Public key that represents
+/// this node on the Tari comms network
+/// `error_out` - Pointer to an int which will be modified to an error code should one occur, may not be null. Functions
+/// as an out parameter.
+///
+/// ## Returns
+/// `*mut TariCommsConfig` - Returns a pointer to a TariCommsConfig, if any of the parameters are
+/// null or a problem is encountered when constructing the NetAddress a ptr::null_mut() is returned
+#[no_mangle]
+pub unsafe extern "C" fn comms_config_create(
+    control_service_address: *const c_char,
+    listener_address: *const c_char,
+    database_name: *const c_char,
+    datastore_path: *const c_char,
+    secret_key: *mut TariPrivateKey,
+    error_out: *mut c_int,
+) -> *mut TariCommsConfig
+{
+    let mut error = 0;
+    ptr::swap(error_out, &mut error as *mut c_int);
+    let control_service_address_string;
+    if !control_service_address.is_null() {
+        control_service_address_string = CStr::from_ptr(control_service_address).to_str().unwrap().to_owned();
+    } else {
+        error = LibWalletError::from(InterfaceError::NullError("control_service_address".to_string())).code;
+        ptr::swap(error_out, &mut error as *mut c_int);
+        return ptr::null_mut();
+    }
+
+    let listener_address_string;
+    if !listener_address.is_null() {
+        listener_address_string = CStr::from_ptr(listener_address).to_str().unwrap().to_owned();
+    } else {
+        listener_address_string = "/ip4/0.0.0.0/tcp/7898".to_string();
+    }
+
+    let database_name_string;
+    if !database_name.is_null() {
+        database_name_string = CStr::from_ptr(database_name).to_str().unwrap().to_owned();
+    } else {
+        error = LibWalletError::from(InterfaceError::NullError("database_name".to_string())).code;
+        ptr::swap(error_out, &mut error as *mut c_int);
+        return ptr::null_mut();
+    }
+
+    let datastore_path_string;
+    if !datastore_path.is_null() {
+        datastore_path_string = CStr::from_ptr(datastore_path).to_str().unwrap().to_owned();
+    } else {
+        error = LibWalletError::from(InterfaceError::NullError("datastore_path".to_string())).code;
+        ptr::swap(error_out, &mut error as *mut c_int);
+        return ptr::null_mut();
+    }
+
+    let listener_address = listener_address_string.parse::<Multiaddr>();
+    let control_service_address = control_service_address_string.parse::<Multiaddr>();
+
+    match listener_address {
+        Ok(listener_address) => match control_service_address {
+            Ok(control_service_address) => {
+                let ni = NodeIdentity::new(
+                    (*secret_key).clone(),
+                    control_service_address,
+                    PeerFeatures::COMMUNICATION_CLIENT,
+                );
+                match ni {
+                    Ok(ni) => {
+                        let config = TariCommsConfig {
+                            node_identity: Arc::new(ni.clone()),
+                            peer_connection_listening_address: listener_address,
+                            socks_proxy_address: None,
+                            control_service: ControlServiceConfig {
+                                listening_address: ni.control_service_address(),
+                                socks_proxy_address: None,
+                                public_peer_address: None,
+                                requested_connection_timeout: Duration::from_millis(2000),
+                            },
+                            establish_connection_timeout: Duration::from_secs(10),
+                            datastore_path: datastore_path_string,
+                            peer_database_name: database_name_string,
+                            inbound_buffer_size: 100,
+                            outbound_buffer_size: 100,
+                            dht: DhtConfig::default(),
+                        };
+
+                        Box::into_raw(Box::new(config))
+                    },
+                    Err(e) => {
+                        error = LibWalletError::from(e).code;
+                        ptr::swap(error_out, &mut error as *mut c_int);
+                        ptr::null_mut()
+                    },
+                }
+            },
+            Err(e) => {
+                error = LibWalletError::from(e).code;
+                ptr::swap(error_out, &mut error as *mut c_int);
+                return ptr::null_mut();
+            },
+        },
+        Err(e) => {
+            error = LibWalletError::from(e).code;
+            ptr::swap(error_out, &mut error as *mut c_int);
+            return ptr::null_mut();
+        },
+    }
+}
+
+/// Frees memory for a TariCommsConfig
+///
+/// ## Arguments
+/// `wc` - The TariCommsConfig pointer
+///
+/// ## Returns
+/// `()` - Does not return a value, equivalent to void in C
+#[no_mangle]
+pub unsafe extern "C" fn comms_config_destroy(wc: *mut TariCommsConfig) {
+    if !wc.is_null() {
+        Box::from_raw(wc);
+    }
+}
+
+/// ---------------------------------------------------------------------------------------------- ///
+
+/// ------------------------------------- Wallet -------------------------------------------------///
+
+/// Creates a TariWallet
+///
+/// ## Arguments
+/// `config` - The TariCommsConfig pointer
+/// `log_path` - An optional file path to the file where the logs will be written. If no log is required pass *null*
+/// pointer.
+/// `passphrase` - An optional pointer to a char array containing the passphrase that the wallet's secrets are
+/// encrypted with. It may be null if they are not encrypted. An encrypted wallet uses the node identity stored with its
+/// secrets, while an unencrypted wallet uses the node identity of `config`, which the client must keep.
+/// `callback_received_transaction` - The callback function pointer matching the function signature
+/// `callback_received_transaction_reply` - The callback function pointer matching the function signature
+/// `callback_received_finalized_transaction` - The callback function pointer matching the function signature
+/// `callback_transaction_broadcast` - The callback function pointer matching the function signature
+/// `callback_transaction_mined` - The callback function pointer matching the function signature
+/// `callback_discovery_process_complete` - The callback function pointer matching the function signature
+/// `error_out` - Pointer to an int which will be modified to an error code should one occur, may not be null. Functions
+/// as an out parameter.
+/// ## Returns
+/// `*mut TariWallet` - Returns a pointer to a TariWallet, note that it returns ptr::null_mut()
+/// if config is null, a wallet error was encountered or if the runtime could not be created
+#[no_mangle]
+pub unsafe extern "C" fn wallet_create(
+    config: *mut TariCommsConfig,
+    log_path: *const c_char,
+    passphrase: *const c_char,
+    callback_received_transaction: unsafe extern "C" fn(*mut TariPendingInboundTransaction),
+    callback_received_transaction_reply: unsafe extern "C" fn(*mut TariCompletedTransaction),
+    callback_received_finalized_transaction: unsafe extern "C" fn(*mut TariCompletedTransaction),
+    callback_transaction_broadcast: unsafe extern "C" fn(*mut TariCompletedTransaction),
+    callback_transaction_mined: unsafe extern "C" fn(*mut TariCompletedTransaction),
+    callback_discovery_process_complete: unsafe extern "C" fn(c_ulonglong, bool),
+    error_out: *mut c_int,
+) -> *mut TariWallet
+{
+    let mut error = 0;
+    ptr::swap(error_out, &mut error as *mut c_int);
+    if config.is_null() {
+        error = LibWalletError::from(InterfaceError::NullError("config".to_string())).code;
+        ptr::swap(error_out, &mut error as *mut c_int);
+        return ptr::null_mut();
+    }
+
+    let mut logging_path_string = None;
+    if !log_path.is_null() {
+        logging_path_string = Some(CStr::from_ptr(log_path).to_str().unwrap().to_owned());
+    }
+
+    let mut passphrase_string = None;
+    if !passphrase.is_null() {
+        passphrase_string = Some(CStr::from_ptr(passphrase).to_str().unwrap().to_owned());
+    }
+
+    let runtime = Runtime::new();
+    let factories = CryptoFactories::default();
+    let w;
+    match runtime {
+        Ok(runtime) => {
+            let sql_database_path = format!(
+                "{}/{}.sqlite3",
                 (*config).datastore_path.clone(),
                 (*config).peer_database_name.clone()
             )
@@ -1710,104 +2263,901 @@ pub unsafe extern "C" fn wallet_create(
             let output_manager_backend = OutputManagerSqliteDatabase::new(sql_database_path.clone()).unwrap();
             let contacts_backend = ContactsServiceSqliteDatabase::new(sql_database_path.clone()).unwrap();
 
-            w = TariWallet::new(
-                WalletConfig {
-                    comms_config: (*config).clone(),
-                    logging_path: logging_path_string,
-                    factories,
-                },
-                runtime,
-                wallet_backend,
-                transaction_backend.clone(),
-                output_manager_backend,
-                contacts_backend,
-            );
+            w = TariWallet::new(
+                WalletConfig {
+                    comms_config: (*config).clone(),
+                    logging_path: logging_path_string,
+                    factories,
+                    passphrase: passphrase_string,
+                },
+                runtime,
+                wallet_backend,
+                transaction_backend.clone(),
+                output_manager_backend,
+                contacts_backend,
+            );
+
+            match w {
+                Ok(mut w) => {
+                    // Register callbacks
+                    w.set_callbacks(
+                        transaction_backend,
+                        callback_received_transaction,
+                        callback_received_transaction_reply,
+                        callback_received_finalized_transaction,
+                        callback_transaction_broadcast,
+                        callback_transaction_mined,
+                        callback_discovery_process_complete,
+                    );
+                    Box::into_raw(Box::new(w))
+                },
+                Err(e) => {
+                    error = LibWalletError::from(e).code;
+                    ptr::swap(error_out, &mut error as *mut c_int);
+                    ptr::null_mut()
+                },
+            }
+        },
+        Err(e) => {
+            error = LibWalletError::from(InterfaceError::TokioError(e.to_string())).code;
+            ptr::swap(error_out, &mut error as *mut c_int);
+            ptr::null_mut()
+        },
+    }
+}
+
+/// This function will generate some test data in the wallet. The data generated will be
+/// as follows:
+///
+/// - Some Contacts
+/// - Add outputs to the wallet that make up its Available Balance that can be spent
+/// - Create transaction history
+///    - Pending Inbound Transactions
+///     - Pending Outbound Transactions
+///    - Completed Transactions
+///
+/// ## Arguments
+/// `wallet` - The TariWallet pointer
+/// `error_out` - Pointer to an int which will be modified to an error code should one occur, may not be null. Functions
+/// as an out parameter.
+///
+/// ## Returns
+/// `bool` - Returns if successful or not
+#[no_mangle]
+pub unsafe extern "C" fn wallet_test_generate_data(
+    wallet: *mut TariWallet,
+    datastore_path: *const c_char,
+    error_out: *mut c_int,
+) -> bool
+{
+    let mut error = 0;
+    ptr::swap(error_out, &mut error as *mut c_int);
+    if wallet.is_null() {
+        error = LibWalletError::from(InterfaceError::NullError("wallet".to_string())).code;
+        ptr::swap(error_out, &mut error as *mut c_int);
+        return false;
+    }
+    let datastore_path_string;
+    if !datastore_path.is_null() {
+        datastore_path_string = CStr::from_ptr(datastore_path).to_str().unwrap().to_owned();
+    } else {
+        error = LibWalletError::from(InterfaceError::NullError("datastore_path".to_string())).code;
+        ptr::swap(error_out, &mut error as *mut c_int);
+        return false;
+    }
+
+    match generate_wallet_test_data(
+        &mut *wallet,
+        datastore_path_string.as_str(),
+        (*wallet).transaction_backend.clone(),
+    ) {
+        Ok(_) => true,
+        Err(e) => {
+            error = LibWalletError::from(e).code;
+            ptr::swap(error_out, &mut error as *mut c_int);
+            false
+        },
+    }
+}
+
+/// This function simulates an external `TariWallet` sending a transaction to this `TariWallet`
+/// which will become a `TariPendingInboundTransaction`
+///
+/// ## Arguments
+/// `wallet` - The TariWallet pointer
+/// `error_out` - Pointer to an int which will be modified to an error code should one occur, may not be null. Functions
+/// as an out parameter.
+///
+/// ## Returns
+/// `bool` - Returns if successful or not
+#[no_mangle]
+pub unsafe extern "C" fn wallet_test_receive_transaction(wallet: *mut TariWallet, error_out: *mut c_int) -> bool {
+    let mut error = 0;
+    ptr::swap(error_out, &mut error as *mut c_int);
+    if wallet.is_null() {
+        error = LibWalletError::from(InterfaceError::NullError("wallet".to_string())).code;
+        ptr::swap(error_out, &mut error as *mut c_int);
+        return false;
+    }
+    match receive_test_transaction(&mut *wallet) {
+        Ok(_) => true,
+        Err(e) => {
+            error = LibWalletError::from(e).code;
+            ptr::swap(error_out, &mut error as *mut c_int);
+            false
+        },
+    }
+}
+
+/// This function simulates a receiver accepting and replying to a `TariPendingOutboundTransaction`.
+/// This results in that transaction being "completed" and it's status set to `Broadcast` which
+/// indicated it is in a base_layer mempool.
+///
+/// ## Arguments
+/// `wallet` - The TariWallet pointer
+/// `tx` - The TariPendingOutboundTransaction
+/// `error_out` - Pointer to an int which will be modified to an error code should one occur, may not be null. Functions
+/// as an out parameter.
+///
+/// ## Returns
+/// `bool` - Returns if successful or not
+#[no_mangle]
+pub unsafe extern "C" fn wallet_test_complete_sent_transaction(
+    wallet: *mut TariWallet,
+    tx: *mut TariPendingOutboundTransaction,
+    error_out: *mut c_int,
+) -> bool
+{
+    let mut error = 0;
+    ptr::swap(error_out, &mut error as *mut c_int);
+    if wallet.is_null() {
+        error = LibWalletError::from(InterfaceError::NullError("wallet".to_string())).code;
+        ptr::swap(error_out, &mut error as *mut c_int);
+        return false;
+    }
+    if tx.is_null() {
+        error = LibWalletError::from(InterfaceError::NullError("tx".to_string())).code;
+        ptr::swap(error_out, &mut error as *mut c_int);
+        return false;
+    }
+    match complete_sent_transaction(&mut *wallet, (*tx).tx_id.clone()) {
+        Ok(_) => true,
+        Err(e) => {
+            error = LibWalletError::from(e).code;
+            ptr::swap(error_out, &mut error as *mut c_int);
+            false
+        },
+    }
+}
+
+/// This function will simulate the process when a completed transaction is broadcast to
+/// the base layer mempool. The function will update the status of the completed transaction
+///
+/// ## Arguments
+/// `wallet` - The TariWallet pointer
+/// `tx` - The pending inbound transaction to operate on
+/// `error_out` - Pointer to an int which will be modified to an error code should one occur, may not be null. Functions
+/// as an out parameter.
+///
+/// ## Returns
+/// `bool` - Returns if successful or not
+#[no_mangle]
+pub unsafe extern "C" fn wallet_test_finalize_received_transaction(
+    wallet: *mut TariWallet,
+    tx: *mut TariPendingInboundTransaction,
+    error_out: *mut c_int,
+) -> bool
+{
+    let mut error = 0;
+    ptr::swap(error_out, &mut error as *mut c_int);
+    if wallet.is_null() {
+        error = LibWalletError::from(InterfaceError::NullError("wallet".to_string())).code;
+        ptr::swap(error_out, &mut error as *mut c_int);
+        return false;
+    }
+
+    match finalize_received_transaction(&mut *wallet, (*tx).tx_id.clone()) {
+        Ok(_) => true,
+        Err(e) => {
+            error = LibWalletError::from(e).code;
+            ptr::swap(error_out, &mut error as *mut c_int);
+            false
+        },
+    }
+}
+
+/// This function will simulate the process when a completed transaction is broadcast to
+/// the base layer mempool. The function will update the status of the completed transaction
+///
+/// ## Arguments
+/// `wallet` - The TariWallet pointer
+/// `tx` - The completed transaction to operate on
+/// `error_out` - Pointer to an int which will be modified to an error code should one occur, may not be null. Functions
+/// as an out parameter.
+///
+/// ## Returns
+/// `bool` - Returns if successful or not
+#[no_mangle]
+pub unsafe extern "C" fn wallet_test_broadcast_transaction(
+    wallet: *mut TariWallet,
+    tx: *mut TariCompletedTransaction,
+    error_out: *mut c_int,
+) -> bool
+{
+    let mut error = 0;
+    ptr::swap(error_out, &mut error as *mut c_int);
+    if wallet.is_null() {
+        error = LibWalletError::from(InterfaceError::NullError("wallet".to_string())).code;
+        ptr::swap(error_out, &mut error as *mut c_int);
+        return false;
+    }
+
+    if tx.is_null() {
+        error = LibWalletError::from(InterfaceError::NullError("tx".to_string())).code;
+        ptr::swap(error_out, &mut error as *mut c_int);
+        return false;
+    }
+
+    match broadcast_transaction(&mut *wallet, (*tx).tx_id.clone()) {
+        Ok(_) => true,
+        Err(e) => {
+            error = LibWalletError::from(e).code;
+            ptr::swap(error_out, &mut error as *mut c_int);
+            false
+        },
+    }
+}
+
+/// This function will simulate the process when a completed transaction is detected as mined on
+/// the base layer. The function will update the status of the completed transaction AND complete
+/// the transaction on the Output Manager Service which will update the status of the outputs
+///
+/// ## Arguments
+/// `wallet` - The TariWallet pointer
+/// `tx` - The completed transaction to operate on
+/// `error_out` - Pointer to an int which will be modified to an error code should one occur, may not be null. Functions
+/// as an out parameter.
+///
+/// ## Returns
+/// `bool` - Returns if successful or not
+#[no_mangle]
+pub unsafe extern "C" fn wallet_test_mine_transaction(
+    wallet: *mut TariWallet,
+    tx: *mut TariCompletedTransaction,
+    error_out: *mut c_int,
+) -> bool
+{
+    let mut error = 0;
+    ptr::swap(error_out, &mut error as *mut c_int);
+    if wallet.is_null() {
+        error = LibWalletError::from(InterfaceError::NullError("wallet".to_string())).code;
+        ptr::swap(error_out, &mut error as *mut c_int);
+        return false;
+    }
+    if tx.is_null() {
+        error = LibWalletError::from(InterfaceError::NullError("tx".to_string())).code;
+        ptr::swap(error_out, &mut error as *mut c_int);
+        return false;
+    }
+    match mine_transaction(&mut *wallet, (*tx).tx_id.clone()) {
+        Ok(_) => true,
+        Err(e) => {
+            error = LibWalletError::from(e).code;
+            ptr::swap(error_out, &mut error as *mut c_int);
+            false
+        },
+    }
+}
+
+/// Adds a base node peer to the TariWallet and sets it as the base node that the wallet submits transactions to and
+/// monitors them with, validates its outputs against and recovers funds from
+///
+/// ## Arguments
+/// `wallet` - The TariWallet pointer
+/// `public_key` - The TariPublicKey pointer
+/// `address` - The pointer to a char array
+/// `error_out` - Pointer to an int which will be modified to an error code should one occur, may not be null. Functions
+/// as an out parameter.
+///
+/// ## Returns
+/// `bool` - Returns if successful or not
+#[no_mangle]
+pub unsafe extern "C" fn wallet_add_base_node_peer(
+    wallet: *mut TariWallet,
+    public_key: *mut TariPublicKey,
+    address: *const c_char,
+    error_out: *mut c_int,
+) -> bool
+{
+    let mut error = 0;
+    ptr::swap(error_out, &mut error as *mut c_int);
+    if wallet.is_null() {
+        error = LibWalletError::from(InterfaceError::NullError("wallet".to_string())).code;
+        ptr::swap(error_out, &mut error as *mut c_int);
+        return false;
+    }
+
+    if public_key.is_null() {
+        error = LibWalletError::from(InterfaceError::NullError("public_key".to_string())).code;
+        ptr::swap(error_out, &mut error as *mut c_int);
+        return false;
+    }
+
+    let address_string;
+    if !address.is_null() {
+        address_string = CStr::from_ptr(address).to_str().unwrap().to_owned();
+    } else {
+        error = LibWalletError::from(InterfaceError::NullError("address".to_string())).code;
+        ptr::swap(error_out, &mut error as *mut c_int);
+        return false;
+    }
+
+    match (*wallet).add_base_node_peer((*public_key).clone(), address_string) {
+        Ok(_) => true,
+        Err(e) => {
+            error = LibWalletError::from(e).code;
+            ptr::swap(error_out, &mut error as *mut c_int);
+            false
+        },
+    }
+}
+
+/// Adds a TariContact to the TariWallet
+///
+/// ## Arguments
+/// `wallet` - The TariWallet pointer
+/// `contact` - The TariContact pointer
+/// `error_out` - Pointer to an int which will be modified to an error code should one occur, may not be null. Functions
+/// as an out parameter.
+///
+/// ## Returns
+/// `bool` - Returns if successful or not
+#[no_mangle]
+pub unsafe extern "C" fn wallet_add_contact(
+    wallet: *mut TariWallet,
+    contact: *mut TariContact,
+    error_out: *mut c_int,
+) -> bool
+{
+    let mut error = 0;
+    ptr::swap(error_out, &mut error as *mut c_int);
+    if wallet.is_null() {
+        error = LibWalletError::from(InterfaceError::NullError("wallet".to_string())).code;
+        ptr::swap(error_out, &mut error as *mut c_int);
+        return false;
+    }
+    if contact.is_null() {
+        error = LibWalletError::from(InterfaceError::NullError("contact".to_string())).code;
+        ptr::swap(error_out, &mut error as *mut c_int);
+        return false;
+    }
+
+    match (*wallet)
+        .runtime
+        .block_on((*wallet).contacts_service.save_contact((*contact).clone()))
+    {
+        Ok(_) => true,
+        Err(e) => {
+            error = LibWalletError::from(WalletError::ContactsServiceError(e)).code;
+            ptr::swap(error_out, &mut error as *mut c_int);
+            false
+        },
+    }
+}
+
+/// Removes a TariContact from the TariWallet
+///
+/// ## Arguments
+/// `wallet` - The TariWallet pointer
+/// `tx` - The TariPendingInboundTransaction pointer
+/// `error_out` - Pointer to an int which will be modified to an error code should one occur, may not be null. Functions
+/// as an out parameter.
+///
+/// ## Returns
+/// `bool` - Returns if successful or not
+#[no_mangle]
+pub unsafe extern "C" fn wallet_remove_contact(
+    wallet: *mut TariWallet,
+    contact: *mut TariContact,
+    error_out: *mut c_int,
+) -> bool
+{
+    let mut error = 0;
+    ptr::swap(error_out, &mut error as *mut c_int);
+    if wallet.is_null() {
+        error = LibWalletError::from(InterfaceError::NullError("wallet".to_string())).code;
+        ptr::swap(error_out, &mut error as *mut c_int);
+        return false;
+    }
+    if contact.is_null() {
+        error = LibWalletError::from(InterfaceError::NullError("contact".to_string())).code;
+        ptr::swap(error_out, &mut error as *mut c_int);
+        return false;
+    }
+
+    match (*wallet)
+        .runtime
+        .block_on((*wallet).contacts_service.remove_contact((*contact).public_key.clone()))
+    {
+        Ok(_) => true,
+        Err(e) => {
+            error = LibWalletError::from(WalletError::ContactsServiceError(e)).code;
+            ptr::swap(error_out, &mut error as *mut c_int);
+            false
+        },
+    }
+}
+
+/// Gets the available balance from a TariWallet. This is the balance the user can spend.
+///
+/// ## Arguments
+/// `wallet` - The TariWallet pointer
+/// `error_out` - Pointer to an int which will be modified to an error code should one occur, may not be null. Functions
+/// as an out parameter.
+///
+/// ## Returns
+/// `c_ulonglong` - The available balance, 0 if wallet is null
+#[no_mangle]
+pub unsafe extern "C" fn wallet_get_available_balance(wallet: *mut TariWallet, error_out: *mut c_int) -> c_ulonglong {
+    let mut error = 0;
+    ptr::swap(error_out, &mut error as *mut c_int);
+    if wallet.is_null() {
+        error = LibWalletError::from(InterfaceError::NullError("wallet".to_string())).code;
+        ptr::swap(error_out, &mut error as *mut c_int);
+        return 0;
+    }
+
+    match (*wallet)
+        .runtime
+        .block_on((*wallet).output_manager_service.get_balance())
+    {
+        Ok(b) => c_ulonglong::from(b.available_balance),
+        Err(e) => {
+            error = LibWalletError::from(WalletError::OutputManagerError(e)).code;
+            ptr::swap(error_out, &mut error as *mut c_int);
+            0
+        },
+    }
+}
+
+/// Gets the incoming balance from a `TariWallet`. This is the uncleared balance of Tari that is
+/// expected to come into the `TariWallet` but is not yet spendable.
+///
+/// ## Arguments
+/// `wallet` - The TariWallet pointer
+/// `error_out` - Pointer to an int which will be modified to an error code should one occur, may not be null. Functions
+/// as an out parameter.
+///
+/// ## Returns
+/// `c_ulonglong` - The incoming balance, 0 if wallet is null
+#[no_mangle]
+pub unsafe extern "C" fn wallet_get_pending_incoming_balance(
+    wallet: *mut TariWallet,
+    error_out: *mut c_int,
+) -> c_ulonglong
+{
+    let mut error = 0;
+    ptr::swap(error_out, &mut error as *mut c_int);
+    if wallet.is_null() {
+        error = LibWalletError::from(InterfaceError::NullError("wallet".to_string())).code;
+        ptr::swap(error_out, &mut error as *mut c_int);
+        return 0;
+    }
+
+    match (*wallet)
+        .runtime
+        .block_on((*wallet).output_manager_service.get_balance())
+    {
+        Ok(b) => c_ulonglong::from(b.pending_incoming_balance),
+        Err(e) => {
+            error = LibWalletError::from(WalletError::OutputManagerError(e)).code;
+            ptr::swap(error_out, &mut error as *mut c_int);
+            0
+        },
+    }
+}
+
+/// Gets the outgoing balance from a `TariWallet`. This is the uncleared balance of Tari that has
+/// been spent
+///
+/// ## Arguments
+/// `wallet` - The TariWallet pointer
+/// `error_out` - Pointer to an int which will be modified to an error code should one occur, may not be null. Functions
+/// as an out parameter.
+///
+/// ## Returns
+/// `c_ulonglong` - The outgoing balance, 0 if wallet is null
+#[no_mangle]
+pub unsafe extern "C" fn wallet_get_pending_outgoing_balance(
+    wallet: *mut TariWallet,
+    error_out: *mut c_int,
+) -> c_ulonglong
+{
+    let mut error = 0;
+    ptr::swap(error_out, &mut error as *mut c_int);
+    if wallet.is_null() {
+        error = LibWalletError::from(InterfaceError::NullError("wallet".to_string())).code;
+        ptr::swap(error_out, &mut error as *mut c_int);
+        return 0;
+    }
+
+    match (*wallet)
+        .runtime
+        .block_on((*wallet).output_manager_service.get_balance())
+    {
+        Ok(b) => c_ulonglong::from(b.pending_outgoing_balance),
+        Err(e) => {
+            error = LibWalletError::from(WalletError::OutputManagerError(e)).code;
+            ptr::swap(error_out, &mut error as *mut c_int);
+            0
+        },
+    }
+}
+
+/// Gets the time-locked balance from a `TariWallet`. This is the balance of outputs that have not matured yet, such as
+/// recent coinbase outputs, and cannot be spent until they do.
+///
+/// ## Arguments
+/// `wallet` - The TariWallet pointer
+/// `error_out` - Pointer to an int which will be modified to an error code should one occur, may not be null. Functions
+/// as an out parameter.
+///
+/// ## Returns
+/// `c_ulonglong` - The time-locked balance, 0 if wallet is null
+#[no_mangle]
+pub unsafe extern "C" fn wallet_get_time_locked_balance(wallet: *mut TariWallet, error_out: *mut c_int) -> c_ulonglong {
+    let mut error = 0;
+    ptr::swap(error_out, &mut error as *mut c_int);
+    if wallet.is_null() {
+        error = LibWalletError::from(InterfaceError::NullError("wallet".to_string())).code;
+        ptr::swap(error_out, &mut error as *mut c_int);
+        return 0;
+    }
+
+    match (*wallet)
+        .runtime
+        .block_on((*wallet).output_manager_service.get_balance())
+    {
+        Ok(b) => c_ulonglong::from(b.time_locked_balance),
+        Err(e) => {
+            error = LibWalletError::from(WalletError::OutputManagerError(e)).code;
+            ptr::swap(error_out, &mut error as *mut c_int);
+            0
+        },
+    }
+}
 
-            match w {
-                Ok(mut w) => {
-                    // Register callbacks
-                    w.set_callbacks(
-                        transaction_backend,
-                        callback_received_transaction,
-                        callback_received_transaction_reply,
-                        callback_received_finalized_transaction,
-                        callback_transaction_broadcast,
-                        callback_transaction_mined,
-                        callback_discovery_process_complete,
-                    );
-                    Box::into_raw(Box::new(w))
-                },
-                Err(e) => {
-                    error = LibWalletError::from(e).code;
-                    ptr::swap(error_out, &mut error as *mut c_int);
-                    ptr::null_mut()
-                },
-            }
+/// Sends a TariPendingOutboundTransaction
+///
+/// ## Arguments
+/// `wallet` - The TariWallet pointer
+/// `dest_public_key` - The TariPublicKey pointer of the peer
+/// `amount` - The amount
+/// `fee_per_gram` - The transaction fee
+/// `message` - The pointer to a char array
+/// `error_out` - Pointer to an int which will be modified to an error code should one occur, may not be null. Functions
+/// as an out parameter.
+///
+/// ## Returns
+/// `bool` - Returns if successful or not
+#[no_mangle]
+pub unsafe extern "C" fn wallet_send_transaction(
+    wallet: *mut TariWallet,
+    dest_public_key: *mut TariPublicKey,
+    amount: c_ulonglong,
+    fee_per_gram: c_ulonglong,
+    message: *const c_char,
+    error_out: *mut c_int,
+) -> bool
+{
+    let mut error = 0;
+    ptr::swap(error_out, &mut error as *mut c_int);
+    if wallet.is_null() {
+        error = LibWalletError::from(InterfaceError::NullError("wallet".to_string())).code;
+        ptr::swap(error_out, &mut error as *mut c_int);
+        return false;
+    }
+
+    if dest_public_key.is_null() {
+        error = LibWalletError::from(InterfaceError::NullError("dest_public_key".to_string())).code;
+        ptr::swap(error_out, &mut error as *mut c_int);
+        return false;
+    }
+
+    let mut message_string = CString::new("").unwrap().to_str().unwrap().to_owned();
+    if !message.is_null() {
+        message_string = CStr::from_ptr(message).to_str().unwrap().to_owned();
+    } else {
+        error = LibWalletError::from(InterfaceError::NullError("message".to_string())).code;
+        ptr::swap(error_out, &mut error as *mut c_int);
+    }
+
+    match (*wallet)
+        .runtime
+        .block_on((*wallet).transaction_service.send_transaction(
+            (*dest_public_key).clone(),
+            MicroTari::from(amount),
+            MicroTari::from(fee_per_gram),
+            message_string,
+        )) {
+        Ok(_) => true,
+        Err(e) => {
+            error = LibWalletError::from(WalletError::TransactionServiceError(e)).code;
+            ptr::swap(error_out, &mut error as *mut c_int);
+            false
+        },
+    }
+}
+
+/// Sends a TariPendingOutboundTransaction that cannot be mined before the given block height
+///
+/// ## Arguments
+/// `wallet` - The TariWallet pointer
+/// `dest_public_key` - The TariPublicKey pointer of the peer
+/// `amount` - The amount
+/// `fee_per_gram` - The transaction fee
+/// `lock_height` - The block height before which the transaction cannot be mined
+/// `message` - The pointer to a char array
+/// `error_out` - Pointer to an int which will be modified to an error code should one occur, may not be null. Functions
+/// as an out parameter.
+///
+/// ## Returns
+/// `bool` - Returns if successful or not
+#[no_mangle]
+pub unsafe extern "C" fn wallet_send_transaction_with_lock_height(
+    wallet: *mut TariWallet,
+    dest_public_key: *mut TariPublicKey,
+    amount: c_ulonglong,
+    fee_per_gram: c_ulonglong,
+    lock_height: c_ulonglong,
+    message: *const c_char,
+    error_out: *mut c_int,
+) -> bool
+{
+    let mut error = 0;
+    ptr::swap(error_out, &mut error as *mut c_int);
+    if wallet.is_null() {
+        error = LibWalletError::from(InterfaceError::NullError("wallet".to_string())).code;
+        ptr::swap(error_out, &mut error as *mut c_int);
+        return false;
+    }
+
+    if dest_public_key.is_null() {
+        error = LibWalletError::from(InterfaceError::NullError("dest_public_key".to_string())).code;
+        ptr::swap(error_out, &mut error as *mut c_int);
+        return false;
+    }
+
+    let mut message_string = CString::new("").unwrap().to_str().unwrap().to_owned();
+    if !message.is_null() {
+        message_string = CStr::from_ptr(message).to_str().unwrap().to_owned();
+    } else {
+        error = LibWalletError::from(InterfaceError::NullError("message".to_string())).code;
+        ptr::swap(error_out, &mut error as *mut c_int);
+    }
+
+    match (*wallet)
+        .runtime
+        .block_on((*wallet).transaction_service.send_transaction_with_lock_height(
+            (*dest_public_key).clone(),
+            MicroTari::from(amount),
+            MicroTari::from(fee_per_gram),
+            Some(lock_height),
+            message_string,
+        )) {
+        Ok(_) => true,
+        Err(e) => {
+            error = LibWalletError::from(WalletError::TransactionServiceError(e)).code;
+            ptr::swap(error_out, &mut error as *mut c_int);
+            false
+        },
+    }
+}
+
+/// Pays a TariPaymentRequest, after checking that it is for the wallet's network and has not expired. The message of
+/// the request is the message of the transaction.
+///
+/// ## Arguments
+/// `wallet` - The TariWallet pointer
+/// `request` - The TariPaymentRequest pointer
+/// `amount` - The amount to pay, which is required if the request does not have an amount and must match it if it does.
+/// Zero pays the amount of the request.
+/// `fee_per_gram` - The transaction fee
+/// `error_out` - Pointer to an int which will be modified to an error code should one occur, may not be null. Functions
+/// as an out parameter.
+///
+/// ## Returns
+/// `bool` - Returns if successful or not
+#[no_mangle]
+pub unsafe extern "C" fn wallet_pay_payment_request(
+    wallet: *mut TariWallet,
+    request: *mut TariPaymentRequest,
+    amount: c_ulonglong,
+    fee_per_gram: c_ulonglong,
+    error_out: *mut c_int,
+) -> bool
+{
+    let mut error = 0;
+    ptr::swap(error_out, &mut error as *mut c_int);
+    if wallet.is_null() {
+        error = LibWalletError::from(InterfaceError::NullError("wallet".to_string())).code;
+        ptr::swap(error_out, &mut error as *mut c_int);
+        return false;
+    }
+
+    if request.is_null() {
+        error = LibWalletError::from(InterfaceError::NullError("request".to_string())).code;
+        ptr::swap(error_out, &mut error as *mut c_int);
+        return false;
+    }
+
+    let amount = if amount == 0 {
+        None
+    } else {
+        Some(MicroTari::from(amount))
+    };
+    match (*wallet)
+        .runtime
+        .block_on((*wallet).transaction_service.pay_payment_request(
+            (*request).clone(),
+            amount,
+            MicroTari::from(fee_per_gram),
+        )) {
+        Ok(_) => true,
+        Err(e) => {
+            error = LibWalletError::from(WalletError::TransactionServiceError(e)).code;
+            ptr::swap(error_out, &mut error as *mut c_int);
+            false
+        },
+    }
+}
+
+/// Estimates the fee of sending a transaction of the given amount with `wallet_send_transaction`, using the outputs
+/// that would be selected to fund it if it were sent now
+///
+/// ## Arguments
+/// `wallet` - The TariWallet pointer
+/// `amount` - The amount
+/// `fee_per_gram` - The transaction fee per gram
+/// `error_out` - Pointer to an int which will be modified to an error code should one occur, may not be null. Functions
+/// as an out parameter.
+///
+/// ## Returns
+/// `c_ulonglong` - Returns the estimated fee, 0 if wallet is null or an error is encountered
+#[no_mangle]
+pub unsafe extern "C" fn wallet_get_fee_estimate(
+    wallet: *mut TariWallet,
+    amount: c_ulonglong,
+    fee_per_gram: c_ulonglong,
+    error_out: *mut c_int,
+) -> c_ulonglong
+{
+    let mut error = 0;
+    ptr::swap(error_out, &mut error as *mut c_int);
+    if wallet.is_null() {
+        error = LibWalletError::from(InterfaceError::NullError("wallet".to_string())).code;
+        ptr::swap(error_out, &mut error as *mut c_int);
+        return 0;
+    }
+
+    match (*wallet).runtime.block_on(
+        (*wallet)
+            .output_manager_service
+            .transaction_fee(MicroTari::from(amount), MicroTari::from(fee_per_gram)),
+    ) {
+        Ok(fee) => c_ulonglong::from(fee),
+        Err(e) => {
+            error = LibWalletError::from(WalletError::OutputManagerError(e)).code;
+            ptr::swap(error_out, &mut error as *mut c_int);
+            0
         },
+    }
+}
+
+/// Estimates the fee of a coin split with `wallet_coin_split`
+///
+/// ## Arguments
+/// `wallet` - The TariWallet pointer
+/// `amount_per_split` - The value of each of the new outputs
+/// `split_count` - The number of new outputs
+/// `fee_per_gram` - The transaction fee per gram
+/// `error_out` - Pointer to an int which will be modified to an error code should one occur, may not be null. Functions
+/// as an out parameter.
+///
+/// ## Returns
+/// `c_ulonglong` - Returns the estimated fee, 0 if wallet is null or an error is encountered
+#[no_mangle]
+pub unsafe extern "C" fn wallet_get_coin_split_fee(
+    wallet: *mut TariWallet,
+    amount_per_split: c_ulonglong,
+    split_count: c_uint,
+    fee_per_gram: c_ulonglong,
+    error_out: *mut c_int,
+) -> c_ulonglong
+{
+    let mut error = 0;
+    ptr::swap(error_out, &mut error as *mut c_int);
+    if wallet.is_null() {
+        error = LibWalletError::from(InterfaceError::NullError("wallet".to_string())).code;
+        ptr::swap(error_out, &mut error as *mut c_int);
+        return 0;
+    }
+
+    match (*wallet)
+        .runtime
+        .block_on((*wallet).output_manager_service.coin_split_fee(
+            MicroTari::from(amount_per_split),
+            split_count as usize,
+            MicroTari::from(fee_per_gram),
+        )) {
+        Ok(fee) => c_ulonglong::from(fee),
         Err(e) => {
-            error = LibWalletError::from(InterfaceError::TokioError(e.to_string())).code;
+            error = LibWalletError::from(WalletError::OutputManagerError(e)).code;
             ptr::swap(error_out, &mut error as *mut c_int);
-            ptr::null_mut()
+            0
         },
     }
 }
 
-/// This function will generate some test data in the wallet. The data generated will be
-/// as follows:
-///
-/// - Some Contacts
-/// - Add outputs to the wallet that make up its Available Balance that can be spent
-/// - Create transaction history
-///    - Pending Inbound Transactions
-///     - Pending Outbound Transactions
-///    - Completed Transactions
+/// Splits the largest outputs of a TariWallet into `split_count` outputs of `amount_per_split` each, so that they can
+/// be spent by transactions in parallel. The transaction pays the wallet itself and is broadcast to the base node.
 ///
 /// ## Arguments
 /// `wallet` - The TariWallet pointer
+/// `amount_per_split` - The value of each of the new outputs
+/// `split_count` - The number of new outputs
+/// `fee_per_gram` - The transaction fee per gram
+/// `message` - The pointer to a char array
 /// `error_out` - Pointer to an int which will be modified to an error code should one occur, may not be null. Functions
 /// as an out parameter.
 ///
 /// ## Returns
-/// `bool` - Returns if successful or not
+/// `c_ulonglong` - Returns the TransactionId of the coin split, 0 if wallet is null or an error is encountered
 #[no_mangle]
-pub unsafe extern "C" fn wallet_test_generate_data(
+pub unsafe extern "C" fn wallet_coin_split(
     wallet: *mut TariWallet,
-    datastore_path: *const c_char,
+    amount_per_split: c_ulonglong,
+    split_count: c_uint,
+    fee_per_gram: c_ulonglong,
+    message: *const c_char,
     error_out: *mut c_int,
-) -> bool
+) -> c_ulonglong
 {
     let mut error = 0;
     ptr::swap(error_out, &mut error as *mut c_int);
     if wallet.is_null() {
         error = LibWalletError::from(InterfaceError::NullError("wallet".to_string())).code;
         ptr::swap(error_out, &mut error as *mut c_int);
-        return false;
+        return 0;
     }
-    let datastore_path_string;
-    if !datastore_path.is_null() {
-        datastore_path_string = CStr::from_ptr(datastore_path).to_str().unwrap().to_owned();
+
+    let message_string;
+    if !message.is_null() {
+        message_string = CStr::from_ptr(message).to_str().unwrap().to_owned();
     } else {
-        error = LibWalletError::from(InterfaceError::NullError("datastore_path".to_string())).code;
+        error = LibWalletError::from(InterfaceError::NullError("message".to_string())).code;
         ptr::swap(error_out, &mut error as *mut c_int);
-        return false;
+        return 0;
     }
 
-    match generate_wallet_test_data(
-        &mut *wallet,
-        datastore_path_string.as_str(),
-        (*wallet).transaction_backend.clone(),
-    ) {
-        Ok(_) => true,
+    match (*wallet)
+        .runtime
+        .block_on((*wallet).transaction_service.create_coin_split(
+            MicroTari::from(amount_per_split),
+            split_count as usize,
+            MicroTari::from(fee_per_gram),
+            message_string,
+        )) {
+        Ok(tx_id) => tx_id,
         Err(e) => {
-            error = LibWalletError::from(e).code;
+            error = LibWalletError::from(WalletError::TransactionServiceError(e)).code;
             ptr::swap(error_out, &mut error as *mut c_int);
-            false
+            0
         },
     }
 }
 
-/// This function simulates an external `TariWallet` sending a transaction to this `TariWallet`
-/// which will become a `TariPendingInboundTransaction`
+/// Get the TariContacts from a TariWallet
 ///
 /// ## Arguments
 /// `wallet` - The TariWallet pointer
@@ -1815,295 +3165,389 @@ pub unsafe extern "C" fn wallet_test_generate_data(
 /// as an out parameter.
 ///
 /// ## Returns
-/// `bool` - Returns if successful or not
+/// `*mut TariContacts` - returns the contacts, note that it returns ptr::null_mut() if
+/// wallet is null
 #[no_mangle]
-pub unsafe extern "C" fn wallet_test_receive_transaction(wallet: *mut TariWallet, error_out: *mut c_int) -> bool {
+pub unsafe extern "C" fn wallet_get_contacts(wallet: *mut TariWallet, error_out: *mut c_int) -> *mut TariContacts {
     let mut error = 0;
     ptr::swap(error_out, &mut error as *mut c_int);
+    let mut contacts = Vec::new();
     if wallet.is_null() {
         error = LibWalletError::from(InterfaceError::NullError("wallet".to_string())).code;
         ptr::swap(error_out, &mut error as *mut c_int);
-        return false;
+        return ptr::null_mut();
     }
-    match receive_test_transaction(&mut *wallet) {
-        Ok(_) => true,
+
+    let retrieved_contacts = (*wallet).runtime.block_on((*wallet).contacts_service.get_contacts());
+    match retrieved_contacts {
+        Ok(retrieved_contacts) => {
+            contacts.append(&mut retrieved_contacts.clone());
+            Box::into_raw(Box::new(TariContacts(contacts)))
+        },
         Err(e) => {
-            error = LibWalletError::from(e).code;
+            error = LibWalletError::from(WalletError::ContactsServiceError(e)).code;
             ptr::swap(error_out, &mut error as *mut c_int);
-            false
+            ptr::null_mut()
         },
     }
 }
 
-/// This function simulates a receiver accepting and replying to a `TariPendingOutboundTransaction`.
-/// This results in that transaction being "completed" and it's status set to `Broadcast` which
-/// indicated it is in a base_layer mempool.
+/// Get the TariCompletedTransactions from a TariWallet
 ///
 /// ## Arguments
 /// `wallet` - The TariWallet pointer
-/// `tx` - The TariPendingOutboundTransaction
 /// `error_out` - Pointer to an int which will be modified to an error code should one occur, may not be null. Functions
 /// as an out parameter.
 ///
 /// ## Returns
-/// `bool` - Returns if successful or not
+/// `*mut TariCompletedTransactions` - returns the transactions, note that it returns ptr::null_mut() if
+/// wallet is null or an error is encountered
 #[no_mangle]
-pub unsafe extern "C" fn wallet_test_complete_sent_transaction(
+pub unsafe extern "C" fn wallet_get_completed_transactions(
     wallet: *mut TariWallet,
-    tx: *mut TariPendingOutboundTransaction,
     error_out: *mut c_int,
-) -> bool
+) -> *mut TariCompletedTransactions
 {
     let mut error = 0;
     ptr::swap(error_out, &mut error as *mut c_int);
+    let mut completed = Vec::new();
     if wallet.is_null() {
         error = LibWalletError::from(InterfaceError::NullError("wallet".to_string())).code;
         ptr::swap(error_out, &mut error as *mut c_int);
-        return false;
-    }
-    if tx.is_null() {
-        error = LibWalletError::from(InterfaceError::NullError("tx".to_string())).code;
-        ptr::swap(error_out, &mut error as *mut c_int);
-        return false;
+        return ptr::null_mut();
     }
-    match complete_sent_transaction(&mut *wallet, (*tx).tx_id.clone()) {
-        Ok(_) => true,
+
+    let completed_transactions = (*wallet)
+        .runtime
+        .block_on((*wallet).transaction_service.get_completed_transactions());
+    match completed_transactions {
+        Ok(completed_transactions) => {
+            for (_id, tx) in &completed_transactions {
+                completed.push(tx.clone());
+            }
+            Box::into_raw(Box::new(TariCompletedTransactions(completed)))
+        },
         Err(e) => {
-            error = LibWalletError::from(e).code;
+            error = LibWalletError::from(WalletError::TransactionServiceError(e)).code;
             ptr::swap(error_out, &mut error as *mut c_int);
-            false
+            ptr::null_mut()
         },
     }
 }
 
-/// This function will simulate the process when a completed transaction is broadcast to
-/// the base layer mempool. The function will update the status of the completed transaction
+/// Exports a page of the completed transactions of a TariWallet, in the order they were completed in, for accounting.
+/// Each transaction is described by its id, direction, counterparty, amount and fee in µT, status, block height,
+/// timestamp and message.
 ///
 /// ## Arguments
 /// `wallet` - The TariWallet pointer
-/// `tx` - The pending inbound transaction to operate on
+/// `from` - Only include the transactions completed at or after this Unix time, zero for no lower bound
+/// `to` - Only include the transactions completed before this Unix time, zero for no upper bound
+/// `offset` - The number of transactions to skip
+/// `limit` - The maximum number of transactions to export, zero to export all of them
+/// `as_json` - Export the transactions as a JSON array if true, as CSV with a header line if false
 /// `error_out` - Pointer to an int which will be modified to an error code should one occur, may not be null. Functions
 /// as an out parameter.
 ///
 /// ## Returns
-/// `bool` - Returns if successful or not
+/// `*mut c_char` - Returns a pointer to a char array of the exported transactions, note that it returns ptr::null_mut()
+/// if wallet is null or an error is encountered
 #[no_mangle]
-pub unsafe extern "C" fn wallet_test_finalize_received_transaction(
+pub unsafe extern "C" fn wallet_export_transaction_history(
     wallet: *mut TariWallet,
-    tx: *mut TariPendingInboundTransaction,
+    from: c_ulonglong,
+    to: c_ulonglong,
+    offset: c_uint,
+    limit: c_uint,
+    as_json: bool,
     error_out: *mut c_int,
-) -> bool
+) -> *mut c_char
 {
     let mut error = 0;
     ptr::swap(error_out, &mut error as *mut c_int);
     if wallet.is_null() {
         error = LibWalletError::from(InterfaceError::NullError("wallet".to_string())).code;
         ptr::swap(error_out, &mut error as *mut c_int);
-        return false;
+        return ptr::null_mut();
     }
 
-    match finalize_received_transaction(&mut *wallet, (*tx).tx_id.clone()) {
-        Ok(_) => true,
+    let query = TransactionHistoryQuery {
+        from: if from == 0 {
+            None
+        } else {
+            Some(NaiveDateTime::from_timestamp(from as i64, 0))
+        },
+        to: if to == 0 {
+            None
+        } else {
+            Some(NaiveDateTime::from_timestamp(to as i64, 0))
+        },
+        offset: offset as usize,
+        limit: if limit == 0 { None } else { Some(limit as usize) },
+    };
+    let format = if as_json {
+        TransactionHistoryFormat::Json
+    } else {
+        TransactionHistoryFormat::Csv
+    };
+    match (*wallet)
+        .runtime
+        .block_on((*wallet).transaction_service.export_transaction_history(query, format))
+    {
+        Ok(export) => CString::new(export).unwrap().into_raw(),
         Err(e) => {
-            error = LibWalletError::from(e).code;
+            error = LibWalletError::from(WalletError::TransactionServiceError(e)).code;
             ptr::swap(error_out, &mut error as *mut c_int);
-            false
+            ptr::null_mut()
         },
     }
 }
 
-/// This function will simulate the process when a completed transaction is broadcast to
-/// the base layer mempool. The function will update the status of the completed transaction
+/// Get the TariPendingInboundTransactions from a TariWallet
 ///
 /// ## Arguments
 /// `wallet` - The TariWallet pointer
-/// `tx` - The completed transaction to operate on
 /// `error_out` - Pointer to an int which will be modified to an error code should one occur, may not be null. Functions
 /// as an out parameter.
 ///
 /// ## Returns
-/// `bool` - Returns if successful or not
+/// `*mut TariPendingInboundTransactions` - returns the transactions, note that it returns ptr::null_mut() if
+/// wallet is null or and error is encountered
 #[no_mangle]
-pub unsafe extern "C" fn wallet_test_broadcast_transaction(
+pub unsafe extern "C" fn wallet_get_pending_inbound_transactions(
     wallet: *mut TariWallet,
-    tx: *mut TariCompletedTransaction,
     error_out: *mut c_int,
-) -> bool
+) -> *mut TariPendingInboundTransactions
 {
     let mut error = 0;
     ptr::swap(error_out, &mut error as *mut c_int);
+    let mut pending = Vec::new();
     if wallet.is_null() {
         error = LibWalletError::from(InterfaceError::NullError("wallet".to_string())).code;
         ptr::swap(error_out, &mut error as *mut c_int);
-        return false;
+        return ptr::null_mut();
     }
 
-    if tx.is_null() {
-        error = LibWalletError::from(InterfaceError::NullError("tx".to_string())).code;
+    let pending_transactions = (*wallet)
+        .runtime
+        .block_on((*wallet).transaction_service.get_pending_inbound_transactions());
+    match pending_transactions {
+        Ok(pending_transactions) => {
+            for (_id, tx) in &pending_transactions {
+                pending.push(tx.clone());
+            }
+            Box::into_raw(Box::new(TariPendingInboundTransactions(pending)))
+        },
+        Err(e) => {
+            error = LibWalletError::from(WalletError::TransactionServiceError(e)).code;
+            ptr::swap(error_out, &mut error as *mut c_int);
+            ptr::null_mut()
+        },
+    }
+}
+
+/// Get the TariPendingOutboundTransactions from a TariWallet
+///
+/// ## Arguments
+/// `wallet` - The TariWallet pointer
+/// `error_out` - Pointer to an int which will be modified to an error code should one occur, may not be null. Functions
+/// as an out parameter.
+///
+/// ## Returns
+/// `*mut TariPendingOutboundTransactions` - returns the transactions, note that it returns ptr::null_mut() if
+/// wallet is null or and error is encountered
+#[no_mangle]
+pub unsafe extern "C" fn wallet_get_pending_outbound_transactions(
+    wallet: *mut TariWallet,
+    error_out: *mut c_int,
+) -> *mut TariPendingOutboundTransactions
+{
+    let mut error = 0;
+    ptr::swap(error_out, &mut error as *mut c_int);
+    let mut pending = Vec::new();
+    if wallet.is_null() {
+        error = LibWalletError::from(InterfaceError::NullError("wallet".to_string())).code;
         ptr::swap(error_out, &mut error as *mut c_int);
-        return false;
+        return ptr::null_mut();
     }
 
-    match broadcast_transaction(&mut *wallet, (*tx).tx_id.clone()) {
-        Ok(_) => true,
+    let pending_transactions = (*wallet)
+        .runtime
+        .block_on((*wallet).transaction_service.get_pending_outbound_transactions());
+    match pending_transactions {
+        Ok(pending_transactions) => {
+            for (_id, tx) in &pending_transactions {
+                pending.push(tx.clone());
+            }
+            Box::into_raw(Box::new(TariPendingOutboundTransactions(pending)))
+        },
         Err(e) => {
-            error = LibWalletError::from(e).code;
+            error = LibWalletError::from(WalletError::TransactionServiceError(e)).code;
             ptr::swap(error_out, &mut error as *mut c_int);
-            false
+            ptr::null_mut()
         },
     }
 }
 
-/// This function will simulate the process when a completed transaction is detected as mined on
-/// the base layer. The function will update the status of the completed transaction AND complete
-/// the transaction on the Output Manager Service which will update the status of the outputs
+/// Get the TariCompletedTransaction from a TariWallet by its' TransactionId
 ///
 /// ## Arguments
 /// `wallet` - The TariWallet pointer
-/// `tx` - The completed transaction to operate on
+/// `transaction_id` - The TransactionId
 /// `error_out` - Pointer to an int which will be modified to an error code should one occur, may not be null. Functions
 /// as an out parameter.
 ///
 /// ## Returns
-/// `bool` - Returns if successful or not
+/// `*mut TariCompletedTransaction` - returns the transaction, note that it returns ptr::null_mut() if
+/// wallet is null, an error is encountered or if the transaction is not found
 #[no_mangle]
-pub unsafe extern "C" fn wallet_test_mine_transaction(
+pub unsafe extern "C" fn wallet_get_completed_transaction_by_id(
     wallet: *mut TariWallet,
-    tx: *mut TariCompletedTransaction,
+    transaction_id: c_ulonglong,
     error_out: *mut c_int,
-) -> bool
+) -> *mut TariCompletedTransaction
 {
     let mut error = 0;
     ptr::swap(error_out, &mut error as *mut c_int);
     if wallet.is_null() {
         error = LibWalletError::from(InterfaceError::NullError("wallet".to_string())).code;
         ptr::swap(error_out, &mut error as *mut c_int);
-        return false;
-    }
-    if tx.is_null() {
-        error = LibWalletError::from(InterfaceError::NullError("tx".to_string())).code;
-        ptr::swap(error_out, &mut error as *mut c_int);
-        return false;
+        return ptr::null_mut();
     }
-    match mine_transaction(&mut *wallet, (*tx).tx_id.clone()) {
-        Ok(_) => true,
+
+    let pending_transactions = (*wallet)
+        .runtime
+        .block_on((*wallet).transaction_service.get_completed_transactions());
+
+    match pending_transactions {
+        Ok(pending_transactions) => {
+            for (id, tx) in &pending_transactions {
+                if id == &transaction_id {
+                    let pending = tx.clone();
+                    return Box::into_raw(Box::new(pending));
+                }
+            }
+            return ptr::null_mut();
+        },
         Err(e) => {
-            error = LibWalletError::from(e).code;
+            error = LibWalletError::from(WalletError::TransactionServiceError(e)).code;
             ptr::swap(error_out, &mut error as *mut c_int);
-            false
+            ptr::null_mut()
         },
     }
 }
 
-/// Adds a base node peer to the TariWallet
+/// Get the TariPendingInboundTransaction from a TariWallet by its' TransactionId
 ///
 /// ## Arguments
 /// `wallet` - The TariWallet pointer
-/// `public_key` - The TariPublicKey pointer
-/// `address` - The pointer to a char array
+/// `transaction_id` - The TransactionId
 /// `error_out` - Pointer to an int which will be modified to an error code should one occur, may not be null. Functions
 /// as an out parameter.
 ///
 /// ## Returns
-/// `bool` - Returns if successful or not
+/// `*mut TariPendingInboundTransaction` - returns the transaction, note that it returns ptr::null_mut() if
+/// wallet is null, an error is encountered or if the transaction is not found
 #[no_mangle]
-pub unsafe extern "C" fn wallet_add_base_node_peer(
+pub unsafe extern "C" fn wallet_get_pending_inbound_transaction_by_id(
     wallet: *mut TariWallet,
-    public_key: *mut TariPublicKey,
-    address: *const c_char,
+    transaction_id: c_ulonglong,
     error_out: *mut c_int,
-) -> bool
+) -> *mut TariPendingInboundTransaction
 {
     let mut error = 0;
     ptr::swap(error_out, &mut error as *mut c_int);
     if wallet.is_null() {
         error = LibWalletError::from(InterfaceError::NullError("wallet".to_string())).code;
         ptr::swap(error_out, &mut error as *mut c_int);
-        return false;
-    }
-
-    if public_key.is_null() {
-        error = LibWalletError::from(InterfaceError::NullError("public_key".to_string())).code;
-        ptr::swap(error_out, &mut error as *mut c_int);
-        return false;
+        return ptr::null_mut();
     }
 
-    let address_string;
-    if !address.is_null() {
-        address_string = CStr::from_ptr(address).to_str().unwrap().to_owned();
-    } else {
-        error = LibWalletError::from(InterfaceError::NullError("address".to_string())).code;
-        ptr::swap(error_out, &mut error as *mut c_int);
-        return false;
-    }
+    let pending_transactions = (*wallet)
+        .runtime
+        .block_on((*wallet).transaction_service.get_pending_inbound_transactions());
 
-    match (*wallet).add_base_node_peer((*public_key).clone(), address_string) {
-        Ok(_) => true,
+    match pending_transactions {
+        Ok(pending_transactions) => {
+            for (id, tx) in &pending_transactions {
+                if id == &transaction_id {
+                    let pending = tx.clone();
+                    return Box::into_raw(Box::new(pending));
+                }
+            }
+            return ptr::null_mut();
+        },
         Err(e) => {
-            error = LibWalletError::from(e).code;
+            error = LibWalletError::from(WalletError::TransactionServiceError(e)).code;
             ptr::swap(error_out, &mut error as *mut c_int);
-            false
+            ptr::null_mut()
         },
     }
 }
 
-/// Adds a TariContact to the TariWallet
+/// Get the TariPendingOutboundTransaction from a TariWallet by its' TransactionId
 ///
 /// ## Arguments
 /// `wallet` - The TariWallet pointer
-/// `contact` - The TariContact pointer
+/// `transaction_id` - The TransactionId
 /// `error_out` - Pointer to an int which will be modified to an error code should one occur, may not be null. Functions
 /// as an out parameter.
 ///
 /// ## Returns
-/// `bool` - Returns if successful or not
+/// `*mut TariPendingOutboundTransaction` - returns the transaction, note that it returns ptr::null_mut() if
+/// wallet is null, an error is encountered or if the transaction is not found
 #[no_mangle]
-pub unsafe extern "C" fn wallet_add_contact(
+pub unsafe extern "C" fn wallet_get_pending_outbound_transaction_by_id(
     wallet: *mut TariWallet,
-    contact: *mut TariContact,
+    transaction_id: c_ulonglong,
     error_out: *mut c_int,
-) -> bool
+) -> *mut TariPendingOutboundTransaction
 {
     let mut error = 0;
     ptr::swap(error_out, &mut error as *mut c_int);
     if wallet.is_null() {
         error = LibWalletError::from(InterfaceError::NullError("wallet".to_string())).code;
         ptr::swap(error_out, &mut error as *mut c_int);
-        return false;
-    }
-    if contact.is_null() {
-        error = LibWalletError::from(InterfaceError::NullError("contact".to_string())).code;
-        ptr::swap(error_out, &mut error as *mut c_int);
-        return false;
+        return ptr::null_mut();
     }
 
-    match (*wallet)
+    let pending_transactions = (*wallet)
         .runtime
-        .block_on((*wallet).contacts_service.save_contact((*contact).clone()))
-    {
-        Ok(_) => true,
+        .block_on((*wallet).transaction_service.get_pending_outbound_transactions());
+
+    match pending_transactions {
+        Ok(pending_transactions) => {
+            for (id, tx) in &pending_transactions {
+                if id == &transaction_id {
+                    let pending = tx.clone();
+                    return Box::into_raw(Box::new(pending));
+                }
+            }
+            return ptr::null_mut();
+        },
         Err(e) => {
-            error = LibWalletError::from(WalletError::ContactsServiceError(e)).code;
+            error = LibWalletError::from(WalletError::TransactionServiceError(e)).code;
             ptr::swap(error_out, &mut error as *mut c_int);
-            false
+            ptr::null_mut()
         },
     }
 }
 
-/// Removes a TariContact from the TariWallet
+/// Cancels a pending inbound or outbound transaction of a TariWallet and tells its counterparty that it has been
+/// cancelled. The outputs that were encumbered by the transaction become available again.
 ///
 /// ## Arguments
 /// `wallet` - The TariWallet pointer
-/// `tx` - The TariPendingInboundTransaction pointer
+/// `transaction_id` - The TransactionId
 /// `error_out` - Pointer to an int which will be modified to an error code should one occur, may not be null. Functions
 /// as an out parameter.
 ///
 /// ## Returns
 /// `bool` - Returns if successful or not
 #[no_mangle]
-pub unsafe extern "C" fn wallet_remove_contact(
+pub unsafe extern "C" fn wallet_cancel_pending_transaction(
     wallet: *mut TariWallet,
-    contact: *mut TariContact,
+    transaction_id: c_ulonglong,
     error_out: *mut c_int,
 ) -> bool
 {
@@ -2114,26 +3558,23 @@ pub unsafe extern "C" fn wallet_remove_contact(
         ptr::swap(error_out, &mut error as *mut c_int);
         return false;
     }
-    if contact.is_null() {
-        error = LibWalletError::from(InterfaceError::NullError("contact".to_string())).code;
-        ptr::swap(error_out, &mut error as *mut c_int);
-        return false;
-    }
 
     match (*wallet)
         .runtime
-        .block_on((*wallet).contacts_service.remove_contact((*contact).public_key.clone()))
+        .block_on((*wallet).transaction_service.cancel_transaction(transaction_id))
     {
         Ok(_) => true,
         Err(e) => {
-            error = LibWalletError::from(WalletError::ContactsServiceError(e)).code;
+            error = LibWalletError::from(WalletError::TransactionServiceError(e)).code;
             ptr::swap(error_out, &mut error as *mut c_int);
             false
         },
     }
 }
 
-/// Gets the available balance from a TariWallet. This is the balance the user can spend.
+/// Sends the messages of all the pending transactions of a TariWallet to their counterparties again. This should be
+/// called when the wallet has reconnected to the network, so that the negotiation of the pending transactions resumes
+/// without waiting for the periodic resend.
 ///
 /// ## Arguments
 /// `wallet` - The TariWallet pointer
@@ -2141,9 +3582,10 @@ pub unsafe extern "C" fn wallet_remove_contact(
 /// as an out parameter.
 ///
 /// ## Returns
-/// `c_ulonglong` - The available balance, 0 if wallet is null
+/// `c_uint` - Returns the number of pending transactions whose messages were resent, note that it returns 0 if wallet
+/// is null or an error is encountered
 #[no_mangle]
-pub unsafe extern "C" fn wallet_get_available_balance(wallet: *mut TariWallet, error_out: *mut c_int) -> c_ulonglong {
+pub unsafe extern "C" fn wallet_resend_pending_transactions(wallet: *mut TariWallet, error_out: *mut c_int) -> c_uint {
     let mut error = 0;
     ptr::swap(error_out, &mut error as *mut c_int);
     if wallet.is_null() {
@@ -2154,19 +3596,18 @@ pub unsafe extern "C" fn wallet_get_available_balance(wallet: *mut TariWallet, e
 
     match (*wallet)
         .runtime
-        .block_on((*wallet).output_manager_service.get_balance())
+        .block_on((*wallet).transaction_service.resend_pending_transactions())
     {
-        Ok(b) => c_ulonglong::from(b.available_balance),
+        Ok(n) => n as c_uint,
         Err(e) => {
-            error = LibWalletError::from(WalletError::OutputManagerError(e)).code;
+            error = LibWalletError::from(WalletError::TransactionServiceError(e)).code;
             ptr::swap(error_out, &mut error as *mut c_int);
             0
         },
     }
 }
 
-/// Gets the incoming balance from a `TariWallet`. This is the uncleared balance of Tari that is
-/// expected to come into the `TariWallet` but is not yet spendable.
+/// Get the unspent outputs of a TariWallet
 ///
 /// ## Arguments
 /// `wallet` - The TariWallet pointer
@@ -2174,36 +3615,36 @@ pub unsafe extern "C" fn wallet_get_available_balance(wallet: *mut TariWallet, e
 /// as an out parameter.
 ///
 /// ## Returns
-/// `c_ulonglong` - The incoming balance, 0 if wallet is null
+/// `*mut TariUnblindedOutputs` - returns the unspent outputs, note that it returns ptr::null_mut() if
+/// wallet is null or an error is encountered
 #[no_mangle]
-pub unsafe extern "C" fn wallet_get_pending_incoming_balance(
+pub unsafe extern "C" fn wallet_get_unspent_outputs(
     wallet: *mut TariWallet,
     error_out: *mut c_int,
-) -> c_ulonglong
+) -> *mut TariUnblindedOutputs
 {
     let mut error = 0;
     ptr::swap(error_out, &mut error as *mut c_int);
     if wallet.is_null() {
         error = LibWalletError::from(InterfaceError::NullError("wallet".to_string())).code;
         ptr::swap(error_out, &mut error as *mut c_int);
-        return 0;
+        return ptr::null_mut();
     }
 
     match (*wallet)
         .runtime
-        .block_on((*wallet).output_manager_service.get_balance())
+        .block_on((*wallet).output_manager_service.get_unspent_outputs())
     {
-        Ok(b) => c_ulonglong::from(b.pending_incoming_balance),
+        Ok(outputs) => Box::into_raw(Box::new(TariUnblindedOutputs(outputs))),
         Err(e) => {
             error = LibWalletError::from(WalletError::OutputManagerError(e)).code;
             ptr::swap(error_out, &mut error as *mut c_int);
-            0
+            ptr::null_mut()
         },
     }
 }
 
-/// Gets the outgoing balance from a `TariWallet`. This is the uncleared balance of Tari that has
-/// been spent
+/// Get the TariPublicKey from a TariWallet
 ///
 /// ## Arguments
 /// `wallet` - The TariWallet pointer
@@ -2211,416 +3652,399 @@ pub unsafe extern "C" fn wallet_get_pending_incoming_balance(
 /// as an out parameter.
 ///
 /// ## Returns
-/// `c_ulonglong` - The outgoing balance, 0 if wallet is null
+/// `*mut TariPublicKey` - returns the public key, note that ptr::null_mut() is returned
+/// if wc is null
 #[no_mangle]
-pub unsafe extern "C" fn wallet_get_pending_outgoing_balance(
-    wallet: *mut TariWallet,
-    error_out: *mut c_int,
-) -> c_ulonglong
-{
+pub unsafe extern "C" fn wallet_get_public_key(wallet: *mut TariWallet, error_out: *mut c_int) -> *mut TariPublicKey {
     let mut error = 0;
     ptr::swap(error_out, &mut error as *mut c_int);
     if wallet.is_null() {
         error = LibWalletError::from(InterfaceError::NullError("wallet".to_string())).code;
         ptr::swap(error_out, &mut error as *mut c_int);
-        return 0;
-    }
-
-    match (*wallet)
-        .runtime
-        .block_on((*wallet).output_manager_service.get_balance())
-    {
-        Ok(b) => c_ulonglong::from(b.pending_outgoing_balance),
-        Err(e) => {
-            error = LibWalletError::from(WalletError::OutputManagerError(e)).code;
-            ptr::swap(error_out, &mut error as *mut c_int);
-            0
-        },
+        return ptr::null_mut();
     }
+    let pk = (*wallet).comms.node_identity().public_key().clone();
+    Box::into_raw(Box::new(pk))
 }
 
-/// Sends a TariPendingOutboundTransaction
+/// Get the seed words from which the keys of a TariWallet can be recovered
 ///
 /// ## Arguments
 /// `wallet` - The TariWallet pointer
-/// `dest_public_key` - The TariPublicKey pointer of the peer
-/// `amount` - The amount
-/// `fee_per_gram` - The transaction fee
-/// `message` - The pointer to a char array
 /// `error_out` - Pointer to an int which will be modified to an error code should one occur, may not be null. Functions
 /// as an out parameter.
 ///
 /// ## Returns
-/// `bool` - Returns if successful or not
+/// `*mut TariSeedWords` - returns the seed words, note that it returns ptr::null_mut() if
+/// wallet is null or an error is encountered
 #[no_mangle]
-pub unsafe extern "C" fn wallet_send_transaction(
-    wallet: *mut TariWallet,
-    dest_public_key: *mut TariPublicKey,
-    amount: c_ulonglong,
-    fee_per_gram: c_ulonglong,
-    message: *const c_char,
-    error_out: *mut c_int,
-) -> bool
-{
+pub unsafe extern "C" fn wallet_get_seed_words(wallet: *mut TariWallet, error_out: *mut c_int) -> *mut TariSeedWords {
     let mut error = 0;
     ptr::swap(error_out, &mut error as *mut c_int);
     if wallet.is_null() {
         error = LibWalletError::from(InterfaceError::NullError("wallet".to_string())).code;
         ptr::swap(error_out, &mut error as *mut c_int);
-        return false;
-    }
-
-    if dest_public_key.is_null() {
-        error = LibWalletError::from(InterfaceError::NullError("dest_public_key".to_string())).code;
-        ptr::swap(error_out, &mut error as *mut c_int);
-        return false;
-    }
-
-    let mut message_string = CString::new("").unwrap().to_str().unwrap().to_owned();
-    if !message.is_null() {
-        message_string = CStr::from_ptr(message).to_str().unwrap().to_owned();
-    } else {
-        error = LibWalletError::from(InterfaceError::NullError("message".to_string())).code;
-        ptr::swap(error_out, &mut error as *mut c_int);
+        return ptr::null_mut();
     }
 
     match (*wallet)
         .runtime
-        .block_on((*wallet).transaction_service.send_transaction(
-            (*dest_public_key).clone(),
-            MicroTari::from(amount),
-            MicroTari::from(fee_per_gram),
-            message_string,
-        )) {
-        Ok(_) => true,
+        .block_on((*wallet).output_manager_service.get_seed_words())
+    {
+        Ok(words) => Box::into_raw(Box::new(TariSeedWords(words))),
         Err(e) => {
-            error = LibWalletError::from(WalletError::TransactionServiceError(e)).code;
+            error = LibWalletError::from(WalletError::OutputManagerError(e)).code;
             ptr::swap(error_out, &mut error as *mut c_int);
-            false
+            ptr::null_mut()
         },
     }
 }
 
-/// Get the TariContacts from a TariWallet
+/// Starts recovering the keys and funds of a TariWallet from seed words by scanning the blockchain of its base node,
+/// which must have been added with `wallet_add_base_node_peer` first. The recovered outputs are added to the wallet
+/// as they are found and are reflected in its balance.
 ///
 /// ## Arguments
 /// `wallet` - The TariWallet pointer
+/// `seed_words` - The TariSeedWords pointer
+/// `birthday_height` - The block height at which to start scanning, 0 to scan from the genesis block
 /// `error_out` - Pointer to an int which will be modified to an error code should one occur, may not be null. Functions
 /// as an out parameter.
 ///
 /// ## Returns
-/// `*mut TariContacts` - returns the contacts, note that it returns ptr::null_mut() if
-/// wallet is null
+/// `bool` - Returns if the recovery was started or not
 #[no_mangle]
-pub unsafe extern "C" fn wallet_get_contacts(wallet: *mut TariWallet, error_out: *mut c_int) -> *mut TariContacts {
+pub unsafe extern "C" fn wallet_start_recovery(
+    wallet: *mut TariWallet,
+    seed_words: *mut TariSeedWords,
+    birthday_height: c_ulonglong,
+    error_out: *mut c_int,
+) -> bool
+{
     let mut error = 0;
     ptr::swap(error_out, &mut error as *mut c_int);
-    let mut contacts = Vec::new();
     if wallet.is_null() {
         error = LibWalletError::from(InterfaceError::NullError("wallet".to_string())).code;
         ptr::swap(error_out, &mut error as *mut c_int);
-        return ptr::null_mut();
+        return false;
     }
 
-    let retrieved_contacts = (*wallet).runtime.block_on((*wallet).contacts_service.get_contacts());
-    match retrieved_contacts {
-        Ok(retrieved_contacts) => {
-            contacts.append(&mut retrieved_contacts.clone());
-            Box::into_raw(Box::new(TariContacts(contacts)))
-        },
+    if seed_words.is_null() {
+        error = LibWalletError::from(InterfaceError::NullError("seed_words".to_string())).code;
+        ptr::swap(error_out, &mut error as *mut c_int);
+        return false;
+    }
+
+    let birthday_height = if birthday_height > 0 {
+        Some(birthday_height)
+    } else {
+        None
+    };
+    match (*wallet).recover_from_seed_words((*seed_words).0.clone(), birthday_height) {
+        Ok(_) => true,
         Err(e) => {
-            error = LibWalletError::from(WalletError::ContactsServiceError(e)).code;
+            error = LibWalletError::from(e).code;
             ptr::swap(error_out, &mut error as *mut c_int);
-            ptr::null_mut()
+            false
         },
     }
 }
 
-/// Get the TariCompletedTransactions from a TariWallet
+/// Encrypts the secrets of a TariWallet with a passphrase, which must then be provided to `wallet_create`
 ///
 /// ## Arguments
 /// `wallet` - The TariWallet pointer
+/// `passphrase` - The pointer to a char array containing the passphrase
 /// `error_out` - Pointer to an int which will be modified to an error code should one occur, may not be null. Functions
 /// as an out parameter.
 ///
 /// ## Returns
-/// `*mut TariCompletedTransactions` - returns the transactions, note that it returns ptr::null_mut() if
-/// wallet is null or an error is encountered
+/// `bool` - Returns if successful or not
 #[no_mangle]
-pub unsafe extern "C" fn wallet_get_completed_transactions(
+pub unsafe extern "C" fn wallet_apply_encryption(
     wallet: *mut TariWallet,
+    passphrase: *const c_char,
     error_out: *mut c_int,
-) -> *mut TariCompletedTransactions
+) -> bool
 {
     let mut error = 0;
     ptr::swap(error_out, &mut error as *mut c_int);
-    let mut completed = Vec::new();
     if wallet.is_null() {
         error = LibWalletError::from(InterfaceError::NullError("wallet".to_string())).code;
         ptr::swap(error_out, &mut error as *mut c_int);
-        return ptr::null_mut();
+        return false;
     }
 
-    let completed_transactions = (*wallet)
-        .runtime
-        .block_on((*wallet).transaction_service.get_completed_transactions());
-    match completed_transactions {
-        Ok(completed_transactions) => {
-            for (_id, tx) in &completed_transactions {
-                completed.push(tx.clone());
-            }
-            Box::into_raw(Box::new(TariCompletedTransactions(completed)))
-        },
+    let passphrase_string;
+    if !passphrase.is_null() {
+        passphrase_string = CStr::from_ptr(passphrase).to_str().unwrap().to_owned();
+    } else {
+        error = LibWalletError::from(InterfaceError::NullError("passphrase".to_string())).code;
+        ptr::swap(error_out, &mut error as *mut c_int);
+        return false;
+    }
+
+    match (*wallet).apply_encryption(passphrase_string.as_str()) {
+        Ok(_) => true,
         Err(e) => {
-            error = LibWalletError::from(WalletError::TransactionServiceError(e)).code;
+            error = LibWalletError::from(e).code;
             ptr::swap(error_out, &mut error as *mut c_int);
-            ptr::null_mut()
+            false
         },
     }
 }
 
-/// Get the TariPendingInboundTransactions from a TariWallet
+/// Decrypts the secrets of an unlocked TariWallet so that a passphrase is no longer required
 ///
 /// ## Arguments
 /// `wallet` - The TariWallet pointer
+/// `passphrase` - The pointer to a char array containing the passphrase
 /// `error_out` - Pointer to an int which will be modified to an error code should one occur, may not be null. Functions
 /// as an out parameter.
 ///
 /// ## Returns
-/// `*mut TariPendingInboundTransactions` - returns the transactions, note that it returns ptr::null_mut() if
-/// wallet is null or and error is encountered
+/// `bool` - Returns if successful or not
 #[no_mangle]
-pub unsafe extern "C" fn wallet_get_pending_inbound_transactions(
+pub unsafe extern "C" fn wallet_remove_encryption(
     wallet: *mut TariWallet,
+    passphrase: *const c_char,
     error_out: *mut c_int,
-) -> *mut TariPendingInboundTransactions
+) -> bool
 {
     let mut error = 0;
     ptr::swap(error_out, &mut error as *mut c_int);
-    let mut pending = Vec::new();
     if wallet.is_null() {
         error = LibWalletError::from(InterfaceError::NullError("wallet".to_string())).code;
         ptr::swap(error_out, &mut error as *mut c_int);
-        return ptr::null_mut();
+        return false;
     }
 
-    let pending_transactions = (*wallet)
-        .runtime
-        .block_on((*wallet).transaction_service.get_pending_inbound_transactions());
-    match pending_transactions {
-        Ok(pending_transactions) => {
-            for (_id, tx) in &pending_transactions {
-                pending.push(tx.clone());
-            }
-            Box::into_raw(Box::new(TariPendingInboundTransactions(pending)))
-        },
+    let passphrase_string;
+    if !passphrase.is_null() {
+        passphrase_string = CStr::from_ptr(passphrase).to_str().unwrap().to_owned();
+    } else {
+        error = LibWalletError::from(InterfaceError::NullError("passphrase".to_string())).code;
+        ptr::swap(error_out, &mut error as *mut c_int);
+        return false;
+    }
+
+    match (*wallet).remove_encryption(passphrase_string.as_str()) {
+        Ok(_) => true,
         Err(e) => {
-            error = LibWalletError::from(WalletError::TransactionServiceError(e)).code;
+            error = LibWalletError::from(e).code;
             ptr::swap(error_out, &mut error as *mut c_int);
-            ptr::null_mut()
+            false
         },
     }
 }
 
-/// Get the TariPendingOutboundTransactions from a TariWallet
+/// Re-encrypts the secrets of an unlocked TariWallet with a new passphrase
 ///
 /// ## Arguments
 /// `wallet` - The TariWallet pointer
+/// `passphrase` - The pointer to a char array containing the passphrase
+/// `new_passphrase` - The pointer to a char array containing the new passphrase
 /// `error_out` - Pointer to an int which will be modified to an error code should one occur, may not be null. Functions
 /// as an out parameter.
 ///
 /// ## Returns
-/// `*mut TariPendingOutboundTransactions` - returns the transactions, note that it returns ptr::null_mut() if
-/// wallet is null or and error is encountered
+/// `bool` - Returns if successful or not
 #[no_mangle]
-pub unsafe extern "C" fn wallet_get_pending_outbound_transactions(
+pub unsafe extern "C" fn wallet_change_passphrase(
     wallet: *mut TariWallet,
+    passphrase: *const c_char,
+    new_passphrase: *const c_char,
     error_out: *mut c_int,
-) -> *mut TariPendingOutboundTransactions
+) -> bool
 {
     let mut error = 0;
     ptr::swap(error_out, &mut error as *mut c_int);
-    let mut pending = Vec::new();
     if wallet.is_null() {
         error = LibWalletError::from(InterfaceError::NullError("wallet".to_string())).code;
         ptr::swap(error_out, &mut error as *mut c_int);
-        return ptr::null_mut();
+        return false;
     }
 
-    let pending_transactions = (*wallet)
-        .runtime
-        .block_on((*wallet).transaction_service.get_pending_outbound_transactions());
-    match pending_transactions {
-        Ok(pending_transactions) => {
-            for (_id, tx) in &pending_transactions {
-                pending.push(tx.clone());
-            }
-            Box::into_raw(Box::new(TariPendingOutboundTransactions(pending)))
-        },
+    let passphrase_string;
+    if !passphrase.is_null() {
+        passphrase_string = CStr::from_ptr(passphrase).to_str().unwrap().to_owned();
+    } else {
+        error = LibWalletError::from(InterfaceError::NullError("passphrase".to_string())).code;
+        ptr::swap(error_out, &mut error as *mut c_int);
+        return false;
+    }
+
+    let new_passphrase_string;
+    if !new_passphrase.is_null() {
+        new_passphrase_string = CStr::from_ptr(new_passphrase).to_str().unwrap().to_owned();
+    } else {
+        error = LibWalletError::from(InterfaceError::NullError("new_passphrase".to_string())).code;
+        ptr::swap(error_out, &mut error as *mut c_int);
+        return false;
+    }
+
+    match (*wallet).change_passphrase(passphrase_string.as_str(), new_passphrase_string.as_str()) {
+        Ok(_) => true,
         Err(e) => {
-            error = LibWalletError::from(WalletError::TransactionServiceError(e)).code;
+            error = LibWalletError::from(e).code;
             ptr::swap(error_out, &mut error as *mut c_int);
-            ptr::null_mut()
+            false
         },
     }
 }
 
-/// Get the TariCompletedTransaction from a TariWallet by its' TransactionId
+/// Unlocks the encrypted secrets of a TariWallet that has been locked
 ///
 /// ## Arguments
 /// `wallet` - The TariWallet pointer
-/// `transaction_id` - The TransactionId
+/// `passphrase` - The pointer to a char array containing the passphrase
 /// `error_out` - Pointer to an int which will be modified to an error code should one occur, may not be null. Functions
 /// as an out parameter.
 ///
 /// ## Returns
-/// `*mut TariCompletedTransaction` - returns the transaction, note that it returns ptr::null_mut() if
-/// wallet is null, an error is encountered or if the transaction is not found
+/// `bool` - Returns if successful or not
 #[no_mangle]
-pub unsafe extern "C" fn wallet_get_completed_transaction_by_id(
+pub unsafe extern "C" fn wallet_unlock(
     wallet: *mut TariWallet,
-    transaction_id: c_ulonglong,
+    passphrase: *const c_char,
     error_out: *mut c_int,
-) -> *mut TariCompletedTransaction
+) -> bool
 {
     let mut error = 0;
     ptr::swap(error_out, &mut error as *mut c_int);
     if wallet.is_null() {
         error = LibWalletError::from(InterfaceError::NullError("wallet".to_string())).code;
         ptr::swap(error_out, &mut error as *mut c_int);
-        return ptr::null_mut();
+        return false;
     }
 
-    let pending_transactions = (*wallet)
-        .runtime
-        .block_on((*wallet).transaction_service.get_completed_transactions());
+    let passphrase_string;
+    if !passphrase.is_null() {
+        passphrase_string = CStr::from_ptr(passphrase).to_str().unwrap().to_owned();
+    } else {
+        error = LibWalletError::from(InterfaceError::NullError("passphrase".to_string())).code;
+        ptr::swap(error_out, &mut error as *mut c_int);
+        return false;
+    }
 
-    match pending_transactions {
-        Ok(pending_transactions) => {
-            for (id, tx) in &pending_transactions {
-                if id == &transaction_id {
-                    let pending = tx.clone();
-                    return Box::into_raw(Box::new(pending));
-                }
-            }
-            return ptr::null_mut();
-        },
+    match (*wallet).unlock(passphrase_string.as_str()) {
+        Ok(_) => true,
         Err(e) => {
-            error = LibWalletError::from(WalletError::TransactionServiceError(e)).code;
+            error = LibWalletError::from(e).code;
             ptr::swap(error_out, &mut error as *mut c_int);
-            ptr::null_mut()
+            false
         },
     }
 }
 
-/// Get the TariPendingInboundTransaction from a TariWallet by its' TransactionId
+/// Locks the encrypted secrets of a TariWallet. While it is locked, operations that require its secrets, such as
+/// getting the balance or sending and receiving transactions, will fail.
 ///
 /// ## Arguments
 /// `wallet` - The TariWallet pointer
-/// `transaction_id` - The TransactionId
 /// `error_out` - Pointer to an int which will be modified to an error code should one occur, may not be null. Functions
 /// as an out parameter.
 ///
 /// ## Returns
-/// `*mut TariPendingInboundTransaction` - returns the transaction, note that it returns ptr::null_mut() if
-/// wallet is null, an error is encountered or if the transaction is not found
+/// `bool` - Returns if successful or not
 #[no_mangle]
-pub unsafe extern "C" fn wallet_get_pending_inbound_transaction_by_id(
-    wallet: *mut TariWallet,
-    transaction_id: c_ulonglong,
-    error_out: *mut c_int,
-) -> *mut TariPendingInboundTransaction
-{
+pub unsafe extern "C" fn wallet_lock(wallet: *mut TariWallet, error_out: *mut c_int) -> bool {
     let mut error = 0;
     ptr::swap(error_out, &mut error as *mut c_int);
     if wallet.is_null() {
         error = LibWalletError::from(InterfaceError::NullError("wallet".to_string())).code;
         ptr::swap(error_out, &mut error as *mut c_int);
-        return ptr::null_mut();
+        return false;
     }
 
-    let pending_transactions = (*wallet)
-        .runtime
-        .block_on((*wallet).transaction_service.get_pending_inbound_transactions());
-
-    match pending_transactions {
-        Ok(pending_transactions) => {
-            for (id, tx) in &pending_transactions {
-                if id == &transaction_id {
-                    let pending = tx.clone();
-                    return Box::into_raw(Box::new(pending));
-                }
-            }
-            return ptr::null_mut();
-        },
+    match (*wallet).lock() {
+        Ok(_) => true,
         Err(e) => {
-            error = LibWalletError::from(WalletError::TransactionServiceError(e)).code;
+            error = LibWalletError::from(e).code;
             ptr::swap(error_out, &mut error as *mut c_int);
-            ptr::null_mut()
+            false
         },
     }
 }
 
-/// Get the TariPendingOutboundTransaction from a TariWallet by its' TransactionId
+/// Writes an encrypted backup of a TariWallet, holding its node identity, base node peers, keys, outputs, transaction
+/// history and contacts, to a file. The wallet must be unlocked.
 ///
 /// ## Arguments
 /// `wallet` - The TariWallet pointer
-/// `transaction_id` - The TransactionId
+/// `path` - The pointer to a char array containing the path of the backup file
+/// `passphrase` - The pointer to a char array containing the passphrase to encrypt the backup with
 /// `error_out` - Pointer to an int which will be modified to an error code should one occur, may not be null. Functions
 /// as an out parameter.
 ///
 /// ## Returns
-/// `*mut TariPendingOutboundTransaction` - returns the transaction, note that it returns ptr::null_mut() if
-/// wallet is null, an error is encountered or if the transaction is not found
+/// `bool` - Returns if successful or not
 #[no_mangle]
-pub unsafe extern "C" fn wallet_get_pending_outbound_transaction_by_id(
+pub unsafe extern "C" fn wallet_backup_to(
     wallet: *mut TariWallet,
-    transaction_id: c_ulonglong,
+    path: *const c_char,
+    passphrase: *const c_char,
     error_out: *mut c_int,
-) -> *mut TariPendingOutboundTransaction
+) -> bool
 {
     let mut error = 0;
     ptr::swap(error_out, &mut error as *mut c_int);
     if wallet.is_null() {
         error = LibWalletError::from(InterfaceError::NullError("wallet".to_string())).code;
         ptr::swap(error_out, &mut error as *mut c_int);
-        return ptr::null_mut();
+        return false;
     }
 
-    let pending_transactions = (*wallet)
-        .runtime
-        .block_on((*wallet).transaction_service.get_pending_outbound_transactions());
+    let path_string;
+    if !path.is_null() {
+        path_string = CStr::from_ptr(path).to_str().unwrap().to_owned();
+    } else {
+        error = LibWalletError::from(InterfaceError::NullError("path".to_string())).code;
+        ptr::swap(error_out, &mut error as *mut c_int);
+        return false;
+    }
 
-    match pending_transactions {
-        Ok(pending_transactions) => {
-            for (id, tx) in &pending_transactions {
-                if id == &transaction_id {
-                    let pending = tx.clone();
-                    return Box::into_raw(Box::new(pending));
-                }
-            }
-            return ptr::null_mut();
-        },
+    let passphrase_string;
+    if !passphrase.is_null() {
+        passphrase_string = CStr::from_ptr(passphrase).to_str().unwrap().to_owned();
+    } else {
+        error = LibWalletError::from(InterfaceError::NullError("passphrase".to_string())).code;
+        ptr::swap(error_out, &mut error as *mut c_int);
+        return false;
+    }
+
+    match (*wallet).backup_to(Path::new(&path_string), passphrase_string.as_str()) {
+        Ok(_) => true,
         Err(e) => {
-            error = LibWalletError::from(WalletError::TransactionServiceError(e)).code;
+            error = LibWalletError::from(e).code;
             ptr::swap(error_out, &mut error as *mut c_int);
-            ptr::null_mut()
+            false
         },
     }
 }
 
-/// Get the TariPublicKey from a TariWallet
+/// Restores an encrypted backup file into a TariWallet that does not hold any outputs or transactions yet. The wallet
+/// must be unlocked. The secret key of the backup's node identity is returned: an encrypted wallet also saves it with
+/// its other secrets, but an unencrypted wallet must be created with it in its TariCommsConfig from then on.
 ///
 /// ## Arguments
 /// `wallet` - The TariWallet pointer
+/// `path` - The pointer to a char array containing the path of the backup file
+/// `passphrase` - The pointer to a char array containing the passphrase the backup was encrypted with
 /// `error_out` - Pointer to an int which will be modified to an error code should one occur, may not be null. Functions
 /// as an out parameter.
 ///
 /// ## Returns
-/// `*mut TariPublicKey` - returns the public key, note that ptr::null_mut() is returned
-/// if wc is null
+/// `*mut TariPrivateKey` - Returns a pointer to the secret key of the restored node identity, note that it returns
+/// ptr::null_mut() if the backup could not be restored
 #[no_mangle]
-pub unsafe extern "C" fn wallet_get_public_key(wallet: *mut TariWallet, error_out: *mut c_int) -> *mut TariPublicKey {
+pub unsafe extern "C" fn wallet_restore_from(
+    wallet: *mut TariWallet,
+    path: *const c_char,
+    passphrase: *const c_char,
+    error_out: *mut c_int,
+) -> *mut TariPrivateKey
+{
     let mut error = 0;
     ptr::swap(error_out, &mut error as *mut c_int);
     if wallet.is_null() {
@@ -2628,8 +4052,33 @@ pub unsafe extern "C" fn wallet_get_public_key(wallet: *mut TariWallet, error_ou
         ptr::swap(error_out, &mut error as *mut c_int);
         return ptr::null_mut();
     }
-    let pk = (*wallet).comms.node_identity().public_key().clone();
-    Box::into_raw(Box::new(pk))
+
+    let path_string;
+    if !path.is_null() {
+        path_string = CStr::from_ptr(path).to_str().unwrap().to_owned();
+    } else {
+        error = LibWalletError::from(InterfaceError::NullError("path".to_string())).code;
+        ptr::swap(error_out, &mut error as *mut c_int);
+        return ptr::null_mut();
+    }
+
+    let passphrase_string;
+    if !passphrase.is_null() {
+        passphrase_string = CStr::from_ptr(passphrase).to_str().unwrap().to_owned();
+    } else {
+        error = LibWalletError::from(InterfaceError::NullError("passphrase".to_string())).code;
+        ptr::swap(error_out, &mut error as *mut c_int);
+        return ptr::null_mut();
+    }
+
+    match (*wallet).restore_from(Path::new(&path_string), passphrase_string.as_str()) {
+        Ok(secret_key) => Box::into_raw(Box::new(secret_key)),
+        Err(e) => {
+            error = LibWalletError::from(e).code;
+            ptr::swap(error_out, &mut error as *mut c_int);
+            ptr::null_mut()
+        },
+    }
 }
 
 /// Frees memory for a TariWallet
@@ -2657,7 +4106,12 @@ mod test {
     use crate::*;
     use libc::{c_char, c_uchar, c_uint};
     use std::{ffi::CString, sync::Mutex};
-    use tari_wallet::{testnet_utils::random_string, transaction_service::storage::database::TransactionStatus};
+    use tari_common::Network;
+    use tari_wallet::{
+        error::PaymentRequestError,
+        testnet_utils::random_string,
+        transaction_service::storage::database::TransactionStatus,
+    };
     use tempdir::TempDir;
 
     fn type_of<T>(_: T) -> String {
@@ -3005,6 +4459,88 @@ mod test {
             byte_vector_destroy(contact_key_bytes);
         }
     }
+    #[test]
+    fn test_seed_words() {
+        unsafe {
+            let mut error = 0;
+            let error_ptr = &mut error as *mut c_int;
+            let seed_words = seed_words_create();
+            assert_eq!(seed_words_get_length(seed_words, error_ptr), 0);
+            let words = ["abandon", "ability", "able"];
+            for w in words.iter() {
+                let word_str = CString::into_raw(CString::new(*w).unwrap()) as *const c_char;
+                assert!(seed_words_push_word(seed_words, word_str, error_ptr));
+                string_destroy(word_str as *mut c_char);
+            }
+            assert_eq!(seed_words_get_length(seed_words, error_ptr), 3);
+            let word = seed_words_get_at(seed_words, 1, error_ptr);
+            assert_eq!(CString::from_raw(word).to_str().unwrap(), "ability");
+            let word = seed_words_get_at(seed_words, 3, error_ptr);
+            assert_eq!(CString::from_raw(word).to_str().unwrap(), "");
+            assert_eq!(error, LibWalletError::from(InterfaceError::PositionInvalidError).code);
+            assert!(!seed_words_push_word(seed_words, ptr::null(), error_ptr));
+            assert_eq!(
+                error,
+                LibWalletError::from(InterfaceError::NullError("word".to_string())).code
+            );
+            assert_eq!(seed_words_get_length(ptr::null_mut(), error_ptr), 0);
+            assert_eq!(
+                error,
+                LibWalletError::from(InterfaceError::NullError("seed_words".to_string())).code
+            );
+            seed_words_destroy(seed_words);
+        }
+    }
+
+    #[test]
+    fn test_payment_request() {
+        unsafe {
+            let mut error = 0;
+            let error_ptr = &mut error as *mut c_int;
+            let private_key = private_key_generate();
+            let public_key = public_key_from_private_key(private_key, error_ptr);
+            let uri = TariPaymentRequest::new(Network::TestNet, (*public_key).clone())
+                .with_amount(MicroTari::from(1234))
+                .with_message("Coffee".to_string())
+                .to_uri();
+            let uri_str = CString::into_raw(CString::new(uri.as_str()).unwrap()) as *const c_char;
+            let request = payment_request_from_uri(uri_str, error_ptr);
+            assert_eq!(error, 0);
+            let payee = payment_request_get_public_key(request, error_ptr);
+            assert_eq!(*payee, *public_key);
+            assert_eq!(payment_request_get_amount(request, error_ptr), 1234);
+            let message = payment_request_get_message(request, error_ptr);
+            assert_eq!(CString::from_raw(message).to_str().unwrap(), "Coffee");
+            assert_eq!(payment_request_get_expiry(request, error_ptr), 0);
+            assert!(!payment_request_is_expired(request, error_ptr));
+            assert_eq!(error, 0);
+
+            let altered = CString::into_raw(CString::new(uri.replace("1234", "4321")).unwrap()) as *const c_char;
+            assert!(payment_request_from_uri(altered, error_ptr).is_null());
+            assert_eq!(error, LibWalletError::from(PaymentRequestError::InvalidChecksum).code);
+            assert!(payment_request_from_uri(ptr::null(), error_ptr).is_null());
+            assert_eq!(
+                error,
+                LibWalletError::from(InterfaceError::NullError("uri".to_string())).code
+            );
+
+            let emoji_id = public_key_to_emoji_id(public_key, error_ptr);
+            assert_eq!(error, 0);
+            let emoji_key = emoji_id_to_public_key(emoji_id, error_ptr);
+            assert_eq!(error, 0);
+            assert_eq!(*emoji_key, *public_key);
+
+            string_destroy(emoji_id);
+            string_destroy(uri_str as *mut c_char);
+            string_destroy(altered as *mut c_char);
+            public_key_destroy(emoji_key);
+            public_key_destroy(payee);
+            payment_request_destroy(request);
+            public_key_destroy(public_key);
+            private_key_destroy(private_key);
+        }
+    }
+
     #[test]
     fn test_wallet_ffi() {
         unsafe {
@@ -3038,6 +4574,7 @@ mod test {
             let alice_wallet = wallet_create(
                 alice_config,
                 ptr::null(),
+                ptr::null(),
                 received_tx_callback,
                 received_tx_reply_callback,
                 received_tx_finalized_callback,
@@ -3069,6 +4606,7 @@ mod test {
             let bob_wallet = wallet_create(
                 bob_config,
                 ptr::null(),
+                ptr::null(),
                 received_tx_callback_bob,
                 received_tx_reply_callback_bob,
                 received_tx_finalized_callback_bob,
@@ -3104,6 +4642,18 @@ mod test {
                 (wallet_get_completed_transactions(&mut (*alice_wallet), error_ptr)).is_null(),
                 false
             );
+
+            let history = wallet_export_transaction_history(alice_wallet, 0, 0, 0, 2, false, error_ptr);
+            assert_eq!(error, 0);
+            let history_str = CStr::from_ptr(history).to_str().unwrap().to_owned();
+            let lines: Vec<&str> = history_str.lines().collect();
+            assert_eq!(lines.len(), 3);
+            assert!(lines[0].starts_with("tx_id,direction,counterparty,amount,fee,status"));
+            string_destroy(history);
+            let history = wallet_export_transaction_history(alice_wallet, 0, 0, 0, 0, true, error_ptr);
+            assert_eq!(error, 0);
+            assert!(CStr::from_ptr(history).to_str().unwrap().starts_with('['));
+            string_destroy(history);
             assert_eq!(
                 (wallet_get_pending_inbound_transactions(&mut (*alice_wallet), error_ptr)).is_null(),
                 false
@@ -3190,6 +4740,130 @@ mod test {
             // Not testing for the discovery_process_completed callback as its tricky to evoke and it is unit tested
             // elsewhere
 
+            let seed_words = wallet_get_seed_words(alice_wallet, error_ptr);
+            assert_eq!(error, 0);
+            assert_eq!(seed_words_get_length(seed_words, error_ptr), 24);
+
+            let unspent_outputs = wallet_get_unspent_outputs(alice_wallet, error_ptr);
+            let num_unspent = unblinded_outputs_get_length(unspent_outputs, error_ptr);
+            assert!(num_unspent > 0);
+            let mut unspent_total = 0;
+            for i in 0..num_unspent {
+                let output = unblinded_outputs_get_at(unspent_outputs, i, error_ptr);
+                unspent_total += unblinded_output_get_value(output, error_ptr);
+                unblinded_output_destroy(output);
+            }
+            assert!(unspent_total >= wallet_get_available_balance(alice_wallet, error_ptr));
+            unblinded_outputs_destroy(unspent_outputs);
+
+            assert!(wallet_get_fee_estimate(alice_wallet, 1000, 100, error_ptr) > 0);
+            assert_eq!(error, 0);
+            assert_eq!(
+                wallet_get_fee_estimate(alice_wallet, unspent_total + 1, 100, error_ptr),
+                0
+            );
+            assert_eq!(error, 101);
+
+            assert_eq!(wallet_get_coin_split_fee(alice_wallet, 0, 3, 10, error_ptr), 0);
+            assert_eq!(error, 110);
+            let coin_split_fee = wallet_get_coin_split_fee(alice_wallet, 100, 3, 10, error_ptr);
+            assert!(coin_split_fee > 0);
+            let split_message_str = CString::into_raw(CString::new("Coin split").unwrap()) as *const c_char;
+            let split_tx_id = wallet_coin_split(alice_wallet, 100, 3, 10, split_message_str, error_ptr);
+            assert_eq!(error, 0);
+            assert_ne!(split_tx_id, 0);
+            let split_tx = wallet_get_completed_transaction_by_id(alice_wallet, split_tx_id, error_ptr);
+            assert_eq!(completed_transaction_get_fee(split_tx, error_ptr), coin_split_fee);
+            completed_transaction_destroy(split_tx);
+            string_destroy(split_message_str as *mut c_char);
+
+            assert!(!wallet_cancel_pending_transaction(alice_wallet, u64::MAX, error_ptr));
+            assert_eq!(error, 204);
+            let pending_outbound = wallet_get_pending_outbound_transactions(alice_wallet, error_ptr);
+            let resent = wallet_resend_pending_transactions(alice_wallet, error_ptr);
+            assert_eq!(error, 0);
+            assert!(resent >= pending_outbound_transactions_get_length(pending_outbound, error_ptr));
+            if pending_outbound_transactions_get_length(pending_outbound, error_ptr) > 0 {
+                let pending_tx = pending_outbound_transactions_get_at(pending_outbound, 0, error_ptr);
+                let pending_tx_id = pending_outbound_transaction_get_transaction_id(pending_tx, error_ptr);
+                assert!(wallet_cancel_pending_transaction(
+                    alice_wallet,
+                    pending_tx_id,
+                    error_ptr
+                ));
+                assert!(!wallet_cancel_pending_transaction(
+                    alice_wallet,
+                    pending_tx_id,
+                    error_ptr
+                ));
+                assert_eq!(error, 211);
+                pending_outbound_transaction_destroy(pending_tx);
+            }
+            pending_outbound_transactions_destroy(pending_outbound);
+
+            let passphrase_str = CString::into_raw(CString::new("correct horse").unwrap()) as *const c_char;
+            let new_passphrase_str = CString::into_raw(CString::new("battery staple").unwrap()) as *const c_char;
+            assert!(wallet_apply_encryption(alice_wallet, passphrase_str, error_ptr));
+            assert!(wallet_lock(alice_wallet, error_ptr));
+            assert!(!wallet_unlock(alice_wallet, new_passphrase_str, error_ptr));
+            assert_eq!(error, 901);
+            assert!(wallet_unlock(alice_wallet, passphrase_str, error_ptr));
+            assert!(wallet_change_passphrase(
+                alice_wallet,
+                passphrase_str,
+                new_passphrase_str,
+                error_ptr
+            ));
+            assert!(wallet_remove_encryption(alice_wallet, new_passphrase_str, error_ptr));
+            assert!(!wallet_lock(alice_wallet, error_ptr));
+            assert_eq!(error, 904);
+
+            let backup_path = alice_temp_dir.path().join("wallet.backup");
+            let backup_path_str =
+                CString::into_raw(CString::new(backup_path.to_str().unwrap()).unwrap()) as *const c_char;
+            assert!(wallet_backup_to(
+                alice_wallet,
+                backup_path_str,
+                passphrase_str,
+                error_ptr
+            ));
+            assert!(backup_path.exists());
+            assert!(wallet_restore_from(bob_wallet, backup_path_str, new_passphrase_str, error_ptr).is_null());
+            assert_eq!(error, 906);
+            let restored_secret_key = wallet_restore_from(bob_wallet, backup_path_str, passphrase_str, error_ptr);
+            assert!(!restored_secret_key.is_null());
+            assert_eq!(
+                *restored_secret_key,
+                (*alice_wallet).comms.node_identity().secret_key().clone()
+            );
+            private_key_destroy(restored_secret_key);
+            assert_eq!(
+                wallet_get_available_balance(bob_wallet, error_ptr),
+                wallet_get_available_balance(alice_wallet, error_ptr)
+            );
+            assert_eq!(
+                contacts_get_length(wallet_get_contacts(bob_wallet, error_ptr), error_ptr),
+                4
+            );
+            // A backup can only be restored into an empty wallet
+            assert!(wallet_restore_from(bob_wallet, backup_path_str, passphrase_str, error_ptr).is_null());
+            assert_eq!(error, 908);
+            string_destroy(backup_path_str as *mut c_char);
+            string_destroy(passphrase_str as *mut c_char);
+            string_destroy(new_passphrase_str as *mut c_char);
+
+            let invalid_seed_words = seed_words_create();
+            let invalid_word_str = CString::into_raw(CString::new("not_a_seed_word").unwrap()) as *const c_char;
+            assert!(seed_words_push_word(invalid_seed_words, invalid_word_str, error_ptr));
+            assert!(!wallet_start_recovery(bob_wallet, invalid_seed_words, 0, error_ptr));
+            assert_eq!(error, 1003);
+            assert!(wallet_start_recovery(bob_wallet, seed_words, 0, error_ptr));
+            assert!(!wallet_start_recovery(bob_wallet, seed_words, 0, error_ptr));
+            assert_eq!(error, 1001);
+            string_destroy(invalid_word_str as *mut c_char);
+            seed_words_destroy(invalid_seed_words);
+            seed_words_destroy(seed_words);
+
             // free string memory
             string_destroy(address_listener_alice_str as *mut c_char);
             string_destroy(address_listener_bob_str as *mut c_char);
@@ -69,6 +69,14 @@ struct TariPendingInboundTransactions;
 
 struct TariPendingInboundTransaction;
 
+struct TariSeedWords;
+
+struct TariUnblindedOutputs;
+
+struct TariUnblindedOutput;
+
+struct TariPaymentRequest;
+
 
 /// -------------------------------- Strings ----------------------------------------------- ///
 
@@ -103,6 +111,12 @@ struct TariPublicKey *public_key_from_private_key(struct TariPrivateKey *secret_
 // Creates a TariPublicKey from a const char* filled with hexadecimal characters
 struct TariPublicKey *public_key_from_hex(const char *hex,int* error_out);
 
+// Encodes a TariPublicKey as an emoji ID
+char *public_key_to_emoji_id(struct TariPublicKey *pk, int* error_out);
+
+// Creates a TariPublicKey from an emoji ID
+struct TariPublicKey *emoji_id_to_public_key(const char *emoji, int* error_out);
+
 // Frees memory for a TariPublicKey pointer
 void public_key_destroy(struct TariPublicKey *pk);
 
@@ -180,6 +194,9 @@ unsigned long long completed_transaction_get_transaction_id(struct TariCompleted
 // Gets the timestamp of a TariCompletedTransaction
 unsigned long long completed_transaction_get_timestamp(struct TariCompletedTransaction *transaction,int* error_out);
 
+// Gets the height of the chain when a TariCompletedTransaction was detected as mined, zero if it has not been mined
+unsigned long long completed_transaction_get_mined_height(struct TariCompletedTransaction *transaction,int* error_out);
+
 // Frees memory for a TariCompletedTransaction
 void completed_transaction_destroy(struct TariCompletedTransaction *transaction);
 
@@ -256,6 +273,68 @@ struct TariPendingInboundTransaction *pending_inbound_transactions_get_at(struct
 // Frees memory of a TariPendingInboundTransaction
 void pending_inbound_transactions_destroy(struct TariPendingInboundTransactions *transactions);
 
+/// -------------------------------- SeedWords ------------------------------------------------------ ///
+
+// Creates an empty TariSeedWords
+struct TariSeedWords *seed_words_create();
+
+// Gets the number of words in a TariSeedWords
+unsigned int seed_words_get_length(struct TariSeedWords *seed_words, int* error_out);
+
+// Gets the word of a TariSeedWords at position
+char *seed_words_get_at(struct TariSeedWords *seed_words, unsigned int position, int* error_out);
+
+// Adds a word to the end of a TariSeedWords
+bool seed_words_push_word(struct TariSeedWords *seed_words, const char *word, int* error_out);
+
+// Frees memory for a TariSeedWords
+void seed_words_destroy(struct TariSeedWords *seed_words);
+
+/// -------------------------------- UnblindedOutputs ------------------------------------------------------ ///
+
+// Gets the number of elements of TariUnblindedOutputs
+unsigned int unblinded_outputs_get_length(struct TariUnblindedOutputs *outputs, int* error_out);
+
+// Gets a TariUnblindedOutput from TariUnblindedOutputs at position
+struct TariUnblindedOutput *unblinded_outputs_get_at(struct TariUnblindedOutputs *outputs, unsigned int position, int* error_out);
+
+// Frees memory for TariUnblindedOutputs
+void unblinded_outputs_destroy(struct TariUnblindedOutputs *outputs);
+
+/// -------------------------------- UnblindedOutput ------------------------------------------------------ ///
+
+// Gets the value of a TariUnblindedOutput
+unsigned long long unblinded_output_get_value(struct TariUnblindedOutput *output, int* error_out);
+
+// Gets the maturity of a TariUnblindedOutput
+unsigned long long unblinded_output_get_maturity(struct TariUnblindedOutput *output, int* error_out);
+
+// Frees memory for a TariUnblindedOutput
+void unblinded_output_destroy(struct TariUnblindedOutput *output);
+
+/// -------------------------------- PaymentRequest ------------------------------------------------------ ///
+
+// Parses a payment request URI into a TariPaymentRequest
+struct TariPaymentRequest *payment_request_from_uri(const char *uri, int* error_out);
+
+// Gets the TariPublicKey of the payee of a TariPaymentRequest
+struct TariPublicKey *payment_request_get_public_key(struct TariPaymentRequest *request, int* error_out);
+
+// Gets the amount of a TariPaymentRequest, zero if it does not have one
+unsigned long long payment_request_get_amount(struct TariPaymentRequest *request, int* error_out);
+
+// Gets the message of a TariPaymentRequest
+char *payment_request_get_message(struct TariPaymentRequest *request, int* error_out);
+
+// Gets the expiry time of a TariPaymentRequest as a Unix timestamp, zero if it does not expire
+long long payment_request_get_expiry(struct TariPaymentRequest *request, int* error_out);
+
+// Checks whether a TariPaymentRequest has expired
+bool payment_request_is_expired(struct TariPaymentRequest *request, int* error_out);
+
+// Frees memory for a TariPaymentRequest
+void payment_request_destroy(struct TariPaymentRequest *request);
+
 /// -------------------------------- TariCommsConfig ----------------------------------------------- ///
 // Creates a TariCommsConfig
 struct TariCommsConfig *comms_config_create(char *control_service_address,
@@ -273,6 +352,7 @@ void comms_config_destroy(struct TariCommsConfig *wc);
 // Creates a TariWallet
 struct TariWallet *wallet_create(struct TariWalletConfig *config,
                                     char *log_path,
+                                    const char *passphrase,
                                     void (*callback_received_transaction)(struct TariPendingInboundTransaction*),
                                     void (*callback_received_transaction_reply)(struct TariCompletedTransaction*),
                                     void (*callback_received_finalized_transaction)(struct TariCompletedTransaction*),
@@ -284,7 +364,7 @@ struct TariWallet *wallet_create(struct TariWalletConfig *config,
 /// Generates test data
 bool wallet_test_generate_data(struct TariWallet *wallet, char *datastore_path,int* error_out);
 
-// Adds a base node peer to the TariWallet
+// Adds a base node peer to the TariWallet and sets it as the base node the wallet uses for monitoring and recovery
 bool wallet_add_base_node_peer(struct TariWallet *wallet, struct TariPublicKey *public_key, char *address,int* error_out);
 
 // Adds a TariContact to the TariWallet
@@ -302,15 +382,36 @@ unsigned long long wallet_get_pending_incoming_balance(struct TariWallet *wallet
 // Gets the outgoing balance from a TariWallet
 unsigned long long wallet_get_pending_outgoing_balance(struct TariWallet *wallet,int* error_out);
 
+// Gets the time-locked balance from a TariWallet
+unsigned long long wallet_get_time_locked_balance(struct TariWallet *wallet,int* error_out);
+
 // Sends a TariPendingOutboundTransaction
 bool wallet_send_transaction(struct TariWallet *wallet, struct TariPublicKey *destination, unsigned long long amount, unsigned long long fee_per_gram,const char *message,int* error_out);
 
+// Sends a TariPendingOutboundTransaction that cannot be mined before the given block height
+bool wallet_send_transaction_with_lock_height(struct TariWallet *wallet, struct TariPublicKey *destination, unsigned long long amount, unsigned long long fee_per_gram, unsigned long long lock_height,const char *message,int* error_out);
+
+// Pays a TariPaymentRequest, zero amount pays the amount of the request
+bool wallet_pay_payment_request(struct TariWallet *wallet, struct TariPaymentRequest *request, unsigned long long amount, unsigned long long fee_per_gram, int* error_out);
+
+// Estimates the fee of sending a transaction of the given amount
+unsigned long long wallet_get_fee_estimate(struct TariWallet *wallet, unsigned long long amount, unsigned long long fee_per_gram, int* error_out);
+
+// Estimates the fee of a coin split
+unsigned long long wallet_get_coin_split_fee(struct TariWallet *wallet, unsigned long long amount_per_split, unsigned int split_count, unsigned long long fee_per_gram, int* error_out);
+
+// Splits the largest outputs of a TariWallet into split_count outputs of amount_per_split each, returning the TransactionId
+unsigned long long wallet_coin_split(struct TariWallet *wallet, unsigned long long amount_per_split, unsigned int split_count, unsigned long long fee_per_gram, const char *message, int* error_out);
+
 // Get the TariContacts from a TariWallet
 struct TariContacts *wallet_get_contacts(struct TariWallet *wallet,int* error_out);
 
 // Get the TariCompletedTransactions from a TariWallet
 struct TariCompletedTransactions *wallet_get_completed_transactions(struct TariWallet *wallet,int* error_out);
 
+// Exports a page of the completed transactions of a TariWallet completed between the from and to Unix times as CSV or JSON
+char *wallet_export_transaction_history(struct TariWallet *wallet, unsigned long long from, unsigned long long to, unsigned int offset, unsigned int limit, bool as_json, int* error_out);
+
 // Get the TariPendingOutboundTransactions from a TariWallet
 struct TariPendingOutboundTransactions *wallet_get_pending_outbound_transactions(struct TariWallet *wallet,int* error_out);
 
@@ -329,6 +430,21 @@ struct TariPendingOutboundTransaction *wallet_get_pending_outbound_transaction_b
 // Get the TariPendingInboundTransaction from a TariWallet by its TransactionId
 struct TariPendingInboundTransaction *wallet_get_pending_inbound_transaction_by_id(struct TariWallet *wallet, unsigned long long transaction_id,int* error_out);
 
+// Cancels a pending inbound or outbound transaction of a TariWallet by its TransactionId
+bool wallet_cancel_pending_transaction(struct TariWallet *wallet, unsigned long long transaction_id, int* error_out);
+
+// Sends the messages of all the pending transactions of a TariWallet to their counterparties again, returns the number of transactions resent
+unsigned int wallet_resend_pending_transactions(struct TariWallet *wallet, int* error_out);
+
+// Get the unspent outputs of a TariWallet
+struct TariUnblindedOutputs *wallet_get_unspent_outputs(struct TariWallet *wallet, int* error_out);
+
+// Get the seed words from which the keys of a TariWallet can be recovered
+struct TariSeedWords *wallet_get_seed_words(struct TariWallet *wallet, int* error_out);
+
+// Starts recovering the funds of a TariWallet from seed words, scanning from birthday_height or the genesis block if 0
+bool wallet_start_recovery(struct TariWallet *wallet, struct TariSeedWords *seed_words, unsigned long long birthday_height, int* error_out);
+
 // Simulates completion of a TariPendingOutboundTransaction
 bool wallet_test_complete_sent_transaction(struct TariWallet *wallet, struct TariPendingOutboundTransaction *tx,int* error_out);
 
@@ -344,6 +460,28 @@ bool wallet_test_mine_transaction(struct TariWallet *wallet, struct TariComplete
 // Simulates a TariPendingInboundtransaction being received
 bool wallet_test_receive_transaction(struct TariWallet *wallet,int* error_out);
 
+// Encrypts the secrets of a TariWallet with a passphrase
+bool wallet_apply_encryption(struct TariWallet *wallet, const char *passphrase, int* error_out);
+
+// Decrypts the secrets of an unlocked TariWallet so that a passphrase is no longer required
+bool wallet_remove_encryption(struct TariWallet *wallet, const char *passphrase, int* error_out);
+
+// Re-encrypts the secrets of an unlocked TariWallet with a new passphrase
+bool wallet_change_passphrase(struct TariWallet *wallet, const char *passphrase, const char *new_passphrase, int* error_out);
+
+// Unlocks the encrypted secrets of a TariWallet
+bool wallet_unlock(struct TariWallet *wallet, const char *passphrase, int* error_out);
+
+// Locks the encrypted secrets of a TariWallet
+bool wallet_lock(struct TariWallet *wallet, int* error_out);
+
+// Writes an encrypted backup of a TariWallet to a file
+bool wallet_backup_to(struct TariWallet *wallet, const char *path, const char *passphrase, int* error_out);
+
+// Restores an encrypted backup file into a TariWallet that does not hold any outputs or transactions yet and returns
+// the secret key of the backup's node identity
+struct TariPrivateKey *wallet_restore_from(struct TariWallet *wallet, const char *path, const char *passphrase, int* error_out);
+
 // Frees memory for a TariWallet
 void wallet_destroy(struct TariWallet *wallet);
 
@@ -70,7 +70,7 @@ pub fn install_default_config_file(path: &Path) -> Result<u64, std::io::Error> {
 }
 
 //---------------------------------------------       Network type        ------------------------------------------//
-#[derive(Clone, Debug, PartialEq)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum Network {
     MainNet,
     TestNet,
@@ -123,6 +123,10 @@ pub struct GlobalConfig {
     pub address: String,
     pub peer_seeds: Vec<String>,
     pub peer_db_path: String,
+    pub wallet_file: PathBuf,
+    pub wallet_address: String,
+    pub wallet_grpc_enabled: bool,
+    pub wallet_grpc_address: String,
 }
 
 impl GlobalConfig {
@@ -200,6 +204,26 @@ fn convert_node_config(network: Network, cfg: Config) -> Result<GlobalConfig, Co
 
     // Peer DB path
     let peer_db_path = sub_dir(&data_dir, "peer_db")?;
+
+    // Wallet settings
+    let key = "wallet.wallet_file";
+    let wallet_file = cfg
+        .get_str(key)
+        .map_err(|e| ConfigurationError::new(key, &e.to_string()))?;
+    let wallet_file = PathBuf::from(wallet_file);
+    let key = "wallet.address";
+    let wallet_address = cfg
+        .get_str(key)
+        .map_err(|e| ConfigurationError::new(key, &e.to_string()))?;
+    let key = "wallet.grpc_enabled";
+    let wallet_grpc_enabled = cfg
+        .get_bool(key)
+        .map_err(|e| ConfigurationError::new(key, &e.to_string()))?;
+    let key = "wallet.grpc_address";
+    let wallet_grpc_address = cfg
+        .get_str(key)
+        .map_err(|e| ConfigurationError::new(key, &e.to_string()))?;
+
     Ok(GlobalConfig {
         network,
         data_dir,
@@ -210,6 +234,10 @@ fn convert_node_config(network: Network, cfg: Config) -> Result<GlobalConfig, Co
         address,
         peer_seeds,
         peer_db_path,
+        wallet_file,
+        wallet_address,
+        wallet_grpc_enabled,
+        wallet_grpc_address,
     })
 }
 
@@ -238,6 +266,7 @@ pub fn default_config() -> Config {
     cfg.set_default("wallet.grpc_address", "tcp://127.0.0.1:18040").unwrap();
     cfg.set_default("wallet.wallet_file", default_subdir("wallet/wallet.dat"))
         .unwrap();
+    cfg.set_default("wallet.address", "/ip4/127.0.0.1/tcp/18188").unwrap();
 
     // Base Node settings
     cfg.set_default("base_node.network", "mainnet").unwrap();
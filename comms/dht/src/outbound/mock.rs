@@ -101,6 +101,11 @@ impl OutboundServiceMockState {
         }
     }
 
+    /// Set the response to the next send request, later requests get the default response again
+    pub fn set_next_response(&self, response: SendMessageResponse) {
+        *acquire_write_lock!(self.next_response) = Some(response);
+    }
+
     pub fn take_next_response(&self) -> Option<SendMessageResponse> {
         acquire_write_lock!(self.next_response).take()
     }
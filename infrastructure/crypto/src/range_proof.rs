@@ -35,6 +35,27 @@ pub enum RangeProofError {
     InvalidProof,
     /// Invalid input was provided to the RangeProofService constructor
     InitializationError,
+    /// The rewind keys did not match the range proof, or the proof does not contain rewind data
+    InvalidRewind,
+}
+
+/// The length of the message that can be embedded in, and later recovered from, a rewindable range proof
+pub const REWIND_PROOF_MESSAGE_LENGTH: usize = 21;
+
+/// The data that anyone holding the public rewind keys can extract from a rewindable range proof
+#[derive(Debug, Clone, PartialEq)]
+pub struct RewindResult {
+    pub committed_value: u64,
+    pub proof_message: [u8; REWIND_PROOF_MESSAGE_LENGTH],
+}
+
+/// The data that the holder of the private rewind keys can extract from a rewindable range proof, including the
+/// blinding factor of the commitment
+#[derive(Debug, Clone, PartialEq)]
+pub struct FullRewindResult<K> {
+    pub committed_value: u64,
+    pub proof_message: [u8; REWIND_PROOF_MESSAGE_LENGTH],
+    pub blinding_factor: K,
 }
 
 pub trait RangeProofService {
@@ -50,4 +71,36 @@ pub trait RangeProofService {
     /// Verify the range proof against the given commitment. If this function returns true, it attests to the
     /// commitment having a value in the range [0; 2^64-1] and that the prover knew both the value and private key.
     fn verify(&self, proof: &Self::P, commitment: &HomomorphicCommitment<Self::PK>) -> bool;
+
+    /// Construct a range proof that embeds the value, the given message and the blinding factor of the commitment.
+    /// The value and message can later be extracted with the public counterparts of `rewind_key` and
+    /// `rewind_blinding_key`, and the blinding factor can be extracted with the private keys themselves.
+    fn construct_proof_with_rewind_key(
+        &self,
+        key: &Self::K,
+        value: u64,
+        rewind_key: &Self::K,
+        rewind_blinding_key: &Self::K,
+        proof_message: &[u8; REWIND_PROOF_MESSAGE_LENGTH],
+    ) -> Result<Self::P, RangeProofError>;
+
+    /// Extract the committed value and message from a rewindable range proof using the public rewind keys. Returns
+    /// `InvalidRewind` if the keys do not belong to the proof.
+    fn rewind_proof_value_only(
+        &self,
+        proof: &Self::P,
+        commitment: &HomomorphicCommitment<Self::PK>,
+        rewind_public_key: &Self::PK,
+        rewind_blinding_public_key: &Self::PK,
+    ) -> Result<RewindResult, RangeProofError>;
+
+    /// Extract the committed value, message and blinding factor from a rewindable range proof using the private
+    /// rewind keys. The recovered value and blinding factor are checked against the commitment.
+    fn rewind_proof_commitment_data(
+        &self,
+        proof: &Self::P,
+        commitment: &HomomorphicCommitment<Self::PK>,
+        rewind_key: &Self::K,
+        rewind_blinding_key: &Self::K,
+    ) -> Result<FullRewindResult<Self::K>, RangeProofError>;
 }
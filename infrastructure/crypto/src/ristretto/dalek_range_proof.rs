@@ -21,7 +21,9 @@
 // USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
 use crate::{
-    range_proof::{RangeProofError, RangeProofService},
+    common::Blake256,
+    keys::PublicKey,
+    range_proof::{FullRewindResult, RangeProofError, RangeProofService, RewindResult, REWIND_PROOF_MESSAGE_LENGTH},
     ristretto::{
         pedersen::{PedersenCommitment, PedersenCommitmentFactory},
         RistrettoPublicKey,
@@ -29,7 +31,11 @@ use crate::{
     },
 };
 use bulletproofs::{BulletproofGens, PedersenGens, RangeProof as DalekProof};
+use curve25519_dalek::scalar::Scalar;
+use digest::Digest;
 use merlin::Transcript;
+use rand::{OsRng, RngCore};
+use tari_utilities::ByteArray;
 
 /// A wrapper around the Dalek library implementation of Bulletproof range proofs.
 pub struct DalekRangeProofService {
@@ -39,6 +45,12 @@ pub struct DalekRangeProofService {
 }
 
 const MASK: usize = 0b111_1000; // Mask for 8,16,32,64; the valid ranges on the Dalek library
+/// Rewind data is appended to the bulletproof as three 32-byte blocks: the encrypted value and message, the masked
+/// blinding factor and a check tag over both. Proofs without rewind keys carry random bytes of the same length, so
+/// rewindable proofs cannot be told apart by their size.
+const REWIND_DATA_LENGTH: usize = 96;
+/// The length of the tag that ties the rewind data to the rewind keys and the commitment
+const REWIND_CHECK_LENGTH: usize = 32;
 
 impl DalekRangeProofService {
     /// Create a new RangeProofService. The Dalek library can only generate proofs for ranges between [0; 2^range),
@@ -58,6 +70,139 @@ impl DalekRangeProofService {
             bp_gens,
         })
     }
+
+    /// The length in bytes of a bulletproof over this service's range, excluding any rewind data
+    fn bulletproof_length(&self) -> usize {
+        (2 * self.range.trailing_zeros() as usize + 9) * 32
+    }
+
+    /// Split a proof into its bulletproof and, if present, its rewind data
+    fn split_proof<'a>(&self, proof: &'a [u8]) -> (&'a [u8], Option<&'a [u8]>) {
+        let bp_len = self.bulletproof_length();
+        if proof.len() == bp_len + REWIND_DATA_LENGTH {
+            (&proof[..bp_len], Some(&proof[bp_len..]))
+        } else {
+            (proof, None)
+        }
+    }
+
+    /// The bulletproof transcript. Any rewind data is committed to the transcript, so that it cannot be stripped or
+    /// altered without invalidating the proof.
+    fn transcript(rewind_data: Option<&[u8]>) -> Transcript {
+        let mut pt = Transcript::new(b"tari");
+        if let Some(data) = rewind_data {
+            pt.append_message(b"rewind_data", data);
+        }
+        pt
+    }
+
+    /// Construct a bulletproof bound to `rewind_data` and append the data to it
+    fn prove_with_rewind_data(
+        &self,
+        key: &RistrettoSecretKey,
+        value: u64,
+        rewind_data: &[u8; REWIND_DATA_LENGTH],
+    ) -> Result<Vec<u8>, RangeProofError>
+    {
+        let mut pt = DalekRangeProofService::transcript(Some(rewind_data));
+        let (proof, _) = DalekProof::prove_single(&self.bp_gens, &self.pc_gens, &mut pt, value, &key.0, self.range)
+            .map_err(|_| RangeProofError::ProofConstructionError)?;
+        let mut proof = proof.to_bytes();
+        proof.extend_from_slice(rewind_data);
+        Ok(proof)
+    }
+
+    /// The keystream used to encrypt the value and message block. Only public data goes into it, so that the value
+    /// can be rewound by anyone holding the public rewind keys.
+    fn value_keystream(
+        rewind_public_key: &RistrettoPublicKey,
+        rewind_blinding_public_key: &RistrettoPublicKey,
+        commitment: &[u8],
+    ) -> [u8; 32]
+    {
+        let hash = Blake256::new()
+            .chain(b"rewind_value")
+            .chain(rewind_public_key.as_bytes())
+            .chain(rewind_blinding_public_key.as_bytes())
+            .chain(commitment)
+            .result();
+        let mut stream = [0u8; 32];
+        stream.copy_from_slice(&hash);
+        stream
+    }
+
+    /// The tag over the encrypted value block and masked blinding factor. A rewind with the wrong keys, or of data
+    /// that was not produced for this commitment, fails to reproduce it.
+    fn check_tag(
+        rewind_public_key: &RistrettoPublicKey,
+        rewind_blinding_public_key: &RistrettoPublicKey,
+        commitment: &[u8],
+        data: &[u8],
+    ) -> [u8; REWIND_CHECK_LENGTH]
+    {
+        let hash = Blake256::new()
+            .chain(b"rewind_check")
+            .chain(rewind_public_key.as_bytes())
+            .chain(rewind_blinding_public_key.as_bytes())
+            .chain(commitment)
+            .chain(data)
+            .result();
+        let mut tag = [0u8; REWIND_CHECK_LENGTH];
+        tag.copy_from_slice(&hash);
+        tag
+    }
+
+    /// The scalar that masks the blinding factor. It depends on the private rewind keys.
+    fn blinding_mask(
+        rewind_key: &RistrettoSecretKey,
+        rewind_blinding_key: &RistrettoSecretKey,
+        commitment: &[u8],
+    ) -> Scalar
+    {
+        let hash = Blake256::new()
+            .chain(b"rewind_blinding")
+            .chain(rewind_key.as_bytes())
+            .chain(rewind_blinding_key.as_bytes())
+            .chain(commitment)
+            .result();
+        let mut bytes = [0u8; 32];
+        bytes.copy_from_slice(&hash);
+        Scalar::from_bytes_mod_order(bytes)
+    }
+
+    /// Check the tag on the rewind data and decrypt its value block
+    fn decrypt_value_block(
+        rewind_data: &[u8],
+        rewind_public_key: &RistrettoPublicKey,
+        rewind_blinding_public_key: &RistrettoPublicKey,
+        commitment: &[u8],
+    ) -> Result<RewindResult, RangeProofError>
+    {
+        let tag_start = REWIND_DATA_LENGTH - REWIND_CHECK_LENGTH;
+        let tag = DalekRangeProofService::check_tag(
+            rewind_public_key,
+            rewind_blinding_public_key,
+            commitment,
+            &rewind_data[..tag_start],
+        );
+        if tag[..] != rewind_data[tag_start..] {
+            return Err(RangeProofError::InvalidRewind);
+        }
+        let keystream =
+            DalekRangeProofService::value_keystream(rewind_public_key, rewind_blinding_public_key, commitment);
+        let mut plain = [0u8; 32];
+        for (i, b) in plain.iter_mut().enumerate() {
+            *b = rewind_data[i] ^ keystream[i];
+        }
+        let mut value = [0u8; 8];
+        value.copy_from_slice(&plain[..8]);
+        let mut proof_message = [0u8; REWIND_PROOF_MESSAGE_LENGTH];
+        proof_message.copy_from_slice(&plain[8..8 + REWIND_PROOF_MESSAGE_LENGTH]);
+        Ok(RewindResult {
+            committed_value: u64::from_le_bytes(value),
+            proof_message,
+        })
+    }
 }
 
 impl RangeProofService for DalekRangeProofService {
@@ -66,35 +211,132 @@ impl RangeProofService for DalekRangeProofService {
     type PK = RistrettoPublicKey;
 
     fn construct_proof(&self, key: &RistrettoSecretKey, value: u64) -> Result<Vec<u8>, RangeProofError> {
-        let mut pt = Transcript::new(b"tari");
-        let k = key.0;
-        let (proof, _) = DalekProof::prove_single(&self.bp_gens, &self.pc_gens, &mut pt, value, &k, self.range)
-            .map_err(|_| RangeProofError::ProofConstructionError)?;
-        Ok(proof.to_bytes())
+        let mut padding = [0u8; REWIND_DATA_LENGTH];
+        OsRng::new()
+            .map_err(|_| RangeProofError::ProofConstructionError)?
+            .fill_bytes(&mut padding);
+        self.prove_with_rewind_data(key, value, &padding)
     }
 
     fn verify(&self, proof: &Self::P, commitment: &PedersenCommitment) -> bool {
-        let rp = DalekProof::from_bytes(&proof).map_err(|_| RangeProofError::InvalidProof);
+        let (proof, rewind_data) = self.split_proof(proof);
+        let rp = DalekProof::from_bytes(proof).map_err(|_| RangeProofError::InvalidProof);
         if rp.is_err() {
             return false;
         }
         let rp = rp.unwrap();
-        let mut pt = Transcript::new(b"tari");
+        let mut pt = DalekRangeProofService::transcript(rewind_data);
         let c = &commitment.0;
         rp.verify_single(&self.bp_gens, &self.pc_gens, &mut pt, &c.compressed, self.range)
             .is_ok()
     }
+
+    fn construct_proof_with_rewind_key(
+        &self,
+        key: &RistrettoSecretKey,
+        value: u64,
+        rewind_key: &RistrettoSecretKey,
+        rewind_blinding_key: &RistrettoSecretKey,
+        proof_message: &[u8; REWIND_PROOF_MESSAGE_LENGTH],
+    ) -> Result<Vec<u8>, RangeProofError>
+    {
+        let commitment = self.pc_gens.commit(Scalar::from(value), key.0).compress();
+        let rewind_public_key = RistrettoPublicKey::from_secret_key(rewind_key);
+        let rewind_blinding_public_key = RistrettoPublicKey::from_secret_key(rewind_blinding_key);
+        let keystream = DalekRangeProofService::value_keystream(
+            &rewind_public_key,
+            &rewind_blinding_public_key,
+            commitment.as_bytes(),
+        );
+        let mut rewind_data = [0u8; REWIND_DATA_LENGTH];
+        rewind_data[..8].copy_from_slice(&value.to_le_bytes());
+        rewind_data[8..8 + REWIND_PROOF_MESSAGE_LENGTH].copy_from_slice(proof_message);
+        for (b, k) in rewind_data[..32].iter_mut().zip(keystream.iter()) {
+            *b ^= k;
+        }
+        let mask = DalekRangeProofService::blinding_mask(rewind_key, rewind_blinding_key, commitment.as_bytes());
+        rewind_data[32..64].copy_from_slice((key.0 + mask).as_bytes());
+        let tag = DalekRangeProofService::check_tag(
+            &rewind_public_key,
+            &rewind_blinding_public_key,
+            commitment.as_bytes(),
+            &rewind_data[..64],
+        );
+        rewind_data[64..].copy_from_slice(&tag);
+        self.prove_with_rewind_data(key, value, &rewind_data)
+    }
+
+    fn rewind_proof_value_only(
+        &self,
+        proof: &Self::P,
+        commitment: &PedersenCommitment,
+        rewind_public_key: &RistrettoPublicKey,
+        rewind_blinding_public_key: &RistrettoPublicKey,
+    ) -> Result<RewindResult, RangeProofError>
+    {
+        let rewind_data = match self.split_proof(proof) {
+            (_, Some(data)) => data,
+            (_, None) => return Err(RangeProofError::InvalidRewind),
+        };
+        DalekRangeProofService::decrypt_value_block(
+            rewind_data,
+            rewind_public_key,
+            rewind_blinding_public_key,
+            commitment.as_bytes(),
+        )
+    }
+
+    fn rewind_proof_commitment_data(
+        &self,
+        proof: &Self::P,
+        commitment: &PedersenCommitment,
+        rewind_key: &RistrettoSecretKey,
+        rewind_blinding_key: &RistrettoSecretKey,
+    ) -> Result<FullRewindResult<RistrettoSecretKey>, RangeProofError>
+    {
+        let rewind_data = match self.split_proof(proof) {
+            (_, Some(data)) => data,
+            (_, None) => return Err(RangeProofError::InvalidRewind),
+        };
+        let RewindResult {
+            committed_value,
+            proof_message,
+        } = DalekRangeProofService::decrypt_value_block(
+            rewind_data,
+            &RistrettoPublicKey::from_secret_key(rewind_key),
+            &RistrettoPublicKey::from_secret_key(rewind_blinding_key),
+            commitment.as_bytes(),
+        )?;
+        let mut masked = [0u8; 32];
+        masked.copy_from_slice(&rewind_data[32..64]);
+        let masked = Scalar::from_canonical_bytes(masked).ok_or(RangeProofError::InvalidRewind)?;
+        let mask = DalekRangeProofService::blinding_mask(rewind_key, rewind_blinding_key, commitment.as_bytes());
+        let blinding_factor = masked - mask;
+        let expected = self
+            .pc_gens
+            .commit(Scalar::from(committed_value), blinding_factor)
+            .compress();
+        if expected.as_bytes() != commitment.as_bytes() {
+            return Err(RangeProofError::InvalidRewind);
+        }
+        Ok(FullRewindResult {
+            committed_value,
+            proof_message,
+            blinding_factor: RistrettoSecretKey(blinding_factor),
+        })
+    }
 }
 
 #[cfg(test)]
 mod test {
     use crate::{
         commitment::HomomorphicCommitmentFactory,
-        keys::SecretKey,
-        range_proof::{RangeProofError, RangeProofService},
+        keys::{PublicKey, SecretKey},
+        range_proof::{RangeProofError, RangeProofService, REWIND_PROOF_MESSAGE_LENGTH},
         ristretto::{
             dalek_range_proof::DalekRangeProofService,
             pedersen::PedersenCommitmentFactory,
+            RistrettoPublicKey,
             RistrettoSecretKey,
         },
     };
@@ -111,7 +353,8 @@ mod test {
         let commitment_factory: PedersenCommitmentFactory = PedersenCommitmentFactory::default();
         let c = commitment_factory.commit(&k, &v);
         let proof = prover.construct_proof(&k, 42).unwrap();
-        assert_eq!(proof.len(), (2 * n + 9) * 32);
+        // Every proof carries rewind-sized data, so rewindable proofs cannot be told apart by length
+        assert_eq!(proof.len(), (2 * n + 9) * 32 + 96);
         assert!(prover.verify(&proof, &c));
         // Invalid value
         let v2 = RistrettoSecretKey::from(43);
@@ -159,4 +402,67 @@ mod test {
             assert_eq!(prover.verify(&proof, &c), false);
         }
     }
+
+    #[test]
+    fn rewind_proof() {
+        let base = PedersenCommitmentFactory::default();
+        let prover = DalekRangeProofService::new(64, &base).unwrap();
+        let mut rng = OsRng::new().unwrap();
+        let k = RistrettoSecretKey::random(&mut rng);
+        let rewind_key = RistrettoSecretKey::random(&mut rng);
+        let rewind_blinding_key = RistrettoSecretKey::random(&mut rng);
+        let message = [7u8; REWIND_PROOF_MESSAGE_LENGTH];
+        let c = base.commit_value(&k, 1234);
+        let proof = prover
+            .construct_proof_with_rewind_key(&k, 1234, &rewind_key, &rewind_blinding_key, &message)
+            .unwrap();
+        assert_eq!(proof.len(), (2 * 6 + 9) * 32 + 96);
+        assert!(prover.verify(&proof, &c));
+        // The rewind data is bound to the proof: stripping or altering it invalidates the proof
+        let bp_len = (2 * 6 + 9) * 32;
+        assert_eq!(prover.verify(&proof[..bp_len].to_vec(), &c), false);
+        let mut tampered = proof.clone();
+        tampered[bp_len] ^= 1;
+        assert_eq!(prover.verify(&tampered, &c), false);
+
+        let rewind_pk = RistrettoPublicKey::from_secret_key(&rewind_key);
+        let rewind_blinding_pk = RistrettoPublicKey::from_secret_key(&rewind_blinding_key);
+        let result = prover
+            .rewind_proof_value_only(&proof, &c, &rewind_pk, &rewind_blinding_pk)
+            .unwrap();
+        assert_eq!(result.committed_value, 1234);
+        assert_eq!(result.proof_message, message);
+
+        let result = prover
+            .rewind_proof_commitment_data(&proof, &c, &rewind_key, &rewind_blinding_key)
+            .unwrap();
+        assert_eq!(result.committed_value, 1234);
+        assert_eq!(result.proof_message, message);
+        assert_eq!(result.blinding_factor, k);
+
+        // Wrong keys
+        let wrong_key = RistrettoSecretKey::random(&mut rng);
+        let wrong_pk = RistrettoPublicKey::from_secret_key(&wrong_key);
+        assert_eq!(
+            prover.rewind_proof_value_only(&proof, &c, &wrong_pk, &rewind_blinding_pk),
+            Err(RangeProofError::InvalidRewind)
+        );
+        assert_eq!(
+            prover.rewind_proof_commitment_data(&proof, &c, &rewind_key, &wrong_key),
+            Err(RangeProofError::InvalidRewind)
+        );
+        // Rewind data cannot be moved onto another commitment's proof
+        let other = base.commit_value(&k, 1235);
+        assert_eq!(
+            prover.rewind_proof_value_only(&proof, &other, &rewind_pk, &rewind_blinding_pk),
+            Err(RangeProofError::InvalidRewind)
+        );
+        // A proof without rewind keys cannot be rewound
+        let proof = prover.construct_proof(&k, 1234).unwrap();
+        assert!(prover.verify(&proof, &c));
+        assert_eq!(
+            prover.rewind_proof_value_only(&proof, &c, &rewind_pk, &rewind_blinding_pk),
+            Err(RangeProofError::InvalidRewind)
+        );
+    }
 }
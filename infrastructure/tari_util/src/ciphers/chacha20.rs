@@ -55,7 +55,7 @@ impl ChaCha20 {
     }
 
     /// Construct a chacha block by performing a number of column and diagonal quarter round operations
-    fn chacha20_block(state: &[u32; 16]) -> [u32; 16] {
+    pub(crate) fn chacha20_block(state: &[u32; 16]) -> [u32; 16] {
         let mut working_state = *state;
         for _iter in 0..10 {
             // 20 total => odd and even round performed for every iteration
@@ -79,7 +79,7 @@ impl ChaCha20 {
 
     /// Construct an initial state from a 128-bit constant, 256-bit key, 96-bit nonce and a 32-bit block counter
     #[allow(clippy::needless_range_loop)]
-    fn construct_state(key: &[u8; 32], nonce: &[u8; 12], counter: u32) -> [u32; 16] {
+    pub(crate) fn construct_state(key: &[u8; 32], nonce: &[u8; 12], counter: u32) -> [u32; 16] {
         let constant: [u8; 16] = [101, 120, 112, 97, 110, 100, 32, 51, 50, 45, 98, 121, 116, 101, 32, 107]; // 0x61707865, 0x3320646e, 0x79622d32, 0x6b206574
         let mut state_bytes = constant.to_vec(); // 128 bit
         state_bytes.extend_from_slice(key); // 256-bit
@@ -118,7 +118,7 @@ impl ChaCha20 {
     }
 
     /// Encode the provided input bytes using a chacha20 keystream
-    fn encode_with_nonce(bytes: &[u8], key: &[u8; 32], nonce: &[u8; 12]) -> Vec<u8> {
+    pub(crate) fn encode_with_nonce(bytes: &[u8], key: &[u8; 32], nonce: &[u8; 12]) -> Vec<u8> {
         const BYTES_PER_BLOCK: usize = 64;
         let block_count = (bytes.len() as f64 / BYTES_PER_BLOCK as f64).ceil() as usize;
         let cipher_bytes = Self::chacha20_cipher_keystream(key, nonce, block_count);
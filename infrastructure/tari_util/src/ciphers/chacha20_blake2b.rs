@@ -0,0 +1,205 @@
+// Copyright 2020, The Tari Project
+//
+// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+// following conditions are met:
+//
+// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+// disclaimer.
+//
+// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+// following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+// 3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+// products derived from this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+// INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+// SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+// WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+// USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+use crate::{
+    ciphers::{
+        chacha20::ChaCha20,
+        cipher::{Cipher, CipherError},
+    },
+    ByteArray,
+};
+use blake2::VarBlake2b;
+use clear_on_drop::clear::Clear;
+use digest::{Input, VariableOutput};
+use rand::{OsRng, RngCore};
+
+const KEY_LENGTH: usize = 32;
+const NONCE_LENGTH: usize = 12;
+/// The length of the authentication tag appended to the cipher text
+pub const TAG_LENGTH: usize = 32;
+
+/// An authenticated encryption scheme that encrypts with the ChaCha20 stream cipher and appends a keyed Blake2b tag
+/// over the nonce and cipher text (encrypt-then-MAC). As with ChaCha20-Poly1305 (RFC 8439), the one-time MAC key is
+/// the first 32 bytes of the ChaCha20 block with counter 0, which is never used for encryption.
+pub struct ChaCha20Blake2b;
+
+impl ChaCha20Blake2b {
+    /// Derive the one-time MAC key for the given key and nonce
+    fn mac_key(key: &[u8; KEY_LENGTH], nonce: &[u8; NONCE_LENGTH]) -> [u8; KEY_LENGTH] {
+        let mut state = ChaCha20::construct_state(key, nonce, 0);
+        let block = ChaCha20::chacha20_block(&state);
+        state.clear();
+        let mut mac_key = [0u8; KEY_LENGTH];
+        for (i, word) in block.iter().take(KEY_LENGTH / 4).enumerate() {
+            mac_key[i * 4..(i + 1) * 4].copy_from_slice(&word.to_ne_bytes());
+        }
+        mac_key
+    }
+
+    /// Calculate the authentication tag of the cipher text
+    fn tag(key: &[u8; KEY_LENGTH], nonce: &[u8; NONCE_LENGTH], cipher_text: &[u8]) -> Vec<u8> {
+        let mut mac_key = ChaCha20Blake2b::mac_key(key, nonce);
+        let mut hasher = VarBlake2b::new_keyed(&mac_key, TAG_LENGTH);
+        mac_key.clear();
+        hasher.input(nonce);
+        hasher.input(cipher_text);
+        hasher.vec_result()
+    }
+
+    fn sized_key_and_nonce(key: &[u8], nonce: &[u8]) -> Result<([u8; KEY_LENGTH], [u8; NONCE_LENGTH]), CipherError> {
+        if key.len() != KEY_LENGTH {
+            return Err(CipherError::KeyLengthError);
+        }
+        if nonce.len() != NONCE_LENGTH {
+            return Err(CipherError::NonceLengthError);
+        }
+        let mut sized_key = [0; KEY_LENGTH];
+        sized_key.copy_from_slice(key);
+        let mut sized_nonce = [0; NONCE_LENGTH];
+        sized_nonce.copy_from_slice(nonce);
+        Ok((sized_key, sized_nonce))
+    }
+}
+
+/// Compare two byte slices in a time that does not depend on where they differ
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+    a.iter().zip(b.iter()).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+impl<D> Cipher<D> for ChaCha20Blake2b
+where D: ByteArray
+{
+    fn seal(plain_text: &D, key: &[u8], nonce: &[u8]) -> Result<Vec<u8>, CipherError> {
+        let (mut sized_key, mut sized_nonce) = ChaCha20Blake2b::sized_key_and_nonce(key, nonce)?;
+        if plain_text.as_bytes().is_empty() {
+            return Err(CipherError::NoDataError);
+        }
+
+        let mut cipher_text = ChaCha20::encode_with_nonce(plain_text.as_bytes(), &sized_key, &sized_nonce);
+        let tag = ChaCha20Blake2b::tag(&sized_key, &sized_nonce, &cipher_text);
+        cipher_text.extend(tag);
+        // Clear copied private data
+        sized_key.clear();
+        sized_nonce.clear();
+
+        Ok(cipher_text)
+    }
+
+    fn open(cipher_text: &[u8], key: &[u8], nonce: &[u8]) -> Result<D, CipherError> {
+        let (mut sized_key, mut sized_nonce) = ChaCha20Blake2b::sized_key_and_nonce(key, nonce)?;
+        if cipher_text.len() <= TAG_LENGTH {
+            return Err(CipherError::NoDataError);
+        }
+
+        let (cipher_text, tag) = cipher_text.split_at(cipher_text.len() - TAG_LENGTH);
+        if !constant_time_eq(&ChaCha20Blake2b::tag(&sized_key, &sized_nonce, cipher_text), tag) {
+            sized_key.clear();
+            sized_nonce.clear();
+            return Err(CipherError::AuthenticationError);
+        }
+        let plain_text = ChaCha20::encode_with_nonce(cipher_text, &sized_key, &sized_nonce);
+        // Clear copied private data
+        sized_key.clear();
+        sized_nonce.clear();
+
+        Ok(D::from_vec(&plain_text)?)
+    }
+
+    fn seal_with_integral_nonce(plain_text: &D, key: &[u8]) -> Result<Vec<u8>, CipherError> {
+        let mut rng = OsRng::new().unwrap();
+        let mut nonce = [0u8; NONCE_LENGTH];
+        rng.fill_bytes(&mut nonce);
+
+        let cipher_text = <ChaCha20Blake2b as Cipher<D>>::seal(plain_text, key, &nonce)?;
+        let mut nonce_with_cipher_text: Vec<u8> = nonce.to_vec();
+        nonce_with_cipher_text.extend(cipher_text);
+        nonce.clear();
+
+        Ok(nonce_with_cipher_text)
+    }
+
+    fn open_with_integral_nonce(cipher_text: &[u8], key: &[u8]) -> Result<D, CipherError> {
+        // If the cipher text is shorter than the required nonce length then the nonce is not properly included
+        if cipher_text.len() < NONCE_LENGTH {
+            return Err(CipherError::NonceLengthError);
+        }
+        let (nonce, cipher_text) = cipher_text.split_at(NONCE_LENGTH);
+        <ChaCha20Blake2b as Cipher<D>>::open(cipher_text, key, nonce)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_seal_and_open() {
+        let key = [7u8; KEY_LENGTH];
+        let nonce = [3u8; NONCE_LENGTH];
+        let plain_text = b"Cryptographic Forum Research Group".to_vec();
+        let cipher_text = ChaCha20Blake2b::seal(&plain_text, &key, &nonce).unwrap();
+        assert_eq!(cipher_text.len(), plain_text.len() + TAG_LENGTH);
+        // The encryption itself is plain ChaCha20
+        let chacha_cipher_text: Vec<u8> = ChaCha20::seal(&plain_text, &key, &nonce).unwrap();
+        assert_eq!(&cipher_text[..plain_text.len()], chacha_cipher_text.as_slice());
+
+        let decrypted: Vec<u8> = ChaCha20Blake2b::open(&cipher_text, &key, &nonce).unwrap();
+        assert_eq!(decrypted, plain_text);
+    }
+
+    #[test]
+    fn test_tampering_is_detected() {
+        let key = [7u8; KEY_LENGTH];
+        let plain_text = b"Cryptographic Forum Research Group".to_vec();
+        let cipher_text = ChaCha20Blake2b::seal_with_integral_nonce(&plain_text, &key).unwrap();
+        let decrypted: Vec<u8> = ChaCha20Blake2b::open_with_integral_nonce(&cipher_text, &key).unwrap();
+        assert_eq!(decrypted, plain_text);
+
+        // Flipping any bit of the nonce, cipher text or tag fails authentication
+        for i in &[0, NONCE_LENGTH + 1, cipher_text.len() - 1] {
+            let mut tampered = cipher_text.clone();
+            tampered[*i] ^= 0x01;
+            let result: Result<Vec<u8>, CipherError> = ChaCha20Blake2b::open_with_integral_nonce(&tampered, &key);
+            assert_eq!(result, Err(CipherError::AuthenticationError));
+        }
+
+        let wrong_key = [8u8; KEY_LENGTH];
+        let result: Result<Vec<u8>, CipherError> = ChaCha20Blake2b::open_with_integral_nonce(&cipher_text, &wrong_key);
+        assert_eq!(result, Err(CipherError::AuthenticationError));
+    }
+
+    #[test]
+    fn test_invalid_input() {
+        let key = [7u8; KEY_LENGTH];
+        let nonce = [3u8; NONCE_LENGTH];
+        let plain_text = b"plain text".to_vec();
+        let result: Result<Vec<u8>, CipherError> = ChaCha20Blake2b::seal(&plain_text, &key[..16], &nonce);
+        assert_eq!(result, Err(CipherError::KeyLengthError));
+        let result: Result<Vec<u8>, CipherError> = ChaCha20Blake2b::seal(&plain_text, &key, &nonce[..8]);
+        assert_eq!(result, Err(CipherError::NonceLengthError));
+        let result: Result<Vec<u8>, CipherError> = ChaCha20Blake2b::open(&[0u8; TAG_LENGTH], &key, &nonce);
+        assert_eq!(result, Err(CipherError::NoDataError));
+    }
+}
@@ -33,6 +33,8 @@ pub enum CipherError {
     NoDataError,
     /// Byte Array conversion error
     ByteArrayError(ByteArrayError),
+    /// The authentication tag of the cipher text is invalid, so the cipher text was tampered with or the key is wrong
+    AuthenticationError,
 }
 
 /// A trait describing an interface to a symmetrical encryption scheme
@@ -21,4 +21,5 @@
 // USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE
 
 pub mod chacha20;
+pub mod chacha20_blake2b;
 pub mod cipher;